@@ -0,0 +1,35 @@
+-- migrations/016_task_comments.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Threaded comments on tasks
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS task_comment (
+  task_comment_id         UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+
+  task_id                 UUID NOT NULL REFERENCES task(task_id) ON DELETE CASCADE,
+  -- null = top-level comment, otherwise a reply
+  parent_comment_id       UUID NULL REFERENCES task_comment(task_comment_id) ON DELETE CASCADE,
+
+  author_employee_id      UUID NOT NULL REFERENCES employee(employee_id) ON DELETE RESTRICT,
+  body                    TEXT NOT NULL,
+
+  created_at              TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS task_comment_task_created_idx
+  ON task_comment(task_id, created_at);
+
+-- ------------------------------------------------------------
+-- Actors for the activity timeline
+-- ------------------------------------------------------------
+
+ALTER TABLE task
+  ADD COLUMN IF NOT EXISTS assigned_at TIMESTAMPTZ NULL,
+  ADD COLUMN IF NOT EXISTS assigned_by_employee_id  UUID NULL REFERENCES employee(employee_id) ON DELETE SET NULL,
+  ADD COLUMN IF NOT EXISTS started_by_employee_id   UUID NULL REFERENCES employee(employee_id) ON DELETE SET NULL,
+  ADD COLUMN IF NOT EXISTS completed_by_employee_id UUID NULL REFERENCES employee(employee_id) ON DELETE SET NULL,
+  ADD COLUMN IF NOT EXISTS canceled_by_employee_id  UUID NULL REFERENCES employee(employee_id) ON DELETE SET NULL;
+
+COMMIT;
@@ -0,0 +1,56 @@
+-- migrations/017_task_templates.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Reusable task templates (managed by admin/manager)
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS task_template (
+  task_template_id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+
+  name                          TEXT NOT NULL,
+  task_type                     TEXT NOT NULL,
+  title                         TEXT NOT NULL,
+  details                       TEXT NULL,
+  priority                      SMALLINT NOT NULL DEFAULT 0 CHECK (priority IN (0,1,2)),
+
+  default_assignee_employee_id  UUID NULL REFERENCES employee(employee_id) ON DELETE SET NULL,
+
+  -- RRULE-ish spec, e.g. 'FREQ=WEEKLY;BYDAY=MO' (null = one-off)
+  recurrence                    TEXT NULL,
+
+  is_active                     BOOLEAN NOT NULL DEFAULT true,
+
+  created_by_employee_id        UUID NOT NULL REFERENCES employee(employee_id) ON DELETE RESTRICT,
+  created_at                    TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at                    TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'task_template_set_updated_at_trg'
+  ) THEN
+    CREATE TRIGGER task_template_set_updated_at_trg
+    BEFORE UPDATE ON task_template
+    FOR EACH ROW
+    EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+-- ------------------------------------------------------------
+-- Recurrence on task instances
+-- ------------------------------------------------------------
+
+ALTER TABLE task
+  ADD COLUMN IF NOT EXISTS task_template_id UUID NULL REFERENCES task_template(task_template_id) ON DELETE SET NULL,
+  ADD COLUMN IF NOT EXISTS recurrence TEXT NULL,
+  -- set once the background job has created the next occurrence
+  ADD COLUMN IF NOT EXISTS next_occurrence_task_id UUID NULL REFERENCES task(task_id) ON DELETE SET NULL;
+
+-- pending materialization: completed recurring tasks without a successor
+CREATE INDEX IF NOT EXISTS task_recurrence_pending_idx
+  ON task(completed_at)
+  WHERE recurrence IS NOT NULL AND status = 2 AND next_occurrence_task_id IS NULL;
+
+COMMIT;
@@ -0,0 +1,46 @@
+-- migrations/018_task_watchers_notifications.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- In-app notifications for staff
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS notification (
+  notification_id         UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+
+  recipient_employee_id   UUID NOT NULL REFERENCES employee(employee_id) ON DELETE CASCADE,
+
+  kind                    TEXT NOT NULL, -- 'TASK_STARTED','TASK_COMPLETED','TASK_CANCELED','TASK_STATUS_CHANGED',...
+  title                   TEXT NOT NULL,
+  body                    TEXT NULL,
+
+  -- optional linkage
+  task_id                 UUID NULL REFERENCES task(task_id) ON DELETE CASCADE,
+
+  created_at              TIMESTAMPTZ NOT NULL DEFAULT now(),
+  read_at                 TIMESTAMPTZ NULL
+);
+
+CREATE INDEX IF NOT EXISTS notification_recipient_created_idx
+  ON notification(recipient_employee_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS notification_recipient_unread_idx
+  ON notification(recipient_employee_id)
+  WHERE read_at IS NULL;
+
+-- ------------------------------------------------------------
+-- Task watchers (extra employees notified on status changes)
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS task_watcher (
+  task_id                 UUID NOT NULL REFERENCES task(task_id) ON DELETE CASCADE,
+  employee_id             UUID NOT NULL REFERENCES employee(employee_id) ON DELETE CASCADE,
+  added_by_employee_id    UUID NULL REFERENCES employee(employee_id) ON DELETE SET NULL,
+  created_at              TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (task_id, employee_id)
+);
+
+CREATE INDEX IF NOT EXISTS task_watcher_employee_idx
+  ON task_watcher(employee_id);
+
+COMMIT;
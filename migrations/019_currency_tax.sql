@@ -0,0 +1,33 @@
+-- migrations/019_currency_tax.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Clinic-level currency + tax rates
+-- ------------------------------------------------------------
+-- Money stays in minor units everywhere (price_cents etc.);
+-- currency_code only says which currency those units belong to.
+-- tax_rates maps tax category -> rate in basis points (1000 = 10%).
+
+ALTER TABLE clinic_settings
+  ADD COLUMN IF NOT EXISTS currency_code TEXT NOT NULL DEFAULT 'MNT',
+  ADD COLUMN IF NOT EXISTS tax_rates JSONB NOT NULL DEFAULT '{"standard": 0, "exempt": 0}'::jsonb;
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint WHERE conname = 'clinic_settings_currency_code_chk'
+  ) THEN
+    ALTER TABLE clinic_settings
+      ADD CONSTRAINT clinic_settings_currency_code_chk
+      CHECK (currency_code ~ '^[A-Z]{3}$');
+  END IF;
+END $$;
+
+-- ------------------------------------------------------------
+-- Per-service tax category (key into clinic_settings.tax_rates)
+-- ------------------------------------------------------------
+
+ALTER TABLE service_catalog
+  ADD COLUMN IF NOT EXISTS tax_category TEXT NOT NULL DEFAULT 'standard';
+
+COMMIT;
@@ -0,0 +1,31 @@
+-- migrations/020_reminder_policy.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Appointment reminder policy (consumed by the reminder job)
+-- ------------------------------------------------------------
+-- Shape (validated by the API, see jobs/appointment_reminders.rs):
+--   enabled       bool     master switch for automatic reminders
+--   hours_before  int      remind when start_at is within this many hours
+--   template      text     SMS body; {name} {first_name} {last_name}
+--                          {register_number} {date} {time} placeholders
+--   sms_enabled   bool     deliver via outbound sms row
+--   send_window   object   {"start":"09:00","end":"20:00"} (clinic time)
+--   max_per_day   int      cap on automatic reminders per day
+
+ALTER TABLE clinic_settings
+  ADD COLUMN IF NOT EXISTS reminder_policy JSONB NOT NULL DEFAULT '{
+    "enabled": false,
+    "hours_before": 24,
+    "template": "{name}, you have a dental appointment on {date} at {time}.",
+    "sms_enabled": true,
+    "send_window": {"start": "09:00", "end": "20:00"},
+    "max_per_day": 200
+  }'::jsonb;
+
+-- Worker looks for upcoming appointments that have not been reminded yet
+CREATE INDEX IF NOT EXISTS appointment_reminder_pending_idx
+  ON appointment(start_at)
+  WHERE reminder_sent_at IS NULL;
+
+COMMIT;
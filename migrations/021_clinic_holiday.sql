@@ -0,0 +1,24 @@
+-- migrations/021_clinic_holiday.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Clinic closures / holiday calendar
+-- ------------------------------------------------------------
+-- holiday_date is a clinic-local calendar date (clinic_settings.timezone).
+-- is_closed = true blocks booking on that date; false is informational only
+-- (e.g. shortened day) and is just shaded in the calendar.
+
+CREATE TABLE IF NOT EXISTS clinic_holiday (
+  holiday_id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  holiday_date        DATE NOT NULL,
+  name                TEXT NOT NULL,
+  is_closed           BOOLEAN NOT NULL DEFAULT true,
+  note                TEXT NULL,
+
+  created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+  created_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+
+  CONSTRAINT clinic_holiday_date_unique UNIQUE (holiday_date)
+);
+
+COMMIT;
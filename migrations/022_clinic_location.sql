@@ -0,0 +1,73 @@
+-- migrations/022_clinic_location.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Clinic locations (multi-site practices)
+-- ------------------------------------------------------------
+-- clinic_settings stays the practice-wide singleton (timezone, currency, ...);
+-- a location is a physical site that employees and appointments belong to.
+
+CREATE TABLE IF NOT EXISTS clinic_location (
+  location_id   UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name          TEXT NOT NULL,
+  address       TEXT NULL,
+  is_active     BOOLEAN NOT NULL DEFAULT true,
+
+  created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CONSTRAINT clinic_location_name_unique UNIQUE (name)
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'clinic_location_set_updated_at'
+  ) THEN
+    CREATE TRIGGER clinic_location_set_updated_at
+      BEFORE UPDATE ON clinic_location
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+-- Existing single-site data moves to a default location
+INSERT INTO clinic_location (name)
+SELECT 'Main'
+WHERE NOT EXISTS (SELECT 1 FROM clinic_location);
+
+-- ------------------------------------------------------------
+-- Home location of an employee, location of an appointment
+-- ------------------------------------------------------------
+
+ALTER TABLE employee
+  ADD COLUMN IF NOT EXISTS location_id UUID NULL REFERENCES clinic_location(location_id) ON DELETE SET NULL;
+
+ALTER TABLE appointment
+  ADD COLUMN IF NOT EXISTS location_id UUID NULL REFERENCES clinic_location(location_id) ON DELETE RESTRICT;
+
+UPDATE employee
+SET location_id = (SELECT location_id FROM clinic_location ORDER BY created_at LIMIT 1)
+WHERE location_id IS NULL;
+
+UPDATE appointment
+SET location_id = (SELECT location_id FROM clinic_location ORDER BY created_at LIMIT 1)
+WHERE location_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS appointment_location_start_idx ON appointment(location_id, start_at);
+
+-- ------------------------------------------------------------
+-- Per-user location access
+-- ------------------------------------------------------------
+-- No rows for a user = access to every location (single-site default).
+-- Any rows = the user is restricted to those locations. Admins are never restricted.
+
+CREATE TABLE IF NOT EXISTS user_location_access (
+  user_id             UUID NOT NULL REFERENCES dcms_user(user_id) ON DELETE CASCADE,
+  location_id         UUID NOT NULL REFERENCES clinic_location(location_id) ON DELETE CASCADE,
+  granted_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  PRIMARY KEY (user_id, location_id)
+);
+
+COMMIT;
@@ -0,0 +1,23 @@
+-- migrations/023_employee_commission.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Doctor commission rate (payroll)
+-- ------------------------------------------------------------
+-- Basis points like clinic_settings.tax_rates: 1500 = 15% of completed production.
+
+ALTER TABLE employee
+  ADD COLUMN IF NOT EXISTS commission_bp INTEGER NOT NULL DEFAULT 0;
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint WHERE conname = 'employee_commission_bp_chk'
+  ) THEN
+    ALTER TABLE employee
+      ADD CONSTRAINT employee_commission_bp_chk
+      CHECK (commission_bp BETWEEN 0 AND 10000);
+  END IF;
+END $$;
+
+COMMIT;
@@ -0,0 +1,56 @@
+-- migrations/024_document_templates.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Document templates (referral letters, sick-leave certificates, ...)
+-- ------------------------------------------------------------
+-- body is plain text with {placeholders}, see document_template_routes.rs.
+
+CREATE TABLE IF NOT EXISTS document_template (
+  template_id         UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name                TEXT NOT NULL,
+  kind                TEXT NOT NULL DEFAULT 'other',   -- referral | sick_leave | other
+  title               TEXT NOT NULL,
+  body                TEXT NOT NULL,
+  is_active           BOOLEAN NOT NULL DEFAULT true,
+
+  created_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CONSTRAINT document_template_kind_chk CHECK (kind IN ('referral', 'sick_leave', 'other'))
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'document_template_set_updated_at'
+  ) THEN
+    CREATE TRIGGER document_template_set_updated_at
+      BEFORE UPDATE ON document_template
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+-- ------------------------------------------------------------
+-- Generated patient documents (stored attachment = rendered PDF)
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS patient_document (
+  document_id         UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  patient_id          UUID NOT NULL REFERENCES patient(patient_id) ON DELETE CASCADE,
+  template_id         UUID NULL REFERENCES document_template(template_id) ON DELETE SET NULL,
+  appointment_id      UUID NULL REFERENCES appointment(appointment_id) ON DELETE SET NULL,
+
+  title               TEXT NOT NULL,
+  body                TEXT NOT NULL,                 -- rendered text (what the PDF shows)
+  content_type        TEXT NOT NULL DEFAULT 'application/pdf',
+  content             BYTEA NOT NULL,
+
+  created_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  created_at          TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS patient_document_patient_idx ON patient_document(patient_id, created_at DESC);
+
+COMMIT;
@@ -0,0 +1,35 @@
+-- migrations/025_patient_deletion.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Patient deletion requests (GDPR-style anonymization)
+-- ------------------------------------------------------------
+-- POST /patients/{id}/request_deletion sets deletion_due_at = now() + retention;
+-- the patient_retention job scrubs PII once it is due and stamps anonymized_at.
+-- Appointments/plan items stay (production + financial aggregates).
+
+ALTER TABLE clinic_settings
+  ADD COLUMN IF NOT EXISTS patient_retention_days INTEGER NOT NULL DEFAULT 30;
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint WHERE conname = 'clinic_settings_patient_retention_days_chk'
+  ) THEN
+    ALTER TABLE clinic_settings
+      ADD CONSTRAINT clinic_settings_patient_retention_days_chk
+      CHECK (patient_retention_days BETWEEN 0 AND 3650);
+  END IF;
+END $$;
+
+ALTER TABLE patient
+  ADD COLUMN IF NOT EXISTS deletion_requested_at TIMESTAMPTZ NULL,
+  ADD COLUMN IF NOT EXISTS deletion_requested_by_user_id UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  ADD COLUMN IF NOT EXISTS deletion_due_at TIMESTAMPTZ NULL,
+  ADD COLUMN IF NOT EXISTS anonymized_at TIMESTAMPTZ NULL;
+
+CREATE INDEX IF NOT EXISTS patient_deletion_due_idx
+  ON patient(deletion_due_at)
+  WHERE deletion_due_at IS NOT NULL AND anonymized_at IS NULL;
+
+COMMIT;
@@ -0,0 +1,23 @@
+-- migrations/026_session_rotation.sql
+-- Refresh-token rotation bookkeeping:
+-- - rotated_count: how many times /auth/refresh rotated this session's token
+-- - session_token_rotated: hashes of tokens that were rotated away; presenting
+--   one again is treated as a stolen token and revokes the session
+-- - revoke_reason: why a session was revoked by the server (NULL = user/admin action)
+
+BEGIN;
+
+ALTER TABLE session_token
+  ADD COLUMN IF NOT EXISTS rotated_count INTEGER NOT NULL DEFAULT 0,
+  ADD COLUMN IF NOT EXISTS revoke_reason TEXT NULL;
+
+CREATE TABLE IF NOT EXISTS session_token_rotated (
+  token_hash        TEXT PRIMARY KEY,
+  session_token_id  UUID NOT NULL REFERENCES session_token(session_token_id) ON DELETE CASCADE,
+  rotated_at        TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS session_token_rotated_session_idx
+  ON session_token_rotated(session_token_id);
+
+COMMIT;
@@ -0,0 +1,31 @@
+-- migrations/027_audit_log.sql
+-- Append-only audit trail for security-relevant actions.
+-- action is a dotted name ('impersonation.start', 'session.revoke', ...);
+-- target_type/target_id point at the affected row, details holds the rest.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS audit_log (
+  audit_log_id        UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+
+  actor_user_id       UUID NULL REFERENCES "dcms_user"(user_id) ON DELETE SET NULL,
+  session_token_id    UUID NULL REFERENCES session_token(session_token_id) ON DELETE SET NULL,
+
+  action              TEXT NOT NULL,
+  target_type         TEXT NULL,
+  target_id           UUID NULL,
+  details             JSONB NOT NULL DEFAULT '{}'::jsonb,
+
+  created_at          TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_created_idx
+  ON audit_log(created_at DESC);
+
+CREATE INDEX IF NOT EXISTS audit_log_actor_idx
+  ON audit_log(actor_user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS audit_log_target_idx
+  ON audit_log(target_type, target_id, created_at DESC);
+
+COMMIT;
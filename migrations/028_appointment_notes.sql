@@ -0,0 +1,34 @@
+-- migrations/028_appointment_notes.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Append-only note timeline per appointment
+-- appointment.note stays as a denormalized copy of the latest pinned note
+-- (read by schedule views, reminders and older clients)
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS appointment_note (
+  appointment_note_id     UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+
+  appointment_id          UUID NOT NULL REFERENCES appointment(appointment_id) ON DELETE CASCADE,
+  -- null = author deleted, or migrated from appointment.note without a known author
+  author_user_id          UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+
+  note_text               TEXT NOT NULL,
+  is_pinned               BOOLEAN NOT NULL DEFAULT false,
+
+  created_at              TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS appointment_note_appointment_created_idx
+  ON appointment_note(appointment_id, created_at);
+
+-- existing single-text notes become the first (pinned) timeline entry
+INSERT INTO appointment_note (appointment_id, author_user_id, note_text, is_pinned, created_at)
+SELECT a.appointment_id, a.created_by_user_id, a.note, true, a.updated_at
+FROM appointment a
+WHERE a.note IS NOT NULL
+  AND btrim(a.note) <> ''
+  AND NOT EXISTS (SELECT 1 FROM appointment_note n WHERE n.appointment_id = a.appointment_id);
+
+COMMIT;
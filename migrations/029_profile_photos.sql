@@ -0,0 +1,34 @@
+-- migrations/029_profile_photos.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Profile photos for patients and employees (schedule UI faces)
+-- Images are re-encoded server-side (JPEG): a bounded "full" version and a
+-- square avatar. photo_updated_at on the owner row versions the photo URL.
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS profile_photo (
+  profile_photo_id        UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+
+  patient_id              UUID NULL UNIQUE REFERENCES patient(patient_id) ON DELETE CASCADE,
+  employee_id             UUID NULL UNIQUE REFERENCES employee(employee_id) ON DELETE CASCADE,
+
+  content_type            TEXT NOT NULL DEFAULT 'image/jpeg',
+  full_image              BYTEA NOT NULL,
+  avatar_image            BYTEA NOT NULL,
+  width                   INT NOT NULL,
+  height                  INT NOT NULL,
+
+  uploaded_by_user_id     UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  updated_at              TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CONSTRAINT profile_photo_one_owner CHECK (num_nonnulls(patient_id, employee_id) = 1)
+);
+
+ALTER TABLE patient
+  ADD COLUMN IF NOT EXISTS photo_updated_at TIMESTAMPTZ NULL;
+
+ALTER TABLE employee
+  ADD COLUMN IF NOT EXISTS photo_updated_at TIMESTAMPTZ NULL;
+
+COMMIT;
@@ -0,0 +1,24 @@
+-- migrations/030_employee_services.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Which services an employee (doctor) performs, with optional duration override
+-- An employee with no rows performs every service (same convention as
+-- user_location_access), so existing schedules keep working.
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS employee_service (
+  employee_id             UUID NOT NULL REFERENCES employee(employee_id) ON DELETE CASCADE,
+  service_id              UUID NOT NULL REFERENCES service_catalog(service_id) ON DELETE CASCADE,
+
+  -- null = service_catalog.default_duration_min
+  duration_min            INT NULL CHECK (duration_min IS NULL OR duration_min > 0),
+
+  created_at              TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  PRIMARY KEY (employee_id, service_id)
+);
+
+CREATE INDEX IF NOT EXISTS employee_service_service_idx ON employee_service(service_id);
+
+COMMIT;
@@ -0,0 +1,40 @@
+-- migrations/031_overlap_policy.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Double-booking policy (validated by the API, see src/overlap_policy.rs)
+-- ------------------------------------------------------------
+-- Shape:
+--   default  rule    clinic-wide rule
+--   doctors  object  employee_id -> rule, overrides the default for that doctor
+-- rule:
+--   {"mode": "strict"}                               no overlaps
+--   {"mode": "concurrent", "max_concurrent": 2}     up to N at the same time
+--   {"mode": "warn"}                                 allowed, response carries a warning
+
+ALTER TABLE clinic_settings
+  ADD COLUMN IF NOT EXISTS overlap_policy JSONB NOT NULL DEFAULT '{
+    "default": {"mode": "strict"},
+    "doctors": {}
+  }'::jsonb;
+
+-- ------------------------------------------------------------
+-- Appointments booked as an allowed overlap (policy or manager override)
+-- are exempt from the exclusion constraint; everything else stays guarded
+-- by the database as before.
+-- ------------------------------------------------------------
+
+ALTER TABLE appointment
+  ADD COLUMN IF NOT EXISTS overlap_allowed BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE appointment DROP CONSTRAINT IF EXISTS appointment_no_overlap_doctor;
+
+ALTER TABLE appointment
+  ADD CONSTRAINT appointment_no_overlap_doctor
+  EXCLUDE USING gist (
+    doctor_employee_id WITH =,
+    tstzrange(start_at, end_at, '[)') WITH &&
+  )
+  WHERE (status NOT IN (1,3) AND NOT overlap_allowed);
+
+COMMIT;
@@ -0,0 +1,35 @@
+-- migrations/032_no_show_risk.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- When an appointment was canceled (status 1), to tell late cancels apart.
+-- Existing canceled rows: best guess is their last update.
+-- ------------------------------------------------------------
+
+ALTER TABLE appointment
+  ADD COLUMN IF NOT EXISTS canceled_at TIMESTAMPTZ NULL;
+
+UPDATE appointment
+SET canceled_at = updated_at
+WHERE status = 1 AND canceled_at IS NULL;
+
+-- ------------------------------------------------------------
+-- Per-patient no-show risk, rebuilt by the background job
+-- (see src/jobs/no_show_risk.rs). Patients with too little history have no row.
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS patient_no_show_risk (
+  patient_id          UUID PRIMARY KEY REFERENCES patient(patient_id) ON DELETE CASCADE,
+
+  -- past appointments in the window (early cancels excluded)
+  appointment_count   INT NOT NULL,
+  no_show_count       INT NOT NULL,
+  late_cancel_count   INT NOT NULL,
+
+  -- 0..100
+  score               SMALLINT NOT NULL CHECK (score BETWEEN 0 AND 100),
+
+  computed_at         TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+COMMIT;
@@ -0,0 +1,86 @@
+-- migrations/033_report_views.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Daily report aggregates (materialized), per clinic-local day and doctor.
+-- Rebuilt by the report refresh job (src/jobs/report_refresh.rs) and by
+-- POST /admin/reports/refresh; reports read these instead of raw appointments.
+-- ------------------------------------------------------------
+
+-- production: plan items of finished appointments at the current catalog price
+CREATE MATERIALIZED VIEW IF NOT EXISTS report_daily_production AS
+SELECT
+  (a.start_at AT TIME ZONE COALESCE((SELECT timezone FROM clinic_settings WHERE singleton_id = TRUE), 'UTC'))::date AS day,
+  a.doctor_employee_id,
+  s.service_type,
+  SUM(pi.qty)::int8 AS qty,
+  SUM(pi.qty::int8 * s.price_cents)::int8 AS total_cents
+FROM appointment a
+JOIN appointment_plan_item pi ON pi.appointment_id = a.appointment_id
+JOIN service_catalog s ON s.service_id = pi.service_id
+WHERE a.status <> 1
+  AND (a.dismissed_at IS NOT NULL OR a.status = 5)
+GROUP BY 1, 2, 3
+WITH DATA;
+
+CREATE UNIQUE INDEX IF NOT EXISTS report_daily_production_key
+  ON report_daily_production(day, doctor_employee_id, service_type);
+
+-- appointment KPIs; outcome is derived like GET /reports/appointments/stats does
+CREATE MATERIALIZED VIEW IF NOT EXISTS report_daily_appointments AS
+WITH a AS (
+  SELECT
+    a.*,
+    CASE
+      WHEN a.status = 1 THEN 'canceled'
+      WHEN a.dismissed_at IS NOT NULL OR a.status = 5 THEN 'finished'
+      WHEN a.seated_at IS NOT NULL THEN 'seated'
+      WHEN a.arrived_at IS NOT NULL THEN 'arrived'
+      WHEN a.status = 3 THEN 'no_show'
+      ELSE 'scheduled'
+    END AS outcome
+  FROM appointment a
+)
+SELECT
+  (a.start_at AT TIME ZONE COALESCE((SELECT timezone FROM clinic_settings WHERE singleton_id = TRUE), 'UTC'))::date AS day,
+  a.doctor_employee_id,
+
+  COUNT(*)::int8 AS total,
+  COUNT(*) FILTER (WHERE a.outcome = 'scheduled')::int8 AS scheduled,
+  COUNT(*) FILTER (WHERE a.outcome = 'arrived')::int8   AS arrived,
+  COUNT(*) FILTER (WHERE a.outcome = 'seated')::int8    AS seated,
+  COUNT(*) FILTER (WHERE a.outcome = 'finished')::int8  AS finished,
+  COUNT(*) FILTER (WHERE a.outcome = 'canceled')::int8  AS canceled,
+  COUNT(*) FILTER (WHERE a.outcome = 'no_show')::int8   AS no_show,
+
+  -- as of the last refresh
+  COUNT(*) FILTER (WHERE a.outcome <> 'canceled' AND a.start_at < now())::int8 AS past_kept,
+
+  COUNT(*) FILTER (WHERE a.seated_at IS NOT NULL AND a.dismissed_at > a.seated_at)::int8 AS timed_visits,
+  COALESCE(SUM(EXTRACT(EPOCH FROM (a.dismissed_at - a.seated_at)) / 60.0)
+    FILTER (WHERE a.seated_at IS NOT NULL AND a.dismissed_at > a.seated_at), 0)::float8 AS visit_minutes,
+
+  COALESCE(SUM(EXTRACT(EPOCH FROM (a.end_at - a.start_at)) / 60)
+    FILTER (WHERE a.outcome NOT IN ('canceled','no_show')), 0)::int8 AS booked_minutes
+FROM a
+GROUP BY 1, 2
+WITH DATA;
+
+CREATE UNIQUE INDEX IF NOT EXISTS report_daily_appointments_key
+  ON report_daily_appointments(day, doctor_employee_id);
+
+-- ------------------------------------------------------------
+-- Last refresh per view (shown on reports as `refreshed_at`)
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS report_refresh (
+  view_name     TEXT PRIMARY KEY,
+  refreshed_at  TIMESTAMPTZ NOT NULL,
+  duration_ms   INT NOT NULL
+);
+
+INSERT INTO report_refresh (view_name, refreshed_at, duration_ms)
+VALUES ('report_daily_production', now(), 0), ('report_daily_appointments', now(), 0)
+ON CONFLICT (view_name) DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,37 @@
+-- migrations/034_login_event.sql
+-- Every login attempt (staff and patient portal), for reviewing suspicious access.
+-- Failed attempts for unknown usernames have user_id NULL and keep the username typed.
+-- failure_reason: 'unknown_user' | 'disabled' | 'role_not_allowed' | 'bad_password'
+-- session_token_id is not a FK: dead sessions are purged, their login history stays.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS login_event (
+  login_event_id      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+
+  user_id             UUID NULL REFERENCES "dcms_user"(user_id) ON DELETE CASCADE,
+  username            TEXT NOT NULL,
+
+  success             BOOLEAN NOT NULL,
+  failure_reason      TEXT NULL,
+
+  session_type        SMALLINT NOT NULL,
+  session_token_id    UUID NULL,
+
+  ip                  TEXT NULL,
+  user_agent          TEXT NULL,
+  device_name         TEXT NULL,
+
+  created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CHECK (success = (failure_reason IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS login_event_user_idx
+  ON login_event(user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS login_event_failed_idx
+  ON login_event(created_at DESC)
+  WHERE NOT success;
+
+COMMIT;
@@ -0,0 +1,30 @@
+-- migrations/035_employee_user_link.sql
+-- Employee profiles created through the API (POST /users/{id}/employee_profile,
+-- create_user with an inline employee):
+-- - employee_display_number comes from a sequence (ordered, never reused),
+--   continuing after the highest number already in use
+-- - a user is linked to at most one employee
+
+BEGIN;
+
+CREATE SEQUENCE IF NOT EXISTS employee_display_seq
+    INCREMENT 1
+    NO MINVALUE
+    NO MAXVALUE
+    CACHE 1;
+
+SELECT setval(
+  'employee_display_seq',
+  GREATEST(COALESCE((SELECT MAX(employee_display_number) FROM employee), 0), 1000000) + 1,
+  false
+);
+
+ALTER TABLE employee
+    ALTER COLUMN employee_display_number
+    SET DEFAULT nextval('employee_display_seq');
+
+CREATE UNIQUE INDEX IF NOT EXISTS employee_user_id_key
+    ON employee(user_id)
+    WHERE user_id IS NOT NULL;
+
+COMMIT;
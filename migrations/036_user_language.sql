@@ -0,0 +1,20 @@
+-- migrations/036_user_language.sql
+-- Language for localized API messages (src/i18n.rs); NULL = use Accept-Language.
+
+BEGIN;
+
+ALTER TABLE "dcms_user"
+  ADD COLUMN IF NOT EXISTS preferred_language TEXT NULL;
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint WHERE conname = 'dcms_user_preferred_language_chk'
+  ) THEN
+    ALTER TABLE "dcms_user"
+      ADD CONSTRAINT dcms_user_preferred_language_chk
+      CHECK (preferred_language IN ('en', 'mn'));
+  END IF;
+END $$;
+
+COMMIT;
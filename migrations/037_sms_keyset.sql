@@ -0,0 +1,13 @@
+-- migrations/037_sms_keyset.sql
+-- Keyset pagination for SMS search (GET /api/v2/sms, src/cursor.rs):
+-- ORDER BY sent_at DESC, sms_id DESC with WHERE (sent_at, sms_id) < cursor.
+
+BEGIN;
+
+CREATE INDEX IF NOT EXISTS sms_sent_at_id_idx
+  ON sms(sent_at DESC, sms_id DESC);
+
+CREATE INDEX IF NOT EXISTS sms_phone_sent_at_id_idx
+  ON sms(phone_number_id, sent_at DESC, sms_id DESC);
+
+COMMIT;
@@ -0,0 +1,20 @@
+-- migrations/038_sms_read_state.sql
+-- Read state of incoming SMS for the per-patient conversation view
+-- (GET /patients/{id}/conversation). Shared by the whole front desk, not per user.
+-- Messages received before this migration count as read.
+
+BEGIN;
+
+ALTER TABLE sms
+  ADD COLUMN IF NOT EXISTS read_at TIMESTAMPTZ NULL,
+  ADD COLUMN IF NOT EXISTS read_by_user_id UUID NULL REFERENCES "dcms_user"(user_id) ON DELETE SET NULL;
+
+UPDATE sms
+SET read_at = created_at
+WHERE direction = 0 AND read_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS sms_unread_idx
+  ON sms(phone_number_id)
+  WHERE direction = 0 AND read_at IS NULL;
+
+COMMIT;
@@ -0,0 +1,18 @@
+-- migrations/039_contact_consent.sql
+-- Do-not-contact flags, per phone number and per patient (patient-level covers
+-- every number). NULL = allowed; the timestamp records when the opt-out happened.
+-- marketing: bulk_send (default purpose) / campaigns
+-- transactional: appointment reminders, bulk_send with purpose=transactional
+-- An inbound "STOP" sets both on that phone number.
+
+BEGIN;
+
+ALTER TABLE phone_number
+  ADD COLUMN IF NOT EXISTS marketing_opt_out_at TIMESTAMPTZ NULL,
+  ADD COLUMN IF NOT EXISTS transactional_opt_out_at TIMESTAMPTZ NULL;
+
+ALTER TABLE patient
+  ADD COLUMN IF NOT EXISTS marketing_opt_out_at TIMESTAMPTZ NULL,
+  ADD COLUMN IF NOT EXISTS transactional_opt_out_at TIMESTAMPTZ NULL;
+
+COMMIT;
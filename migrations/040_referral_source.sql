@@ -0,0 +1,45 @@
+-- migrations/040_referral_source.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Referral sources ("how did you hear about us")
+-- ------------------------------------------------------------
+-- Clinic-managed catalog (GET/POST /clinic/referral_sources); a patient points at
+-- one, picked at registration. Sources are deactivated rather than deleted so
+-- GET /reports/referrals keeps attributing old patients.
+
+CREATE TABLE IF NOT EXISTS referral_source (
+  referral_source_id  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name                TEXT NOT NULL,
+  is_active           BOOLEAN NOT NULL DEFAULT true,
+
+  created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CONSTRAINT referral_source_name_unique UNIQUE (name)
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'referral_source_set_updated_at'
+  ) THEN
+    CREATE TRIGGER referral_source_set_updated_at
+      BEFORE UPDATE ON referral_source
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+INSERT INTO referral_source (name)
+VALUES ('Google'), ('Social media'), ('Friend / family'), ('Insurance'), ('Walk-in')
+ON CONFLICT (name) DO NOTHING;
+
+ALTER TABLE patient
+  ADD COLUMN IF NOT EXISTS referral_source_id UUID NULL
+    REFERENCES referral_source(referral_source_id);
+
+CREATE INDEX IF NOT EXISTS patient_referral_source_idx
+  ON patient(referral_source_id)
+  WHERE referral_source_id IS NOT NULL;
+
+COMMIT;
@@ -0,0 +1,41 @@
+-- migrations/041_household.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Households (family grouping of patients)
+-- ------------------------------------------------------------
+-- A patient belongs to at most one household. Used by the patient summary
+-- (family members) and POST /appointments/household (back-to-back booking).
+
+CREATE TABLE IF NOT EXISTS household (
+  household_id  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name          TEXT NOT NULL,
+
+  created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at    TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'household_set_updated_at'
+  ) THEN
+    CREATE TRIGGER household_set_updated_at
+      BEFORE UPDATE ON household
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+CREATE TABLE IF NOT EXISTS household_member (
+  household_id  UUID NOT NULL REFERENCES household(household_id) ON DELETE CASCADE,
+  patient_id    UUID NOT NULL REFERENCES patient(patient_id) ON DELETE CASCADE,
+  relationship  TEXT NOT NULL DEFAULT 'other',
+  created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  PRIMARY KEY (household_id, patient_id),
+  CONSTRAINT household_member_patient_unique UNIQUE (patient_id),
+  CONSTRAINT household_member_relationship_check
+    CHECK (relationship IN ('head', 'spouse', 'parent', 'child', 'sibling', 'other'))
+);
+
+COMMIT;
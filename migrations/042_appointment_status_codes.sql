@@ -0,0 +1,18 @@
+-- migrations/042_appointment_status_codes.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- appointment.status follows the codes of 010_appointments.sql again:
+--   0 scheduled, 1 canceled, 2 confirmed, 3 no-show, 4 arrived, 5 finished
+-- The front-desk milestones used to write 2 (arrive), 3 (seat) and 4 (dismiss),
+-- so arrived patients read as "confirmed" and seated ones as "no-show".
+-- The milestone timestamps tell those rows apart from statuses set on purpose.
+-- ------------------------------------------------------------
+
+UPDATE appointment
+SET status = CASE WHEN dismissed_at IS NOT NULL THEN 5 ELSE 4 END
+WHERE (status = 2 AND arrived_at IS NOT NULL)
+   OR (status = 3 AND seated_at IS NOT NULL)
+   OR (status = 4 AND dismissed_at IS NOT NULL);
+
+COMMIT;
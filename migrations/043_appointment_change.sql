@@ -0,0 +1,72 @@
+-- migrations/043_appointment_change.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Per-field change log of appointments (GET /appointments/{id}/history),
+-- fed by a trigger so every writer is covered, jobs included.
+-- Actor = updated_by_user_id of the new row (created_by_user_id on insert):
+-- every UPDATE of appointment must set it, NULL for system jobs.
+-- Not logged: bookkeeping columns and `note` (the note timeline,
+-- appointment_note, has its own authors).
+-- Appointments changed before this migration have no history.
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS appointment_change (
+  appointment_change_id   BIGSERIAL PRIMARY KEY,
+
+  appointment_id          UUID NOT NULL REFERENCES appointment(appointment_id) ON DELETE CASCADE,
+  -- null = system (jobs) or user deleted
+  changed_by_user_id      UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  changed_at              TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  -- column name, or 'created' for the insert
+  field                   TEXT NOT NULL,
+  old_value               JSONB NULL,
+  new_value               JSONB NULL
+);
+
+CREATE INDEX IF NOT EXISTS appointment_change_appointment_idx
+  ON appointment_change(appointment_id, appointment_change_id);
+
+CREATE OR REPLACE FUNCTION appointment_log_change()
+RETURNS trigger AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    INSERT INTO appointment_change (appointment_id, changed_by_user_id, field, new_value)
+    VALUES (
+      NEW.appointment_id,
+      NEW.created_by_user_id,
+      'created',
+      jsonb_build_object(
+        'start_at', NEW.start_at,
+        'end_at', NEW.end_at,
+        'doctor_employee_id', NEW.doctor_employee_id,
+        'status', NEW.status
+      )
+    );
+  ELSE
+    INSERT INTO appointment_change (appointment_id, changed_by_user_id, field, old_value, new_value)
+    SELECT NEW.appointment_id, NEW.updated_by_user_id, n.key, o.value, n.value
+    FROM jsonb_each(to_jsonb(NEW)) n
+    JOIN jsonb_each(to_jsonb(OLD)) o USING (key)
+    WHERE n.value IS DISTINCT FROM o.value
+      AND n.key NOT IN ('updated_at', 'updated_by_user_id', 'created_at', 'created_by_user_id', 'note')
+    ORDER BY n.key;
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'appointment_log_change_trg'
+  ) THEN
+    CREATE TRIGGER appointment_log_change_trg
+    AFTER INSERT OR UPDATE ON appointment
+    FOR EACH ROW
+    EXECUTE FUNCTION appointment_log_change();
+  END IF;
+END $$;
+
+COMMIT;
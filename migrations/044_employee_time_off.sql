@@ -0,0 +1,53 @@
+-- migrations/044_employee_time_off.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Employee time off
+-- ------------------------------------------------------------
+-- Requested by the employee (or admin/manager on their behalf), decided by
+-- admin/manager. Approved ranges block the doctor's slots (availability search,
+-- booking); approval opens a RESCHEDULE_APPOINTMENT task per conflicting
+-- appointment for the front desk.
+
+CREATE TABLE IF NOT EXISTS employee_time_off (
+  time_off_id           UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  employee_id           UUID NOT NULL REFERENCES employee(employee_id) ON DELETE CASCADE,
+
+  start_at              TIMESTAMPTZ NOT NULL,
+  end_at                TIMESTAMPTZ NOT NULL,
+  reason                TEXT NULL,
+
+  status                SMALLINT NOT NULL DEFAULT 0 CHECK (status IN (0,1,2,3)),
+  -- 0 pending, 1 approved, 2 denied, 3 canceled
+
+  requested_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  decided_by_user_id    UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  decided_at            TIMESTAMPTZ NULL,
+  decision_note         TEXT NULL,
+
+  created_at            TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at            TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CONSTRAINT employee_time_off_range_check CHECK (end_at > start_at)
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'employee_time_off_set_updated_at'
+  ) THEN
+    CREATE TRIGGER employee_time_off_set_updated_at
+      BEFORE UPDATE ON employee_time_off
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS employee_time_off_employee_idx
+  ON employee_time_off(employee_id, start_at);
+
+-- availability / booking checks only look at approved ranges
+CREATE INDEX IF NOT EXISTS employee_time_off_approved_idx
+  ON employee_time_off(employee_id, start_at, end_at)
+  WHERE status = 1;
+
+COMMIT;
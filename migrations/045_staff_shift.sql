@@ -0,0 +1,47 @@
+-- migrations/045_staff_shift.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Staff shifts
+-- ------------------------------------------------------------
+-- Roster for non-doctor staff (reception, assistants); doctors are scheduled
+-- by business hours and time off. Assigning an assistant to an appointment
+-- requires a shift covering it.
+
+CREATE TABLE IF NOT EXISTS staff_shift (
+  shift_id             UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  employee_id          UUID NOT NULL REFERENCES employee(employee_id) ON DELETE CASCADE,
+  location_id          UUID NULL REFERENCES clinic_location(location_id) ON DELETE SET NULL,
+
+  start_at             TIMESTAMPTZ NOT NULL,
+  end_at               TIMESTAMPTZ NOT NULL,
+  note                 TEXT NULL,
+
+  created_by_user_id   UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  updated_by_user_id   UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  created_at           TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at           TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CONSTRAINT staff_shift_range_check CHECK (end_at > start_at),
+  -- one shift at a time per employee (back-to-back is fine)
+  CONSTRAINT staff_shift_no_overlap
+    EXCLUDE USING gist (
+      employee_id WITH =,
+      tstzrange(start_at, end_at, '[)') WITH &&
+    )
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'staff_shift_set_updated_at'
+  ) THEN
+    CREATE TRIGGER staff_shift_set_updated_at
+      BEFORE UPDATE ON staff_shift
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS staff_shift_start_idx ON staff_shift(start_at);
+
+COMMIT;
@@ -0,0 +1,66 @@
+-- migrations/046_procedure_template.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Procedure note templates
+-- ------------------------------------------------------------
+-- Managed by doctors/managers (/procedure_templates). `fields` is a JSON array of
+-- field definitions ({key, label, kind, required, options, default}); `body` is
+-- the note text with {key} placeholders. Deactivated rather than deleted, notes
+-- keep pointing at the template they were written from.
+
+CREATE TABLE IF NOT EXISTS procedure_template (
+  template_id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name                 TEXT NOT NULL,
+  -- suggested for appointments planning this service
+  service_id           UUID NULL REFERENCES service_catalog(service_id) ON DELETE SET NULL,
+
+  fields               JSONB NOT NULL DEFAULT '[]'::jsonb,
+  body                 TEXT NOT NULL,
+  is_active            BOOLEAN NOT NULL DEFAULT true,
+
+  created_by_user_id   UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  updated_by_user_id   UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  created_at           TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at           TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CONSTRAINT procedure_template_fields_array CHECK (jsonb_typeof(fields) = 'array')
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS procedure_template_name_unique
+  ON procedure_template (lower(name));
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'procedure_template_set_updated_at'
+  ) THEN
+    CREATE TRIGGER procedure_template_set_updated_at
+      BEFORE UPDATE ON procedure_template
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+INSERT INTO procedure_template (name, fields, body)
+VALUES (
+  'Composite filling',
+  '[
+    {"key": "tooth", "label": "Tooth", "kind": "tooth", "required": true},
+    {"key": "surfaces", "label": "Surfaces", "kind": "surfaces", "required": true},
+    {"key": "anesthetic", "label": "Anesthetic", "kind": "choice",
+     "options": ["None", "Lidocaine 2%", "Articaine 4%"], "default": "None"},
+    {"key": "materials", "label": "Materials", "kind": "text", "default": "Composite resin"},
+    {"key": "shade", "label": "Shade", "kind": "text"},
+    {"key": "notes", "label": "Notes", "kind": "text"}
+  ]'::jsonb,
+  E'Composite filling, tooth {tooth}, surfaces {surfaces}.\nAnesthetic: {anesthetic}\nMaterials: {materials}\nShade: {shade}\n{notes}'
+)
+ON CONFLICT DO NOTHING;
+
+-- procedure notes: the appointment note a template rendered, with its values
+ALTER TABLE appointment_note
+  ADD COLUMN IF NOT EXISTS procedure_template_id UUID NULL
+    REFERENCES procedure_template(template_id) ON DELETE SET NULL,
+  ADD COLUMN IF NOT EXISTS procedure_fields JSONB NULL;
+
+COMMIT;
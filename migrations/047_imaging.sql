@@ -0,0 +1,68 @@
+-- migrations/047_imaging.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Imaging orders (radiographs, CBCT)
+-- ------------------------------------------------------------
+-- A doctor requests an image; whoever takes it marks it performed (with dose
+-- notes) and uploads the file(s). Files are stored like generated documents:
+-- bytes in imaging_attachment, served by /imaging_attachments/{id}.
+
+CREATE TABLE IF NOT EXISTS imaging_order (
+  imaging_order_id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  patient_id                UUID NOT NULL REFERENCES patient(patient_id) ON DELETE CASCADE,
+  appointment_id            UUID NULL REFERENCES appointment(appointment_id) ON DELETE SET NULL,
+
+  modality                  SMALLINT NOT NULL CHECK (modality IN (0,1,2,3,4,5)),
+  -- 0 periapical, 1 bitewing, 2 occlusal, 3 panoramic, 4 cephalometric, 5 cbct
+  region                    TEXT NULL,          -- teeth / area, free text ("36-37", "upper left")
+  reason                    TEXT NULL,
+
+  status                    SMALLINT NOT NULL DEFAULT 0 CHECK (status IN (0,1,2)),
+  -- 0 requested, 1 performed, 2 canceled
+
+  requested_by_employee_id  UUID NOT NULL REFERENCES employee(employee_id) ON DELETE RESTRICT,
+  requested_at              TIMESTAMPTZ NOT NULL DEFAULT now(),
+  performed_at              TIMESTAMPTZ NULL,
+  performed_by_user_id      UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  dose_notes                TEXT NULL,          -- exposure settings / dose as recorded by the device
+  canceled_at               TIMESTAMPTZ NULL,
+
+  created_at                TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at                TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'imaging_order_set_updated_at'
+  ) THEN
+    CREATE TRIGGER imaging_order_set_updated_at
+      BEFORE UPDATE ON imaging_order
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS imaging_order_patient_idx ON imaging_order(patient_id, requested_at DESC);
+
+-- worklist: orders still to be taken
+CREATE INDEX IF NOT EXISTS imaging_order_requested_idx
+  ON imaging_order(requested_at)
+  WHERE status = 0;
+
+CREATE TABLE IF NOT EXISTS imaging_attachment (
+  attachment_id        UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  imaging_order_id     UUID NOT NULL REFERENCES imaging_order(imaging_order_id) ON DELETE CASCADE,
+
+  file_name            TEXT NOT NULL,
+  content_type         TEXT NOT NULL,
+  size_bytes           INTEGER NOT NULL,
+  content              BYTEA NOT NULL,
+
+  uploaded_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  created_at           TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS imaging_attachment_order_idx ON imaging_attachment(imaging_order_id, created_at);
+
+COMMIT;
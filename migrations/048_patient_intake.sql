@@ -0,0 +1,68 @@
+-- migrations/048_patient_intake.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Pre-visit intake forms
+-- ------------------------------------------------------------
+-- Staff text the patient a link (/intake/{token}); the patient fills in
+-- demographics and medical history without logging in. Only sha256(token) is
+-- stored. A link works until expires_at and only once; staff then approve the
+-- submission (copied onto patient / patient_medical_history) or reject it.
+-- `submission` is the form as JSON, encrypted like other PII columns.
+
+CREATE TABLE IF NOT EXISTS patient_intake (
+  intake_id            UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  patient_id           UUID NOT NULL REFERENCES patient(patient_id) ON DELETE CASCADE,
+  token_hash           TEXT NOT NULL UNIQUE,
+  -- where the link was texted; NULL = handed over some other way
+  phone_number_id      UUID NULL REFERENCES phone_number(phone_number_id) ON DELETE SET NULL,
+
+  status               SMALLINT NOT NULL DEFAULT 0 CHECK (status IN (0,1,2,3,4)),
+  -- 0 sent, 1 submitted, 2 approved, 3 rejected, 4 revoked
+  expires_at           TIMESTAMPTZ NOT NULL,
+
+  submission           TEXT NULL,
+  submitted_at         TIMESTAMPTZ NULL,
+
+  reviewed_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  reviewed_at          TIMESTAMPTZ NULL,
+  review_note          TEXT NULL,
+
+  created_by_user_id   UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  created_at           TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at           TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'patient_intake_set_updated_at'
+  ) THEN
+    CREATE TRIGGER patient_intake_set_updated_at
+      BEFORE UPDATE ON patient_intake
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS patient_intake_patient_idx ON patient_intake(patient_id, created_at DESC);
+
+-- review queue
+CREATE INDEX IF NOT EXISTS patient_intake_submitted_idx
+  ON patient_intake(submitted_at)
+  WHERE status = 1;
+
+-- ------------------------------------------------------------
+-- Medical history (one row per patient, encrypted text columns)
+-- ------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS patient_medical_history (
+  patient_id           UUID PRIMARY KEY REFERENCES patient(patient_id) ON DELETE CASCADE,
+  allergies            TEXT NULL,
+  medications          TEXT NULL,
+  conditions           TEXT NULL,
+  notes                TEXT NULL,
+
+  updated_by_user_id   UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  updated_at           TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+COMMIT;
@@ -0,0 +1,25 @@
+-- migrations/049_kiosk_device.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Reception kiosks (patient self check-in)
+-- ------------------------------------------------------------
+-- A registered tablet holds a long-lived device token (only sha256 stored).
+-- Kiosk tokens are not sessions: the regular Bearer auth never accepts them and
+-- they only open /kiosk/*. Revoked rather than deleted, so the audit trail
+-- (details.kiosk_id) keeps pointing at a device.
+
+CREATE TABLE IF NOT EXISTS kiosk_device (
+  kiosk_id             UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name                 TEXT NOT NULL,
+  -- check-ins only match appointments at this location; NULL = any
+  location_id          UUID NULL REFERENCES clinic_location(location_id) ON DELETE SET NULL,
+  token_hash           TEXT NOT NULL UNIQUE,
+
+  created_by_user_id   UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  created_at           TIMESTAMPTZ NOT NULL DEFAULT now(),
+  last_seen_at         TIMESTAMPTZ NULL,
+  revoked_at           TIMESTAMPTZ NULL
+);
+
+COMMIT;
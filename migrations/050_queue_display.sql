@@ -0,0 +1,16 @@
+-- migrations/050_queue_display.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Waiting-room displays
+-- ------------------------------------------------------------
+-- Displays are registered like kiosks (same table, same device tokens); `kind`
+-- decides which device routes a token opens: 0 check-in kiosk, 1 queue display.
+ALTER TABLE kiosk_device
+  ADD COLUMN IF NOT EXISTS kind SMALLINT NOT NULL DEFAULT 0 CHECK (kind IN (0,1));
+
+-- The doctor's treatment room as shown on the display ("Room 2", "Chair B")
+ALTER TABLE employee
+  ADD COLUMN IF NOT EXISTS room TEXT NULL;
+
+COMMIT;
@@ -0,0 +1,35 @@
+-- migrations/051_appointment_feedback.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Post-visit feedback (1–5 rating + comment)
+-- ------------------------------------------------------------
+-- With clinic_settings.feedback_sms_enabled (and FEEDBACK_FORM_URL set) the
+-- feedback_requests job texts a rating link shortly after an appointment is
+-- dismissed; one request per appointment. Only the token hash is stored, the
+-- link works once and expires. comment is PII (encrypted like sms_text).
+
+ALTER TABLE clinic_settings
+  ADD COLUMN IF NOT EXISTS feedback_sms_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS appointment_feedback (
+  feedback_id       UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  appointment_id    UUID NOT NULL UNIQUE REFERENCES appointment(appointment_id) ON DELETE CASCADE,
+  token_hash        TEXT NOT NULL UNIQUE,
+  phone_number_id   UUID NULL REFERENCES phone_number(phone_number_id) ON DELETE SET NULL,
+  sent_at           TIMESTAMPTZ NOT NULL DEFAULT now(),
+  expires_at        TIMESTAMPTZ NOT NULL,
+
+  rating            SMALLINT NULL CHECK (rating BETWEEN 1 AND 5),
+  comment           TEXT NULL,
+  submitted_at      TIMESTAMPTZ NULL,
+
+  CONSTRAINT appointment_feedback_submitted_chk CHECK ((rating IS NULL) = (submitted_at IS NULL))
+);
+
+-- The job looks for recently dismissed appointments without a request
+CREATE INDEX IF NOT EXISTS appointment_dismissed_at_idx
+  ON appointment(dismissed_at)
+  WHERE dismissed_at IS NOT NULL;
+
+COMMIT;
@@ -0,0 +1,20 @@
+-- migrations/052_sms_reply_policy.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Inbound SMS replies to reminders (see src/sms_replies.rs)
+-- ------------------------------------------------------------
+-- Shape (validated by the API):
+--   enabled              bool     master switch
+--   confirm_keywords     [text]   first word of the reply confirms the appointment
+--   reschedule_keywords  [text]   first word opens a RESCHEDULE_APPOINTMENT task
+-- A reply applies to the patient's next reminded appointment that hasn't started.
+
+ALTER TABLE clinic_settings
+  ADD COLUMN IF NOT EXISTS sms_reply_policy JSONB NOT NULL DEFAULT '{
+    "enabled": false,
+    "confirm_keywords": ["1"],
+    "reschedule_keywords": ["2"]
+  }'::jsonb;
+
+COMMIT;
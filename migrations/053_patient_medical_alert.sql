@@ -0,0 +1,30 @@
+-- migrations/053_patient_medical_alert.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Clinical alerts (allergy to anesthetic, anticoagulants, ...)
+-- ------------------------------------------------------------
+-- Short structured flags next to the free-text patient_medical_history. Active
+-- alerts are shown when booking: POST /appointments answers 409 CLINICAL_ALERTS
+-- until the receptionist resends with acknowledge_warnings=true.
+-- kind: 0 allergy, 1 medication, 2 condition, 3 other
+-- label/note are PII (encrypted like the medical history). Resolved, not deleted.
+
+CREATE TABLE IF NOT EXISTS patient_medical_alert (
+  alert_id             UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  patient_id           UUID NOT NULL REFERENCES patient(patient_id) ON DELETE CASCADE,
+  kind                 SMALLINT NOT NULL CHECK (kind IN (0,1,2,3)),
+  label                TEXT NOT NULL,
+  note                 TEXT NULL,
+
+  created_by_user_id   UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  created_at           TIMESTAMPTZ NOT NULL DEFAULT now(),
+  resolved_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  resolved_at          TIMESTAMPTZ NULL
+);
+
+CREATE INDEX IF NOT EXISTS patient_medical_alert_active_idx
+  ON patient_medical_alert(patient_id)
+  WHERE resolved_at IS NULL;
+
+COMMIT;
@@ -0,0 +1,79 @@
+-- migrations/054_hq_change.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Change feed for the DCMS HQ sync (hq_grpc ChangeFeed): one row per
+-- written patient / appointment, fed by triggers so every writer is
+-- covered. Only ids go in here; HQ re-reads the entity.
+-- Child tables report their parent as an update: phone_number -> patient,
+-- appointment_plan_item -> appointment.
+-- Readers page by (tx_id, seq) and only see transactions older than their
+-- snapshot's xmin, so a slow transaction can't commit rows behind a cursor
+-- that already moved past them (seq alone is allocated before commit).
+-- Rows are purged after 30 days (jobs::hq_change_cleanup).
+-- ------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS hq_change (
+  seq           BIGSERIAL PRIMARY KEY,
+  entity        TEXT NOT NULL CHECK (entity IN ('patient', 'appointment')),
+  entity_id     UUID NOT NULL,
+  op            TEXT NOT NULL CHECK (op IN ('insert', 'update', 'delete')),
+  tx_id         BIGINT NOT NULL DEFAULT pg_current_xact_id()::text::bigint,
+  changed_at    TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS hq_change_cursor_idx ON hq_change(tx_id, seq);
+CREATE INDEX IF NOT EXISTS hq_change_changed_at_idx ON hq_change(changed_at);
+
+-- TG_ARGV: entity, id column, and optionally a fixed op (child tables)
+CREATE OR REPLACE FUNCTION hq_log_change()
+RETURNS trigger AS $$
+DECLARE
+  rec RECORD;
+BEGIN
+  IF TG_OP = 'UPDATE' AND OLD IS NOT DISTINCT FROM NEW THEN
+    RETURN NULL;
+  END IF;
+  IF TG_OP = 'DELETE' THEN
+    rec := OLD;
+  ELSE
+    rec := NEW;
+  END IF;
+  INSERT INTO hq_change (entity, entity_id, op)
+  VALUES (TG_ARGV[0], (to_jsonb(rec) ->> TG_ARGV[1])::uuid, COALESCE(TG_ARGV[2], lower(TG_OP)));
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DO $$
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'patient_hq_change_trg') THEN
+    CREATE TRIGGER patient_hq_change_trg
+    AFTER INSERT OR UPDATE OR DELETE ON patient
+    FOR EACH ROW
+    EXECUTE FUNCTION hq_log_change('patient', 'patient_id');
+  END IF;
+
+  IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'phone_number_hq_change_trg') THEN
+    CREATE TRIGGER phone_number_hq_change_trg
+    AFTER INSERT OR UPDATE OR DELETE ON phone_number
+    FOR EACH ROW
+    EXECUTE FUNCTION hq_log_change('patient', 'patient_id', 'update');
+  END IF;
+
+  IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'appointment_hq_change_trg') THEN
+    CREATE TRIGGER appointment_hq_change_trg
+    AFTER INSERT OR UPDATE OR DELETE ON appointment
+    FOR EACH ROW
+    EXECUTE FUNCTION hq_log_change('appointment', 'appointment_id');
+  END IF;
+
+  IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'appointment_plan_item_hq_change_trg') THEN
+    CREATE TRIGGER appointment_plan_item_hq_change_trg
+    AFTER INSERT OR UPDATE OR DELETE ON appointment_plan_item
+    FOR EACH ROW
+    EXECUTE FUNCTION hq_log_change('appointment', 'appointment_id', 'update');
+  END IF;
+END $$;
+
+COMMIT;
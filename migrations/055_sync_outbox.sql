@@ -0,0 +1,54 @@
+-- migrations/055_sync_outbox.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- hq_change (054) becomes the general change outbox: besides the HQ sync
+-- gRPC feed it now backs GET /api/v1/sync/changes for the offline-capable
+-- desktop client. Same rows, same triggers (so every writer is covered,
+-- jobs and direct route SQL included); only the names change.
+-- Payloads are not stored: the endpoint attaches the entity's current
+-- state when it reads the outbox (see routes/sync_routes.rs).
+-- ------------------------------------------------------------
+
+DO $$
+BEGIN
+  IF to_regclass('public.hq_change') IS NOT NULL THEN
+    ALTER TABLE hq_change RENAME TO sync_outbox;
+    ALTER SEQUENCE hq_change_seq_seq RENAME TO sync_outbox_seq_seq;
+    ALTER INDEX hq_change_pkey RENAME TO sync_outbox_pkey;
+    ALTER INDEX hq_change_cursor_idx RENAME TO sync_outbox_cursor_idx;
+    ALTER INDEX hq_change_changed_at_idx RENAME TO sync_outbox_changed_at_idx;
+    ALTER TABLE sync_outbox RENAME CONSTRAINT hq_change_entity_check TO sync_outbox_entity_check;
+    ALTER TABLE sync_outbox RENAME CONSTRAINT hq_change_op_check TO sync_outbox_op_check;
+
+    ALTER TRIGGER patient_hq_change_trg ON patient RENAME TO patient_sync_outbox_trg;
+    ALTER TRIGGER phone_number_hq_change_trg ON phone_number RENAME TO phone_number_sync_outbox_trg;
+    ALTER TRIGGER appointment_hq_change_trg ON appointment RENAME TO appointment_sync_outbox_trg;
+    ALTER TRIGGER appointment_plan_item_hq_change_trg ON appointment_plan_item
+      RENAME TO appointment_plan_item_sync_outbox_trg;
+
+    ALTER FUNCTION hq_log_change() RENAME TO sync_outbox_log;
+  END IF;
+END $$;
+
+-- TG_ARGV: entity, id column, and optionally a fixed op (child tables)
+CREATE OR REPLACE FUNCTION sync_outbox_log()
+RETURNS trigger AS $$
+DECLARE
+  rec RECORD;
+BEGIN
+  IF TG_OP = 'UPDATE' AND OLD IS NOT DISTINCT FROM NEW THEN
+    RETURN NULL;
+  END IF;
+  IF TG_OP = 'DELETE' THEN
+    rec := OLD;
+  ELSE
+    rec := NEW;
+  END IF;
+  INSERT INTO sync_outbox (entity, entity_id, op)
+  VALUES (TG_ARGV[0], (to_jsonb(rec) ->> TG_ARGV[1])::uuid, COALESCE(TG_ARGV[2], lower(TG_OP)));
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMIT;
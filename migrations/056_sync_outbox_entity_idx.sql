@@ -0,0 +1,11 @@
+-- migrations/056_sync_outbox_entity_idx.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- POST /api/v1/sync/push compares a client's base_version with the
+-- entity's newest sync_outbox seq (sync_outbox::version), once per item.
+-- ------------------------------------------------------------
+
+CREATE INDEX IF NOT EXISTS sync_outbox_entity_idx ON sync_outbox(entity, entity_id, seq);
+
+COMMIT;
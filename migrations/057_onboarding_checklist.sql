@@ -0,0 +1,59 @@
+-- migrations/057_onboarding_checklist.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- First-visit onboarding checklist
+-- ------------------------------------------------------------
+-- Clinic-managed catalog (GET/POST /clinic/onboarding_items). Booking an
+-- appointment with is_new_patient copies the active items onto it; reception ticks
+-- them off (PUT /appointments/{id}/onboarding/{item}). Items with
+-- required_before_seating block POST /appointments/{id}/seat until done; the flag
+-- is read live, so switching it off unblocks appointments already booked.
+-- Items are deactivated rather than deleted so old checklists keep their labels.
+
+CREATE TABLE IF NOT EXISTS onboarding_item (
+  onboarding_item_id       UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  code                     TEXT NOT NULL,
+  label                    TEXT NOT NULL,
+  required_before_seating  BOOLEAN NOT NULL DEFAULT false,
+  sort_order               INT NOT NULL DEFAULT 0,
+  is_active                BOOLEAN NOT NULL DEFAULT true,
+
+  created_at               TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at               TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CONSTRAINT onboarding_item_code_unique UNIQUE (code)
+);
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_trigger WHERE tgname = 'onboarding_item_set_updated_at'
+  ) THEN
+    CREATE TRIGGER onboarding_item_set_updated_at
+      BEFORE UPDATE ON onboarding_item
+      FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+  END IF;
+END $$;
+
+-- nothing blocks seating until the clinic turns it on
+INSERT INTO onboarding_item (code, label, sort_order)
+VALUES
+  ('intake_form',    'Intake form',            10),
+  ('consent',        'Treatment consent',      20),
+  ('insurance_card', 'Insurance card scanned', 30),
+  ('xray',           'X-ray',                  40)
+ON CONFLICT (code) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS appointment_onboarding_item (
+  appointment_id        UUID NOT NULL REFERENCES appointment(appointment_id) ON DELETE CASCADE,
+  onboarding_item_id    UUID NOT NULL REFERENCES onboarding_item(onboarding_item_id),
+
+  completed_at          TIMESTAMPTZ NULL,
+  completed_by_user_id  UUID NULL REFERENCES dcms_user(user_id),
+  note                  TEXT NULL,
+
+  PRIMARY KEY (appointment_id, onboarding_item_id)
+);
+
+COMMIT;
@@ -0,0 +1,20 @@
+-- migrations/058_register_number_format.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Server-generated register numbers (see src/numbering.rs)
+-- ------------------------------------------------------------
+-- Shape (validated by the API):
+--   prefix  text  0..10 letters / digits / '-', put before the number
+--   digits  int   1..12, zero-padding of patient_register_seq (longer numbers aren't cut)
+-- The server now picks patient register numbers and employee display numbers
+-- itself, skipping values already entered by hand. The column defaults from 007
+-- and 035 stay for SQL seeds and imports; they ignore the format.
+
+ALTER TABLE clinic_settings
+  ADD COLUMN IF NOT EXISTS register_number_format JSONB NOT NULL DEFAULT '{
+    "prefix": "P",
+    "digits": 6
+  }'::jsonb;
+
+COMMIT;
@@ -0,0 +1,15 @@
+-- migrations/059_phone_number_match_key.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Phone lookup by the last 8 digits
+-- ------------------------------------------------------------
+-- GET /phone_numbers/lookup (inbound-call screen-pop, SMS sender matching) and
+-- duplicate detection on patient create match numbers on their last 8 digits, so
+-- "+976 9911 8840" finds a number stored as "99118840". The expression must stay
+-- the same as in repos::patients (PHONE_MATCH_DIGITS) for the index to be used.
+
+CREATE INDEX IF NOT EXISTS phone_number_match_key_idx
+  ON phone_number (right(regexp_replace(phone_number, '\D', '', 'g'), 8));
+
+COMMIT;
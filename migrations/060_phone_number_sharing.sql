@@ -0,0 +1,34 @@
+-- migrations/060_phone_number_sharing.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Phone numbers shared across patients (see src/phone_sharing.rs)
+-- ------------------------------------------------------------
+-- clinic_settings.phone_number_sharing, checked when a number is added or changed
+-- and another patient already has it (same last 8 digits):
+--   allow    duplicates are stored silently (the old behaviour)
+--   confirm  409 PHONE_NUMBER_IN_USE unless the request says `shared: true`
+--   deny     always 409; move the number with POST /phone_numbers/{id}/transfer
+-- Numbers shared before this migration stay as they are.
+
+ALTER TABLE clinic_settings
+  ADD COLUMN IF NOT EXISTS phone_number_sharing TEXT NOT NULL DEFAULT 'confirm';
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint WHERE conname = 'clinic_settings_phone_number_sharing_check'
+  ) THEN
+    ALTER TABLE clinic_settings
+      ADD CONSTRAINT clinic_settings_phone_number_sharing_check
+      CHECK (phone_number_sharing IN ('allow', 'confirm', 'deny'));
+  END IF;
+END $$;
+
+-- A number transferred without its SMS history stays on the old patient for that
+-- history only: released rows are hidden from the phone list, never primary, not
+-- matched by lookups and can't get new messages.
+ALTER TABLE phone_number
+  ADD COLUMN IF NOT EXISTS released_at TIMESTAMPTZ NULL;
+
+COMMIT;
@@ -0,0 +1,14 @@
+-- migrations/061_sms_soft_delete.sql
+-- DELETE /sms/{id} no longer removes communication history: it marks the message
+-- deleted (who and when, also in audit_log) and listings leave it out. Admins see
+-- deleted messages with ?include_deleted=true and can bring them back with
+-- POST /sms/{id}/restore. Phone numbers with deleted SMS still count as having
+-- history (DELETE /phone_numbers/{id} stays blocked).
+
+BEGIN;
+
+ALTER TABLE sms
+  ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ NULL,
+  ADD COLUMN IF NOT EXISTS deleted_by_user_id UUID NULL REFERENCES "dcms_user"(user_id) ON DELETE SET NULL;
+
+COMMIT;
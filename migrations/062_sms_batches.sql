@@ -0,0 +1,49 @@
+-- migrations/062_sms_batches.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Bulk SMS as background batches (see src/jobs/sms_batches.rs)
+-- ------------------------------------------------------------
+-- POST /sms/bulk_send validates the recipients, stores a batch and returns its
+-- batch_id; the worker writes the sms rows in chunks (one multi-row INSERT each)
+-- and GET /sms/batches/{id} reports progress. Consent and released numbers are
+-- checked again at send time: such recipients end up `failed` with an `error`.
+
+CREATE TABLE IF NOT EXISTS sms_batch (
+  batch_id            UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  sms_text            TEXT NOT NULL,                 -- encrypted like sms.sms_text (pii.rs)
+  purpose             TEXT NOT NULL,                 -- consent purpose: marketing | transactional
+  status              TEXT NOT NULL DEFAULT 'queued',
+  requested           INT NOT NULL,                  -- ids in the request, invalid / opted out included
+  created_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+
+  created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+  started_at          TIMESTAMPTZ NULL,
+  finished_at         TIMESTAMPTZ NULL,
+
+  CONSTRAINT sms_batch_status_check CHECK (status IN ('queued', 'sending', 'done')),
+  CONSTRAINT sms_batch_purpose_check CHECK (purpose IN ('marketing', 'transactional'))
+);
+
+CREATE INDEX IF NOT EXISTS sms_batch_open_idx
+  ON sms_batch(created_at)
+  WHERE status <> 'done';
+
+CREATE TABLE IF NOT EXISTS sms_batch_recipient (
+  batch_id         UUID NOT NULL REFERENCES sms_batch(batch_id) ON DELETE CASCADE,
+  phone_number_id  UUID NOT NULL REFERENCES phone_number(phone_number_id) ON DELETE CASCADE,
+
+  status           TEXT NOT NULL DEFAULT 'pending',
+  error            TEXT NULL,                        -- failed only: opted_out | number_released
+  sms_id           UUID NULL REFERENCES sms(sms_id) ON DELETE SET NULL,
+  processed_at     TIMESTAMPTZ NULL,
+
+  PRIMARY KEY (batch_id, phone_number_id),
+  CONSTRAINT sms_batch_recipient_status_check CHECK (status IN ('pending', 'sent', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS sms_batch_recipient_pending_idx
+  ON sms_batch_recipient(batch_id)
+  WHERE status = 'pending';
+
+COMMIT;
@@ -0,0 +1,45 @@
+-- migrations/063_patient_contact_preferences.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Per-patient communication preferences (see src/contact_prefs.rs)
+-- ------------------------------------------------------------
+-- preferred_channel   'sms' | 'call' | 'email'; NULL = no preference (for staff)
+-- preferred_language  'en' | 'mn'; picks the reminder template; NULL = clinic default
+-- quiet_hours         [{"start":"HH:MM","end":"HH:MM"}, ...] in clinic time, a window
+--                     may wrap midnight; automatic and bulk SMS wait until it ends
+--                     (validated by the API, evaluated in SQL by the jobs)
+
+ALTER TABLE patient
+  ADD COLUMN IF NOT EXISTS preferred_channel TEXT NULL,
+  ADD COLUMN IF NOT EXISTS preferred_language TEXT NULL,
+  ADD COLUMN IF NOT EXISTS quiet_hours JSONB NOT NULL DEFAULT '[]'::jsonb;
+
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint WHERE conname = 'patient_preferred_channel_chk'
+  ) THEN
+    ALTER TABLE patient
+      ADD CONSTRAINT patient_preferred_channel_chk
+      CHECK (preferred_channel IN ('sms', 'call', 'email'));
+  END IF;
+
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint WHERE conname = 'patient_preferred_language_chk'
+  ) THEN
+    ALTER TABLE patient
+      ADD CONSTRAINT patient_preferred_language_chk
+      CHECK (preferred_language IN ('en', 'mn'));
+  END IF;
+
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint WHERE conname = 'patient_quiet_hours_chk'
+  ) THEN
+    ALTER TABLE patient
+      ADD CONSTRAINT patient_quiet_hours_chk
+      CHECK (jsonb_typeof(quiet_hours) = 'array');
+  END IF;
+END $$;
+
+COMMIT;
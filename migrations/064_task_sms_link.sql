@@ -0,0 +1,16 @@
+-- migrations/064_task_sms_link.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Tasks about a specific SMS ("call the patient back about this message")
+-- ------------------------------------------------------------
+-- Next to patient_id / appointment_id; the task's patient is the SMS's patient.
+-- Invoice and lab case links follow once those tables exist.
+
+ALTER TABLE task
+  ADD COLUMN IF NOT EXISTS sms_id UUID NULL REFERENCES sms(sms_id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS task_sms_idx
+  ON task(sms_id) WHERE sms_id IS NOT NULL;
+
+COMMIT;
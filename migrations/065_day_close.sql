@@ -0,0 +1,19 @@
+-- migrations/065_day_close.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- End-of-day close (GET /reports/day_close, POST /reports/day_close/sign_off)
+-- ------------------------------------------------------------
+-- One row per clinic-local day the manager signed off. `snapshot` is the report
+-- as it was signed (src/day_close.rs DaySummary), so the printout stays the same
+-- when appointments or tasks of that day change later.
+
+CREATE TABLE IF NOT EXISTS day_close (
+  close_date             DATE PRIMARY KEY,
+  snapshot               JSONB NOT NULL,
+  note                   TEXT NULL,
+  signed_off_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+  signed_off_at          TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+COMMIT;
@@ -0,0 +1,37 @@
+-- migrations/066_data_export.sql
+BEGIN;
+
+-- ------------------------------------------------------------
+-- Nightly CSV exports to S3 / SFTP (see src/data_export.rs)
+-- ------------------------------------------------------------
+-- One row per run, scheduled (jobs/data_export.rs) or started by an admin.
+-- target is the destination without credentials (those stay in EXPORT_* env);
+-- files lists what was uploaded: [{"dataset","key","rows","bytes"}].
+-- The partial unique index lets one instance claim a day's scheduled run; failed
+-- runs don't count, so a later tick may try again.
+
+CREATE TABLE IF NOT EXISTS data_export_run (
+  run_id                UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  export_date           DATE NOT NULL,                 -- clinic-local day, names the folder
+  trigger               TEXT NOT NULL,
+  status                TEXT NOT NULL DEFAULT 'running',
+  target                TEXT NOT NULL,
+  files                 JSONB NOT NULL DEFAULT '[]'::jsonb,
+  error                 TEXT NULL,
+  requested_by_user_id  UUID NULL REFERENCES dcms_user(user_id) ON DELETE SET NULL,
+
+  started_at            TIMESTAMPTZ NOT NULL DEFAULT now(),
+  finished_at           TIMESTAMPTZ NULL,
+
+  CONSTRAINT data_export_run_trigger_check CHECK (trigger IN ('schedule', 'manual')),
+  CONSTRAINT data_export_run_status_check CHECK (status IN ('running', 'succeeded', 'failed'))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS data_export_run_scheduled_uq
+  ON data_export_run(export_date)
+  WHERE trigger = 'schedule' AND status IN ('running', 'succeeded');
+
+CREATE INDEX IF NOT EXISTS data_export_run_started_idx
+  ON data_export_run(started_at DESC);
+
+COMMIT;
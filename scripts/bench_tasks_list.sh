@@ -0,0 +1,74 @@
+#!/usr/bin/env bash
+# Latency benchmark for the task list endpoints.
+# Seeds N tasks created by ADMIN_USER's employee profile, times
+# GET /tasks/created?limit=N, writes a summary to bench_output.txt
+# and removes the seeded tasks again.
+set -euo pipefail
+
+BASE_URL="${BASE_URL:-http://127.0.0.1:8080/api/v1}"
+DATABASE_URL="${DATABASE_URL:-}"
+
+ADMIN_USER="${ADMIN_USER:-admin}"
+ADMIN_PASS="${ADMIN_PASS:-admin123}"
+
+TASKS="${TASKS:-200}"
+RUNS="${RUNS:-20}"
+OUT="${OUT:-bench_output.txt}"
+
+need() { command -v "$1" >/dev/null 2>&1 || { echo "ERROR: missing dependency: $1"; exit 1; }; }
+need curl
+need jq
+need psql
+
+if [[ -z "${DATABASE_URL}" ]]; then
+  echo "ERROR: DATABASE_URL is required"
+  exit 1
+fi
+
+TOKEN=$(
+  curl -sS -X POST "${BASE_URL}/auth/login" \
+    -H "Content-Type: application/json" \
+    -d "{\"username\":\"${ADMIN_USER}\",\"password\":\"${ADMIN_PASS}\",\"device_name\":\"bench\",\"remember_me\":false}" \
+  | jq -r '.data.access_token'
+)
+if [[ -z "${TOKEN}" || "${TOKEN}" == "null" ]]; then
+  echo "ERROR: login failed"
+  exit 1
+fi
+
+cleanup() {
+  psql "${DATABASE_URL}" -qAt -c "DELETE FROM task WHERE task_type = 'BENCH';" >/dev/null
+}
+trap cleanup EXIT
+
+echo "[bench] seeding ${TASKS} tasks..."
+psql "${DATABASE_URL}" -qAt -v ON_ERROR_STOP=1 >/dev/null <<SQL
+INSERT INTO task (created_by_employee_id, task_type, title, priority, due_at)
+SELECT e.employee_id, 'BENCH', 'bench task ' || g, g % 3, now() + (g || ' minutes')::interval
+FROM employee e
+JOIN dcms_user u ON u.user_id = e.user_id
+CROSS JOIN generate_series(1, ${TASKS}) g
+WHERE u.username = '${ADMIN_USER}';
+SQL
+
+URL="${BASE_URL}/tasks/created?limit=${TASKS}"
+
+# warm-up
+curl -sS -o /dev/null -H "Authorization: Bearer ${TOKEN}" "${URL}"
+
+echo "[bench] ${RUNS} runs of GET /tasks/created?limit=${TASKS}..."
+TIMES=()
+for _ in $(seq 1 "${RUNS}"); do
+  TIMES+=("$(curl -sS -o /dev/null -w '%{time_total}' -H "Authorization: Bearer ${TOKEN}" "${URL}")")
+done
+
+printf '%s\n' "${TIMES[@]}" | sort -n | awk -v tasks="${TASKS}" -v runs="${RUNS}" '
+  { t[NR] = $1 * 1000; sum += $1 * 1000 }
+  END {
+    printf "GET /tasks/created?limit=%d  runs=%d\n", tasks, runs
+    printf "  min  %.1f ms\n", t[1]
+    printf "  p50  %.1f ms\n", t[int((NR + 1) / 2)]
+    printf "  p95  %.1f ms\n", t[int(NR * 0.95 + 0.5)]
+    printf "  max  %.1f ms\n", t[NR]
+    printf "  mean %.1f ms\n", sum / NR
+  }' | tee "${OUT}"
@@ -8,6 +8,7 @@
 # - patch (display_name / roles / is_active)
 # - disable/enable endpoints
 # - negative tests (forbidden for non-admin/manager, invalid role, duplicate username)
+# - only admins disable or re-enable an admin account
 # - "remove" (NOTE: no delete endpoint implemented; we simulate removal by disabling)
 #
 # Requirements: curl, jq
@@ -235,11 +236,11 @@ fi
 
 section "GET each created user and validate fields"
 for pair in \
-  "$ID_PATIENT:$U_PATIENT:0" \
-  "$ID_DOCTOR:$U_DOCTOR:3" \
-  "$ID_RECEPT:$U_RECEPT:4" \
-  "$ID_MANAGER:$U_MANAGER:2" \
-  "$ID_ADMIN2:$U_ADMIN2:1"
+  "$ID_PATIENT:$U_PATIENT:patient" \
+  "$ID_DOCTOR:$U_DOCTOR:doctor" \
+  "$ID_RECEPT:$U_RECEPT:receptionist" \
+  "$ID_MANAGER:$U_MANAGER:manager" \
+  "$ID_ADMIN2:$U_ADMIN2:admin"
 do
   IFS=":" read -r uid uname role <<<"$pair"
   resp="$(get_user "$ADMIN_TOKEN" "$uid")"
@@ -260,7 +261,7 @@ echo "[ok] display_name updated"
 section "PATCH: change roles (reception -> manager)"
 resp="$(patch_user "$ADMIN_TOKEN" "$ID_RECEPT" '{"roles":2}')"
 echo "$resp" | jq .
-assert_eq "2" "$(echo "$resp" | jq -r '.data.roles')" "roles not updated"
+assert_eq "manager" "$(echo "$resp" | jq -r '.data.roles')" "roles not updated"
 echo "[ok] roles updated"
 
 section "PATCH: set is_active=false via PATCH"
@@ -328,6 +329,25 @@ fi
 echo "$body" | jq '.data.users[0:3]' >/dev/null
 echo "[ok] manager can list users"
 
+section "RBAC: disabling an admin is admin-only"
+# deactivating an admin locks them out, so it counts as revoking admin
+read -r code body < <(curl_with_status POST "$BASE_URL/users/$ID_ADMIN2/disable" "$MANAGER_TOKEN")
+echo "[manager disables admin] http=$code"
+assert_eq "403" "$code" "manager must not disable an admin"
+read -r code body < <(curl_with_status PATCH "$BASE_URL/users/$ID_ADMIN2" "$MANAGER_TOKEN" '{"is_active":false}')
+echo "[manager PATCH admin is_active=false] http=$code"
+assert_eq "403" "$code" "manager must not deactivate an admin via PATCH"
+assert_eq "true" "$(get_user "$ADMIN_TOKEN" "$ID_ADMIN2" | jq -r '.data.is_active')" "admin was disabled by a manager"
+
+resp="$(disable_user "$ADMIN_TOKEN" "$ID_ADMIN2")"
+assert_eq "true" "$(echo "$resp" | jq -r '.data.ok')" "admin could not disable another admin"
+read -r code body < <(curl_with_status POST "$BASE_URL/users/$ID_ADMIN2/enable" "$MANAGER_TOKEN")
+echo "[manager re-enables admin] http=$code"
+assert_eq "403" "$code" "manager must not re-enable an admin"
+resp="$(enable_user "$ADMIN_TOKEN" "$ID_ADMIN2")"
+assert_eq "true" "$(echo "$resp" | jq -r '.data.ok')" "admin could not re-enable another admin"
+echo "[ok] only admins disable or enable admins"
+
 section '"Remove" users (simulate removal by disabling)'
 # Since there is no DELETE /users endpoint, "remove" == disable in this build.
 for uid in "$ID_PATIENT" "$ID_DOCTOR" "$ID_RECEPT" "$ID_MANAGER" "$ID_ADMIN2" "$ID_MISC1" "$ID_MISC2"; do
@@ -0,0 +1,62 @@
+// src/audit.rs
+//
+// audit_log writer. Call it inside the same transaction as the change when there
+// is one, so the entry and the action commit (or roll back) together.
+
+use serde_json::Value as JsonValue;
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::middleware::auth_context::AuthContext;
+
+pub async fn record<'e, E: PgExecutor<'e>>(
+    db: E,
+    auth: &AuthContext,
+    action: &str,
+    target_type: &str,
+    target_id: Option<Uuid>,
+    details: JsonValue,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (actor_user_id, session_token_id, action, target_type, target_id, details)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(auth.impersonator_user_id.unwrap_or(auth.user_id))
+    .bind(auth.session_token_id)
+    .bind(action)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(details)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Entry made by a reception kiosk: no user or session, the device goes in `details.kiosk_id`.
+pub async fn record_kiosk<'e, E: PgExecutor<'e>>(
+    db: E,
+    kiosk_id: Uuid,
+    action: &str,
+    target_type: &str,
+    target_id: Option<Uuid>,
+    mut details: JsonValue,
+) -> Result<(), sqlx::Error> {
+    if let Some(obj) = details.as_object_mut() {
+        obj.insert("kiosk_id".into(), JsonValue::String(kiosk_id.to_string()));
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (actor_user_id, session_token_id, action, target_type, target_id, details)
+        VALUES (NULL, NULL, $1, $2, $3, $4)
+        "#,
+    )
+    .bind(action)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(details)
+    .execute(db)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+// src/backup.rs
+//
+// Logical backups with the Postgres client tools (must be on PATH and at least
+// the server's major version):
+// - POST /admin/backup streams `pg_dump --format=custom` as a download
+// - `cargo run --bin restore -- <file> --yes` loads it back (src/bin/restore.rs)
+// Custom format because pg_restore can then drop/recreate objects itself and
+// restore everything in one transaction.
+
+use chrono::{DateTime, Utc};
+use std::process::Stdio;
+use tokio::process::Command;
+
+pub const PG_DUMP_BIN: &str = "pg_dump";
+
+/// Ownership/grants belong to whoever restores, not to the source server.
+pub const COMMON_ARGS: [&str; 2] = ["--no-owner", "--no-privileges"];
+
+/// `dcms-20260116-020000.dump`
+pub fn dump_filename(at: DateTime<Utc>) -> String {
+    format!("dcms-{}.dump", at.format("%Y%m%d-%H%M%S"))
+}
+
+fn dump_args(database_url: &str) -> Vec<String> {
+    let mut args = vec!["--format=custom".to_string()];
+    args.extend(COMMON_ARGS.iter().map(|a| a.to_string()));
+    args.push(format!("--dbname={database_url}"));
+    args
+}
+
+/// pg_dump writing the archive to stdout; stderr is piped for error messages.
+pub fn dump_command(database_url: &str) -> Command {
+    let mut cmd = Command::new(PG_DUMP_BIN);
+    cmd.args(dump_args(database_url))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn filename_and_args() {
+        let at = Utc.with_ymd_and_hms(2026, 1, 16, 2, 0, 0).unwrap();
+        assert_eq!(dump_filename(at), "dcms-20260116-020000.dump");
+
+        let dump = dump_args("postgres://u@h/db");
+        assert_eq!(dump.first().map(String::as_str), Some("--format=custom"));
+        assert_eq!(dump.last().map(String::as_str), Some("--dbname=postgres://u@h/db"));
+    }
+}
@@ -0,0 +1,113 @@
+// Re-encrypts PII columns (patient.email, sms.sms_text) under PII_ENCRYPTION_KEY.
+//
+//   PII_ENCRYPTION_KEY=<new> PII_ENCRYPTION_KEY_OLD=<old> rekey_pii [--dry-run]
+//
+// - plaintext values are encrypted (first rollout: leave PII_ENCRYPTION_KEY_OLD unset)
+// - values already readable with the new key are skipped, so the run is resumable
+// - everything else is decrypted with the old key and re-encrypted
+// Run it while the server is stopped or already running with the new key.
+
+#[allow(dead_code)]
+#[path = "../pii.rs"]
+mod pii;
+
+use pii::PiiCipher;
+use sqlx::{PgPool, Row, postgres::PgPoolOptions};
+use uuid::Uuid;
+
+const BATCH_SIZE: i64 = 500;
+
+struct Target {
+    table: &'static str,
+    id_col: &'static str,
+    col: &'static str,
+}
+
+const TARGETS: &[Target] = &[
+    Target { table: "patient", id_col: "patient_id", col: "email" },
+    Target { table: "sms", id_col: "sms_id", col: "sms_text" },
+];
+
+fn rekey_value(stored: &str, new: &PiiCipher, old: Option<&PiiCipher>) -> anyhow::Result<Option<String>> {
+    if !pii::is_encrypted(stored) {
+        return Ok(Some(new.encrypt(stored)));
+    }
+    if new.decrypt(stored).is_ok() {
+        return Ok(None);
+    }
+    let old = old.ok_or_else(|| anyhow::anyhow!("value not readable with the new key and PII_ENCRYPTION_KEY_OLD is not set"))?;
+    let plain = old.decrypt(stored).map_err(anyhow::Error::msg)?;
+    Ok(Some(new.encrypt(&plain)))
+}
+
+async fn rekey_column(
+    db: &PgPool,
+    t: &Target,
+    new: &PiiCipher,
+    old: Option<&PiiCipher>,
+    dry_run: bool,
+) -> anyhow::Result<(u64, u64)> {
+    let select = format!(
+        "SELECT {id}, {col} FROM {table} WHERE {col} IS NOT NULL AND {id} > $1 ORDER BY {id} LIMIT $2",
+        id = t.id_col,
+        col = t.col,
+        table = t.table,
+    );
+    let update = format!("UPDATE {} SET {} = $2 WHERE {} = $1", t.table, t.col, t.id_col);
+
+    let (mut scanned, mut changed) = (0u64, 0u64);
+    let mut after = Uuid::nil();
+    loop {
+        let rows = sqlx::query(&select).bind(after).bind(BATCH_SIZE).fetch_all(db).await?;
+        if rows.is_empty() {
+            break;
+        }
+
+        let mut tx = db.begin().await?;
+        for r in &rows {
+            let id: Uuid = r.try_get(0)?;
+            let stored: String = r.try_get(1)?;
+            after = id;
+            scanned += 1;
+
+            let next = rekey_value(&stored, new, old)
+                .map_err(|e| anyhow::anyhow!("{}.{} {id}: {e}", t.table, t.col))?;
+            if let Some(next) = next {
+                changed += 1;
+                if !dry_run {
+                    sqlx::query(&update).bind(id).bind(next).execute(&mut *tx).await?;
+                }
+            }
+        }
+        tx.commit().await?;
+    }
+
+    Ok((scanned, changed))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+
+    let dry_run = std::env::args().skip(1).any(|a| a == "--dry-run");
+
+    let new_key = std::env::var("PII_ENCRYPTION_KEY").map_err(|_| anyhow::anyhow!("PII_ENCRYPTION_KEY is required"))?;
+    let new = PiiCipher::from_base64_key(&new_key).map_err(anyhow::Error::msg)?;
+    let old = match std::env::var("PII_ENCRYPTION_KEY_OLD").ok().filter(|s| !s.trim().is_empty()) {
+        Some(k) => Some(PiiCipher::from_base64_key(&k).map_err(anyhow::Error::msg)?),
+        None => None,
+    };
+
+    let db = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&std::env::var("DATABASE_URL")?)
+        .await?;
+
+    for t in TARGETS {
+        let (scanned, changed) = rekey_column(&db, t, &new, old.as_ref(), dry_run).await?;
+        let verb = if dry_run { "would re-encrypt" } else { "re-encrypted" };
+        println!("{}.{}: scanned {scanned}, {verb} {changed}", t.table, t.col);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+// Restores a dump from POST /admin/backup into DATABASE_URL.
+//
+//   restore <file.dump> --yes
+//
+// - pg_restore --clean: objects in the dump are dropped and recreated, so the
+//   database ends up exactly as it was when the backup was taken
+// - single transaction: a failing restore leaves the database untouched
+// Stop the server first; sessions in the dump are valid again afterwards.
+
+#[allow(dead_code)]
+#[path = "../backup.rs"]
+mod backup;
+
+use std::process::Stdio;
+
+use tokio::process::Command;
+
+const PG_RESTORE_BIN: &str = "pg_restore";
+
+fn restore_command(database_url: &str, file: &str) -> Command {
+    let mut cmd = Command::new(PG_RESTORE_BIN);
+    cmd.args(["--clean", "--if-exists", "--single-transaction", "--exit-on-error"])
+        .args(backup::COMMON_ARGS)
+        .arg(format!("--dbname={database_url}"))
+        .arg(file)
+        .stdin(Stdio::null());
+    cmd
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let confirmed = args.iter().any(|a| a == "--yes");
+    let Some(file) = args.iter().find(|a| !a.starts_with("--")) else {
+        anyhow::bail!("usage: restore <file.dump> --yes");
+    };
+    if !std::path::Path::new(file).is_file() {
+        anyhow::bail!("{file}: no such file");
+    }
+
+    let database_url = std::env::var("DATABASE_URL").map_err(|_| anyhow::anyhow!("DATABASE_URL is required"))?;
+    if !confirmed {
+        anyhow::bail!("this replaces the contents of DATABASE_URL with {file}; re-run with --yes");
+    }
+
+    let status = restore_command(&database_url, file).status().await?;
+    if !status.success() {
+        anyhow::bail!("{} {status}; nothing was changed", PG_RESTORE_BIN);
+    }
+    println!("restored {file}");
+    Ok(())
+}
@@ -0,0 +1,74 @@
+// src/clinic_time.rs
+//
+// Clinic-local time. clinic_settings.timezone is an IANA name (validated with
+// chrono-tz on write); "today", day/week views and reports cut days at local
+// midnight, not UTC midnight.
+
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+
+pub fn parse_tz(name: &str) -> Option<Tz> {
+    name.trim().parse::<Tz>().ok()
+}
+
+/// The clinic's timezone; falls back to UTC when unset or not a valid IANA name.
+pub async fn clinic_tz(db: &PgPool) -> Result<Tz, sqlx::Error> {
+    let name: Option<String> =
+        sqlx::query_scalar("SELECT timezone FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(db)
+            .await?;
+
+    Ok(match name {
+        Some(name) => parse_tz(&name).unwrap_or_else(|| {
+            tracing::warn!("clinic_settings.timezone '{name}' is not a valid IANA timezone, using UTC");
+            Tz::UTC
+        }),
+        None => Tz::UTC,
+    })
+}
+
+/// Local midnight of `date` as a UTC instant. If midnight falls into a DST gap,
+/// the first valid local time after it is used.
+pub fn local_day_start(date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    let mut t = date.and_time(NaiveTime::MIN);
+    loop {
+        if let Some(dt) = tz.from_local_datetime(&t).earliest() {
+            return dt.with_timezone(&Utc);
+        }
+        t += chrono::Duration::minutes(30);
+    }
+}
+
+/// [start, end) of `days` local days starting at `date`.
+pub fn local_days_range(date: NaiveDate, days: u64, tz: Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+    let end_date = date + chrono::Days::new(days);
+    (local_day_start(date, tz), local_day_start(end_date, tz))
+}
+
+pub fn local_today(tz: Tz) -> NaiveDate {
+    Utc::now().with_timezone(&tz).date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ulaanbaatar_midnight_is_previous_day_16_utc() {
+        let tz = parse_tz("Asia/Ulaanbaatar").unwrap();
+        let d = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        let (start, end) = local_days_range(d, 1, tz);
+        assert_eq!(start.to_rfc3339(), "2026-03-01T16:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2026-03-02T16:00:00+00:00");
+        assert!(parse_tz("Mars/Olympus").is_none());
+    }
+
+    #[test]
+    fn dst_days_are_not_24h() {
+        let tz = parse_tz("Europe/Berlin").unwrap();
+        let d = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap();
+        let (start, end) = local_days_range(d, 1, tz);
+        assert_eq!((end - start).num_hours(), 23);
+    }
+}
@@ -3,23 +3,123 @@ use std::env;
 #[derive(Clone, Debug)]
 pub struct Config {
     pub database_url: String,
+    /// optional read replica for read-heavy endpoints
+    pub database_read_url: Option<String>,
+    pub db_pool: crate::db::PoolSettings,
     pub bind_addr: String,
     pub session_ttl_hours: i64,
+    pub session_sliding: bool,
+    pub session_max_lifetime_hours: i64,
+    pub impersonation_ttl_minutes: i64,
+    pub session_retention_days: i64,
+    pub sms_segment_price_cents: Option<i64>,
+    pub pii_encryption_key: Option<String>,
+    pub intake_form_url: Option<String>,
+    pub feedback_form_url: Option<String>,
+    /// OTLP/gRPC collector for trace export (e.g. http://localhost:4317); None = logs only
+    pub otlp_endpoint: Option<String>,
+    pub otel_service_name: String,
+    /// listen address of the HQ sync gRPC server (feature hq-grpc); None = not started
+    pub hq_grpc_addr: Option<String>,
+    /// nightly CSV export target (EXPORT_* variables); None = no exports
+    pub data_export: Option<crate::data_export::ExportSettings>,
 }
 
 impl Config {
     pub fn from_env() -> anyhow::Result<Self> {
         let database_url = env::var("DATABASE_URL")?;
+        // Postgres only (exclusion constraints, advisory locks, materialized views);
+        // see doc/impr.md "SQLite / offline demo mode".
+        if !database_url.starts_with("postgres://") && !database_url.starts_with("postgresql://") {
+            anyhow::bail!("DATABASE_URL must be a postgres:// URL (other backends are not supported)");
+        }
+        let database_read_url = env::var("DATABASE_READ_URL").ok().filter(|s| !s.trim().is_empty());
+        let defaults = crate::db::PoolSettings::default();
+        let db_pool = crate::db::PoolSettings {
+            max_connections: env::var("DB_MAX_CONNECTIONS")
+                .ok()
+                .and_then(|s| s.parse::<u32>().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(defaults.max_connections),
+            acquire_timeout_secs: env::var("DB_ACQUIRE_TIMEOUT_SECS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(defaults.acquire_timeout_secs),
+            idle_timeout_secs: env::var("DB_IDLE_TIMEOUT_SECS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(defaults.idle_timeout_secs),
+            statement_cache_size: env::var("DB_STATEMENT_CACHE_SIZE")
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(defaults.statement_cache_size),
+            statement_timeout_secs: env::var("DB_STATEMENT_TIMEOUT_SECS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(defaults.statement_timeout_secs),
+            slow_query_ms: env::var("DB_SLOW_QUERY_MS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(defaults.slow_query_ms),
+        };
         let bind_addr = env::var("BIND_ADDR").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
         let session_ttl_hours = env::var("SESSION_TTL_HOURS")
             .ok()
             .and_then(|s| s.parse::<i64>().ok())
             .unwrap_or(24);
+        let session_sliding = env::var("SESSION_SLIDING")
+            .map(|s| matches!(s.trim(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        let session_max_lifetime_hours = env::var("SESSION_MAX_LIFETIME_HOURS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(24 * 30);
+        let impersonation_ttl_minutes = env::var("IMPERSONATION_TTL_MINUTES")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .filter(|m| *m > 0)
+            .unwrap_or(120);
+        let session_retention_days = env::var("SESSION_RETENTION_DAYS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .filter(|d| *d >= 0)
+            .unwrap_or(30);
+        let sms_segment_price_cents = env::var("SMS_SEGMENT_PRICE_CENTS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .filter(|p| *p >= 0);
+        let pii_encryption_key = env::var("PII_ENCRYPTION_KEY").ok().filter(|s| !s.trim().is_empty());
+        let intake_form_url = env::var("INTAKE_FORM_URL").ok().filter(|s| !s.trim().is_empty());
+        let feedback_form_url = env::var("FEEDBACK_FORM_URL").ok().filter(|s| !s.trim().is_empty());
+        let otlp_endpoint = env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|s| !s.trim().is_empty());
+        let otel_service_name = env::var("OTEL_SERVICE_NAME")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| "dcms-backend".to_string());
+        let hq_grpc_addr = env::var("HQ_GRPC_ADDR").ok().filter(|s| !s.trim().is_empty());
+        let data_export =
+            crate::data_export::ExportSettings::from_env(|name| env::var(name).ok()).map_err(anyhow::Error::msg)?;
 
         Ok(Self {
             database_url,
+            database_read_url,
+            db_pool,
             bind_addr,
             session_ttl_hours,
+            session_sliding,
+            session_max_lifetime_hours,
+            impersonation_ttl_minutes,
+            session_retention_days,
+            sms_segment_price_cents,
+            pii_encryption_key,
+            intake_form_url,
+            feedback_form_url,
+            otlp_endpoint,
+            otel_service_name,
+            hq_grpc_addr,
+            data_export,
         })
     }
 }
@@ -0,0 +1,86 @@
+// src/consent.rs
+//
+// Do-not-contact flags (migration 039). A message may go to a phone number only
+// if neither the number nor its patient opted out for the message's purpose.
+// Checked by bulk_send and the appointment reminder job; inbound "STOP" messages
+// (add_sms with direction=0) opt the number out of everything; other replies may
+// confirm or reschedule an appointment (crate::sms_replies).
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactPurpose {
+    /// promotions, recall blasts; the default for bulk_send
+    #[default]
+    Marketing,
+    /// appointment reminders and other service messages
+    Transactional,
+}
+
+impl ContactPurpose {
+    /// as stored (sms_batch.purpose) and sent in requests
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContactPurpose::Marketing => "marketing",
+            ContactPurpose::Transactional => "transactional",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "marketing" => Some(ContactPurpose::Marketing),
+            "transactional" => Some(ContactPurpose::Transactional),
+            _ => None,
+        }
+    }
+
+    /// opt-out column on both `phone_number` and `patient`
+    pub fn opt_out_column(self) -> &'static str {
+        match self {
+            ContactPurpose::Marketing => "marketing_opt_out_at",
+            ContactPurpose::Transactional => "transactional_opt_out_at",
+        }
+    }
+}
+
+/// SQL condition: phone `ph` (joined to patient `p`) may be texted for `purpose`.
+pub fn allowed_sql(purpose: ContactPurpose) -> String {
+    let col = purpose.opt_out_column();
+    format!("(ph.{col} IS NULL AND p.{col} IS NULL)")
+}
+
+const STOP_KEYWORDS: &[&str] = &["STOP", "STOPALL", "UNSUBSCRIBE", "CANCEL", "END", "QUIT", "ЗОГС", "ЗОГСОО"];
+
+/// Inbound text is an opt-out request: a stop keyword as the first word
+/// ("Stop", "STOP please", "зогс."), case-insensitive.
+pub fn is_stop_request(text: &str) -> bool {
+    text.split_whitespace()
+        .next()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_uppercase())
+        .is_some_and(|w| STOP_KEYWORDS.contains(&w.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_keywords() {
+        assert!(is_stop_request("STOP"));
+        assert!(is_stop_request("  stop please"));
+        assert!(is_stop_request("Зогс."));
+        assert!(!is_stop_request("Don't stop the reminders"));
+        assert!(!is_stop_request("stopping by at 10"));
+        assert!(!is_stop_request(""));
+    }
+
+    #[test]
+    fn purpose_names_match_serde() {
+        for p in [ContactPurpose::Marketing, ContactPurpose::Transactional] {
+            assert_eq!(serde_json::to_value(p).unwrap(), p.as_str());
+            assert_eq!(ContactPurpose::parse(p.as_str()), Some(p));
+        }
+        assert_eq!(ContactPurpose::parse("promo"), None);
+    }
+}
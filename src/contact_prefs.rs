@@ -0,0 +1,295 @@
+// src/contact_prefs.rs
+//
+// How a patient wants to be contacted (migration 063), edited through
+// GET/PUT /patients/{id}/contact_preferences:
+// - preferred_channel: sms | call | email, for staff deciding whether to call or
+//   text; automatic messages are SMS either way and follow the opt-outs (consent.rs)
+// - preferred_language: en | mn, picks reminder_policy.templates[lang] (else the
+//   plain `template`) and the language of the feedback text
+// - quiet_hours: clinic-time windows, possibly wrapping midnight, during which the
+//   reminder, feedback and bulk SMS jobs hold the patient's messages back; they go
+//   out on a later tick once the window is over
+
+use chrono::{NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    audit, clinic_time,
+    error::ApiError,
+    i18n::{self, Lang},
+    middleware::auth_context::AuthContext,
+};
+
+pub const MAX_QUIET_WINDOWS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactChannel {
+    Sms,
+    Call,
+    Email,
+}
+
+impl ContactChannel {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim() {
+            "sms" => Ok(Self::Sms),
+            "call" => Ok(Self::Call),
+            "email" => Ok(Self::Email),
+            _ => Err("preferred_channel must be sms | call | email".into()),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sms => "sms",
+            Self::Call => "call",
+            Self::Email => "email",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuietWindow {
+    pub start: String, // "HH:MM"
+    pub end: String,   // "HH:MM"; before `start` = ends the next morning
+}
+
+fn parse_hhmm(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+}
+
+/// Same rule as `outside_quiet_hours_sql`.
+fn window_contains(start: NaiveTime, end: NaiveTime, local: NaiveTime) -> bool {
+    if start < end {
+        local >= start && local < end
+    } else {
+        local >= start || local < end
+    }
+}
+
+/// Validates the windows and rewrites them as zero-padded HH:MM.
+pub fn normalize_quiet_hours(windows: Vec<QuietWindow>) -> Result<Vec<QuietWindow>, String> {
+    if windows.len() > MAX_QUIET_WINDOWS {
+        return Err(format!("quiet_hours takes at most {MAX_QUIET_WINDOWS} windows"));
+    }
+    windows
+        .into_iter()
+        .map(|w| {
+            let (Some(start), Some(end)) = (parse_hhmm(&w.start), parse_hhmm(&w.end)) else {
+                return Err("quiet_hours start/end must be HH:MM".to_string());
+            };
+            if start == end {
+                return Err("quiet_hours start and end must differ".to_string());
+            }
+            Ok(QuietWindow { start: start.format("%H:%M").to_string(), end: end.format("%H:%M").to_string() })
+        })
+        .collect()
+}
+
+/// `local` is the clinic-local time of day.
+pub fn in_quiet_hours(windows: &[QuietWindow], local: NaiveTime) -> bool {
+    windows.iter().any(|w| match (parse_hhmm(&w.start), parse_hhmm(&w.end)) {
+        (Some(start), Some(end)) => window_contains(start, end, local),
+        _ => false,
+    })
+}
+
+/// SQL condition: patient `p` has no quiet-hours window around `local_time`, a
+/// `time` bind parameter such as "$4" holding the clinic-local time of day.
+pub fn outside_quiet_hours_sql(local_time: &str) -> String {
+    format!(
+        r#"NOT EXISTS (
+          SELECT 1 FROM jsonb_array_elements(p.quiet_hours) q
+          WHERE CASE
+            WHEN (q->>'start')::time < (q->>'end')::time
+              THEN {local_time}::time >= (q->>'start')::time AND {local_time}::time < (q->>'end')::time
+            ELSE {local_time}::time >= (q->>'start')::time OR {local_time}::time < (q->>'end')::time
+          END
+        )"#
+    )
+}
+
+/// GET/PUT /patients/{id}/contact_preferences
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContactPreferencesDto {
+    pub preferred_channel: Option<ContactChannel>,
+    pub preferred_language: Option<Lang>,
+    pub quiet_hours: Vec<QuietWindow>,
+    /// inside one of the windows right now: automatic SMS are on hold
+    pub quiet_now: bool,
+}
+
+/// PUT replaces all three; missing fields are cleared.
+#[derive(Debug, Deserialize)]
+pub struct ContactPreferencesRequest {
+    #[serde(default)]
+    pub preferred_channel: Option<String>, // "sms" | "call" | "email"
+    #[serde(default)]
+    pub preferred_language: Option<String>, // "en" | "mn"
+    #[serde(default)]
+    pub quiet_hours: Vec<QuietWindow>,
+}
+
+/// A validated PUT body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactPreferences {
+    pub preferred_channel: Option<ContactChannel>,
+    pub preferred_language: Option<Lang>,
+    pub quiet_hours: Vec<QuietWindow>,
+}
+
+impl ContactPreferencesRequest {
+    pub fn validate(self) -> Result<ContactPreferences, ApiError> {
+        let bad = |e: String| ApiError::BadRequest("VALIDATION_ERROR", e);
+        let preferred_channel = match self.preferred_channel.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(s) => Some(ContactChannel::parse(s).map_err(bad)?),
+        };
+        let preferred_language = match self.preferred_language.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(tag) => Some(Lang::parse(tag).ok_or_else(|| {
+                bad(format!("preferred_language must be one of: {}", i18n::SUPPORTED.join(", ")))
+            })?),
+        };
+        let quiet_hours = normalize_quiet_hours(self.quiet_hours).map_err(bad)?;
+        Ok(ContactPreferences { preferred_channel, preferred_language, quiet_hours })
+    }
+}
+
+type PrefsRow = (Option<String>, Option<String>, JsonValue);
+
+async fn to_dto(db: &PgPool, (channel, language, quiet_hours): PrefsRow) -> Result<ContactPreferencesDto, ApiError> {
+    let tz = clinic_time::clinic_tz(db).await?;
+    let local_now = Utc::now().with_timezone(&tz).time();
+    // all three are checked on write (API + CHECK constraints)
+    let quiet_hours: Vec<QuietWindow> = serde_json::from_value(quiet_hours).unwrap_or_else(|e| {
+        tracing::warn!("patient.quiet_hours: {e}; ignoring");
+        Vec::new()
+    });
+    Ok(ContactPreferencesDto {
+        preferred_channel: channel.as_deref().and_then(|c| ContactChannel::parse(c).ok()),
+        preferred_language: language.as_deref().and_then(Lang::parse),
+        quiet_now: in_quiet_hours(&quiet_hours, local_now),
+        quiet_hours,
+    })
+}
+
+pub async fn load(db: &PgPool, patient_id: Uuid) -> Result<ContactPreferencesDto, ApiError> {
+    let row: Option<PrefsRow> = sqlx::query_as(
+        "SELECT preferred_channel, preferred_language, quiet_hours FROM patient WHERE patient_id = $1",
+    )
+    .bind(patient_id)
+    .fetch_optional(db)
+    .await?;
+    let row = row.ok_or_else(|| ApiError::NotFound("NOT_FOUND", "patient not found".into()))?;
+    to_dto(db, row).await
+}
+
+/// Stores validated preferences (audited) and returns them as GET would.
+pub async fn save(
+    db: &PgPool,
+    auth: &AuthContext,
+    patient_id: Uuid,
+    prefs: &ContactPreferences,
+) -> Result<ContactPreferencesDto, ApiError> {
+    let mut tx = db.begin().await?;
+    let quiet_hours = serde_json::to_value(&prefs.quiet_hours).unwrap_or_default();
+    let row: PrefsRow = sqlx::query_as(
+        r#"
+        UPDATE patient
+        SET preferred_channel = $2, preferred_language = $3, quiet_hours = $4
+        WHERE patient_id = $1 AND anonymized_at IS NULL
+        RETURNING preferred_channel, preferred_language, quiet_hours
+        "#,
+    )
+    .bind(patient_id)
+    .bind(prefs.preferred_channel.map(ContactChannel::as_str))
+    .bind(prefs.preferred_language.map(Lang::as_str))
+    .bind(&quiet_hours)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("CONTACT_PREFERENCES_UPDATE_FAILED"))?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "patient not found".into()))?;
+
+    audit::record(
+        &mut *tx,
+        auth,
+        "patient.contact_preferences",
+        "patient",
+        Some(patient_id),
+        serde_json::json!({
+            "preferred_channel": prefs.preferred_channel,
+            "preferred_language": prefs.preferred_language,
+            "quiet_hours": quiet_hours,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    to_dto(db, row).await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn t(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn window(start: &str, end: &str) -> QuietWindow {
+        QuietWindow { start: start.into(), end: end.into() }
+    }
+
+    #[test]
+    fn quiet_windows_may_wrap_midnight() {
+        let windows = vec![window("21:00", "08:30"), window("13:00", "14:00")];
+        assert!(in_quiet_hours(&windows, t(23, 15)));
+        assert!(in_quiet_hours(&windows, t(13, 30)));
+        assert!(!in_quiet_hours(&windows, t(12, 0)));
+        assert!(!in_quiet_hours(&[], t(3, 0)));
+        assert!(window_contains(t(21, 0), t(8, 30), t(23, 15)));
+        assert!(window_contains(t(21, 0), t(8, 30), t(6, 0)));
+        assert!(!window_contains(t(21, 0), t(8, 30), t(8, 30)));
+        assert!(!window_contains(t(21, 0), t(8, 30), t(20, 59)));
+        assert!(window_contains(t(13, 0), t(14, 0), t(13, 0)));
+        assert!(!window_contains(t(13, 0), t(14, 0), t(14, 0)));
+    }
+
+    #[test]
+    fn requests_are_validated_and_normalized() {
+        let req: ContactPreferencesRequest = serde_json::from_value(json!({
+            "preferred_channel": "call",
+            "preferred_language": "mn-MN",
+            "quiet_hours": [{ "start": "9:00", "end": "7:05" }]
+        }))
+        .unwrap();
+        let prefs = req.validate().unwrap();
+        assert_eq!(prefs.preferred_channel, Some(ContactChannel::Call));
+        assert_eq!(prefs.preferred_language, Some(Lang::Mn));
+        assert_eq!(prefs.quiet_hours, vec![window("09:00", "07:05")]);
+
+        // empty body clears everything
+        let req: ContactPreferencesRequest = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(req.validate().unwrap().quiet_hours, vec![]);
+
+        let bad = [
+            json!({ "preferred_channel": "fax" }),
+            json!({ "preferred_language": "de" }),
+            json!({ "quiet_hours": [{ "start": "22:00", "end": "22:00" }] }),
+            json!({ "quiet_hours": [{ "start": "25:00", "end": "07:00" }] }),
+            json!({ "quiet_hours": vec![json!({ "start": "01:00", "end": "02:00" }); MAX_QUIET_WINDOWS + 1] }),
+        ];
+        for v in bad {
+            let req: ContactPreferencesRequest = serde_json::from_value(v.clone()).unwrap();
+            assert!(req.validate().is_err(), "{v}");
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// src/cursor.rs
+//
+// Opaque keyset-pagination cursor for lists ordered by (timestamp DESC, id DESC)
+// (GET /api/v2/sms; intended for other history lists too).
+// Token = base64url("<unix micros>:<uuid>"); clients pass `next_cursor` back as
+// `cursor` unchanged and must not build or parse it themselves.
+// Query side: `WHERE (ts, id) < ($at, $id) ORDER BY ts DESC, id DESC LIMIT n + 1`,
+// then `page()` trims the extra row and derives the next cursor.
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.at.timestamp_micros(), self.id))
+    }
+
+    pub fn decode(token: &str) -> Result<Cursor, ApiError> {
+        let invalid = || ApiError::BadRequest("VALIDATION_ERROR", "invalid cursor".into());
+        let raw = URL_SAFE_NO_PAD.decode(token.trim()).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (micros, id) = raw.split_once(':').ok_or_else(invalid)?;
+        let at = micros
+            .parse::<i64>()
+            .ok()
+            .and_then(DateTime::from_timestamp_micros)
+            .ok_or_else(invalid)?;
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+        Ok(Cursor { at, id })
+    }
+}
+
+/// `rows` fetched with LIMIT `limit + 1`: keeps `limit` rows and returns the cursor
+/// of the last one if there were more.
+pub fn page<T>(mut rows: Vec<T>, limit: i64, key: impl Fn(&T) -> Cursor) -> (Vec<T>, Option<String>) {
+    let limit = limit.max(0) as usize;
+    if rows.len() <= limit {
+        return (rows, None);
+    }
+    rows.truncate(limit);
+    let next = rows.last().map(|r| key(r).encode());
+    (rows, next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_rejects_garbage() {
+        let c = Cursor {
+            at: DateTime::from_timestamp_micros(1_792_108_800_123_456).unwrap(),
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(Cursor::decode(&c.encode()).unwrap(), c);
+        assert!(Cursor::decode("not a cursor").is_err());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("12:nope")).is_err());
+    }
+
+    #[test]
+    fn page_trims_the_lookahead_row() {
+        let id = Uuid::nil();
+        let key = |n: &i64| Cursor { at: DateTime::from_timestamp(*n, 0).unwrap(), id };
+
+        let (rows, next) = page(vec![5, 4, 3], 2, key);
+        assert_eq!(rows, vec![5, 4]);
+        assert_eq!(Cursor::decode(&next.unwrap()).unwrap().at.timestamp(), 4);
+
+        let (rows, next) = page(vec![5, 4], 2, key);
+        assert_eq!(rows.len(), 2);
+        assert!(next.is_none());
+    }
+}
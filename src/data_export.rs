@@ -0,0 +1,644 @@
+// src/data_export.rs
+//
+// Nightly CSV export for the owner's BI tooling, so it never needs database
+// credentials (EXPORT_* env, see README):
+// - after EXPORT_HOUR clinic time, jobs::data_export writes one CSV per dataset and
+//   uploads it as `{target}/{YYYY-MM-DD}/{dataset}.csv`; POST /admin/exports/run does
+//   the same on demand (and replaces that day's files)
+// - targets: s3://bucket/prefix (SigV4; AWS, or any S3-compatible EXPORT_S3_ENDPOINT)
+//   or sftp://user@host[:port]/dir, both uploaded with the curl CLI (on PATH; built
+//   with SFTP support for sftp://). Credentials reach curl on stdin, never on its
+//   command line.
+// - full snapshots from one REPEATABLE READ transaction, not increments
+// - patients without names, contact details or register numbers (often a national
+//   ID); the BI side joins on patient_id
+// - every run is a data_export_run row (migration 066), GET /admin/exports
+
+use std::{fmt, path::Path, process::Stdio, time::Duration};
+
+use anyhow::Context as _;
+use chrono::{DateTime, NaiveDate, Utc};
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool, Row};
+use tokio::{io::AsyncWriteExt, process::Command};
+use uuid::Uuid;
+
+use crate::models::AppState;
+
+pub const CURL_BIN: &str = "curl";
+pub const DEFAULT_EXPORT_HOUR: u32 = 2;
+const DEFAULT_S3_REGION: &str = "us-east-1";
+const DEFAULT_SFTP_PORT: u16 = 22;
+/// per file, passed to curl as --max-time
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// A credential; Debug doesn't print it (Config derives Debug).
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportTarget {
+    S3 {
+        bucket: String,
+        /// without leading/trailing '/'; may be empty
+        prefix: String,
+        region: String,
+        /// S3-compatible service (MinIO, R2, ...), path-style; None = AWS
+        endpoint: Option<String>,
+        access_key_id: String,
+        secret_access_key: Secret,
+    },
+    Sftp {
+        user: String,
+        host: String,
+        port: u16,
+        /// absolute ("/exports") or home-relative ("/~/exports")
+        dir: String,
+        password: Option<Secret>,
+        /// private key file (instead of, or protected by, the password)
+        key_file: Option<String>,
+        /// pins the server's host key (curl --hostpubsha256)
+        host_key_sha256: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dataset {
+    Appointments,
+    Patients,
+}
+
+impl Dataset {
+    pub const ALL: [Dataset; 2] = [Dataset::Appointments, Dataset::Patients];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Dataset::Appointments => "appointments",
+            Dataset::Patients => "patients",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.as_str() == s)
+    }
+
+    /// (CSV header, SQL expression); every expression is text, timestamps ISO 8601 UTC.
+    fn columns(self) -> Vec<(&'static str, String)> {
+        let text = |e: &str| format!("{e}::text");
+        match self {
+            Dataset::Appointments => vec![
+                ("appointment_id", text("a.appointment_id")),
+                ("patient_id", text("a.patient_id")),
+                ("doctor_employee_id", text("a.doctor_employee_id")),
+                ("assistant_employee_id", text("a.assistant_employee_id")),
+                ("location_id", text("a.location_id")),
+                ("start_at", iso("a.start_at")),
+                ("end_at", iso("a.end_at")),
+                ("status", text("a.status")),
+                ("source", "a.source".into()),
+                ("is_new_patient", text("a.is_new_patient")),
+                ("arrived_at", iso("a.arrived_at")),
+                ("seated_at", iso("a.seated_at")),
+                ("dismissed_at", iso("a.dismissed_at")),
+                ("canceled_at", iso("a.canceled_at")),
+                ("created_at", iso("a.created_at")),
+                ("planned_service_id", text("a.planned_service_id")),
+                // plan items at catalog price, whatever the status
+                ("planned_cents", text("COALESCE(pi.cents, 0)")),
+            ],
+            Dataset::Patients => vec![
+                ("patient_id", text("p.patient_id")),
+                ("gender", text("p.gender")),
+                ("birth_year", text("EXTRACT(YEAR FROM p.birthday)::int")),
+                ("status", text("p.status")),
+                ("referral_source", "rs.name".into()),
+                ("preferred_language", "p.preferred_language".into()),
+                ("created_at", iso("p.created_at")),
+                ("anonymized_at", iso("p.anonymized_at")),
+            ],
+        }
+    }
+
+    fn header(self) -> String {
+        let names: Vec<&str> = self.columns().into_iter().map(|(name, _)| name).collect();
+        format!("{}\n", names.join(","))
+    }
+
+    fn select_sql(self) -> String {
+        let exprs: Vec<String> = self.columns().into_iter().map(|(_, expr)| expr).collect();
+        let from = match self {
+            Dataset::Appointments => {
+                r#"FROM appointment a
+                LEFT JOIN LATERAL (
+                  SELECT SUM(i.qty::int8 * s.price_cents) AS cents
+                  FROM appointment_plan_item i
+                  JOIN service_catalog s ON s.service_id = i.service_id
+                  WHERE i.appointment_id = a.appointment_id
+                ) pi ON TRUE
+                ORDER BY a.start_at, a.appointment_id"#
+            }
+            Dataset::Patients => {
+                r#"FROM patient p
+                LEFT JOIN referral_source rs ON rs.referral_source_id = p.referral_source_id
+                ORDER BY p.created_at, p.patient_id"#
+            }
+        };
+        format!("SELECT {} {from}", exprs.join(", "))
+    }
+}
+
+fn iso(col: &str) -> String {
+    format!("to_char({col} AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"')")
+}
+
+/// Quotes a CSV field when it needs it (RFC 4180).
+pub fn csv_field(v: &str) -> String {
+    if v.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", v.replace('"', "\"\""))
+    } else {
+        v.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSettings {
+    pub target: ExportTarget,
+    /// clinic-local hour (0..23) after which the nightly run starts
+    pub hour: u32,
+    pub datasets: Vec<Dataset>,
+}
+
+impl ExportSettings {
+    /// From the EXPORT_* variables; None when EXPORT_TARGET is unset.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Result<Option<Self>, String> {
+        let var = |name: &str| var(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let Some(target) = var("EXPORT_TARGET") else {
+            return Ok(None);
+        };
+
+        let target = if let Some(rest) = target.strip_prefix("s3://") {
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            if bucket.is_empty()
+                || !bucket.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+            {
+                return Err("EXPORT_TARGET: s3://<bucket>/<prefix>, bucket in lowercase letters, digits, '-' and '.'".into());
+            }
+            let (Some(access_key_id), Some(secret_access_key)) =
+                (var("EXPORT_S3_ACCESS_KEY_ID"), var("EXPORT_S3_SECRET_ACCESS_KEY"))
+            else {
+                return Err("s3 exports need EXPORT_S3_ACCESS_KEY_ID and EXPORT_S3_SECRET_ACCESS_KEY".into());
+            };
+            let endpoint = var("EXPORT_S3_ENDPOINT").map(|e| e.trim_end_matches('/').to_string());
+            if endpoint.as_deref().is_some_and(|e| !e.starts_with("https://") && !e.starts_with("http://")) {
+                return Err("EXPORT_S3_ENDPOINT must be an http(s) URL".into());
+            }
+            ExportTarget::S3 {
+                bucket: bucket.to_string(),
+                prefix: prefix.trim_matches('/').to_string(),
+                region: var("EXPORT_S3_REGION").unwrap_or_else(|| DEFAULT_S3_REGION.into()),
+                endpoint,
+                access_key_id,
+                secret_access_key: Secret(secret_access_key),
+            }
+        } else if let Some(rest) = target.strip_prefix("sftp://") {
+            let (authority, dir) = rest.split_once('/').unwrap_or((rest, ""));
+            let Some((user, host_port)) = authority.split_once('@').filter(|(u, h)| !u.is_empty() && !h.is_empty())
+            else {
+                return Err("EXPORT_TARGET: sftp://<user>@<host>[:port]/<dir>".into());
+            };
+            let (host, port) = match host_port.rsplit_once(':') {
+                Some((host, port)) => {
+                    (host, port.parse::<u16>().map_err(|_| "EXPORT_TARGET: invalid sftp port".to_string())?)
+                }
+                None => (host_port, DEFAULT_SFTP_PORT),
+            };
+            let password = var("EXPORT_SFTP_PASSWORD").map(Secret);
+            let key_file = var("EXPORT_SFTP_KEY_FILE");
+            if password.is_none() && key_file.is_none() {
+                return Err("sftp exports need EXPORT_SFTP_PASSWORD or EXPORT_SFTP_KEY_FILE".into());
+            }
+            ExportTarget::Sftp {
+                user: user.to_string(),
+                host: host.to_string(),
+                port,
+                dir: format!("/{}", dir.trim_matches('/')),
+                password,
+                key_file,
+                host_key_sha256: var("EXPORT_SFTP_HOST_KEY_SHA256"),
+            }
+        } else {
+            return Err("EXPORT_TARGET must start with s3:// or sftp://".into());
+        };
+
+        let hour = match var("EXPORT_HOUR") {
+            None => DEFAULT_EXPORT_HOUR,
+            Some(h) => h.parse::<u32>().ok().filter(|h| *h < 24).ok_or("EXPORT_HOUR must be 0..23")?,
+        };
+        let datasets = match var("EXPORT_DATASETS") {
+            None => Dataset::ALL.to_vec(),
+            Some(list) => {
+                let mut out = Vec::new();
+                for name in list.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                    let d = Dataset::parse(name).ok_or_else(|| {
+                        format!("EXPORT_DATASETS: unknown dataset '{name}' (appointments, patients)")
+                    })?;
+                    if !out.contains(&d) {
+                        out.push(d);
+                    }
+                }
+                if out.is_empty() {
+                    return Err("EXPORT_DATASETS lists no dataset".into());
+                }
+                out
+            }
+        };
+        Ok(Some(Self { target, hour, datasets }))
+    }
+}
+
+/// Config-file string for curl (`-K -`): quoted, with `\` and `"` escaped.
+fn curl_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl ExportTarget {
+    /// Where the files go, without credentials (stored on each run).
+    pub fn display(&self) -> String {
+        match self {
+            ExportTarget::S3 { bucket, prefix, .. } if prefix.is_empty() => format!("s3://{bucket}"),
+            ExportTarget::S3 { bucket, prefix, .. } => format!("s3://{bucket}/{prefix}"),
+            ExportTarget::Sftp { user, host, port, dir, .. } => format!("sftp://{user}@{host}:{port}{dir}"),
+        }
+    }
+
+    /// Object key / remote path below the target, e.g. `2026-10-16/appointments.csv`.
+    pub fn file_key(export_date: NaiveDate, dataset: Dataset) -> String {
+        format!("{export_date}/{}.csv", dataset.as_str())
+    }
+
+    fn url(&self, key: &str) -> String {
+        match self {
+            ExportTarget::S3 { bucket, prefix, region, endpoint, .. } => {
+                let path = if prefix.is_empty() { key.to_string() } else { format!("{prefix}/{key}") };
+                match endpoint {
+                    Some(endpoint) => format!("{endpoint}/{bucket}/{path}"),
+                    None => format!("https://{bucket}.s3.{region}.amazonaws.com/{path}"),
+                }
+            }
+            ExportTarget::Sftp { host, port, dir, .. } => {
+                format!("sftp://{host}:{port}{}/{key}", dir.trim_end_matches('/'))
+            }
+        }
+    }
+
+    /// curl arguments and the config it reads from stdin (the credentials).
+    fn curl_invocation(&self, file: &Path, key: &str) -> (Vec<String>, String) {
+        let mut args: Vec<String> = [
+            "--fail",
+            "--silent",
+            "--show-error",
+            "--config",
+            "-",
+            "--max-time",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+        args.push(UPLOAD_TIMEOUT.as_secs().to_string());
+        let config = match self {
+            ExportTarget::S3 { region, access_key_id, secret_access_key, .. } => {
+                args.extend([
+                    "--aws-sigv4".to_string(),
+                    format!("aws:amz:{region}:s3"),
+                    "--header".to_string(),
+                    "x-amz-content-sha256: UNSIGNED-PAYLOAD".to_string(),
+                    "--header".to_string(),
+                    "Content-Type: text/csv; charset=utf-8".to_string(),
+                ]);
+                format!("user = {}\n", curl_quote(&format!("{access_key_id}:{}", secret_access_key.0)))
+            }
+            ExportTarget::Sftp { user, password, key_file, host_key_sha256, .. } => {
+                args.push("--ftp-create-dirs".to_string());
+                if let Some(k) = key_file {
+                    args.extend(["--key".to_string(), k.clone()]);
+                }
+                if let Some(h) = host_key_sha256 {
+                    args.extend(["--hostpubsha256".to_string(), h.clone()]);
+                }
+                let mut config = String::new();
+                match (password, key_file) {
+                    // with a key file the password unlocks the key
+                    (Some(p), Some(_)) => {
+                        config.push_str(&format!("user = {}\npass = {}\n", curl_quote(&format!("{user}:")), curl_quote(&p.0)))
+                    }
+                    (Some(p), None) => config.push_str(&format!("user = {}\n", curl_quote(&format!("{user}:{}", p.0)))),
+                    (None, _) => config.push_str(&format!("user = {}\n", curl_quote(&format!("{user}:")))),
+                }
+                config
+            }
+        };
+        args.extend(["--upload-file".to_string(), file.display().to_string(), self.url(key)]);
+        (args, config)
+    }
+
+    async fn upload(&self, file: &Path, key: &str) -> anyhow::Result<()> {
+        let (args, config) = self.curl_invocation(file, key);
+        let mut child = Command::new(CURL_BIN)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("cannot start {CURL_BIN}"))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(config.as_bytes()).await?;
+        }
+        let out = child.wait_with_output().await?;
+        if !out.status.success() {
+            anyhow::bail!("upload of {key} failed ({}): {}", out.status, String::from_utf8_lossy(&out.stderr).trim());
+        }
+        Ok(())
+    }
+}
+
+/* ============================================================
+   Runs
+   ============================================================ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    Schedule,
+    Manual,
+}
+
+impl Trigger {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Trigger::Schedule => "schedule",
+            Trigger::Manual => "manual",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedFile {
+    pub dataset: Dataset,
+    pub key: String,
+    pub rows: i64,
+    pub bytes: u64,
+}
+
+/// GET /admin/exports
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExportRun {
+    pub run_id: Uuid,
+    /// clinic-local day; names the folder
+    pub export_date: NaiveDate,
+    pub trigger: String, // "schedule" | "manual"
+    pub status: String,  // "running" | "succeeded" | "failed"
+    pub target: String,
+    /// [{ dataset, key, rows, bytes }] uploaded so far
+    pub files: serde_json::Value,
+    pub error: Option<String>,
+    pub requested_by_user_id: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+const RUN_COLUMNS: &str =
+    "run_id, export_date, trigger, status, target, files, error, requested_by_user_id, started_at, finished_at";
+
+/// Records a new running run. None when a scheduled run for `export_date` is
+/// already running or done (another instance got there first).
+pub async fn start_run(
+    db: &PgPool,
+    settings: &ExportSettings,
+    trigger: Trigger,
+    export_date: NaiveDate,
+    requested_by_user_id: Option<Uuid>,
+) -> Result<Option<ExportRun>, sqlx::Error> {
+    sqlx::query_as::<_, ExportRun>(&format!(
+        r#"
+        INSERT INTO data_export_run (export_date, trigger, target, requested_by_user_id)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (export_date) WHERE trigger = 'schedule' AND status IN ('running', 'succeeded') DO NOTHING
+        RETURNING {RUN_COLUMNS}
+        "#
+    ))
+    .bind(export_date)
+    .bind(trigger.as_str())
+    .bind(settings.target.display())
+    .bind(requested_by_user_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Exports and uploads every dataset for `run_id`, then marks the run succeeded
+/// or failed (with the error) and returns it.
+pub async fn execute(
+    state: &AppState,
+    settings: &ExportSettings,
+    run_id: Uuid,
+    export_date: NaiveDate,
+) -> Result<ExportRun, sqlx::Error> {
+    let dir = std::env::temp_dir().join(format!("dcms-export-{run_id}"));
+    let mut files = Vec::new();
+    let result = export_all(state, settings, export_date, &dir, &mut files).await;
+    if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+        tracing::warn!("data export {run_id}: cannot remove {}: {e}", dir.display());
+    }
+    let (status, error) = match result {
+        Ok(()) => ("succeeded", None),
+        Err(e) => {
+            tracing::warn!("data export {run_id} failed: {e:#}");
+            ("failed", Some(format!("{e:#}")))
+        }
+    };
+    let files = serde_json::to_value(&files).unwrap_or_default();
+    let sql = format!(
+        r#"
+        UPDATE data_export_run
+        SET status = $2, error = $3, files = $4, finished_at = now()
+        WHERE run_id = $1
+        RETURNING {RUN_COLUMNS}
+        "#
+    );
+    crate::db::retry_transient(|| {
+        sqlx::query_as::<_, ExportRun>(&sql)
+            .bind(run_id)
+            .bind(status)
+            .bind(&error)
+            .bind(&files)
+            .fetch_one(&state.db)
+    })
+    .await
+}
+
+async fn export_all(
+    state: &AppState,
+    settings: &ExportSettings,
+    export_date: NaiveDate,
+    dir: &Path,
+    files: &mut Vec<ExportedFile>,
+) -> anyhow::Result<()> {
+    tokio::fs::create_dir_all(dir).await.with_context(|| format!("cannot create {}", dir.display()))?;
+
+    // one snapshot for all datasets; exempt from DB_STATEMENT_TIMEOUT_SECS like the
+    // report refresh, a full table can take longer
+    let mut tx = state.read_db().begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY").execute(&mut *tx).await?;
+    sqlx::query("SET LOCAL statement_timeout = 0").execute(&mut *tx).await?;
+    let mut written = Vec::with_capacity(settings.datasets.len());
+    for &dataset in &settings.datasets {
+        let path = dir.join(format!("{}.csv", dataset.as_str()));
+        let rows = write_csv(&mut tx, dataset, &path).await.with_context(|| format!("{} export", dataset.as_str()))?;
+        written.push((dataset, path, rows));
+    }
+    tx.commit().await?;
+
+    for (dataset, path, rows) in written {
+        let key = ExportTarget::file_key(export_date, dataset);
+        let bytes = tokio::fs::metadata(&path).await?.len();
+        settings.target.upload(&path, &key).await?;
+        files.push(ExportedFile { dataset, key, rows, bytes });
+    }
+    Ok(())
+}
+
+/// Streams `dataset` into a CSV file; returns the row count.
+async fn write_csv(conn: &mut PgConnection, dataset: Dataset, path: &Path) -> anyhow::Result<i64> {
+    let file = tokio::fs::File::create(path).await?;
+    let mut out = tokio::io::BufWriter::new(file);
+    out.write_all(dataset.header().as_bytes()).await?;
+
+    let sql = dataset.select_sql();
+    let mut rows = sqlx::query(&sql).fetch(&mut *conn);
+    let mut n = 0i64;
+    let mut line = String::new();
+    while let Some(row) = rows.try_next().await? {
+        line.clear();
+        for i in 0..row.len() {
+            if i > 0 {
+                line.push(',');
+            }
+            let value: Option<String> = row.try_get(i)?;
+            line.push_str(&csv_field(value.as_deref().unwrap_or("")));
+        }
+        line.push('\n');
+        out.write_all(line.as_bytes()).await?;
+        n += 1;
+    }
+    out.flush().await?;
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn settings(vars: &[(&str, &str)]) -> Result<Option<ExportSettings>, String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        ExportSettings::from_env(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn s3_target_from_env() {
+        assert_eq!(settings(&[]).unwrap(), None);
+        let s = settings(&[
+            ("EXPORT_TARGET", "s3://clinic-bi/dcms/nightly/"),
+            ("EXPORT_S3_ACCESS_KEY_ID", "AKID"),
+            ("EXPORT_S3_SECRET_ACCESS_KEY", "s3cr\"et"),
+            ("EXPORT_S3_REGION", "ap-northeast-2"),
+            ("EXPORT_HOUR", "3"),
+            ("EXPORT_DATASETS", "patients, appointments, patients"),
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(s.hour, 3);
+        assert_eq!(s.datasets, vec![Dataset::Patients, Dataset::Appointments]);
+        assert_eq!(s.target.display(), "s3://clinic-bi/dcms/nightly");
+        assert!(!format!("{s:?}").contains("s3cr"));
+
+        let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let key = ExportTarget::file_key(date, Dataset::Appointments);
+        assert_eq!(key, "2026-10-16/appointments.csv");
+        let (args, config) = s.target.curl_invocation(Path::new("/tmp/a.csv"), &key);
+        assert_eq!(
+            args.last().map(String::as_str),
+            Some("https://clinic-bi.s3.ap-northeast-2.amazonaws.com/dcms/nightly/2026-10-16/appointments.csv")
+        );
+        assert!(args.contains(&"aws:amz:ap-northeast-2:s3".to_string()));
+        assert!(args.iter().all(|a| !a.contains("s3cr")));
+        assert_eq!(config, "user = \"AKID:s3cr\\\"et\"\n");
+    }
+
+    #[test]
+    fn sftp_target_from_env() {
+        let s = settings(&[
+            ("EXPORT_TARGET", "sftp://bi@files.example:2222/~/dcms"),
+            ("EXPORT_SFTP_PASSWORD", "pw"),
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(s.hour, DEFAULT_EXPORT_HOUR);
+        assert_eq!(s.datasets, Dataset::ALL.to_vec());
+        assert_eq!(s.target.display(), "sftp://bi@files.example:2222/~/dcms");
+        let (args, config) = s.target.curl_invocation(Path::new("/tmp/p.csv"), "2026-10-16/patients.csv");
+        assert_eq!(args.last().map(String::as_str), Some("sftp://files.example:2222/~/dcms/2026-10-16/patients.csv"));
+        assert!(args.contains(&"--ftp-create-dirs".to_string()));
+        assert!(args.iter().all(|a| a != "pw"));
+        assert_eq!(config, "user = \"bi:pw\"\n");
+
+        let s = settings(&[("EXPORT_TARGET", "sftp://bi@files.example"), ("EXPORT_SFTP_KEY_FILE", "/etc/dcms/id_ed25519")])
+            .unwrap()
+            .unwrap();
+        let (args, config) = s.target.curl_invocation(Path::new("/tmp/p.csv"), "k.csv");
+        assert_eq!(args.last().map(String::as_str), Some("sftp://files.example:22/k.csv"));
+        assert!(args.windows(2).any(|w| w[0] == "--key" && w[1] == "/etc/dcms/id_ed25519"));
+        assert_eq!(config, "user = \"bi:\"\n");
+    }
+
+    #[test]
+    fn bad_settings_are_refused() {
+        let bad: [&[(&str, &str)]; 7] = [
+            &[("EXPORT_TARGET", "ftp://x/y")],
+            &[("EXPORT_TARGET", "s3://bucket/p")],
+            &[("EXPORT_TARGET", "s3:///p"), ("EXPORT_S3_ACCESS_KEY_ID", "a"), ("EXPORT_S3_SECRET_ACCESS_KEY", "b")],
+            &[("EXPORT_TARGET", "sftp://host/dir"), ("EXPORT_SFTP_PASSWORD", "pw")],
+            &[("EXPORT_TARGET", "sftp://bi@host/dir")],
+            &[("EXPORT_TARGET", "sftp://bi@host/dir"), ("EXPORT_SFTP_PASSWORD", "pw"), ("EXPORT_HOUR", "24")],
+            &[("EXPORT_TARGET", "sftp://bi@host/dir"), ("EXPORT_SFTP_PASSWORD", "pw"), ("EXPORT_DATASETS", "invoices")],
+        ];
+        for vars in bad {
+            assert!(settings(vars).is_err(), "{vars:?}");
+        }
+    }
+
+    #[test]
+    fn dataset_sql_matches_its_header() {
+        for d in Dataset::ALL {
+            assert_eq!(Dataset::parse(d.as_str()), Some(d));
+            let columns = d.columns();
+            assert_eq!(d.header().trim_end().split(',').count(), columns.len());
+            assert!(d.select_sql().starts_with("SELECT "));
+        }
+        // no names, contact details or register numbers
+        let patients = Dataset::Patients.select_sql();
+        for col in ["first_name", "last_name", "email", "register_number", "phone"] {
+            assert!(!patients.contains(col), "{col}");
+        }
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("plain"), "plain");
+    }
+}
@@ -0,0 +1,465 @@
+// src/day_close.rs
+//
+// End-of-day close: one clinic-local day on a page the manager prints or mails
+// and signs off (GET /reports/day_close, POST /reports/day_close/sign_off).
+// - appointments scheduled vs. finished, no-shows, cancellations, walk-ins
+// - production (catalog prices of finished appointments, as /reports/revenue);
+//   there are no payment tables, so nothing is broken down by payment method
+// - patients registered and SMS sent/received that day
+// - tasks still open when the report was made, and those completed that day
+// Signing off stores the report as it was (migration 065); from then on the day
+// shows that snapshot, not live numbers.
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    audit, clinic_time, day_sheet::escape, error::ApiError, middleware::auth_context::AuthContext,
+    money::DEFAULT_CURRENCY,
+};
+
+pub const MAX_NOTE_CHARS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct DayAppointments {
+    /// not canceled
+    pub scheduled: i64,
+    pub finished: i64,
+    pub no_show: i64,
+    /// scheduled, but neither finished nor a no-show (still in the chair, or never closed)
+    #[sqlx(skip)]
+    pub not_finished: i64,
+    pub canceled: i64,
+    /// not canceled, source WALKIN
+    pub walk_ins: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct DaySms {
+    pub sent: i64,
+    pub received: i64,
+}
+
+/// Open tasks are counted when the report is made, not as of the day's end.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct DayTasks {
+    pub open: i64,
+    pub in_progress: i64,
+    /// open / in progress with due_at before the day's end
+    pub overdue: i64,
+    /// completed during the day
+    pub completed: i64,
+}
+
+/// The report body; stored as day_close.snapshot on sign-off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaySummary {
+    pub date: NaiveDate,
+    pub clinic_name: String,
+    pub currency_code: String,
+    pub generated_at: DateTime<Utc>,
+    pub appointments: DayAppointments,
+    pub production_basis: String,
+    pub production_cents: i64,
+    /// patients registered that day
+    pub new_patients: i64,
+    pub sms: DaySms,
+    pub tasks: DayTasks,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SignOff {
+    pub signed_off_at: DateTime<Utc>,
+    pub signed_off_by_user_id: Option<Uuid>,
+    pub signed_off_by: Option<String>,
+    pub note: Option<String>,
+}
+
+/// GET /reports/day_close
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayClose {
+    #[serde(flatten)]
+    pub summary: DaySummary,
+    /// None until the day is signed off; after that `summary` is the signed snapshot
+    pub sign_off: Option<SignOff>,
+}
+
+/// Trimmed; blank is None.
+pub fn normalize_note(note: Option<String>) -> Result<Option<String>, ApiError> {
+    let Some(note) = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) else {
+        return Ok(None);
+    };
+    if note.chars().count() > MAX_NOTE_CHARS {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("note must be at most {MAX_NOTE_CHARS} chars"),
+        ));
+    }
+    Ok(Some(note))
+}
+
+/// Live numbers for `date`.
+pub async fn summarize(db: &PgPool, date: NaiveDate, tz: Tz) -> Result<DaySummary, ApiError> {
+    let (day_start, day_end) = clinic_time::local_days_range(date, 1, tz);
+
+    let row = sqlx::query(
+        r#"
+        WITH day AS (
+          SELECT a.appointment_id, a.status, a.source, a.arrived_at,
+                 (a.dismissed_at IS NOT NULL OR a.status = 5) AS finished
+          FROM appointment a
+          WHERE a.start_at >= $1 AND a.start_at < $2
+        )
+        SELECT
+          COUNT(*) FILTER (WHERE d.status <> 1)::int8 AS scheduled,
+          COUNT(*) FILTER (WHERE d.status <> 1 AND d.finished)::int8 AS finished,
+          COUNT(*) FILTER (WHERE d.status = 3 AND d.arrived_at IS NULL AND NOT d.finished)::int8 AS no_show,
+          COUNT(*) FILTER (WHERE d.status = 1)::int8 AS canceled,
+          COUNT(*) FILTER (WHERE d.status <> 1 AND d.source = 'WALKIN')::int8 AS walk_ins,
+          (
+            SELECT COALESCE(SUM(pi.qty::int8 * s.price_cents), 0)::int8
+            FROM day f
+            JOIN appointment_plan_item pi ON pi.appointment_id = f.appointment_id
+            JOIN service_catalog s ON s.service_id = pi.service_id
+            WHERE f.status <> 1 AND f.finished
+          ) AS production_cents,
+          (SELECT COUNT(*) FROM patient WHERE created_at >= $1 AND created_at < $2) AS new_patients,
+          (
+            SELECT COUNT(*) FROM sms
+            WHERE direction = 1 AND deleted_at IS NULL AND sent_at >= $1 AND sent_at < $2
+          ) AS sent,
+          (
+            SELECT COUNT(*) FROM sms
+            WHERE direction = 0 AND deleted_at IS NULL AND sent_at >= $1 AND sent_at < $2
+          ) AS received,
+          (SELECT COUNT(*) FROM task WHERE status = 0) AS open,
+          (SELECT COUNT(*) FROM task WHERE status = 1) AS in_progress,
+          (SELECT COUNT(*) FROM task WHERE status IN (0, 1) AND due_at < $2) AS overdue,
+          (
+            SELECT COUNT(*) FROM task
+            WHERE status = 2 AND completed_at >= $1 AND completed_at < $2
+          ) AS completed,
+          (SELECT clinic_name FROM clinic_settings WHERE singleton_id = TRUE) AS clinic_name,
+          (SELECT currency_code FROM clinic_settings WHERE singleton_id = TRUE) AS currency_code
+        FROM day d
+        "#,
+    )
+    .bind(day_start)
+    .bind(day_end)
+    .fetch_one(db)
+    .await?;
+
+    let mut appointments = DayAppointments::from_row(&row).map_err(internal_row)?;
+    appointments.not_finished = appointments.scheduled - appointments.finished - appointments.no_show;
+    let clinic_name: Option<String> = row.try_get("clinic_name").map_err(internal_row)?;
+    let currency_code: Option<String> = row.try_get("currency_code").map_err(internal_row)?;
+    Ok(DaySummary {
+        date,
+        clinic_name: clinic_name.unwrap_or_default(),
+        currency_code: currency_code.unwrap_or_else(|| DEFAULT_CURRENCY.into()),
+        generated_at: Utc::now(),
+        appointments,
+        production_basis: "production_at_catalog_price".into(),
+        production_cents: row.try_get("production_cents").map_err(internal_row)?,
+        new_patients: row.try_get("new_patients").map_err(internal_row)?,
+        sms: DaySms::from_row(&row).map_err(internal_row)?,
+        tasks: DayTasks::from_row(&row).map_err(internal_row)?,
+    })
+}
+
+type SignOffRow = (serde_json::Value, Option<String>, Option<Uuid>, Option<String>, DateTime<Utc>);
+
+/// The signed snapshot when the day is closed, else live numbers.
+pub async fn load(db: &PgPool, date: NaiveDate, tz: Tz) -> Result<DayClose, ApiError> {
+    let signed: Option<SignOffRow> = sqlx::query_as(
+        r#"
+        SELECT c.snapshot, c.note, c.signed_off_by_user_id, u.display_name, c.signed_off_at
+        FROM day_close c
+        LEFT JOIN dcms_user u ON u.user_id = c.signed_off_by_user_id
+        WHERE c.close_date = $1
+        "#,
+    )
+    .bind(date)
+    .fetch_optional(db)
+    .await?;
+    let Some((snapshot, note, signed_off_by_user_id, signed_off_by, signed_off_at)) = signed else {
+        return Ok(DayClose { summary: summarize(db, date, tz).await?, sign_off: None });
+    };
+    let summary = match serde_json::from_value(snapshot) {
+        Ok(s) => s,
+        Err(e) => {
+            // written by `sign_off` only
+            tracing::warn!("day_close {date}: snapshot: {e}; showing live numbers");
+            summarize(db, date, tz).await?
+        }
+    };
+    Ok(DayClose {
+        summary,
+        sign_off: Some(SignOff { signed_off_at, signed_off_by_user_id, signed_off_by, note }),
+    })
+}
+
+/// Stores the live numbers for `date` as signed by `auth` (audited); a day is
+/// signed off once, a second attempt is 409 DAY_ALREADY_CLOSED.
+pub async fn sign_off(
+    db: &PgPool,
+    auth: &AuthContext,
+    date: NaiveDate,
+    tz: Tz,
+    note: Option<String>,
+) -> Result<DayClose, ApiError> {
+    let summary = summarize(db, date, tz).await?;
+    let snapshot = serde_json::to_value(&summary).map_err(|e| ApiError::Internal(format!("day_close snapshot: {e}")))?;
+
+    let mut tx = db.begin().await?;
+    let signed_off_at: DateTime<Utc> = sqlx::query_scalar(
+        r#"
+        INSERT INTO day_close (close_date, snapshot, note, signed_off_by_user_id)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (close_date) DO NOTHING
+        RETURNING signed_off_at
+        "#,
+    )
+    .bind(date)
+    .bind(&snapshot)
+    .bind(&note)
+    .bind(auth.user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("DAY_CLOSE_FAILED"))?
+    .ok_or_else(|| ApiError::Conflict("DAY_ALREADY_CLOSED", format!("{date} is already signed off")))?;
+
+    audit::record(
+        &mut *tx,
+        auth,
+        "day_close.sign_off",
+        "day_close",
+        None,
+        serde_json::json!({ "date": date, "note": note }),
+    )
+    .await?;
+    tx.commit().await?;
+
+    let signed_off_by: Option<String> =
+        sqlx::query_scalar("SELECT display_name FROM dcms_user WHERE user_id = $1")
+            .bind(auth.user_id)
+            .fetch_optional(db)
+            .await?;
+    Ok(DayClose {
+        summary,
+        sign_off: Some(SignOff { signed_off_at, signed_off_by_user_id: Some(auth.user_id), signed_off_by, note }),
+    })
+}
+
+fn internal_row(e: sqlx::Error) -> ApiError {
+    ApiError::Internal(format!("row decode error: {e}"))
+}
+
+/* ============================================================
+   Printable (format=html | pdf)
+   ============================================================ */
+
+const STYLE: &str = "\
+@page { size: A4; margin: 15mm; }
+body { font-family: sans-serif; font-size: 11pt; color: #000; }
+h1 { font-size: 15pt; margin: 0 0 2mm; }
+h2 { font-size: 12pt; margin: 6mm 0 2mm; }
+.meta { margin-bottom: 4mm; }
+table { border-collapse: collapse; min-width: 60%; }
+th, td { border: 1px solid #444; padding: 1.5mm 3mm; text-align: left; }
+td.n { text-align: right; }
+.sign { margin-top: 12mm; }";
+
+fn money(cents: i64, currency: &str) -> String {
+    format!("{}.{:02} {currency}", cents / 100, (cents % 100).abs())
+}
+
+impl DayClose {
+    pub fn title(&self) -> String {
+        format!("Day close {}", self.summary.date)
+    }
+
+    /// (section, [(label, value)]) in print order.
+    fn sections(&self) -> Vec<(&'static str, Vec<(&'static str, String)>)> {
+        let s = &self.summary;
+        let a = &s.appointments;
+        vec![
+            (
+                "Appointments",
+                vec![
+                    ("Scheduled", a.scheduled.to_string()),
+                    ("Finished", a.finished.to_string()),
+                    ("Not finished", a.not_finished.to_string()),
+                    ("No-show", a.no_show.to_string()),
+                    ("Canceled", a.canceled.to_string()),
+                    ("Walk-ins", a.walk_ins.to_string()),
+                ],
+            ),
+            (
+                "Production",
+                vec![("At catalog prices", money(s.production_cents, &s.currency_code))],
+            ),
+            (
+                "Patients and messages",
+                vec![
+                    ("New patients", s.new_patients.to_string()),
+                    ("SMS sent", s.sms.sent.to_string()),
+                    ("SMS received", s.sms.received.to_string()),
+                ],
+            ),
+            (
+                "Tasks",
+                vec![
+                    ("Open", s.tasks.open.to_string()),
+                    ("In progress", s.tasks.in_progress.to_string()),
+                    ("Overdue", s.tasks.overdue.to_string()),
+                    ("Completed today", s.tasks.completed.to_string()),
+                ],
+            ),
+        ]
+    }
+
+    fn local(t: DateTime<Utc>, tz: Tz) -> String {
+        t.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    /// A4 page; times in clinic time.
+    pub fn to_html(&self, tz: Tz) -> String {
+        let s = &self.summary;
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{STYLE}</style></head><body>\n\
+             <h1>{}</h1>\n<div class=\"meta\">{} &middot; generated {}</div>\n",
+            escape(&self.title()),
+            escape(&self.title()),
+            escape(&s.clinic_name),
+            Self::local(s.generated_at, tz),
+        );
+        for (section, rows) in self.sections() {
+            let _ = writeln!(out, "<h2>{section}</h2>\n<table>");
+            for (label, value) in rows {
+                let _ = writeln!(out, "<tr><th>{label}</th><td class=\"n\">{}</td></tr>", escape(&value));
+            }
+            out.push_str("</table>\n");
+        }
+        match &self.sign_off {
+            Some(so) => {
+                let _ = writeln!(
+                    out,
+                    "<p class=\"sign\">Signed off by {} at {}</p>",
+                    escape(so.signed_off_by.as_deref().unwrap_or("(deleted user)")),
+                    Self::local(so.signed_off_at, tz),
+                );
+                if let Some(note) = &so.note {
+                    let _ = writeln!(out, "<p>{}</p>", escape(note));
+                }
+            }
+            None => out.push_str("<p class=\"sign\">Manager: ______________________ &nbsp; Signature: ______________</p>\n"),
+        }
+        out.push_str("</body></html>\n");
+        out
+    }
+
+    /// Body for `pdf::text_document` (the title goes separately).
+    pub fn to_text(&self, tz: Tz) -> String {
+        let s = &self.summary;
+        let mut out = format!("{}\nGenerated {}\n", s.clinic_name, Self::local(s.generated_at, tz));
+        for (section, rows) in self.sections() {
+            let _ = writeln!(out, "\n{section}");
+            for (label, value) in rows {
+                let _ = writeln!(out, "    {label}: {value}");
+            }
+        }
+        out.push('\n');
+        match &self.sign_off {
+            Some(so) => {
+                let _ = writeln!(
+                    out,
+                    "Signed off by {} at {}",
+                    so.signed_off_by.as_deref().unwrap_or("(deleted user)"),
+                    Self::local(so.signed_off_at, tz),
+                );
+                if let Some(note) = &so.note {
+                    let _ = writeln!(out, "{note}");
+                }
+            }
+            None => out.push_str("Manager: ______________________   Signature: ______________\n"),
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(sign_off: Option<SignOff>) -> DayClose {
+        DayClose {
+            summary: DaySummary {
+                date: NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+                clinic_name: "Demo <Dental>".into(),
+                currency_code: "MNT".into(),
+                generated_at: "2026-10-16T11:30:00Z".parse().unwrap(),
+                appointments: DayAppointments {
+                    scheduled: 12,
+                    finished: 9,
+                    no_show: 2,
+                    not_finished: 1,
+                    canceled: 3,
+                    walk_ins: 1,
+                },
+                production_basis: "production_at_catalog_price".into(),
+                production_cents: 12_345_607,
+                new_patients: 2,
+                sms: DaySms { sent: 40, received: 5 },
+                tasks: DayTasks { open: 4, in_progress: 1, overdue: 2, completed: 6 },
+            },
+            sign_off,
+        }
+    }
+
+    #[test]
+    fn printable_page_has_the_numbers_and_a_signature_line() {
+        let tz: Tz = "Asia/Ulaanbaatar".parse().unwrap();
+        let d = day(None);
+        let html = d.to_html(tz);
+        assert!(html.contains("Demo &lt;Dental&gt; &middot; generated 2026-10-16 19:30"));
+        assert!(html.contains("<tr><th>Not finished</th><td class=\"n\">1</td></tr>"));
+        assert!(html.contains("123456.07 MNT"));
+        assert!(html.contains("Signature: ____"));
+        let text = d.to_text(tz);
+        assert!(text.contains("    SMS sent: 40\n"));
+        assert!(text.contains("Signature: ____"));
+    }
+
+    #[test]
+    fn signed_days_name_the_signer() {
+        let tz: Tz = "Asia/Ulaanbaatar".parse().unwrap();
+        let d = day(Some(SignOff {
+            signed_off_at: "2026-10-16T12:05:00Z".parse().unwrap(),
+            signed_off_by_user_id: None,
+            signed_off_by: Some("Manager One".into()),
+            note: Some("cash drawer short & recounted".into()),
+        }));
+        let html = d.to_html(tz);
+        assert!(html.contains("Signed off by Manager One at 2026-10-16 20:05"));
+        assert!(html.contains("cash drawer short &amp; recounted"));
+        assert!(!html.contains("Signature: ____"));
+        // the snapshot round-trips through day_close.snapshot
+        let json = serde_json::to_value(&d.summary).unwrap();
+        assert_eq!(serde_json::from_value::<DaySummary>(json).unwrap(), d.summary);
+    }
+
+    #[test]
+    fn notes_are_trimmed_and_bounded() {
+        assert_eq!(normalize_note(Some("  ok ".into())).unwrap(), Some("ok".into()));
+        assert_eq!(normalize_note(Some("   ".into())).unwrap(), None);
+        assert!(normalize_note(Some("x".repeat(MAX_NOTE_CHARS + 1))).is_err());
+    }
+}
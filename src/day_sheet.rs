@@ -0,0 +1,176 @@
+// src/day_sheet.rs
+//
+// Printable day sheet (GET /appointments/day/print): one doctor's appointments of a
+// day for the paper backup at the front desk. The handler gathers the rows; this
+// only lays them out.
+// - HTML (default): A4 print stylesheet, prints Mongolian names as they are
+// - text for crate::pdf (`format=pdf`): Latin-1 only there, Cyrillic comes out as '?'
+
+use std::fmt::Write as _;
+
+use chrono::NaiveDate;
+
+pub struct DaySheet {
+    pub clinic_name: String,
+    pub date: NaiveDate,
+    pub doctor: String,
+    /// clinic-local "YYYY-MM-DD HH:MM", so a stale printout is recognizable
+    pub printed_at: String,
+    pub rows: Vec<DaySheetRow>,
+}
+
+pub struct DaySheetRow {
+    /// clinic-local "HH:MM-HH:MM"
+    pub time: String,
+    pub patient: String,
+    pub register_number: Option<String>,
+    pub phone: Option<String>,
+    pub planned: String,
+    pub status: &'static str,
+    pub note: Option<String>,
+    /// active clinical alerts, "kind: label"
+    pub alerts: Vec<String>,
+}
+
+const STYLE: &str = "\
+@page { size: A4; margin: 12mm; }
+body { font-family: sans-serif; font-size: 10pt; color: #000; }
+h1 { font-size: 14pt; margin: 0 0 2mm; }
+.meta { margin-bottom: 4mm; }
+table { width: 100%; border-collapse: collapse; }
+th, td { border: 1px solid #444; padding: 1.5mm; text-align: left; vertical-align: top; }
+tr { page-break-inside: avoid; }
+.alert { font-weight: bold; }
+.empty { margin-top: 6mm; }";
+
+impl DaySheet {
+    pub fn title(&self) -> String {
+        format!("{} - {}", self.date, self.doctor)
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{STYLE}</style></head><body>\n\
+             <h1>{}</h1>\n<div class=\"meta\">{} &middot; {} &middot; printed {}</div>\n",
+            escape(&self.title()),
+            escape(&self.doctor),
+            escape(&self.clinic_name),
+            self.date,
+            escape(&self.printed_at),
+        );
+        if self.rows.is_empty() {
+            out.push_str("<p class=\"empty\">No appointments.</p>\n</body></html>\n");
+            return out;
+        }
+        out.push_str(
+            "<table>\n<tr><th>Time</th><th>Patient</th><th>Phone</th><th>Planned</th><th>Status</th><th>Note</th><th>Alerts</th></tr>\n",
+        );
+        for r in &self.rows {
+            let patient = match &r.register_number {
+                Some(rn) => format!("{}<br>{}", escape(&r.patient), escape(rn)),
+                None => escape(&r.patient),
+            };
+            let alerts: Vec<String> = r.alerts.iter().map(|a| escape(a)).collect();
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{patient}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"alert\">{}</td></tr>",
+                escape(&r.time),
+                escape(r.phone.as_deref().unwrap_or("")),
+                escape(&r.planned),
+                r.status,
+                escape(r.note.as_deref().unwrap_or("")),
+                alerts.join("<br>"),
+            );
+        }
+        out.push_str("</table>\n</body></html>\n");
+        out
+    }
+
+    /// Body for `pdf::text_document` (the title goes separately).
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{}\nPrinted {}\n\n", self.clinic_name, self.printed_at);
+        if self.rows.is_empty() {
+            out.push_str("No appointments.\n");
+        }
+        for r in &self.rows {
+            let _ = write!(out, "{}  {}", r.time, r.patient);
+            if let Some(rn) = &r.register_number {
+                let _ = write!(out, " ({rn})");
+            }
+            if let Some(phone) = &r.phone {
+                let _ = write!(out, "  tel. {phone}");
+            }
+            let _ = writeln!(out, "  [{}]", r.status);
+            if !r.planned.is_empty() {
+                let _ = writeln!(out, "    Planned: {}", r.planned);
+            }
+            if let Some(note) = &r.note {
+                let _ = writeln!(out, "    Note: {note}");
+            }
+            for alert in &r.alerts {
+                let _ = writeln!(out, "    ALERT {alert}");
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// HTML text escaping, also used by crate::day_close.
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sheet(rows: Vec<DaySheetRow>) -> DaySheet {
+        DaySheet {
+            clinic_name: "Demo Dental".into(),
+            date: NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+            doctor: "Dr. Bat".into(),
+            printed_at: "2026-10-16 08:00".into(),
+            rows,
+        }
+    }
+
+    #[test]
+    fn html_escapes_patient_text_and_lists_alerts() {
+        let html = sheet(vec![DaySheetRow {
+            time: "09:00-09:30".into(),
+            patient: "Болд <script>".into(),
+            register_number: Some("УБ99".into()),
+            phone: Some("99112233".into()),
+            planned: "Filling x2".into(),
+            status: "confirmed",
+            note: Some("a & b".into()),
+            alerts: vec!["allergy: lidocaine".into()],
+        }])
+        .to_html();
+        assert!(html.contains("Болд &lt;script&gt;<br>УБ99"));
+        assert!(html.contains("a &amp; b"));
+        assert!(html.contains("<td class=\"alert\">allergy: lidocaine</td>"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn empty_day_says_so() {
+        let s = sheet(vec![]);
+        assert!(s.to_html().contains("No appointments."));
+        assert!(s.to_text().contains("No appointments."));
+    }
+}
@@ -1,9 +1,167 @@
-use sqlx::{PgPool, postgres::PgPoolOptions};
+use std::{
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
-pub async fn connect_pg(database_url: &str) -> anyhow::Result<PgPool> {
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect(database_url)
+use sqlx::{
+    ConnectOptions, PgPool,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+
+const REPLICA_CHECK_INTERVAL_SECS: u64 = 5;
+const REPLICA_CHECK_TIMEOUT_SECS: u64 = 2;
+/// a replica further behind than this is treated as failing
+const REPLICA_MAX_LAG_SECS: f64 = 30.0;
+
+/// Pool parameters (DB_* env vars, see config.rs); defaults match what was
+/// hard-coded before / sqlx's own defaults.
+#[derive(Clone, Copy, Debug)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+    /// 0 = idle connections are never closed
+    pub idle_timeout_secs: u64,
+    /// prepared statements cached per connection; 0 disables the cache
+    pub statement_cache_size: usize,
+    /// Postgres cancels any statement running longer (57014); 0 = no limit
+    pub statement_timeout_secs: u64,
+    /// statements at least this slow are logged (telemetry::SlowQueries)
+    pub slow_query_ms: u64,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+            statement_cache_size: 100,
+            statement_timeout_secs: 30,
+            slow_query_ms: 500,
+        }
+    }
+}
+
+impl PoolSettings {
+    fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_secs))
+            .idle_timeout((self.idle_timeout_secs > 0).then(|| Duration::from_secs(self.idle_timeout_secs)))
+    }
+
+    fn connect_options(&self, database_url: &str) -> anyhow::Result<PgConnectOptions> {
+        let mut options = PgConnectOptions::from_str(database_url)?
+            .statement_cache_capacity(self.statement_cache_size)
+            .log_slow_statements(log::LevelFilter::Warn, Duration::from_millis(self.slow_query_ms));
+        // a session default, so it covers every statement on the pool; long jobs lift it
+        // with `SET LOCAL statement_timeout = 0` (see jobs::report_refresh)
+        if self.statement_timeout_secs > 0 {
+            options = options.options([("statement_timeout", format!("{}s", self.statement_timeout_secs))]);
+        }
+        Ok(options)
+    }
+}
+
+pub async fn connect_pg(database_url: &str, settings: &PoolSettings) -> anyhow::Result<PgPool> {
+    let pool = settings
+        .pool_options()
+        .connect_with(settings.connect_options(database_url)?)
         .await?;
     Ok(pool)
 }
+
+/// Read replica (DATABASE_READ_URL). Connects lazily, so a replica that is down
+/// at startup doesn't stop the server; `monitor` keeps `healthy` up to date and
+/// `AppState::read_db` falls back to the primary while it is false.
+#[derive(Clone)]
+pub struct ReadReplica {
+    pool: PgPool,
+    healthy: Arc<AtomicBool>,
+}
+
+impl ReadReplica {
+    /// Same settings as the primary, except a short acquire timeout: a struggling
+    /// replica should fail fast rather than hold requests.
+    pub fn connect_lazy(database_url: &str, settings: &PoolSettings) -> anyhow::Result<Self> {
+        let pool = settings
+            .pool_options()
+            .acquire_timeout(Duration::from_secs(REPLICA_CHECK_TIMEOUT_SECS.min(settings.acquire_timeout_secs)))
+            .connect_lazy_with(settings.connect_options(database_url)?);
+        Ok(Self {
+            pool,
+            healthy: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn pool_if_healthy(&self) -> Option<&PgPool> {
+        self.healthy.load(Ordering::Relaxed).then_some(&self.pool)
+    }
+
+    /// Health check loop; spawned from main.
+    pub async fn monitor(self) {
+        let mut tick = tokio::time::interval(Duration::from_secs(REPLICA_CHECK_INTERVAL_SECS));
+        let mut first = true;
+        loop {
+            tick.tick().await;
+            let problem = match self.lag_secs().await {
+                Ok(lag) if lag <= REPLICA_MAX_LAG_SECS => None,
+                Ok(lag) => Some(format!("read replica is {lag:.0}s behind")),
+                Err(e) => Some(format!("read replica unavailable: {e}")),
+            };
+            let was = self.healthy.swap(problem.is_none(), Ordering::Relaxed);
+            match problem {
+                // only log state changes, not every failed check
+                Some(p) if was || first => tracing::warn!("{p}; reading from primary"),
+                Some(p) => tracing::debug!("{p}"),
+                None if !was => tracing::info!("read replica is healthy, serving reads from it"),
+                None => {}
+            }
+            first = false;
+        }
+    }
+
+    /// Replay lag in seconds; 0 when fully caught up (or when the URL points at a primary).
+    async fn lag_secs(&self) -> anyhow::Result<f64> {
+        let lag = tokio::time::timeout(
+            Duration::from_secs(REPLICA_CHECK_TIMEOUT_SECS),
+            sqlx::query_scalar::<_, f64>(
+                r#"
+                SELECT CASE
+                  WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+                  ELSE COALESCE(EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp()), 0)
+                END::float8
+                "#,
+            )
+            .fetch_one(&self.pool),
+        )
+        .await??;
+        Ok(lag)
+    }
+}
+
+/// The replica when configured and healthy, else the primary.
+pub fn read_pool<'a>(primary: &'a PgPool, replica: Option<&'a ReadReplica>) -> &'a PgPool {
+    replica.and_then(|r| r.pool_if_healthy()).unwrap_or(primary)
+}
+
+/// Runs `op` and retries it once if it failed with a serialization failure or
+/// deadlock. `op` must be a whole transaction (begin..commit) so the retry starts
+/// from scratch.
+pub async fn retry_transient<T, F, Fut>(mut op: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, sqlx::Error>>,
+{
+    match op().await {
+        Err(e) if crate::error::DbError::is_transient(&e) => {
+            tracing::warn!("transient db error, retrying once: {e}");
+            op().await
+        }
+        res => res,
+    }
+}
@@ -0,0 +1,123 @@
+// src/employee_services.rs
+//
+// Service capabilities (employee_service).
+// An employee with no rows performs every service; otherwise only the listed ones.
+// duration_min on a row overrides service_catalog.default_duration_min for that employee.
+
+use std::collections::HashMap;
+
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+/// Display names of the given services the employee does not perform.
+pub async fn unsupported_services<'e, E: PgExecutor<'e>>(
+    db: E,
+    employee_id: Uuid,
+    service_ids: &[Uuid],
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT sc.display_name
+        FROM service_catalog sc
+        WHERE sc.service_id = ANY($2)
+          AND EXISTS (SELECT 1 FROM employee_service es WHERE es.employee_id = $1)
+          AND NOT EXISTS (
+            SELECT 1 FROM employee_service es
+            WHERE es.employee_id = $1 AND es.service_id = sc.service_id
+          )
+        ORDER BY sc.display_number
+        "#,
+    )
+    .bind(employee_id)
+    .bind(service_ids)
+    .fetch_all(db)
+    .await
+}
+
+/// Rejects plan items for services the doctor does not perform.
+pub async fn ensure_can_perform<'e, E: PgExecutor<'e>>(
+    db: E,
+    doctor_employee_id: Uuid,
+    service_ids: &[Uuid],
+) -> Result<(), ApiError> {
+    if service_ids.is_empty() {
+        return Ok(());
+    }
+    let missing = unsupported_services(db, doctor_employee_id, service_ids).await?;
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(
+            "SERVICE_NOT_OFFERED",
+            format!("doctor does not perform: {}", missing.join(", ")),
+        ))
+    }
+}
+
+/// Appointment length for planned items: per item the doctor's override, else the
+/// catalog default, times qty; summed and rounded up to the clinic slot size.
+pub async fn planned_duration_min(
+    db: &PgPool,
+    doctor_employee_id: Uuid,
+    items: &[(Uuid, i32)],
+) -> Result<i64, ApiError> {
+    let service_ids: Vec<Uuid> = items.iter().map(|(id, _)| *id).collect();
+    let rows: Vec<(Uuid, String, Option<i32>)> = sqlx::query_as(
+        r#"
+        SELECT sc.service_id, sc.display_name, COALESCE(es.duration_min, sc.default_duration_min)
+        FROM service_catalog sc
+        LEFT JOIN employee_service es ON es.service_id = sc.service_id AND es.employee_id = $1
+        WHERE sc.service_id = ANY($2)
+        "#,
+    )
+    .bind(doctor_employee_id)
+    .bind(&service_ids)
+    .fetch_all(db)
+    .await?;
+    let by_id: HashMap<Uuid, (String, Option<i32>)> =
+        rows.into_iter().map(|(id, name, min)| (id, (name, min))).collect();
+
+    let mut total = 0i64;
+    for (service_id, qty) in items {
+        match by_id.get(service_id) {
+            None => return Err(ApiError::NotFound("NOT_FOUND", format!("service {service_id} not found"))),
+            Some((name, None)) => {
+                return Err(ApiError::BadRequest(
+                    "VALIDATION_ERROR",
+                    format!("{name} has no default duration; end_at is required"),
+                ))
+            }
+            Some((_, Some(min))) => total += i64::from(*min) * i64::from(*qty),
+        }
+    }
+
+    let slot: Option<i32> =
+        sqlx::query_scalar("SELECT default_slot_minutes FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(db)
+            .await?;
+    Ok(round_up_to_slot(total, slot.unwrap_or(15).into()))
+}
+
+/// Rounds up to a whole number of slots (at least one).
+pub fn round_up_to_slot(minutes: i64, slot: i64) -> i64 {
+    if slot <= 0 {
+        return minutes;
+    }
+    let slots = (minutes + slot - 1) / slot;
+    slots.max(1) * slot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_to_slots() {
+        assert_eq!(round_up_to_slot(30, 15), 30);
+        assert_eq!(round_up_to_slot(31, 15), 45);
+        assert_eq!(round_up_to_slot(0, 15), 15);
+        assert_eq!(round_up_to_slot(50, 0), 50);
+    }
+}
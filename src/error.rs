@@ -5,6 +5,8 @@ use axum::{
 };
 use serde::Serialize;
 
+use crate::{i18n, middleware::request_context};
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: ErrorObject,
@@ -14,6 +16,12 @@ pub struct ErrorResponse {
 pub struct ErrorObject {
     pub code: String,
     pub message: String,
+    /// catalog message for `code` in the request language (crate::i18n)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_localized: Option<&'static str>,
+    /// machine-readable context for the client (e.g. duplicate candidates)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 #[derive(Debug)]
@@ -21,10 +29,12 @@ pub enum ApiError {
     Unauthorized(&'static str, String),
     Forbidden(&'static str, String),
     BadRequest(&'static str, String),
-    #[allow(dead_code)]
     NotFound(&'static str, String),
-    #[allow(dead_code)]
     Conflict(&'static str, String),
+    /// 409 with `error.details`, for conflicts the client resolves by looking at data
+    ConflictWithDetails(&'static str, String, serde_json::Value),
+    PayloadTooLarge(&'static str, String),
+    UnsupportedMediaType(&'static str, String),
     Internal(String),
 }
 
@@ -37,13 +47,35 @@ impl ApiError {
         ApiError::Unauthorized("SESSION_EXPIRED", "Session expired".into())
     }
 
+    fn error_object(code: &str, message: &str) -> ErrorObject {
+        ErrorObject {
+            code: code.to_string(),
+            message: message.to_string(),
+            message_localized: i18n::message(code, request_context::current_lang()),
+            details: None,
+        }
+    }
+
     fn to_error_response(code: &str, message: &str) -> Json<ErrorResponse> {
-        Json(ErrorResponse {
-            error: ErrorObject {
-                code: code.to_string(),
-                message: message.to_string(),
-            },
-        })
+        Json(ErrorResponse { error: ApiError::error_object(code, message) })
+    }
+
+    /// The `error` object this would respond with, for per-item results inside a 200
+    /// (POST /sync/push). None for Internal: that should fail the whole request.
+    pub fn to_error_object(&self) -> Option<ErrorObject> {
+        match self {
+            ApiError::Unauthorized(code, msg)
+            | ApiError::Forbidden(code, msg)
+            | ApiError::BadRequest(code, msg)
+            | ApiError::NotFound(code, msg)
+            | ApiError::Conflict(code, msg)
+            | ApiError::PayloadTooLarge(code, msg)
+            | ApiError::UnsupportedMediaType(code, msg) => Some(ApiError::error_object(code, msg)),
+            ApiError::ConflictWithDetails(code, msg, details) => {
+                Some(ErrorObject { details: Some(details.clone()), ..ApiError::error_object(code, msg) })
+            }
+            ApiError::Internal(_) => None,
+        }
     }
 }
 
@@ -65,11 +97,167 @@ impl IntoResponse for ApiError {
             ApiError::Conflict(code, msg) => {
                 (StatusCode::CONFLICT, ApiError::to_error_response(code, &msg)).into_response()
             }
-            ApiError::Internal(msg) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                ApiError::to_error_response("INTERNAL", &msg),
-            )
-                .into_response(),
+            ApiError::ConflictWithDetails(code, msg, details) => {
+                let mut body = ApiError::to_error_response(code, &msg);
+                body.error.details = Some(details);
+                (StatusCode::CONFLICT, body).into_response()
+            }
+            ApiError::PayloadTooLarge(code, msg) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, ApiError::to_error_response(code, &msg)).into_response()
+            }
+            ApiError::UnsupportedMediaType(code, msg) => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, ApiError::to_error_response(code, &msg)).into_response()
+            }
+            ApiError::Internal(msg) => {
+                // details (SQL errors, row decode failures) go to the log, not the client
+                tracing::error!("internal error: {msg}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ApiError::to_error_response("INTERNAL", "Internal server error"),
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+/* ============================================================
+   Database errors
+   ============================================================ */
+
+/// sqlx errors classified by what the client can do about them.
+#[derive(Debug)]
+pub enum DbError {
+    /// 23505
+    UniqueViolation { constraint: Option<String> },
+    /// 23503
+    ForeignKeyViolation { constraint: Option<String> },
+    /// 23514 / 23502 / 23P01 (check, not-null, exclusion)
+    ConstraintViolation { constraint: Option<String> },
+    /// 40001 / 40P01: safe to retry the whole transaction
+    SerializationFailure,
+    /// no pooled connection within DB_ACQUIRE_TIMEOUT_SECS
+    PoolExhausted,
+    /// 57014: cancelled, normally by DB_STATEMENT_TIMEOUT_SECS
+    StatementTimeout,
+    NotFound,
+    Other(sqlx::Error),
+}
+
+impl DbError {
+    pub fn is_transient(e: &sqlx::Error) -> bool {
+        matches!(
+            e.as_database_error().and_then(|d| d.code()).as_deref(),
+            Some("40001" | "40P01")
+        )
+    }
+}
+
+impl From<sqlx::Error> for DbError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => return DbError::NotFound,
+            sqlx::Error::PoolTimedOut => return DbError::PoolExhausted,
+            _ => {}
         }
+        let Some(db) = e.as_database_error() else {
+            return DbError::Other(e);
+        };
+        let constraint = db.constraint().map(str::to_string);
+        match db.code().as_deref() {
+            Some("23505") => DbError::UniqueViolation { constraint },
+            Some("23503") => DbError::ForeignKeyViolation { constraint },
+            Some("23514" | "23502" | "23P01") => DbError::ConstraintViolation { constraint },
+            Some("40001" | "40P01") => DbError::SerializationFailure,
+            Some("57014") => DbError::StatementTimeout,
+            _ => DbError::Other(e),
+        }
+    }
+}
+
+fn constraint_suffix(constraint: &Option<String>) -> String {
+    constraint.as_deref().map(|c| format!(" ({c})")).unwrap_or_default()
+}
+
+impl From<DbError> for ApiError {
+    fn from(e: DbError) -> Self {
+        match e {
+            DbError::UniqueViolation { constraint } => ApiError::Conflict(
+                "CONFLICT",
+                format!("record already exists{}", constraint_suffix(&constraint)),
+            ),
+            DbError::ForeignKeyViolation { constraint } => ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                format!(
+                    "referenced record does not exist or is still in use{}",
+                    constraint_suffix(&constraint)
+                ),
+            ),
+            DbError::ConstraintViolation { constraint } => ApiError::Conflict(
+                "CONSTRAINT_VIOLATION",
+                format!("change violates a data constraint{}", constraint_suffix(&constraint)),
+            ),
+            DbError::SerializationFailure => ApiError::Conflict(
+                "RETRY",
+                "concurrent update, please retry".into(),
+            ),
+            DbError::PoolExhausted => {
+                tracing::warn!(
+                    route = %request_context::current_route(),
+                    "db pool exhausted: no connection within the acquire timeout (see DB_MAX_CONNECTIONS)"
+                );
+                ApiError::Internal("db pool exhausted".into())
+            }
+            DbError::StatementTimeout => {
+                // the statement itself is in the slow-query line logged when it ended
+                tracing::warn!(
+                    route = %request_context::current_route(),
+                    "db statement cancelled (see DB_STATEMENT_TIMEOUT_SECS)"
+                );
+                ApiError::Internal("db statement timeout".into())
+            }
+            DbError::NotFound => ApiError::NotFound("NOT_FOUND", "record not found".into()),
+            DbError::Other(e) => ApiError::Internal(format!("db error: {e}")),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        DbError::from(e).into()
+    }
+}
+
+impl ApiError {
+    /// `map_err` for writes that report a domain code (e.g. "TASK_CREATE_FAILED"):
+    /// constraint violations keep that code with a redacted message, anything else
+    /// maps like `From<sqlx::Error>`.
+    pub fn write_failed(code: &'static str) -> impl Fn(sqlx::Error) -> ApiError {
+        move |e| match ApiError::from(e) {
+            ApiError::Conflict("CONFLICT" | "CONSTRAINT_VIOLATION", msg)
+            | ApiError::BadRequest("VALIDATION_ERROR", msg) => ApiError::BadRequest(code, msg),
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_database_errors_map_to_not_found_or_internal() {
+        assert!(matches!(ApiError::from(sqlx::Error::RowNotFound), ApiError::NotFound("NOT_FOUND", _)));
+        assert!(matches!(ApiError::from(sqlx::Error::PoolTimedOut), ApiError::Internal(_)));
+        assert!(!DbError::is_transient(&sqlx::Error::PoolTimedOut));
+    }
+
+    #[test]
+    fn error_objects_keep_details_and_leave_out_internal_errors() {
+        let e = ApiError::ConflictWithDetails("DUPLICATE_PATIENT", "dup".into(), serde_json::json!({ "n": 1 }));
+        let obj = e.to_error_object().unwrap();
+        assert_eq!((obj.code.as_str(), obj.message.as_str()), ("DUPLICATE_PATIENT", "dup"));
+        assert_eq!(obj.details, Some(serde_json::json!({ "n": 1 })));
+        assert!(ApiError::Internal("db down".into()).to_error_object().is_none());
     }
 }
@@ -0,0 +1,130 @@
+// src/extract.rs
+//
+// Drop-in replacement for `axum::Json` (extractor + response).
+// Axum's own rejections are plain-text 400/415/422/413 bodies; this maps them to
+// the standard `{ "error": { code, message } }` envelope so clients only have one
+// error shape to handle.
+
+use axum::{
+    extract::{
+        FromRequest, Request,
+        rejection::{BytesRejection, JsonRejection},
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::error::ApiError;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    axum::Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Json::<T>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(Json(value)),
+            Err(rejection) => Err(rejection.into()),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            // wrong field type / missing field: body_text() names the field
+            JsonRejection::JsonDataError(e) => ApiError::BadRequest("VALIDATION_ERROR", e.body_text()),
+            JsonRejection::JsonSyntaxError(e) => ApiError::BadRequest("INVALID_JSON", e.body_text()),
+            JsonRejection::MissingJsonContentType(_) => ApiError::UnsupportedMediaType(
+                "UNSUPPORTED_MEDIA_TYPE",
+                "Expected request with `Content-Type: application/json`".into(),
+            ),
+            JsonRejection::BytesRejection(e) => e.into(),
+            other => ApiError::BadRequest("INVALID_BODY", other.body_text()),
+        }
+    }
+}
+
+/// For raw-body handlers: take `Result<Bytes, BytesRejection>` and `?` it.
+impl From<BytesRejection> for ApiError {
+    fn from(rejection: BytesRejection) -> Self {
+        // DefaultBodyLimit exceeded
+        if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            ApiError::PayloadTooLarge("PAYLOAD_TOO_LARGE", "Request body is too large".into())
+        } else {
+            ApiError::BadRequest("INVALID_BODY", rejection.body_text())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, body::Body, extract::DefaultBodyLimit, routing::post};
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Payload {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    async fn handler(Json(_): Json<Payload>) -> Json<bool> {
+        Json(true)
+    }
+
+    async fn call(content_type: &str, body: &'static str) -> (StatusCode, serde_json::Value) {
+        let app = Router::new()
+            .route("/", post(handler))
+            .layer(DefaultBodyLimit::max(32));
+        let res = app
+            .oneshot(
+                Request::post("/")
+                    .header("content-type", content_type)
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = res.status();
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn rejections_use_error_envelope() {
+        let (status, body) = call("application/json", r#"{"name":"a"}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!(true));
+
+        let (status, body) = call("application/json", r#"{"name":1}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+
+        let (status, body) = call("application/json", r#"{"name":"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "INVALID_JSON");
+
+        let (status, body) = call("text/plain", r#"{"name":"a"}"#).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["error"]["code"], "UNSUPPORTED_MEDIA_TYPE");
+
+        let (status, body) = call("application/json", r#"{"name":"this is longer than the limit"}"#).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["error"]["code"], "PAYLOAD_TOO_LARGE");
+    }
+}
@@ -0,0 +1,356 @@
+// src/hq_grpc/mod.rs
+//
+// gRPC server for the DCMS HQ sync (feature `hq-grpc`, started when HQ_GRPC_ADDR is
+// set). Contract: proto/hq_sync.proto. Three server-streaming calls:
+// - StreamPatients / StreamAppointments: keyset pages through the same service
+//   functions as the HTTP routes (services::patients, services::appointments)
+// - ChangeFeed: patient / appointment writes from sync_outbox (crate::sync_outbox),
+//   optionally followed (polled) until HQ disconnects
+// Callers authenticate with a DCMS HQ session (POST /auth/hq/login: admin,
+// session_type=3) in the `authorization` metadata; other sessions are refused.
+// Invoices are not streamed: there is no invoice table yet (README "Not built yet").
+//
+// The service glue is written out here instead of generated by tonic-build (the
+// build has no protoc); pb.rs holds the messages.
+
+pub mod pb;
+
+use std::{convert::Infallible, future::Future, net::SocketAddr, time::Duration};
+
+use futures_util::{Stream, TryStreamExt, stream};
+use tonic::{
+    Status,
+    codegen::{Body, BoxFuture, BoxStream, Context, Poll, Service, StdError, http},
+    metadata::MetadataMap,
+    server::{Grpc, NamedService},
+};
+use tonic_prost::ProstCodec;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    error::ApiError,
+    middleware::auth_context::{self, AuthContext},
+    models::{AppState, Role},
+    routes::auth_routes::SESSION_TYPE_DCMSHQ,
+    services::{
+        appointments::{self, AppointmentBlockDto},
+        patients::{self, PatientRow},
+    },
+    sync_outbox::{self, ChangeCursor, ChangeRow},
+};
+
+const SERVICE_NAME: &str = "dcms.hq.v1.HqSync";
+/// rows per query behind every stream
+const PAGE_SIZE: i64 = 500;
+/// ids per StreamPatients / StreamAppointments request
+const MAX_STREAM_IDS: usize = 1000;
+/// ChangeFeed with `follow`: how often to look for new changes
+const FOLLOW_POLL_SECS: u64 = 5;
+
+pub async fn serve(state: AppState, addr: SocketAddr) -> anyhow::Result<()> {
+    tracing::info!("HQ sync gRPC listening on {addr}");
+    tonic::transport::Server::builder()
+        .add_service(HqSyncServer { state })
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+#[derive(Clone)]
+struct HqSyncServer {
+    state: AppState,
+}
+
+impl NamedService for HqSyncServer {
+    const NAME: &'static str = SERVICE_NAME;
+}
+
+impl<B> Service<http::Request<B>> for HqSyncServer
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let state = self.state.clone();
+        match req.uri().path() {
+            "/dcms.hq.v1.HqSync/StreamPatients" => server_streaming(req, Rpc { state, handler: stream_patients }),
+            "/dcms.hq.v1.HqSync/StreamAppointments" => {
+                server_streaming(req, Rpc { state, handler: stream_appointments })
+            }
+            "/dcms.hq.v1.HqSync/ChangeFeed" => server_streaming(req, Rpc { state, handler: change_feed }),
+            _ => Box::pin(async {
+                let mut response = http::Response::new(tonic::body::Body::default());
+                let headers = response.headers_mut();
+                headers.insert(Status::GRPC_STATUS, (tonic::Code::Unimplemented as i32).into());
+                headers.insert(http::header::CONTENT_TYPE, tonic::metadata::GRPC_CONTENT_TYPE);
+                Ok(response)
+            }),
+        }
+    }
+}
+
+/// One server-streaming method: `handler(state, request)` -> stream of replies.
+struct Rpc<F> {
+    state: AppState,
+    handler: F,
+}
+
+impl<Req, Resp, F, Fut> Service<tonic::Request<Req>> for Rpc<F>
+where
+    F: Fn(AppState, tonic::Request<Req>) -> Fut,
+    Fut: Future<Output = Result<BoxStream<Resp>, Status>> + Send + 'static,
+{
+    type Response = tonic::Response<BoxStream<Resp>>;
+    type Error = Status;
+    type Future = BoxFuture<Self::Response, Status>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: tonic::Request<Req>) -> Self::Future {
+        let fut = (self.handler)(self.state.clone(), req);
+        Box::pin(async move { fut.await.map(tonic::Response::new) })
+    }
+}
+
+fn server_streaming<B, Req, Resp, F, Fut>(
+    req: http::Request<B>,
+    rpc: Rpc<F>,
+) -> BoxFuture<http::Response<tonic::body::Body>, Infallible>
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+    Req: prost::Message + Default + Send + 'static,
+    Resp: prost::Message + Send + 'static,
+    F: Fn(AppState, tonic::Request<Req>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<BoxStream<Resp>, Status>> + Send + 'static,
+{
+    Box::pin(async move {
+        let mut grpc = Grpc::new(ProstCodec::<Resp, Req>::default());
+        Ok(grpc.server_streaming(rpc, req).await)
+    })
+}
+
+fn to_status(e: ApiError) -> Status {
+    match e {
+        ApiError::Unauthorized(_, msg) => Status::unauthenticated(msg),
+        ApiError::Forbidden(_, msg) => Status::permission_denied(msg),
+        ApiError::BadRequest(_, msg) => Status::invalid_argument(msg),
+        ApiError::NotFound(_, msg) => Status::not_found(msg),
+        ApiError::Conflict(_, msg) | ApiError::ConflictWithDetails(_, msg, _) => Status::failed_precondition(msg),
+        ApiError::PayloadTooLarge(_, msg) => Status::resource_exhausted(msg),
+        ApiError::UnsupportedMediaType(_, msg) => Status::invalid_argument(msg),
+        ApiError::Internal(msg) => {
+            tracing::error!("hq grpc: {msg}");
+            Status::internal("internal error")
+        }
+    }
+}
+
+fn bearer(metadata: &MetadataMap) -> Result<String, Status> {
+    metadata
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|t| t.trim().to_string())
+        .ok_or_else(|| Status::unauthenticated("missing bearer token"))
+}
+
+/// Token of a live DCMS HQ session; anything else is refused.
+async fn authorize(state: &AppState, token: &str) -> Result<AuthContext, Status> {
+    let auth = auth_context::authenticate(state, token).await.map_err(to_status)?;
+
+    let session_type: i16 = sqlx::query_scalar("SELECT session_type FROM session_token WHERE session_token_id = $1")
+        .bind(auth.session_token_id)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| to_status(e.into()))?;
+    if session_type != SESSION_TYPE_DCMSHQ || auth.role != Role::Admin || auth.is_impersonating() {
+        return Err(Status::permission_denied("DCMS HQ session required (POST /auth/hq/login)"));
+    }
+    Ok(auth)
+}
+
+/// Audit entry per stream opened (best-effort: the read goes ahead either way).
+async fn record_start(state: &AppState, auth: &AuthContext, action: &str, details: serde_json::Value) {
+    if let Err(e) = audit::record(&state.db, auth, action, "hq_sync", None, details).await {
+        tracing::warn!("hq grpc: audit {action} failed: {e}");
+    }
+}
+
+/// `ids` of a StreamRequest: None = all.
+fn parse_ids(raw: &[String]) -> Result<Option<Vec<Uuid>>, Status> {
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    if raw.len() > MAX_STREAM_IDS {
+        return Err(Status::invalid_argument(format!("at most {MAX_STREAM_IDS} ids per request")));
+    }
+    raw.iter()
+        .map(|s| Uuid::parse_str(s.trim()).map_err(|_| Status::invalid_argument(format!("ids: {s} is not a UUID"))))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Rows of `fetch(after)` pages, PAGE_SIZE at a time, until a short page.
+fn keyset_pages<T, F, Fut>(fetch: F, key: fn(&T) -> Uuid) -> impl Stream<Item = Result<T, ApiError>>
+where
+    F: Fn(Option<Uuid>) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, ApiError>>,
+{
+    // Some(after) = next page to read, None = done
+    stream::try_unfold((fetch, Some(None)), move |(fetch, next)| async move {
+        let Some(after) = next else { return Ok::<_, ApiError>(None) };
+        let page = fetch(after).await?;
+        let next = (page.len() as i64 == PAGE_SIZE).then(|| page.last().map(key));
+        Ok(Some((stream::iter(page.into_iter().map(Ok)), (fetch, next))))
+    })
+    .try_flatten()
+}
+
+async fn stream_patients(
+    state: AppState,
+    req: tonic::Request<pb::StreamRequest>,
+) -> Result<BoxStream<pb::Patient>, Status> {
+    let auth = authorize(&state, &bearer(req.metadata())?).await?;
+    let ids = parse_ids(&req.get_ref().ids)?;
+    record_start(&state, &auth, "hq_sync.patients", serde_json::json!({ "ids": ids.as_ref().map(Vec::len) })).await;
+
+    let rows = keyset_pages(
+        move |after| {
+            let (state, ids) = (state.clone(), ids.clone());
+            async move { patients::page_after(&*state.repos.patients, after, ids.as_deref(), PAGE_SIZE).await }
+        },
+        |p: &PatientRow| p.patient_id,
+    );
+    Ok(Box::pin(rows.map_ok(pb::Patient::from).map_err(to_status)))
+}
+
+async fn stream_appointments(
+    state: AppState,
+    req: tonic::Request<pb::StreamRequest>,
+) -> Result<BoxStream<pb::Appointment>, Status> {
+    let auth = authorize(&state, &bearer(req.metadata())?).await?;
+    let ids = parse_ids(&req.get_ref().ids)?;
+    record_start(&state, &auth, "hq_sync.appointments", serde_json::json!({ "ids": ids.as_ref().map(Vec::len) }))
+        .await;
+
+    let rows = keyset_pages(
+        move |after| {
+            let (state, ids) = (state.clone(), ids.clone());
+            async move { appointments::blocks_after(&*state.repos.appointments, after, ids.as_deref(), PAGE_SIZE).await }
+        },
+        |b: &AppointmentBlockDto| b.appointment_id,
+    );
+    Ok(Box::pin(rows.map_ok(pb::Appointment::from).map_err(to_status)))
+}
+
+async fn change_feed(
+    state: AppState,
+    req: tonic::Request<pb::ChangeFeedRequest>,
+) -> Result<BoxStream<pb::Change>, Status> {
+    let token = bearer(req.metadata())?;
+    let auth = authorize(&state, &token).await?;
+    let pb::ChangeFeedRequest { after_cursor, follow } = req.into_inner();
+    let after = match after_cursor.trim() {
+        "" => None,
+        token => Some(ChangeCursor::decode(token).map_err(to_status)?),
+    };
+    if let Some(cursor) = after {
+        // FAILED_PRECONDITION: resync with StreamPatients / StreamAppointments
+        sync_outbox::ensure_cursor_kept(&state.db, cursor).await.map_err(to_status)?;
+    }
+    record_start(&state, &auth, "hq_sync.changes", serde_json::json!({ "follow": follow })).await;
+
+    let rows = stream::try_unfold((state, token, after), move |(state, token, mut after)| async move {
+        loop {
+            let page = sync_outbox::after(&state.db, after, PAGE_SIZE).await.map_err(to_status)?;
+            if let Some(last) = page.last() {
+                after = Some(last.cursor());
+                let changes = stream::iter(page.into_iter().map(Ok));
+                return Ok::<_, Status>(Some((changes, (state, token, after))));
+            }
+            if !follow {
+                return Ok(None);
+            }
+            tokio::time::sleep(Duration::from_secs(FOLLOW_POLL_SECS)).await;
+            // a followed feed can stay open for days: end it with the session
+            authorize(&state, &token).await?;
+        }
+    })
+    .try_flatten();
+    Ok(Box::pin(rows.map_ok(pb::Change::from)))
+}
+
+/* ============================================================
+   Rows -> messages
+   ============================================================ */
+
+fn rfc3339(ts: chrono::DateTime<chrono::Utc>) -> String {
+    ts.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+impl From<PatientRow> for pb::Patient {
+    fn from(p: PatientRow) -> Self {
+        Self {
+            patient_id: p.patient_id.to_string(),
+            register_number: p.register_number,
+            first_name: p.first_name,
+            last_name: p.last_name,
+            birthday: p.birthday.map(|d| d.to_string()),
+            gender: p.gender.as_str().to_string(),
+            status: p.status.into(),
+            created_at: rfc3339(p.created_at),
+            last_seen_at: p.last_seen_at.map(rfc3339),
+            primary_phone: p.primary_phone,
+            referral_source_id: p.referral_source_id.map(|id| id.to_string()),
+        }
+    }
+}
+
+impl From<AppointmentBlockDto> for pb::Appointment {
+    fn from(b: AppointmentBlockDto) -> Self {
+        Self {
+            appointment_id: b.appointment_id.to_string(),
+            patient_id: b.patient.id.to_string(),
+            doctor_employee_id: b.doctor.id.to_string(),
+            location_id: b.location_id.map(|id| id.to_string()),
+            start_at: rfc3339(b.start_at),
+            end_at: rfc3339(b.end_at),
+            status: b.status.as_str().to_string(),
+            source: b.source,
+            confirmed_at: b.confirmed_at.map(rfc3339),
+            planned_items: b
+                .planned_items
+                .into_iter()
+                .map(|i| pb::PlanItem {
+                    service_id: i.service_id.to_string(),
+                    display_name: i.display_name,
+                    qty: i.qty,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<ChangeRow> for pb::Change {
+    fn from(c: ChangeRow) -> Self {
+        Self {
+            cursor: c.cursor().encode(),
+            entity: c.entity,
+            entity_id: c.entity_id.to_string(),
+            op: c.op,
+            changed_at: rfc3339(c.changed_at),
+        }
+    }
+}
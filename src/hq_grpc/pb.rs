@@ -0,0 +1,94 @@
+// src/hq_grpc/pb.rs
+//
+// Messages of proto/hq_sync.proto (package dcms.hq.v1), derived by hand: the build
+// has no protoc. Same tags as the .proto; change both together.
+
+use prost::Message;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct StreamRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub ids: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Patient {
+    #[prost(string, tag = "1")]
+    pub patient_id: String,
+    #[prost(string, tag = "2")]
+    pub register_number: String,
+    #[prost(string, tag = "3")]
+    pub first_name: String,
+    #[prost(string, tag = "4")]
+    pub last_name: String,
+    #[prost(string, optional, tag = "5")]
+    pub birthday: Option<String>,
+    #[prost(string, tag = "6")]
+    pub gender: String,
+    #[prost(int32, tag = "7")]
+    pub status: i32,
+    #[prost(string, tag = "8")]
+    pub created_at: String,
+    #[prost(string, optional, tag = "9")]
+    pub last_seen_at: Option<String>,
+    #[prost(string, optional, tag = "10")]
+    pub primary_phone: Option<String>,
+    #[prost(string, optional, tag = "11")]
+    pub referral_source_id: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Appointment {
+    #[prost(string, tag = "1")]
+    pub appointment_id: String,
+    #[prost(string, tag = "2")]
+    pub patient_id: String,
+    #[prost(string, tag = "3")]
+    pub doctor_employee_id: String,
+    #[prost(string, optional, tag = "4")]
+    pub location_id: Option<String>,
+    #[prost(string, tag = "5")]
+    pub start_at: String,
+    #[prost(string, tag = "6")]
+    pub end_at: String,
+    #[prost(string, tag = "7")]
+    pub status: String,
+    #[prost(string, tag = "8")]
+    pub source: String,
+    #[prost(string, optional, tag = "9")]
+    pub confirmed_at: Option<String>,
+    #[prost(message, repeated, tag = "10")]
+    pub planned_items: Vec<PlanItem>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PlanItem {
+    #[prost(string, tag = "1")]
+    pub service_id: String,
+    #[prost(string, tag = "2")]
+    pub display_name: String,
+    #[prost(int32, tag = "3")]
+    pub qty: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ChangeFeedRequest {
+    #[prost(string, tag = "1")]
+    pub after_cursor: String,
+    #[prost(bool, tag = "2")]
+    pub follow: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Change {
+    #[prost(string, tag = "1")]
+    pub cursor: String,
+    #[prost(string, tag = "2")]
+    pub entity: String,
+    #[prost(string, tag = "3")]
+    pub entity_id: String,
+    #[prost(string, tag = "4")]
+    pub op: String,
+    #[prost(string, tag = "5")]
+    pub changed_at: String,
+}
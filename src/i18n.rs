@@ -0,0 +1,155 @@
+// src/i18n.rs
+//
+// Localized error messages, keyed by the stable error code (the English
+// `message` stays as is; `message_localized` is added next to it).
+// Language per request (middleware::request_context): the user's
+// `preferred_language`, else `Accept-Language`, else English.
+// Codes without a catalog entry get no `message_localized`; `*_FAILED` write
+// codes share one generic entry.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[default]
+    En,
+    Mn,
+}
+
+pub const SUPPORTED: [&str; 2] = ["en", "mn"];
+
+impl Lang {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Mn => "mn",
+        }
+    }
+
+    /// "mn", "mn-MN", "EN_us" ...; None for anything unsupported
+    pub fn parse(tag: &str) -> Option<Lang> {
+        let primary = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match primary.as_str() {
+            "en" => Some(Lang::En),
+            "mn" => Some(Lang::Mn),
+            _ => None,
+        }
+    }
+
+    /// Best supported language of an `Accept-Language` header (q-values respected).
+    pub fn from_accept_language(header: &str) -> Option<Lang> {
+        let mut best: Option<(Lang, f32)> = None;
+        for part in header.split(',') {
+            let mut it = part.split(';');
+            let Some(lang) = it.next().and_then(Lang::parse) else {
+                continue;
+            };
+            let q = it
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if q > 0.0 && best.is_none_or(|(_, bq)| q > bq) {
+                best = Some((lang, q));
+            }
+        }
+        best.map(|(lang, _)| lang)
+    }
+}
+
+/// (code, English, Mongolian)
+const CATALOG: &[(&str, &str, &str)] = &[
+    ("VALIDATION_ERROR", "Invalid request data", "Хүсэлтийн өгөгдөл буруу байна"),
+    ("NOT_FOUND", "Not found", "Олдсонгүй"),
+    ("FORBIDDEN", "You don't have permission to do this", "Танд энэ үйлдлийг хийх эрх байхгүй"),
+    ("INVALID_CREDENTIALS", "Username or password is incorrect", "Нэвтрэх нэр эсвэл нууц үг буруу байна"),
+    ("SESSION_EXPIRED", "Session expired, please log in again", "Нэвтрэлтийн хугацаа дууссан, дахин нэвтэрнэ үү"),
+    ("CONFLICT", "Record already exists", "Бичлэг аль хэдийн бүртгэгдсэн байна"),
+    ("CONSTRAINT_VIOLATION", "The change violates a data constraint", "Өөрчлөлт өгөгдлийн хязгаарлалтыг зөрчиж байна"),
+    ("RETRY", "Concurrent update, please retry", "Зэрэг өөрчлөлт гарлаа, дахин оролдоно уу"),
+    ("INTERNAL", "Internal server error", "Серверийн дотоод алдаа"),
+    ("PAYLOAD_TOO_LARGE", "Request is too large", "Хүсэлтийн хэмжээ хэт том байна"),
+    ("UNSUPPORTED_MEDIA_TYPE", "Unsupported content type", "Дэмжигдээгүй контентын төрөл"),
+    ("INVALID_JSON", "Malformed JSON", "JSON формат буруу байна"),
+    ("INVALID_BODY", "Invalid request body", "Хүсэлтийн агуулга буруу байна"),
+    ("INVALID_IMAGE", "Invalid image", "Зураг буруу байна"),
+    ("USERNAME_TAKEN", "Username is already taken", "Энэ нэвтрэх нэр бүртгэлтэй байна"),
+    ("LAST_ADMIN", "The last active admin cannot be demoted or disabled", "Сүүлийн идэвхтэй админы эрхийг бууруулах эсвэл идэвхгүй болгох боломжгүй"),
+    ("NO_EMPLOYEE_PROFILE", "This account has no employee profile", "Энэ хэрэглэгчид ажилтны профайл байхгүй байна"),
+    ("EMPLOYEE_PROFILE_EXISTS", "This user already has an employee profile", "Энэ хэрэглэгч ажилтны профайлтай аль хэдийн холбогдсон байна"),
+    ("EMPLOYEE_NUMBER_TAKEN", "This employee number is already in use", "Энэ ажилтны дугаар ашиглагдаж байна"),
+    ("EMPLOYEE_ALREADY_LINKED", "This employee is linked to another user", "Энэ ажилтан өөр хэрэглэгчтэй холбогдсон байна"),
+    ("APPOINTMENT_OVERLAP", "The doctor already has an appointment at this time", "Эмчид энэ цагт өөр цаг захиалга байна"),
+    ("INVALID_STATUS_TRANSITION", "This status change is not allowed", "Төлөвийг ингэж өөрчлөх боломжгүй"),
+    ("CLINIC_CLOSED", "The clinic is closed at this time", "Эмнэлэг энэ хугацаанд амарна"),
+    ("DOCTOR_TIME_OFF", "The doctor is off at this time", "Эмч энэ хугацаанд чөлөөтэй байна"),
+    ("TIME_OFF_OVERLAP", "This overlaps another time off request", "Өөр чөлөөний хүсэлттэй давхцаж байна"),
+    ("SHIFT_OVERLAP", "This overlaps another shift of the employee", "Ажилтны өөр ээлжтэй давхцаж байна"),
+    ("SHIFT_TIME_OFF", "The employee is on time off then", "Ажилтан энэ хугацаанд чөлөөтэй байна"),
+    ("ASSISTANT_NOT_ON_SHIFT", "The assistant is not on shift at this time", "Туслах энэ цагт ээлжинд гараагүй байна"),
+    ("ASSISTANT_DOUBLE_BOOKED", "The assistant is on another appointment at this time", "Туслах энэ цагт өөр үзлэгт орсон байна"),
+    ("SERVICE_NOT_OFFERED", "The doctor doesn't perform this service", "Эмч энэ үйлчилгээг үзүүлдэггүй"),
+    ("HOLIDAY_EXISTS", "A closure already exists for this date", "Энэ өдөр амралтын өдрөөр бүртгэгдсэн байна"),
+    ("PROCEDURE_TEMPLATE_EXISTS", "A procedure template with this name already exists", "Ийм нэртэй эмчилгээний загвар бүртгэлтэй байна"),
+    ("LOCATION_EXISTS", "A location with this name already exists", "Ийм нэртэй салбар бүртгэлтэй байна"),
+    ("DUPLICATE_PATIENT", "This patient may already be registered", "Энэ өвчтөн бүртгэлтэй байж магадгүй"),
+    ("REGISTER_NUMBER_TAKEN", "This register number is already in use", "Энэ бүртгэлийн дугаар ашиглагдаж байна"),
+    ("HOUSEHOLD_MEMBER_EXISTS", "The patient already belongs to another household", "Өвчтөн өөр өрхөд бүртгэлтэй байна"),
+    ("CONTACT_OPTED_OUT", "The patient opted out of these messages", "Өвчтөн эдгээр мессежээс татгалзсан байна"),
+    ("PHONE_NUMBER_IN_USE", "This phone number belongs to another patient", "Энэ утасны дугаар өөр өвчтөнд бүртгэлтэй байна"),
+    ("PHONE_NUMBER_EXISTS", "The patient already has this phone number", "Өвчтөнд энэ утасны дугаар бүртгэлтэй байна"),
+    ("PHONE_NUMBER_RELEASED", "This phone number was moved to another patient", "Энэ утасны дугаарыг өөр өвчтөнд шилжүүлсэн байна"),
+    ("INTAKE_NOT_CONFIGURED", "Intake form links are not set up", "Урьдчилсан асуумжийн холбоос тохируулагдаагүй байна"),
+    ("INTAKE_LINK_EXPIRED", "This link has expired, please ask the clinic for a new one", "Холбоосын хугацаа дууссан, эмнэлгээс шинэ холбоос авна уу"),
+    ("INTAKE_LINK_USED", "This link is no longer valid", "Энэ холбоос хүчингүй болсон байна"),
+    ("CLINICAL_ALERTS", "The patient has clinical alerts, please review them before booking", "Үйлчлүүлэгчид эмнэлзүйн анхааруулга байна, цаг захиалахын өмнө танилцана уу"),
+    ("FEEDBACK_LINK_EXPIRED", "This feedback link has expired", "Санал асуулгын холбоосын хугацаа дууссан байна"),
+    ("FEEDBACK_LINK_USED", "Thank you, your feedback has already been received", "Баярлалаа, таны санал аль хэдийн бүртгэгдсэн байна"),
+    ("KIOSK_NO_APPOINTMENT", "No appointment found for today, please see the reception", "Өнөөдөр таны цаг захиалга олдсонгүй, ресепшнд хандана уу"),
+    ("SYNC_CURSOR_EXPIRED", "Offline data is too old to update, a full reload is needed", "Офлайн өгөгдөл хэт хуучирсан тул бүрэн дахин ачаалах шаардлагатай"),
+    ("REFERRAL_SOURCE_EXISTS", "A referral source with this name already exists", "Ийм нэртэй эх сурвалж бүртгэлтэй байна"),
+    ("ONBOARDING_INCOMPLETE", "Finish the first-visit checklist before seating the patient", "Үйлчлүүлэгчийг суулгахын өмнө анхны үзлэгийн жагсаалтыг гүйцээнэ үү"),
+    ("ONBOARDING_ITEM_EXISTS", "A checklist item with this code already exists", "Ийм кодтой жагсаалтын зүйл бүртгэлтэй байна"),
+    ("DAY_ALREADY_CLOSED", "This day has already been signed off", "Энэ өдрийг аль хэдийн хаасан байна"),
+    ("EXPORT_NOT_CONFIGURED", "Data exports are not set up", "Өгөгдлийн экспорт тохируулагдаагүй байна"),
+    ("EXPORT_RUNNING", "An export is already running", "Экспорт аль хэдийн явагдаж байна"),
+];
+
+/// shared by every `*_FAILED` write code
+const WRITE_FAILED: (&str, &str) = ("The change could not be saved", "Өөрчлөлтийг хадгалж чадсангүй");
+
+/// Message for `code` in `lang`, None when the catalog doesn't know the code.
+pub fn message(code: &str, lang: Lang) -> Option<&'static str> {
+    let (en, mn) = CATALOG
+        .iter()
+        .find(|(c, _, _)| *c == code)
+        .map(|(_, en, mn)| (*en, *mn))
+        .or_else(|| code.ends_with("_FAILED").then_some(WRITE_FAILED))?;
+    Some(match lang {
+        Lang::En => en,
+        Lang::Mn => mn,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_best_supported_accept_language() {
+        assert_eq!(Lang::from_accept_language("mn-MN,mn;q=0.9,en;q=0.8"), Some(Lang::Mn));
+        assert_eq!(Lang::from_accept_language("ru, en;q=0.5, mn;q=0.7"), Some(Lang::Mn));
+        assert_eq!(Lang::from_accept_language("de-DE, fr"), None);
+        assert_eq!(Lang::from_accept_language("mn;q=0, en"), Some(Lang::En));
+    }
+
+    #[test]
+    fn catalog_covers_both_languages() {
+        for (code, en, mn) in CATALOG {
+            assert!(!en.is_empty() && !mn.is_empty(), "{code}");
+        }
+        assert_eq!(message("NOT_FOUND", Lang::Mn), Some("Олдсонгүй"));
+        assert_eq!(message("TASK_CREATE_FAILED", Lang::En), Some(WRITE_FAILED.0));
+        assert_eq!(message("SOMETHING_ELSE", Lang::En), None);
+    }
+}
@@ -0,0 +1,341 @@
+// src/jobs/appointment_reminders.rs
+//
+// Automatic appointment reminders, driven by clinic_settings.reminder_policy:
+// - every tick, upcoming appointments starting within `hours_before` that have
+//   no reminder_sent_at get an outbound sms row (rendered from `template`, or
+//   `templates[lang]` for patients with a preferred_language)
+// - only inside `send_window` (HH:MM, clinic time) and at most `max_per_day`
+//   per clinic-local day
+// - patients in their quiet hours (contact_prefs) are skipped for now and picked
+//   up by a later tick, as long as the appointment hasn't started
+// - reminder_sent_at is stamped so each appointment is reminded once
+//   (staff can still use POST /appointments/{id}/reminder_sent manually)
+
+use std::{collections::BTreeMap, time::Duration};
+
+use chrono::{DateTime, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::{
+    clinic_time,
+    consent::{self, ContactPurpose},
+    contact_prefs, db,
+    i18n::Lang,
+    models::AppState,
+    pii::PiiString,
+};
+
+const JOB_INTERVAL_SECS: u64 = 60;
+const JOB_BATCH_SIZE: i64 = 50;
+
+const REMINDER_SUBJECT: &str = "Appointment reminder";
+const REMINDER_NOTE: &str = "auto:reminder";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SendWindow {
+    pub start: String, // "HH:MM"
+    pub end: String,   // "HH:MM"
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReminderPolicy {
+    pub enabled: bool,
+    pub hours_before: i32,
+    pub template: String,
+    /// "en" / "mn" variants of `template`; optional
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, String>,
+    pub sms_enabled: bool,
+    pub send_window: SendWindow,
+    pub max_per_day: i32,
+}
+
+fn parse_hhmm(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+}
+
+impl ReminderPolicy {
+    /// Parses and validates a policy as stored in clinic_settings.reminder_policy.
+    pub fn from_json(v: &JsonValue) -> Result<Self, String> {
+        let p: Self = serde_json::from_value(v.clone()).map_err(|e| format!("reminder_policy: {e}"))?;
+
+        if !(1..=168).contains(&p.hours_before) {
+            return Err("reminder_policy.hours_before must be 1..168".into());
+        }
+        if p.template.trim().is_empty() || p.template.len() > 640 {
+            return Err("reminder_policy.template must be 1..640 chars".into());
+        }
+        for (lang, template) in &p.templates {
+            if Lang::parse(lang).map(Lang::as_str) != Some(lang.as_str()) {
+                return Err(format!("reminder_policy.templates: unsupported language {lang:?}"));
+            }
+            if template.trim().is_empty() || template.len() > 640 {
+                return Err(format!("reminder_policy.templates.{lang} must be 1..640 chars"));
+            }
+        }
+        if !(0..=10_000).contains(&p.max_per_day) {
+            return Err("reminder_policy.max_per_day must be 0..10000".into());
+        }
+        let (Some(start), Some(end)) = (parse_hhmm(&p.send_window.start), parse_hhmm(&p.send_window.end))
+        else {
+            return Err("reminder_policy.send_window start/end must be HH:MM".into());
+        };
+        if start >= end {
+            return Err("reminder_policy.send_window start must be before end".into());
+        }
+        Ok(p)
+    }
+
+    /// The template for a patient's preferred_language; `template` when there is
+    /// no variant for it.
+    pub fn template_for(&self, lang: Option<Lang>) -> &str {
+        lang.and_then(|l| self.templates.get(l.as_str())).unwrap_or(&self.template)
+    }
+
+    /// `local` is the clinic-local time of day.
+    pub fn in_send_window(&self, local: NaiveTime) -> bool {
+        match (parse_hhmm(&self.send_window.start), parse_hhmm(&self.send_window.end)) {
+            (Some(start), Some(end)) => local >= start && local < end,
+            _ => false,
+        }
+    }
+}
+
+/// Fills the reminder placeholders ({date}/{time} in `start_at`'s timezone);
+/// unknown placeholders are left as-is.
+pub fn render_reminder<Tz: TimeZone>(
+    template: &str,
+    first_name: &str,
+    last_name: &str,
+    register_number: &str,
+    start_at: DateTime<Tz>,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    template
+        .replace("{name}", &format!("{first_name} {last_name}"))
+        .replace("{first_name}", first_name)
+        .replace("{last_name}", last_name)
+        .replace("{register_number}", register_number)
+        .replace("{date}", &start_at.format("%Y-%m-%d").to_string())
+        .replace("{time}", &start_at.format("%H:%M").to_string())
+}
+
+/* ============================================================
+   Job
+   ============================================================ */
+
+pub async fn run(state: AppState) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        match db::retry_transient(|| send_due_reminders(&state)).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("appointment reminders: sent {n} reminder(s)"),
+            Err(e) => tracing::warn!("appointment reminder job failed: {e}"),
+        }
+    }
+}
+
+/// Sends reminders for appointments that are due according to the clinic policy.
+pub async fn send_due_reminders(state: &AppState) -> Result<usize, sqlx::Error> {
+    let raw: Option<JsonValue> =
+        sqlx::query_scalar("SELECT reminder_policy FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(&state.db)
+            .await?;
+
+    let Some(raw) = raw else {
+        return Ok(0);
+    };
+    let policy = match ReminderPolicy::from_json(&raw) {
+        Ok(p) => p,
+        Err(e) => {
+            // policy is validated on write; a broken row just disables the job
+            tracing::warn!("appointment reminders disabled: {e}");
+            return Ok(0);
+        }
+    };
+
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let now = Utc::now();
+    let local_now = now.with_timezone(&tz);
+    if !policy.enabled || !policy.sms_enabled || !policy.in_send_window(local_now.time()) {
+        return Ok(0);
+    }
+    let local_day_start = clinic_time::local_day_start(local_now.date_naive(), tz);
+
+    let mut tx = state.db.begin().await?;
+
+    let sent_today: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM sms
+        WHERE note = $1
+          AND sent_at >= $2
+        "#,
+    )
+    .bind(REMINDER_NOTE)
+    .bind(local_day_start)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let remaining = (policy.max_per_day as i64 - sent_today).min(JOB_BATCH_SIZE);
+    if remaining <= 0 {
+        return Ok(0);
+    }
+
+    // Not canceled/no-show, not yet arrived, with a primary phone to text that
+    // hasn't opted out of reminders (patient- or number-level), and not in the
+    // patient's quiet hours right now.
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+          a.appointment_id,
+          a.start_at,
+          p.first_name,
+          p.last_name,
+          p.register_number,
+          p.preferred_language,
+          ph.phone_number_id
+        FROM appointment a
+        JOIN patient p ON p.patient_id = a.patient_id
+        JOIN phone_number ph ON ph.patient_id = a.patient_id AND ph.is_primary = true
+        WHERE a.reminder_sent_at IS NULL
+          AND {allowed}
+          AND {outside_quiet}
+          AND a.status NOT IN (1, 3)
+          AND a.arrived_at IS NULL
+          AND a.start_at > $1
+          AND a.start_at <= $1 + make_interval(hours => $2)
+        ORDER BY a.start_at ASC
+        LIMIT $3
+        FOR UPDATE OF a SKIP LOCKED
+        "#,
+        allowed = consent::allowed_sql(ContactPurpose::Transactional),
+        outside_quiet = contact_prefs::outside_quiet_hours_sql("$4"),
+    ))
+    .bind(now)
+    .bind(policy.hours_before)
+    .bind(remaining)
+    .bind(local_now.time())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut sent = 0;
+    for r in rows {
+        let appointment_id: Uuid = r.try_get("appointment_id")?;
+        let start_at: DateTime<Utc> = r.try_get("start_at")?;
+        let first_name: String = r.try_get("first_name")?;
+        let last_name: String = r.try_get("last_name")?;
+        let register_number: String = r.try_get("register_number")?;
+        let lang: Option<String> = r.try_get("preferred_language")?;
+        let phone_number_id: Uuid = r.try_get("phone_number_id")?;
+
+        let text = render_reminder(
+            policy.template_for(lang.as_deref().and_then(Lang::parse)),
+            &first_name,
+            &last_name,
+            &register_number,
+            start_at.with_timezone(&tz),
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO sms (phone_number_id, direction, sent_at, subject, sms_text, note)
+            VALUES ($1, 1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(phone_number_id)
+        .bind(now)
+        .bind(REMINDER_SUBJECT)
+        .bind(PiiString::from(text))
+        .bind(REMINDER_NOTE)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE appointment SET reminder_sent_at = $2, updated_at = now(), updated_by_user_id = NULL WHERE appointment_id = $1",
+        )
+        .bind(appointment_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        sent += 1;
+    }
+
+    tx.commit().await?;
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> JsonValue {
+        serde_json::json!({
+            "enabled": true,
+            "hours_before": 24,
+            "template": "{name}: {date} {time}",
+            "sms_enabled": true,
+            "send_window": { "start": "09:00", "end": "20:00" },
+            "max_per_day": 100
+        })
+    }
+
+    #[test]
+    fn policy_validation() {
+        assert!(ReminderPolicy::from_json(&policy()).is_ok());
+
+        let mut bad = policy();
+        bad["hours_before"] = 0.into();
+        assert!(ReminderPolicy::from_json(&bad).is_err());
+
+        let mut bad = policy();
+        bad["send_window"] = serde_json::json!({ "start": "20:00", "end": "09:00" });
+        assert!(ReminderPolicy::from_json(&bad).is_err());
+
+        let mut bad = policy();
+        bad["extra"] = true.into();
+        assert!(ReminderPolicy::from_json(&bad).is_err());
+
+        let mut bad = policy();
+        bad["templates"] = serde_json::json!({ "de": "{name}" });
+        assert!(ReminderPolicy::from_json(&bad).is_err());
+
+        let mut bad = policy();
+        bad["templates"] = serde_json::json!({ "mn": " " });
+        assert!(ReminderPolicy::from_json(&bad).is_err());
+    }
+
+    #[test]
+    fn templates_follow_the_patient_language() {
+        let mut v = policy();
+        v["templates"] = serde_json::json!({ "mn": "{name}: {date} {time}-д үзлэгтэй." });
+        let p = ReminderPolicy::from_json(&v).unwrap();
+        assert_eq!(p.template_for(Some(Lang::Mn)), "{name}: {date} {time}-д үзлэгтэй.");
+        // no English variant: the plain template
+        assert_eq!(p.template_for(Some(Lang::En)), p.template);
+        assert_eq!(p.template_for(None), p.template);
+        // policies stored before `templates` existed still round-trip unchanged
+        let old = ReminderPolicy::from_json(&policy()).unwrap();
+        assert_eq!(serde_json::to_value(&old).unwrap(), policy());
+    }
+
+    #[test]
+    fn window_and_render() {
+        let p = ReminderPolicy::from_json(&policy()).unwrap();
+        assert!(p.in_send_window(NaiveTime::from_hms_opt(9, 0, 0).unwrap()));
+        assert!(!p.in_send_window(NaiveTime::from_hms_opt(20, 0, 0).unwrap()));
+
+        // rendered in clinic time: 06:30 UTC is 14:30 in Ulaanbaatar
+        let tz = clinic_time::parse_tz("Asia/Ulaanbaatar").unwrap();
+        let start = Utc.with_ymd_and_hms(2026, 3, 3, 6, 30, 0).unwrap().with_timezone(&tz);
+        assert_eq!(render_reminder(&p.template, "Bat", "Dorj", "AA00", start), "Bat Dorj: 2026-03-03 14:30");
+    }
+}
@@ -0,0 +1,77 @@
+// src/jobs/data_export.rs
+//
+// Nightly run of crate::data_export, spawned only when EXPORT_TARGET is set:
+// - every tick, once the clinic-local hour reaches EXPORT_HOUR, claims today's
+//   scheduled run (data_export_run, one per day across instances) and runs it
+// - a failed run is retried on a later tick, up to MAX_ATTEMPTS per day
+// - runs still "running" after STALE_AFTER_HOURS (the server died mid-run) are
+//   marked failed first, which frees the day again
+
+use std::time::Duration;
+
+use chrono::{Timelike, Utc};
+
+use crate::{clinic_time, data_export::{self, ExportSettings, Trigger}, db, models::AppState};
+
+const JOB_INTERVAL_SECS: u64 = 300;
+const MAX_ATTEMPTS: i64 = 3;
+const STALE_AFTER_HOURS: i32 = 6;
+
+pub async fn run(state: AppState, settings: ExportSettings) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        if let Err(e) = db::retry_transient(|| fail_stale(&state)).await {
+            tracing::warn!("data export job failed: {e}");
+            continue;
+        }
+        match run_due(&state, &settings).await {
+            Ok(None) => {}
+            Ok(Some(run)) => tracing::info!("data export {}: {} ({})", run.export_date, run.status, run.target),
+            Err(e) => tracing::warn!("data export job failed: {e}"),
+        }
+    }
+}
+
+/// Marks runs that can't still be going as failed.
+pub async fn fail_stale(state: &AppState) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query(
+        r#"
+        UPDATE data_export_run
+        SET status = 'failed', error = 'interrupted', finished_at = now()
+        WHERE status = 'running' AND started_at < now() - make_interval(hours => $1)
+        "#,
+    )
+    .bind(STALE_AFTER_HOURS)
+    .execute(&state.db)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Today's scheduled run when it is due and nobody ran it yet.
+pub async fn run_due(
+    state: &AppState,
+    settings: &ExportSettings,
+) -> Result<Option<data_export::ExportRun>, sqlx::Error> {
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let local_now = Utc::now().with_timezone(&tz);
+    if local_now.hour() < settings.hour {
+        return Ok(None);
+    }
+    let export_date = local_now.date_naive();
+
+    let failed: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM data_export_run WHERE trigger = 'schedule' AND export_date = $1 AND status = 'failed'",
+    )
+    .bind(export_date)
+    .fetch_one(&state.db)
+    .await?;
+    if failed >= MAX_ATTEMPTS {
+        return Ok(None);
+    }
+
+    let Some(run) = data_export::start_run(&state.db, settings, Trigger::Schedule, export_date, None).await? else {
+        return Ok(None);
+    };
+    data_export::execute(state, settings, run.run_id, export_date).await.map(Some)
+}
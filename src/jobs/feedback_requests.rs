@@ -0,0 +1,126 @@
+// src/jobs/feedback_requests.rs
+//
+// Post-visit rating links (see services::feedback), when
+// clinic_settings.feedback_sms_enabled is on and FEEDBACK_FORM_URL is set:
+// - every tick, appointments dismissed within the last SEND_WITHIN_HOURS that
+//   have no appointment_feedback row get one plus an outbound sms row
+// - only patients with a primary phone that hasn't opted out of service messages;
+//   patients in their quiet hours wait for a later tick (contact_prefs)
+// - the text is in the patient's preferred_language
+// - one request per appointment, whether or not the patient answers
+
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    auth, clinic_time,
+    consent::{self, ContactPurpose},
+    contact_prefs, db,
+    i18n::Lang,
+    models::{AppState, AppointmentStatus, SmsDirection},
+    pii::PiiString,
+    services::{feedback, intake},
+};
+
+const JOB_INTERVAL_SECS: u64 = 60;
+const JOB_BATCH_SIZE: i64 = 50;
+
+const FEEDBACK_SUBJECT: &str = "Visit feedback";
+const FEEDBACK_NOTE: &str = "auto:feedback";
+
+pub async fn run(state: AppState) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        match db::retry_transient(|| send_feedback_requests(&state)).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("feedback requests: sent {n} link(s)"),
+            Err(e) => tracing::warn!("feedback request job failed: {e}"),
+        }
+    }
+}
+
+pub async fn send_feedback_requests(state: &AppState) -> Result<usize, sqlx::Error> {
+    let Some(base_url) = state.feedback_form_url.as_deref() else {
+        return Ok(0);
+    };
+    let settings: Option<(bool, String)> =
+        sqlx::query_as("SELECT feedback_sms_enabled, clinic_name FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(&state.db)
+            .await?;
+    let Some((true, clinic_name)) = settings else {
+        return Ok(0);
+    };
+
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let now = Utc::now();
+    let mut tx = state.db.begin().await?;
+
+    let rows: Vec<(Uuid, String, Option<String>, Uuid)> = sqlx::query_as(&format!(
+        r#"
+        SELECT a.appointment_id, p.first_name, p.preferred_language, ph.phone_number_id
+        FROM appointment a
+        JOIN patient p ON p.patient_id = a.patient_id
+        JOIN phone_number ph ON ph.patient_id = a.patient_id AND ph.is_primary = true
+        WHERE a.dismissed_at > $1 - make_interval(hours => $2)
+          AND a.status <> $3
+          AND p.deletion_requested_at IS NULL
+          AND {allowed}
+          AND {outside_quiet}
+          AND NOT EXISTS (SELECT 1 FROM appointment_feedback f WHERE f.appointment_id = a.appointment_id)
+        ORDER BY a.dismissed_at
+        LIMIT $4
+        FOR UPDATE OF a SKIP LOCKED
+        "#,
+        allowed = consent::allowed_sql(ContactPurpose::Transactional),
+        outside_quiet = contact_prefs::outside_quiet_hours_sql("$5"),
+    ))
+    .bind(now)
+    .bind(feedback::SEND_WITHIN_HOURS as i32)
+    .bind(AppointmentStatus::Canceled)
+    .bind(JOB_BATCH_SIZE)
+    .bind(now.with_timezone(&tz).time())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let expires_at = now + chrono::Duration::days(feedback::LINK_TTL_DAYS);
+    for (appointment_id, first_name, lang, phone_number_id) in &rows {
+        let lang = lang.as_deref().and_then(Lang::parse).unwrap_or_default();
+        let token = auth::generate_access_token();
+        let link = intake::link(base_url, &token);
+
+        sqlx::query(
+            r#"
+            INSERT INTO appointment_feedback (appointment_id, token_hash, phone_number_id, sent_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(appointment_id)
+        .bind(auth::hash_access_token(&token))
+        .bind(phone_number_id)
+        .bind(now)
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO sms (phone_number_id, direction, sent_at, subject, sms_text, note)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(phone_number_id)
+        .bind(SmsDirection::Send as i16)
+        .bind(now)
+        .bind(FEEDBACK_SUBJECT)
+        .bind(PiiString::from(feedback::sms_text(lang, first_name, &clinic_name, &link)))
+        .bind(FEEDBACK_NOTE)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(rows.len())
+}
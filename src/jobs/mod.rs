@@ -0,0 +1,13 @@
+// src/jobs/mod.rs
+//
+// Background jobs spawned from main. Each job owns its own loop/interval.
+pub mod appointment_reminders;
+pub mod data_export;
+pub mod feedback_requests;
+pub mod no_show_risk;
+pub mod patient_retention;
+pub mod report_refresh;
+pub mod session_cleanup;
+pub mod sms_batches;
+pub mod sync_outbox_cleanup;
+pub mod task_recurrence;
@@ -0,0 +1,146 @@
+// src/jobs/no_show_risk.rs
+//
+// Patient no-show risk (patient_no_show_risk), rebuilt every JOB_INTERVAL_SECS:
+// - window: appointments that started in the last WINDOW_DAYS
+// - no-show: status 3 (no_show) that was never seated
+// - late cancel: canceled less than LATE_CANCEL_HOURS before start_at;
+//   earlier cancels don't count at all
+// - score 0..100 = (no-shows + late cancels / 2) per appointment
+// - patients with fewer than MIN_APPOINTMENTS have no score
+// Shown on the patient of appointment blocks and on the patient summary; blocks
+// of high-risk patients that are still unconfirmed get `needs_double_confirm`.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{db, models::AppState};
+
+const JOB_INTERVAL_SECS: u64 = 3600;
+
+const WINDOW_DAYS: i32 = 365;
+const LATE_CANCEL_HOURS: i32 = 24;
+const MIN_APPOINTMENTS: i64 = 3;
+
+/// score at or above this is "high risk"
+pub const HIGH_RISK_SCORE: i16 = 30;
+
+pub fn is_high_risk(score: Option<i16>) -> bool {
+    score.is_some_and(|s| s >= HIGH_RISK_SCORE)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct NoShowRisk {
+    pub score: i16,
+    pub appointment_count: i32,
+    pub no_show_count: i32,
+    pub late_cancel_count: i32,
+    pub computed_at: DateTime<Utc>,
+}
+
+fn score(appointments: i64, no_shows: i64, late_cancels: i64) -> i16 {
+    if appointments <= 0 {
+        return 0;
+    }
+    let weighted = no_shows * 2 + late_cancels;
+    ((weighted * 50) / appointments).clamp(0, 100) as i16
+}
+
+pub async fn run(state: AppState) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        match db::retry_transient(|| refresh(&state)).await {
+            Ok(n) => tracing::debug!("no-show risk: scored {n} patient(s)"),
+            Err(e) => tracing::warn!("no-show risk job failed: {e}"),
+        }
+    }
+}
+
+/// Recomputes every score; patients that dropped below MIN_APPOINTMENTS lose theirs.
+pub async fn refresh(state: &AppState) -> Result<usize, sqlx::Error> {
+    let stats: Vec<(Uuid, i64, i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT
+          patient_id,
+          COUNT(*) FILTER (
+            WHERE status <> 1 OR canceled_at >= start_at - make_interval(hours => $2)
+          ) AS appointments,
+          COUNT(*) FILTER (WHERE status = 3 AND seated_at IS NULL) AS no_shows,
+          COUNT(*) FILTER (
+            WHERE status = 1 AND canceled_at >= start_at - make_interval(hours => $2)
+          ) AS late_cancels
+        FROM appointment
+        WHERE start_at < now()
+          AND start_at >= now() - make_interval(days => $1)
+        GROUP BY patient_id
+        "#,
+    )
+    .bind(WINDOW_DAYS)
+    .bind(LATE_CANCEL_HOURS)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut ids = Vec::new();
+    let (mut appointments, mut no_shows, mut late_cancels, mut scores) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (patient_id, n, ns, lc) in stats {
+        if n < MIN_APPOINTMENTS {
+            continue;
+        }
+        ids.push(patient_id);
+        appointments.push(n as i32);
+        no_shows.push(ns as i32);
+        late_cancels.push(lc as i32);
+        scores.push(score(n, ns, lc));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO patient_no_show_risk
+          (patient_id, appointment_count, no_show_count, late_cancel_count, score, computed_at)
+        SELECT *, now()
+        FROM UNNEST($1::uuid[], $2::int[], $3::int[], $4::int[], $5::smallint[])
+        ON CONFLICT (patient_id) DO UPDATE SET
+          appointment_count = EXCLUDED.appointment_count,
+          no_show_count = EXCLUDED.no_show_count,
+          late_cancel_count = EXCLUDED.late_cancel_count,
+          score = EXCLUDED.score,
+          computed_at = EXCLUDED.computed_at
+        "#,
+    )
+    .bind(&ids)
+    .bind(&appointments)
+    .bind(&no_shows)
+    .bind(&late_cancels)
+    .bind(&scores)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM patient_no_show_risk WHERE NOT (patient_id = ANY($1))")
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(ids.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_no_shows_double_late_cancels() {
+        assert_eq!(score(10, 0, 0), 0);
+        assert_eq!(score(10, 1, 0), 10);
+        assert_eq!(score(10, 0, 1), 5);
+        assert_eq!(score(4, 2, 1), 62);
+        assert_eq!(score(3, 3, 3), 100);
+        assert!(is_high_risk(Some(score(10, 3, 0))));
+        assert!(!is_high_risk(None));
+    }
+}
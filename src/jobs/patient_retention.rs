@@ -0,0 +1,191 @@
+// src/jobs/patient_retention.rs
+//
+// Patient anonymization (POST /patients/{id}/request_deletion):
+// - once patient.deletion_due_at has passed, PII is scrubbed in one transaction:
+//   name/email/birthday/register number, phone numbers, SMS text, notes,
+//   generated documents, profile photo, clinical alerts, intake submissions and
+//   medical history, imaging files and the free text on imaging orders, free-text
+//   on appointments (incl. note timeline and feedback comments)/tasks/waitlist
+// - appointments + plan items are kept so production/financial aggregates stay intact
+// - patient.anonymized_at is stamped so each patient is processed once
+
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::{db, models::AppState};
+
+const JOB_INTERVAL_SECS: u64 = 300;
+const JOB_BATCH_SIZE: i64 = 20;
+
+const REDACTED: &str = "[redacted]";
+
+pub async fn run(state: AppState) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        match db::retry_transient(|| anonymize_due(&state)).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("patient retention: anonymized {n} patient(s)"),
+            Err(e) => tracing::warn!("patient retention job failed: {e}"),
+        }
+    }
+}
+
+/// Anonymizes patients whose deletion is due.
+pub async fn anonymize_due(state: &AppState) -> Result<usize, sqlx::Error> {
+    let mut tx = state.db.begin().await?;
+
+    let due: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT patient_id
+        FROM patient
+        WHERE deletion_due_at IS NOT NULL
+          AND deletion_due_at <= now()
+          AND anonymized_at IS NULL
+        ORDER BY deletion_due_at
+        LIMIT $1
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(JOB_BATCH_SIZE)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    // register_number is unique, so it becomes an opaque per-patient token
+    sqlx::query(
+        r#"
+        UPDATE patient
+        SET
+          first_name = 'Deleted',
+          last_name = 'Patient',
+          email = NULL,
+          birthday = NULL,
+          user_id = NULL,
+          register_number = 'X' || replace(patient_id::text, '-', ''),
+          photo_updated_at = NULL,
+          anonymized_at = now()
+        WHERE patient_id = ANY($1)
+        "#,
+    )
+    .bind(&due)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE sms
+        SET sms_text = $2, subject = NULL, note = NULL
+        WHERE phone_number_id IN (SELECT phone_number_id FROM phone_number WHERE patient_id = ANY($1))
+        "#,
+    )
+    .bind(&due)
+    .bind(REDACTED)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE phone_number
+        SET phone_number = 'deleted-' || phone_number_id::text, label = $2
+        WHERE patient_id = ANY($1)
+        "#,
+    )
+    .bind(&due)
+    .bind(REDACTED)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE patient_note SET note_text = $2 WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .bind(REDACTED)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM patient_document WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM patient_medical_alert WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM patient_intake WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM patient_medical_history WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM profile_photo WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .execute(&mut *tx)
+        .await?;
+
+    // the orders stay (modality, dates) like appointments do
+    sqlx::query(
+        r#"
+        DELETE FROM imaging_attachment
+        WHERE imaging_order_id IN (SELECT imaging_order_id FROM imaging_order WHERE patient_id = ANY($1))
+        "#,
+    )
+    .bind(&due)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE imaging_order SET region = NULL, reason = NULL, dose_notes = NULL WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE appointment SET note = NULL WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE appointment_note
+        SET note_text = $2
+        WHERE appointment_id IN (SELECT appointment_id FROM appointment WHERE patient_id = ANY($1))
+        "#,
+    )
+    .bind(&due)
+    .bind(REDACTED)
+    .execute(&mut *tx)
+    .await?;
+
+    // ratings stay for the feedback report
+    sqlx::query(
+        r#"
+        UPDATE appointment_feedback
+        SET comment = NULL
+        WHERE appointment_id IN (SELECT appointment_id FROM appointment WHERE patient_id = ANY($1))
+        "#,
+    )
+    .bind(&due)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE waitlist_entry SET reason = NULL, notes = NULL WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE task SET details = NULL WHERE patient_id = ANY($1)")
+        .bind(&due)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(due.len())
+}
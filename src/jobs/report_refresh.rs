@@ -0,0 +1,77 @@
+// src/jobs/report_refresh.rs
+//
+// Keeps the report materialized views (migration 033) fresh:
+// - every JOB_INTERVAL_SECS, and on demand via POST /admin/reports/refresh
+// - REFRESH ... CONCURRENTLY, so reports keep reading the old data meanwhile; exempt
+//   from DB_STATEMENT_TIMEOUT_SECS, a rebuild can legitimately take longer
+// - report_refresh records when each view was last rebuilt
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::{db, models::AppState};
+
+const JOB_INTERVAL_SECS: u64 = 900;
+
+pub const VIEWS: [&str; 2] = ["report_daily_production", "report_daily_appointments"];
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RefreshedView {
+    pub view_name: String,
+    pub refreshed_at: DateTime<Utc>,
+    pub duration_ms: i32,
+}
+
+pub async fn run(state: AppState) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        match db::retry_transient(|| refresh_all(&state.db)).await {
+            Ok(views) => tracing::debug!("report views refreshed: {}", views.len()),
+            Err(e) => tracing::warn!("report refresh job failed: {e}"),
+        }
+    }
+}
+
+/// Rebuilds every report view.
+pub async fn refresh_all(db: &PgPool) -> Result<Vec<RefreshedView>, sqlx::Error> {
+    let mut out = Vec::with_capacity(VIEWS.len());
+    for view in VIEWS {
+        let started = Instant::now();
+        let mut tx = db.begin().await?;
+        sqlx::query("SET LOCAL statement_timeout = 0").execute(&mut *tx).await?;
+        sqlx::query(&format!("REFRESH MATERIALIZED VIEW CONCURRENTLY {view}"))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        let duration_ms = started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+        let row: RefreshedView = sqlx::query_as(
+            r#"
+            INSERT INTO report_refresh (view_name, refreshed_at, duration_ms)
+            VALUES ($1, now(), $2)
+            ON CONFLICT (view_name) DO UPDATE SET
+              refreshed_at = EXCLUDED.refreshed_at,
+              duration_ms = EXCLUDED.duration_ms
+            RETURNING view_name, refreshed_at, duration_ms
+            "#,
+        )
+        .bind(view)
+        .bind(duration_ms)
+        .fetch_one(db)
+        .await?;
+        out.push(row);
+    }
+    Ok(out)
+}
+
+/// When `view` was last rebuilt (None if never).
+pub async fn refreshed_at(db: &PgPool, view: &str) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    sqlx::query_scalar("SELECT refreshed_at FROM report_refresh WHERE view_name = $1")
+        .bind(view)
+        .fetch_optional(db)
+        .await
+}
@@ -0,0 +1,86 @@
+// src/jobs/session_cleanup.rs
+//
+// Purges dead session_token rows (expired or revoked more than
+// SESSION_RETENTION_DAYS ago), every JOB_INTERVAL_SECS and on demand via
+// POST /admin/sessions/cleanup. Rotated token hashes go with them (ON DELETE
+// CASCADE); audit_log keeps its rows with session_token_id set to NULL.
+// Counters since process start are returned by the admin endpoint.
+
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::{db, models::AppState};
+
+const JOB_INTERVAL_SECS: u64 = 3600;
+/// rows per DELETE, so a big backlog doesn't hold locks for long
+const JOB_BATCH_SIZE: i64 = 1000;
+
+static RUNS: AtomicU64 = AtomicU64::new(0);
+static PURGED_TOTAL: AtomicU64 = AtomicU64::new(0);
+/// unix seconds, 0 = never
+static LAST_RUN_AT: AtomicI64 = AtomicI64::new(0);
+
+#[derive(Debug, Serialize)]
+pub struct CleanupStats {
+    pub runs: u64,
+    pub purged_total: u64,
+    pub last_run_at: Option<DateTime<Utc>>,
+}
+
+pub fn stats() -> CleanupStats {
+    let last = LAST_RUN_AT.load(Ordering::Relaxed);
+    CleanupStats {
+        runs: RUNS.load(Ordering::Relaxed),
+        purged_total: PURGED_TOTAL.load(Ordering::Relaxed),
+        last_run_at: (last > 0).then(|| DateTime::from_timestamp(last, 0)).flatten(),
+    }
+}
+
+pub async fn run(state: AppState) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        match db::retry_transient(|| purge(&state.db, state.session_retention_days)).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("session cleanup: purged {n} session(s)"),
+            Err(e) => tracing::warn!("session cleanup job failed: {e}"),
+        }
+    }
+}
+
+/// Deletes sessions that expired or were revoked more than `retention_days` ago.
+pub async fn purge(db: &PgPool, retention_days: i64) -> Result<u64, sqlx::Error> {
+    let mut purged = 0u64;
+    loop {
+        let n = sqlx::query(
+            r#"
+            DELETE FROM session_token
+            WHERE session_token_id IN (
+              SELECT session_token_id
+              FROM session_token
+              WHERE expires_at < now() - make_interval(days => $1::int)
+                 OR revoked_at < now() - make_interval(days => $1::int)
+              LIMIT $2
+            )
+            "#,
+        )
+        .bind(retention_days.clamp(0, i32::MAX as i64) as i32)
+        .bind(JOB_BATCH_SIZE)
+        .execute(db)
+        .await?
+        .rows_affected();
+        purged += n;
+        if n < JOB_BATCH_SIZE as u64 {
+            break;
+        }
+    }
+
+    RUNS.fetch_add(1, Ordering::Relaxed);
+    PURGED_TOTAL.fetch_add(purged, Ordering::Relaxed);
+    LAST_RUN_AT.store(Utc::now().timestamp(), Ordering::Relaxed);
+    Ok(purged)
+}
@@ -0,0 +1,218 @@
+// src/jobs/sms_batches.rs
+//
+// Worker for bulk SMS batches (POST /sms/bulk_send, migration 062):
+// - every tick, takes the oldest open batch and writes its pending recipients'
+//   sms rows, CHUNK_SIZE per transaction with one multi-row INSERT, until no
+//   batch has pending recipients left
+// - consent and released numbers are checked again at send time; those
+//   recipients are marked failed (`opted_out` / `number_released`)
+// - recipients in their quiet hours (contact_prefs) stay pending until the window
+//   is over; a batch that only has such recipients left is passed over meanwhile
+// - a batch without pending recipients is marked done
+// Batches are locked with SKIP LOCKED, so several instances can share the work.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    clinic_time,
+    consent::{self, ContactPurpose},
+    contact_prefs, db,
+    models::{AppState, SmsDirection},
+    pii::PiiString,
+};
+
+const JOB_INTERVAL_SECS: u64 = 5;
+/// recipients per transaction
+const CHUNK_SIZE: i64 = 100;
+
+pub async fn run(state: AppState) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        let mut sent = 0;
+        loop {
+            match db::retry_transient(|| process_chunk(&state)).await {
+                Ok(Some(n)) => sent += n,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!("sms batch job failed: {e}");
+                    break;
+                }
+            }
+        }
+        if sent > 0 {
+            tracing::info!("sms batches: sent {sent} message(s)");
+        }
+    }
+}
+
+/// Why a recipient can't be texted any more; None = send.
+fn recipient_error(released: bool, allowed: bool) -> Option<&'static str> {
+    if released {
+        Some("number_released")
+    } else if !allowed {
+        Some("opted_out")
+    } else {
+        None
+    }
+}
+
+/// One chunk of the oldest open batch with work to do now. None = nothing left to
+/// do; Some(n) = n sms rows written (0 when the chunk only had failures or the
+/// batch just finished).
+pub async fn process_chunk(state: &AppState) -> Result<Option<usize>, sqlx::Error> {
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let local_now = Utc::now().with_timezone(&tz).time();
+    let outside_quiet = contact_prefs::outside_quiet_hours_sql("$1");
+
+    let mut tx = state.db.begin().await?;
+
+    // finished (nothing pending), or someone pending outside their quiet hours
+    let batch: Option<(Uuid, PiiString, String)> = sqlx::query_as(&format!(
+        r#"
+        SELECT b.batch_id, b.sms_text, b.purpose
+        FROM sms_batch b
+        WHERE b.status <> 'done'
+          AND (
+            NOT EXISTS (
+              SELECT 1 FROM sms_batch_recipient r
+              WHERE r.batch_id = b.batch_id AND r.status = 'pending'
+            )
+            OR EXISTS (
+              SELECT 1
+              FROM sms_batch_recipient r
+              JOIN phone_number ph ON ph.phone_number_id = r.phone_number_id
+              JOIN patient p ON p.patient_id = ph.patient_id
+              WHERE r.batch_id = b.batch_id AND r.status = 'pending' AND {outside_quiet}
+            )
+          )
+        ORDER BY b.created_at
+        LIMIT 1
+        FOR UPDATE OF b SKIP LOCKED
+        "#
+    ))
+    .bind(local_now)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((batch_id, text, purpose)) = batch else {
+        return Ok(None);
+    };
+    // CHECK constraint; marketing is the stricter one anyway
+    let purpose = ContactPurpose::parse(&purpose).unwrap_or_default();
+
+    sqlx::query("UPDATE sms_batch SET status = 'sending', started_at = COALESCE(started_at, now()) WHERE batch_id = $1")
+        .bind(batch_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let recipients: Vec<(Uuid, bool, bool)> = sqlx::query_as(&format!(
+        r#"
+        SELECT r.phone_number_id, ph.released_at IS NOT NULL, {allowed}
+        FROM sms_batch_recipient r
+        JOIN phone_number ph ON ph.phone_number_id = r.phone_number_id
+        JOIN patient p ON p.patient_id = ph.patient_id
+        WHERE r.batch_id = $2 AND r.status = 'pending' AND {outside_quiet}
+        ORDER BY r.phone_number_id
+        LIMIT $3
+        FOR UPDATE OF r
+        "#,
+        allowed = consent::allowed_sql(purpose),
+    ))
+    .bind(local_now)
+    .bind(batch_id)
+    .bind(CHUNK_SIZE)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if recipients.is_empty() {
+        // nothing pending (the pick above already made sure)
+        sqlx::query("UPDATE sms_batch SET status = 'done', finished_at = now() WHERE batch_id = $1")
+            .bind(batch_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        return Ok(Some(0));
+    }
+
+    let mut send_ids = Vec::with_capacity(recipients.len());
+    let (mut failed_ids, mut failed_errors) = (Vec::new(), Vec::new());
+    for (phone_number_id, released, allowed) in recipients {
+        match recipient_error(released, allowed) {
+            None => send_ids.push(phone_number_id),
+            Some(error) => {
+                failed_ids.push(phone_number_id);
+                failed_errors.push(error);
+            }
+        }
+    }
+
+    if !failed_ids.is_empty() {
+        sqlx::query(
+            r#"
+            UPDATE sms_batch_recipient r
+            SET status = 'failed', error = t.error, processed_at = now()
+            FROM UNNEST($2::uuid[], $3::text[]) AS t(phone_number_id, error)
+            WHERE r.batch_id = $1 AND r.phone_number_id = t.phone_number_id
+            "#,
+        )
+        .bind(batch_id)
+        .bind(&failed_ids)
+        .bind(&failed_errors)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if send_ids.is_empty() {
+        tx.commit().await?;
+        return Ok(Some(0));
+    }
+
+    // one ciphertext for the whole chunk: the rows hold the same text anyway
+    let created: Vec<(Uuid, Uuid)> = sqlx::query_as(
+        r#"
+        INSERT INTO sms (phone_number_id, direction, sent_at, subject, sms_text, note)
+        SELECT t.phone_number_id, $2, now(), NULL, $3, NULL
+        FROM UNNEST($1::uuid[]) AS t(phone_number_id)
+        RETURNING phone_number_id, sms_id
+        "#,
+    )
+    .bind(&send_ids)
+    .bind(SmsDirection::Send as i16)
+    .bind(&text)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let (phone_ids, sms_ids): (Vec<Uuid>, Vec<Uuid>) = created.into_iter().unzip();
+    sqlx::query(
+        r#"
+        UPDATE sms_batch_recipient r
+        SET status = 'sent', sms_id = t.sms_id, processed_at = now()
+        FROM UNNEST($2::uuid[], $3::uuid[]) AS t(phone_number_id, sms_id)
+        WHERE r.batch_id = $1 AND r.phone_number_id = t.phone_number_id
+        "#,
+    )
+    .bind(batch_id)
+    .bind(&phone_ids)
+    .bind(&sms_ids)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(sms_ids.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn released_numbers_fail_before_consent_is_looked_at() {
+        assert_eq!(recipient_error(false, true), None);
+        assert_eq!(recipient_error(false, false), Some("opted_out"));
+        assert_eq!(recipient_error(true, true), Some("number_released"));
+        assert_eq!(recipient_error(true, false), Some("number_released"));
+    }
+}
@@ -0,0 +1,58 @@
+// src/jobs/sync_outbox_cleanup.rs
+//
+// Purges sync_outbox rows (the change log behind GET /sync/changes and the HQ sync
+// feed, see crate::sync_outbox) older than RETENTION_DAYS, every JOB_INTERVAL_SECS.
+// The newest row is always kept, so a client that is up to date keeps a valid
+// cursor through quiet weeks; one whose cursor was purged gets SYNC_CURSOR_EXPIRED
+// and reloads in full.
+
+use std::time::Duration;
+
+use sqlx::PgPool;
+
+use crate::{db, models::AppState};
+
+const JOB_INTERVAL_SECS: u64 = 3600;
+const RETENTION_DAYS: i32 = 30;
+/// rows per DELETE, so a big backlog doesn't hold locks for long
+const JOB_BATCH_SIZE: i64 = 5000;
+
+pub async fn run(state: AppState) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        match db::retry_transient(|| purge(&state.db)).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("sync outbox cleanup: purged {n} row(s)"),
+            Err(e) => tracing::warn!("sync outbox cleanup job failed: {e}"),
+        }
+    }
+}
+
+async fn purge(db: &PgPool) -> Result<u64, sqlx::Error> {
+    let mut purged = 0u64;
+    loop {
+        let n = sqlx::query(
+            r#"
+            DELETE FROM sync_outbox
+            WHERE seq IN (
+              SELECT seq
+              FROM sync_outbox
+              WHERE changed_at < now() - make_interval(days => $1)
+                AND seq <> (SELECT max(seq) FROM sync_outbox)
+              LIMIT $2
+            )
+            "#,
+        )
+        .bind(RETENTION_DAYS)
+        .bind(JOB_BATCH_SIZE)
+        .execute(db)
+        .await?
+        .rows_affected();
+        purged += n;
+        if n < JOB_BATCH_SIZE as u64 {
+            break;
+        }
+    }
+    Ok(purged)
+}
@@ -0,0 +1,326 @@
+// src/jobs/task_recurrence.rs
+//
+// Recurring tasks:
+// - a task (or template) carries an RRULE-ish spec, e.g. "FREQ=WEEKLY;BYDAY=MO"
+// - when a recurring task is completed, this job creates the next occurrence
+//   and links it via task.next_occurrence_task_id (so it only happens once)
+//
+// Supported spec (subset of RFC 5545 RRULE, times are UTC):
+//   FREQ=DAILY|WEEKLY|MONTHLY   (required)
+//   INTERVAL=n                  (1..=365, default 1)
+//   BYDAY=MO,TU,...             (WEEKLY only)
+//   BYMONTHDAY=n                (MONTHLY only, 1..=28)
+
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, Months, Utc, Weekday};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::{db, models::AppState};
+
+const JOB_INTERVAL_SECS: u64 = 60;
+const JOB_BATCH_SIZE: i64 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub freq: Freq,
+    pub interval: u32,
+    pub by_day: Vec<Weekday>,
+    pub by_month_day: Option<u32>,
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    match s {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+impl Recurrence {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut freq = None;
+        let mut interval = 1u32;
+        let mut by_day = Vec::new();
+        let mut by_month_day = None;
+
+        let spec = spec.trim().trim_start_matches("RRULE:");
+        for part in spec.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((key, value)) = part.split_once('=') else {
+                return Err(format!("invalid recurrence part '{part}'"));
+            };
+            let value = value.trim().to_ascii_uppercase();
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.as_str() {
+                        "DAILY" => Freq::Daily,
+                        "WEEKLY" => Freq::Weekly,
+                        "MONTHLY" => Freq::Monthly,
+                        _ => return Err("FREQ must be DAILY, WEEKLY or MONTHLY".into()),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=365).contains(n))
+                        .ok_or("INTERVAL must be 1..365")?;
+                }
+                "BYDAY" => {
+                    for d in value.split(',') {
+                        let wd = parse_weekday(d.trim()).ok_or(format!("invalid BYDAY value '{d}'"))?;
+                        if !by_day.contains(&wd) {
+                            by_day.push(wd);
+                        }
+                    }
+                }
+                "BYMONTHDAY" => {
+                    by_month_day = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|n| (1..=28).contains(n))
+                            .ok_or("BYMONTHDAY must be 1..28")?,
+                    );
+                }
+                other => return Err(format!("unsupported recurrence key '{other}'")),
+            }
+        }
+
+        let Some(freq) = freq else {
+            return Err("FREQ is required".into());
+        };
+        if !by_day.is_empty() && freq != Freq::Weekly {
+            return Err("BYDAY is only supported with FREQ=WEEKLY".into());
+        }
+        if by_month_day.is_some() && freq != Freq::Monthly {
+            return Err("BYMONTHDAY is only supported with FREQ=MONTHLY".into());
+        }
+        by_day.sort_by_key(|d| d.num_days_from_monday());
+
+        Ok(Self {
+            freq,
+            interval,
+            by_day,
+            by_month_day,
+        })
+    }
+
+    /// Next occurrence strictly after `after`, keeping the time of day.
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        match self.freq {
+            Freq::Daily => after + Days::new(self.interval as u64),
+            Freq::Weekly => {
+                if self.by_day.is_empty() {
+                    return after + Days::new(7 * self.interval as u64);
+                }
+                let today = after.weekday().num_days_from_monday();
+                // later day in the same week?
+                if let Some(d) = self.by_day.iter().find(|d| d.num_days_from_monday() > today) {
+                    return after + Days::new((d.num_days_from_monday() - today) as u64);
+                }
+                // otherwise first BYDAY of the week `interval` weeks ahead
+                let week_start = after - Days::new(today as u64);
+                week_start
+                    + Days::new(7 * self.interval as u64)
+                    + Days::new(self.by_day[0].num_days_from_monday() as u64)
+            }
+            Freq::Monthly => {
+                let Some(target_day) = self.by_month_day else {
+                    // keep the day of month, clamped to the month's length by chrono
+                    return after + Months::new(self.interval);
+                };
+                if after.day() < target_day {
+                    return after.with_day(target_day).unwrap_or(after);
+                }
+                let next = after + Months::new(self.interval);
+                next.with_day(target_day).unwrap_or(next)
+            }
+        }
+    }
+
+    /// Next occurrence after `base` that is also in the future (skips missed ones).
+    pub fn next_upcoming(&self, base: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut next = self.next_after(base);
+        while next <= now {
+            next = self.next_after(next);
+        }
+        next
+    }
+}
+
+/* ============================================================
+   Job
+   ============================================================ */
+
+pub async fn run(state: AppState) {
+    let mut tick = tokio::time::interval(Duration::from_secs(JOB_INTERVAL_SECS));
+    loop {
+        tick.tick().await;
+        match db::retry_transient(|| materialize_pending(&state)).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("task recurrence: created {n} next occurrence(s)"),
+            Err(e) => tracing::warn!("task recurrence job failed: {e}"),
+        }
+    }
+}
+
+/// Creates the next occurrence for completed recurring tasks that don't have one yet.
+pub async fn materialize_pending(state: &AppState) -> Result<usize, sqlx::Error> {
+    let mut tx = state.db.begin().await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          task_id,
+          recurrence,
+          due_at,
+          completed_at
+        FROM task
+        WHERE recurrence IS NOT NULL
+          AND status = 2
+          AND next_occurrence_task_id IS NULL
+        ORDER BY completed_at ASC
+        LIMIT $1
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(JOB_BATCH_SIZE)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let now = Utc::now();
+    let mut created = 0;
+
+    for r in rows {
+        let task_id: Uuid = r.try_get("task_id")?;
+        let spec: String = r.try_get("recurrence")?;
+        let due_at: Option<DateTime<Utc>> = r.try_get("due_at")?;
+        let completed_at: Option<DateTime<Utc>> = r.try_get("completed_at")?;
+
+        let rec = match Recurrence::parse(&spec) {
+            Ok(rec) => rec,
+            Err(e) => {
+                // specs are validated on write; don't retry a broken one every minute
+                tracing::warn!("task {task_id}: invalid recurrence '{spec}': {e}");
+                sqlx::query("UPDATE task SET next_occurrence_task_id = task_id WHERE task_id = $1")
+                    .bind(task_id)
+                    .execute(&mut *tx)
+                    .await?;
+                continue;
+            }
+        };
+
+        let base = due_at.or(completed_at).unwrap_or(now);
+        let next_due = rec.next_upcoming(base, now);
+
+        let next_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO task (
+              created_by_employee_id,
+              assigned_to_employee_id,
+              patient_id,
+              task_type,
+              status,
+              priority,
+              due_at,
+              title,
+              details,
+              updated_by_employee_id,
+              assigned_at,
+              assigned_by_employee_id,
+              task_template_id,
+              recurrence
+            )
+            SELECT
+              created_by_employee_id,
+              assigned_to_employee_id,
+              patient_id,
+              task_type,
+              0,
+              priority,
+              $2,
+              title,
+              details,
+              created_by_employee_id,
+              CASE WHEN assigned_to_employee_id IS NULL THEN NULL ELSE now() END,
+              CASE WHEN assigned_to_employee_id IS NULL THEN NULL ELSE created_by_employee_id END,
+              task_template_id,
+              recurrence
+            FROM task
+            WHERE task_id = $1
+            RETURNING task_id
+            "#,
+        )
+        .bind(task_id)
+        .bind(next_due)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE task SET next_occurrence_task_id = $2 WHERE task_id = $1")
+            .bind(task_id)
+            .bind(next_id)
+            .execute(&mut *tx)
+            .await?;
+
+        created += 1;
+    }
+
+    tx.commit().await?;
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, 9, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parse_rejects_bad_specs() {
+        assert!(Recurrence::parse("").is_err());
+        assert!(Recurrence::parse("FREQ=YEARLY").is_err());
+        assert!(Recurrence::parse("FREQ=DAILY;BYDAY=MO").is_err());
+        assert!(Recurrence::parse("FREQ=MONTHLY;BYMONTHDAY=31").is_err());
+        assert!(Recurrence::parse("FREQ=WEEKLY;INTERVAL=0").is_err());
+    }
+
+    #[test]
+    fn weekly_by_day() {
+        // 2026-10-12 is a Monday
+        let rec = Recurrence::parse("FREQ=WEEKLY;BYDAY=MO").unwrap();
+        assert_eq!(rec.next_after(at(2026, 10, 12)), at(2026, 10, 19));
+        assert_eq!(rec.next_after(at(2026, 10, 14)), at(2026, 10, 19));
+
+        let rec = Recurrence::parse("RRULE:FREQ=WEEKLY;BYDAY=FR,MO;INTERVAL=2").unwrap();
+        assert_eq!(rec.next_after(at(2026, 10, 12)), at(2026, 10, 16));
+        assert_eq!(rec.next_after(at(2026, 10, 16)), at(2026, 10, 26));
+    }
+
+    #[test]
+    fn monthly_and_daily() {
+        let rec = Recurrence::parse("FREQ=MONTHLY;BYMONTHDAY=1").unwrap();
+        assert_eq!(rec.next_after(at(2026, 10, 1)), at(2026, 11, 1));
+        let rec = Recurrence::parse("FREQ=MONTHLY;BYMONTHDAY=20").unwrap();
+        assert_eq!(rec.next_after(at(2026, 10, 16)), at(2026, 10, 20));
+
+        let rec = Recurrence::parse("FREQ=DAILY;INTERVAL=3").unwrap();
+        assert_eq!(rec.next_upcoming(at(2026, 10, 1), at(2026, 10, 5)), at(2026, 10, 7));
+    }
+}
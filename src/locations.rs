@@ -0,0 +1,68 @@
+// src/locations.rs
+//
+// Location access (clinic_location + user_location_access).
+// A user with no access rows can use every location; admins always can.
+// Routes use `location_scope` to turn an optional ?location_id into the set of
+// locations a query may touch.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{error::ApiError, middleware::auth_context::AuthContext, models::Role};
+
+/// Locations the user is restricted to; `None` = unrestricted.
+pub async fn allowed_locations(db: &PgPool, auth: &AuthContext) -> Result<Option<Vec<Uuid>>, sqlx::Error> {
+    if auth.role == Role::Admin {
+        return Ok(None);
+    }
+
+    let ids: Vec<Uuid> = sqlx::query_scalar("SELECT location_id FROM user_location_access WHERE user_id = $1")
+        .bind(auth.user_id)
+        .fetch_all(db)
+        .await?;
+
+    Ok((!ids.is_empty()).then_some(ids))
+}
+
+/// Fails unless the location exists, is active and the user may use it.
+pub async fn ensure_location_access(db: &PgPool, auth: &AuthContext, location_id: Uuid) -> Result<(), ApiError> {
+    let active: Option<bool> = sqlx::query_scalar("SELECT is_active FROM clinic_location WHERE location_id = $1")
+        .bind(location_id)
+        .fetch_optional(db)
+        .await?;
+
+    match active {
+        None => return Err(ApiError::NotFound("NOT_FOUND", "location not found".into())),
+        Some(false) => {
+            return Err(ApiError::BadRequest("VALIDATION_ERROR", "location is inactive".into()));
+        }
+        Some(true) => {}
+    }
+
+    let allowed = allowed_locations(db, auth)
+        .await?;
+
+    match allowed {
+        Some(ids) if !ids.contains(&location_id) => Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "You do not have access to this location".into(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Location filter for list/schedule queries: the requested location (after an
+/// access check), else the user's allowed set; `None` = no filter.
+pub async fn location_scope(
+    db: &PgPool,
+    auth: &AuthContext,
+    requested: Option<Uuid>,
+) -> Result<Option<Vec<Uuid>>, ApiError> {
+    match requested {
+        Some(id) => {
+            ensure_location_access(db, auth, id).await?;
+            Ok(Some(vec![id]))
+        }
+        None => Ok(allowed_locations(db, auth).await?),
+    }
+}
@@ -0,0 +1,162 @@
+// src/login_events.rs
+//
+// login_event writer/reader (migration 034). Written by auth_routes::login_with_type
+// for every attempt that got past request validation; read by
+// GET /auth/my_logins and GET /users/{id}/login_history.
+// Writing is best effort: a failed insert is logged, the login itself goes on.
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// Where a request came from. `ip` is the peer address; when the peer is a proxy on
+/// the same host (loopback), the first `X-Forwarded-For` entry is used instead.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|c| c.0.ip());
+        let forwarded = parts
+            .headers
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        let ip = match (peer, forwarded) {
+            (Some(p), Some(f)) if p.is_loopback() => Some(f.to_string()),
+            (Some(p), _) => Some(p.to_string()),
+            (None, _) => None,
+        };
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.chars().take(MAX_USER_AGENT_LEN).collect());
+        Ok(ClientInfo { ip, user_agent })
+    }
+}
+
+pub struct LoginAttempt<'a> {
+    pub username: &'a str,
+    pub session_type: i16,
+    pub device_name: Option<&'a str>,
+    pub client: &'a ClientInfo,
+}
+
+/// `outcome`: Ok(session_token_id) or Err(failure_reason).
+pub async fn record(db: &PgPool, attempt: &LoginAttempt<'_>, user_id: Option<Uuid>, outcome: Result<Uuid, &str>) {
+    let (session_token_id, failure_reason) = match outcome {
+        Ok(id) => (Some(id), None),
+        Err(reason) => (None, Some(reason)),
+    };
+    let res = sqlx::query(
+        r#"
+        INSERT INTO login_event
+          (user_id, username, success, failure_reason, session_type, session_token_id, ip, user_agent, device_name)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+    )
+    .bind(user_id)
+    .bind(attempt.username)
+    .bind(failure_reason.is_none())
+    .bind(failure_reason)
+    .bind(attempt.session_type)
+    .bind(session_token_id)
+    .bind(attempt.client.ip.as_deref())
+    .bind(attempt.client.user_agent.as_deref())
+    .bind(attempt.device_name)
+    .execute(db)
+    .await;
+    if let Err(e) = res {
+        tracing::warn!("login_event insert failed for {}: {e}", attempt.username);
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LoginEventRow {
+    pub login_event_id: Uuid,
+    pub success: bool,
+    pub failure_reason: Option<String>,
+    pub session_type: i16,
+    pub session_token_id: Option<Uuid>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub device_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginHistoryQuery {
+    /// only failed (false) or successful (true) attempts
+    pub success: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Newest first.
+pub async fn list_for_user(
+    db: &PgPool,
+    user_id: Uuid,
+    q: &LoginHistoryQuery,
+) -> Result<Vec<LoginEventRow>, sqlx::Error> {
+    sqlx::query_as::<_, LoginEventRow>(
+        r#"
+        SELECT login_event_id, success, failure_reason, session_type, session_token_id,
+               ip, user_agent, device_name, created_at
+        FROM login_event
+        WHERE user_id = $1
+          AND ($2::boolean IS NULL OR success = $2)
+        ORDER BY created_at DESC
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(user_id)
+    .bind(q.success)
+    .bind(q.limit.unwrap_or(50).clamp(1, 500))
+    .bind(q.offset.unwrap_or(0).max(0))
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    async fn client_of(peer: &str, forwarded: Option<&str>) -> ClientInfo {
+        let mut req = Request::builder().header(header::USER_AGENT, "dcms-desktop/1.0");
+        if let Some(f) = forwarded {
+            req = req.header("x-forwarded-for", f);
+        }
+        let (mut parts, ()) = req.body(()).unwrap().into_parts();
+        parts.extensions.insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+        ClientInfo::from_request_parts(&mut parts, &()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn forwarded_for_only_trusted_from_loopback() {
+        let c = client_of("127.0.0.1:5000", Some("203.0.113.7, 10.0.0.1")).await;
+        assert_eq!(c.ip.as_deref(), Some("203.0.113.7"));
+        assert_eq!(c.user_agent.as_deref(), Some("dcms-desktop/1.0"));
+
+        let c = client_of("192.168.1.20:5000", Some("203.0.113.7")).await;
+        assert_eq!(c.ip.as_deref(), Some("192.168.1.20"));
+    }
+}
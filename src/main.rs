@@ -1,34 +1,100 @@
+mod audit;
 mod auth;
+mod backup;
+mod clinic_time;
 mod config;
+mod consent;
+mod contact_prefs;
+mod cursor;
+mod data_export;
+mod day_close;
+mod day_sheet;
 mod middleware;
 
 mod db;
+mod employee_services;
 mod error;
+mod extract;
+#[cfg(feature = "hq-grpc")]
+mod hq_grpc;
+mod i18n;
+mod jobs;
+mod locations;
+mod login_events;
 mod models;
+mod money;
+mod notifications;
+mod numbering;
+mod onboarding;
+mod overlap_policy;
+mod pdf;
+mod permissions;
+mod phone_sharing;
+mod photos;
+mod pii;
+mod presence;
+mod repos;
 mod routes;
+mod services;
+mod session_cache;
+mod sms_replies;
+mod sms_segments;
+mod sync_outbox;
+mod telemetry;
 
 use crate::{config::Config, models::AppState};
 
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
 use axum::http::header;
-use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
 
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive("info".parse()?))
-        .init();
-
     let cfg = Config::from_env()?;
-    let pool = db::connect_pg(&cfg.database_url).await?;
+    let telemetry = telemetry::init(&cfg)?;
+
+    let cipher = cfg
+        .pii_encryption_key
+        .as_deref()
+        .map(pii::PiiCipher::from_base64_key)
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
+    if cipher.is_none() {
+        tracing::warn!("PII_ENCRYPTION_KEY not set; patient email and SMS text are stored in plaintext");
+    }
+    pii::init(cipher);
+
+    let pool = db::connect_pg(&cfg.database_url, &cfg.db_pool).await?;
+    let db_read = cfg
+        .database_read_url
+        .as_deref()
+        .map(|url| db::ReadReplica::connect_lazy(url, &cfg.db_pool))
+        .transpose()?;
+    if let Some(replica) = &db_read {
+        tokio::spawn(replica.clone().monitor());
+    }
 
+    let repos = repos::Repos::postgres(pool.clone(), db_read.clone());
     let state = AppState {
         db: pool,
+        database_url: cfg.database_url.clone(),
+        db_read,
         session_ttl_hours: cfg.session_ttl_hours,
+        session_sliding: cfg.session_sliding,
+        session_max_lifetime_hours: cfg.session_max_lifetime_hours,
+        impersonation_ttl_minutes: cfg.impersonation_ttl_minutes,
+        session_retention_days: cfg.session_retention_days,
+        sms_segment_price_cents: cfg.sms_segment_price_cents,
+        intake_form_url: cfg.intake_form_url.clone(),
+        feedback_form_url: cfg.feedback_form_url.clone(),
+        data_export: cfg.data_export.clone(),
+        session_cache: session_cache::SessionCache::new(),
+        presence: presence::Presence::new(),
+        repos,
     };
 
     // DEV ONLY: allow browser/WebView clients (Tauri static frontend) to call the API.
@@ -40,15 +106,50 @@ async fn main() -> anyhow::Result<()> {
             header::CONTENT_TYPE,
             header::AUTHORIZATION,
             header::ACCEPT,
-        ]);
+            header::IF_NONE_MATCH,
+        ])
+        .expose_headers([header::ETAG]);
+
+    tokio::spawn(jobs::task_recurrence::run(state.clone()));
+    tokio::spawn(jobs::appointment_reminders::run(state.clone()));
+    tokio::spawn(jobs::feedback_requests::run(state.clone()));
+    tokio::spawn(jobs::patient_retention::run(state.clone()));
+    tokio::spawn(jobs::no_show_risk::run(state.clone()));
+    tokio::spawn(jobs::report_refresh::run(state.clone()));
+    tokio::spawn(jobs::session_cleanup::run(state.clone()));
+    tokio::spawn(jobs::sms_batches::run(state.clone()));
+    tokio::spawn(jobs::sync_outbox_cleanup::run(state.clone()));
+    if let Some(settings) = cfg.data_export.clone() {
+        tokio::spawn(jobs::data_export::run(state.clone(), settings));
+    }
+
+    if let Some(addr) = cfg.hq_grpc_addr.as_deref() {
+        #[cfg(feature = "hq-grpc")]
+        {
+            let addr = addr.parse()?;
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = hq_grpc::serve(state, addr).await {
+                    tracing::error!("HQ sync gRPC server stopped: {e}");
+                }
+            });
+        }
+        #[cfg(not(feature = "hq-grpc"))]
+        tracing::warn!("HQ_GRPC_ADDR={addr} ignored: built without the hq-grpc feature");
+    }
 
     let app = routes::router(state)
         .layer(cors)
-        .layer(TraceLayer::new_for_http());
+        // gzip when the client sends Accept-Encoding (WebView on slow clinic Wi-Fi)
+        .layer(CompressionLayer::new())
+        .layer(axum::middleware::from_fn(middleware::request_context::scope))
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::request_span));
 
     tracing::info!("Listening on http://{}", cfg.bind_addr);
     let listener = tokio::net::TcpListener::bind(&cfg.bind_addr).await?;
-    axum::serve(listener, app).await?;
-    Ok(())
+    // peer address for login_event.ip (login_events::ClientInfo)
+    let served = axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await;
+    telemetry.shutdown();
+    Ok(served?)
 }
 
@@ -6,42 +6,102 @@ use uuid::Uuid;
 
 use crate::auth::hash_access_token;
 use crate::error::ApiError;
-use crate::models::AppState;
+use crate::i18n::Lang;
+use crate::middleware::request_context;
+use crate::models::{AppState, Role};
+use crate::session_cache::CachedSession;
 
 #[derive(Debug, Clone)]
 pub struct AuthContext {
     pub user_id: Uuid,
-    pub role: i16,
+    pub role: Role,
     pub session_token_id: Uuid,
+    /// Set when an admin is acting as this user (POST /auth/impersonate/{user_id}).
+    pub impersonator_user_id: Option<Uuid>,
+}
+
+impl AuthContext {
+    pub fn is_impersonating(&self) -> bool {
+        self.impersonator_user_id.is_some()
+    }
+
+    /// Guard for account-level/destructive actions that must be done as yourself.
+    pub fn ensure_not_impersonating(&self) -> Result<(), ApiError> {
+        if self.is_impersonating() {
+            return Err(ApiError::Forbidden(
+                "FORBIDDEN",
+                "Not allowed while impersonating".into(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, sqlx::FromRow)]
 struct SessionLookupRow {
     session_token_id: Uuid,
     user_id: Uuid,
-    roles: i16,
+    roles: Role,
+    impersonator_user_id: Option<Uuid>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    preferred_language: Option<String>,
+}
+
+/// A token that /auth/refresh already rotated away is being used again: either the
+/// client or an attacker holds a stale copy, so the whole session is revoked.
+async fn revoke_if_replayed(state: &AppState, token_hash: &str) {
+    let res = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE session_token st
+        SET revoked_at = now(), revoke_reason = 'token_replay'
+        FROM session_token_rotated r
+        WHERE r.token_hash = $1
+          AND st.session_token_id = r.session_token_id
+          AND st.revoked_at IS NULL
+        RETURNING st.session_token_id
+        "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(&state.db)
+    .await;
+
+    match res {
+        Ok(Some(id)) => {
+            state.session_cache.invalidate_session(id);
+            tracing::warn!("rotated token replayed; revoked session {id}");
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("token replay check failed: {e}"),
+    }
 }
 
 impl FromRequestParts<AppState> for AuthContext {
     type Rejection = ApiError;
 
-    fn from_request_parts(
-        parts: &mut Parts,
-        state: &AppState,
-    ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
-        async move {
-            // Extract Authorization: Bearer <token>
-            let TypedHeader(authz): TypedHeader<Authorization<Bearer>> =
-                TypedHeader::from_request_parts(parts, state)
-                    .await
-                    .map_err(|_| ApiError::session_expired())?;
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        // Extract Authorization: Bearer <token>
+        let TypedHeader(authz): TypedHeader<Authorization<Bearer>> =
+            TypedHeader::from_request_parts(parts, state)
+                .await
+                .map_err(|_| ApiError::session_expired())?;
 
-            let token_hash = hash_access_token(authz.token());
+        authenticate(state, authz.token()).await
+    }
+}
+
+/// Bearer token -> session, for callers outside the HTTP extractor (the HQ gRPC server,
+/// the GET /ws hello).
+pub async fn authenticate(state: &AppState, token: &str) -> Result<AuthContext, ApiError> {
+    let token_hash = hash_access_token(token);
 
+    let session = match state.session_cache.get(&token_hash) {
+        Some(cached) => cached,
+        None => {
             // Validate session_token + ensure dcms_user is active
-            let row: SessionLookupRow = sqlx::query_as::<_, SessionLookupRow>(
+            let row: Option<SessionLookupRow> = sqlx::query_as::<_, SessionLookupRow>(
                 r#"
-                SELECT st.session_token_id, st.user_id, u.roles
+                SELECT st.session_token_id, st.user_id, u.roles, st.impersonator_user_id, st.expires_at,
+                       u.preferred_language
                 FROM session_token st
                 JOIN "dcms_user" u ON u.user_id = st.user_id
                 WHERE st.session_token_hash = $1
@@ -52,27 +112,48 @@ impl FromRequestParts<AppState> for AuthContext {
             )
             .bind(&token_hash)
             .fetch_optional(&state.db)
-            .await
-            .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-            .ok_or_else(ApiError::session_expired)?;
+            .await?;
 
-            // Touch last_seen_at (best-effort)
-            let _ = sqlx::query(
-                r#"
-                UPDATE session_token
-                SET last_seen_at = now()
-                WHERE session_token_id = $1
-                "#,
-            )
-            .bind(row.session_token_id)
-            .execute(&state.db)
-            .await;
+            let Some(row) = row else {
+                revoke_if_replayed(state, &token_hash).await;
+                return Err(ApiError::session_expired());
+            };
 
-            Ok(AuthContext {
+            let session = CachedSession {
+                session_token_id: row.session_token_id,
                 user_id: row.user_id,
                 role: row.roles,
-                session_token_id: row.session_token_id,
-            })
+                impersonator_user_id: row.impersonator_user_id,
+                expires_at: row.expires_at,
+                preferred_language: row.preferred_language.as_deref().and_then(Lang::parse),
+            };
+            state.session_cache.insert(token_hash, session.clone());
+            session
         }
+    };
+
+    if let Some(lang) = session.preferred_language {
+        request_context::set_lang(lang);
     }
+
+    // Touch last_seen_at (best-effort, at most once a minute per session)
+    if state.session_cache.should_touch(session.session_token_id) {
+        let _ = sqlx::query(
+            r#"
+            UPDATE session_token
+            SET last_seen_at = now()
+            WHERE session_token_id = $1
+            "#,
+        )
+        .bind(session.session_token_id)
+        .execute(&state.db)
+        .await;
+    }
+
+    Ok(AuthContext {
+        user_id: session.user_id,
+        role: session.role,
+        session_token_id: session.session_token_id,
+        impersonator_user_id: session.impersonator_user_id,
+    })
 }
@@ -0,0 +1,63 @@
+// src/middleware/deprecation.rs
+//
+// Response headers for endpoints slated for removal:
+// - `Deprecation: @<unix ts>` (RFC 9745) - when the endpoint was deprecated
+// - `Sunset: <HTTP-date>` (RFC 8594) - when it stops working
+//
+// Attach per route:
+//   get(handler).layer(middleware::from_fn_with_state(SOME_DEPRECATION, deprecation::headers))
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use chrono::DateTime;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Deprecation {
+    /// unix seconds
+    pub deprecated_at: i64,
+    /// unix seconds
+    pub sunset_at: i64,
+}
+
+impl Deprecation {
+    fn deprecation_value(&self) -> String {
+        format!("@{}", self.deprecated_at)
+    }
+
+    fn sunset_value(&self) -> Option<String> {
+        DateTime::from_timestamp(self.sunset_at, 0)
+            .map(|t| t.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+    }
+}
+
+pub async fn headers(State(d): State<Deprecation>, req: Request, next: Next) -> Response {
+    let mut res = next.run(req).await;
+    let h = res.headers_mut();
+
+    if let Ok(v) = HeaderValue::from_str(&d.deprecation_value()) {
+        h.insert("deprecation", v);
+    }
+    if let Some(v) = d.sunset_value().and_then(|s| HeaderValue::from_str(&s).ok()) {
+        h.insert("sunset", v);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_formats() {
+        let d = Deprecation {
+            deprecated_at: 1792108800, // 2026-10-16
+            sunset_at: 1806537600,     // 2027-04-01
+        };
+        assert_eq!(d.deprecation_value(), "@1792108800");
+        assert_eq!(d.sunset_value().unwrap(), "Thu, 01 Apr 2027 00:00:00 GMT");
+    }
+}
@@ -0,0 +1,90 @@
+// src/middleware/etag.rs
+//
+// ETag / If-None-Match for read-heavy GET endpoints (services, clinic meta, schedule
+// views). The ETag is a hash of the JSON body, so nothing has to track versions:
+// the handler still runs, but an unchanged response goes back as an empty 304.
+// - only 200 responses get an ETag
+// - `Cache-Control: private, no-cache`: responses depend on the caller, and the
+//   client must revalidate every time (it just gets 304 when nothing changed)
+//
+// Attach per route:  get(handler).layer(middleware::from_fn(etag::etag))
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use crate::error::ApiError;
+
+pub async fn etag(req: Request, next: Next) -> Response {
+    let if_none_match = req.headers().get(IF_NONE_MATCH).cloned();
+    let res = next.run(req).await;
+    if res.status() != StatusCode::OK {
+        return res;
+    }
+
+    let (mut parts, body) = res.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => return ApiError::Internal(format!("etag: reading body failed: {e}")).into_response(),
+    };
+
+    let tag = etag_for(&bytes);
+    let Ok(tag_value) = HeaderValue::from_str(&tag) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    parts.headers.insert(ETAG, tag_value.clone());
+    parts
+        .headers
+        .insert(CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+
+    if if_none_match.is_some_and(|v| matches(&v, &tag)) {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, tag_value);
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+fn etag_for(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// If-None-Match uses weak comparison: `W/` prefixes are ignored, `*` matches anything.
+fn matches(if_none_match: &HeaderValue, tag: &str) -> bool {
+    let Ok(v) = if_none_match.to_str() else {
+        return false;
+    };
+    v.split(',')
+        .map(str::trim)
+        .any(|t| t == "*" || t.strip_prefix("W/").unwrap_or(t) == tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_none_match_comparison() {
+        let tag = etag_for(br#"{"data":[]}"#);
+        assert_eq!(tag, etag_for(br#"{"data":[]}"#));
+        assert_ne!(tag, etag_for(br#"{"data":[1]}"#));
+
+        let hv = |s: &str| HeaderValue::from_str(s).unwrap();
+        assert!(matches(&hv(&tag), &tag));
+        assert!(matches(&hv(&format!("W/{tag}")), &tag));
+        assert!(matches(&hv(&format!("\"other\", {tag}")), &tag));
+        assert!(matches(&hv("*"), &tag));
+        assert!(!matches(&hv("\"other\""), &tag));
+    }
+}
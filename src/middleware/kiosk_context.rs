@@ -0,0 +1,77 @@
+// src/middleware/kiosk_context.rs
+//
+// Bearer auth for reception kiosks (kiosk_device, migration 049). Kiosk tokens
+// live apart from session_token, so AuthContext rejects them and a kiosk can
+// only reach handlers that ask for a KioskContext (/kiosk/*, /display/*), and
+// only those of its kind. Not cached: device traffic is a few requests a minute
+// and revocation takes effect immediately.
+// The token may also come as `?token=`, for browser EventSource (display boards),
+// which can't set headers.
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum_extra::TypedHeader;
+use headers::{Authorization, authorization::Bearer};
+use uuid::Uuid;
+
+use crate::auth::hash_access_token;
+use crate::error::ApiError;
+use crate::models::{AppState, DeviceKind};
+
+#[derive(Debug, Clone)]
+pub struct KioskContext {
+    pub kiosk_id: Uuid,
+    pub name: String,
+    pub kind: DeviceKind,
+    /// check-ins are limited to this location's appointments; None = any
+    pub location_id: Option<Uuid>,
+}
+
+impl KioskContext {
+    /// A display token can't check patients in, a kiosk token can't show the queue.
+    pub fn ensure_kind(&self, kind: DeviceKind) -> Result<(), ApiError> {
+        if self.kind == kind {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden("FORBIDDEN", format!("this route is for {kind} devices")))
+        }
+    }
+}
+
+/// `token` from the query string (URL-safe base64, so no decoding needed).
+fn query_token(parts: &Parts) -> Option<String> {
+    parts
+        .uri
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+impl FromRequestParts<AppState> for KioskContext {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let token = match TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await {
+            Ok(TypedHeader(authz)) => authz.token().to_string(),
+            Err(_) => query_token(parts).ok_or_else(ApiError::session_expired)?,
+        };
+
+        // lookup and last_seen_at in one statement
+        let row: Option<(Uuid, String, DeviceKind, Option<Uuid>)> = sqlx::query_as(
+            r#"
+            UPDATE kiosk_device
+            SET last_seen_at = now()
+            WHERE token_hash = $1 AND revoked_at IS NULL
+            RETURNING kiosk_id, name, kind, location_id
+            "#,
+        )
+        .bind(hash_access_token(&token))
+        .fetch_optional(&state.db)
+        .await?;
+
+        let (kiosk_id, name, kind, location_id) = row.ok_or_else(ApiError::session_expired)?;
+        Ok(KioskContext { kiosk_id, name, kind, location_id })
+    }
+}
@@ -1,2 +1,6 @@
 // src/middleware/mod.rs
 pub mod auth_context;
+pub mod deprecation;
+pub mod etag;
+pub mod kiosk_context;
+pub mod request_context;
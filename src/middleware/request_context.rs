@@ -0,0 +1,101 @@
+// src/middleware/request_context.rs
+//
+// Per-request context kept in a task-local, for code without access to the
+// request (error conversion, db helpers):
+// - route ("GET /api/v1/appointments/day"), so log lines can say which endpoint
+//   they belong to
+// - language for localized error messages (crate::i18n): Accept-Language here,
+//   replaced by the user's preferred_language once AuthContext has loaded it
+// Applied once around the whole app in main.rs.
+
+use std::cell::Cell;
+
+use axum::{
+    extract::Request,
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::i18n::Lang;
+
+struct RequestContext {
+    route: String,
+    lang: Cell<Lang>,
+}
+
+tokio::task_local! {
+    static CONTEXT: RequestContext;
+}
+
+pub async fn scope(req: Request, next: Next) -> Response {
+    let route = format!("{} {}", req.method(), req.uri().path());
+    let lang = req
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Lang::from_accept_language)
+        .unwrap_or_default();
+    let ctx = RequestContext { route, lang: Cell::new(lang) };
+    CONTEXT.scope(ctx, next.run(req)).await
+}
+
+/// "-" outside a request (background jobs).
+pub fn current_route() -> String {
+    CONTEXT.try_with(|c| c.route.clone()).unwrap_or_else(|_| "-".into())
+}
+
+/// English outside a request.
+pub fn current_lang() -> Lang {
+    CONTEXT.try_with(|c| c.lang.get()).unwrap_or_default()
+}
+
+/// Overrides the Accept-Language choice for the rest of the request.
+pub fn set_lang(lang: Lang) {
+    let _ = CONTEXT.try_with(|c| c.lang.set(lang));
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, body::Body, routing::get};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn route_is_visible_inside_the_request() {
+        let app = Router::new()
+            .route("/api/v1/things/{id}", get(|| async { current_route() }))
+            .layer(axum::middleware::from_fn(scope));
+        let res = app
+            .oneshot(Request::get("/api/v1/things/42").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"GET /api/v1/things/42");
+        assert_eq!(current_route(), "-");
+    }
+
+    #[tokio::test]
+    async fn lang_from_accept_language_until_overridden() {
+        let app = Router::new()
+            .route("/a", get(|| async { format!("{:?}", current_lang()) }))
+            .route(
+                "/b",
+                get(|| async {
+                    set_lang(Lang::En);
+                    format!("{:?}", current_lang())
+                }),
+            )
+            .layer(axum::middleware::from_fn(scope));
+        for (path, want) in [("/a", "Mn"), ("/b", "En")] {
+            let req = Request::get(path)
+                .header(header::ACCEPT_LANGUAGE, "mn-MN, en;q=0.5")
+                .body(Body::empty())
+                .unwrap();
+            let res = app.clone().oneshot(req).await.unwrap();
+            let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(&body[..], want.as_bytes());
+        }
+    }
+}
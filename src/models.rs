@@ -3,16 +3,61 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::{error::ApiError, pii::PiiString};
+
 #[derive(Clone)]
 pub struct AppState {
     pub db: sqlx::PgPool,
+    /// primary's URL, for pg_dump (POST /admin/backup)
+    pub database_url: String,
+    /// None = no DATABASE_READ_URL; use `read_db()` rather than this directly
+    pub db_read: Option<crate::db::ReadReplica>,
     pub session_ttl_hours: i64,
+    /// `/auth/refresh` also pushes expires_at forward (capped at created_at + max lifetime)
+    pub session_sliding: bool,
+    pub session_max_lifetime_hours: i64,
+    pub impersonation_ttl_minutes: i64,
+    /// dead sessions are purged this long after expiry/revocation
+    pub session_retention_days: i64,
+    /// gateway price per SMS segment (minor units) for send estimates; None = unknown
+    pub sms_segment_price_cents: Option<i64>,
+    /// public page of the intake form; the texted link is `{url}/{token}`. None = links can't be texted
+    pub intake_form_url: Option<String>,
+    /// public page of the post-visit rating form (`{url}/{token}`). None = no feedback requests
+    pub feedback_form_url: Option<String>,
+    /// nightly export target (EXPORT_TARGET); None = exports are off
+    pub data_export: Option<crate::data_export::ExportSettings>,
+    pub session_cache: crate::session_cache::SessionCache,
+    /// staff online over GET /ws and the schedule view each has open
+    pub presence: crate::presence::Presence,
+    /// data access behind the services (Postgres in production, fakes in unit tests)
+    pub repos: crate::repos::Repos,
+}
+
+impl AppState {
+    /// Pool for read-only queries that can live with replica lag (schedule views,
+    /// reports, search): the replica when configured and healthy, else the primary.
+    /// Never use it to read back something the same request just wrote.
+    pub fn read_db(&self) -> &sqlx::PgPool {
+        crate::db::read_pool(&self.db, self.db_read.as_ref())
+    }
 }
 
 /* -------------------------
    API DTOs
 --------------------------*/
 
+/// Success envelope of every JSON endpoint: `{ "data": ... }`.
+/// Endpoints that add siblings to `data` (`next_cursor`, `warnings`, ...) keep their
+/// own response struct, still with `data` first.
+#[derive(Debug, Serialize)]
+pub struct ApiOk<T> {
+    pub data: T,
+}
+
+/// List endpoints: `{ "data": [...] }`, never a bare array.
+pub type ApiList<T> = ApiOk<Vec<T>>;
+
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
     pub username: String,
@@ -21,11 +66,6 @@ pub struct LoginRequest {
     pub remember_me: Option<bool>, // reserved for future
 }
 
-#[derive(Debug, Serialize)]
-pub struct LoginResponse {
-    pub data: LoginResponseData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct LoginResponseData {
     pub access_token: String,
@@ -34,22 +74,14 @@ pub struct LoginResponseData {
     pub clinic: ClinicProfile,
 }
 
-#[derive(Debug, Serialize)]
-pub struct MeResponse {
-    pub data: MeResponseData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct MeResponseData {
     pub dcms_user: UserProfile,
     pub clinic: ClinicProfile,
     pub session: SessionInfo,
     pub message: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct OkResponse {
-    pub data: OkData,
+    /// "en" | "mn", None = Accept-Language decides
+    pub preferred_language: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -76,6 +108,8 @@ pub struct ClinicProfile {
 pub struct SessionInfo {
     pub session_token_id: Uuid,
     pub expires_at: DateTime<Utc>,
+    pub impersonating: bool,
+    pub impersonator_user_id: Option<Uuid>,
 }
 
 /* -------------------------
@@ -88,7 +122,7 @@ pub struct UserRow {
     pub username: String,
     pub display_name: String,
     pub password_hash: String,
-    pub roles: i16,
+    pub roles: Role,
     pub is_active: bool,
 }
 
@@ -124,9 +158,16 @@ pub struct SmsRow {
     pub direction: SmsDirection,
     pub sent_at: DateTime<Utc>, // ✅ matches DB column name
     pub subject: Option<String>,
-    pub sms_text: String,
+    pub sms_text: PiiString,
     pub note: Option<String>,
     pub created_at: DateTime<Utc>, // ✅ your SQL RETURNING includes created_at
+    /// soft delete (migration 061); only admins see deleted rows (`?include_deleted=true`)
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_by_user_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -138,25 +179,395 @@ pub struct ServiceCatalogRow {
     pub default_duration_min: Option<i32>,
     pub disclaimer: Option<String>,
     pub price_cents: i32,
+    pub tax_category: String,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 /* -------------------------
-   Helpers
+   Domain enums
 --------------------------*/
 
-/// Role mapping according to your DB spec:
-/// 0 Patient, 1 Admin, 2 Manager, 3 Doctor, 4 Receptionist
-pub fn role_to_string(role: i16) -> String {
-    match role {
-        0 => "patient",
-        1 => "admin",
-        2 => "manager",
-        3 => "doctor",
-        4 => "receptionist",
-        _ => "unknown",
-    }
-    .to_string()
+/// SMALLINT-backed enum that goes over the wire as its snake_case name.
+/// Deserializing also takes the numeric DB code (JSON number, or "2" in a query
+/// string) so clients written against the numeric API keep working.
+macro_rules! smallint_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident = $code:literal => $label:literal,)+ }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+        #[sqlx(type_name = "smallint")]
+        #[repr(i16)]
+        pub enum $name {
+            $($variant = $code,)+
+        }
+
+        impl $name {
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $label,)+
+                }
+            }
+
+            pub fn from_code(code: i16) -> Option<Self> {
+                match code {
+                    $($code => Some(Self::$variant),)+
+                    _ => None,
+                }
+            }
+
+            /// Name or numeric code.
+            pub fn parse(s: &str) -> Option<Self> {
+                match s {
+                    $($label => Some(Self::$variant),)+
+                    _ => s.parse().ok().and_then(Self::from_code),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct V;
+
+                impl serde::de::Visitor<'_> for V {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(f, "one of: {}", [$($label),+].join(", "))
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<$name, E> {
+                        $name::parse(v).ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+                    }
+
+                    fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<$name, E> {
+                        i16::try_from(v)
+                            .ok()
+                            .and_then($name::from_code)
+                            .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Signed(v), &self))
+                    }
+
+                    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<$name, E> {
+                        i16::try_from(v)
+                            .ok()
+                            .and_then($name::from_code)
+                            .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Unsigned(v), &self))
+                    }
+                }
+
+                deserializer.deserialize_any(V)
+            }
+        }
+    };
+}
+
+smallint_enum! {
+    /// `dcms_user.roles` (a single role per user)
+    Role {
+        Patient = 0 => "patient",
+        Admin = 1 => "admin",
+        Manager = 2 => "manager",
+        Doctor = 3 => "doctor",
+        Receptionist = 4 => "receptionist",
+    }
+}
+
+impl Role {
+    pub fn is_staff(self) -> bool {
+        self != Role::Patient
+    }
+
+    /// clinic-wide management rights
+    pub fn is_admin_or_manager(self) -> bool {
+        matches!(self, Role::Admin | Role::Manager)
+    }
+
+    /// may be an appointment's assistant (front desk doubles as chair-side
+    /// assistants; there's no separate assistant role)
+    pub fn can_assist(self) -> bool {
+        self == Role::Receptionist
+    }
+}
+
+smallint_enum! {
+    /// `patient.gender` / `employee.gender`
+    Gender {
+        Unspecified = 0 => "unspecified",
+        Male = 1 => "male",
+        Female = 2 => "female",
+    }
+}
+
+smallint_enum! {
+    /// `appointment.status`. Arrival/seating/dismissal are also stamped as
+    /// timestamps; the status only moves on arrival and dismissal.
+    AppointmentStatus {
+        Scheduled = 0 => "scheduled",
+        Canceled = 1 => "canceled",
+        Confirmed = 2 => "confirmed",
+        NoShow = 3 => "no_show",
+        Arrived = 4 => "arrived",
+        Finished = 5 => "finished",
+    }
+}
+
+impl AppointmentStatus {
+    /// The one place appointment status changes are validated (PATCH and the
+    /// front-desk milestones). Setting the current status again is always allowed.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use AppointmentStatus::*;
+        self == next
+            || matches!(
+                (self, next),
+                (Scheduled | Confirmed, Scheduled | Confirmed | Canceled | NoShow | Arrived)
+                    | (NoShow, Scheduled | Confirmed | Arrived)
+                    | (Canceled, Scheduled)
+                    | (Arrived, Finished)
+            )
+    }
+
+    pub fn ensure_transition(self, next: Self) -> Result<(), ApiError> {
+        ensure_transition(self.can_transition_to(next), self, next)
+    }
+
+    /// Still holds the doctor's time (overlap checks, reminders).
+    pub fn occupies_slot(self) -> bool {
+        !matches!(self, AppointmentStatus::Canceled | AppointmentStatus::NoShow)
+    }
+}
+
+smallint_enum! {
+    /// `task.status`
+    TaskStatus {
+        Open = 0 => "open",
+        InProgress = 1 => "in_progress",
+        Done = 2 => "done",
+        Canceled = 3 => "canceled",
+    }
+}
+
+impl TaskStatus {
+    pub const ALL: [TaskStatus; 4] = [TaskStatus::Open, TaskStatus::InProgress, TaskStatus::Done, TaskStatus::Canceled];
+
+    /// Open tasks move anywhere; done/canceled ones can only be reopened.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        self == next || self.is_active() || next == TaskStatus::Open
+    }
+
+    pub fn ensure_transition(self, next: Self) -> Result<(), ApiError> {
+        ensure_transition(self.can_transition_to(next), self, next)
+    }
+
+    /// open or in progress
+    pub fn is_active(self) -> bool {
+        matches!(self, TaskStatus::Open | TaskStatus::InProgress)
+    }
+}
+
+smallint_enum! {
+    /// `task.priority` / `task_template.priority`
+    TaskPriority {
+        Normal = 0 => "normal",
+        High = 1 => "high",
+        Urgent = 2 => "urgent",
+    }
+}
+
+smallint_enum! {
+    /// `employee_time_off.status`
+    TimeOffStatus {
+        Pending = 0 => "pending",
+        Approved = 1 => "approved",
+        Denied = 2 => "denied",
+        Canceled = 3 => "canceled",
+    }
+}
+
+impl TimeOffStatus {
+    /// Pending requests get decided or withdrawn; approved ones can still be canceled.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        match self {
+            TimeOffStatus::Pending => next != TimeOffStatus::Pending,
+            TimeOffStatus::Approved => next == TimeOffStatus::Canceled,
+            TimeOffStatus::Denied | TimeOffStatus::Canceled => false,
+        }
+    }
+
+    pub fn ensure_transition(self, next: Self) -> Result<(), ApiError> {
+        ensure_transition(self.can_transition_to(next), self, next)
+    }
+}
+
+smallint_enum! {
+    /// `imaging_order.modality`
+    ImagingModality {
+        Periapical = 0 => "periapical",
+        Bitewing = 1 => "bitewing",
+        Occlusal = 2 => "occlusal",
+        Panoramic = 3 => "panoramic",
+        Cephalometric = 4 => "cephalometric",
+        Cbct = 5 => "cbct",
+    }
+}
+
+smallint_enum! {
+    /// `imaging_order.status`
+    ImagingStatus {
+        Requested = 0 => "requested",
+        Performed = 1 => "performed",
+        Canceled = 2 => "canceled",
+    }
+}
+
+impl ImagingStatus {
+    /// A requested image is taken or canceled; both are final.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        self == ImagingStatus::Requested && next != ImagingStatus::Requested
+    }
+
+    pub fn ensure_transition(self, next: Self) -> Result<(), ApiError> {
+        ensure_transition(self.can_transition_to(next), self, next)
+    }
+}
+
+smallint_enum! {
+    /// `patient_medical_alert.kind`
+    MedicalAlertKind {
+        Allergy = 0 => "allergy",
+        Medication = 1 => "medication",
+        Condition = 2 => "condition",
+        Other = 3 => "other",
+    }
+}
+
+smallint_enum! {
+    /// `kiosk_device.kind`: which device routes the token opens
+    DeviceKind {
+        Kiosk = 0 => "kiosk",
+        Display = 1 => "display",
+    }
+}
+
+smallint_enum! {
+    /// `patient_intake.status`
+    IntakeStatus {
+        Sent = 0 => "sent",
+        Submitted = 1 => "submitted",
+        Approved = 2 => "approved",
+        Rejected = 3 => "rejected",
+        Revoked = 4 => "revoked",
+    }
+}
+
+impl IntakeStatus {
+    /// A sent link is filled in once (or revoked); staff then approve or reject
+    /// the submission.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use IntakeStatus::*;
+        matches!((self, next), (Sent, Submitted | Revoked) | (Submitted, Approved | Rejected))
+    }
+
+    pub fn ensure_transition(self, next: Self) -> Result<(), ApiError> {
+        ensure_transition(self.can_transition_to(next), self, next)
+    }
+}
+
+fn ensure_transition(allowed: bool, from: impl std::fmt::Display, to: impl std::fmt::Display) -> Result<(), ApiError> {
+    if allowed {
+        return Ok(());
+    }
+    Err(ApiError::Conflict(
+        "INVALID_STATUS_TRANSITION",
+        format!("status cannot change from {from} to {to}"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enums_serialize_as_names_and_accept_legacy_codes() {
+        assert_eq!(serde_json::to_value(AppointmentStatus::NoShow).unwrap(), "no_show");
+        assert_eq!(serde_json::to_value(Role::Receptionist).unwrap(), "receptionist");
+
+        let from_name: TaskStatus = serde_json::from_value(serde_json::json!("in_progress")).unwrap();
+        let from_code: TaskStatus = serde_json::from_value(serde_json::json!(1)).unwrap();
+        assert_eq!((from_name, from_code), (TaskStatus::InProgress, TaskStatus::InProgress));
+        assert_eq!(TaskPriority::parse("2"), Some(TaskPriority::Urgent));
+
+        assert!(serde_json::from_value::<Gender>(serde_json::json!(3)).is_err());
+        assert!(serde_json::from_value::<Gender>(serde_json::json!("other")).is_err());
+    }
+
+    #[test]
+    fn appointment_transitions() {
+        use AppointmentStatus::*;
+        assert!(Scheduled.can_transition_to(Arrived));
+        assert!(NoShow.can_transition_to(Arrived));
+        assert!(Arrived.can_transition_to(Finished));
+        assert!(Finished.can_transition_to(Finished));
+        assert!(Canceled.can_transition_to(Scheduled));
+
+        assert!(!Scheduled.can_transition_to(Finished));
+        assert!(!Canceled.can_transition_to(Arrived));
+        assert!(!Finished.can_transition_to(Scheduled));
+        assert!(matches!(
+            Arrived.ensure_transition(Canceled),
+            Err(ApiError::Conflict("INVALID_STATUS_TRANSITION", _))
+        ));
+    }
+
+    #[test]
+    fn task_transitions() {
+        use TaskStatus::*;
+        assert!(Open.can_transition_to(Done));
+        assert!(InProgress.can_transition_to(Canceled));
+        assert!(Done.can_transition_to(Open));
+        assert!(!Done.can_transition_to(InProgress));
+        assert!(!Canceled.can_transition_to(Done));
+    }
+
+    #[test]
+    fn time_off_transitions() {
+        use TimeOffStatus::*;
+        assert!(Pending.can_transition_to(Approved));
+        assert!(Pending.can_transition_to(Denied));
+        assert!(Approved.can_transition_to(Canceled));
+        assert!(!Approved.can_transition_to(Denied));
+        assert!(!Denied.can_transition_to(Approved));
+        assert!(!Canceled.can_transition_to(Pending));
+    }
+
+    #[test]
+    fn imaging_transitions() {
+        use ImagingStatus::*;
+        assert!(Requested.can_transition_to(Performed));
+        assert!(Requested.can_transition_to(Canceled));
+        assert!(!Performed.can_transition_to(Canceled));
+        assert!(!Canceled.can_transition_to(Performed));
+    }
+
+    #[test]
+    fn intake_transitions() {
+        use IntakeStatus::*;
+        assert!(Sent.can_transition_to(Submitted));
+        assert!(Sent.can_transition_to(Revoked));
+        assert!(Submitted.can_transition_to(Approved));
+        assert!(!Sent.can_transition_to(Approved));
+        assert!(!Submitted.can_transition_to(Submitted));
+        assert!(!Rejected.can_transition_to(Approved));
+    }
 }
@@ -0,0 +1,57 @@
+// src/money.rs
+//
+// Money is always stored in minor units (i64/i32 cents); the currency is
+// clinic-wide (clinic_settings.currency_code). Tax rates are basis points
+// (1000 = 10%) keyed by tax category (clinic_settings.tax_rates); doctor
+// commission rates (employee.commission_bp) use the same unit.
+
+use serde_json::Value as JsonValue;
+
+/// clinic_settings.currency_code when there is no settings row yet
+pub const DEFAULT_CURRENCY: &str = "MNT";
+pub const DEFAULT_TAX_CATEGORY: &str = "standard";
+
+/// clinic_settings.tax_rates when there is no settings row yet.
+pub fn default_tax_rates() -> JsonValue {
+    serde_json::json!({ DEFAULT_TAX_CATEGORY: 0 })
+}
+
+/// Rate in basis points for a category; unknown categories fall back to "standard", then 0.
+pub fn tax_rate_bp(tax_rates: &JsonValue, category: &str) -> i64 {
+    tax_rates
+        .get(category)
+        .or_else(|| tax_rates.get(DEFAULT_TAX_CATEGORY))
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0)
+}
+
+/// `rate_bp` basis points of an amount, rounded half-up to the minor unit.
+pub fn bp_of(cents: i64, rate_bp: i64) -> i64 {
+    (cents * rate_bp + 5_000).div_euclid(10_000)
+}
+
+/// Tax on a net amount, rounded half-up to the minor unit.
+pub fn tax_cents(net_cents: i64, rate_bp: i64) -> i64 {
+    bp_of(net_cents, rate_bp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tax_rounds_half_up() {
+        assert_eq!(tax_cents(10_000, 1_000), 1_000);
+        assert_eq!(tax_cents(5, 1_000), 1); // 0.5 -> 1
+        assert_eq!(tax_cents(4, 1_000), 0); // 0.4 -> 0
+        assert_eq!(tax_cents(12_345, 0), 0);
+    }
+
+    #[test]
+    fn unknown_category_uses_standard() {
+        let rates = serde_json::json!({ "standard": 1000, "exempt": 0 });
+        assert_eq!(tax_rate_bp(&rates, "exempt"), 0);
+        assert_eq!(tax_rate_bp(&rates, "lab"), 1000);
+        assert_eq!(tax_rate_bp(&serde_json::json!({}), "lab"), 0);
+    }
+}
@@ -0,0 +1,43 @@
+// src/notifications.rs
+//
+// In-app notification subsystem (notification table).
+// Routes call these helpers; delivery to clients is by polling GET /notifications.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Notify everyone involved in a task (creator, assignee, watchers) except the actor.
+/// Returns how many notifications were created.
+pub async fn notify_task_participants(
+    db: &PgPool,
+    task_id: Uuid,
+    actor_employee_id: Uuid,
+    kind: &str,
+    body: &str,
+) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query(
+        r#"
+        INSERT INTO notification (recipient_employee_id, kind, title, body, task_id)
+        SELECT DISTINCT r.employee_id, $2, t.title, $3, t.task_id
+        FROM task t
+        CROSS JOIN LATERAL (
+          SELECT t.created_by_employee_id AS employee_id
+          UNION
+          SELECT t.assigned_to_employee_id
+          UNION
+          SELECT w.employee_id FROM task_watcher w WHERE w.task_id = t.task_id
+        ) r
+        WHERE t.task_id = $1
+          AND r.employee_id IS NOT NULL
+          AND r.employee_id <> $4
+        "#,
+    )
+    .bind(task_id)
+    .bind(kind)
+    .bind(body)
+    .bind(actor_employee_id)
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected())
+}
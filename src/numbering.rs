@@ -0,0 +1,220 @@
+// src/numbering.rs
+//
+// Patient register numbers and employee display numbers, picked by the server:
+// - register_number = prefix + zero-padded patient_register_seq value, shaped by
+//   clinic_settings.register_number_format (default {"prefix":"P","digits":6} -> P000123)
+// - employee_display_number = the next employee_display_seq value (a plain number)
+// Generated values skip numbers already entered by hand (e.g. a national ID typed
+// in as the register number); the unique constraints still catch races.
+// The create forms ask GET /patients/check_register_number and
+// GET /employees/check_display_number before submitting a number of their own.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::{ApiError, DbError};
+
+const MAX_PREFIX_LEN: usize = 10;
+const MAX_DIGITS: u8 = 12;
+pub const MAX_REGISTER_NUMBER_LEN: usize = 32;
+/// sequence values tried before giving up (each one taken by hand-entered numbers)
+const MAX_SKIPS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegisterNumberFormat {
+    pub prefix: String,
+    pub digits: u8,
+}
+
+impl Default for RegisterNumberFormat {
+    fn default() -> Self {
+        Self { prefix: "P".into(), digits: 6 }
+    }
+}
+
+impl RegisterNumberFormat {
+    /// Parses and validates a format as stored in clinic_settings.register_number_format.
+    pub fn from_json(v: &JsonValue) -> Result<Self, String> {
+        let f: Self = serde_json::from_value(v.clone()).map_err(|e| format!("register_number_format: {e}"))?;
+        if f.prefix.chars().count() > MAX_PREFIX_LEN || !f.prefix.chars().all(|c| c.is_alphanumeric() || c == '-') {
+            return Err(format!(
+                "register_number_format.prefix must be 0..{MAX_PREFIX_LEN} letters, digits or '-'"
+            ));
+        }
+        if !(1..=MAX_DIGITS).contains(&f.digits) {
+            return Err(format!("register_number_format.digits must be 1..{MAX_DIGITS}"));
+        }
+        Ok(f)
+    }
+
+    pub fn format(&self, n: i64) -> String {
+        format!("{}{:0width$}", self.prefix, n, width = usize::from(self.digits))
+    }
+}
+
+pub fn default_format_json() -> JsonValue {
+    serde_json::to_value(RegisterNumberFormat::default()).unwrap_or_default()
+}
+
+async fn load_format(conn: &mut PgConnection) -> Result<RegisterNumberFormat, ApiError> {
+    let raw: Option<JsonValue> =
+        sqlx::query_scalar("SELECT register_number_format FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(&mut *conn)
+            .await?;
+    let Some(raw) = raw else {
+        return Ok(RegisterNumberFormat::default());
+    };
+    Ok(RegisterNumberFormat::from_json(&raw).unwrap_or_else(|e| {
+        // validated on write
+        tracing::warn!("{e}; using the default register number format");
+        RegisterNumberFormat::default()
+    }))
+}
+
+/// A hand-entered register number, trimmed: 1..32 chars without spaces.
+pub fn normalize_register_number(value: &str) -> Result<String, ApiError> {
+    let value = value.trim();
+    if value.is_empty()
+        || value.chars().count() > MAX_REGISTER_NUMBER_LEN
+        || value.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("register_number must be 1..{MAX_REGISTER_NUMBER_LEN} chars without spaces"),
+        ));
+    }
+    Ok(value.to_string())
+}
+
+/// `except_patient`: the patient being edited, whose own number doesn't count.
+pub async fn register_number_taken(
+    conn: &mut PgConnection,
+    value: &str,
+    except_patient: Option<Uuid>,
+) -> Result<bool, ApiError> {
+    Ok(sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM patient WHERE register_number = $1 AND patient_id IS DISTINCT FROM $2)",
+    )
+    .bind(value)
+    .bind(except_patient)
+    .fetch_one(conn)
+    .await?)
+}
+
+pub async fn employee_number_taken(
+    conn: &mut PgConnection,
+    value: i64,
+    except_employee: Option<Uuid>,
+) -> Result<bool, ApiError> {
+    Ok(sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM employee WHERE employee_display_number = $1 AND employee_id IS DISTINCT FROM $2)",
+    )
+    .bind(value)
+    .bind(except_employee)
+    .fetch_one(conn)
+    .await?)
+}
+
+pub async fn next_register_number(conn: &mut PgConnection) -> Result<String, ApiError> {
+    let format = load_format(conn).await?;
+    for _ in 0..MAX_SKIPS {
+        let n: i64 = sqlx::query_scalar("SELECT nextval('patient_register_seq')").fetch_one(&mut *conn).await?;
+        let candidate = format.format(n);
+        if !register_number_taken(conn, &candidate, None).await? {
+            return Ok(candidate);
+        }
+    }
+    Err(ApiError::Internal(format!("no free register number after {MAX_SKIPS} tries")))
+}
+
+pub async fn next_employee_display_number(conn: &mut PgConnection) -> Result<i64, ApiError> {
+    for _ in 0..MAX_SKIPS {
+        let n: i64 = sqlx::query_scalar("SELECT nextval('employee_display_seq')").fetch_one(&mut *conn).await?;
+        if !employee_number_taken(conn, n, None).await? {
+            return Ok(n);
+        }
+    }
+    Err(ApiError::Internal(format!("no free employee display number after {MAX_SKIPS} tries")))
+}
+
+/// GET /patients/check_register_number, GET /employees/check_display_number
+#[derive(Debug, Serialize)]
+pub struct NumberCheckDto {
+    /// as it would be stored (trimmed)
+    pub value: String,
+    pub available: bool,
+    pub reason: Option<&'static str>, // "invalid" | "taken"
+}
+
+pub async fn check_register_number(
+    conn: &mut PgConnection,
+    value: &str,
+    except_patient: Option<Uuid>,
+) -> Result<NumberCheckDto, ApiError> {
+    let Ok(value) = normalize_register_number(value) else {
+        return Ok(NumberCheckDto { value: value.trim().to_string(), available: false, reason: Some("invalid") });
+    };
+    let taken = register_number_taken(conn, &value, except_patient).await?;
+    Ok(NumberCheckDto { value, available: !taken, reason: taken.then_some("taken") })
+}
+
+pub async fn check_employee_number(
+    conn: &mut PgConnection,
+    value: &str,
+    except_employee: Option<Uuid>,
+) -> Result<NumberCheckDto, ApiError> {
+    let Some(n) = value.trim().parse::<i64>().ok().filter(|n| *n > 0) else {
+        return Ok(NumberCheckDto { value: value.trim().to_string(), available: false, reason: Some("invalid") });
+    };
+    let taken = employee_number_taken(conn, n, except_employee).await?;
+    Ok(NumberCheckDto { value: n.to_string(), available: !taken, reason: taken.then_some("taken") })
+}
+
+/// Patient writes: a taken register number (also one taken between the check and
+/// the write) is 409 REGISTER_NUMBER_TAKEN; anything else as usual.
+pub fn register_number_conflict(e: sqlx::Error) -> ApiError {
+    match DbError::from(e) {
+        DbError::UniqueViolation { constraint: Some(c) } if c == "patient_register_number_key" => {
+            ApiError::Conflict("REGISTER_NUMBER_TAKEN", "register_number is already in use".into())
+        }
+        other => other.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn format_validation_and_padding() {
+        assert_eq!(RegisterNumberFormat::from_json(&default_format_json()).unwrap(), RegisterNumberFormat::default());
+        assert_eq!(RegisterNumberFormat::default().format(123), "P000123");
+        let f = RegisterNumberFormat::from_json(&json!({ "prefix": "ДК-", "digits": 3 })).unwrap();
+        assert_eq!(f.format(7), "ДК-007");
+        // never cut
+        assert_eq!(f.format(12345), "ДК-12345");
+        let bad = [
+            json!({ "prefix": "P 1", "digits": 6 }),
+            json!({ "prefix": "VERYLONGPREFIX", "digits": 6 }),
+            json!({ "prefix": "P", "digits": 0 }),
+            json!({ "prefix": "P", "digits": 13 }),
+            json!({ "prefix": "P" }),
+        ];
+        for v in bad {
+            assert!(RegisterNumberFormat::from_json(&v).is_err(), "{v}");
+        }
+    }
+
+    #[test]
+    fn hand_entered_numbers_are_trimmed_and_single_words() {
+        assert_eq!(normalize_register_number("  УБ99112233 ").unwrap(), "УБ99112233");
+        assert!(normalize_register_number("   ").is_err());
+        assert!(normalize_register_number("УБ 99").is_err());
+        assert!(normalize_register_number(&"9".repeat(MAX_REGISTER_NUMBER_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+// src/onboarding.rs
+//
+// First-visit onboarding checklist (migration 057): intake form, consent, insurance
+// card, X-ray, ... from the clinic's catalog (clinic_routes, /clinic/onboarding_items).
+// - booking with is_new_patient copies the active items onto the appointment
+//   (services::appointments::insert, same transaction)
+// - reception ticks them off: PUT /appointments/{id}/onboarding/{onboarding_item_id}
+// - items marked required_before_seating and still open make POST .../seat answer
+//   409 ONBOARDING_INCOMPLETE (AppointmentRepo::seating_blockers)
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+pub const MAX_NOTE_LEN: usize = 500;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ChecklistItemDto {
+    pub onboarding_item_id: Uuid,
+    pub code: String,
+    pub label: String,
+    pub required_before_seating: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub completed_by_user_id: Option<Uuid>,
+    pub completed_by_name: Option<String>,
+    pub note: Option<String>,
+}
+
+/// An open item that keeps the patient from being seated (`error.details.items`).
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct SeatingBlocker {
+    pub onboarding_item_id: Uuid,
+    pub code: String,
+    pub label: String,
+}
+
+/// Copies the active catalog items onto a new patient's appointment.
+pub async fn attach(conn: &mut PgConnection, appointment_id: Uuid) -> Result<(), ApiError> {
+    sqlx::query(
+        r#"
+        INSERT INTO appointment_onboarding_item (appointment_id, onboarding_item_id)
+        SELECT $1, onboarding_item_id FROM onboarding_item WHERE is_active
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(appointment_id)
+    .execute(conn)
+    .await
+    .map_err(ApiError::write_failed("ONBOARDING_ATTACH_FAILED"))?;
+    Ok(())
+}
+
+/// The appointment's checklist in catalog order; empty when it has none.
+pub async fn checklist(db: &PgPool, appointment_id: Uuid) -> Result<Vec<ChecklistItemDto>, ApiError> {
+    Ok(sqlx::query_as::<_, ChecklistItemDto>(
+        r#"
+        SELECT
+          oi.onboarding_item_id,
+          oi.code,
+          oi.label,
+          oi.required_before_seating,
+          aoi.completed_at,
+          aoi.completed_by_user_id,
+          u.display_name AS completed_by_name,
+          aoi.note
+        FROM appointment_onboarding_item aoi
+        JOIN onboarding_item oi ON oi.onboarding_item_id = aoi.onboarding_item_id
+        LEFT JOIN dcms_user u ON u.user_id = aoi.completed_by_user_id
+        WHERE aoi.appointment_id = $1
+        ORDER BY oi.sort_order, oi.label
+        "#,
+    )
+    .bind(appointment_id)
+    .fetch_all(db)
+    .await?)
+}
+
+/// Ticks an item off (or reopens it); a completed item keeps its first completion.
+/// `note`: None keeps it, "" clears it.
+/// NOT_FOUND when the item isn't on the appointment's checklist.
+pub async fn set_done(
+    db: &PgPool,
+    appointment_id: Uuid,
+    onboarding_item_id: Uuid,
+    user_id: Uuid,
+    done: bool,
+    note: Option<&str>,
+) -> Result<(), ApiError> {
+    let updated = sqlx::query(
+        r#"
+        UPDATE appointment_onboarding_item
+        SET
+          completed_at         = CASE WHEN $3 THEN COALESCE(completed_at, now()) END,
+          completed_by_user_id = CASE WHEN $3 THEN COALESCE(completed_by_user_id, $4) END,
+          note                 = CASE WHEN $5::text IS NULL THEN note ELSE NULLIF($5, '') END
+        WHERE appointment_id = $1 AND onboarding_item_id = $2
+        "#,
+    )
+    .bind(appointment_id)
+    .bind(onboarding_item_id)
+    .bind(done)
+    .bind(user_id)
+    .bind(note)
+    .execute(db)
+    .await
+    .map_err(ApiError::write_failed("ONBOARDING_UPDATE_FAILED"))?
+    .rows_affected();
+    if updated == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "item is not on this appointment's checklist".into()));
+    }
+    Ok(())
+}
+
+/// 409 ONBOARDING_INCOMPLETE listing the open required items, if any.
+pub fn ensure_can_seat(blockers: Vec<SeatingBlocker>) -> Result<(), ApiError> {
+    if blockers.is_empty() {
+        return Ok(());
+    }
+    let labels: Vec<&str> = blockers.iter().map(|b| b.label.as_str()).collect();
+    let message = format!("complete before seating: {}", labels.join(", "));
+    Err(ApiError::ConflictWithDetails(
+        "ONBOARDING_INCOMPLETE",
+        message,
+        serde_json::json!({ "items": blockers }),
+    ))
+}
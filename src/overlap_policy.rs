@@ -0,0 +1,264 @@
+// src/overlap_policy.rs
+//
+// Double-booking policy (clinic_settings.overlap_policy), per doctor:
+// - strict:     no overlapping appointments (the default)
+// - concurrent: up to `max_concurrent` appointments at the same moment
+// - warn:       overlaps are allowed, the response carries a warning
+// Admin/manager can book past the policy with `override_overlap`; each override
+// is written to audit_log. Checks run under a per-doctor transaction lock, and
+// appointments that don't overlap anything stay guarded by the
+// appointment_no_overlap_doctor exclusion constraint.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{audit, error::ApiError, middleware::auth_context::AuthContext};
+
+const MAX_CONCURRENT_LIMIT: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum OverlapRule {
+    Strict,
+    Concurrent { max_concurrent: u32 },
+    Warn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OverlapPolicy {
+    pub default: OverlapRule,
+    #[serde(default)]
+    pub doctors: HashMap<Uuid, OverlapRule>,
+}
+
+impl Default for OverlapPolicy {
+    fn default() -> Self {
+        Self {
+            default: OverlapRule::Strict,
+            doctors: HashMap::new(),
+        }
+    }
+}
+
+impl OverlapPolicy {
+    /// Parses and validates a policy as stored in clinic_settings.overlap_policy.
+    pub fn from_json(v: &JsonValue) -> Result<Self, String> {
+        let p: Self = serde_json::from_value(v.clone()).map_err(|e| format!("overlap_policy: {e}"))?;
+        for rule in std::iter::once(&p.default).chain(p.doctors.values()) {
+            if let OverlapRule::Concurrent { max_concurrent } = rule
+                && !(2..=MAX_CONCURRENT_LIMIT).contains(max_concurrent)
+            {
+                return Err(format!(
+                    "overlap_policy.max_concurrent must be 2..{MAX_CONCURRENT_LIMIT}"
+                ));
+            }
+        }
+        Ok(p)
+    }
+
+    pub fn rule_for(&self, doctor_employee_id: Uuid) -> OverlapRule {
+        self.doctors.get(&doctor_employee_id).copied().unwrap_or(self.default)
+    }
+}
+
+pub fn default_policy_json() -> JsonValue {
+    serde_json::to_value(OverlapPolicy::default()).unwrap_or_default()
+}
+
+async fn load_policy(conn: &mut PgConnection) -> Result<OverlapPolicy, ApiError> {
+    let raw: Option<JsonValue> =
+        sqlx::query_scalar("SELECT overlap_policy FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(&mut *conn)
+            .await?;
+    let Some(raw) = raw else {
+        return Ok(OverlapPolicy::default());
+    };
+    Ok(OverlapPolicy::from_json(&raw).unwrap_or_else(|e| {
+        tracing::warn!("{e}; falling back to strict overlap policy");
+        OverlapPolicy::default()
+    }))
+}
+
+/// Result of an overlap check for one appointment time range.
+#[derive(Debug, Default)]
+pub struct OverlapOutcome {
+    /// store as appointment.overlap_allowed (exempts the row from the exclusion constraint)
+    pub overlap_allowed: bool,
+    pub warnings: Vec<String>,
+    /// set when a manager override was needed; pass to `record_override`
+    pub overridden: Option<JsonValue>,
+}
+
+/// Checks the doctor's schedule against the policy. Must run inside the
+/// transaction that writes the appointment: it takes a per-doctor lock that is
+/// held until commit, so concurrent bookings are checked one after another.
+pub async fn check(
+    conn: &mut PgConnection,
+    auth: &AuthContext,
+    doctor_employee_id: Uuid,
+    start_at: DateTime<Utc>,
+    end_at: DateTime<Utc>,
+    exclude_appointment_id: Option<Uuid>,
+    override_overlap: bool,
+) -> Result<OverlapOutcome, ApiError> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('appointment_doctor:' || $1::text))")
+        .bind(doctor_employee_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let existing: Vec<(Uuid, DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        SELECT appointment_id, start_at, end_at
+        FROM appointment
+        WHERE doctor_employee_id = $1
+          AND status NOT IN (1,3)
+          AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+          AND ($4::uuid IS NULL OR appointment_id <> $4)
+        ORDER BY start_at
+        "#,
+    )
+    .bind(doctor_employee_id)
+    .bind(start_at)
+    .bind(end_at)
+    .bind(exclude_appointment_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if existing.is_empty() {
+        return Ok(OverlapOutcome::default());
+    }
+
+    let rule = load_policy(conn).await?.rule_for(doctor_employee_id);
+    let intervals: Vec<_> = existing.iter().map(|(_, s, e)| (*s, *e)).collect();
+    // the new appointment counts too
+    let concurrent = peak_concurrency(start_at, end_at, &intervals) + 1;
+
+    let allowed = match rule {
+        OverlapRule::Strict => false,
+        OverlapRule::Concurrent { max_concurrent } => concurrent <= max_concurrent as usize,
+        OverlapRule::Warn => true,
+    };
+    let mut warnings = Vec::new();
+    if rule == OverlapRule::Warn {
+        warnings.push(format!(
+            "overlaps {} other appointment(s) of this doctor",
+            existing.len()
+        ));
+    }
+
+    if allowed {
+        return Ok(OverlapOutcome {
+            overlap_allowed: true,
+            warnings,
+            overridden: None,
+        });
+    }
+
+    if !(override_overlap && (auth.role.is_admin_or_manager())) {
+        return Err(ApiError::Conflict(
+            "APPOINTMENT_OVERLAP",
+            match rule {
+                OverlapRule::Concurrent { max_concurrent } => format!(
+                    "doctor would have {concurrent} appointments at once (max {max_concurrent})"
+                ),
+                _ => format!("doctor already has {} appointment(s) at this time", existing.len()),
+            },
+        ));
+    }
+
+    warnings.push("overlap policy overridden".into());
+    Ok(OverlapOutcome {
+        overlap_allowed: true,
+        warnings,
+        overridden: Some(serde_json::json!({
+            "doctor_employee_id": doctor_employee_id,
+            "start_at": start_at,
+            "end_at": end_at,
+            "rule": rule,
+            "concurrent": concurrent,
+            "conflicting_appointment_ids": existing.iter().map(|(id, _, _)| *id).collect::<Vec<_>>(),
+        })),
+    })
+}
+
+/// Audit entry for a manager override (same transaction as the booking).
+pub async fn record_override(
+    conn: &mut PgConnection,
+    auth: &AuthContext,
+    appointment_id: Uuid,
+    outcome: &OverlapOutcome,
+) -> Result<(), ApiError> {
+    if let Some(details) = &outcome.overridden {
+        audit::record(
+            &mut *conn,
+            auth,
+            "appointment.overlap_override",
+            "appointment",
+            Some(appointment_id),
+            details.clone(),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Most existing appointments running at the same moment inside [start, end).
+fn peak_concurrency(start: DateTime<Utc>, end: DateTime<Utc>, existing: &[(DateTime<Utc>, DateTime<Utc>)]) -> usize {
+    let mut events: Vec<(DateTime<Utc>, i32)> = Vec::with_capacity(existing.len() * 2);
+    for (s, e) in existing {
+        let (s, e) = ((*s).max(start), (*e).min(end));
+        if s < e {
+            events.push((s, 1));
+            events.push((e, -1));
+        }
+    }
+    // ends before starts at the same instant: back-to-back is not concurrent
+    events.sort();
+
+    let (mut cur, mut peak) = (0i32, 0i32);
+    for (_, delta) in events {
+        cur += delta;
+        peak = peak.max(cur);
+    }
+    peak as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 5, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn peak_counts_simultaneous_appointments() {
+        let existing = [(at(9, 0), at(9, 30)), (at(9, 30), at(10, 0)), (at(9, 15), at(9, 45))];
+        assert_eq!(peak_concurrency(at(9, 0), at(10, 0), &existing), 2);
+        assert_eq!(peak_concurrency(at(9, 50), at(10, 30), &existing), 1);
+        assert_eq!(peak_concurrency(at(10, 0), at(10, 30), &existing), 0);
+    }
+
+    #[test]
+    fn parses_policy() {
+        let doctor = Uuid::nil();
+        let p = OverlapPolicy::from_json(&serde_json::json!({
+            "default": { "mode": "strict" },
+            "doctors": { doctor.to_string(): { "mode": "concurrent", "max_concurrent": 2 } }
+        }))
+        .unwrap();
+        assert_eq!(p.rule_for(doctor), OverlapRule::Concurrent { max_concurrent: 2 });
+        assert_eq!(p.rule_for(Uuid::from_u128(1)), OverlapRule::Strict);
+
+        assert_eq!(OverlapPolicy::from_json(&default_policy_json()).unwrap(), OverlapPolicy::default());
+        assert!(OverlapPolicy::from_json(&serde_json::json!({ "default": { "mode": "concurrent", "max_concurrent": 1 } })).is_err());
+        assert!(OverlapPolicy::from_json(&serde_json::json!({ "default": { "mode": "sometimes" } })).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+// src/pdf.rs
+//
+// Minimal text-only PDF output (A4, Helvetica) for generated patient documents.
+// Uses the standard Type1 font with WinAnsi encoding, so no font file is embedded;
+// characters outside Latin-1 (e.g. Cyrillic) are replaced with '?'. Embed a TTF
+// font here if documents need to be printed in Mongolian.
+
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+const PAGE_W: f32 = 595.0;
+const PAGE_H: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const TITLE_SIZE: f32 = 16.0;
+const BODY_SIZE: f32 = 11.0;
+const LEADING: f32 = 15.0;
+const WRAP_COLS: usize = 90;
+
+fn win_ansi(s: &str) -> Vec<u8> {
+    s.chars()
+        .map(|c| if (c as u32) < 256 { c as u8 } else { b'?' })
+        .collect()
+}
+
+/// Greedy word wrap at `cols` characters; blank lines are kept.
+fn wrap(text: &str, cols: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    for para in text.lines() {
+        let mut line = String::new();
+        for word in para.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > cols {
+                out.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        out.push(line);
+    }
+    out
+}
+
+/// Renders a title and a plain-text body into a PDF document.
+pub fn text_document(title: &str, body: &str) -> Vec<u8> {
+    let lines = wrap(body, WRAP_COLS);
+    let first_page_lines = ((PAGE_H - 2.0 * MARGIN - 2.0 * LEADING) / LEADING) as usize;
+    let page_lines = ((PAGE_H - 2.0 * MARGIN) / LEADING) as usize;
+
+    let mut pages: Vec<&[String]> = Vec::new();
+    let (first, mut rest) = lines.split_at(first_page_lines.min(lines.len()));
+    pages.push(first);
+    while !rest.is_empty() {
+        let (chunk, tail) = rest.split_at(page_lines.min(rest.len()));
+        pages.push(chunk);
+        rest = tail;
+    }
+
+    let catalog_id = Ref::new(1);
+    let tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let font_name = Name(b"F1");
+    let page_ids: Vec<Ref> = (0..pages.len()).map(|i| Ref::new(4 + 2 * i as i32)).collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(tree_id);
+    pdf.pages(tree_id).kids(page_ids.iter().copied()).count(pages.len() as i32);
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    for (i, chunk) in pages.iter().enumerate() {
+        let page_id = page_ids[i];
+        let content_id = Ref::new(page_id.get() + 1);
+
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_W, PAGE_H));
+        page.parent(tree_id);
+        page.contents(content_id);
+        page.resources().fonts().pair(font_name, font_id);
+        page.finish();
+
+        let mut content = Content::new();
+        content.begin_text();
+        content.set_leading(LEADING);
+        content.next_line(MARGIN, PAGE_H - MARGIN);
+        if i == 0 {
+            content.set_font(font_name, TITLE_SIZE);
+            content.show(Str(&win_ansi(title)));
+            content.next_line_using_leading();
+            content.next_line_using_leading();
+        }
+        content.set_font(font_name, BODY_SIZE);
+        for line in chunk.iter() {
+            content.show(Str(&win_ansi(line)));
+            content.next_line_using_leading();
+        }
+        content.end_text();
+        pdf.stream(content_id, &content.finish());
+    }
+
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_and_produces_a_pdf() {
+        let lines = wrap("one two three\n\nfour", 9);
+        assert_eq!(lines, vec!["one two", "three", "", "four"]);
+
+        let bytes = text_document("Referral", &"word ".repeat(5_000));
+        assert!(bytes.starts_with(b"%PDF-"));
+        assert!(bytes.windows(5).any(|w| w == b"%%EOF"));
+    }
+}
@@ -0,0 +1,603 @@
+// src/permissions.rs
+//
+// Route-level access policy: which roles may call each endpoint at all. Every route
+// of the API is listed here (paths relative to /api/vN). `enforce` checks the table
+// before the handler runs, and GET /admin/permissions serves the same table so the
+// client hides what the server would refuse anyway.
+//
+// This is only the outer gate. Handlers still do the finer checks (a doctor's own
+// appointments, a manager deciding someone else's time off, ...), summed up in
+// `note` so the matrix says what a 200 doesn't.
+//
+// Adding a route means adding its row; the tests fail for a route without one.
+
+use axum::{
+    extract::{FromRequestParts, MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+
+use crate::{
+    error::ApiError,
+    middleware::auth_context::AuthContext,
+    models::{AppState, Role},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    /// no login (sign-in itself, the texted intake/feedback forms, GET /ws, which
+    /// signs in with its first message)
+    Public,
+    /// kiosk or display token (middleware::kiosk_context), never a user session
+    Device,
+    /// a user session with one of `RouteRule::roles`
+    Session,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteRule {
+    #[serde(serialize_with = "serialize_method")]
+    pub method: Method,
+    pub path: &'static str,
+    pub access: Access,
+    /// allowed roles for Session routes; empty otherwise
+    pub roles: &'static [Role],
+    /// further restrictions the handler applies
+    pub note: Option<&'static str>,
+}
+
+fn serialize_method<S: serde::Serializer>(m: &Method, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(m.as_str())
+}
+
+const ANY: &[Role] = &[Role::Patient, Role::Admin, Role::Manager, Role::Doctor, Role::Receptionist];
+const STAFF: &[Role] = &[Role::Admin, Role::Manager, Role::Doctor, Role::Receptionist];
+/// appointment and task managers (appointment_routes / task_routes `can_manage_*`)
+const FRONT_DESK: &[Role] = &[Role::Admin, Role::Manager, Role::Receptionist];
+const CLINICAL: &[Role] = &[Role::Admin, Role::Manager, Role::Doctor];
+const ADMIN_MANAGER: &[Role] = &[Role::Admin, Role::Manager];
+const ADMIN: &[Role] = &[Role::Admin];
+
+const DOCTOR_OWN: &str = "doctors: their own appointments/schedule only";
+const DOCTOR_TASKS: &str = "doctors: tasks they created, are assigned or watch";
+const OWN_TIME_OFF: &str = "own time off unless admin/manager";
+
+const fn public(method: Method, path: &'static str) -> RouteRule {
+    RouteRule { method, path, access: Access::Public, roles: &[], note: None }
+}
+
+const fn device(method: Method, path: &'static str, note: &'static str) -> RouteRule {
+    RouteRule { method, path, access: Access::Device, roles: &[], note: Some(note) }
+}
+
+const fn session(method: Method, path: &'static str, roles: &'static [Role]) -> RouteRule {
+    RouteRule { method, path, access: Access::Session, roles, note: None }
+}
+
+const fn scoped(method: Method, path: &'static str, roles: &'static [Role], note: &'static str) -> RouteRule {
+    RouteRule { method, path, access: Access::Session, roles, note: Some(note) }
+}
+
+const GET: Method = Method::GET;
+const POST: Method = Method::POST;
+const PUT: Method = Method::PUT;
+const PATCH: Method = Method::PATCH;
+const DELETE: Method = Method::DELETE;
+
+pub static ROUTES: &[RouteRule] = &[
+    // auth_routes
+    public(POST, "/auth/login"),
+    public(POST, "/auth/patient/login"),
+    public(POST, "/auth/hq/login"),
+    session(GET, "/auth/me", ANY),
+    session(PUT, "/auth/me/language", ANY),
+    session(GET, "/auth/my_logins", ANY),
+    session(POST, "/auth/logout", ANY),
+    session(POST, "/auth/logout_all_except_current", ANY),
+    session(POST, "/auth/refresh", ANY),
+    session(GET, "/auth/sessions", ANY),
+    scoped(GET, "/auth/sessions/{session_token_id}", ANY, "other users' sessions: admin/manager"),
+    scoped(POST, "/auth/sessions/{session_token_id}/extend", ANY, "other users' sessions: admin/manager"),
+    session(POST, "/auth/sessions/revoke_all", ANY),
+    scoped(POST, "/auth/sessions/{session_token_id}/revoke", ANY, "own sessions only (admins: /admin/sessions)"),
+    session(POST, "/auth/impersonate/{user_id}", ADMIN),
+    session(POST, "/auth/change_password", ANY),
+    session(POST, "/auth/reset_password", ADMIN_MANAGER),
+    // user_routes
+    session(GET, "/users", ADMIN_MANAGER),
+    scoped(POST, "/users", ADMIN_MANAGER, "only admin creates admins"),
+    session(GET, "/users/{user_id}", ADMIN_MANAGER),
+    scoped(PATCH, "/users/{user_id}", ADMIN_MANAGER, "only admin grants or revokes the admin role"),
+    session(POST, "/users/{user_id}/disable", ADMIN_MANAGER),
+    session(POST, "/users/{user_id}/enable", ADMIN_MANAGER),
+    session(POST, "/users/{user_id}/employee_profile", ADMIN_MANAGER),
+    session(GET, "/users/{user_id}/login_history", ADMIN),
+    // admin_routes
+    session(GET, "/admin/sessions", ADMIN),
+    session(POST, "/admin/sessions/{session_token_id}/revoke", ADMIN),
+    session(POST, "/admin/sessions/cleanup", ADMIN),
+    session(GET, "/admin/audit", ADMIN),
+    session(POST, "/admin/reports/refresh", ADMIN),
+    session(POST, "/admin/backup", ADMIN),
+    session(GET, "/admin/exports", ADMIN),
+    session(POST, "/admin/exports/run", ADMIN),
+    session(GET, "/admin/exports/{run_id}", ADMIN),
+    session(GET, "/admin/permissions", ANY),
+    // service_routes
+    session(GET, "/services", ANY),
+    session(GET, "/services/{service_id}/providers", ANY),
+    // clinic_routes
+    session(GET, "/clinic", ANY),
+    session(PATCH, "/clinic", ADMIN),
+    session(GET, "/clinic/settings", ANY),
+    session(PATCH, "/clinic/settings", ADMIN),
+    session(GET, "/clinic/holidays", ANY),
+    session(POST, "/clinic/holidays", ADMIN),
+    session(DELETE, "/clinic/holidays/{holiday_id}", ADMIN),
+    session(GET, "/clinic/locations", ANY),
+    session(POST, "/clinic/locations", ADMIN),
+    session(PATCH, "/clinic/locations/{location_id}", ADMIN),
+    session(PUT, "/clinic/locations/{location_id}/employees/{employee_id}", ADMIN),
+    session(GET, "/clinic/locations/{location_id}/access", ADMIN),
+    session(POST, "/clinic/locations/{location_id}/access", ADMIN),
+    session(DELETE, "/clinic/locations/{location_id}/access/{user_id}", ADMIN),
+    session(GET, "/clinic/referral_sources", ANY),
+    session(POST, "/clinic/referral_sources", ADMIN),
+    session(PATCH, "/clinic/referral_sources/{referral_source_id}", ADMIN),
+    session(GET, "/clinic/onboarding_items", ANY),
+    session(POST, "/clinic/onboarding_items", ADMIN),
+    session(PATCH, "/clinic/onboarding_items/{onboarding_item_id}", ADMIN),
+    session(GET, "/clinic/meta", ANY),
+    // patient_comm_routes
+    session(GET, "/patients/{patient_id}/phone_numbers", STAFF),
+    session(POST, "/patients/{patient_id}/phone_numbers", STAFF),
+    session(GET, "/patients/{patient_id}/phone_numbers_alias", STAFF),
+    session(POST, "/phone_numbers/normalize", STAFF),
+    session(GET, "/phone_numbers/lookup", STAFF),
+    session(GET, "/phone_numbers/{phone_number_id}", STAFF),
+    session(PATCH, "/phone_numbers/{phone_number_id}", STAFF),
+    session(DELETE, "/phone_numbers/{phone_number_id}", ADMIN_MANAGER),
+    session(POST, "/phone_numbers/{phone_number_id}/make_primary", STAFF),
+    session(POST, "/phone_numbers/{phone_number_id}/transfer", STAFF),
+    session(GET, "/phone_numbers/{phone_number_id}/consent", STAFF),
+    session(PUT, "/phone_numbers/{phone_number_id}/consent", STAFF),
+    session(GET, "/patients/{patient_id}/consent", STAFF),
+    session(PUT, "/patients/{patient_id}/consent", STAFF),
+    session(GET, "/patients/{patient_id}/contact_preferences", STAFF),
+    session(PUT, "/patients/{patient_id}/contact_preferences", STAFF),
+    session(GET, "/phone_numbers/{phone_number_id}/sms", STAFF),
+    session(POST, "/phone_numbers/{phone_number_id}/sms", STAFF),
+    session(GET, "/sms", STAFF),
+    session(GET, "/sms/{sms_id}", STAFF),
+    session(DELETE, "/sms/{sms_id}", ADMIN),
+    session(POST, "/sms/{sms_id}/restore", ADMIN),
+    session(POST, "/sms/bulk_send", STAFF),
+    session(GET, "/sms/batches/{batch_id}", STAFF),
+    session(POST, "/sms/render", STAFF),
+    session(POST, "/sms/estimate", STAFF),
+    session(GET, "/patients/{patient_id}/conversation", STAFF),
+    session(POST, "/patients/{patient_id}/conversation/read", STAFF),
+    // patient_routes
+    session(POST, "/patients", STAFF),
+    session(GET, "/patients", STAFF),
+    session(GET, "/patients/check_register_number", STAFF),
+    session(GET, "/patients/{patient_id}", STAFF),
+    session(PATCH, "/patients/{patient_id}", STAFF),
+    session(GET, "/patients/{patient_id}/summary", STAFF),
+    session(GET, "/patients/{patient_id}/export", ADMIN),
+    session(POST, "/patients/{patient_id}/archive", STAFF),
+    session(POST, "/patients/{patient_id}/restore", STAFF),
+    session(POST, "/patients/{patient_id}/request_deletion", STAFF),
+    session(POST, "/patients/{patient_id}/cancel_deletion", ADMIN),
+    session(POST, "/patients/{patient_id}/link_user/{user_id}", STAFF),
+    session(POST, "/patients/{patient_id}/unlink_user", STAFF),
+    // appointment_routes
+    scoped(GET, "/appointments/week", STAFF, DOCTOR_OWN),
+    scoped(GET, "/appointments/day", STAFF, DOCTOR_OWN),
+    scoped(GET, "/appointments/today", STAFF, DOCTOR_OWN),
+    scoped(GET, "/schedule/grid", STAFF, DOCTOR_OWN),
+    scoped(GET, "/appointments/day/print", STAFF, DOCTOR_OWN),
+    scoped(GET, "/appointments/overdue", STAFF, DOCTOR_OWN),
+    scoped(GET, "/queue/today", STAFF, DOCTOR_OWN),
+    scoped(GET, "/availability/search", STAFF, DOCTOR_OWN),
+    scoped(GET, "/appointments/{appointment_id}", STAFF, DOCTOR_OWN),
+    scoped(GET, "/appointments", STAFF, DOCTOR_OWN),
+    session(POST, "/appointments", FRONT_DESK),
+    session(POST, "/appointments/household", FRONT_DESK),
+    session(PATCH, "/appointments/{appointment_id}", FRONT_DESK),
+    session(POST, "/appointments/{appointment_id}/arrive", FRONT_DESK),
+    session(POST, "/appointments/{appointment_id}/seat", FRONT_DESK),
+    session(POST, "/appointments/{appointment_id}/dismiss", FRONT_DESK),
+    session(PUT, "/appointments/{appointment_id}/plan_items", FRONT_DESK),
+    session(POST, "/appointments/{appointment_id}/confirm", FRONT_DESK),
+    session(POST, "/appointments/{appointment_id}/reminder_sent", FRONT_DESK),
+    scoped(GET, "/appointments/{appointment_id}/history", STAFF, DOCTOR_OWN),
+    scoped(GET, "/appointments/{appointment_id}/onboarding", STAFF, DOCTOR_OWN),
+    scoped(PUT, "/appointments/{appointment_id}/onboarding/{onboarding_item_id}", STAFF, DOCTOR_OWN),
+    scoped(GET, "/appointments/{appointment_id}/notes", STAFF, DOCTOR_OWN),
+    scoped(POST, "/appointments/{appointment_id}/notes", STAFF, DOCTOR_OWN),
+    scoped(POST, "/appointments/{appointment_id}/notes/{appointment_note_id}/pin", STAFF, DOCTOR_OWN),
+    scoped(POST, "/appointments/{appointment_id}/procedure_notes", CLINICAL, DOCTOR_OWN),
+    scoped(POST, "/appointments/{appointment_id}/procedure_notes/prefill", CLINICAL, DOCTOR_OWN),
+    // task_routes
+    session(POST, "/tasks", STAFF),
+    session(GET, "/tasks/inbox", FRONT_DESK),
+    session(GET, "/tasks/my", STAFF),
+    session(GET, "/tasks/created", STAFF),
+    scoped(GET, "/tasks/board", STAFF, "doctors: their own tasks only"),
+    session(POST, "/tasks/bulk_assign", ADMIN_MANAGER),
+    scoped(GET, "/tasks/{task_id}", STAFF, DOCTOR_TASKS),
+    scoped(PATCH, "/tasks/{task_id}", STAFF, DOCTOR_TASKS),
+    session(POST, "/tasks/{task_id}/assign", FRONT_DESK),
+    scoped(POST, "/tasks/{task_id}/start", STAFF, "doctors: tasks they created or are assigned"),
+    scoped(POST, "/tasks/{task_id}/complete", STAFF, "doctors: tasks they are assigned"),
+    scoped(POST, "/tasks/{task_id}/cancel", STAFF, "doctors: tasks they created"),
+    scoped(POST, "/tasks/{task_id}/comments", STAFF, DOCTOR_TASKS),
+    scoped(POST, "/tasks/{task_id}/watchers", STAFF, "adding someone else: admin/manager/receptionist"),
+    scoped(DELETE, "/tasks/{task_id}/watchers/{employee_id}", STAFF, "removing someone else: admin/manager/receptionist"),
+    session(GET, "/task_templates", STAFF),
+    session(POST, "/task_templates", ADMIN_MANAGER),
+    session(PATCH, "/task_templates/{task_template_id}", ADMIN_MANAGER),
+    session(POST, "/task_templates/{task_template_id}/instantiate", STAFF),
+    // notification_routes (employee notifications)
+    session(GET, "/notifications", STAFF),
+    session(POST, "/notifications/read_all", STAFF),
+    session(POST, "/notifications/{notification_id}/read", STAFF),
+    // report_routes
+    session(GET, "/reports/appointments/stats", ADMIN_MANAGER),
+    session(GET, "/reports/utilization", ADMIN_MANAGER),
+    session(GET, "/reports/revenue", ADMIN_MANAGER),
+    session(GET, "/reports/commissions", ADMIN_MANAGER),
+    session(PUT, "/reports/commissions/{employee_id}/rate", ADMIN),
+    session(GET, "/reports/referrals", ADMIN_MANAGER),
+    session(GET, "/reports/feedback", ADMIN_MANAGER),
+    session(GET, "/reports/appointment_sources", ADMIN_MANAGER),
+    session(GET, "/reports/retention", ADMIN_MANAGER),
+    session(GET, "/reports/day_close", ADMIN_MANAGER),
+    session(POST, "/reports/day_close/sign_off", ADMIN_MANAGER),
+    session(GET, "/dashboard/manager", ADMIN_MANAGER),
+    // document_template_routes
+    session(GET, "/document_templates", STAFF),
+    session(POST, "/document_templates", ADMIN_MANAGER),
+    session(PATCH, "/document_templates/{template_id}", ADMIN_MANAGER),
+    session(GET, "/patients/{patient_id}/documents", STAFF),
+    session(POST, "/patients/{patient_id}/documents", STAFF),
+    session(GET, "/documents/{document_id}/pdf", STAFF),
+    // photo_routes
+    scoped(GET, "/patients/{patient_id}/photo", ANY, "patients: their own photo only"),
+    session(POST, "/patients/{patient_id}/photo", STAFF),
+    session(DELETE, "/patients/{patient_id}/photo", STAFF),
+    session(GET, "/employees/{employee_id}/photo", STAFF),
+    scoped(POST, "/employees/{employee_id}/photo", STAFF, "own photo unless admin/manager"),
+    scoped(DELETE, "/employees/{employee_id}/photo", STAFF, "own photo unless admin/manager"),
+    // employee_routes
+    session(GET, "/employees/{employee_id}/services", STAFF),
+    session(PUT, "/employees/{employee_id}/services", ADMIN_MANAGER),
+    session(PUT, "/employees/{employee_id}/room", ADMIN_MANAGER),
+    session(GET, "/employees/check_display_number", ADMIN_MANAGER),
+    // household_routes
+    session(POST, "/households", STAFF),
+    session(GET, "/households/{household_id}", STAFF),
+    session(PATCH, "/households/{household_id}", STAFF),
+    session(DELETE, "/households/{household_id}", STAFF),
+    session(PUT, "/households/{household_id}/members/{patient_id}", STAFF),
+    session(DELETE, "/households/{household_id}/members/{patient_id}", STAFF),
+    // time_off_routes
+    scoped(GET, "/time_off", STAFF, OWN_TIME_OFF),
+    scoped(POST, "/time_off", STAFF, OWN_TIME_OFF),
+    session(GET, "/time_off/{time_off_id}/conflicts", ADMIN_MANAGER),
+    scoped(POST, "/time_off/{time_off_id}/approve", ADMIN_MANAGER, "managers: not their own"),
+    scoped(POST, "/time_off/{time_off_id}/deny", ADMIN_MANAGER, "managers: not their own"),
+    scoped(POST, "/time_off/{time_off_id}/cancel", STAFF, OWN_TIME_OFF),
+    // shift_routes
+    session(GET, "/shifts", STAFF),
+    scoped(POST, "/shifts", ADMIN_MANAGER, "locations the caller has access to"),
+    session(GET, "/shifts/week", STAFF),
+    scoped(PATCH, "/shifts/{shift_id}", ADMIN_MANAGER, "locations the caller has access to"),
+    session(DELETE, "/shifts/{shift_id}", ADMIN_MANAGER),
+    session(GET, "/assistants/{employee_id}/day", STAFF),
+    // procedure_template_routes
+    session(GET, "/procedure_templates", STAFF),
+    session(POST, "/procedure_templates", CLINICAL),
+    session(PATCH, "/procedure_templates/{template_id}", CLINICAL),
+    // imaging_routes
+    scoped(POST, "/patients/{patient_id}/imaging_orders", CLINICAL, "doctors order as themselves"),
+    session(GET, "/patients/{patient_id}/imaging", STAFF),
+    session(GET, "/imaging_orders", STAFF),
+    session(POST, "/imaging_orders/{imaging_order_id}/perform", STAFF),
+    session(POST, "/imaging_orders/{imaging_order_id}/cancel", CLINICAL),
+    session(POST, "/imaging_orders/{imaging_order_id}/attachments", STAFF),
+    session(GET, "/imaging_attachments/{attachment_id}", STAFF),
+    session(DELETE, "/imaging_attachments/{attachment_id}", ADMIN_MANAGER),
+    // intake_routes
+    session(POST, "/patients/{patient_id}/intake", STAFF),
+    session(GET, "/patients/{patient_id}/intake", STAFF),
+    session(GET, "/patients/{patient_id}/medical_history", STAFF),
+    session(GET, "/intakes", STAFF),
+    session(POST, "/intakes/{intake_id}/approve", STAFF),
+    session(POST, "/intakes/{intake_id}/reject", STAFF),
+    session(POST, "/intakes/{intake_id}/revoke", STAFF),
+    public(GET, "/intake/{token}"),
+    public(POST, "/intake/{token}"),
+    // medical_alert_routes
+    session(GET, "/patients/{patient_id}/alerts", STAFF),
+    session(POST, "/patients/{patient_id}/alerts", STAFF),
+    session(POST, "/medical_alerts/{alert_id}/resolve", STAFF),
+    // sync_routes
+    session(GET, "/sync/cursor", STAFF),
+    scoped(GET, "/sync/changes", STAFF, DOCTOR_OWN),
+    scoped(POST, "/sync/push", STAFF, "appointment items: admin/manager/receptionist"),
+    // ws_routes
+    public(GET, "/ws"),
+    session(GET, "/presence", STAFF),
+    // kiosk_routes / display_routes
+    session(GET, "/kiosks", ADMIN_MANAGER),
+    session(POST, "/kiosks", ADMIN_MANAGER),
+    session(POST, "/kiosks/{kiosk_id}/revoke", ADMIN_MANAGER),
+    device(POST, "/kiosk/checkin", "kiosk devices"),
+    device(GET, "/display/queue", "display devices"),
+    // feedback_routes
+    public(GET, "/feedback/{token}"),
+    public(POST, "/feedback/{token}"),
+];
+
+/// The rule for a matched route; `path` may carry the /api/vN prefix.
+pub fn rule_for(method: &Method, path: &str) -> Option<&'static RouteRule> {
+    let path = ["/api/v1", "/api/v2"]
+        .iter()
+        .find_map(|p| path.strip_prefix(p))
+        .unwrap_or(path);
+    // HEAD is answered by the GET handler
+    let method = if method == Method::HEAD { &GET } else { method };
+    ROUTES.iter().find(|r| r.path == path && r.method == method)
+}
+
+impl RouteRule {
+    pub fn allows(&self, role: Role) -> bool {
+        self.access != Access::Session || self.roles.contains(&role)
+    }
+}
+
+/// Route layer: refuses roles the table doesn't list before any extractor runs,
+/// so a request body is never parsed for a caller who may not send it. Routes open
+/// to every role (and unlisted ones) are left to the handler.
+pub async fn enforce(
+    State(state): State<AppState>,
+    matched: Option<MatchedPath>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let rule = matched.and_then(|m| rule_for(req.method(), m.as_str()));
+    let Some(rule) = rule.filter(|r| r.access == Access::Session && r.roles != ANY) else {
+        return Ok(next.run(req).await);
+    };
+
+    let (mut parts, body) = req.into_parts();
+    let auth = AuthContext::from_request_parts(&mut parts, &state).await?;
+    if !rule.allows(auth.role) {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            format!("{} can't use this route", auth.role),
+        ));
+    }
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{
+        body::Body,
+        http::{StatusCode, header},
+    };
+    use chrono::Utc;
+    use futures_util::future::join_all;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        auth::hash_access_token,
+        presence::Presence,
+        repos::Repos,
+        routes,
+        session_cache::{CachedSession, SessionCache},
+    };
+
+    /// Router sources and where routes::api_v1 mounts them (home_routes is outside /api).
+    const ROUTE_SOURCES: &[(&str, &str)] = &[
+        ("/admin", include_str!("routes/admin_routes.rs")),
+        ("", include_str!("routes/appointment_routes.rs")),
+        ("/auth", include_str!("routes/auth_routes.rs")),
+        ("", include_str!("routes/clinic_routes.rs")),
+        ("", include_str!("routes/display_routes.rs")),
+        ("", include_str!("routes/document_template_routes.rs")),
+        ("", include_str!("routes/employee_routes.rs")),
+        ("", include_str!("routes/feedback_routes.rs")),
+        ("", include_str!("routes/household_routes.rs")),
+        ("", include_str!("routes/imaging_routes.rs")),
+        ("", include_str!("routes/intake_routes.rs")),
+        ("", include_str!("routes/kiosk_routes.rs")),
+        ("", include_str!("routes/medical_alert_routes.rs")),
+        ("", include_str!("routes/notification_routes.rs")),
+        ("", include_str!("routes/patient_comm_routes.rs")),
+        ("", include_str!("routes/patient_routes.rs")),
+        ("", include_str!("routes/photo_routes.rs")),
+        ("", include_str!("routes/procedure_template_routes.rs")),
+        ("", include_str!("routes/report_routes.rs")),
+        ("/services", include_str!("routes/service_routes.rs")),
+        ("", include_str!("routes/shift_routes.rs")),
+        ("", include_str!("routes/sync_routes.rs")),
+        ("", include_str!("routes/ws_routes.rs")),
+        ("", include_str!("routes/task_routes.rs")),
+        ("", include_str!("routes/time_off_routes.rs")),
+        ("/users", include_str!("routes/user_routes.rs")),
+    ];
+
+    /// (method, path) of every `.route(path, get(..).post(..))` in a router source.
+    /// `any(..)` routes (v2 tombstones) aren't method-specific and are skipped.
+    fn registered(prefix: &str, src: &str) -> Vec<(Method, String)> {
+        let mut out = Vec::new();
+        for (at, call) in src.match_indices(".route(") {
+            let rest = &src[at + call.len()..];
+            let Some(path) = rest.trim_start().strip_prefix('"').and_then(|r| r.split('"').next()) else {
+                continue;
+            };
+            let mut depth = 1;
+            let end = rest
+                .find(|c| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                })
+                .expect("unbalanced .route(");
+            let args = &rest[..end];
+
+            let full = match format!("{prefix}{path}") {
+                p if p.len() > 1 => p.trim_end_matches('/').to_string(),
+                p => p,
+            };
+            for (method, name) in [(GET, "get("), (POST, "post("), (PUT, "put("), (PATCH, "patch("), (DELETE, "delete(")] {
+                let called = args
+                    .match_indices(name)
+                    .any(|(i, _)| !args[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_'));
+                if called {
+                    out.push((method, full.clone()));
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn table_lists_every_route_once() {
+        let routes: Vec<_> = ROUTE_SOURCES.iter().flat_map(|(prefix, src)| registered(prefix, src)).collect();
+        assert!(routes.len() > 100, "route scraping broke: {} routes", routes.len());
+
+        for (method, path) in &routes {
+            assert!(rule_for(method, path).is_some(), "no permissions::ROUTES row for {method} {path}");
+        }
+        for (i, rule) in ROUTES.iter().enumerate() {
+            assert!(
+                routes.iter().any(|(m, p)| *m == rule.method && p == rule.path),
+                "stale row {} {}",
+                rule.method,
+                rule.path
+            );
+            assert!(
+                !ROUTES[..i].iter().any(|r| r.method == rule.method && r.path == rule.path),
+                "duplicate row {} {}",
+                rule.method,
+                rule.path
+            );
+            assert_eq!(rule.roles.is_empty(), rule.access != Access::Session, "{} {}", rule.method, rule.path);
+        }
+    }
+
+    /// The real router over a database that refuses connections: allowed calls get
+    /// past the role checks and fail on the first query, refused ones never get there.
+    fn test_state() -> AppState {
+        let url = "postgres://dcms@127.0.0.1:1/dcms";
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy(url)
+            .unwrap();
+        AppState {
+            db: db.clone(),
+            database_url: url.into(),
+            db_read: None,
+            session_ttl_hours: 12,
+            session_sliding: false,
+            session_max_lifetime_hours: 24,
+            impersonation_ttl_minutes: 30,
+            session_retention_days: 30,
+            sms_segment_price_cents: None,
+            intake_form_url: None,
+            feedback_form_url: None,
+            data_export: None,
+            session_cache: SessionCache::new(),
+            presence: Presence::new(),
+            repos: Repos::postgres(db, None),
+        }
+    }
+
+    fn sign_in(cache: &SessionCache, role: Role) -> String {
+        let token = format!("test-token-{role}");
+        let session_token_id = Uuid::new_v4();
+        cache.insert(
+            hash_access_token(&token),
+            CachedSession {
+                session_token_id,
+                user_id: Uuid::new_v4(),
+                role,
+                impersonator_user_id: None,
+                expires_at: Utc::now() + chrono::Duration::hours(1),
+                preferred_language: None,
+            },
+        );
+        // skip the last_seen_at write
+        cache.should_touch(session_token_id);
+        token
+    }
+
+    #[tokio::test]
+    async fn router_enforces_the_table_for_every_role() {
+        let state = test_state();
+        let tokens: Vec<(Role, String)> = ANY.iter().map(|&r| (r, sign_in(&state.session_cache, r))).collect();
+        let app = routes::router(state);
+
+        let mut checks = Vec::new();
+        for rule in ROUTES {
+            let uri = format!("/api/v1{}", rule.path)
+                .split('/')
+                .map(|seg| if seg.starts_with('{') { Uuid::nil().to_string() } else { seg.to_string() })
+                .collect::<Vec<_>>()
+                .join("/");
+            let callers = std::iter::once(None).chain(tokens.iter().map(Some));
+            for caller in callers {
+                let mut req = axum::http::Request::builder().method(rule.method.clone()).uri(&uri);
+                if let Some((_, token)) = caller {
+                    req = req.header(header::AUTHORIZATION, format!("Bearer {token}"));
+                }
+                let body = if rule.method == GET {
+                    Body::empty()
+                } else {
+                    req = req.header(header::CONTENT_TYPE, "application/json");
+                    Body::from("{}")
+                };
+                let app = app.clone();
+                let req = req.body(body).unwrap();
+                checks.push(async move {
+                    let status = app.oneshot(req).await.unwrap().status();
+                    (rule, caller.map(|(role, _)| *role), status)
+                });
+            }
+        }
+
+        let mut wrong = Vec::new();
+        for (rule, role, status) in join_all(checks).await {
+            let ok = match (rule.access, role) {
+                (_, _) if matches!(status, StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED) => false,
+                (Access::Public, _) => !matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN),
+                (Access::Device | Access::Session, None) => status == StatusCode::UNAUTHORIZED,
+                (Access::Device, Some(_)) => true,
+                (Access::Session, Some(role)) if rule.allows(role) => {
+                    !matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+                }
+                (Access::Session, Some(_)) => status == StatusCode::FORBIDDEN,
+            };
+            if !ok {
+                let who = role.map_or("anonymous".to_string(), |r| r.to_string());
+                wrong.push(format!("{} {} as {who}: {status}", rule.method, rule.path));
+            }
+        }
+        assert!(wrong.is_empty(), "{} mismatches:\n{}", wrong.len(), wrong.join("\n"));
+    }
+}
@@ -0,0 +1,300 @@
+// src/phone_sharing.rs
+//
+// Phone numbers on more than one patient (clinic_settings.phone_number_sharing):
+// - allow:   stored silently, as before
+// - confirm: adding/changing a number another patient has (same last 8 digits) is
+//            409 PHONE_NUMBER_IN_USE with `details.holders`, unless `shared: true`
+//            (families sharing a phone); the default
+// - deny:    always 409; the number has to be moved instead
+// POST /phone_numbers/{id}/transfer moves a number to another patient (recycled
+// numbers, a child getting their own record). With `move_sms` the row moves with
+// its SMS history and opt-outs; without, the new owner gets a fresh row and the old
+// one is released (kept for the old patient's history only, migration 060), or
+// deleted when it has no SMS.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    error::{ApiError, DbError},
+    middleware::auth_context::AuthContext,
+    models::PhoneNumberRow,
+    repos::PatientRepo,
+    services::patients::{self, PhoneMatch},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhoneSharing {
+    Allow,
+    #[default]
+    Confirm,
+    Deny,
+}
+
+impl PhoneSharing {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim() {
+            "allow" => Ok(Self::Allow),
+            "confirm" => Ok(Self::Confirm),
+            "deny" => Ok(Self::Deny),
+            _ => Err("phone_number_sharing must be allow | confirm | deny".into()),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Confirm => "confirm",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+pub async fn load_policy(db: &PgPool) -> Result<PhoneSharing, ApiError> {
+    let raw: Option<String> =
+        sqlx::query_scalar("SELECT phone_number_sharing FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(db)
+            .await?;
+    let Some(raw) = raw else {
+        return Ok(PhoneSharing::default());
+    };
+    Ok(PhoneSharing::parse(&raw).unwrap_or_else(|e| {
+        // the column has a CHECK constraint
+        tracing::warn!("{e}; using confirm");
+        PhoneSharing::default()
+    }))
+}
+
+/// Before `number` goes onto `patient_id`'s record: other patients with the same
+/// number per the policy. `shared`: the request confirmed a shared number.
+/// Numbers too short to match on (under 8 digits) are never checked.
+pub async fn ensure_can_use(
+    repo: &dyn PatientRepo,
+    policy: PhoneSharing,
+    number: &str,
+    patient_id: Uuid,
+    shared: bool,
+) -> Result<(), ApiError> {
+    if policy == PhoneSharing::Allow || (policy == PhoneSharing::Confirm && shared) {
+        return Ok(());
+    }
+    if patients::normalize_lookup_number(number).is_err() {
+        return Ok(());
+    }
+    let holders: Vec<PhoneMatch> = patients::lookup_phone(repo, number)
+        .await?
+        .matches
+        .into_iter()
+        .filter(|m| m.patient_id != patient_id)
+        .collect();
+    if holders.is_empty() {
+        return Ok(());
+    }
+    let message = match policy {
+        PhoneSharing::Confirm => "number is on another patient's record; send shared: true to keep both, or transfer it",
+        _ => "number is on another patient's record; transfer it instead",
+    };
+    Err(ApiError::ConflictWithDetails(
+        "PHONE_NUMBER_IN_USE",
+        message.into(),
+        serde_json::json!({ "holders": holders, "can_share": policy == PhoneSharing::Confirm }),
+    ))
+}
+
+/// POST /phone_numbers/{id}/transfer
+#[derive(Debug, Serialize)]
+pub struct TransferDto {
+    /// the number on the new patient's record
+    pub phone_number: PhoneNumberRow,
+    pub from_patient_id: Uuid,
+    /// what happened to the old patient's row
+    pub source_row: &'static str, // "moved" | "released" | "deleted"
+}
+
+fn number_exists() -> ApiError {
+    ApiError::Conflict("PHONE_NUMBER_EXISTS", "the patient already has this number".into())
+}
+
+fn exists_conflict(e: sqlx::Error) -> ApiError {
+    match DbError::from(e) {
+        DbError::UniqueViolation { constraint: Some(c) } if c == "phone_number_patient_number_unique" => {
+            number_exists()
+        }
+        other => other.into(),
+    }
+}
+
+/// Moves a live number to `to_patient_id`; the sharing policy doesn't apply (the
+/// number leaves the old record). Run inside the caller's transaction.
+pub async fn transfer(
+    conn: &mut PgConnection,
+    auth: &AuthContext,
+    phone_number_id: Uuid,
+    to_patient_id: Uuid,
+    move_sms: bool,
+    make_primary: bool,
+) -> Result<TransferDto, ApiError> {
+    let source: Option<(Uuid, String, String)> = sqlx::query_as(
+        r#"
+        SELECT patient_id, phone_number, label
+        FROM phone_number
+        WHERE phone_number_id = $1 AND released_at IS NULL
+        FOR UPDATE
+        "#,
+    )
+    .bind(phone_number_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some((from_patient_id, number, label)) = source else {
+        return Err(ApiError::NotFound("NOT_FOUND", "phone number not found".into()));
+    };
+    if from_patient_id == to_patient_id {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "the number is already on this patient".into()));
+    }
+    let target_ok: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM patient WHERE patient_id = $1 AND anonymized_at IS NULL)")
+            .bind(to_patient_id)
+            .fetch_one(&mut *conn)
+            .await?;
+    if !target_ok {
+        return Err(ApiError::NotFound("NOT_FOUND", "patient not found".into()));
+    }
+
+    if make_primary {
+        sqlx::query("UPDATE phone_number SET is_primary = FALSE, updated_at = now() WHERE patient_id = $1 AND is_primary")
+            .bind(to_patient_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let (row, source_row) = if move_sms {
+        let row = sqlx::query_as::<_, PhoneNumberRow>(
+            r#"
+            UPDATE phone_number
+            SET patient_id = $2, is_primary = $3, updated_at = now()
+            WHERE phone_number_id = $1
+            RETURNING phone_number_id, patient_id, phone_number, label, is_primary, created_at, updated_at
+            "#,
+        )
+        .bind(phone_number_id)
+        .bind(to_patient_id)
+        .bind(make_primary)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(exists_conflict)?;
+        (row, "moved")
+    } else {
+        let has_sms: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM sms WHERE phone_number_id = $1)")
+            .bind(phone_number_id)
+            .fetch_one(&mut *conn)
+            .await?;
+        let source_row = if has_sms {
+            sqlx::query(
+                "UPDATE phone_number SET released_at = now(), is_primary = FALSE, updated_at = now() WHERE phone_number_id = $1",
+            )
+            .bind(phone_number_id)
+            .execute(&mut *conn)
+            .await?;
+            "released"
+        } else {
+            sqlx::query("DELETE FROM phone_number WHERE phone_number_id = $1")
+                .bind(phone_number_id)
+                .execute(&mut *conn)
+                .await?;
+            "deleted"
+        };
+        // a number the target once released comes back to life instead
+        let row = sqlx::query_as::<_, PhoneNumberRow>(
+            r#"
+            INSERT INTO phone_number (patient_id, phone_number, label, is_primary)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (patient_id, phone_number) DO UPDATE
+              SET released_at = NULL, label = EXCLUDED.label, is_primary = EXCLUDED.is_primary, updated_at = now()
+              WHERE phone_number.released_at IS NOT NULL
+            RETURNING phone_number_id, patient_id, phone_number, label, is_primary, created_at, updated_at
+            "#,
+        )
+        .bind(to_patient_id)
+        .bind(&number)
+        .bind(&label)
+        .bind(make_primary)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(number_exists)?;
+        (row, source_row)
+    };
+
+    audit::record(
+        &mut *conn,
+        auth,
+        "phone_number.transfer",
+        "phone_number",
+        Some(row.phone_number_id),
+        serde_json::json!({
+            "from_phone_number_id": phone_number_id,
+            "from_patient_id": from_patient_id,
+            "to_patient_id": to_patient_id,
+            "move_sms": move_sms,
+            "source_row": source_row,
+        }),
+    )
+    .await?;
+
+    Ok(TransferDto { phone_number: row, from_patient_id, source_row })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::Gender, repos::fake::FakePatientRepo, services::patients::PatientFields};
+
+    async fn patient_with_phone(repo: &mut FakePatientRepo, first: &str, phone: &str) -> Uuid {
+        let fields = PatientFields {
+            register_number: None,
+            user_id: None,
+            first_name: first.into(),
+            last_name: "X".into(),
+            email: None,
+            birthday: None,
+            gender: Gender::Female,
+            status: 0,
+            referral_source_id: None,
+        };
+        let row = repo.insert(&fields).await.unwrap();
+        repo.primary_phones.insert(row.patient_id, phone.into());
+        row.patient_id
+    }
+
+    #[test]
+    fn policy_names_round_trip() {
+        for p in [PhoneSharing::Allow, PhoneSharing::Confirm, PhoneSharing::Deny] {
+            assert_eq!(PhoneSharing::parse(p.as_str()), Ok(p));
+        }
+        assert!(PhoneSharing::parse("unique").is_err());
+    }
+
+    #[tokio::test]
+    async fn shared_numbers_follow_the_policy() {
+        let mut repo = FakePatientRepo::default();
+        let mother = patient_with_phone(&mut repo, "Saraa", "+97699118840").await;
+        let child = patient_with_phone(&mut repo, "Anu", "+97688001122").await;
+
+        let err = ensure_can_use(&repo, PhoneSharing::Confirm, "9911 8840", child, false).await.unwrap_err();
+        let ApiError::ConflictWithDetails("PHONE_NUMBER_IN_USE", _, details) = err else {
+            panic!("{err:?}");
+        };
+        assert_eq!(details["holders"][0]["patient_id"], serde_json::json!(mother));
+        assert_eq!(details["can_share"], true);
+
+        ensure_can_use(&repo, PhoneSharing::Confirm, "9911 8840", child, true).await.unwrap();
+        ensure_can_use(&repo, PhoneSharing::Allow, "9911 8840", child, false).await.unwrap();
+        assert!(ensure_can_use(&repo, PhoneSharing::Deny, "9911 8840", child, true).await.is_err());
+        // the patient's own number, and numbers nobody has
+        ensure_can_use(&repo, PhoneSharing::Deny, "+97699118840", mother, false).await.unwrap();
+        ensure_can_use(&repo, PhoneSharing::Deny, "+97677665544", child, false).await.unwrap();
+        ensure_can_use(&repo, PhoneSharing::Deny, "+1234", child, false).await.unwrap();
+    }
+}
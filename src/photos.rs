@@ -0,0 +1,111 @@
+// src/photos.rs
+//
+// Profile photo processing (patients + employees).
+// - uploads are decoded (JPEG/PNG/WebP), EXIF orientation applied, and re-encoded
+//   as JPEG, which also drops metadata (GPS etc.) from phone pictures
+// - two variants: "full" fits in FULL_MAX_PX, "avatar" is an AVATAR_PX square crop
+// - URLs carry ?v=<photo_updated_at> so clients can cache them forever
+
+use std::io::Cursor;
+
+use chrono::{DateTime, Utc};
+use image::{
+    DynamicImage, ImageDecoder, ImageReader, Limits,
+    codecs::jpeg::JpegEncoder,
+    imageops::FilterType,
+};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+const FULL_MAX_PX: u32 = 512;
+const AVATAR_PX: u32 = 128;
+const JPEG_QUALITY: u8 = 85;
+/// decoding limit, guards against decompression bombs
+const MAX_SOURCE_PX: u32 = 8000;
+
+pub const CONTENT_TYPE: &str = "image/jpeg";
+
+pub struct ProcessedPhoto {
+    pub full: Vec<u8>,
+    pub avatar: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn process_upload(bytes: &[u8]) -> Result<ProcessedPhoto, ApiError> {
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| ApiError::Internal(format!("photo: reading upload failed: {e}")))?;
+    if reader.format().is_none() {
+        return Err(ApiError::UnsupportedMediaType(
+            "UNSUPPORTED_MEDIA_TYPE",
+            "photo must be a JPEG, PNG or WebP image".into(),
+        ));
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_PX);
+    limits.max_image_height = Some(MAX_SOURCE_PX);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(invalid_image)?;
+    let orientation = decoder.orientation().map_err(invalid_image)?;
+    let mut img = DynamicImage::from_decoder(decoder).map_err(invalid_image)?;
+    img.apply_orientation(orientation);
+
+    let full = img.resize(FULL_MAX_PX, FULL_MAX_PX, FilterType::Lanczos3);
+    let avatar = img.resize_to_fill(AVATAR_PX, AVATAR_PX, FilterType::Lanczos3);
+
+    Ok(ProcessedPhoto {
+        width: full.width(),
+        height: full.height(),
+        full: encode_jpeg(&full)?,
+        avatar: encode_jpeg(&avatar)?,
+    })
+}
+
+fn encode_jpeg(img: &DynamicImage) -> Result<Vec<u8>, ApiError> {
+    let mut out = Vec::new();
+    DynamicImage::ImageRgb8(img.to_rgb8())
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY))
+        .map_err(|e| ApiError::Internal(format!("photo: jpeg encode failed: {e}")))?;
+    Ok(out)
+}
+
+fn invalid_image(e: image::ImageError) -> ApiError {
+    match e {
+        image::ImageError::Limits(_) => ApiError::BadRequest(
+            "INVALID_IMAGE",
+            format!("image is larger than {MAX_SOURCE_PX}x{MAX_SOURCE_PX} pixels"),
+        ),
+        e => ApiError::BadRequest("INVALID_IMAGE", format!("could not decode image: {e}")),
+    }
+}
+
+/// `/api/v1/{collection}/{id}/photo?v=...`, or None when there is no photo.
+pub fn photo_url(collection: &str, id: Uuid, photo_updated_at: Option<DateTime<Utc>>) -> Option<String> {
+    photo_updated_at.map(|t| format!("/api/v1/{collection}/{id}/photo?v={}", t.timestamp()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    #[test]
+    fn resizes_and_reencodes() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(1200, 600, image::Rgb([200, 10, 10])))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let p = process_upload(&png).unwrap();
+        assert_eq!((p.width, p.height), (512, 256));
+        let avatar = image::load_from_memory(&p.avatar).unwrap();
+        assert_eq!((avatar.width(), avatar.height()), (AVATAR_PX, AVATAR_PX));
+        assert_eq!(image::guess_format(&p.full).unwrap(), ImageFormat::Jpeg);
+
+        assert!(process_upload(b"not an image").is_err());
+    }
+}
@@ -0,0 +1,193 @@
+// src/pii.rs
+//
+// Application-level encryption for sensitive columns (patient.email, sms.sms_text).
+// - key: PII_ENCRYPTION_KEY, base64 of 32 bytes (AES-256-GCM); inject it from the
+//   secret manager/KMS, never commit it
+// - stored format: "enc:v1:" + base64(nonce || ciphertext)
+// - values without the prefix are plaintext (rows written before encryption was
+//   enabled) and are returned as-is; `rekey_pii` encrypts them in place
+// - `PiiString` encrypts on bind and decrypts on decode, so models only swap the
+//   field type
+//
+// Phone numbers are not encrypted: they are looked up and deduplicated by value.
+
+use std::sync::OnceLock;
+
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use base64::{Engine, engine::general_purpose::STANDARD as B64};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::{
+    Decode, Encode, Postgres, Type,
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef},
+};
+
+pub const PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+pub struct PiiCipher {
+    cipher: Aes256Gcm,
+}
+
+impl PiiCipher {
+    pub fn from_base64_key(key: &str) -> Result<Self, String> {
+        let raw = B64
+            .decode(key.trim())
+            .map_err(|e| format!("PII key is not valid base64: {e}"))?;
+        if raw.len() != 32 {
+            return Err(format!("PII key must be 32 bytes, got {}", raw.len()));
+        }
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&raw)),
+        })
+    }
+
+    pub fn encrypt(&self, plain: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        // encrypting into a Vec cannot fail for inputs of this size
+        let ct = self
+            .cipher
+            .encrypt(&nonce, plain.as_bytes())
+            .expect("AES-GCM encryption failed");
+
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ct);
+        format!("{PREFIX}{}", B64.encode(out))
+    }
+
+    /// Decrypts an `enc:v1:` value; anything else is returned unchanged.
+    pub fn decrypt(&self, stored: &str) -> Result<String, String> {
+        let Some(b64) = stored.strip_prefix(PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let raw = B64.decode(b64).map_err(|e| format!("bad PII ciphertext: {e}"))?;
+        if raw.len() < NONCE_LEN {
+            return Err("bad PII ciphertext: too short".into());
+        }
+        let (nonce, ct) = raw.split_at(NONCE_LEN);
+        let plain = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ct)
+            .map_err(|_| "PII decryption failed (wrong key?)".to_string())?;
+        String::from_utf8(plain).map_err(|e| format!("bad PII plaintext: {e}"))
+    }
+}
+
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(PREFIX)
+}
+
+/* ============================================================
+   Process-wide cipher (set once in main)
+   ============================================================ */
+
+static CIPHER: OnceLock<Option<PiiCipher>> = OnceLock::new();
+
+/// `None` disables encryption of new values; existing ciphertext then fails to decode.
+pub fn init(cipher: Option<PiiCipher>) {
+    if CIPHER.set(cipher).is_err() {
+        tracing::warn!("pii::init called twice; keeping the first cipher");
+    }
+}
+
+fn cipher() -> Option<&'static PiiCipher> {
+    CIPHER.get().and_then(Option::as_ref)
+}
+
+pub fn seal(plain: &str) -> String {
+    match cipher() {
+        Some(c) => c.encrypt(plain),
+        None => plain.to_string(),
+    }
+}
+
+pub fn open(stored: &str) -> Result<String, String> {
+    match cipher() {
+        Some(c) => c.decrypt(stored),
+        None if is_encrypted(stored) => Err("PII value is encrypted but PII_ENCRYPTION_KEY is not set".into()),
+        None => Ok(stored.to_string()),
+    }
+}
+
+/* ============================================================
+   PiiString: TEXT column encrypted at rest
+   ============================================================ */
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PiiString(pub String);
+
+impl From<String> for PiiString {
+    fn from(s: String) -> Self {
+        Self(s)
+    }
+}
+
+impl From<&str> for PiiString {
+    fn from(s: &str) -> Self {
+        Self(s.to_string())
+    }
+}
+
+impl Serialize for PiiString {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for PiiString {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        String::deserialize(d).map(Self)
+    }
+}
+
+impl Type<Postgres> for PiiString {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for PiiString {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <String as Encode<Postgres>>::encode(seal(&self.0), buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for PiiString {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let stored = <&str as Decode<Postgres>>::decode(value)?;
+        Ok(Self(open(stored)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+
+    #[test]
+    fn round_trip_and_plaintext_passthrough() {
+        let c = PiiCipher::from_base64_key(KEY).unwrap();
+
+        let a = c.encrypt("Bat Dorj");
+        let b = c.encrypt("Bat Dorj");
+        assert!(is_encrypted(&a));
+        assert_ne!(a, b, "nonce must be random");
+        assert_eq!(c.decrypt(&a).unwrap(), "Bat Dorj");
+
+        assert_eq!(c.decrypt("legacy@example.com").unwrap(), "legacy@example.com");
+
+        let other = PiiCipher::from_base64_key(&B64.encode([7u8; 32])).unwrap();
+        assert!(other.decrypt(&a).is_err());
+
+        assert!(PiiCipher::from_base64_key("c2hvcnQ=").is_err());
+    }
+}
@@ -0,0 +1,163 @@
+// src/presence.rs
+//
+// Which staff are online and which schedule view each has open, so reception can
+// tell whether the doctor is looking at the day / queue that shows "arrived".
+// - one entry per open WebSocket (routes::ws_routes); a user with two windows has
+//   two, and the snapshot shows their most recently changed view
+// - views are the client's own labels ("day:2026-10-16", "queue", ...), capped at
+//   MAX_VIEW_LEN chars; None = no schedule view open
+// - every change wakes the open sockets, which send a fresh snapshot
+//
+// Per process, like the session cache: behind a load balancer each instance only
+// knows its own sockets.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::Role;
+
+pub const MAX_VIEW_LEN: usize = 100;
+
+#[derive(Debug, Clone)]
+struct Connection {
+    user_id: Uuid,
+    display_name: String,
+    role: Role,
+    view: Option<String>,
+    view_since: DateTime<Utc>,
+    online_since: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PresenceDto {
+    pub user_id: Uuid,
+    pub display_name: String,
+    pub role: Role,
+    pub view: Option<String>,
+    /// when `view` was opened: a doctor who has been on "queue" since before the
+    /// patient arrived has it on screen
+    pub view_since: DateTime<Utc>,
+    /// the user's oldest open connection
+    pub online_since: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct Presence {
+    connections: Arc<Mutex<HashMap<Uuid, Connection>>>,
+    changed: broadcast::Sender<()>,
+}
+
+impl Presence {
+    pub fn new() -> Self {
+        Self { connections: Arc::default(), changed: broadcast::channel(16).0 }
+    }
+
+    /// Registers a socket; returns its connection id for `set_view` / `leave`.
+    pub fn join(&self, user_id: Uuid, display_name: String, role: Role, view: Option<String>) -> Uuid {
+        let now = Utc::now();
+        let connection_id = Uuid::new_v4();
+        let connection =
+            Connection { user_id, display_name, role, view: clip(view), view_since: now, online_since: now };
+        self.lock().insert(connection_id, connection);
+        self.notify();
+        connection_id
+    }
+
+    pub fn set_view(&self, connection_id: Uuid, view: Option<String>) {
+        let view = clip(view);
+        {
+            let mut connections = self.lock();
+            let Some(c) = connections.get_mut(&connection_id) else { return };
+            if c.view == view {
+                return;
+            }
+            c.view = view;
+            c.view_since = Utc::now();
+        }
+        self.notify();
+    }
+
+    pub fn leave(&self, connection_id: Uuid) {
+        if self.lock().remove(&connection_id).is_some() {
+            self.notify();
+        }
+    }
+
+    /// One row per online user, by display name.
+    pub fn snapshot(&self) -> Vec<PresenceDto> {
+        let mut by_user: HashMap<Uuid, PresenceDto> = HashMap::new();
+        for c in self.lock().values() {
+            let dto = by_user.entry(c.user_id).or_insert_with(|| PresenceDto {
+                user_id: c.user_id,
+                display_name: c.display_name.clone(),
+                role: c.role,
+                view: c.view.clone(),
+                view_since: c.view_since,
+                online_since: c.online_since,
+            });
+            if c.view_since > dto.view_since {
+                dto.view = c.view.clone();
+                dto.view_since = c.view_since;
+            }
+            dto.online_since = dto.online_since.min(c.online_since);
+        }
+        let mut rows: Vec<PresenceDto> = by_user.into_values().collect();
+        rows.sort_by(|a, b| a.display_name.cmp(&b.display_name).then(a.user_id.cmp(&b.user_id)));
+        rows
+    }
+
+    /// Fires after every change (a lagging receiver just sends one snapshot).
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.changed.subscribe()
+    }
+
+    fn notify(&self) {
+        // no receivers = no open sockets; nothing to tell
+        let _ = self.changed.send(());
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, Connection>> {
+        self.connections.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn clip(view: Option<String>) -> Option<String> {
+    view.map(|v| v.trim().chars().take(MAX_VIEW_LEN).collect::<String>()).filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_merges_a_users_windows_into_their_latest_view() {
+        let presence = Presence::new();
+        let doctor = Uuid::new_v4();
+        let first = presence.join(doctor, "Bat".into(), Role::Doctor, Some("day:2026-10-16".into()));
+        let second = presence.join(doctor, "Bat".into(), Role::Doctor, None);
+        presence.join(Uuid::new_v4(), "Anu".into(), Role::Receptionist, Some("  queue ".into()));
+
+        presence.set_view(second, Some("queue".into()));
+        let rows = presence.snapshot();
+        assert_eq!(rows.iter().map(|r| r.display_name.as_str()).collect::<Vec<_>>(), ["Anu", "Bat"]);
+        assert_eq!(rows[0].view.as_deref(), Some("queue"));
+        assert_eq!(rows[1].view.as_deref(), Some("queue"));
+
+        presence.leave(second);
+        presence.leave(first);
+        assert_eq!(presence.snapshot().len(), 1);
+    }
+
+    #[test]
+    fn views_are_trimmed_and_capped() {
+        assert_eq!(clip(Some("   ".into())), None);
+        assert_eq!(clip(Some("x".repeat(150))).map(|v| v.len()), Some(MAX_VIEW_LEN));
+    }
+}
@@ -0,0 +1,828 @@
+// src/repos/appointments.rs
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde_json::Value as JsonValue;
+use sqlx::{Row, postgres::PgRow};
+use uuid::Uuid;
+
+use super::PgRepo;
+use crate::{
+    clinic_time,
+    error::ApiError,
+    jobs::no_show_risk,
+    models::{AppointmentStatus, Role, TimeOffStatus},
+    onboarding::SeatingBlocker,
+    photos,
+    services::{
+        appointments::{
+            AppointmentBlockDto, AppointmentChangeDto, AppointmentNoteDto, AppointmentPlanItemDto, Milestone, OverdueCounts,
+            OverdueFilter, OverdueSort, PersonBrief, QueueEntryDto, planned_summary, queue_state,
+        },
+        availability::SlotProvider,
+    },
+};
+
+#[async_trait]
+pub trait AppointmentRepo: Send + Sync {
+    async fn clinic_tz(&self) -> Result<Tz, ApiError>;
+    /// Employee profile linked to a login; None = no profile.
+    async fn employee_id_for_user(&self, user_id: Uuid) -> Result<Option<Uuid>, ApiError>;
+    /// None = no such appointment
+    async fn doctor_of(&self, appointment_id: Uuid) -> Result<Option<Uuid>, ApiError>;
+    /// None = no such appointment
+    async fn status_of(&self, appointment_id: Uuid) -> Result<Option<AppointmentStatus>, ApiError>;
+    /// A doctor's blocks starting in [start_ts, end_ts), oldest first; read replica.
+    async fn blocks_in_range(
+        &self,
+        doctor_employee_id: Uuid,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+        locations: Option<&[Uuid]>,
+    ) -> Result<Vec<AppointmentBlockDto>, ApiError>;
+    /// One page of `filter`'s overdue blocks in its sort order; read replica.
+    async fn overdue(&self, filter: &OverdueFilter) -> Result<Vec<AppointmentBlockDto>, ApiError>;
+    /// Scheduled / no-show counts over the filter's whole range (limit, offset and
+    /// include_no_show ignored).
+    async fn overdue_counts(&self, filter: &OverdueFilter) -> Result<OverdueCounts, ApiError>;
+    /// Primary, so a write can be read back.
+    async fn get_block(&self, appointment_id: Uuid) -> Result<Option<AppointmentBlockDto>, ApiError>;
+    /// The blocks that exist among `appointment_ids`, by start time; primary, like get_block.
+    async fn blocks_by_ids(&self, appointment_ids: &[Uuid]) -> Result<Vec<AppointmentBlockDto>, ApiError>;
+    /// Keyset page by appointment_id for bulk export (HQ sync); `ids` None = every appointment.
+    #[cfg_attr(not(feature = "hq-grpc"), allow(dead_code))]
+    async fn blocks_after(
+        &self,
+        after: Option<Uuid>,
+        ids: Option<&[Uuid]>,
+        limit: i64,
+    ) -> Result<Vec<AppointmentBlockDto>, ApiError>;
+    /// Non-canceled appointments starting in [start_ts, end_ts), in waiting-room order;
+    /// `wait_minutes` is left for the service.
+    async fn queue(
+        &self,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+        doctor_employee_id: Option<Uuid>,
+        locations: Option<&[Uuid]>,
+    ) -> Result<Vec<QueueEntryDto>, ApiError>;
+    /// First closure (clinic_holiday.is_closed) within the dates, inclusive.
+    async fn first_closure(&self, first_day: NaiveDate, last_day: NaiveDate) -> Result<Option<(NaiveDate, String)>, ApiError>;
+    /// Earliest approved time off of the employee overlapping [start_ts, end_ts).
+    async fn first_time_off(
+        &self,
+        employee_id: Uuid,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+    ) -> Result<Option<(DateTime<Utc>, DateTime<Utc>)>, ApiError>;
+    /// (login role, active) of an employee; role None = no login. None = no such employee.
+    async fn assistant_profile(&self, employee_id: Uuid) -> Result<Option<(Option<Role>, bool)>, ApiError>;
+    /// First slot-occupying appointment the employee assists in overlapping
+    /// [start_ts, end_ts), other than `exclude`.
+    async fn assistant_conflict(
+        &self,
+        employee_id: Uuid,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+        exclude: Option<Uuid>,
+    ) -> Result<Option<(Uuid, DateTime<Utc>, DateTime<Utc>)>, ApiError>;
+    /// Whether the employee's shifts (adjacent ones joined) cover all of [start_ts, end_ts).
+    async fn on_shift(&self, employee_id: Uuid, start_ts: DateTime<Utc>, end_ts: DateTime<Utc>) -> Result<bool, ApiError>;
+    /// Every closed date within the dates, inclusive.
+    async fn closed_days(&self, first_day: NaiveDate, last_day: NaiveDate) -> Result<Vec<NaiveDate>, ApiError>;
+    /// (default_slot_minutes, business_hours); 15 and no hours when unset.
+    async fn scheduling_settings(&self) -> Result<(i64, JsonValue), ApiError>;
+    /// Active doctors who perform the service (same rule as /services/{id}/providers),
+    /// by doctor number; None = no such active service.
+    async fn slot_providers(
+        &self,
+        service_id: Uuid,
+        doctor_employee_id: Option<Uuid>,
+        locations: Option<&[Uuid]>,
+    ) -> Result<Option<Vec<SlotProvider>>, ApiError>;
+    /// (doctor, start, end) of slot-occupying appointments and approved time off
+    /// overlapping [start_ts, end_ts).
+    async fn busy_intervals(
+        &self,
+        doctor_employee_ids: &[Uuid],
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+    ) -> Result<Vec<(Uuid, DateTime<Utc>, DateTime<Utc>)>, ApiError>;
+    /// Oldest first.
+    async fn notes(&self, appointment_id: Uuid) -> Result<Vec<AppointmentNoteDto>, ApiError>;
+    /// appointment_change rows, oldest first; status values still as codes.
+    async fn history(&self, appointment_id: Uuid) -> Result<Vec<AppointmentChangeDto>, ApiError>;
+    /// Stamps the milestone and moves the status to `milestone.status()`; the
+    /// transition is checked by the service.
+    async fn mark(&self, appointment_id: Uuid, user_id: Uuid, milestone: Milestone) -> Result<(), ApiError>;
+    /// Open onboarding items that must be done before seating, in checklist order.
+    async fn seating_blockers(&self, appointment_id: Uuid) -> Result<Vec<SeatingBlocker>, ApiError>;
+}
+
+/// One row per (appointment, plan item); folded by `fold_rows_into_blocks`, which
+/// keeps the query's order. Queries using it start with a `/* name */` comment, the
+/// span name in trace exports (see telemetry.rs).
+const BLOCK_SELECT: &str = r#"
+    SELECT
+      a.appointment_id,
+      a.start_at,
+      a.end_at,
+      a.status,
+      a.priority,
+      a.color_override,
+      a.note,
+      a.source,
+      a.confirmed_at,
+      a.reminder_sent_at,
+      a.location_id,
+
+      p.patient_id,
+      p.first_name AS p_first,
+      p.last_name  AS p_last,
+      p.register_number AS p_reg,
+      p.photo_updated_at AS p_photo,
+      nsr.score AS p_risk,
+
+      d.employee_id AS d_id,
+      d.employee_display_number AS d_no,
+      d.first_name AS d_first,
+      d.last_name  AS d_last,
+      d.photo_updated_at AS d_photo,
+
+      api.service_id AS svc_id,
+      api.qty AS svc_qty,
+      sc.display_name AS svc_name,
+      sc.display_number AS svc_no
+
+    FROM appointment a
+    JOIN patient p ON p.patient_id = a.patient_id
+    JOIN employee d ON d.employee_id = a.doctor_employee_id
+    LEFT JOIN patient_no_show_risk nsr ON nsr.patient_id = p.patient_id
+    LEFT JOIN appointment_plan_item api ON api.appointment_id = a.appointment_id
+    LEFT JOIN service_catalog sc ON sc.service_id = api.service_id
+"#;
+
+/// SET clause for the milestone's timestamp
+fn milestone_set_sql(milestone: Milestone) -> &'static str {
+    match milestone {
+        Milestone::Arrived => "arrived_at = COALESCE(arrived_at, now())",
+        Milestone::Seated => "seated_at = COALESCE(seated_at, now())",
+        Milestone::Dismissed => "dismissed_at = COALESCE(dismissed_at, now())",
+        Milestone::Confirmed => "confirmed_at = COALESCE(confirmed_at, now())",
+        Milestone::ReminderSent => "reminder_sent_at = COALESCE(reminder_sent_at, now())",
+    }
+}
+
+#[async_trait]
+impl AppointmentRepo for PgRepo {
+    async fn clinic_tz(&self) -> Result<Tz, ApiError> {
+        Ok(clinic_time::clinic_tz(&self.db).await?)
+    }
+
+    async fn employee_id_for_user(&self, user_id: Uuid) -> Result<Option<Uuid>, ApiError> {
+        Ok(sqlx::query_scalar("SELECT employee_id FROM employee WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.db)
+            .await?)
+    }
+
+    async fn doctor_of(&self, appointment_id: Uuid) -> Result<Option<Uuid>, ApiError> {
+        Ok(sqlx::query_scalar("SELECT doctor_employee_id FROM appointment WHERE appointment_id = $1")
+            .bind(appointment_id)
+            .fetch_optional(&self.db)
+            .await?)
+    }
+
+    async fn status_of(&self, appointment_id: Uuid) -> Result<Option<AppointmentStatus>, ApiError> {
+        Ok(sqlx::query_scalar("SELECT status FROM appointment WHERE appointment_id = $1")
+            .bind(appointment_id)
+            .fetch_optional(&self.db)
+            .await?)
+    }
+
+    async fn blocks_in_range(
+        &self,
+        doctor_employee_id: Uuid,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+        locations: Option<&[Uuid]>,
+    ) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+        let sql = format!(
+            r#"/* appointments.blocks_in_range */ {BLOCK_SELECT}
+            WHERE a.doctor_employee_id = $1
+              AND a.start_at >= $2
+              AND a.start_at <  $3
+              AND ($4::uuid[] IS NULL OR a.location_id = ANY($4))
+            ORDER BY a.start_at ASC, sc.display_number ASC
+            "#
+        );
+        let rows = sqlx::query(&sql)
+            .bind(doctor_employee_id)
+            .bind(start_ts)
+            .bind(end_ts)
+            .bind(locations)
+            .fetch_all(self.read_db())
+            .await?;
+
+        fold_rows_into_blocks(rows)
+    }
+
+    async fn overdue(&self, f: &OverdueFilter) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+        let order_by = match f.sort {
+            OverdueSort::StartAt => "a.start_at ASC, a.appointment_id",
+            OverdueSort::StartAtDesc => "a.start_at DESC, a.appointment_id",
+            OverdueSort::Priority => "a.priority DESC, a.start_at ASC, a.appointment_id",
+        };
+        // page over appointments first; BLOCK_SELECT has one row per plan item
+        let sql = format!(
+            r#"/* appointments.overdue */ {BLOCK_SELECT}
+            WHERE a.appointment_id IN (
+              SELECT a.appointment_id
+              FROM appointment a
+              WHERE a.doctor_employee_id = $1
+                AND a.start_at >= $2
+                AND a.start_at <  $3
+                AND ($4::uuid[] IS NULL OR a.location_id = ANY($4))
+                AND (a.status = $5 OR ($6 AND a.status = $7))
+              ORDER BY {order_by}
+              LIMIT $8 OFFSET $9
+            )
+            ORDER BY {order_by}, sc.display_number ASC
+            "#
+        );
+        let rows = sqlx::query(&sql)
+            .bind(f.doctor_employee_id)
+            .bind(f.since)
+            .bind(f.now)
+            .bind(f.locations.as_deref())
+            .bind(AppointmentStatus::Scheduled)
+            .bind(f.include_no_show)
+            .bind(AppointmentStatus::NoShow)
+            .bind(f.limit)
+            .bind(f.offset)
+            .fetch_all(self.read_db())
+            .await?;
+
+        fold_rows_into_blocks(rows)
+    }
+
+    async fn overdue_counts(&self, f: &OverdueFilter) -> Result<OverdueCounts, ApiError> {
+        let (scheduled, no_show): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+              COUNT(*) FILTER (WHERE status = $5),
+              COUNT(*) FILTER (WHERE status = $6)
+            FROM appointment
+            WHERE doctor_employee_id = $1
+              AND start_at >= $2
+              AND start_at <  $3
+              AND ($4::uuid[] IS NULL OR location_id = ANY($4))
+            "#,
+        )
+        .bind(f.doctor_employee_id)
+        .bind(f.since)
+        .bind(f.now)
+        .bind(f.locations.as_deref())
+        .bind(AppointmentStatus::Scheduled)
+        .bind(AppointmentStatus::NoShow)
+        .fetch_one(self.read_db())
+        .await?;
+        Ok(OverdueCounts { scheduled, no_show })
+    }
+
+    async fn get_block(&self, appointment_id: Uuid) -> Result<Option<AppointmentBlockDto>, ApiError> {
+        let sql = format!(
+            r#"/* appointments.get_block */ {BLOCK_SELECT}
+            WHERE a.appointment_id = $1
+            ORDER BY sc.display_number ASC
+            "#
+        );
+        let rows = sqlx::query(&sql).bind(appointment_id).fetch_all(&self.db).await?;
+
+        Ok(fold_rows_into_blocks(rows)?.pop())
+    }
+
+    async fn blocks_by_ids(&self, appointment_ids: &[Uuid]) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+        let sql = format!(
+            r#"/* appointments.blocks_by_ids */ {BLOCK_SELECT}
+            WHERE a.appointment_id = ANY($1)
+            ORDER BY a.start_at ASC, a.appointment_id, sc.display_number ASC
+            "#
+        );
+        let rows = sqlx::query(&sql).bind(appointment_ids).fetch_all(&self.db).await?;
+
+        fold_rows_into_blocks(rows)
+    }
+
+    async fn blocks_after(
+        &self,
+        after: Option<Uuid>,
+        ids: Option<&[Uuid]>,
+        limit: i64,
+    ) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+        // page over appointments first; BLOCK_SELECT has one row per plan item
+        let sql = format!(
+            r#"/* appointments.blocks_after */ {BLOCK_SELECT}
+            WHERE a.appointment_id IN (
+              SELECT a.appointment_id
+              FROM appointment a
+              WHERE ($1::uuid IS NULL OR a.appointment_id > $1)
+                AND ($2::uuid[] IS NULL OR a.appointment_id = ANY($2))
+              ORDER BY a.appointment_id
+              LIMIT $3
+            )
+            ORDER BY a.appointment_id, sc.display_number ASC
+            "#
+        );
+        let rows = sqlx::query(&sql).bind(after).bind(ids).bind(limit).fetch_all(self.read_db()).await?;
+
+        fold_rows_into_blocks(rows)
+    }
+
+    async fn queue(
+        &self,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+        doctor_employee_id: Option<Uuid>,
+        locations: Option<&[Uuid]>,
+    ) -> Result<Vec<QueueEntryDto>, ApiError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+              a.appointment_id,
+              a.status,
+              a.start_at,
+              a.end_at,
+              a.arrived_at,
+              a.seated_at,
+              a.dismissed_at,
+
+              p.patient_id,
+              p.first_name AS p_first,
+              p.last_name  AS p_last,
+              p.photo_updated_at AS p_photo,
+
+              d.employee_id AS d_id,
+              d.employee_display_number AS d_no,
+              d.first_name AS d_first,
+              d.last_name  AS d_last,
+              d.photo_updated_at AS d_photo
+
+            FROM appointment a
+            JOIN patient p ON p.patient_id = a.patient_id
+            JOIN employee d ON d.employee_id = a.doctor_employee_id
+
+            WHERE a.start_at >= $1
+              AND a.start_at <  $2
+              AND a.status <> $5
+              AND ($3::uuid IS NULL OR a.doctor_employee_id = $3)
+              AND ($4::uuid[] IS NULL OR a.location_id = ANY($4))
+
+            ORDER BY
+              CASE
+                WHEN a.dismissed_at IS NOT NULL THEN 3
+                WHEN a.seated_at    IS NOT NULL THEN 2
+                WHEN a.arrived_at   IS NOT NULL THEN 1
+                ELSE 0
+              END ASC,
+              COALESCE(a.dismissed_at, a.seated_at, a.arrived_at, a.start_at) ASC
+            "#,
+        )
+        .bind(start_ts)
+        .bind(end_ts)
+        .bind(doctor_employee_id)
+        .bind(locations)
+        .bind(AppointmentStatus::Canceled)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for r in rows {
+            let arrived_at: Option<DateTime<Utc>> = r.try_get("arrived_at").map_err(internal_row)?;
+            let seated_at: Option<DateTime<Utc>> = r.try_get("seated_at").map_err(internal_row)?;
+            let dismissed_at: Option<DateTime<Utc>> = r.try_get("dismissed_at").map_err(internal_row)?;
+
+            let p_first: String = r.try_get("p_first").map_err(internal_row)?;
+            let p_last: String = r.try_get("p_last").map_err(internal_row)?;
+            let d_first: String = r.try_get("d_first").map_err(internal_row)?;
+            let d_last: String = r.try_get("d_last").map_err(internal_row)?;
+            let p_id: Uuid = r.try_get("patient_id").map_err(internal_row)?;
+            let d_id: Uuid = r.try_get("d_id").map_err(internal_row)?;
+
+            out.push(QueueEntryDto {
+                appointment_id: r.try_get("appointment_id").map_err(internal_row)?,
+                queue_state: queue_state(arrived_at, seated_at, dismissed_at),
+                status: r.try_get("status").map_err(internal_row)?,
+                start_at: r.try_get("start_at").map_err(internal_row)?,
+                end_at: r.try_get("end_at").map_err(internal_row)?,
+                arrived_at,
+                seated_at,
+                dismissed_at,
+                wait_minutes: None,
+                patient: PersonBrief {
+                    id: p_id,
+                    display: format!("{p_first} {p_last}"),
+                    number: None,
+                    photo_url: photos::photo_url("patients", p_id, r.try_get("p_photo").map_err(internal_row)?),
+                    no_show_risk: None,
+                },
+                doctor: PersonBrief {
+                    id: d_id,
+                    display: format!("{d_first} {d_last}"),
+                    number: Some(r.try_get("d_no").map_err(internal_row)?),
+                    photo_url: photos::photo_url("employees", d_id, r.try_get("d_photo").map_err(internal_row)?),
+                    no_show_risk: None,
+                },
+            });
+        }
+        Ok(out)
+    }
+
+    async fn first_closure(&self, first_day: NaiveDate, last_day: NaiveDate) -> Result<Option<(NaiveDate, String)>, ApiError> {
+        Ok(sqlx::query_as(
+            r#"
+            SELECT holiday_date, name
+            FROM clinic_holiday
+            WHERE is_closed = true
+              AND holiday_date BETWEEN $1 AND $2
+            ORDER BY holiday_date
+            LIMIT 1
+            "#,
+        )
+        .bind(first_day)
+        .bind(last_day)
+        .fetch_optional(&self.db)
+        .await?)
+    }
+
+    async fn first_time_off(
+        &self,
+        employee_id: Uuid,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+    ) -> Result<Option<(DateTime<Utc>, DateTime<Utc>)>, ApiError> {
+        Ok(sqlx::query_as(
+            r#"
+            SELECT start_at, end_at
+            FROM employee_time_off
+            WHERE employee_id = $1
+              AND status = $4
+              AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+            ORDER BY start_at
+            LIMIT 1
+            "#,
+        )
+        .bind(employee_id)
+        .bind(start_ts)
+        .bind(end_ts)
+        .bind(TimeOffStatus::Approved)
+        .fetch_optional(&self.db)
+        .await?)
+    }
+
+    async fn assistant_profile(&self, employee_id: Uuid) -> Result<Option<(Option<Role>, bool)>, ApiError> {
+        Ok(sqlx::query_as(
+            r#"
+            SELECT
+              u.roles,
+              (e.fired_at IS NULL OR e.fired_at > CURRENT_DATE) AND COALESCE(u.is_active, true)
+            FROM employee e
+            LEFT JOIN "dcms_user" u ON u.user_id = e.user_id
+            WHERE e.employee_id = $1
+            "#,
+        )
+        .bind(employee_id)
+        .fetch_optional(&self.db)
+        .await?)
+    }
+
+    async fn assistant_conflict(
+        &self,
+        employee_id: Uuid,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+        exclude: Option<Uuid>,
+    ) -> Result<Option<(Uuid, DateTime<Utc>, DateTime<Utc>)>, ApiError> {
+        Ok(sqlx::query_as(
+            r#"
+            SELECT appointment_id, start_at, end_at
+            FROM appointment
+            WHERE assistant_employee_id = $1
+              AND status NOT IN ($5, $6)
+              AND ($4::uuid IS NULL OR appointment_id <> $4)
+              AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+            ORDER BY start_at
+            LIMIT 1
+            "#,
+        )
+        .bind(employee_id)
+        .bind(start_ts)
+        .bind(end_ts)
+        .bind(exclude)
+        .bind(AppointmentStatus::Canceled)
+        .bind(AppointmentStatus::NoShow)
+        .fetch_optional(&self.db)
+        .await?)
+    }
+
+    async fn on_shift(&self, employee_id: Uuid, start_ts: DateTime<Utc>, end_ts: DateTime<Utc>) -> Result<bool, ApiError> {
+        Ok(sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(range_agg(tstzrange(start_at, end_at, '[)')), '{}') @> tstzrange($2, $3, '[)')
+            FROM staff_shift
+            WHERE employee_id = $1
+              AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+            "#,
+        )
+        .bind(employee_id)
+        .bind(start_ts)
+        .bind(end_ts)
+        .fetch_one(&self.db)
+        .await?)
+    }
+
+    async fn closed_days(&self, first_day: NaiveDate, last_day: NaiveDate) -> Result<Vec<NaiveDate>, ApiError> {
+        Ok(sqlx::query_scalar(
+            "SELECT holiday_date FROM clinic_holiday WHERE is_closed = true AND holiday_date BETWEEN $1 AND $2",
+        )
+        .bind(first_day)
+        .bind(last_day)
+        .fetch_all(self.read_db())
+        .await?)
+    }
+
+    async fn scheduling_settings(&self) -> Result<(i64, JsonValue), ApiError> {
+        let row: Option<(i32, JsonValue)> = sqlx::query_as(
+            "SELECT default_slot_minutes, business_hours FROM clinic_settings WHERE singleton_id = TRUE",
+        )
+        .fetch_optional(self.read_db())
+        .await?;
+        Ok(row.map_or((15, JsonValue::Null), |(slot, hours)| (slot.into(), hours)))
+    }
+
+    async fn slot_providers(
+        &self,
+        service_id: Uuid,
+        doctor_employee_id: Option<Uuid>,
+        locations: Option<&[Uuid]>,
+    ) -> Result<Option<Vec<SlotProvider>>, ApiError> {
+        let active: Option<bool> = sqlx::query_scalar("SELECT is_active FROM service_catalog WHERE service_id = $1")
+            .bind(service_id)
+            .fetch_optional(self.read_db())
+            .await?;
+        if active != Some(true) {
+            return Ok(None);
+        }
+
+        let rows: Vec<(Uuid, i64, String, Option<DateTime<Utc>>, Option<Uuid>, Option<i32>)> = sqlx::query_as(
+            r#"
+            SELECT
+              e.employee_id,
+              e.employee_display_number,
+              e.first_name || ' ' || e.last_name,
+              e.photo_updated_at,
+              e.location_id,
+              COALESCE(es.duration_min, sc.default_duration_min)
+            FROM employee e
+            JOIN "dcms_user" u ON u.user_id = e.user_id
+            JOIN service_catalog sc ON sc.service_id = $1
+            LEFT JOIN employee_service es ON es.employee_id = e.employee_id AND es.service_id = sc.service_id
+            WHERE u.roles = 3
+              AND u.is_active = true
+              AND (
+                es.service_id IS NOT NULL
+                OR NOT EXISTS (SELECT 1 FROM employee_service x WHERE x.employee_id = e.employee_id)
+              )
+              AND ($2::uuid IS NULL OR e.employee_id = $2)
+              AND ($3::uuid[] IS NULL OR e.location_id = ANY($3))
+            ORDER BY e.employee_display_number ASC
+            "#,
+        )
+        .bind(service_id)
+        .bind(doctor_employee_id)
+        .bind(locations)
+        .fetch_all(self.read_db())
+        .await?;
+
+        Ok(Some(
+            rows.into_iter()
+                .map(|(id, number, display, photo, location_id, duration_min)| SlotProvider {
+                    doctor: PersonBrief {
+                        id,
+                        display,
+                        number: Some(number),
+                        photo_url: photos::photo_url("employees", id, photo),
+                        no_show_risk: None,
+                    },
+                    location_id,
+                    duration_min,
+                })
+                .collect(),
+        ))
+    }
+
+    async fn busy_intervals(
+        &self,
+        doctor_employee_ids: &[Uuid],
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+    ) -> Result<Vec<(Uuid, DateTime<Utc>, DateTime<Utc>)>, ApiError> {
+        // canceled / no-show appointments free the chair (AppointmentStatus::occupies_slot)
+        Ok(sqlx::query_as(
+            r#"
+            SELECT doctor_employee_id, start_at, end_at
+            FROM appointment
+            WHERE doctor_employee_id = ANY($1)
+              AND status NOT IN (1,3)
+              AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+            UNION ALL
+            SELECT employee_id, start_at, end_at
+            FROM employee_time_off
+            WHERE employee_id = ANY($1)
+              AND status = $4
+              AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+            ORDER BY 2
+            "#,
+        )
+        .bind(doctor_employee_ids)
+        .bind(start_ts)
+        .bind(end_ts)
+        .bind(TimeOffStatus::Approved)
+        .fetch_all(self.read_db())
+        .await?)
+    }
+
+    async fn notes(&self, appointment_id: Uuid) -> Result<Vec<AppointmentNoteDto>, ApiError> {
+        Ok(sqlx::query_as::<_, AppointmentNoteDto>(
+            r#"
+            SELECT
+              n.appointment_note_id,
+              n.appointment_id,
+              n.author_user_id,
+              u.display_name AS author_name,
+              n.note_text,
+              n.is_pinned,
+              n.created_at,
+              n.procedure_template_id,
+              n.procedure_fields
+            FROM appointment_note n
+            LEFT JOIN dcms_user u ON u.user_id = n.author_user_id
+            WHERE n.appointment_id = $1
+            ORDER BY n.created_at ASC, n.appointment_note_id ASC
+            "#,
+        )
+        .bind(appointment_id)
+        .fetch_all(&self.db)
+        .await?)
+    }
+
+    async fn history(&self, appointment_id: Uuid) -> Result<Vec<AppointmentChangeDto>, ApiError> {
+        Ok(sqlx::query_as::<_, AppointmentChangeDto>(
+            r#"
+            SELECT
+              c.changed_at,
+              c.field,
+              c.old_value,
+              c.new_value,
+              c.changed_by_user_id,
+              u.display_name AS changed_by_name
+            FROM appointment_change c
+            LEFT JOIN dcms_user u ON u.user_id = c.changed_by_user_id
+            WHERE c.appointment_id = $1
+            ORDER BY c.appointment_change_id ASC
+            "#,
+        )
+        .bind(appointment_id)
+        .fetch_all(self.read_db())
+        .await?)
+    }
+
+    async fn mark(&self, appointment_id: Uuid, user_id: Uuid, milestone: Milestone) -> Result<(), ApiError> {
+        let sql = format!(
+            "UPDATE appointment SET {}, status = COALESCE($3, status), updated_at = now(), updated_by_user_id = $2 \
+             WHERE appointment_id = $1",
+            milestone_set_sql(milestone)
+        );
+        sqlx::query(&sql)
+            .bind(appointment_id)
+            .bind(user_id)
+            .bind(milestone.status())
+            .execute(&self.db)
+            .await
+            .map_err(ApiError::write_failed("APPOINTMENT_UPDATE_FAILED"))?;
+        Ok(())
+    }
+
+    async fn seating_blockers(&self, appointment_id: Uuid) -> Result<Vec<SeatingBlocker>, ApiError> {
+        Ok(sqlx::query_as::<_, SeatingBlocker>(
+            r#"
+            SELECT oi.onboarding_item_id, oi.code, oi.label
+            FROM appointment_onboarding_item aoi
+            JOIN onboarding_item oi ON oi.onboarding_item_id = aoi.onboarding_item_id
+            WHERE aoi.appointment_id = $1
+              AND aoi.completed_at IS NULL
+              AND oi.required_before_seating
+            ORDER BY oi.sort_order, oi.label
+            "#,
+        )
+        .bind(appointment_id)
+        .fetch_all(&self.db)
+        .await?)
+    }
+}
+
+/* ============================================================
+   Helper: fold joined rows into appointment blocks
+   ============================================================ */
+
+fn fold_rows_into_blocks(rows: Vec<PgRow>) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+    let mut blocks: Vec<AppointmentBlockDto> = Vec::new();
+    let mut index: HashMap<Uuid, usize> = HashMap::new();
+
+    for r in rows {
+        let appointment_id: Uuid = r.try_get("appointment_id").map_err(internal_row)?;
+        let start_at: DateTime<Utc> = r.try_get("start_at").map_err(internal_row)?;
+        let end_at: DateTime<Utc> = r.try_get("end_at").map_err(internal_row)?;
+        let status: AppointmentStatus = r.try_get("status").map_err(internal_row)?;
+        let priority: i16 = r.try_get("priority").map_err(internal_row)?;
+        let color_override: Option<i32> = r.try_get("color_override").map_err(internal_row)?;
+        let note: Option<String> = r.try_get("note").map_err(internal_row)?;
+
+        let source: String = r.try_get("source").unwrap_or_else(|_| "SCHEDULED".into());
+        let confirmed_at: Option<DateTime<Utc>> = r.try_get("confirmed_at").ok();
+        let reminder_sent_at: Option<DateTime<Utc>> = r.try_get("reminder_sent_at").ok();
+        let location_id: Option<Uuid> = r.try_get("location_id").ok().flatten();
+
+        let p_id: Uuid = r.try_get("patient_id").map_err(internal_row)?;
+        let p_first: String = r.try_get("p_first").map_err(internal_row)?;
+        let p_last: String = r.try_get("p_last").map_err(internal_row)?;
+        let p_reg: Option<i64> = r.try_get("p_reg").ok();
+
+        let d_id: Uuid = r.try_get("d_id").map_err(internal_row)?;
+        let d_no: i64 = r.try_get("d_no").map_err(internal_row)?;
+        let d_first: String = r.try_get("d_first").map_err(internal_row)?;
+        let d_last: String = r.try_get("d_last").map_err(internal_row)?;
+        let p_photo: Option<DateTime<Utc>> = r.try_get("p_photo").map_err(internal_row)?;
+        let d_photo: Option<DateTime<Utc>> = r.try_get("d_photo").map_err(internal_row)?;
+        let p_risk: Option<i16> = r.try_get("p_risk").map_err(internal_row)?;
+
+        let i = *index.entry(appointment_id).or_insert_with(|| {
+            blocks.push(AppointmentBlockDto {
+                appointment_id,
+                start_at,
+                end_at,
+                status,
+                priority,
+                color_override,
+                note: note.clone(),
+                source: source.clone(),
+                confirmed_at,
+                reminder_sent_at,
+                location_id,
+                patient: PersonBrief {
+                    id: p_id,
+                    display: format!("{p_first} {p_last}"),
+                    number: p_reg,
+                    photo_url: photos::photo_url("patients", p_id, p_photo),
+                    no_show_risk: p_risk,
+                },
+                doctor: PersonBrief {
+                    id: d_id,
+                    display: format!("{d_first} {d_last}"),
+                    number: Some(d_no),
+                    photo_url: photos::photo_url("employees", d_id, d_photo),
+                    no_show_risk: None,
+                },
+                planned_items: vec![],
+                planned_summary: String::new(),
+                needs_double_confirm: status == AppointmentStatus::Scheduled && confirmed_at.is_none() && no_show_risk::is_high_risk(p_risk),
+            });
+            blocks.len() - 1
+        });
+        let entry = &mut blocks[i];
+
+        let svc_id: Option<Uuid> = r.try_get("svc_id").ok();
+        if let Some(service_id) = svc_id {
+            let qty: i32 = r.try_get("svc_qty").unwrap_or(1);
+            let name: String = r.try_get("svc_name").unwrap_or_else(|_| "Service".into());
+            entry.planned_items.push(AppointmentPlanItemDto {
+                service_id,
+                display_name: name,
+                qty,
+            });
+        }
+    }
+
+    for v in &mut blocks {
+        v.planned_summary = planned_summary(&v.planned_items);
+    }
+
+    Ok(blocks)
+}
+
+fn internal_row(e: sqlx::Error) -> ApiError {
+    ApiError::Internal(format!("row decode error: {e}"))
+}
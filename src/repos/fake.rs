@@ -0,0 +1,461 @@
+// src/repos/fake.rs
+//
+// In-memory repositories for service unit tests. Only as much behaviour as the
+// services rely on; anything a test doesn't seed comes back empty.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+use super::{AppointmentRepo, PatientRepo};
+use crate::{
+    error::ApiError,
+    models::{AppointmentStatus, Role},
+    onboarding::SeatingBlocker,
+    services::{
+        appointments::{
+            AppointmentBlockDto, AppointmentChangeDto, AppointmentNoteDto, Milestone, OverdueCounts, OverdueFilter,
+            QueueEntryDto,
+        },
+        availability::SlotProvider,
+        patients::{DuplicateCandidate, DuplicateKey, PatientDeletionRow, PatientFields, PatientRow, PhoneMatch},
+    },
+};
+
+pub struct FakePatientRepo {
+    pub tz: Tz,
+    pub patients: Mutex<Vec<PatientRow>>,
+    pub primary_phones: HashMap<Uuid, String>,
+    /// referral_source_id -> is_active
+    pub referral_sources: HashMap<Uuid, bool>,
+    pub user_ids: Vec<Uuid>,
+}
+
+impl Default for FakePatientRepo {
+    fn default() -> Self {
+        Self {
+            tz: Tz::UTC,
+            patients: Mutex::default(),
+            primary_phones: HashMap::new(),
+            referral_sources: HashMap::new(),
+            user_ids: Vec::new(),
+        }
+    }
+}
+
+impl FakePatientRepo {
+    fn row(patient_id: Uuid, register_number: String, f: &PatientFields) -> PatientRow {
+        PatientRow {
+            patient_id,
+            register_number,
+            user_id: f.user_id,
+            first_name: f.first_name.clone(),
+            last_name: f.last_name.clone(),
+            email: f.email.clone(),
+            birthday: f.birthday,
+            gender: f.gender,
+            status: f.status,
+            created_at: Utc::now(),
+            last_seen_at: None,
+            photo_url: None,
+            referral_source_id: f.referral_source_id,
+            display_name: format!("{} {}", f.first_name, f.last_name),
+            age: None,
+            primary_phone: None,
+        }
+    }
+
+    fn with_patient<T>(&self, patient_id: Uuid, f: impl FnOnce(&mut PatientRow) -> T) -> Option<T> {
+        self.patients.lock().unwrap().iter_mut().find(|p| p.patient_id == patient_id).map(f)
+    }
+}
+
+#[async_trait]
+impl PatientRepo for FakePatientRepo {
+    async fn clinic_tz(&self) -> Result<Tz, ApiError> {
+        Ok(self.tz)
+    }
+
+    async fn get(&self, patient_id: Uuid) -> Result<Option<PatientRow>, ApiError> {
+        Ok(self.with_patient(patient_id, |p| p.clone()))
+    }
+
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<PatientRow>, ApiError> {
+        let q = query.to_lowercase();
+        Ok(self
+            .patients
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|p| {
+                q.is_empty()
+                    || [&p.register_number, &p.first_name, &p.last_name]
+                        .iter()
+                        .any(|s| s.to_lowercase().contains(&q))
+            })
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn page_after(&self, after: Option<Uuid>, ids: Option<&[Uuid]>, limit: i64) -> Result<Vec<PatientRow>, ApiError> {
+        let mut rows: Vec<PatientRow> = self
+            .patients
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|p| after.is_none_or(|a| p.patient_id > a) && ids.is_none_or(|ids| ids.contains(&p.patient_id)))
+            .cloned()
+            .collect();
+        rows.sort_by_key(|p| p.patient_id);
+        rows.truncate(limit as usize);
+        Ok(rows)
+    }
+
+    async fn primary_phones(&self, patient_ids: &[Uuid]) -> Result<HashMap<Uuid, String>, ApiError> {
+        Ok(self
+            .primary_phones
+            .iter()
+            .filter(|(id, _)| patient_ids.contains(id))
+            .map(|(id, phone)| (*id, phone.clone()))
+            .collect())
+    }
+
+    async fn referral_source_active(&self, referral_source_id: Uuid) -> Result<Option<bool>, ApiError> {
+        Ok(self.referral_sources.get(&referral_source_id).copied())
+    }
+
+    /// name + birthday only (no phone numbers in the fake)
+    async fn duplicate_candidates(&self, key: &DuplicateKey, limit: i64) -> Result<Vec<DuplicateCandidate>, ApiError> {
+        Ok(self
+            .patients
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|p| {
+                let (first, last) = (p.first_name.to_lowercase(), p.last_name.to_lowercase());
+                key.birthday.is_some()
+                    && p.birthday == key.birthday
+                    && ((first == key.first_name && last == key.last_name)
+                        || (first == key.last_name && last == key.first_name))
+            })
+            .take(limit as usize)
+            .map(|p| DuplicateCandidate {
+                patient_id: p.patient_id,
+                register_number: p.register_number.clone(),
+                display_name: p.display_name.clone(),
+                birthday: p.birthday,
+                matched_on: vec!["name_birthday"],
+            })
+            .collect())
+    }
+
+    /// primary numbers only (`primary_phones`); anonymization isn't modelled
+    async fn phone_matches(&self, digits: &str, key: &str, limit: i64) -> Result<Vec<PhoneMatch>, ApiError> {
+        let patients = self.patients.lock().unwrap();
+        let mut out: Vec<PhoneMatch> = patients
+            .iter()
+            .filter_map(|p| {
+                let number = self.primary_phones.get(&p.patient_id)?;
+                let stored: String = number.chars().filter(char::is_ascii_digit).collect();
+                stored.ends_with(key).then(|| PhoneMatch {
+                    phone_number_id: Uuid::new_v4(),
+                    phone_number: number.clone(),
+                    label: "Self".into(),
+                    is_primary: true,
+                    matched_on: if stored == digits { "exact" } else { "last_digits" },
+                    patient_id: p.patient_id,
+                    register_number: p.register_number.clone(),
+                    display_name: p.display_name.clone(),
+                    birthday: p.birthday,
+                    status: p.status,
+                })
+            })
+            .collect();
+        out.sort_by_key(|m| m.matched_on != "exact");
+        out.truncate(limit as usize);
+        Ok(out)
+    }
+
+    async fn insert(&self, fields: &PatientFields) -> Result<PatientRow, ApiError> {
+        let mut patients = self.patients.lock().unwrap();
+        let register_number = fields
+            .register_number
+            .clone()
+            .unwrap_or_else(|| format!("P{:06}", patients.len() + 1));
+        let row = Self::row(Uuid::new_v4(), register_number, fields);
+        let out = row.clone();
+        patients.push(row);
+        Ok(out)
+    }
+
+    async fn update(&self, patient_id: Uuid, fields: &PatientFields) -> Result<Option<PatientRow>, ApiError> {
+        Ok(self.with_patient(patient_id, |p| {
+            let register_number = fields.register_number.clone().unwrap_or_else(|| p.register_number.clone());
+            *p = Self::row(patient_id, register_number, fields);
+            p.clone()
+        }))
+    }
+
+    async fn user_exists(&self, user_id: Uuid) -> Result<bool, ApiError> {
+        Ok(self.user_ids.contains(&user_id))
+    }
+
+    async fn set_user(&self, patient_id: Uuid, user_id: Option<Uuid>) -> Result<Option<PatientRow>, ApiError> {
+        Ok(self.with_patient(patient_id, |p| {
+            p.user_id = user_id;
+            p.clone()
+        }))
+    }
+
+    async fn set_status(&self, patient_id: Uuid, status: i16) -> Result<Option<PatientRow>, ApiError> {
+        Ok(self.with_patient(patient_id, |p| {
+            p.status = status;
+            p.clone()
+        }))
+    }
+
+    async fn request_deletion(&self, _: Uuid, _: Uuid) -> Result<Option<PatientDeletionRow>, ApiError> {
+        Ok(None)
+    }
+
+    async fn cancel_deletion(&self, _: Uuid) -> Result<Option<PatientDeletionRow>, ApiError> {
+        Ok(None)
+    }
+}
+
+pub struct FakeAppointmentRepo {
+    pub tz: Tz,
+    /// user_id -> employee_id
+    pub employees: HashMap<Uuid, Uuid>,
+    /// appointment_id -> doctor_employee_id
+    pub doctors: HashMap<Uuid, Uuid>,
+    pub statuses: Mutex<HashMap<Uuid, AppointmentStatus>>,
+    /// closed dates (clinic_holiday.is_closed)
+    pub closures: Vec<(NaiveDate, String)>,
+    pub slot_minutes: i64,
+    pub business_hours: JsonValue,
+    /// service_id -> doctors who perform it (the service exists and is active)
+    pub providers: HashMap<Uuid, Vec<SlotProvider>>,
+    /// (doctor_employee_id, start, end) of slot-occupying appointments
+    pub busy: Vec<(Uuid, DateTime<Utc>, DateTime<Utc>)>,
+    /// (employee_id, start, end) of approved time off
+    pub time_off: Vec<(Uuid, DateTime<Utc>, DateTime<Utc>)>,
+    /// (employee_id, start, end) of staff shifts
+    pub shifts: Vec<(Uuid, DateTime<Utc>, DateTime<Utc>)>,
+    /// employee_id -> (login role, active)
+    pub assistants: HashMap<Uuid, (Option<Role>, bool)>,
+    /// (assistant_employee_id, appointment_id, start, end) of slot-occupying appointments
+    pub assisting: Vec<(Uuid, Uuid, DateTime<Utc>, DateTime<Utc>)>,
+    pub marked: Mutex<Vec<(Uuid, Milestone)>>,
+    /// appointment_id -> open onboarding items required before seating
+    pub seating_blockers: HashMap<Uuid, Vec<SeatingBlocker>>,
+}
+
+impl Default for FakeAppointmentRepo {
+    fn default() -> Self {
+        Self {
+            tz: Tz::UTC,
+            employees: HashMap::new(),
+            doctors: HashMap::new(),
+            statuses: Mutex::default(),
+            closures: Vec::new(),
+            slot_minutes: 30,
+            business_hours: JsonValue::Null,
+            providers: HashMap::new(),
+            busy: Vec::new(),
+            time_off: Vec::new(),
+            shifts: Vec::new(),
+            assistants: HashMap::new(),
+            assisting: Vec::new(),
+            marked: Mutex::default(),
+            seating_blockers: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl AppointmentRepo for FakeAppointmentRepo {
+    async fn clinic_tz(&self) -> Result<Tz, ApiError> {
+        Ok(self.tz)
+    }
+
+    async fn employee_id_for_user(&self, user_id: Uuid) -> Result<Option<Uuid>, ApiError> {
+        Ok(self.employees.get(&user_id).copied())
+    }
+
+    async fn doctor_of(&self, appointment_id: Uuid) -> Result<Option<Uuid>, ApiError> {
+        Ok(self.doctors.get(&appointment_id).copied())
+    }
+
+    async fn status_of(&self, appointment_id: Uuid) -> Result<Option<AppointmentStatus>, ApiError> {
+        Ok(self.statuses.lock().unwrap().get(&appointment_id).copied())
+    }
+
+    async fn blocks_in_range(
+        &self,
+        _: Uuid,
+        _: DateTime<Utc>,
+        _: DateTime<Utc>,
+        _: Option<&[Uuid]>,
+    ) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+        Ok(vec![])
+    }
+
+    async fn overdue(&self, _: &OverdueFilter) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+        Ok(vec![])
+    }
+
+    async fn overdue_counts(&self, _: &OverdueFilter) -> Result<OverdueCounts, ApiError> {
+        Ok(OverdueCounts::default())
+    }
+
+    async fn get_block(&self, _: Uuid) -> Result<Option<AppointmentBlockDto>, ApiError> {
+        Ok(None)
+    }
+
+    async fn blocks_by_ids(&self, _: &[Uuid]) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+        Ok(vec![])
+    }
+
+    async fn blocks_after(&self, _: Option<Uuid>, _: Option<&[Uuid]>, _: i64) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+        Ok(vec![])
+    }
+
+    async fn queue(
+        &self,
+        _: DateTime<Utc>,
+        _: DateTime<Utc>,
+        _: Option<Uuid>,
+        _: Option<&[Uuid]>,
+    ) -> Result<Vec<QueueEntryDto>, ApiError> {
+        Ok(vec![])
+    }
+
+    async fn first_closure(&self, first_day: NaiveDate, last_day: NaiveDate) -> Result<Option<(NaiveDate, String)>, ApiError> {
+        let mut hits: Vec<_> = self
+            .closures
+            .iter()
+            .filter(|(d, _)| (first_day..=last_day).contains(d))
+            .cloned()
+            .collect();
+        hits.sort();
+        Ok(hits.into_iter().next())
+    }
+
+    async fn first_time_off(
+        &self,
+        employee_id: Uuid,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+    ) -> Result<Option<(DateTime<Utc>, DateTime<Utc>)>, ApiError> {
+        Ok(self
+            .time_off
+            .iter()
+            .filter(|(id, start, end)| *id == employee_id && *start < end_ts && start_ts < *end)
+            .map(|(_, start, end)| (*start, *end))
+            .min())
+    }
+
+    async fn assistant_profile(&self, employee_id: Uuid) -> Result<Option<(Option<Role>, bool)>, ApiError> {
+        Ok(self.assistants.get(&employee_id).copied())
+    }
+
+    async fn assistant_conflict(
+        &self,
+        employee_id: Uuid,
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+        exclude: Option<Uuid>,
+    ) -> Result<Option<(Uuid, DateTime<Utc>, DateTime<Utc>)>, ApiError> {
+        Ok(self
+            .assisting
+            .iter()
+            .filter(|(id, appt, start, end)| {
+                *id == employee_id && Some(*appt) != exclude && *start < end_ts && start_ts < *end
+            })
+            .map(|(_, appt, start, end)| (*appt, *start, *end))
+            .min_by_key(|(_, start, _)| *start))
+    }
+
+    async fn on_shift(&self, employee_id: Uuid, start_ts: DateTime<Utc>, end_ts: DateTime<Utc>) -> Result<bool, ApiError> {
+        let mut shifts: Vec<_> = self.shifts.iter().filter(|(id, _, _)| *id == employee_id).collect();
+        shifts.sort_by_key(|(_, start, _)| *start);
+        let mut covered_to = start_ts;
+        for (_, start, end) in shifts {
+            if *start <= covered_to && *end > covered_to {
+                covered_to = *end;
+            }
+        }
+        Ok(covered_to >= end_ts)
+    }
+
+    async fn closed_days(&self, first_day: NaiveDate, last_day: NaiveDate) -> Result<Vec<NaiveDate>, ApiError> {
+        Ok(self
+            .closures
+            .iter()
+            .map(|(d, _)| *d)
+            .filter(|d| (first_day..=last_day).contains(d))
+            .collect())
+    }
+
+    async fn scheduling_settings(&self) -> Result<(i64, JsonValue), ApiError> {
+        Ok((self.slot_minutes, self.business_hours.clone()))
+    }
+
+    async fn slot_providers(
+        &self,
+        service_id: Uuid,
+        doctor_employee_id: Option<Uuid>,
+        locations: Option<&[Uuid]>,
+    ) -> Result<Option<Vec<SlotProvider>>, ApiError> {
+        Ok(self.providers.get(&service_id).map(|providers| {
+            providers
+                .iter()
+                .filter(|p| doctor_employee_id.is_none_or(|id| p.doctor.id == id))
+                .filter(|p| locations.is_none_or(|ls| p.location_id.is_some_and(|l| ls.contains(&l))))
+                .cloned()
+                .collect()
+        }))
+    }
+
+    async fn busy_intervals(
+        &self,
+        doctor_employee_ids: &[Uuid],
+        start_ts: DateTime<Utc>,
+        end_ts: DateTime<Utc>,
+    ) -> Result<Vec<(Uuid, DateTime<Utc>, DateTime<Utc>)>, ApiError> {
+        Ok(self
+            .busy
+            .iter()
+            .chain(&self.time_off)
+            .filter(|(id, start, end)| doctor_employee_ids.contains(id) && *start < end_ts && start_ts < *end)
+            .copied()
+            .collect())
+    }
+
+    async fn notes(&self, _: Uuid) -> Result<Vec<AppointmentNoteDto>, ApiError> {
+        Ok(vec![])
+    }
+
+    async fn history(&self, _: Uuid) -> Result<Vec<AppointmentChangeDto>, ApiError> {
+        Ok(vec![])
+    }
+
+    async fn mark(&self, appointment_id: Uuid, _: Uuid, milestone: Milestone) -> Result<(), ApiError> {
+        if let Some(status) = milestone.status() {
+            self.statuses.lock().unwrap().insert(appointment_id, status);
+        }
+        self.marked.lock().unwrap().push((appointment_id, milestone));
+        Ok(())
+    }
+
+    async fn seating_blockers(&self, appointment_id: Uuid) -> Result<Vec<SeatingBlocker>, ApiError> {
+        Ok(self.seating_blockers.get(&appointment_id).cloned().unwrap_or_default())
+    }
+}
@@ -0,0 +1,52 @@
+// src/repos/mod.rs
+//
+// Repositories: the SQL behind services/, as traits so the service rules can be
+// unit-tested against in-memory fakes (`fake`, tests only). The Postgres
+// implementations are built once in main and shared through `AppState::repos`.
+// Multi-statement writes that must share one transaction (booking, appointment
+// PATCH, plan items) stay on `PgConnection` in the services.
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+
+use crate::db::ReadReplica;
+
+pub mod appointments;
+pub mod patients;
+
+#[cfg(test)]
+pub mod fake;
+
+pub use appointments::AppointmentRepo;
+pub use patients::PatientRepo;
+
+#[derive(Clone)]
+pub struct Repos {
+    pub appointments: Arc<dyn AppointmentRepo>,
+    pub patients: Arc<dyn PatientRepo>,
+}
+
+impl Repos {
+    /// `replica`: lag-tolerant reads (schedule views, search) go there while it is healthy.
+    pub fn postgres(db: PgPool, replica: Option<ReadReplica>) -> Self {
+        let pg = PgRepo { db, replica };
+        Self {
+            appointments: Arc::new(pg.clone()),
+            patients: Arc::new(pg),
+        }
+    }
+}
+
+/// Postgres implementation of every repository trait.
+#[derive(Clone)]
+pub struct PgRepo {
+    db: PgPool,
+    replica: Option<ReadReplica>,
+}
+
+impl PgRepo {
+    fn read_db(&self) -> &PgPool {
+        crate::db::read_pool(&self.db, self.replica.as_ref())
+    }
+}
@@ -0,0 +1,394 @@
+// src/repos/patients.rs
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono_tz::Tz;
+use sqlx::{PgConnection, Row};
+use uuid::Uuid;
+
+use super::PgRepo;
+use crate::{
+    clinic_time,
+    error::ApiError,
+    numbering,
+    services::patients::{
+        DuplicateCandidate, DuplicateKey, PATIENT_STATUS_ARCHIVED, PHONE_MATCH_DIGITS, PatientDeletionRow,
+        PatientFields, PatientRow, PhoneMatch,
+    },
+};
+
+/// Rows come back without the derived fields (`services::patients::fill_derived`).
+#[async_trait]
+pub trait PatientRepo: Send + Sync {
+    async fn clinic_tz(&self) -> Result<Tz, ApiError>;
+    async fn get(&self, patient_id: Uuid) -> Result<Option<PatientRow>, ApiError>;
+    /// Register number / first / last name substring, newest first; empty query = newest.
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<PatientRow>, ApiError>;
+    /// Keyset page by patient_id (HQ sync, /sync/changes); `ids` None = every patient.
+    async fn page_after(&self, after: Option<Uuid>, ids: Option<&[Uuid]>, limit: i64) -> Result<Vec<PatientRow>, ApiError>;
+    async fn primary_phones(&self, patient_ids: &[Uuid]) -> Result<HashMap<Uuid, String>, ApiError>;
+    /// None = no such source
+    async fn referral_source_active(&self, referral_source_id: Uuid) -> Result<Option<bool>, ApiError>;
+    /// Name match first, then phone match; anonymized patients excluded.
+    async fn duplicate_candidates(&self, key: &DuplicateKey, limit: i64) -> Result<Vec<DuplicateCandidate>, ApiError>;
+    /// Numbers whose digits equal `digits` ("exact") or end in `key`, the last
+    /// PHONE_MATCH_DIGITS of them ("last_digits"); exact first, then primary numbers.
+    /// Released numbers (060) and anonymized patients excluded.
+    async fn phone_matches(&self, digits: &str, key: &str, limit: i64) -> Result<Vec<PhoneMatch>, ApiError>;
+    /// `register_number` None = the next one in the clinic's format (numbering.rs);
+    /// 409 REGISTER_NUMBER_TAKEN when it's in use
+    async fn insert(&self, fields: &PatientFields) -> Result<PatientRow, ApiError>;
+    /// Overwrites every field (None register_number keeps the current one); None = no such patient.
+    async fn update(&self, patient_id: Uuid, fields: &PatientFields) -> Result<Option<PatientRow>, ApiError>;
+    async fn user_exists(&self, user_id: Uuid) -> Result<bool, ApiError>;
+    async fn set_user(&self, patient_id: Uuid, user_id: Option<Uuid>) -> Result<Option<PatientRow>, ApiError>;
+    async fn set_status(&self, patient_id: Uuid, status: i16) -> Result<Option<PatientRow>, ApiError>;
+    /// Archives and schedules anonymization (first request wins); None = missing or anonymized.
+    async fn request_deletion(
+        &self,
+        patient_id: Uuid,
+        requested_by_user_id: Uuid,
+    ) -> Result<Option<PatientDeletionRow>, ApiError>;
+    /// None = no pending request.
+    async fn cancel_deletion(&self, patient_id: Uuid) -> Result<Option<PatientDeletionRow>, ApiError>;
+}
+
+/// SELECT / RETURNING list matching `PatientRow`'s FromRow
+const PATIENT_COLUMNS: &str = "patient_id, register_number, user_id, first_name, last_name, email, \
+     birthday, gender, status, created_at, last_seen_at, photo_updated_at, referral_source_id";
+
+#[async_trait]
+impl PatientRepo for PgRepo {
+    async fn clinic_tz(&self) -> Result<Tz, ApiError> {
+        Ok(clinic_time::clinic_tz(self.read_db()).await?)
+    }
+
+    async fn get(&self, patient_id: Uuid) -> Result<Option<PatientRow>, ApiError> {
+        Ok(
+            sqlx::query_as::<_, PatientRow>(&format!("SELECT {PATIENT_COLUMNS} FROM patient WHERE patient_id = $1"))
+                .bind(patient_id)
+                .fetch_optional(&self.db)
+                .await?,
+        )
+    }
+
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<PatientRow>, ApiError> {
+        let like = (!query.is_empty()).then(|| format!("%{query}%"));
+        Ok(sqlx::query_as::<_, PatientRow>(&format!(
+            r#"
+            SELECT {PATIENT_COLUMNS}
+            FROM patient
+            WHERE $1::text IS NULL
+               OR register_number ILIKE $1
+               OR first_name ILIKE $1
+               OR last_name ILIKE $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        ))
+        .bind(like)
+        .bind(limit)
+        .fetch_all(self.read_db())
+        .await?)
+    }
+
+    async fn page_after(&self, after: Option<Uuid>, ids: Option<&[Uuid]>, limit: i64) -> Result<Vec<PatientRow>, ApiError> {
+        Ok(sqlx::query_as::<_, PatientRow>(&format!(
+            r#"
+            SELECT {PATIENT_COLUMNS}
+            FROM patient
+            WHERE ($1::uuid IS NULL OR patient_id > $1)
+              AND ($2::uuid[] IS NULL OR patient_id = ANY($2))
+            ORDER BY patient_id
+            LIMIT $3
+            "#
+        ))
+        .bind(after)
+        .bind(ids)
+        .bind(limit)
+        .fetch_all(self.read_db())
+        .await?)
+    }
+
+    async fn primary_phones(&self, patient_ids: &[Uuid]) -> Result<HashMap<Uuid, String>, ApiError> {
+        Ok(sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT patient_id, phone_number
+            FROM phone_number
+            WHERE patient_id = ANY($1)
+              AND is_primary = true
+            "#,
+        )
+        .bind(patient_ids)
+        .fetch_all(self.read_db())
+        .await?
+        .into_iter()
+        .collect())
+    }
+
+    async fn referral_source_active(&self, referral_source_id: Uuid) -> Result<Option<bool>, ApiError> {
+        Ok(sqlx::query_scalar("SELECT is_active FROM referral_source WHERE referral_source_id = $1")
+            .bind(referral_source_id)
+            .fetch_optional(&self.db)
+            .await?)
+    }
+
+    async fn duplicate_candidates(&self, key: &DuplicateKey, limit: i64) -> Result<Vec<DuplicateCandidate>, ApiError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT *
+            FROM (
+              SELECT
+                p.patient_id, p.register_number, p.first_name, p.last_name, p.birthday,
+                (
+                  $3::date IS NOT NULL AND p.birthday = $3
+                  AND (
+                    (lower(regexp_replace(btrim(p.first_name), '\s+', ' ', 'g')) = $1
+                     AND lower(regexp_replace(btrim(p.last_name), '\s+', ' ', 'g')) = $2)
+                    OR
+                    (lower(regexp_replace(btrim(p.first_name), '\s+', ' ', 'g')) = $2
+                     AND lower(regexp_replace(btrim(p.last_name), '\s+', ' ', 'g')) = $1)
+                  )
+                ) AS name_match,
+                (
+                  $4::text IS NOT NULL
+                  AND EXISTS (
+                    SELECT 1
+                    FROM phone_number ph
+                    WHERE ph.patient_id = p.patient_id
+                      AND ph.released_at IS NULL
+                      AND right(regexp_replace(ph.phone_number, '\D', '', 'g'), $5) = $4
+                  )
+                ) AS phone_match
+              FROM patient p
+              WHERE p.anonymized_at IS NULL
+            ) c
+            WHERE c.name_match OR c.phone_match
+            ORDER BY c.name_match DESC, c.phone_match DESC, c.register_number
+            LIMIT $6
+            "#,
+        )
+        .bind(&key.first_name)
+        .bind(&key.last_name)
+        .bind(key.birthday)
+        .bind(&key.phone_key)
+        .bind(PHONE_MATCH_DIGITS as i32)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await?;
+
+        rows.iter()
+            .map(|r| {
+                let first: String = r.try_get("first_name")?;
+                let last: String = r.try_get("last_name")?;
+                let mut matched_on = Vec::new();
+                if r.try_get::<bool, _>("name_match")? {
+                    matched_on.push("name_birthday");
+                }
+                if r.try_get::<bool, _>("phone_match")? {
+                    matched_on.push("phone");
+                }
+                Ok(DuplicateCandidate {
+                    patient_id: r.try_get("patient_id")?,
+                    register_number: r.try_get("register_number")?,
+                    display_name: format!("{first} {last}"),
+                    birthday: r.try_get("birthday")?,
+                    matched_on,
+                })
+            })
+            .collect::<Result<_, sqlx::Error>>()
+            .map_err(|e| ApiError::Internal(format!("row decode error: {e}")))
+    }
+
+    async fn phone_matches(&self, digits: &str, key: &str, limit: i64) -> Result<Vec<PhoneMatch>, ApiError> {
+        // the 8 is spelled out so the planner can use phone_number_match_key_idx (059)
+        let rows = sqlx::query(
+            r#"
+            SELECT
+              ph.phone_number_id, ph.phone_number, ph.label, ph.is_primary,
+              regexp_replace(ph.phone_number, '\D', '', 'g') = $1 AS exact,
+              p.patient_id, p.register_number, p.first_name, p.last_name, p.birthday, p.status
+            FROM phone_number ph
+            JOIN patient p ON p.patient_id = ph.patient_id
+            WHERE right(regexp_replace(ph.phone_number, '\D', '', 'g'), 8) = $2
+              AND ph.released_at IS NULL
+              AND p.anonymized_at IS NULL
+            ORDER BY exact DESC, ph.is_primary DESC, p.last_seen_at DESC NULLS LAST, p.register_number
+            LIMIT $3
+            "#,
+        )
+        .bind(digits)
+        .bind(key)
+        .bind(limit)
+        .fetch_all(self.read_db())
+        .await?;
+
+        rows.iter()
+            .map(|r| {
+                let first: String = r.try_get("first_name")?;
+                let last: String = r.try_get("last_name")?;
+                Ok(PhoneMatch {
+                    phone_number_id: r.try_get("phone_number_id")?,
+                    phone_number: r.try_get("phone_number")?,
+                    label: r.try_get("label")?,
+                    is_primary: r.try_get("is_primary")?,
+                    matched_on: if r.try_get("exact")? { "exact" } else { "last_digits" },
+                    patient_id: r.try_get("patient_id")?,
+                    register_number: r.try_get("register_number")?,
+                    display_name: format!("{first} {last}"),
+                    birthday: r.try_get("birthday")?,
+                    status: r.try_get("status")?,
+                })
+            })
+            .collect::<Result<_, sqlx::Error>>()
+            .map_err(|e| ApiError::Internal(format!("row decode error: {e}")))
+    }
+
+    async fn insert(&self, f: &PatientFields) -> Result<PatientRow, ApiError> {
+        let mut conn = self.db.acquire().await?;
+        let register_number = match &f.register_number {
+            Some(rn) => rn.clone(),
+            None => numbering::next_register_number(&mut conn).await?,
+        };
+        sqlx::query_as::<_, PatientRow>(&format!(
+            r#"
+            INSERT INTO patient (register_number, user_id, first_name, last_name, email, birthday, gender, status, referral_source_id, created_at, last_seen_at)
+            VALUES ($9,$1,$2,$3,$4,$5,$6,$7,$8, now(), now())
+            RETURNING {PATIENT_COLUMNS}
+            "#
+        ))
+        .bind(f.user_id)
+        .bind(&f.first_name)
+        .bind(&f.last_name)
+        .bind(&f.email)
+        .bind(f.birthday)
+        .bind(f.gender)
+        .bind(f.status)
+        .bind(f.referral_source_id)
+        .bind(register_number)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(numbering::register_number_conflict)
+    }
+
+    async fn update(&self, patient_id: Uuid, f: &PatientFields) -> Result<Option<PatientRow>, ApiError> {
+        update_on(&mut *self.db.acquire().await?, patient_id, f).await
+    }
+
+    async fn user_exists(&self, user_id: Uuid) -> Result<bool, ApiError> {
+        Ok(sqlx::query_scalar(r#"SELECT EXISTS (SELECT 1 FROM "dcms_user" WHERE user_id = $1)"#)
+            .bind(user_id)
+            .fetch_one(&self.db)
+            .await?)
+    }
+
+    async fn set_user(&self, patient_id: Uuid, user_id: Option<Uuid>) -> Result<Option<PatientRow>, ApiError> {
+        Ok(sqlx::query_as::<_, PatientRow>(&format!(
+            "UPDATE patient SET user_id = $1, last_seen_at = now() WHERE patient_id = $2 RETURNING {PATIENT_COLUMNS}"
+        ))
+        .bind(user_id)
+        .bind(patient_id)
+        .fetch_optional(&self.db)
+        .await?)
+    }
+
+    async fn set_status(&self, patient_id: Uuid, status: i16) -> Result<Option<PatientRow>, ApiError> {
+        Ok(sqlx::query_as::<_, PatientRow>(&format!(
+            "UPDATE patient SET status = $1, last_seen_at = now() WHERE patient_id = $2 RETURNING {PATIENT_COLUMNS}"
+        ))
+        .bind(status)
+        .bind(patient_id)
+        .fetch_optional(&self.db)
+        .await?)
+    }
+
+    async fn request_deletion(
+        &self,
+        patient_id: Uuid,
+        requested_by_user_id: Uuid,
+    ) -> Result<Option<PatientDeletionRow>, ApiError> {
+        Ok(sqlx::query_as::<_, PatientDeletionRow>(
+            r#"
+            UPDATE patient
+            SET
+              status = $1,
+              deletion_requested_at = COALESCE(deletion_requested_at, now()),
+              deletion_requested_by_user_id = COALESCE(deletion_requested_by_user_id, $3),
+              deletion_due_at = COALESCE(
+                deletion_due_at,
+                now() + make_interval(days => COALESCE(
+                  (SELECT patient_retention_days FROM clinic_settings WHERE singleton_id = TRUE),
+                  30
+                ))
+              )
+            WHERE patient_id = $2
+              AND anonymized_at IS NULL
+            RETURNING patient_id, status, deletion_requested_at, deletion_due_at, anonymized_at
+            "#,
+        )
+        .bind(PATIENT_STATUS_ARCHIVED)
+        .bind(patient_id)
+        .bind(requested_by_user_id)
+        .fetch_optional(&self.db)
+        .await?)
+    }
+
+    async fn cancel_deletion(&self, patient_id: Uuid) -> Result<Option<PatientDeletionRow>, ApiError> {
+        Ok(sqlx::query_as::<_, PatientDeletionRow>(
+            r#"
+            UPDATE patient
+            SET
+              deletion_requested_at = NULL,
+              deletion_requested_by_user_id = NULL,
+              deletion_due_at = NULL
+            WHERE patient_id = $1
+              AND deletion_due_at IS NOT NULL
+              AND anonymized_at IS NULL
+            RETURNING patient_id, status, deletion_requested_at, deletion_due_at, anonymized_at
+            "#,
+        )
+        .bind(patient_id)
+        .fetch_optional(&self.db)
+        .await?)
+    }
+}
+
+/// `PatientRepo::update` on `conn`, for a caller that needs it inside its own
+/// transaction (POST /sync/push).
+pub async fn update_on(
+    conn: &mut PgConnection,
+    patient_id: Uuid,
+    f: &PatientFields,
+) -> Result<Option<PatientRow>, ApiError> {
+    sqlx::query_as::<_, PatientRow>(&format!(
+        r#"
+        UPDATE patient
+        SET register_number = COALESCE($1, register_number),
+            user_id = $2,
+            first_name = $3,
+            last_name = $4,
+            email = $5,
+            birthday = $6,
+            gender = $7,
+            status = $8,
+            referral_source_id = $10,
+            last_seen_at = now()
+        WHERE patient_id = $9
+        RETURNING {PATIENT_COLUMNS}
+        "#
+    ))
+    .bind(&f.register_number)
+    .bind(f.user_id)
+    .bind(&f.first_name)
+    .bind(&f.last_name)
+    .bind(&f.email)
+    .bind(f.birthday)
+    .bind(f.gender)
+    .bind(f.status)
+    .bind(patient_id)
+    .bind(f.referral_source_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(numbering::register_number_conflict)
+}
@@ -0,0 +1,533 @@
+// src/routes/admin_routes.rs
+//
+// Admin console:
+// - cross-user session management: /auth/sessions only covers the caller's own
+//   sessions; these let an admin find and kill sessions of any account
+//   (e.g. a compromised receptionist login)
+// - purge of dead sessions (normally done hourly by jobs/session_cleanup.rs)
+// - audit_log viewer
+// - on-demand rebuild of the report views (normally refreshed by a background job)
+// - database backup download (pg_dump, see backup.rs)
+// - nightly export runs (data_export.rs): history, and a run on demand
+// - the route/role matrix (permissions.rs); readable by any signed-in user, the
+//   client hides buttons with it
+
+use std::io;
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use futures_util::{future, stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::{
+    audit, backup, clinic_time,
+    data_export::{self, ExportRun, Trigger},
+    error::ApiError,
+    extract::Json,
+    jobs::{
+        report_refresh::{self, RefreshedView},
+        session_cleanup::{self, CleanupStats},
+    },
+    middleware::auth_context::AuthContext,
+    models::{ApiList, ApiOk, AppState, Role},
+    permissions::{self, RouteRule},
+};
+
+fn ensure_admin(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role == Role::Admin {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin can use the admin console".into(),
+        ))
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // /api/v1/admin/sessions?user_id=&active=&limit=&offset=
+        .route("/sessions", get(list_sessions))
+        // /api/v1/admin/sessions/{session_token_id}/revoke
+        .route("/sessions/{session_token_id}/revoke", post(revoke_session))
+        // /api/v1/admin/sessions/cleanup
+        .route("/sessions/cleanup", post(cleanup_sessions))
+        // /api/v1/admin/audit?action=&actor_user_id=&target_id=&limit=&offset=
+        .route("/audit", get(list_audit_log))
+        // /api/v1/admin/reports/refresh
+        .route("/reports/refresh", post(refresh_reports))
+        // /api/v1/admin/backup
+        .route("/backup", post(download_backup))
+        // /api/v1/admin/exports?limit=&offset=
+        .route("/exports", get(list_export_runs))
+        // /api/v1/admin/exports/run
+        .route("/exports/run", post(run_export))
+        // /api/v1/admin/exports/{run_id}
+        .route("/exports/{run_id}", get(get_export_run))
+        // /api/v1/admin/permissions
+        .route("/permissions", get(list_permissions))
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AdminSessionRow {
+    pub session_token_id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub roles: Role,
+    pub session_type: i16,
+    pub device_name: Option<String>,
+    pub impersonator_user_id: Option<Uuid>,
+    pub rotated_count: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoke_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminSessionsData {
+    pub sessions: Vec<AdminSessionRow>,
+    pub current_session_token_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminSessionsQuery {
+    pub user_id: Option<Uuid>,
+    /// true = not revoked and not expired, false = revoked or expired, omitted = all
+    pub active: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<AdminSessionsQuery>,
+) -> Result<Json<ApiOk<AdminSessionsData>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let limit = q.limit.unwrap_or(100).clamp(1, 500);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    let sessions: Vec<AdminSessionRow> = sqlx::query_as::<_, AdminSessionRow>(
+        r#"
+        SELECT
+          st.session_token_id,
+          st.user_id,
+          u.username,
+          u.display_name,
+          u.roles,
+          st.session_type,
+          st.device_name,
+          st.impersonator_user_id,
+          st.rotated_count,
+          st.created_at,
+          st.last_seen_at,
+          st.expires_at,
+          st.revoked_at,
+          st.revoke_reason
+        FROM session_token st
+        JOIN "dcms_user" u ON u.user_id = st.user_id
+        WHERE ($1::uuid IS NULL OR st.user_id = $1)
+          AND (
+            $2::boolean IS NULL
+            OR ((st.revoked_at IS NULL AND st.expires_at > now()) = $2)
+          )
+        ORDER BY st.last_seen_at DESC NULLS LAST, st.created_at DESC
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(q.user_id)
+    .bind(q.active)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk {
+        data: AdminSessionsData {
+            sessions,
+            current_session_token_id: auth.session_token_id,
+        },
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminRevokeData {
+    pub ok: bool,
+    pub revoked_session_token_id: Uuid,
+    pub user_id: Uuid,
+}
+
+/// Revokes any user's session (use /auth/logout for your own).
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(session_token_id): Path<Uuid>,
+) -> Result<Json<ApiOk<AdminRevokeData>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let mut tx = state
+        .db
+        .begin()
+        .await?;
+
+    let (user_id, impersonator_user_id): (Uuid, Option<Uuid>) = sqlx::query_as(
+        r#"
+        UPDATE session_token
+        SET revoked_at = now()
+        WHERE session_token_id = $1
+          AND revoked_at IS NULL
+        RETURNING user_id, impersonator_user_id
+        "#,
+    )
+    .bind(session_token_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| {
+        ApiError::NotFound("NOT_FOUND", "session not found or already revoked".into())
+    })?;
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "session.revoke",
+        "session_token",
+        Some(session_token_id),
+        serde_json::json!({
+            "user_id": user_id,
+            "impersonator_user_id": impersonator_user_id,
+        }),
+    )
+    .await?;
+
+    tx.commit()
+        .await?;
+
+    state.session_cache.invalidate_session(session_token_id);
+
+    Ok(Json(ApiOk {
+        data: AdminRevokeData {
+            ok: true,
+            revoked_session_token_id: session_token_id,
+            user_id,
+        },
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionCleanupData {
+    pub purged: u64,
+    pub retention_days: i64,
+    /// since server start, including background runs
+    pub totals: CleanupStats,
+}
+
+/// Purges dead sessions now (the background job does this hourly).
+pub async fn cleanup_sessions(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<ApiOk<SessionCleanupData>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let purged = session_cleanup::purge(&state.db, state.session_retention_days).await?;
+    audit::record(
+        &state.db,
+        &auth,
+        "session.cleanup",
+        "session_token",
+        None,
+        serde_json::json!({ "purged": purged, "retention_days": state.session_retention_days }),
+    )
+    .await?;
+
+    Ok(Json(ApiOk {
+        data: SessionCleanupData {
+            purged,
+            retention_days: state.session_retention_days,
+            totals: session_cleanup::stats(),
+        },
+    }))
+}
+
+/* ============================================================
+   Audit log
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditLogRow {
+    pub audit_log_id: Uuid,
+    pub actor_user_id: Option<Uuid>,
+    pub actor_username: Option<String>,
+    pub session_token_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub details: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    /// exact action, or a prefix ending in '.' (e.g. "impersonation.")
+    pub action: Option<String>,
+    pub actor_user_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+pub async fn list_audit_log(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<AuditLogQuery>,
+) -> Result<Json<ApiList<AuditLogRow>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let limit = q.limit.unwrap_or(100).clamp(1, 500);
+    let offset = q.offset.unwrap_or(0).max(0);
+    let action = q.action.as_deref().map(str::trim).filter(|s| !s.is_empty());
+
+    let rows: Vec<AuditLogRow> = sqlx::query_as::<_, AuditLogRow>(
+        r#"
+        SELECT
+          al.audit_log_id,
+          al.actor_user_id,
+          u.username AS actor_username,
+          al.session_token_id,
+          al.action,
+          al.target_type,
+          al.target_id,
+          al.details,
+          al.created_at
+        FROM audit_log al
+        LEFT JOIN "dcms_user" u ON u.user_id = al.actor_user_id
+        WHERE ($1::text IS NULL
+               OR al.action = $1
+               OR (right($1, 1) = '.' AND starts_with(al.action, $1)))
+          AND ($2::uuid IS NULL OR al.actor_user_id = $2)
+          AND ($3::uuid IS NULL OR al.target_id = $3)
+        ORDER BY al.created_at DESC
+        LIMIT $4 OFFSET $5
+        "#,
+    )
+    .bind(action)
+    .bind(q.actor_user_id)
+    .bind(q.target_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: rows }))
+}
+
+/* ============================================================
+   Report views
+   ============================================================ */
+
+/// Rebuilds the report materialized views now (instead of waiting for the job).
+pub async fn refresh_reports(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<ApiList<RefreshedView>>, ApiError> {
+    ensure_admin(&auth)?;
+    let data = report_refresh::refresh_all(&state.db).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+/* ============================================================
+   Backup
+   ============================================================ */
+
+/// Streams `pg_dump --format=custom` of the whole database as a download.
+/// Failures before the first bytes are a normal 500; if pg_dump fails later the
+/// transfer is aborted, so a truncated dump never looks like a finished one.
+pub async fn download_backup(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Response, ApiError> {
+    ensure_admin(&auth)?;
+
+    let mut child = backup::dump_command(&state.database_url)
+        .spawn()
+        .map_err(|e| ApiError::Internal(format!("cannot start {}: {e}", backup::PG_DUMP_BIN)))?;
+    let (Some(stdout), Some(mut stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        return Err(ApiError::Internal("pg_dump output not piped".into()));
+    };
+    let stderr = tokio::spawn(async move {
+        let mut buf = String::new();
+        let _ = stderr.read_to_string(&mut buf).await;
+        buf
+    });
+
+    // after stdout ends: nothing if pg_dump succeeded, else an error that aborts the body
+    let exit = stream::once(async move {
+        let status = child.wait().await;
+        let stderr = stderr.await.unwrap_or_default();
+        match status {
+            Ok(s) if s.success() => None,
+            Ok(s) => Some(Err(io::Error::other(format!("pg_dump {s}: {}", stderr.trim())))),
+            Err(e) => Some(Err(e)),
+        }
+    })
+    .filter_map(future::ready);
+    let mut dump = Box::pin(ReaderStream::new(stdout).chain(exit));
+
+    let first = match dump.next().await {
+        Some(Ok(chunk)) => chunk,
+        Some(Err(e)) => return Err(ApiError::Internal(format!("backup failed: {e}"))),
+        None => return Err(ApiError::Internal("backup failed: pg_dump produced no output".into())),
+    };
+
+    let filename = backup::dump_filename(chrono::Utc::now());
+    audit::record(
+        &state.db,
+        &auth,
+        "database.backup",
+        "database",
+        None,
+        serde_json::json!({ "filename": filename }),
+    )
+    .await?;
+
+    let body = Body::from_stream(stream::once(future::ready(Ok::<_, io::Error>(first))).chain(dump.inspect(|chunk| {
+        if let Err(e) = chunk {
+            tracing::error!("backup download aborted: {e}");
+        }
+    })));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/* ============================================================
+   Data exports
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct ExportRunsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Export runs, newest first.
+pub async fn list_export_runs(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ExportRunsQuery>,
+) -> Result<Json<ApiList<ExportRun>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let limit = q.limit.unwrap_or(50).clamp(1, 500);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    let rows = sqlx::query_as::<_, ExportRun>(
+        r#"
+        SELECT run_id, export_date, trigger, status, target, files, error,
+               requested_by_user_id, started_at, finished_at
+        FROM data_export_run
+        ORDER BY started_at DESC
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: rows }))
+}
+
+pub async fn get_export_run(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<ApiOk<ExportRun>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let row = sqlx::query_as::<_, ExportRun>(
+        r#"
+        SELECT run_id, export_date, trigger, status, target, files, error,
+               requested_by_user_id, started_at, finished_at
+        FROM data_export_run
+        WHERE run_id = $1
+        "#,
+    )
+    .bind(run_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "export run not found".into()))?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+/// Starts an export of today's data now and returns the running run; poll
+/// GET /admin/exports/{run_id} for the result. Files of the same day are replaced.
+pub async fn run_export(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<ApiOk<ExportRun>>, ApiError> {
+    ensure_admin(&auth)?;
+    let Some(settings) = state.data_export.clone() else {
+        return Err(ApiError::Conflict("EXPORT_NOT_CONFIGURED", "EXPORT_TARGET is not set".into()));
+    };
+
+    let running: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM data_export_run WHERE status = 'running')")
+        .fetch_one(&state.db)
+        .await?;
+    if running {
+        return Err(ApiError::Conflict("EXPORT_RUNNING", "an export is already running".into()));
+    }
+
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let export_date = clinic_time::local_today(tz);
+    let run = data_export::start_run(&state.db, &settings, Trigger::Manual, export_date, Some(auth.user_id))
+        .await?
+        .ok_or_else(|| ApiError::Internal("manual export run not recorded".into()))?;
+
+    audit::record(
+        &state.db,
+        &auth,
+        "data_export.run",
+        "data_export_run",
+        Some(run.run_id),
+        serde_json::json!({ "export_date": export_date, "target": run.target }),
+    )
+    .await?;
+
+    let run_id = run.run_id;
+    tokio::spawn(async move {
+        if let Err(e) = data_export::execute(&state, &settings, run_id, export_date).await {
+            tracing::error!("data export {run_id}: cannot record the result: {e}");
+        }
+    });
+
+    Ok(Json(ApiOk { data: run }))
+}
+
+/* ============================================================
+   Permissions
+   ============================================================ */
+
+/// Which roles may call each route (paths relative to /api/vN). Not admin-only:
+/// every client needs it, and the same table answers 403 anyway.
+pub async fn list_permissions(_auth: AuthContext) -> Json<ApiList<RouteRule>> {
+    Json(ApiOk { data: permissions::ROUTES.to_vec() })
+}
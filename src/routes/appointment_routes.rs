@@ -1,19 +1,43 @@
 // src/routes/appointment_routes.rs
+//
+// Thin handlers: role gates, request validation and envelopes. Queries and
+// booking rules live in services::appointments.
+
+use std::collections::HashMap;
 
 use axum::{
     extract::{Path, Query, State},
+    http::header,
+    middleware,
+    response::{IntoResponse, Response},
     routing::{get, patch, post, put},
-    Json, Router,
+    Router,
 };
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
 use uuid::Uuid;
 
 use crate::{
+    clinic_time,
+    day_sheet::{DaySheet, DaySheetRow},
     error::ApiError,
-    middleware::auth_context::AuthContext,
-    models::AppState,
+    extract::Json,
+    locations,
+    middleware::{auth_context::AuthContext, etag},
+    models::{ApiList, ApiOk, AppState, AppointmentStatus, Role},
+    onboarding::{self, ChecklistItemDto},
+    pdf,
+    services::{
+        appointments::{
+            self, AppointmentBlockDto, AppointmentBlockSummaryDto, AppointmentChangeDto, AppointmentNoteDto,
+            AppointmentPatch, CreatePlanItem, Milestone, NewAppointment, OverdueCounts, OverdueFilter, OverdueSort,
+            QueueEntryDto,
+        },
+        availability::{self, AvailableSlotDto, ScheduleGridDto, SlotSearch},
+        medical_alerts,
+        patients,
+        procedure_templates::{self, FilledProcedure},
+    },
 };
 
 /*
@@ -26,16 +50,16 @@ Roles (dcms_user.roles):
 */
 
 fn is_admin(auth: &AuthContext) -> bool {
-    auth.role == 1
+    auth.role == Role::Admin
 }
 fn is_manager(auth: &AuthContext) -> bool {
-    auth.role == 2
+    auth.role == Role::Manager
 }
 fn is_doctor(auth: &AuthContext) -> bool {
-    auth.role == 3
+    auth.role == Role::Doctor
 }
 fn is_receptionist(auth: &AuthContext) -> bool {
-    auth.role == 4
+    auth.role == Role::Receptionist
 }
 
 fn can_manage_appointments(auth: &AuthContext) -> bool {
@@ -79,42 +103,39 @@ fn ensure_view_doctor_scope(
     ))
 }
 
-async fn resolve_doctor_employee_id_by_user_id(state: &AppState, user_id: Uuid) -> Result<Uuid, ApiError> {
-    let row = sqlx::query(
-        r#"
-        SELECT employee_id
-        FROM employee
-        WHERE user_id = $1
-        "#,
-    )
-    .bind(user_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    let Some(row) = row else {
-        return Err(ApiError::BadRequest(
-            "NO_EMPLOYEE_PROFILE",
-            "Doctor account has no employee profile".into(),
-        ));
-    };
-
-    let employee_id: Uuid = row
-        .try_get("employee_id")
-        .map_err(|e| ApiError::Internal(format!("row decode error: {e}")))?;
-    Ok(employee_id)
+/// Whose schedule a view shows: the requested doctor, or the caller when they are one.
+async fn schedule_doctor(state: &AppState, auth: &AuthContext, requested: Option<Uuid>) -> Result<Uuid, ApiError> {
+    match ensure_view_doctor_scope(auth, requested)? {
+        Some(id) => Ok(id),
+        None if is_doctor(auth) => appointments::doctor_employee_id_for_user(&*state.repos.appointments, auth.user_id).await,
+        None => Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "doctor_employee_id is required for non-doctor users".into(),
+        )),
+    }
 }
 
 pub fn router() -> Router<AppState> {
     Router::new()
-        // schedule views
-        .route("/appointments/week", get(get_appointments_week))
-        .route("/appointments/day", get(get_appointments_day))
-        .route("/appointments/today", get(get_appointments_today))
+        // schedule views (polled by the WebView; 304 when unchanged)
+        .merge(
+            Router::new()
+                .route("/appointments/week", get(get_appointments_week))
+                .route("/appointments/day", get(get_appointments_day))
+                .route("/appointments/today", get(get_appointments_today))
+                .route("/schedule/grid", get(get_schedule_grid))
+                .route_layer(middleware::from_fn(etag::etag)),
+        )
+        .route("/appointments/day/print", get(print_appointments_day))
         .route("/appointments/overdue", get(get_appointments_overdue))
+        // front-desk waiting room
+        .route("/queue/today", get(get_queue_today))
+        // "next available" booking
+        .route("/availability/search", get(search_availability))
         // CRUD
         .route("/appointments/{appointment_id}", get(get_appointment))
-        .route("/appointments", post(create_appointment))
+        .route("/appointments", get(get_appointments_batch).post(create_appointment))
+        .route("/appointments/household", post(create_household_appointments))
         .route("/appointments/{appointment_id}", patch(patch_appointment))
         // status transitions
         .route("/appointments/{appointment_id}/arrive", post(mark_arrived))
@@ -125,51 +146,50 @@ pub fn router() -> Router<AppState> {
         // confirmation/reminder
         .route("/appointments/{appointment_id}/confirm", post(mark_confirmed))
         .route("/appointments/{appointment_id}/reminder_sent", post(mark_reminder_sent))
+        // field-level change log
+        .route("/appointments/{appointment_id}/history", get(get_appointment_history))
+        // first-visit checklist (is_new_patient appointments)
+        .route("/appointments/{appointment_id}/onboarding", get(get_onboarding_checklist))
+        .route(
+            "/appointments/{appointment_id}/onboarding/{onboarding_item_id}",
+            put(put_onboarding_item),
+        )
+        // note timeline
+        .route(
+            "/appointments/{appointment_id}/notes",
+            get(list_appointment_notes).post(add_appointment_note),
+        )
+        .route(
+            "/appointments/{appointment_id}/notes/{appointment_note_id}/pin",
+            post(pin_appointment_note),
+        )
+        .route("/appointments/{appointment_id}/procedure_notes", post(add_procedure_note))
+        .route(
+            "/appointments/{appointment_id}/procedure_notes/prefill",
+            post(prefill_procedure_note),
+        )
 }
 
 /* ============================================================
    Response DTOs
    ============================================================ */
 
+/// create/PATCH response: the block plus non-blocking warnings (acknowledged
+/// clinical alerts, allowed overlaps)
 #[derive(Debug, Serialize)]
-pub struct ApiOk<T> {
-    pub data: T,
-}
-
-#[derive(Debug, Serialize)]
-pub struct PersonBrief {
-    pub id: Uuid,
-    pub display: String,
-    pub number: Option<i64>,
+pub struct AppointmentWriteResponse {
+    pub data: AppointmentBlockDto,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
+/// overdue page plus whole-range counts for the badge
 #[derive(Debug, Serialize)]
-pub struct AppointmentPlanItemDto {
-    pub service_id: Uuid,
-    pub display_name: String,
-    pub qty: i32,
-}
-
-#[derive(Debug, Serialize)]
-pub struct AppointmentBlockDto {
-    pub appointment_id: Uuid,
-    pub start_at: DateTime<Utc>,
-    pub end_at: DateTime<Utc>,
-    pub status: i16,
-    pub priority: i16,
-    pub color_override: Option<i32>,
-    pub note: Option<String>,
-
-    // Phase-1 add-ons (from migrations 014; if you didn't add confirmed/reminder columns, remove them)
-    pub source: String,
-    pub confirmed_at: Option<DateTime<Utc>>,
-    pub reminder_sent_at: Option<DateTime<Utc>>,
-
-    pub patient: PersonBrief,
-    pub doctor: PersonBrief,
-
-    pub planned_items: Vec<AppointmentPlanItemDto>,
-    pub planned_summary: String,
+pub struct OverdueResponse {
+    pub data: Vec<AppointmentBlockDto>,
+    /// matching the filters, before limit/offset
+    pub total: i64,
+    pub counts: OverdueCounts,
 }
 
 /* ============================================================
@@ -181,101 +201,61 @@ pub struct WeekQuery {
     pub start: String,              // YYYY-MM-DD
     pub days: Option<i64>,          // default 7
     pub doctor_employee_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    pub detail: Option<String>,     // "full" (default) | "summary"
 }
 
 #[derive(Debug, Deserialize)]
 pub struct DayQuery {
     pub date: String,               // YYYY-MM-DD
     pub doctor_employee_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    pub detail: Option<String>,     // "full" (default) | "summary"
 }
 
 #[derive(Debug, Deserialize)]
 pub struct TodayQuery {
     pub doctor_employee_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    pub detail: Option<String>,     // "full" (default) | "summary"
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AvailabilityQuery {
+    pub service_id: Uuid,
+    pub doctor_employee_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    pub from: Option<String>,       // YYYY-MM-DD, default today
+    pub days: Option<u64>,          // default 7
+    pub limit: Option<usize>,       // default 5
 }
 
 #[derive(Debug, Deserialize)]
 pub struct OverdueQuery {
     pub doctor_employee_id: Option<Uuid>,
     pub within_days: Option<i64>,   // default 30
+    pub location_id: Option<Uuid>,
+    pub include_no_show: Option<bool>, // default false
+    pub sort: Option<OverdueSort>,  // start_at (default) | start_at_desc | priority
+    pub limit: Option<i64>,         // default 100, max 500
+    pub offset: Option<i64>,        // default 0
 }
 
-/* ============================================================
-   Shared fetch helper
-   ============================================================ */
-
-async fn fetch_blocks_in_range(
-    state: &AppState,
-    doctor_employee_id: Uuid,
-    start_ts: DateTime<Utc>,
-    end_ts: DateTime<Utc>,
-    extra_where_sql: Option<&'static str>,
-    extra_bind: Option<i64>,
-) -> Result<Vec<AppointmentBlockDto>, ApiError> {
-    // NOTE: we bind doctor_id, start_ts, end_ts, then optionally extra_bind if extra_where_sql uses $4.
-    let mut sql = String::from(
-        r#"
-        SELECT
-          a.appointment_id,
-          a.start_at,
-          a.end_at,
-          a.status,
-          a.priority,
-          a.color_override,
-          a.note,
-          a.source,
-          a.confirmed_at,
-          a.reminder_sent_at,
-
-          p.patient_id,
-          p.first_name AS p_first,
-          p.last_name  AS p_last,
-          p.register_number AS p_reg,
-
-          d.employee_id AS d_id,
-          d.employee_display_number AS d_no,
-          d.first_name AS d_first,
-          d.last_name  AS d_last,
-
-          api.service_id AS svc_id,
-          api.qty AS svc_qty,
-          sc.display_name AS svc_name,
-          sc.display_number AS svc_no
-
-        FROM appointment a
-        JOIN patient p ON p.patient_id = a.patient_id
-        JOIN employee d ON d.employee_id = a.doctor_employee_id
-        LEFT JOIN appointment_plan_item api ON api.appointment_id = a.appointment_id
-        LEFT JOIN service_catalog sc ON sc.service_id = api.service_id
-
-        WHERE a.doctor_employee_id = $1
-          AND a.start_at >= $2
-          AND a.start_at <  $3
-        "#,
-    );
-
-    if let Some(extra) = extra_where_sql {
-        sql.push_str("\n  AND ");
-        sql.push_str(extra);
+/// `?detail=` of the schedule views; true = summary blocks
+fn wants_summary(detail: Option<&str>) -> Result<bool, ApiError> {
+    match detail.map(str::trim) {
+        None | Some("full") => Ok(false),
+        Some("summary") => Ok(true),
+        Some(_) => Err(ApiError::BadRequest("VALIDATION_ERROR", "detail must be full or summary".into())),
     }
+}
 
-    sql.push_str("\n ORDER BY a.start_at ASC, sc.display_number ASC\n");
-
-    let mut q = sqlx::query(&sql)
-        .bind(doctor_employee_id)
-        .bind(start_ts)
-        .bind(end_ts);
-
-    if let Some(v) = extra_bind {
-        q = q.bind(v);
+fn schedule_response(blocks: Vec<AppointmentBlockDto>, summary: bool) -> Response {
+    if summary {
+        let data: Vec<AppointmentBlockSummaryDto> = blocks.into_iter().map(Into::into).collect();
+        return Json(ApiOk { data }).into_response();
     }
-
-    let rows = q
-        .fetch_all(&state.db)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    fold_rows_into_blocks(rows)
+    Json(ApiOk { data: blocks }).into_response()
 }
 
 /* ============================================================
@@ -286,7 +266,8 @@ pub async fn get_appointments_week(
     State(state): State<AppState>,
     auth: AuthContext,
     Query(q): Query<WeekQuery>,
-) -> Result<Json<ApiOk<Vec<AppointmentBlockDto>>>, ApiError> {
+) -> Result<Response, ApiError> {
+    let summary = wants_summary(q.detail.as_deref())?;
     let days = q.days.unwrap_or(7);
     if !(1..=14).contains(&days) {
         return Err(ApiError::BadRequest("VALIDATION_ERROR", "days must be between 1 and 14".into()));
@@ -295,27 +276,21 @@ pub async fn get_appointments_week(
     let start_date = NaiveDate::parse_from_str(q.start.trim(), "%Y-%m-%d")
         .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "start must be YYYY-MM-DD".into()))?;
 
-    let requested = ensure_view_doctor_scope(&auth, q.doctor_employee_id)?;
-    let doctor_employee_id = match requested {
-        Some(id) => id,
-        None => {
-            if is_doctor(&auth) {
-                resolve_doctor_employee_id_by_user_id(&state, auth.user_id).await?
-            } else {
-                return Err(ApiError::BadRequest(
-                    "VALIDATION_ERROR",
-                    "doctor_employee_id is required for non-doctor users".into(),
-                ));
-            }
-        }
-    };
+    let doctor_employee_id = schedule_doctor(&state, &auth, q.doctor_employee_id).await?;
 
-    let start_ts =
-        DateTime::<Utc>::from_naive_utc_and_offset(start_date.and_hms_opt(0, 0, 0).unwrap(), Utc);
-    let end_ts = start_ts + chrono::Duration::days(days);
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let (start_ts, end_ts) = clinic_time::local_days_range(start_date, days as u64, tz);
 
-    let blocks = fetch_blocks_in_range(&state, doctor_employee_id, start_ts, end_ts, None, None).await?;
-    Ok(Json(ApiOk { data: blocks }))
+    let locations = locations::location_scope(&state.db, &auth, q.location_id).await?;
+    let blocks = appointments::blocks_in_range(
+        &*state.repos.appointments,
+        doctor_employee_id,
+        start_ts,
+        end_ts,
+        locations.as_deref(),
+    )
+    .await?;
+    Ok(schedule_response(blocks, summary))
 }
 
 /* ============================================================
@@ -326,31 +301,166 @@ pub async fn get_appointments_day(
     State(state): State<AppState>,
     auth: AuthContext,
     Query(q): Query<DayQuery>,
-) -> Result<Json<ApiOk<Vec<AppointmentBlockDto>>>, ApiError> {
+) -> Result<Response, ApiError> {
+    let summary = wants_summary(q.detail.as_deref())?;
     let date = NaiveDate::parse_from_str(q.date.trim(), "%Y-%m-%d")
         .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "date must be YYYY-MM-DD".into()))?;
 
-    let requested = ensure_view_doctor_scope(&auth, q.doctor_employee_id)?;
-    let doctor_employee_id = match requested {
-        Some(id) => id,
-        None => {
-            if is_doctor(&auth) {
-                resolve_doctor_employee_id_by_user_id(&state, auth.user_id).await?
-            } else {
-                return Err(ApiError::BadRequest(
-                    "VALIDATION_ERROR",
-                    "doctor_employee_id is required for non-doctor users".into(),
-                ));
-            }
-        }
+    let doctor_employee_id = schedule_doctor(&state, &auth, q.doctor_employee_id).await?;
+
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let (start_ts, end_ts) = clinic_time::local_days_range(date, 1, tz);
+
+    let locations = locations::location_scope(&state.db, &auth, q.location_id).await?;
+    let blocks = appointments::blocks_in_range(
+        &*state.repos.appointments,
+        doctor_employee_id,
+        start_ts,
+        end_ts,
+        locations.as_deref(),
+    )
+    .await?;
+    Ok(schedule_response(blocks, summary))
+}
+
+/* ============================================================
+   GET /schedule/grid
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct GridQuery {
+    pub date: String,               // YYYY-MM-DD
+    pub doctor_employee_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+}
+
+/// The day view's slot rows (services::availability::day_grid), so every client
+/// draws the same grid.
+pub async fn get_schedule_grid(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<GridQuery>,
+) -> Result<Json<ApiOk<ScheduleGridDto>>, ApiError> {
+    let date = NaiveDate::parse_from_str(q.date.trim(), "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "date must be YYYY-MM-DD".into()))?;
+    let doctor_employee_id = schedule_doctor(&state, &auth, q.doctor_employee_id).await?;
+    let locations = locations::location_scope(&state.db, &auth, q.location_id).await?;
+    let grid =
+        availability::day_grid(&*state.repos.appointments, doctor_employee_id, date, locations.as_deref()).await?;
+    Ok(Json(ApiOk { data: grid }))
+}
+
+/* ============================================================
+   GET /appointments/day/print
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct DayPrintQuery {
+    pub date: String,               // YYYY-MM-DD
+    pub doctor_employee_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    pub format: Option<String>,     // "html" (default) | "pdf"
+}
+
+/// Day sheet for the front desk's paper backup (crate::day_sheet); canceled
+/// appointments are left off.
+pub async fn print_appointments_day(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<DayPrintQuery>,
+) -> Result<Response, ApiError> {
+    let as_pdf = match q.format.as_deref().map(str::trim) {
+        None | Some("") | Some("html") => false,
+        Some("pdf") => true,
+        Some(_) => return Err(ApiError::BadRequest("VALIDATION_ERROR", "format must be html or pdf".into())),
     };
+    let date = NaiveDate::parse_from_str(q.date.trim(), "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "date must be YYYY-MM-DD".into()))?;
+
+    let doctor_employee_id = schedule_doctor(&state, &auth, q.doctor_employee_id).await?;
+    let doctor: String =
+        sqlx::query_scalar("SELECT first_name || ' ' || last_name FROM employee WHERE employee_id = $1")
+            .bind(doctor_employee_id)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "doctor not found".into()))?;
+
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let (start_ts, end_ts) = clinic_time::local_days_range(date, 1, tz);
+    let locations = locations::location_scope(&state.db, &auth, q.location_id).await?;
+    let mut blocks = appointments::blocks_in_range(
+        &*state.repos.appointments,
+        doctor_employee_id,
+        start_ts,
+        end_ts,
+        locations.as_deref(),
+    )
+    .await?;
+    blocks.retain(|b| b.status != AppointmentStatus::Canceled);
+
+    let mut patient_ids: Vec<Uuid> = blocks.iter().map(|b| b.patient.id).collect();
+    patient_ids.sort();
+    patient_ids.dedup();
+    let mut patient_rows = HashMap::new();
+    let mut alerts: HashMap<Uuid, Vec<String>> = HashMap::new();
+    if !patient_ids.is_empty() {
+        let repo = &*state.repos.patients;
+        for p in patients::page_after(repo, None, Some(&patient_ids), patient_ids.len() as i64).await? {
+            patient_rows.insert(p.patient_id, p);
+        }
+        let mut conn = state.db.acquire().await?;
+        for a in medical_alerts::active_for(&mut conn, &patient_ids).await? {
+            alerts.entry(a.patient_id).or_default().push(format!("{}: {}", a.kind, a.label.0));
+        }
+    }
 
-    let start_ts =
-        DateTime::<Utc>::from_naive_utc_and_offset(date.and_hms_opt(0, 0, 0).unwrap(), Utc);
-    let end_ts = start_ts + chrono::Duration::days(1);
+    let clinic_name: String =
+        sqlx::query_scalar("SELECT clinic_name FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(&state.db)
+            .await?
+            .unwrap_or_default();
+
+    let rows = blocks
+        .into_iter()
+        .map(|b| {
+            let p = patient_rows.get(&b.patient.id);
+            DaySheetRow {
+                time: format!(
+                    "{}-{}",
+                    b.start_at.with_timezone(&tz).format("%H:%M"),
+                    b.end_at.with_timezone(&tz).format("%H:%M")
+                ),
+                patient: b.patient.display,
+                register_number: p.map(|p| p.register_number.clone()),
+                phone: p.and_then(|p| p.primary_phone.clone()),
+                planned: b.planned_summary,
+                status: b.status.as_str(),
+                note: b.note.filter(|n| !n.trim().is_empty()),
+                alerts: alerts.get(&b.patient.id).cloned().unwrap_or_default(),
+            }
+        })
+        .collect();
+    let sheet = DaySheet {
+        clinic_name,
+        date,
+        doctor,
+        printed_at: Utc::now().with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string(),
+        rows,
+    };
 
-    let blocks = fetch_blocks_in_range(&state, doctor_employee_id, start_ts, end_ts, None, None).await?;
-    Ok(Json(ApiOk { data: blocks }))
+    let (content_type, ext, body) = if as_pdf {
+        ("application/pdf", "pdf", pdf::text_document(&sheet.title(), &sheet.to_text()))
+    } else {
+        ("text/html; charset=utf-8", "html", sheet.to_html().into_bytes())
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("inline; filename=\"day-sheet-{date}.{ext}\"")),
+        ],
+        body,
+    )
+        .into_response())
 }
 
 /* ============================================================
@@ -361,29 +471,23 @@ pub async fn get_appointments_today(
     State(state): State<AppState>,
     auth: AuthContext,
     Query(q): Query<TodayQuery>,
-) -> Result<Json<ApiOk<Vec<AppointmentBlockDto>>>, ApiError> {
-    let requested = ensure_view_doctor_scope(&auth, q.doctor_employee_id)?;
-    let doctor_employee_id = match requested {
-        Some(id) => id,
-        None => {
-            if is_doctor(&auth) {
-                resolve_doctor_employee_id_by_user_id(&state, auth.user_id).await?
-            } else {
-                return Err(ApiError::BadRequest(
-                    "VALIDATION_ERROR",
-                    "doctor_employee_id is required for non-doctor users".into(),
-                ));
-            }
-        }
-    };
+) -> Result<Response, ApiError> {
+    let summary = wants_summary(q.detail.as_deref())?;
+    let doctor_employee_id = schedule_doctor(&state, &auth, q.doctor_employee_id).await?;
 
-    let today = chrono::Utc::now().date_naive();
-    let start_ts =
-        DateTime::<Utc>::from_naive_utc_and_offset(today.and_hms_opt(0, 0, 0).unwrap(), Utc);
-    let end_ts = start_ts + chrono::Duration::days(1);
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let (start_ts, end_ts) = clinic_time::local_days_range(clinic_time::local_today(tz), 1, tz);
 
-    let blocks = fetch_blocks_in_range(&state, doctor_employee_id, start_ts, end_ts, None, None).await?;
-    Ok(Json(ApiOk { data: blocks }))
+    let locations = locations::location_scope(&state.db, &auth, q.location_id).await?;
+    let blocks = appointments::blocks_in_range(
+        &*state.repos.appointments,
+        doctor_employee_id,
+        start_ts,
+        end_ts,
+        locations.as_deref(),
+    )
+    .await?;
+    Ok(schedule_response(blocks, summary))
 }
 
 /* ============================================================
@@ -394,7 +498,7 @@ pub async fn get_appointments_overdue(
     State(state): State<AppState>,
     auth: AuthContext,
     Query(q): Query<OverdueQuery>,
-) -> Result<Json<ApiOk<Vec<AppointmentBlockDto>>>, ApiError> {
+) -> Result<Json<OverdueResponse>, ApiError> {
     let within_days = q.within_days.unwrap_or(30);
     if !(1..=365).contains(&within_days) {
         return Err(ApiError::BadRequest(
@@ -403,38 +507,106 @@ pub async fn get_appointments_overdue(
         ));
     }
 
+    let doctor_employee_id = schedule_doctor(&state, &auth, q.doctor_employee_id).await?;
+    let locations = locations::location_scope(&state.db, &auth, q.location_id).await?;
+
+    // still scheduled (or no-show, if asked) and start_at < now
+    let now = Utc::now();
+    let include_no_show = q.include_no_show.unwrap_or(false);
+    let filter = OverdueFilter {
+        doctor_employee_id,
+        since: now - chrono::Duration::days(within_days),
+        now,
+        locations,
+        include_no_show,
+        sort: q.sort.unwrap_or_default(),
+        limit: q.limit.unwrap_or(100).clamp(1, 500),
+        offset: q.offset.unwrap_or(0).max(0),
+    };
+    let (blocks, counts) = appointments::overdue(&*state.repos.appointments, &filter).await?;
+
+    Ok(Json(OverdueResponse {
+        data: blocks,
+        total: counts.total(include_no_show),
+        counts,
+    }))
+}
+
+/* ============================================================
+   GET /queue/today
+   ============================================================ */
+
+pub async fn get_queue_today(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<TodayQuery>,
+) -> Result<Json<ApiList<QueueEntryDto>>, ApiError> {
+    // admin/manager/receptionist: whole clinic (optionally one doctor); doctor: own patients only
     let requested = ensure_view_doctor_scope(&auth, q.doctor_employee_id)?;
-    let doctor_employee_id = match requested {
-        Some(id) => id,
-        None => {
-            if is_doctor(&auth) {
-                resolve_doctor_employee_id_by_user_id(&state, auth.user_id).await?
-            } else {
-                return Err(ApiError::BadRequest(
-                    "VALIDATION_ERROR",
-                    "doctor_employee_id is required for non-doctor users".into(),
-                ));
-            }
-        }
+    let doctor_filter = match requested {
+        Some(id) => Some(id),
+        None if is_doctor(&auth) => Some(appointments::doctor_employee_id_for_user(&*state.repos.appointments, auth.user_id).await?),
+        None => None,
     };
+    let locations = locations::location_scope(&state.db, &auth, q.location_id).await?;
 
-    let now = chrono::Utc::now();
-    let start_ts = now - chrono::Duration::days(within_days);
-    let end_ts = now;
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let (start_ts, end_ts) = clinic_time::local_days_range(clinic_time::local_today(tz), 1, tz);
 
-    // overdue definition (Phase 1):
-    // status = 0 (scheduled) and start_at < now
-    let blocks = fetch_blocks_in_range(
-        &state,
-        doctor_employee_id,
-        start_ts,
-        end_ts,
-        Some("a.status = 0"),
-        None,
-    )
-    .await?;
+    let out = appointments::queue(&*state.repos.appointments, start_ts, end_ts, doctor_filter, locations.as_deref()).await?;
+    Ok(Json(ApiOk { data: out }))
+}
 
-    Ok(Json(ApiOk { data: blocks }))
+/* ============================================================
+   GET /availability/search
+   ============================================================ */
+
+/// The next open slots for a service, across every doctor who performs it
+/// (or one); doctors only search their own calendar.
+pub async fn search_availability(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<AvailabilityQuery>,
+) -> Result<Json<ApiList<AvailableSlotDto>>, ApiError> {
+    let days = q.days.unwrap_or(availability::DEFAULT_DAYS);
+    if !(1..=availability::MAX_DAYS).contains(&days) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("days must be between 1 and {}", availability::MAX_DAYS),
+        ));
+    }
+    let limit = q.limit.unwrap_or(availability::DEFAULT_LIMIT);
+    if !(1..=availability::MAX_LIMIT).contains(&limit) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("limit must be between 1 and {}", availability::MAX_LIMIT),
+        ));
+    }
+
+    let doctor_employee_id = match ensure_view_doctor_scope(&auth, q.doctor_employee_id)? {
+        Some(id) => Some(id),
+        None if is_doctor(&auth) => Some(appointments::doctor_employee_id_for_user(&*state.repos.appointments, auth.user_id).await?),
+        None => None,
+    };
+    let locations = locations::location_scope(&state.db, &auth, q.location_id).await?;
+
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let from = match q.from.as_deref() {
+        Some(s) => NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+            .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "from must be YYYY-MM-DD".into()))?,
+        None => clinic_time::local_today(tz),
+    };
+
+    let search = SlotSearch {
+        service_id: q.service_id,
+        doctor_employee_id,
+        locations,
+        from,
+        days,
+        limit,
+    };
+    let slots = availability::search(&*state.repos.appointments, &search, Utc::now()).await?;
+    Ok(Json(ApiOk { data: slots }))
 }
 
 /* ============================================================
@@ -446,59 +618,10 @@ pub async fn get_appointment(
     auth: AuthContext,
     Path(appointment_id): Path<Uuid>,
 ) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
-    let rows = sqlx::query(
-        r#"
-        SELECT
-          a.appointment_id,
-          a.start_at,
-          a.end_at,
-          a.status,
-          a.priority,
-          a.color_override,
-          a.note,
-          a.source,
-          a.confirmed_at,
-          a.reminder_sent_at,
-
-          p.patient_id,
-          p.first_name AS p_first,
-          p.last_name  AS p_last,
-          p.register_number AS p_reg,
-
-          d.employee_id AS d_id,
-          d.employee_display_number AS d_no,
-          d.first_name AS d_first,
-          d.last_name  AS d_last,
-
-          api.service_id AS svc_id,
-          api.qty AS svc_qty,
-          sc.display_name AS svc_name,
-          sc.display_number AS svc_no
-
-        FROM appointment a
-        JOIN patient p ON p.patient_id = a.patient_id
-        JOIN employee d ON d.employee_id = a.doctor_employee_id
-        LEFT JOIN appointment_plan_item api ON api.appointment_id = a.appointment_id
-        LEFT JOIN service_catalog sc ON sc.service_id = api.service_id
-
-        WHERE a.appointment_id = $1
-        ORDER BY sc.display_number ASC
-        "#,
-    )
-    .bind(appointment_id)
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    if rows.is_empty() {
-        return Err(ApiError::BadRequest("NOT_FOUND", "appointment not found".into()));
-    }
-
-    let mut blocks = fold_rows_into_blocks(rows)?;
-    let block = blocks.remove(0);
+    let block = appointments::get_block(&*state.repos.appointments, appointment_id).await?;
 
     if is_doctor(&auth) {
-        let my_emp = resolve_doctor_employee_id_by_user_id(&state, auth.user_id).await?;
+        let my_emp = appointments::doctor_employee_id_for_user(&*state.repos.appointments, auth.user_id).await?;
         if block.doctor.id != my_emp {
             return Err(ApiError::Forbidden(
                 "FORBIDDEN",
@@ -510,6 +633,34 @@ pub async fn get_appointment(
     Ok(Json(ApiOk { data: block }))
 }
 
+/* ============================================================
+   GET /appointments?ids=a,b,c
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct BatchQuery {
+    pub ids: String, // comma-separated, up to services::appointments::MAX_BATCH_IDS
+}
+
+/// Refetch of several blocks at once (e.g. after change events). Ids that don't
+/// exist, or for a doctor belong to another doctor, are left out rather than
+/// failing the batch.
+pub async fn get_appointments_batch(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<BatchQuery>,
+) -> Result<Json<ApiList<AppointmentBlockDto>>, ApiError> {
+    let ids = appointments::parse_id_list(&q.ids)?;
+    let mut blocks = appointments::blocks_by_ids(&*state.repos.appointments, &ids).await?;
+
+    if is_doctor(&auth) {
+        let my_emp = appointments::doctor_employee_id_for_user(&*state.repos.appointments, auth.user_id).await?;
+        blocks.retain(|b| b.doctor.id == my_emp);
+    }
+
+    Ok(Json(ApiOk { data: blocks }))
+}
+
 /* ============================================================
    POST /appointments (create)
    ============================================================ */
@@ -519,7 +670,8 @@ pub struct CreateAppointmentRequest {
     pub patient_id: Uuid,
     pub doctor_employee_id: Uuid,
     pub start_at: DateTime<Utc>,
-    pub end_at: DateTime<Utc>,
+    /// omitted: computed from planned_items (service durations, rounded to the slot size)
+    pub end_at: Option<DateTime<Utc>>,
     pub assistant_employee_id: Option<Uuid>,
     pub receptionist_employee_id: Option<Uuid>,
     pub note: Option<String>,
@@ -529,247 +681,270 @@ pub struct CreateAppointmentRequest {
 
     // Phase-1 add-on (migration 014)
     pub source: Option<String>, // "SCHEDULED" | "WALKIN" | "WAITLIST"
-}
 
-#[derive(Debug, Deserialize)]
-pub struct CreatePlanItem {
-    pub service_id: Uuid,
-    pub qty: Option<i32>,
-    pub note: Option<String>,
-}
+    /// admin/manager only: book even though the clinic is closed that day
+    /// or the doctor has approved time off, or the assistant is off shift
+    pub override_closure: Option<bool>,
 
-fn normalize_source(s: Option<String>) -> Result<String, ApiError> {
-    let v = s.unwrap_or_else(|| "SCHEDULED".to_string());
-    let up = v.trim().to_uppercase();
-    match up.as_str() {
-        "SCHEDULED" | "WALKIN" | "WAITLIST" => Ok(up),
-        _ => Err(ApiError::BadRequest(
-            "VALIDATION_ERROR",
-            "source must be SCHEDULED, WALKIN, or WAITLIST".into(),
-        )),
-    }
+    /// admin/manager only: book past the doctor's overlap policy (audited)
+    pub override_overlap: Option<bool>,
+
+    /// defaults to the doctor's home location
+    pub location_id: Option<Uuid>,
+
+    /// the receptionist has seen the patient's clinical alerts (409 CLINICAL_ALERTS without it)
+    pub acknowledge_warnings: Option<bool>,
 }
 
 pub async fn create_appointment(
     State(state): State<AppState>,
     auth: AuthContext,
     Json(req): Json<CreateAppointmentRequest>,
-) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
+) -> Result<Json<AppointmentWriteResponse>, ApiError> {
     ensure_manage(&auth)?;
 
-    if req.end_at <= req.start_at {
-        return Err(ApiError::BadRequest("VALIDATION_ERROR", "end_at must be > start_at".into()));
-    }
+    let end_at = appointments::resolve_end_at(
+        &state.db,
+        req.doctor_employee_id,
+        req.start_at,
+        req.end_at,
+        req.planned_items.as_deref(),
+    )
+    .await?;
     let priority = req.priority.unwrap_or(0);
-    if priority != 0 && priority != 1 {
-        return Err(ApiError::BadRequest("VALIDATION_ERROR", "priority must be 0 or 1".into()));
-    }
-
-    let source = normalize_source(req.source)?;
-
-    let mut tx = state
-        .db
-        .begin()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    let row = sqlx::query(
-        r#"
-        INSERT INTO appointment (
-          patient_id,
-          doctor_employee_id,
-          receptionist_employee_id,
-          assistant_employee_id,
-          start_at,
-          end_at,
-          status,
-          is_new_patient,
-          priority,
-          note,
-          source,
-          created_by_user_id,
-          updated_by_user_id
-        )
-        VALUES ($1,$2,$3,$4,$5,$6, 0, $7, $8, $9, $10, $11, $11)
-        RETURNING appointment_id
-        "#,
+    appointments::validate_priority(priority)?;
+    let source = appointments::normalize_source(req.source)?;
+
+    let override_closure = req.override_closure.unwrap_or(false);
+    appointments::ensure_clinic_open(&*state.repos.appointments, &auth, req.start_at, end_at, override_closure).await?;
+    appointments::ensure_doctor_not_off(
+        &*state.repos.appointments,
+        &auth,
+        req.doctor_employee_id,
+        req.start_at,
+        end_at,
+        override_closure,
     )
-    .bind(req.patient_id)
-    .bind(req.doctor_employee_id)
-    .bind(req.receptionist_employee_id)
-    .bind(req.assistant_employee_id)
-    .bind(req.start_at)
-    .bind(req.end_at)
-    .bind(req.is_new_patient.unwrap_or(false))
-    .bind(priority)
-    .bind(req.note)
-    .bind(source)
-    .bind(auth.user_id)
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| ApiError::BadRequest("APPOINTMENT_CREATE_FAILED", format!("{e}")))?;
-
-    let appointment_id: Uuid = row
-        .try_get("appointment_id")
-        .map_err(|e| ApiError::Internal(format!("row decode error: {e}")))?;
-
-    if let Some(items) = req.planned_items {
-        for it in items {
-            let qty = it.qty.unwrap_or(1);
-            if qty <= 0 {
-                return Err(ApiError::BadRequest("VALIDATION_ERROR", "qty must be > 0".into()));
-            }
-            sqlx::query(
-                r#"
-                INSERT INTO appointment_plan_item (appointment_id, service_id, qty, note)
-                VALUES ($1,$2,$3,$4)
-                "#,
-            )
-            .bind(appointment_id)
-            .bind(it.service_id)
-            .bind(qty)
-            .bind(it.note)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| ApiError::BadRequest("PLAN_ITEM_CREATE_FAILED", format!("{e}")))?;
-        }
-    }
-
-    tx.commit()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
+    appointments::ensure_assistant_available(
+        &*state.repos.appointments,
+        &auth,
+        req.assistant_employee_id,
+        req.start_at,
+        end_at,
+        None,
+        override_closure,
+    )
+    .await?;
+    let location_id =
+        appointments::resolve_location(&state.db, &auth, req.doctor_employee_id, req.location_id).await?;
+
+    let new = NewAppointment {
+        patient_id: req.patient_id,
+        doctor_employee_id: req.doctor_employee_id,
+        start_at: req.start_at,
+        end_at,
+        assistant_employee_id: req.assistant_employee_id,
+        receptionist_employee_id: req.receptionist_employee_id,
+        note: req.note,
+        priority,
+        is_new_patient: req.is_new_patient.unwrap_or(false),
+        planned_items: req.planned_items,
+        source,
+        location_id,
+        override_overlap: req.override_overlap.unwrap_or(false),
+    };
 
-    get_appointment(State(state), auth, Path(appointment_id)).await
+    let mut tx = state.db.begin().await?;
+    let alerts = medical_alerts::active_for(&mut tx, &[req.patient_id]).await?;
+    let mut warnings = medical_alerts::booking_warnings(&alerts, req.acknowledge_warnings.unwrap_or(false))?;
+    let (appointment_id, overlap) = appointments::insert(&mut tx, &auth, new).await?;
+    tx.commit().await?;
+    warnings.extend(overlap.warnings);
+
+    Ok(Json(AppointmentWriteResponse {
+        data: appointments::get_block(&*state.repos.appointments, appointment_id).await?,
+        warnings,
+    }))
 }
 
 /* ============================================================
-   PATCH /appointments/{id}
+   POST /appointments/household (back-to-back family booking)
    ============================================================ */
 
+const MAX_HOUSEHOLD_BOOKING: usize = 10;
+
 #[derive(Debug, Deserialize)]
-pub struct PatchAppointmentRequest {
-    pub start_at: Option<DateTime<Utc>>,
-    pub end_at: Option<DateTime<Utc>>,
-    pub status: Option<i16>,
-    pub priority: Option<i16>,
-    pub assistant_employee_id: Option<Option<Uuid>>,
-    pub receptionist_employee_id: Option<Option<Uuid>>,
-    pub note: Option<Option<String>>,
-    pub color_override: Option<Option<i32>>,
+pub struct HouseholdBookingMember {
+    pub patient_id: Uuid,
+    /// slot length; omitted = computed from planned_items
+    pub duration_min: Option<i64>,
+    pub planned_items: Option<Vec<CreatePlanItem>>,
+    pub note: Option<String>,
+    pub is_new_patient: Option<bool>,
+}
 
-    // Phase-1 add-ons
+/// Same options as POST /appointments, shared by every member's appointment.
+#[derive(Debug, Deserialize)]
+pub struct HouseholdBookingRequest {
+    pub household_id: Uuid,
+    pub doctor_employee_id: Uuid,
+    /// first member's start; each next member starts when the previous one ends
+    pub start_at: DateTime<Utc>,
+    pub members: Vec<HouseholdBookingMember>,
+    pub assistant_employee_id: Option<Uuid>,
+    pub receptionist_employee_id: Option<Uuid>,
+    pub priority: Option<i16>,
     pub source: Option<String>,
-    pub confirmed_at: Option<Option<DateTime<Utc>>>,
-    pub reminder_sent_at: Option<Option<DateTime<Utc>>>,
+    pub override_closure: Option<bool>,
+    pub override_overlap: Option<bool>,
+    pub location_id: Option<Uuid>,
+    /// covers every member's clinical alerts
+    pub acknowledge_warnings: Option<bool>,
 }
 
-pub async fn patch_appointment(
+#[derive(Debug, Serialize)]
+pub struct HouseholdBookingResponse {
+    /// in booking order
+    pub data: Vec<AppointmentBlockDto>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// All members or none: one transaction, so a clash on the third slot books nobody.
+pub async fn create_household_appointments(
     State(state): State<AppState>,
     auth: AuthContext,
-    Path(appointment_id): Path<Uuid>,
-    Json(req): Json<PatchAppointmentRequest>,
-) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
+    Json(req): Json<HouseholdBookingRequest>,
+) -> Result<Json<HouseholdBookingResponse>, ApiError> {
     ensure_manage(&auth)?;
 
-    if let Some(s) = req.status {
-        if !(0..=5).contains(&s) {
-            return Err(ApiError::BadRequest("VALIDATION_ERROR", "invalid status".into()));
-        }
+    if req.members.is_empty() || req.members.len() > MAX_HOUSEHOLD_BOOKING {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("members must contain 1..{MAX_HOUSEHOLD_BOOKING} patients"),
+        ));
     }
-    if let Some(p) = req.priority {
-        if p != 0 && p != 1 {
-            return Err(ApiError::BadRequest("VALIDATION_ERROR", "priority must be 0 or 1".into()));
-        }
+    let patient_ids: Vec<Uuid> = req.members.iter().map(|m| m.patient_id).collect();
+    if (1..patient_ids.len()).any(|i| patient_ids[..i].contains(&patient_ids[i])) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "members must not repeat a patient".into()));
     }
-
-    let source = if req.source.is_some() {
-        Some(normalize_source(req.source)?)
-    } else {
-        None
-    };
-
-    let row = sqlx::query(
-        r#"
-        UPDATE appointment
-        SET
-          start_at = COALESCE($2, start_at),
-          end_at   = COALESCE($3, end_at),
-          status   = COALESCE($4, status),
-          priority = COALESCE($5, priority),
-          assistant_employee_id    = COALESCE($6, assistant_employee_id),
-          receptionist_employee_id = COALESCE($7, receptionist_employee_id),
-          note           = COALESCE($8, note),
-          color_override = COALESCE($9, color_override),
-          source         = COALESCE($10, source),
-          confirmed_at       = COALESCE($11, confirmed_at),
-          reminder_sent_at   = COALESCE($12, reminder_sent_at),
-          updated_at = now(),
-          updated_by_user_id = $13
-        WHERE appointment_id = $1
-        RETURNING appointment_id, start_at, end_at
-        "#,
+    let priority = req.priority.unwrap_or(0);
+    appointments::validate_priority(priority)?;
+    let source = appointments::normalize_source(req.source)?;
+
+    appointments::ensure_household_members(&state.db, req.household_id, &patient_ids).await?;
+
+    let durations: Vec<_> = req
+        .members
+        .iter()
+        .map(|m| (m.duration_min, m.planned_items.as_deref()))
+        .collect();
+    let slots = appointments::consecutive_slots(&state.db, req.doctor_employee_id, req.start_at, &durations).await?;
+    let last_end = slots.last().map_or(req.start_at, |(_, end_at)| *end_at);
+
+    let override_closure = req.override_closure.unwrap_or(false);
+    appointments::ensure_clinic_open(&*state.repos.appointments, &auth, req.start_at, last_end, override_closure).await?;
+    appointments::ensure_doctor_not_off(
+        &*state.repos.appointments,
+        &auth,
+        req.doctor_employee_id,
+        req.start_at,
+        last_end,
+        override_closure,
     )
-    .bind(appointment_id)
-    .bind(req.start_at)
-    .bind(req.end_at)
-    .bind(req.status)
-    .bind(req.priority)
-    .bind(req.assistant_employee_id.unwrap_or(None))
-    .bind(req.receptionist_employee_id.unwrap_or(None))
-    .bind(req.note.unwrap_or(None))
-    .bind(req.color_override.unwrap_or(None))
-    .bind(source)
-    .bind(req.confirmed_at.unwrap_or(None))
-    .bind(req.reminder_sent_at.unwrap_or(None))
-    .bind(auth.user_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::BadRequest("APPOINTMENT_UPDATE_FAILED", format!("{e}")))?;
-
-    let Some(row) = row else {
-        return Err(ApiError::BadRequest("NOT_FOUND", "appointment not found".into()));
-    };
+    .await?;
+    appointments::ensure_assistant_available(
+        &*state.repos.appointments,
+        &auth,
+        req.assistant_employee_id,
+        req.start_at,
+        last_end,
+        None,
+        override_closure,
+    )
+    .await?;
+    let location_id =
+        appointments::resolve_location(&state.db, &auth, req.doctor_employee_id, req.location_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let alerts = medical_alerts::active_for(&mut tx, &patient_ids).await?;
+    let mut warnings = medical_alerts::booking_warnings(&alerts, req.acknowledge_warnings.unwrap_or(false))?;
+
+    let mut appointment_ids = Vec::with_capacity(slots.len());
+    for (m, (start_at, end_at)) in req.members.into_iter().zip(slots) {
+        let new = NewAppointment {
+            patient_id: m.patient_id,
+            doctor_employee_id: req.doctor_employee_id,
+            start_at,
+            end_at,
+            assistant_employee_id: req.assistant_employee_id,
+            receptionist_employee_id: req.receptionist_employee_id,
+            note: m.note,
+            priority,
+            is_new_patient: m.is_new_patient.unwrap_or(false),
+            planned_items: m.planned_items,
+            source: source.clone(),
+            location_id,
+            override_overlap: req.override_overlap.unwrap_or(false),
+        };
+        let (appointment_id, overlap) = appointments::insert(&mut tx, &auth, new).await?;
+        appointment_ids.push(appointment_id);
+        warnings.extend(overlap.warnings);
+    }
+
+    tx.commit().await?;
 
-    let start_at: DateTime<Utc> = row
-        .try_get("start_at")
-        .map_err(|e| ApiError::Internal(format!("{e}")))?;
-    let end_at: DateTime<Utc> = row
-        .try_get("end_at")
-        .map_err(|e| ApiError::Internal(format!("{e}")))?;
-    if end_at <= start_at {
-        return Err(ApiError::BadRequest("VALIDATION_ERROR", "end_at must be > start_at".into()));
+    let mut data = Vec::with_capacity(appointment_ids.len());
+    for appointment_id in appointment_ids {
+        data.push(appointments::get_block(&*state.repos.appointments, appointment_id).await?);
     }
 
-    get_appointment(State(state), auth, Path(appointment_id)).await
+    Ok(Json(HouseholdBookingResponse { data, warnings }))
 }
 
 /* ============================================================
-   Status transitions
+   PATCH /appointments/{id}
    ============================================================ */
 
-pub async fn mark_arrived(
+pub async fn patch_appointment(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(appointment_id): Path<Uuid>,
+    Json(req): Json<AppointmentPatch>,
+) -> Result<Json<AppointmentWriteResponse>, ApiError> {
+    ensure_manage(&auth)?;
+
+    let overlap = appointments::update(&state.db, &*state.repos.appointments, &auth, appointment_id, req).await?;
+
+    Ok(Json(AppointmentWriteResponse {
+        data: appointments::get_block(&*state.repos.appointments, appointment_id).await?,
+        warnings: overlap.warnings,
+    }))
+}
+
+/* ============================================================
+   Status transitions
+   POST /appointments/{id}/arrive|seat|dismiss|confirm|reminder_sent
+   ============================================================ */
+
+async fn mark(
+    state: AppState,
+    auth: AuthContext,
+    appointment_id: Uuid,
+    milestone: Milestone,
 ) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
     ensure_manage(&auth)?;
-    sqlx::query(
-        r#"
-        UPDATE appointment
-        SET arrived_at = COALESCE(arrived_at, now()),
-            status = 2,
-            updated_at = now(),
-            updated_by_user_id = $2
-        WHERE appointment_id = $1
-        "#,
-    )
-    .bind(appointment_id)
-    .bind(auth.user_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::BadRequest("APPOINTMENT_UPDATE_FAILED", format!("{e}")))?;
+    appointments::mark(&*state.repos.appointments, &auth, appointment_id, milestone).await?;
+    Ok(Json(ApiOk { data: appointments::get_block(&*state.repos.appointments, appointment_id).await? }))
+}
 
-    get_appointment(State(state), auth, Path(appointment_id)).await
+pub async fn mark_arrived(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(appointment_id): Path<Uuid>,
+) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
+    mark(state, auth, appointment_id, Milestone::Arrived).await
 }
 
 pub async fn mark_seated(
@@ -777,24 +952,7 @@ pub async fn mark_seated(
     auth: AuthContext,
     Path(appointment_id): Path<Uuid>,
 ) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
-    ensure_manage(&auth)?;
-    sqlx::query(
-        r#"
-        UPDATE appointment
-        SET seated_at = COALESCE(seated_at, now()),
-            status = 3,
-            updated_at = now(),
-            updated_by_user_id = $2
-        WHERE appointment_id = $1
-        "#,
-    )
-    .bind(appointment_id)
-    .bind(auth.user_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::BadRequest("APPOINTMENT_UPDATE_FAILED", format!("{e}")))?;
-
-    get_appointment(State(state), auth, Path(appointment_id)).await
+    mark(state, auth, appointment_id, Milestone::Seated).await
 }
 
 pub async fn mark_dismissed(
@@ -802,54 +960,15 @@ pub async fn mark_dismissed(
     auth: AuthContext,
     Path(appointment_id): Path<Uuid>,
 ) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
-    ensure_manage(&auth)?;
-    sqlx::query(
-        r#"
-        UPDATE appointment
-        SET dismissed_at = COALESCE(dismissed_at, now()),
-            status = 4,
-            updated_at = now(),
-            updated_by_user_id = $2
-        WHERE appointment_id = $1
-        "#,
-    )
-    .bind(appointment_id)
-    .bind(auth.user_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::BadRequest("APPOINTMENT_UPDATE_FAILED", format!("{e}")))?;
-
-    get_appointment(State(state), auth, Path(appointment_id)).await
+    mark(state, auth, appointment_id, Milestone::Dismissed).await
 }
 
-/* ============================================================
-   POST /appointments/{id}/confirm
-   POST /appointments/{id}/reminder_sent
-   ============================================================ */
-
 pub async fn mark_confirmed(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(appointment_id): Path<Uuid>,
 ) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
-    ensure_manage(&auth)?;
-
-    sqlx::query(
-        r#"
-        UPDATE appointment
-        SET confirmed_at = COALESCE(confirmed_at, now()),
-            updated_at = now(),
-            updated_by_user_id = $2
-        WHERE appointment_id = $1
-        "#,
-    )
-    .bind(appointment_id)
-    .bind(auth.user_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::BadRequest("APPOINTMENT_UPDATE_FAILED", format!("{e}")))?;
-
-    get_appointment(State(state), auth, Path(appointment_id)).await
+    mark(state, auth, appointment_id, Milestone::Confirmed).await
 }
 
 pub async fn mark_reminder_sent(
@@ -857,24 +976,7 @@ pub async fn mark_reminder_sent(
     auth: AuthContext,
     Path(appointment_id): Path<Uuid>,
 ) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
-    ensure_manage(&auth)?;
-
-    sqlx::query(
-        r#"
-        UPDATE appointment
-        SET reminder_sent_at = COALESCE(reminder_sent_at, now()),
-            updated_at = now(),
-            updated_by_user_id = $2
-        WHERE appointment_id = $1
-        "#,
-    )
-    .bind(appointment_id)
-    .bind(auth.user_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::BadRequest("APPOINTMENT_UPDATE_FAILED", format!("{e}")))?;
-
-    get_appointment(State(state), auth, Path(appointment_id)).await
+    mark(state, auth, appointment_id, Milestone::ReminderSent).await
 }
 
 /* ============================================================
@@ -893,149 +995,225 @@ pub async fn put_plan_items(
     Json(req): Json<PutPlanItemsRequest>,
 ) -> Result<Json<ApiOk<AppointmentBlockDto>>, ApiError> {
     ensure_manage(&auth)?;
+    appointments::replace_plan_items(&state.db, &auth, appointment_id, req.items).await?;
+    Ok(Json(ApiOk { data: appointments::get_block(&*state.repos.appointments, appointment_id).await? }))
+}
 
-    let mut tx = state
-        .db
-        .begin()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    sqlx::query(r#"DELETE FROM appointment_plan_item WHERE appointment_id = $1"#)
-        .bind(appointment_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    for it in req.items {
-        let qty = it.qty.unwrap_or(1);
-        if qty <= 0 {
-            return Err(ApiError::BadRequest("VALIDATION_ERROR", "qty must be > 0".into()));
+/* ============================================================
+   GET /appointments/{id}/history
+   ============================================================ */
+
+// oldest first; one entry per changed field
+pub async fn get_appointment_history(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(appointment_id): Path<Uuid>,
+) -> Result<Json<ApiList<AppointmentChangeDto>>, ApiError> {
+    ensure_can_access_appointment(&state, &auth, appointment_id).await?;
+    let changes = appointments::history(&*state.repos.appointments, appointment_id).await?;
+    Ok(Json(ApiOk { data: changes }))
+}
+
+/* ============================================================
+   Appointment notes (append-only timeline)
+   ============================================================ */
+
+/// Notes, history and the first-visit checklist: staff who can manage appointments
+/// see every appointment's, doctors only their own.
+async fn ensure_can_access_appointment(
+    state: &AppState,
+    auth: &AuthContext,
+    appointment_id: Uuid,
+) -> Result<(), ApiError> {
+    let doctor_employee_id = appointments::doctor_of(&*state.repos.appointments, appointment_id).await?;
+
+    if can_manage_appointments(auth) {
+        return Ok(());
+    }
+    if is_doctor(auth) {
+        let my_emp = appointments::doctor_employee_id_for_user(&*state.repos.appointments, auth.user_id).await?;
+        if doctor_employee_id == my_emp {
+            return Ok(());
         }
-        sqlx::query(
-            r#"
-            INSERT INTO appointment_plan_item (appointment_id, service_id, qty, note)
-            VALUES ($1,$2,$3,$4)
-            "#,
-        )
-        .bind(appointment_id)
-        .bind(it.service_id)
-        .bind(qty)
-        .bind(it.note)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| ApiError::BadRequest("PLAN_ITEM_CREATE_FAILED", format!("{e}")))?;
     }
+    Err(ApiError::Forbidden(
+        "FORBIDDEN",
+        "You do not have access to this appointment".into(),
+    ))
+}
 
-    sqlx::query(
-        r#"
-        UPDATE appointment
-        SET updated_at = now(), updated_by_user_id = $2
-        WHERE appointment_id = $1
-        "#,
-    )
-    .bind(appointment_id)
-    .bind(auth.user_id)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+// GET /appointments/{id}/notes : oldest first
+pub async fn list_appointment_notes(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(appointment_id): Path<Uuid>,
+) -> Result<Json<ApiList<AppointmentNoteDto>>, ApiError> {
+    ensure_can_access_appointment(&state, &auth, appointment_id).await?;
+    let notes = appointments::notes(&*state.repos.appointments, appointment_id).await?;
+    Ok(Json(ApiOk { data: notes }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddAppointmentNoteRequest {
+    pub note_text: String,
+    pub is_pinned: Option<bool>, // default false
+}
+
+// POST /appointments/{id}/notes : returns the whole timeline
+pub async fn add_appointment_note(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(appointment_id): Path<Uuid>,
+    Json(req): Json<AddAppointmentNoteRequest>,
+) -> Result<Json<ApiList<AppointmentNoteDto>>, ApiError> {
+    ensure_can_access_appointment(&state, &auth, appointment_id).await?;
+
+    let text = req.note_text.trim();
+    if text.is_empty() {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "note_text is required".into()));
+    }
 
-    tx.commit()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    let mut tx = state.db.begin().await?;
+    appointments::add_note(&mut tx, appointment_id, auth.user_id, text, req.is_pinned.unwrap_or(false)).await?;
+    tx.commit().await?;
 
-    get_appointment(State(state), auth, Path(appointment_id)).await
+    let notes = appointments::notes(&*state.repos.appointments, appointment_id).await?;
+    Ok(Json(ApiOk { data: notes }))
 }
 
-/* ============================================================
-   Helper: fold joined rows into appointment blocks
-   ============================================================ */
+#[derive(Debug, Deserialize)]
+pub struct ProcedureNoteRequest {
+    pub template_id: Uuid,
+    #[serde(default)]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+    pub is_pinned: Option<bool>, // default false; only for saving
+}
 
-fn fold_rows_into_blocks(
-    rows: Vec<sqlx::postgres::PgRow>,
-) -> Result<Vec<AppointmentBlockDto>, ApiError> {
-    use std::collections::BTreeMap;
-
-    let mut map: BTreeMap<Uuid, AppointmentBlockDto> = BTreeMap::new();
-
-    for r in rows {
-        let appointment_id: Uuid = r.try_get("appointment_id").map_err(internal_row)?;
-        let start_at: DateTime<Utc> = r.try_get("start_at").map_err(internal_row)?;
-        let end_at: DateTime<Utc> = r.try_get("end_at").map_err(internal_row)?;
-        let status: i16 = r.try_get("status").map_err(internal_row)?;
-        let priority: i16 = r.try_get("priority").map_err(internal_row)?;
-        let color_override: Option<i32> = r.try_get("color_override").map_err(internal_row)?;
-        let note: Option<String> = r.try_get("note").map_err(internal_row)?;
-
-        let source: String = r.try_get("source").unwrap_or_else(|_| "SCHEDULED".into());
-        let confirmed_at: Option<DateTime<Utc>> = r.try_get("confirmed_at").ok();
-        let reminder_sent_at: Option<DateTime<Utc>> = r.try_get("reminder_sent_at").ok();
-
-        let p_id: Uuid = r.try_get("patient_id").map_err(internal_row)?;
-        let p_first: String = r.try_get("p_first").map_err(internal_row)?;
-        let p_last: String = r.try_get("p_last").map_err(internal_row)?;
-        let p_reg: Option<i64> = r.try_get("p_reg").ok();
-
-        let d_id: Uuid = r.try_get("d_id").map_err(internal_row)?;
-        let d_no: i64 = r.try_get("d_no").map_err(internal_row)?;
-        let d_first: String = r.try_get("d_first").map_err(internal_row)?;
-        let d_last: String = r.try_get("d_last").map_err(internal_row)?;
-
-        let entry = map.entry(appointment_id).or_insert_with(|| AppointmentBlockDto {
-            appointment_id,
-            start_at,
-            end_at,
-            status,
-            priority,
-            color_override,
-            note: note.clone(),
-            source: source.clone(),
-            confirmed_at,
-            reminder_sent_at,
-            patient: PersonBrief {
-                id: p_id,
-                display: format!("{p_first} {p_last}"),
-                number: p_reg,
-            },
-            doctor: PersonBrief {
-                id: d_id,
-                display: format!("{d_first} {d_last}"),
-                number: Some(d_no),
-            },
-            planned_items: vec![],
-            planned_summary: String::new(),
-        });
-
-        let svc_id: Option<Uuid> = r.try_get("svc_id").ok();
-        if let Some(service_id) = svc_id {
-            let qty: i32 = r.try_get("svc_qty").unwrap_or(1);
-            let name: String = r.try_get("svc_name").unwrap_or_else(|_| "Service".into());
-            entry.planned_items.push(AppointmentPlanItemDto {
-                service_id,
-                display_name: name,
-                qty,
-            });
-        }
+/// Procedure notes are clinical documentation: the appointment's doctor or admin/manager.
+async fn ensure_can_document(state: &AppState, auth: &AuthContext, appointment_id: Uuid) -> Result<(), ApiError> {
+    ensure_can_access_appointment(state, auth, appointment_id).await?;
+    if is_doctor(auth) || is_admin(auth) || is_manager(auth) {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only doctors and admin/manager can write procedure notes".into(),
+        ))
     }
+}
 
-    for v in map.values_mut() {
-        if v.planned_items.is_empty() {
-            v.planned_summary = "(no planned items)".into();
-        } else {
-            let mut parts: Vec<String> = vec![];
-            for it in &v.planned_items {
-                if it.qty <= 1 {
-                    parts.push(it.display_name.clone());
-                } else {
-                    parts.push(format!("{}×{}", it.display_name, it.qty));
-                }
-            }
-            v.planned_summary = parts.join(" + ");
-        }
+async fn fill_procedure(
+    state: &AppState,
+    appointment_id: Uuid,
+    req: &ProcedureNoteRequest,
+) -> Result<FilledProcedure, ApiError> {
+    let template = procedure_templates::load(&state.db, req.template_id).await?;
+    let context = procedure_templates::appointment_context(&state.db, appointment_id).await?;
+    procedure_templates::fill(&template.fields, &template.body, &req.fields, &context)
+}
+
+// POST /appointments/{id}/procedure_notes/prefill : the draft a template gives (nothing is saved)
+pub async fn prefill_procedure_note(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(appointment_id): Path<Uuid>,
+    Json(req): Json<ProcedureNoteRequest>,
+) -> Result<Json<ApiOk<FilledProcedure>>, ApiError> {
+    ensure_can_document(&state, &auth, appointment_id).await?;
+    let filled = fill_procedure(&state, appointment_id, &req).await?;
+    Ok(Json(ApiOk { data: filled }))
+}
+
+// POST /appointments/{id}/procedure_notes : saves the filled template as a note; returns the timeline
+pub async fn add_procedure_note(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(appointment_id): Path<Uuid>,
+    Json(req): Json<ProcedureNoteRequest>,
+) -> Result<Json<ApiList<AppointmentNoteDto>>, ApiError> {
+    ensure_can_document(&state, &auth, appointment_id).await?;
+    let filled = fill_procedure(&state, appointment_id, &req).await?;
+    if !filled.missing.is_empty() {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("required fields missing: {}", filled.missing.join(", ")),
+        ));
     }
 
-    Ok(map.into_values().collect())
+    let mut tx = state.db.begin().await?;
+    appointments::add_procedure_note(
+        &mut tx,
+        appointment_id,
+        auth.user_id,
+        req.template_id,
+        &filled,
+        req.is_pinned.unwrap_or(false),
+    )
+    .await?;
+    tx.commit().await?;
+
+    let notes = appointments::notes(&*state.repos.appointments, appointment_id).await?;
+    Ok(Json(ApiOk { data: notes }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PinAppointmentNoteRequest {
+    pub is_pinned: bool,
 }
 
-fn internal_row(e: sqlx::Error) -> ApiError {
-    ApiError::Internal(format!("row decode error: {e}"))
+// POST /appointments/{id}/notes/{note_id}/pin : notes are append-only, only the pin flag changes
+pub async fn pin_appointment_note(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path((appointment_id, appointment_note_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<PinAppointmentNoteRequest>,
+) -> Result<Json<ApiList<AppointmentNoteDto>>, ApiError> {
+    ensure_can_access_appointment(&state, &auth, appointment_id).await?;
+    appointments::set_note_pinned(&state.db, appointment_id, appointment_note_id, req.is_pinned).await?;
+
+    let notes = appointments::notes(&*state.repos.appointments, appointment_id).await?;
+    Ok(Json(ApiOk { data: notes }))
+}
+
+/* ============================================================
+   First-visit checklist
+   GET /appointments/{id}/onboarding
+   PUT /appointments/{id}/onboarding/{onboarding_item_id}
+   ============================================================ */
+
+// catalog order; empty for appointments booked without is_new_patient
+pub async fn get_onboarding_checklist(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(appointment_id): Path<Uuid>,
+) -> Result<Json<ApiList<ChecklistItemDto>>, ApiError> {
+    ensure_can_access_appointment(&state, &auth, appointment_id).await?;
+    Ok(Json(ApiOk { data: onboarding::checklist(&state.db, appointment_id).await? }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PutOnboardingItemRequest {
+    pub done: bool,
+    pub note: Option<String>, // omitted = keep; "" = clear
+}
+
+// returns the whole checklist
+pub async fn put_onboarding_item(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path((appointment_id, onboarding_item_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<PutOnboardingItemRequest>,
+) -> Result<Json<ApiList<ChecklistItemDto>>, ApiError> {
+    ensure_can_access_appointment(&state, &auth, appointment_id).await?;
+
+    let note = req.note.as_deref().map(str::trim);
+    if note.is_some_and(|n| n.chars().count() > onboarding::MAX_NOTE_LEN) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("note must be at most {} chars", onboarding::MAX_NOTE_LEN),
+        ));
+    }
+
+    onboarding::set_done(&state.db, appointment_id, onboarding_item_id, auth.user_id, req.done, note).await?;
+    Ok(Json(ApiOk { data: onboarding::checklist(&state.db, appointment_id).await? }))
 }
@@ -1,7 +1,7 @@
 use axum::{
-    Json, Router,
-    extract::State,
-    routing::{get, post},
+    Router,
+    extract::{Query, State},
+    routing::{get, post, put},
 };
 use chrono::{Duration, Utc};
 
@@ -10,17 +10,21 @@ use uuid::Uuid;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    audit,
     auth::{generate_access_token, hash_access_token, verify_password, hash_password},
     error::ApiError,
+    extract::Json,
+    i18n::{self, Lang},
+    login_events::{self, ClientInfo, LoginAttempt, LoginHistoryQuery, LoginEventRow},
     middleware::auth_context::AuthContext,
-    models::{role_to_string, *},
+    models::*,
 };
 
 // Session type according to migrations/003_session_token.sql
 const SESSION_TYPE_UNDEFINED: i16 = 0;
 const SESSION_TYPE_USER_PORTAL: i16 = 1;
 const SESSION_TYPE_PATIENT_WEB: i16 = 2;
-const SESSION_TYPE_DCMSHQ: i16 = 3;
+pub const SESSION_TYPE_DCMSHQ: i16 = 3;
 
 // Safety limits (can be moved to config later)
 const MAX_EXTEND_HOURS: i64 = 24 * 30; // 30 days
@@ -41,7 +45,13 @@ pub fn router() -> Router<AppState> {
         .route("/login", post(login))
         // Future: patient portal login (session_type=2)
         .route("/patient/login", post(patient_login))
+        // DCMS HQ sync client (session_type=3, admin only); the token is for the hq-grpc server
+        .route("/hq/login", post(hq_login))
         .route("/me", get(me))
+        // language of localized error messages (null = Accept-Language)
+        .route("/me/language", put(set_my_language))
+        // own login attempts (success + failure), newest first
+        .route("/my_logins", get(my_logins))
         .route("/logout", post(logout))
         // Convenience: revoke all other sessions but keep the current one
         .route("/logout_all_except_current", post(logout_all_except_current))
@@ -70,8 +80,7 @@ async fn load_clinic_name(state: &AppState) -> Result<String, ApiError> {
         "#,
     )
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     Ok(clinic_name.unwrap_or_else(|| "Clinic".to_string()))
 }
@@ -80,8 +89,9 @@ async fn login_with_type(
     state: &AppState,
     req: &LoginRequest,
     session_type: i16,
-    required_role: Option<i16>,
-) -> Result<LoginResponse, ApiError> {
+    required_role: Option<Role>,
+    client: &ClientInfo,
+) -> Result<ApiOk<LoginResponseData>, ApiError> {
     let username = req.username.trim();
     if username.is_empty() || req.password.is_empty() {
         return Err(ApiError::BadRequest(
@@ -96,9 +106,15 @@ async fn login_with_type(
         ));
     }
 
+    let attempt = LoginAttempt {
+        username,
+        session_type,
+        device_name: req.device_name.as_deref(),
+        client,
+    };
 
     // 1) Load dcms_user
-    let dcms_user: UserRow = sqlx::query_as::<_, UserRow>(
+    let dcms_user: Option<UserRow> = sqlx::query_as::<_, UserRow>(
         r#"
         SELECT user_id, username, display_name, password_hash, roles, is_active
         FROM "dcms_user"
@@ -107,28 +123,33 @@ async fn login_with_type(
     )
     .bind(username)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(ApiError::invalid_credentials)?;
+    .await?;
+    let Some(dcms_user) = dcms_user else {
+        login_events::record(&state.db, &attempt, None, Err("unknown_user")).await;
+        return Err(ApiError::invalid_credentials());
+    };
 
     if !dcms_user.is_active {
+        login_events::record(&state.db, &attempt, Some(dcms_user.user_id), Err("disabled")).await;
         return Err(ApiError::Forbidden(
             "FORBIDDEN",
             "Account is disabled".into(),
         ));
     }
 
-    if let Some(rr) = required_role {
-        if dcms_user.roles != rr {
-            return Err(ApiError::Forbidden(
-                "FORBIDDEN",
-                "Account type not allowed for this login".into(),
-            ));
-        }
+    if let Some(rr) = required_role
+        && dcms_user.roles != rr
+    {
+        login_events::record(&state.db, &attempt, Some(dcms_user.user_id), Err("role_not_allowed")).await;
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Account type not allowed for this login".into(),
+        ));
     }
 
     // 2) Verify password
     if !verify_password(&req.password, &dcms_user.password_hash) {
+        login_events::record(&state.db, &attempt, Some(dcms_user.user_id), Err("bad_password")).await;
         return Err(ApiError::invalid_credentials());
     }
 
@@ -165,10 +186,11 @@ async fn login_with_type(
     .bind(req.device_name.as_deref())
     .bind(expires_at)
     .fetch_one(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    Ok(LoginResponse {
+    login_events::record(&state.db, &attempt, Some(dcms_user.user_id), Ok(session.session_token_id)).await;
+
+    Ok(ApiOk {
         data: LoginResponseData {
             access_token,
             expires_at: session.expires_at,
@@ -176,7 +198,7 @@ async fn login_with_type(
                 user_id: dcms_user.user_id,
                 username: dcms_user.username,
                 display_name: dcms_user.display_name,
-                roles: vec![role_to_string(dcms_user.roles)],
+                roles: vec![dcms_user.roles.to_string()],
             },
             clinic: ClinicProfile { clinic_name },
         },
@@ -185,9 +207,10 @@ async fn login_with_type(
 
 pub async fn login(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, ApiError> {
-    let resp = login_with_type(&state, &req, SESSION_TYPE_USER_PORTAL, None).await?;
+) -> Result<Json<ApiOk<LoginResponseData>>, ApiError> {
+    let resp = login_with_type(&state, &req, SESSION_TYPE_USER_PORTAL, None, &client).await?;
     Ok(Json(resp))
 }
 
@@ -195,17 +218,39 @@ pub async fn login(
 /// and uses session_type=2.
 pub async fn patient_login(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, ApiError> {
-    let resp = login_with_type(&state, &req, SESSION_TYPE_PATIENT_WEB, Some(0)).await?;
+) -> Result<Json<ApiOk<LoginResponseData>>, ApiError> {
+    let resp = login_with_type(&state, &req, SESSION_TYPE_PATIENT_WEB, Some(Role::Patient), &client).await?;
     Ok(Json(resp))
 }
 
+/// DCMS HQ login: admin credentials, session_type=3. Only these sessions are
+/// accepted by the HQ sync gRPC server (hq_grpc).
+pub async fn hq_login(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(req): Json<LoginRequest>,
+) -> Result<Json<ApiOk<LoginResponseData>>, ApiError> {
+    let resp = login_with_type(&state, &req, SESSION_TYPE_DCMSHQ, Some(Role::Admin), &client).await?;
+    Ok(Json(resp))
+}
+
+/// The caller's own login attempts (`?success=false` = failed ones only).
+pub async fn my_logins(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<LoginHistoryQuery>,
+) -> Result<Json<ApiList<LoginEventRow>>, ApiError> {
+    let data = login_events::list_for_user(&state.db, auth.user_id, &q).await?;
+    Ok(Json(ApiOk { data }))
+}
+
 
 pub async fn me(
     State(state): State<AppState>,
     auth: AuthContext,
-) -> Result<Json<MeResponse>, ApiError> {
+) -> Result<Json<ApiOk<MeResponseData>>, ApiError> {
     // Load dcms_user
     let dcms_user: UserRow = sqlx::query_as::<_, UserRow>(
         r#"
@@ -216,8 +261,7 @@ pub async fn me(
     )
     .bind(auth.user_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
+    .await?
     .ok_or_else(ApiError::session_expired)?;
 
     if !dcms_user.is_active {
@@ -227,6 +271,12 @@ pub async fn me(
     // Load clinic name (singleton)
     let clinic_name = load_clinic_name(&state).await?;
 
+    let preferred_language: Option<String> =
+        sqlx::query_scalar(r#"SELECT preferred_language FROM "dcms_user" WHERE user_id = $1"#)
+            .bind(auth.user_id)
+            .fetch_one(&state.db)
+            .await?;
+
     // Load session token (ensure still active)
     let session: SessionTokenRow = sqlx::query_as::<_, SessionTokenRow>(
         r#"
@@ -241,32 +291,78 @@ pub async fn me(
     .bind(auth.session_token_id)
     .bind(auth.user_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
+    .await?
     .ok_or_else(ApiError::session_expired)?;
 
-    Ok(Json(MeResponse {
+    Ok(Json(ApiOk {
         data: MeResponseData {
             dcms_user: UserProfile {
                 user_id: dcms_user.user_id,
                 username: dcms_user.username,
                 display_name: dcms_user.display_name,
-                roles: vec![role_to_string(dcms_user.roles)],
+                roles: vec![dcms_user.roles.to_string()],
             },
             clinic: ClinicProfile { clinic_name },
             session: SessionInfo {
                 session_token_id: session.session_token_id,
                 expires_at: session.expires_at,
+                impersonating: auth.is_impersonating(),
+                impersonator_user_id: auth.impersonator_user_id,
             },
             message: "login success".into(),
+            preferred_language,
         },
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SetLanguageRequest {
+    /// "en" | "mn"; null = follow Accept-Language
+    pub preferred_language: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetLanguageData {
+    pub preferred_language: Option<Lang>,
+}
+
+pub async fn set_my_language(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<SetLanguageRequest>,
+) -> Result<Json<ApiOk<SetLanguageData>>, ApiError> {
+    let lang = match req.preferred_language.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(tag) => Some(Lang::parse(tag).ok_or_else(|| {
+            ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                format!("preferred_language must be one of: {}", i18n::SUPPORTED.join(", ")),
+            )
+        })?),
+    };
+    sqlx::query(r#"UPDATE "dcms_user" SET preferred_language = $1 WHERE user_id = $2"#)
+        .bind(lang.map(Lang::as_str))
+        .bind(auth.user_id)
+        .execute(&state.db)
+        .await?;
+
+    // cached sessions carry the language
+    state.session_cache.invalidate_user(auth.user_id);
+
+    Ok(Json(ApiOk {
+        data: SetLanguageData { preferred_language: lang },
+    }))
+}
+
 pub async fn logout(
     State(state): State<AppState>,
     auth: AuthContext,
-) -> Result<Json<OkResponse>, ApiError> {
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    let mut tx = state
+        .db
+        .begin()
+        .await?;
+
     let rows = sqlx::query(
         r#"
         UPDATE session_token
@@ -278,15 +374,31 @@ pub async fn logout(
     )
     .bind(auth.session_token_id)
     .bind(auth.user_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .execute(&mut *tx)
+    .await?;
 
     if rows.rows_affected() == 0 {
         return Err(ApiError::session_expired());
     }
 
-    Ok(Json(OkResponse {
+    if auth.is_impersonating() {
+        audit::record(
+            &mut *tx,
+            &auth,
+            "impersonation.stop",
+            "dcms_user",
+            Some(auth.user_id),
+            serde_json::json!({ "reason": "logout" }),
+        )
+        .await?;
+    }
+
+    tx.commit()
+        .await?;
+
+    state.session_cache.invalidate_session(auth.session_token_id);
+
+    Ok(Json(ApiOk {
         data: OkData { ok: true },
     }))
 }
@@ -296,7 +408,9 @@ pub async fn logout(
 pub async fn logout_all_except_current(
     State(state): State<AppState>,
     auth: AuthContext,
-) -> Result<Json<RevokeAllResponse>, ApiError> {
+) -> Result<Json<ApiOk<RevokeAllData>>, ApiError> {
+    auth.ensure_not_impersonating()?;
+
     // This is basically "revoke_all" but exposed as an explicit UX action.
     let res = sqlx::query(
         r#"
@@ -311,10 +425,11 @@ pub async fn logout_all_except_current(
     .bind(auth.user_id)
     .bind(auth.session_token_id)
     .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    Ok(Json(RevokeAllResponse {
+    state.session_cache.invalidate_user(auth.user_id);
+
+    Ok(Json(ApiOk {
         data: RevokeAllData {
             ok: true,
             revoked_count: res.rows_affected() as i64,
@@ -322,56 +437,104 @@ pub async fn logout_all_except_current(
     }))
 }
 
-#[derive(Debug, Serialize)]
-pub struct RefreshResponse {
-    pub data: RefreshData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct RefreshData {
     pub ok: bool,
     pub access_token: String,
     pub expires_at: chrono::DateTime<chrono::Utc>,
     pub session_token_id: Uuid,
+    pub rotated_count: i32,
 }
 
 /// POST /api/v1/auth/refresh
 /// Rotates the access token for the *current* session.
 /// This immediately invalidates the old token, but keeps the same session_token_id.
+/// The old token hash is kept in session_token_rotated: presenting it again revokes
+/// the session (see AuthContext). With SESSION_SLIDING on, expires_at also moves to
+/// now + TTL, never past created_at + SESSION_MAX_LIFETIME_HOURS (impersonation
+/// sessions keep their fixed expiry).
 pub async fn refresh(
     State(state): State<AppState>,
     auth: AuthContext,
-) -> Result<Json<RefreshResponse>, ApiError> {
+) -> Result<Json<ApiOk<RefreshData>>, ApiError> {
     let new_token = generate_access_token();
     let new_hash = hash_access_token(&new_token);
 
-    let row: Option<(chrono::DateTime<chrono::Utc>,)> = sqlx::query_as(
+    let ttl_hours = if auth.role == Role::Patient {
+        DEFAULT_PATIENT_TTL_HOURS
+    } else {
+        state.session_ttl_hours
+    };
+
+    let mut tx = state
+        .db
+        .begin()
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO session_token_rotated (token_hash, session_token_id)
+        SELECT session_token_hash, session_token_id
+        FROM session_token
+        WHERE session_token_id = $1
+          AND user_id = $2
+          AND revoked_at IS NULL
+          AND expires_at > now()
+        ON CONFLICT (token_hash) DO NOTHING
+        "#,
+    )
+    .bind(auth.session_token_id)
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let row: Option<(chrono::DateTime<chrono::Utc>, i32)> = sqlx::query_as(
         r#"
         UPDATE session_token
         SET session_token_hash = $1,
-            last_seen_at = now()
+            last_seen_at = now(),
+            rotated_count = rotated_count + 1,
+            expires_at = CASE
+              WHEN $4 AND impersonator_user_id IS NULL THEN GREATEST(
+                expires_at,
+                LEAST(
+                  now() + make_interval(hours => $5::int),
+                  created_at + make_interval(hours => $6::int)
+                )
+              )
+              ELSE expires_at
+            END
         WHERE session_token_id = $2
           AND user_id = $3
           AND revoked_at IS NULL
           AND expires_at > now()
-        RETURNING expires_at
+        RETURNING expires_at, rotated_count
         "#,
     )
     .bind(new_hash)
     .bind(auth.session_token_id)
     .bind(auth.user_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .bind(state.session_sliding)
+    .bind(ttl_hours)
+    .bind(state.session_max_lifetime_hours)
+    .fetch_optional(&mut *tx)
+    .await?;
 
-    let expires_at = row.ok_or_else(ApiError::session_expired)?.0;
+    let (expires_at, rotated_count) = row.ok_or_else(ApiError::session_expired)?;
 
-    Ok(Json(RefreshResponse {
+    tx.commit()
+        .await?;
+
+    // the old token must stop working right away, not after the cache TTL
+    state.session_cache.invalidate_session(auth.session_token_id);
+
+    Ok(Json(ApiOk {
         data: RefreshData {
             ok: true,
             access_token: new_token,
             expires_at,
             session_token_id: auth.session_token_id,
+            rotated_count,
         },
     }))
 }
@@ -390,11 +553,6 @@ pub struct SessionListItem {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct ListSessionsResponse {
-    pub data: ListSessionsData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ListSessionsData {
     pub sessions: Vec<SessionListItem>,
@@ -404,7 +562,7 @@ pub struct ListSessionsData {
 pub async fn list_sessions(
     State(state): State<AppState>,
     auth: AuthContext,
-) -> Result<Json<ListSessionsResponse>, ApiError> {
+) -> Result<Json<ApiOk<ListSessionsData>>, ApiError> {
     // "active sessions" only: not revoked, not expired
     let rows: Vec<SessionListItem> = sqlx::query_as::<_, SessionListItem>(
         r#"
@@ -424,10 +582,9 @@ pub async fn list_sessions(
     )
     .bind(auth.user_id)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    Ok(Json(ListSessionsResponse {
+    Ok(Json(ApiOk {
         data: ListSessionsData {
             sessions: rows,
             current_session_token_id: auth.session_token_id,
@@ -447,11 +604,6 @@ pub struct SessionDetail {
     pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct GetSessionResponse {
-    pub data: GetSessionData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct GetSessionData {
     pub session: SessionDetail,
@@ -463,9 +615,9 @@ pub async fn get_session(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(session_token_id): Path<Uuid>,
-) -> Result<Json<GetSessionResponse>, ApiError> {
+) -> Result<Json<ApiOk<GetSessionData>>, ApiError> {
     // owner can view own; admin/manager can view any
-    let (sql, bind_user): (&str, bool) = if auth.role == 1 || auth.role == 2 {
+    let (sql, bind_user): (&str, bool) = if auth.role.is_admin_or_manager() {
         (
             r#"
             SELECT session_token_id, user_id, session_type, device_name, expires_at, created_at, last_seen_at, revoked_at
@@ -493,11 +645,10 @@ pub async fn get_session(
 
     let session = q
         .fetch_optional(&state.db)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-        .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "session not found".into()))?;
+        .await?
+        .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "session not found".into()))?;
 
-    Ok(Json(GetSessionResponse {
+    Ok(Json(ApiOk {
         data: GetSessionData { session },
     }))
 }
@@ -509,11 +660,6 @@ pub struct ExtendSessionRequest {
     pub extend_hours: Option<i64>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct ExtendSessionResponse {
-    pub data: ExtendSessionData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ExtendSessionData {
     pub ok: bool,
@@ -528,9 +674,11 @@ pub async fn extend_session(
     auth: AuthContext,
     Path(session_token_id): Path<Uuid>,
     Json(req): Json<ExtendSessionRequest>,
-) -> Result<Json<ExtendSessionResponse>, ApiError> {
-    let requested = req.extend_hours.unwrap_or_else(|| {
-        if auth.role == 0 {
+) -> Result<Json<ApiOk<ExtendSessionData>>, ApiError> {
+    auth.ensure_not_impersonating()?;
+
+    let requested = req.extend_hours.unwrap_or({
+        if auth.role == Role::Patient {
             DEFAULT_PATIENT_TTL_HOURS
         } else {
             state.session_ttl_hours
@@ -551,7 +699,7 @@ pub async fn extend_session(
     }
 
     // owner can extend own; admin/manager can extend any
-    let bind_user = !(auth.role == 1 || auth.role == 2);
+    let bind_user = !auth.role.is_admin_or_manager();
 
     // We compute: new_expires = GREATEST(expires_at, now()) + requested hours
     // but cap it to now + MAX_EXTEND_HOURS to avoid infinite growth.
@@ -565,6 +713,7 @@ pub async fn extend_session(
         WHERE session_token_id = $1
           AND user_id = $2
           AND revoked_at IS NULL
+          AND impersonator_user_id IS NULL
         RETURNING expires_at
         "#
     } else {
@@ -576,6 +725,7 @@ pub async fn extend_session(
             )
         WHERE session_token_id = $1
           AND revoked_at IS NULL
+          AND impersonator_user_id IS NULL
         RETURNING expires_at
         "#
     };
@@ -587,23 +737,21 @@ pub async fn extend_session(
             .bind(requested)
             .bind(MAX_EXTEND_HOURS)
             .fetch_optional(&state.db)
-            .await
-            .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
+            .await?
     } else {
         sqlx::query_as(sql)
             .bind(session_token_id)
             .bind(requested)
             .bind(MAX_EXTEND_HOURS)
             .fetch_optional(&state.db)
-            .await
-            .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
+            .await?
     };
 
     let expires_at = expires_row
-        .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "session not found, revoked, or not allowed".into()))?
+        .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "session not found, revoked, or not allowed".into()))?
         .0;
 
-    Ok(Json(ExtendSessionResponse {
+    Ok(Json(ApiOk {
         data: ExtendSessionData {
             ok: true,
             session_token_id,
@@ -612,11 +760,6 @@ pub async fn extend_session(
     }))
 }
 
-#[derive(Debug, Serialize)]
-pub struct RevokeOneResponse {
-    pub data: RevokeOneData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct RevokeOneData {
     pub ok: bool,
@@ -627,7 +770,7 @@ pub async fn revoke_session(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(session_token_id): Path<Uuid>,
-) -> Result<Json<RevokeOneResponse>, ApiError> {
+) -> Result<Json<ApiOk<RevokeOneData>>, ApiError> {
     // Revoke only your own session (admin override can be added later)
     let res = sqlx::query(
         r#"
@@ -641,17 +784,18 @@ pub async fn revoke_session(
     .bind(session_token_id)
     .bind(auth.user_id)
     .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     if res.rows_affected() == 0 {
-        return Err(ApiError::BadRequest(
+        return Err(ApiError::NotFound(
             "NOT_FOUND",
             "session not found, already revoked, or not yours".into(),
         ));
     }
 
-    Ok(Json(RevokeOneResponse {
+    state.session_cache.invalidate_session(session_token_id);
+
+    Ok(Json(ApiOk {
         data: RevokeOneData {
             ok: true,
             revoked_session_token_id: session_token_id,
@@ -659,11 +803,6 @@ pub async fn revoke_session(
     }))
 }
 
-#[derive(Debug, Serialize)]
-pub struct RevokeAllResponse {
-    pub data: RevokeAllData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct RevokeAllData {
     pub ok: bool,
@@ -673,7 +812,9 @@ pub struct RevokeAllData {
 pub async fn revoke_all_sessions(
     State(state): State<AppState>,
     auth: AuthContext,
-) -> Result<Json<RevokeAllResponse>, ApiError> {
+) -> Result<Json<ApiOk<RevokeAllData>>, ApiError> {
+    auth.ensure_not_impersonating()?;
+
     // Revoke everything except current session (and only active ones)
     let res = sqlx::query(
         r#"
@@ -688,10 +829,11 @@ pub async fn revoke_all_sessions(
     .bind(auth.user_id)
     .bind(auth.session_token_id)
     .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    Ok(Json(RevokeAllResponse {
+    state.session_cache.invalidate_user(auth.user_id);
+
+    Ok(Json(ApiOk {
         data: RevokeAllData {
             ok: true,
             revoked_count: res.rows_affected() as i64,
@@ -703,11 +845,6 @@ pub async fn revoke_all_sessions(
 // Admin-only: impersonation
 // =========================
 
-#[derive(Debug, Serialize)]
-pub struct ImpersonateResponse {
-    pub data: ImpersonateData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ImpersonateData {
     pub access_token: String,
@@ -718,6 +855,8 @@ pub struct ImpersonateData {
 
 /// POST /api/v1/auth/impersonate/{user_id}
 /// Creates a new session as the target user (admin-only).
+/// The session expires after IMPERSONATION_TTL_MINUTES and can't be extended;
+/// start (here) and stop (logout) are written to audit_log.
 ///
 /// Requires DB migration that adds these nullable columns to `session_token`:
 /// - impersonator_user_id UUID NULL
@@ -726,8 +865,16 @@ pub async fn impersonate(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(target_user_id): Path<Uuid>,
-) -> Result<Json<ImpersonateResponse>, ApiError> {
+) -> Result<Json<ApiOk<ImpersonateData>>, ApiError> {
     ensure_admin(&auth)?;
+    auth.ensure_not_impersonating()?;
+
+    if target_user_id == auth.user_id {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "cannot impersonate yourself".into(),
+        ));
+    }
 
     // Load target user
     let target: UserRow = sqlx::query_as::<_, UserRow>(
@@ -739,12 +886,11 @@ pub async fn impersonate(
     )
     .bind(target_user_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "target user not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "target user not found".into()))?;
 
     if !target.is_active {
-        return Err(ApiError::BadRequest(
+        return Err(ApiError::NotFound(
             "NOT_FOUND",
             "target user is disabled".into(),
         ));
@@ -758,9 +904,14 @@ pub async fn impersonate(
     let token_hash = hash_access_token(&access_token);
 
     // Impersonation sessions should be short-lived by default.
-    let expires_at = Utc::now() + Duration::hours(2);
+    let expires_at = Utc::now() + Duration::minutes(state.impersonation_ttl_minutes);
+
+    let mut tx = state
+        .db
+        .begin()
+        .await?;
 
-    let _session: SessionTokenRow = sqlx::query_as::<_, SessionTokenRow>(
+    let session: SessionTokenRow = sqlx::query_as::<_, SessionTokenRow>(
         r#"
         INSERT INTO session_token
             (user_id, session_token_hash, session_type, device_name, expires_at,
@@ -777,19 +928,35 @@ pub async fn impersonate(
     .bind(expires_at)
     .bind(auth.user_id)
     .bind(target.user_id)
-    .fetch_one(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .fetch_one(&mut *tx)
+    .await?;
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "impersonation.start",
+        "dcms_user",
+        Some(target.user_id),
+        serde_json::json!({
+            "impersonation_session_token_id": session.session_token_id,
+            "target_username": target.username,
+            "expires_at": session.expires_at,
+        }),
+    )
+    .await?;
+
+    tx.commit()
+        .await?;
 
-    Ok(Json(ImpersonateResponse {
+    Ok(Json(ApiOk {
         data: ImpersonateData {
             access_token,
-            expires_at: _session.expires_at,
+            expires_at: session.expires_at,
             dcms_user: UserProfile {
                 user_id: target.user_id,
                 username: target.username,
                 display_name: target.display_name,
-                roles: vec![role_to_string(target.roles)],
+                roles: vec![target.roles.to_string()],
             },
             clinic: ClinicProfile { clinic_name },
         },
@@ -806,11 +973,6 @@ pub struct ChangePasswordRequest {
     pub new_password: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct ChangePasswordResponse {
-    pub data: OkData,
-}
-
 fn validate_new_password(pw: &str) -> Result<(), ApiError> {
     let pw = pw.trim();
     if pw.len() < 8 {
@@ -826,7 +988,9 @@ pub async fn change_password(
     State(state): State<AppState>,
     auth: AuthContext,
     Json(req): Json<ChangePasswordRequest>,
-) -> Result<Json<ChangePasswordResponse>, ApiError> {
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    auth.ensure_not_impersonating()?;
+
     if req.old_password.is_empty() || req.new_password.is_empty() {
         return Err(ApiError::BadRequest(
             "VALIDATION_ERROR",
@@ -846,8 +1010,7 @@ pub async fn change_password(
     )
     .bind(auth.user_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
+    .await?
     .ok_or_else(ApiError::session_expired)?;
 
     // Verify old password
@@ -858,11 +1021,10 @@ pub async fn change_password(
 
     // Hash + update
     let new_hash = hash_password(&req.new_password)
-        .map_err(|e| ApiError::Internal(e))?;
+        .map_err(ApiError::Internal)?;
 
     // Do in a transaction so we can revoke sessions consistently
-    let mut tx = state.db.begin().await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    let mut tx = state.db.begin().await?;
 
     sqlx::query(
         r#"
@@ -874,8 +1036,7 @@ pub async fn change_password(
     .bind(new_hash)
     .bind(auth.user_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     // Security: revoke all OTHER active sessions (keep current)
     sqlx::query(
@@ -891,13 +1052,13 @@ pub async fn change_password(
     .bind(auth.user_id)
     .bind(auth.session_token_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    tx.commit().await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    tx.commit().await?;
 
-    Ok(Json(ChangePasswordResponse {
+    state.session_cache.invalidate_user(auth.user_id);
+
+    Ok(Json(ApiOk {
         data: OkData { ok: true },
     }))
 }
@@ -911,11 +1072,6 @@ pub struct ResetPasswordRequest {
     pub new_password: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct ResetPasswordResponse {
-    pub data: ResetPasswordData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ResetPasswordData {
     pub ok: bool,
@@ -926,7 +1082,7 @@ pub struct ResetPasswordData {
 
 fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
     // roles: 1 admin, 2 manager
-    if auth.role == 1 || auth.role == 2 {
+    if auth.role.is_admin_or_manager() {
         Ok(())
     } else {
         Err(ApiError::Forbidden(
@@ -937,7 +1093,7 @@ fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
 }
 
 fn ensure_admin(auth: &AuthContext) -> Result<(), ApiError> {
-    if auth.role == 1 {
+    if auth.role == Role::Admin {
         Ok(())
     } else {
         Err(ApiError::Forbidden(
@@ -958,8 +1114,9 @@ pub async fn reset_password(
     State(state): State<AppState>,
     auth: AuthContext,
     Json(req): Json<ResetPasswordRequest>,
-) -> Result<Json<ResetPasswordResponse>, ApiError> {
+) -> Result<Json<ApiOk<ResetPasswordData>>, ApiError> {
     ensure_admin_or_manager(&auth)?;
+    auth.ensure_not_impersonating()?;
 
     let username = req.username.trim();
     if username.is_empty() {
@@ -983,10 +1140,9 @@ pub async fn reset_password(
     };
 
     let new_hash = hash_password(&new_pw)
-        .map_err(|e| ApiError::Internal(e))?;
+        .map_err(ApiError::Internal)?;
 
-    let mut tx = state.db.begin().await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    let mut tx = state.db.begin().await?;
 
     // Find target user
     let target: (Uuid, String) = sqlx::query_as(
@@ -998,9 +1154,8 @@ pub async fn reset_password(
     )
     .bind(username)
     .fetch_optional(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "user not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "user not found".into()))?;
 
     // Update password hash
     sqlx::query(
@@ -1013,8 +1168,7 @@ pub async fn reset_password(
     .bind(new_hash)
     .bind(target.0)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     // Security: revoke ALL active sessions for that user
     sqlx::query(
@@ -1027,13 +1181,13 @@ pub async fn reset_password(
     )
     .bind(target.0)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
+
+    tx.commit().await?;
 
-    tx.commit().await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    state.session_cache.invalidate_user(target.0);
 
-    Ok(Json(ResetPasswordResponse {
+    Ok(Json(ApiOk {
         data: ResetPasswordData {
             ok: true,
             user_id: target.0,
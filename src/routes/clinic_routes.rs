@@ -1,17 +1,28 @@
 // src/routes/clinic_routes.rs
 
 use axum::{
-    extract::State,
-    routing::{get, patch},
-    Json, Router,
+    extract::{Path, Query, State},
+    middleware,
+    routing::{delete, get, patch, put},
+    Router,
 };
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use uuid::Uuid;
 
 use crate::{
+    clinic_time,
     error::ApiError,
-    middleware::auth_context::AuthContext,
-    models::AppState,
+    extract::Json,
+    middleware::{auth_context::AuthContext, etag},
+    jobs::appointment_reminders::ReminderPolicy,
+    models::{ApiList, ApiOk, AppState, OkData, Role},
+    money,
+    numbering::{self, RegisterNumberFormat},
+    overlap_policy::{self, OverlapPolicy},
+    phone_sharing::PhoneSharing,
+    sms_replies::{self, SmsReplyPolicy},
 };
 
 pub fn router() -> Router<AppState> {
@@ -22,13 +33,52 @@ pub fn router() -> Router<AppState> {
         // settings
         .route("/clinic/settings", get(get_clinic_settings))
         .route("/clinic/settings", patch(patch_clinic_settings))
+        // closures / holidays
+        .route("/clinic/holidays", get(list_clinic_holidays).post(create_clinic_holiday))
+        .route("/clinic/holidays/{holiday_id}", delete(delete_clinic_holiday))
+        // locations (multi-site)
+        .route("/clinic/locations", get(list_clinic_locations).post(create_clinic_location))
+        .route("/clinic/locations/{location_id}", patch(patch_clinic_location))
+        .route(
+            "/clinic/locations/{location_id}/employees/{employee_id}",
+            put(set_employee_location),
+        )
+        .route(
+            "/clinic/locations/{location_id}/access",
+            get(list_location_access).post(grant_location_access),
+        )
+        .route(
+            "/clinic/locations/{location_id}/access/{user_id}",
+            delete(revoke_location_access),
+        )
+        // referral sources ("how did you hear about us")
+        .route(
+            "/clinic/referral_sources",
+            get(list_referral_sources).post(create_referral_source),
+        )
+        .route(
+            "/clinic/referral_sources/{referral_source_id}",
+            patch(patch_referral_source),
+        )
+        // first-visit checklist items (see onboarding.rs)
+        .route(
+            "/clinic/onboarding_items",
+            get(list_onboarding_items).post(create_onboarding_item),
+        )
+        .route(
+            "/clinic/onboarding_items/{onboarding_item_id}",
+            patch(patch_onboarding_item),
+        )
         // meta (UI helper)
-        .route("/clinic/meta", get(get_clinic_meta))
+        .route(
+            "/clinic/meta",
+            get(get_clinic_meta).layer(middleware::from_fn(etag::etag)),
+        )
 }
 
 fn ensure_admin(auth: &AuthContext) -> Result<(), ApiError> {
     // roles: 1 admin
-    if auth.role == 1 {
+    if auth.role == Role::Admin {
         Ok(())
     } else {
         Err(ApiError::Forbidden(
@@ -46,14 +96,13 @@ fn validate_timezone(tz: &str) -> Result<(), ApiError> {
             "timezone is required".into(),
         ));
     }
-    if tz.len() > 64 {
+    // IANA names only, e.g. "Asia/Ulaanbaatar", "UTC"
+    if clinic_time::parse_tz(tz).is_none() {
         return Err(ApiError::BadRequest(
             "VALIDATION_ERROR",
-            "timezone too long".into(),
+            format!("unknown timezone '{tz}' (expected an IANA name like Asia/Ulaanbaatar)"),
         ));
     }
-    // Soft validation only (keep it simple for now)
-    // Typical examples: "Asia/Ulaanbaatar", "UTC"
     Ok(())
 }
 
@@ -81,15 +130,88 @@ fn validate_business_hours(bh: &JsonValue) -> Result<(), ApiError> {
     Ok(())
 }
 
+fn validate_currency_code(code: &str) -> Result<(), ApiError> {
+    // ISO 4217 alpha code, e.g. "MNT", "USD"
+    if code.len() != 3 || !code.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "currency_code must be a 3-letter ISO 4217 code (e.g. MNT)".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_tax_rates(rates: &JsonValue) -> Result<(), ApiError> {
+    // Expect object: { "standard": 1000, "exempt": 0 } (basis points, 1000 = 10%)
+    let Some(obj) = rates.as_object() else {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "tax_rates must be a JSON object".into(),
+        ));
+    };
+    if !obj.contains_key(money::DEFAULT_TAX_CATEGORY) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("tax_rates must contain '{}'", money::DEFAULT_TAX_CATEGORY),
+        ));
+    }
+    for (category, bp) in obj {
+        if category.trim().is_empty() || category.len() > 32 {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "tax category names must be 1..32 chars".into(),
+            ));
+        }
+        if !bp.as_i64().is_some_and(|v| (0..=10_000).contains(&v)) {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                format!("tax rate for '{category}' must be an integer 0..10000 (basis points)"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_reminder_policy(policy: &JsonValue) -> Result<(), ApiError> {
+    // Same parser the reminder job uses, so a stored policy is always runnable.
+    ReminderPolicy::from_json(policy)
+        .map(|_| ())
+        .map_err(|e| ApiError::BadRequest("VALIDATION_ERROR", e))
+}
+
+fn validate_overlap_policy(policy: &JsonValue) -> Result<(), ApiError> {
+    OverlapPolicy::from_json(policy)
+        .map(|_| ())
+        .map_err(|e| ApiError::BadRequest("VALIDATION_ERROR", e))
+}
+
+fn validate_sms_reply_policy(policy: &JsonValue) -> Result<(), ApiError> {
+    SmsReplyPolicy::from_json(policy)
+        .map(|_| ())
+        .map_err(|e| ApiError::BadRequest("VALIDATION_ERROR", e))
+}
+
+fn validate_register_number_format(format: &JsonValue) -> Result<(), ApiError> {
+    RegisterNumberFormat::from_json(format)
+        .map(|_| ())
+        .map_err(|e| ApiError::BadRequest("VALIDATION_ERROR", e))
+}
+
+fn default_reminder_policy() -> JsonValue {
+    serde_json::json!({
+        "enabled": false,
+        "hours_before": 24,
+        "template": "{name}, you have a dental appointment on {date} at {time}.",
+        "sms_enabled": true,
+        "send_window": { "start": "09:00", "end": "20:00" },
+        "max_per_day": 200
+    })
+}
+
 /* ============================================================
    1) /clinic (PROFILE)
    ============================================================ */
 
-#[derive(Debug, Serialize)]
-pub struct ClinicResponse {
-    pub data: ClinicData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ClinicData {
     pub clinic_name: String,
@@ -98,7 +220,7 @@ pub struct ClinicData {
 pub async fn get_clinic(
     State(state): State<AppState>,
     _auth: AuthContext,
-) -> Result<Json<ClinicResponse>, ApiError> {
+) -> Result<Json<ApiOk<ClinicData>>, ApiError> {
     let clinic_name: Option<String> = sqlx::query_scalar(
         r#"
         SELECT clinic_name
@@ -107,10 +229,9 @@ pub async fn get_clinic(
         "#,
     )
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    Ok(Json(ClinicResponse {
+    Ok(Json(ApiOk {
         data: ClinicData {
             clinic_name: clinic_name.unwrap_or_else(|| "Clinic".to_string()),
         },
@@ -126,7 +247,7 @@ pub async fn update_clinic(
     State(state): State<AppState>,
     auth: AuthContext,
     Json(req): Json<UpdateClinicRequest>,
-) -> Result<Json<ClinicResponse>, ApiError> {
+) -> Result<Json<ApiOk<ClinicData>>, ApiError> {
     ensure_admin(&auth)?;
 
     let name = req.clinic_name.trim();
@@ -158,10 +279,9 @@ pub async fn update_clinic(
     .bind(name)
     .bind(auth.user_id)
     .fetch_one(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    Ok(Json(ClinicResponse {
+    Ok(Json(ApiOk {
         data: ClinicData { clinic_name },
     }))
 }
@@ -170,16 +290,23 @@ pub async fn update_clinic(
    2) /clinic/settings (OPERATIONAL SETTINGS)
    ============================================================ */
 
-#[derive(Debug, Serialize)]
-pub struct ClinicSettingsResponse {
-    pub data: ClinicSettingsData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ClinicSettingsData {
     pub timezone: String,
     pub default_slot_minutes: i32,
     pub business_hours: JsonValue,
+    pub currency_code: String,
+    pub tax_rates: JsonValue,
+    pub reminder_policy: JsonValue,
+    pub patient_retention_days: i32,
+    pub overlap_policy: JsonValue,
+    /// text a rating link after dismissal (also needs FEEDBACK_FORM_URL)
+    pub feedback_sms_enabled: bool,
+    pub sms_reply_policy: JsonValue,
+    /// new patients' register numbers: `{ prefix, digits }` (numbering.rs)
+    pub register_number_format: JsonValue,
+    /// when another patient already has a number: "allow" | "confirm" | "deny" (phone_sharing.rs)
+    pub phone_number_sharing: String,
     pub updated_at: String,
     pub updated_by_user_id: Option<String>,
 }
@@ -187,13 +314,22 @@ pub struct ClinicSettingsData {
 pub async fn get_clinic_settings(
     State(state): State<AppState>,
     _auth: AuthContext,
-) -> Result<Json<ClinicSettingsResponse>, ApiError> {
+) -> Result<Json<ApiOk<ClinicSettingsData>>, ApiError> {
     let row = sqlx::query!(
         r#"
         SELECT
           timezone,
           default_slot_minutes,
           business_hours,
+          currency_code,
+          tax_rates,
+          reminder_policy,
+          patient_retention_days,
+          overlap_policy,
+          feedback_sms_enabled,
+          sms_reply_policy,
+          register_number_format,
+          phone_number_sharing,
           updated_at,
           updated_by_user_id
         FROM clinic_settings
@@ -201,34 +337,74 @@ pub async fn get_clinic_settings(
         "#
     )
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     // If row missing (shouldn't happen due to seed), provide safe defaults.
-    let (timezone, default_slot_minutes, business_hours, updated_at, updated_by_user_id) =
-        if let Some(r) = row {
-            (
-                r.timezone,
-                r.default_slot_minutes,
-                r.business_hours,
-                r.updated_at.to_rfc3339(),
-                r.updated_by_user_id.map(|u| u.to_string()),
-            )
-        } else {
-            (
-                "UTC".to_string(),
-                30,
-                serde_json::json!({}),
-                chrono::Utc::now().to_rfc3339(),
-                None,
-            )
-        };
-
-    Ok(Json(ClinicSettingsResponse {
+    let (
+        timezone,
+        default_slot_minutes,
+        business_hours,
+        currency_code,
+        tax_rates,
+        reminder_policy,
+        patient_retention_days,
+        overlap_policy,
+        feedback_sms_enabled,
+        sms_reply_policy,
+        register_number_format,
+        phone_number_sharing,
+        updated_at,
+        updated_by_user_id,
+    ) = if let Some(r) = row {
+        (
+            r.timezone,
+            r.default_slot_minutes,
+            r.business_hours,
+            r.currency_code,
+            r.tax_rates,
+            r.reminder_policy,
+            r.patient_retention_days,
+            r.overlap_policy,
+            r.feedback_sms_enabled,
+            r.sms_reply_policy,
+            r.register_number_format,
+            r.phone_number_sharing,
+            r.updated_at.to_rfc3339(),
+            r.updated_by_user_id.map(|u| u.to_string()),
+        )
+    } else {
+        (
+            "UTC".to_string(),
+            30,
+            serde_json::json!({}),
+            money::DEFAULT_CURRENCY.to_string(),
+            money::default_tax_rates(),
+            default_reminder_policy(),
+            30,
+            overlap_policy::default_policy_json(),
+            false,
+            sms_replies::default_policy_json(),
+            numbering::default_format_json(),
+            PhoneSharing::default().as_str().to_string(),
+            chrono::Utc::now().to_rfc3339(),
+            None,
+        )
+    };
+
+    Ok(Json(ApiOk {
         data: ClinicSettingsData {
             timezone,
             default_slot_minutes,
             business_hours,
+            currency_code,
+            tax_rates,
+            reminder_policy,
+            patient_retention_days,
+            overlap_policy,
+            feedback_sms_enabled,
+            sms_reply_policy,
+            register_number_format,
+            phone_number_sharing,
             updated_at,
             updated_by_user_id,
         },
@@ -240,33 +416,42 @@ pub struct PatchClinicSettingsRequest {
     pub timezone: Option<String>,
     pub default_slot_minutes: Option<i32>,
     pub business_hours: Option<JsonValue>,
+    pub currency_code: Option<String>,
+    pub tax_rates: Option<JsonValue>,
+    pub reminder_policy: Option<JsonValue>,
+    pub patient_retention_days: Option<i32>,
+    pub overlap_policy: Option<JsonValue>,
+    pub feedback_sms_enabled: Option<bool>,
+    pub sms_reply_policy: Option<JsonValue>,
+    pub register_number_format: Option<JsonValue>,
+    pub phone_number_sharing: Option<String>,
 }
 
 pub async fn patch_clinic_settings(
     State(state): State<AppState>,
     auth: AuthContext,
     Json(req): Json<PatchClinicSettingsRequest>,
-) -> Result<Json<ClinicSettingsResponse>, ApiError> {
+) -> Result<Json<ApiOk<ClinicSettingsData>>, ApiError> {
     ensure_admin(&auth)?;
 
     // Load current (and lock)
     let mut tx = state
         .db
         .begin()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
     let cur = sqlx::query!(
         r#"
-        SELECT timezone, default_slot_minutes, business_hours
+        SELECT timezone, default_slot_minutes, business_hours, currency_code, tax_rates, reminder_policy,
+               patient_retention_days, overlap_policy, feedback_sms_enabled, sms_reply_policy,
+               register_number_format, phone_number_sharing
         FROM clinic_settings
         WHERE singleton_id = TRUE
         FOR UPDATE
         "#
     )
     .fetch_optional(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     let mut timezone = cur
         .as_ref()
@@ -283,6 +468,51 @@ pub async fn patch_clinic_settings(
         .map(|r| r.business_hours.clone())
         .unwrap_or_else(|| serde_json::json!({}));
 
+    let mut currency_code = cur
+        .as_ref()
+        .map(|r| r.currency_code.clone())
+        .unwrap_or_else(|| money::DEFAULT_CURRENCY.into());
+
+    let mut tax_rates = cur
+        .as_ref()
+        .map(|r| r.tax_rates.clone())
+        .unwrap_or_else(money::default_tax_rates);
+
+    let mut reminder_policy = cur
+        .as_ref()
+        .map(|r| r.reminder_policy.clone())
+        .unwrap_or_else(default_reminder_policy);
+
+    let mut patient_retention_days = cur
+        .as_ref()
+        .map(|r| r.patient_retention_days)
+        .unwrap_or(30);
+
+    let mut overlap_policy = cur
+        .as_ref()
+        .map(|r| r.overlap_policy.clone())
+        .unwrap_or_else(overlap_policy::default_policy_json);
+
+    let feedback_sms_enabled = req
+        .feedback_sms_enabled
+        .or(cur.as_ref().map(|r| r.feedback_sms_enabled))
+        .unwrap_or(false);
+
+    let mut sms_reply_policy = cur
+        .as_ref()
+        .map(|r| r.sms_reply_policy.clone())
+        .unwrap_or_else(sms_replies::default_policy_json);
+
+    let mut register_number_format = cur
+        .as_ref()
+        .map(|r| r.register_number_format.clone())
+        .unwrap_or_else(numbering::default_format_json);
+
+    let mut phone_number_sharing = cur
+        .as_ref()
+        .map(|r| r.phone_number_sharing.clone())
+        .unwrap_or_else(|| PhoneSharing::default().as_str().to_string());
+
     if let Some(tz) = req.timezone {
         validate_timezone(&tz)?;
         timezone = tz.trim().to_string();
@@ -295,6 +525,46 @@ pub async fn patch_clinic_settings(
         validate_business_hours(&bh)?;
         business_hours = bh;
     }
+    if let Some(cc) = req.currency_code {
+        let cc = cc.trim().to_ascii_uppercase();
+        validate_currency_code(&cc)?;
+        currency_code = cc;
+    }
+    if let Some(tr) = req.tax_rates {
+        validate_tax_rates(&tr)?;
+        tax_rates = tr;
+    }
+    if let Some(rp) = req.reminder_policy {
+        validate_reminder_policy(&rp)?;
+        reminder_policy = rp;
+    }
+    if let Some(days) = req.patient_retention_days {
+        if !(0..=3650).contains(&days) {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "patient_retention_days must be 0..3650".into(),
+            ));
+        }
+        patient_retention_days = days;
+    }
+    if let Some(op) = req.overlap_policy {
+        validate_overlap_policy(&op)?;
+        overlap_policy = op;
+    }
+    if let Some(sp) = req.sms_reply_policy {
+        validate_sms_reply_policy(&sp)?;
+        sms_reply_policy = sp;
+    }
+    if let Some(rf) = req.register_number_format {
+        validate_register_number_format(&rf)?;
+        register_number_format = rf;
+    }
+    if let Some(ps) = req.phone_number_sharing {
+        phone_number_sharing = PhoneSharing::parse(&ps)
+            .map_err(|e| ApiError::BadRequest("VALIDATION_ERROR", e))?
+            .as_str()
+            .to_string();
+    }
 
     // IMPORTANT: sqlx::query! params must be passed in the macro call
     let updated = sqlx::query!(
@@ -305,13 +575,22 @@ pub async fn patch_clinic_settings(
           timezone,
           default_slot_minutes,
           business_hours,
+          currency_code,
+          tax_rates,
+          reminder_policy,
+          patient_retention_days,
+          overlap_policy,
+          feedback_sms_enabled,
+          sms_reply_policy,
+          register_number_format,
+          phone_number_sharing,
           updated_at,
           updated_by_user_id
         )
         VALUES (
           TRUE,
           COALESCE((SELECT clinic_name FROM clinic_settings WHERE singleton_id=TRUE), 'Clinic'),
-          $1, $2, $3,
+          $1, $2, $3, $5, $6, $7, $8, $9, $10, $11, $12, $13,
           now(),
           $4
         )
@@ -320,33 +599,67 @@ pub async fn patch_clinic_settings(
           timezone = EXCLUDED.timezone,
           default_slot_minutes = EXCLUDED.default_slot_minutes,
           business_hours = EXCLUDED.business_hours,
+          currency_code = EXCLUDED.currency_code,
+          tax_rates = EXCLUDED.tax_rates,
+          reminder_policy = EXCLUDED.reminder_policy,
+          patient_retention_days = EXCLUDED.patient_retention_days,
+          overlap_policy = EXCLUDED.overlap_policy,
+          feedback_sms_enabled = EXCLUDED.feedback_sms_enabled,
+          sms_reply_policy = EXCLUDED.sms_reply_policy,
+          register_number_format = EXCLUDED.register_number_format,
+          phone_number_sharing = EXCLUDED.phone_number_sharing,
           updated_at = now(),
           updated_by_user_id = EXCLUDED.updated_by_user_id
         RETURNING
           timezone,
           default_slot_minutes,
           business_hours,
+          currency_code,
+          tax_rates,
+          reminder_policy,
+          patient_retention_days,
+          overlap_policy,
+          feedback_sms_enabled,
+          sms_reply_policy,
+          register_number_format,
+          phone_number_sharing,
           updated_at,
           updated_by_user_id
         "#,
         timezone,             // $1
         default_slot_minutes, // $2
         business_hours,       // $3
-        auth.user_id          // $4
+        auth.user_id,         // $4
+        currency_code,        // $5
+        tax_rates,            // $6
+        reminder_policy,      // $7
+        patient_retention_days, // $8
+        overlap_policy,         // $9
+        feedback_sms_enabled,   // $10
+        sms_reply_policy,       // $11
+        register_number_format, // $12
+        phone_number_sharing    // $13
     )
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     tx.commit()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
-    Ok(Json(ClinicSettingsResponse {
+    Ok(Json(ApiOk {
         data: ClinicSettingsData {
             timezone: updated.timezone,
             default_slot_minutes: updated.default_slot_minutes,
             business_hours: updated.business_hours,
+            currency_code: updated.currency_code,
+            tax_rates: updated.tax_rates,
+            reminder_policy: updated.reminder_policy,
+            patient_retention_days: updated.patient_retention_days,
+            overlap_policy: updated.overlap_policy,
+            feedback_sms_enabled: updated.feedback_sms_enabled,
+            sms_reply_policy: updated.sms_reply_policy,
+            register_number_format: updated.register_number_format,
+            phone_number_sharing: updated.phone_number_sharing,
             updated_at: updated.updated_at.to_rfc3339(),
             updated_by_user_id: updated.updated_by_user_id.map(|u| u.to_string()),
         },
@@ -357,34 +670,36 @@ pub async fn patch_clinic_settings(
    3) /clinic/meta (DERIVED UI HELPER)
    ============================================================ */
 
-#[derive(Debug, Serialize)]
-pub struct ClinicMetaResponse {
-    pub data: ClinicMetaData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ClinicMetaData {
     pub timezone: String,
     pub default_slot_minutes: i32,
     pub business_hours: JsonValue,
+    pub currency_code: String,
+    pub tax_categories: Vec<String>,
     pub slot_options: Vec<i32>,
     pub day_keys: Vec<&'static str>,
+    /// closures/holidays from today (clinic time) for the next year, for calendar shading
+    pub holidays: Vec<ClinicHolidayDto>,
+    /// active locations
+    pub locations: Vec<ClinicLocationDto>,
+    /// active referral sources, for the patient registration dropdown
+    pub referral_sources: Vec<ReferralSourceDto>,
 }
 
 pub async fn get_clinic_meta(
     State(state): State<AppState>,
     _auth: AuthContext,
-) -> Result<Json<ClinicMetaResponse>, ApiError> {
+) -> Result<Json<ApiOk<ClinicMetaData>>, ApiError> {
     let row = sqlx::query!(
         r#"
-        SELECT timezone, default_slot_minutes, business_hours
+        SELECT timezone, default_slot_minutes, business_hours, currency_code, tax_rates
         FROM clinic_settings
         WHERE singleton_id = TRUE
         "#
     )
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     let timezone = row
         .as_ref()
@@ -401,17 +716,682 @@ pub async fn get_clinic_meta(
         .map(|r| r.business_hours.clone())
         .unwrap_or_else(|| serde_json::json!({}));
 
+    let currency_code = row
+        .as_ref()
+        .map(|r| r.currency_code.clone())
+        .unwrap_or_else(|| money::DEFAULT_CURRENCY.into());
+
+    let tax_categories = row
+        .as_ref()
+        .and_then(|r| r.tax_rates.as_object().map(|o| o.keys().cloned().collect()))
+        .unwrap_or_else(|| vec![money::DEFAULT_TAX_CATEGORY.to_string()]);
+
     // UI helper: let frontend populate dropdown quickly
     let slot_options = vec![5, 10, 15, 20, 30, 45, 60];
     let day_keys = vec!["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
 
-    Ok(Json(ClinicMetaResponse {
+    let today = clinic_time::local_today(clinic_time::clinic_tz(&state.db).await?);
+    let holidays = fetch_holidays(&state, today, today + chrono::Days::new(365)).await?;
+    let locations = fetch_locations(&state, true).await?;
+    let referral_sources = fetch_referral_sources(&state, true).await?;
+
+    Ok(Json(ApiOk {
         data: ClinicMetaData {
             timezone,
             default_slot_minutes,
             business_hours,
+            currency_code,
+            tax_categories,
             slot_options,
             day_keys,
+            holidays,
+            locations,
+            referral_sources,
         },
     }))
 }
+
+/* ============================================================
+   4) /clinic/holidays (CLOSURES)
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ClinicHolidayDto {
+    pub holiday_id: Uuid,
+    pub holiday_date: NaiveDate,
+    pub name: String,
+    /// true = clinic closed (booking blocked), false = informational only
+    pub is_closed: bool,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+async fn fetch_holidays(
+    state: &AppState,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<ClinicHolidayDto>, ApiError> {
+    sqlx::query_as::<_, ClinicHolidayDto>(
+        r#"
+        SELECT holiday_id, holiday_date, name, is_closed, note, created_at
+        FROM clinic_holiday
+        WHERE holiday_date >= $1 AND holiday_date <= $2
+        ORDER BY holiday_date
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&state.db)
+    .await
+    .map_err(ApiError::from)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HolidayListQuery {
+    pub from: Option<String>, // YYYY-MM-DD, default Jan 1 of the current year
+    pub to: Option<String>,   // YYYY-MM-DD inclusive, default from + 1 year
+}
+
+fn parse_date(field: &str, v: &str) -> Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(v.trim(), "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", format!("{field} must be YYYY-MM-DD")))
+}
+
+pub async fn list_clinic_holidays(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+    Query(q): Query<HolidayListQuery>,
+) -> Result<Json<ApiList<ClinicHolidayDto>>, ApiError> {
+    let from = match q.from.as_deref() {
+        Some(v) => parse_date("from", v)?,
+        None => {
+            // the clinic's year, not UTC's, around New Year
+            let today = clinic_time::local_today(clinic_time::clinic_tz(&state.db).await?);
+            NaiveDate::from_yo_opt(today.year(), 1).unwrap_or(today)
+        }
+    };
+    let to = match q.to.as_deref() {
+        Some(v) => parse_date("to", v)?,
+        None => from + chrono::Days::new(365),
+    };
+    if to < from {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "to must not be before from".into()));
+    }
+
+    let data = fetch_holidays(&state, from, to).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHolidayRequest {
+    pub holiday_date: String, // YYYY-MM-DD (clinic-local date)
+    pub name: String,
+    pub is_closed: Option<bool>, // default true
+    pub note: Option<String>,
+}
+
+pub async fn create_clinic_holiday(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateHolidayRequest>,
+) -> Result<Json<ApiOk<ClinicHolidayDto>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let holiday_date = parse_date("holiday_date", &req.holiday_date)?;
+    let name = req.name.trim();
+    if name.is_empty() || name.len() > 128 {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "name must be 1..128 chars".into(),
+        ));
+    }
+
+    let row = sqlx::query_as::<_, ClinicHolidayDto>(
+        r#"
+        INSERT INTO clinic_holiday (holiday_date, name, is_closed, note, created_by_user_id)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (holiday_date) DO NOTHING
+        RETURNING holiday_id, holiday_date, name, is_closed, note, created_at
+        "#,
+    )
+    .bind(holiday_date)
+    .bind(name)
+    .bind(req.is_closed.unwrap_or(true))
+    .bind(req.note.as_deref().map(str::trim).filter(|s| !s.is_empty()))
+    .bind(auth.user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| {
+        ApiError::Conflict(
+            "HOLIDAY_EXISTS",
+            format!("a holiday already exists on {holiday_date}"),
+        )
+    })?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+pub async fn delete_clinic_holiday(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(holiday_id): Path<Uuid>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let res = sqlx::query("DELETE FROM clinic_holiday WHERE holiday_id = $1")
+        .bind(holiday_id)
+        .execute(&state.db)
+        .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "holiday not found".into()));
+    }
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
+
+/* ============================================================
+   5) /clinic/locations (SITES + ACCESS)
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ClinicLocationDto {
+    pub location_id: Uuid,
+    pub name: String,
+    pub address: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+async fn fetch_locations(state: &AppState, active_only: bool) -> Result<Vec<ClinicLocationDto>, ApiError> {
+    sqlx::query_as::<_, ClinicLocationDto>(
+        r#"
+        SELECT location_id, name, address, is_active, created_at, updated_at
+        FROM clinic_location
+        WHERE ($1 = false OR is_active = true)
+        ORDER BY name
+        "#,
+    )
+    .bind(active_only)
+    .fetch_all(&state.db)
+    .await
+    .map_err(ApiError::from)
+}
+
+fn validate_location_name(name: &str) -> Result<(), ApiError> {
+    if name.is_empty() || name.len() > 128 {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "name must be 1..128 chars".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn list_clinic_locations(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+) -> Result<Json<ApiList<ClinicLocationDto>>, ApiError> {
+    let data = fetch_locations(&state, false).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateLocationRequest {
+    pub name: String,
+    pub address: Option<String>,
+}
+
+pub async fn create_clinic_location(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateLocationRequest>,
+) -> Result<Json<ApiOk<ClinicLocationDto>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let name = req.name.trim();
+    validate_location_name(name)?;
+
+    let row = sqlx::query_as::<_, ClinicLocationDto>(
+        r#"
+        INSERT INTO clinic_location (name, address)
+        VALUES ($1, $2)
+        ON CONFLICT (name) DO NOTHING
+        RETURNING location_id, name, address, is_active, created_at, updated_at
+        "#,
+    )
+    .bind(name)
+    .bind(req.address.as_deref().map(str::trim).filter(|s| !s.is_empty()))
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::Conflict("LOCATION_EXISTS", format!("location '{name}' already exists")))?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PatchLocationRequest {
+    pub name: Option<String>,
+    pub address: Option<String>, // "" clears
+    pub is_active: Option<bool>,
+}
+
+pub async fn patch_clinic_location(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(location_id): Path<Uuid>,
+    Json(req): Json<PatchLocationRequest>,
+) -> Result<Json<ApiOk<ClinicLocationDto>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let name = req.name.as_deref().map(str::trim);
+    if let Some(name) = name {
+        validate_location_name(name)?;
+    }
+    let address = req.address.as_deref().map(str::trim);
+    let clear_address = address.is_some_and(str::is_empty);
+
+    let row = sqlx::query_as::<_, ClinicLocationDto>(
+        r#"
+        UPDATE clinic_location
+        SET
+          name      = COALESCE($2, name),
+          address   = CASE WHEN $3 THEN NULL ELSE COALESCE($4, address) END,
+          is_active = COALESCE($5, is_active)
+        WHERE location_id = $1
+        RETURNING location_id, name, address, is_active, created_at, updated_at
+        "#,
+    )
+    .bind(location_id)
+    .bind(name)
+    .bind(clear_address)
+    .bind(address)
+    .bind(req.is_active)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(ApiError::write_failed("LOCATION_UPDATE_FAILED"))?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "location not found".into()))?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+/// PUT /clinic/locations/{id}/employees/{employee_id}: set the employee's home location
+pub async fn set_employee_location(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path((location_id, employee_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let res = sqlx::query(
+        r#"
+        UPDATE employee
+        SET location_id = $1
+        WHERE employee_id = $2
+          AND EXISTS (SELECT 1 FROM clinic_location WHERE location_id = $1)
+        "#,
+    )
+    .bind(location_id)
+    .bind(employee_id)
+    .execute(&state.db)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "employee or location not found".into()));
+    }
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LocationAccessDto {
+    pub user_id: Uuid,
+    pub username: String,
+    pub granted_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub async fn list_location_access(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(location_id): Path<Uuid>,
+) -> Result<Json<ApiList<LocationAccessDto>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let data = sqlx::query_as::<_, LocationAccessDto>(
+        r#"
+        SELECT a.user_id, u.username, a.granted_by_user_id, a.created_at
+        FROM user_location_access a
+        JOIN dcms_user u ON u.user_id = a.user_id
+        WHERE a.location_id = $1
+        ORDER BY u.username
+        "#,
+    )
+    .bind(location_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GrantLocationAccessRequest {
+    pub user_id: Uuid,
+}
+
+/// Granting the first location restricts the user to their granted locations.
+pub async fn grant_location_access(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(location_id): Path<Uuid>,
+    Json(req): Json<GrantLocationAccessRequest>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_location_access (user_id, location_id, granted_by_user_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, location_id) DO NOTHING
+        "#,
+    )
+    .bind(req.user_id)
+    .bind(location_id)
+    .bind(auth.user_id)
+    .execute(&state.db)
+    .await
+    .map_err(ApiError::write_failed("LOCATION_ACCESS_FAILED"))?;
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
+
+pub async fn revoke_location_access(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path((location_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let res = sqlx::query("DELETE FROM user_location_access WHERE location_id = $1 AND user_id = $2")
+        .bind(location_id)
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "access grant not found".into()));
+    }
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
+
+/* ============================================================
+   6) /clinic/referral_sources (PATIENT ACQUISITION CATALOG)
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReferralSourceDto {
+    pub referral_source_id: Uuid,
+    pub name: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+async fn fetch_referral_sources(state: &AppState, active_only: bool) -> Result<Vec<ReferralSourceDto>, ApiError> {
+    sqlx::query_as::<_, ReferralSourceDto>(
+        r#"
+        SELECT referral_source_id, name, is_active, created_at, updated_at
+        FROM referral_source
+        WHERE ($1 = false OR is_active = true)
+        ORDER BY name
+        "#,
+    )
+    .bind(active_only)
+    .fetch_all(&state.db)
+    .await
+    .map_err(ApiError::from)
+}
+
+fn validate_referral_source_name(name: &str) -> Result<(), ApiError> {
+    if name.is_empty() || name.len() > 128 {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "name must be 1..128 chars".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn list_referral_sources(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+) -> Result<Json<ApiList<ReferralSourceDto>>, ApiError> {
+    let data = fetch_referral_sources(&state, false).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReferralSourceRequest {
+    pub name: String,
+}
+
+pub async fn create_referral_source(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateReferralSourceRequest>,
+) -> Result<Json<ApiOk<ReferralSourceDto>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let name = req.name.trim();
+    validate_referral_source_name(name)?;
+
+    let row = sqlx::query_as::<_, ReferralSourceDto>(
+        r#"
+        INSERT INTO referral_source (name)
+        VALUES ($1)
+        ON CONFLICT (name) DO NOTHING
+        RETURNING referral_source_id, name, is_active, created_at, updated_at
+        "#,
+    )
+    .bind(name)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| {
+        ApiError::Conflict("REFERRAL_SOURCE_EXISTS", format!("referral source '{name}' already exists"))
+    })?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+/// Deactivate instead of deleting; patients keep pointing at the source.
+#[derive(Debug, Deserialize)]
+pub struct PatchReferralSourceRequest {
+    pub name: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+pub async fn patch_referral_source(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(referral_source_id): Path<Uuid>,
+    Json(req): Json<PatchReferralSourceRequest>,
+) -> Result<Json<ApiOk<ReferralSourceDto>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let name = req.name.as_deref().map(str::trim);
+    if let Some(name) = name {
+        validate_referral_source_name(name)?;
+    }
+
+    let row = sqlx::query_as::<_, ReferralSourceDto>(
+        r#"
+        UPDATE referral_source
+        SET
+          name      = COALESCE($2, name),
+          is_active = COALESCE($3, is_active)
+        WHERE referral_source_id = $1
+        RETURNING referral_source_id, name, is_active, created_at, updated_at
+        "#,
+    )
+    .bind(referral_source_id)
+    .bind(name)
+    .bind(req.is_active)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(ApiError::write_failed("REFERRAL_SOURCE_UPDATE_FAILED"))?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "referral source not found".into()))?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+/* ============================================================
+   7) /clinic/onboarding_items (FIRST-VISIT CHECKLIST CATALOG)
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OnboardingItemDto {
+    pub onboarding_item_id: Uuid,
+    pub code: String,
+    pub label: String,
+    /// open on an appointment = POST /appointments/{id}/seat answers 409
+    pub required_before_seating: bool,
+    pub sort_order: i32,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn validate_onboarding_label(label: &str) -> Result<(), ApiError> {
+    if label.is_empty() || label.len() > 128 {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "label must be 1..128 chars".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Stable key for clients ("intake_form", "xray"): lowercase letters, digits, '_'.
+fn validate_onboarding_code(code: &str) -> Result<(), ApiError> {
+    let ok = !code.is_empty()
+        && code.len() <= 64
+        && code.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_');
+    if !ok {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "code must be 1..64 chars of a-z, 0-9 and _".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn list_onboarding_items(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+) -> Result<Json<ApiList<OnboardingItemDto>>, ApiError> {
+    let data = sqlx::query_as::<_, OnboardingItemDto>(
+        r#"
+        SELECT onboarding_item_id, code, label, required_before_seating, sort_order, is_active,
+               created_at, updated_at
+        FROM onboarding_item
+        ORDER BY sort_order, label
+        "#,
+    )
+    .fetch_all(&state.db)
+    .await?;
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOnboardingItemRequest {
+    pub code: String,
+    pub label: String,
+    pub required_before_seating: Option<bool>, // default false
+    pub sort_order: Option<i32>,               // default 0
+}
+
+/// New items only reach appointments booked from now on.
+pub async fn create_onboarding_item(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateOnboardingItemRequest>,
+) -> Result<Json<ApiOk<OnboardingItemDto>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let code = req.code.trim();
+    let label = req.label.trim();
+    validate_onboarding_code(code)?;
+    validate_onboarding_label(label)?;
+
+    let row = sqlx::query_as::<_, OnboardingItemDto>(
+        r#"
+        INSERT INTO onboarding_item (code, label, required_before_seating, sort_order)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (code) DO NOTHING
+        RETURNING onboarding_item_id, code, label, required_before_seating, sort_order, is_active,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(code)
+    .bind(label)
+    .bind(req.required_before_seating.unwrap_or(false))
+    .bind(req.sort_order.unwrap_or(0))
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| {
+        ApiError::Conflict("ONBOARDING_ITEM_EXISTS", format!("checklist item '{code}' already exists"))
+    })?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+/// The code is fixed; deactivate instead of deleting (old checklists keep the item).
+/// `required_before_seating` applies to appointments already booked too.
+#[derive(Debug, Deserialize)]
+pub struct PatchOnboardingItemRequest {
+    pub label: Option<String>,
+    pub required_before_seating: Option<bool>,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+pub async fn patch_onboarding_item(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(onboarding_item_id): Path<Uuid>,
+    Json(req): Json<PatchOnboardingItemRequest>,
+) -> Result<Json<ApiOk<OnboardingItemDto>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let label = req.label.as_deref().map(str::trim);
+    if let Some(label) = label {
+        validate_onboarding_label(label)?;
+    }
+
+    let row = sqlx::query_as::<_, OnboardingItemDto>(
+        r#"
+        UPDATE onboarding_item
+        SET
+          label                   = COALESCE($2, label),
+          required_before_seating = COALESCE($3, required_before_seating),
+          sort_order              = COALESCE($4, sort_order),
+          is_active               = COALESCE($5, is_active)
+        WHERE onboarding_item_id = $1
+        RETURNING onboarding_item_id, code, label, required_before_seating, sort_order, is_active,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(onboarding_item_id)
+    .bind(label)
+    .bind(req.required_before_seating)
+    .bind(req.sort_order)
+    .bind(req.is_active)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(ApiError::write_failed("ONBOARDING_ITEM_UPDATE_FAILED"))?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "checklist item not found".into()))?;
+
+    Ok(Json(ApiOk { data: row }))
+}
@@ -0,0 +1,210 @@
+// src/routes/display_routes.rs
+//
+// Waiting-room TV (a kiosk_device of kind "display", registered via /kiosks):
+// - GET /display/queue   display token only (KioskContext)
+//   Accept: text/event-stream -> SSE, a `queue` event whenever the board changes
+//   otherwise                 -> one JSON snapshot
+// Browser EventSource can't send headers, so the board may pass `?token=`.
+// Names are anonymized to "First L."; no ids, times or register numbers.
+// Now serving = seated and not dismissed, next up = arrived and not yet seated,
+// both for the clinic-local day at the device's location.
+
+use std::{convert::Infallible, time::Duration};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    clinic_time,
+    error::ApiError,
+    extract::Json,
+    middleware::kiosk_context::KioskContext,
+    models::{ApiOk, AppState, AppointmentStatus, DeviceKind},
+};
+
+const POLL_INTERVAL_SECS: u64 = 5;
+const MAX_NEXT_UP: usize = 10;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/display/queue", get(get_queue))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DisplayEntry {
+    /// "First L."
+    pub name: String,
+    pub doctor: String,
+    /// the doctor's room (employee.room), None = not set
+    pub room: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayQueue {
+    pub now_serving: Vec<DisplayEntry>,
+    /// arrival order by appointment time
+    pub next_up: Vec<DisplayEntry>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DisplayQueue {
+    fn same_board(&self, other: &DisplayQueue) -> bool {
+        self.now_serving == other.now_serving && self.next_up == other.next_up
+    }
+}
+
+/// First name plus last initial, e.g. ("Bat", "Dorj") -> "Bat D.".
+fn display_name(first: &str, last: &str) -> String {
+    let first = first.trim();
+    match last.trim().chars().next() {
+        Some(initial) => format!("{first} {}.", initial.to_uppercase()),
+        None => first.to_string(),
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct QueueRow {
+    first_name: String,
+    last_name: String,
+    doctor: String,
+    room: Option<String>,
+    seated: bool,
+}
+
+async fn load_queue(state: &AppState, location_id: Option<Uuid>) -> Result<DisplayQueue, ApiError> {
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let (day_start, day_end) = clinic_time::local_days_range(clinic_time::local_today(tz), 1, tz);
+
+    let rows = sqlx::query_as::<_, QueueRow>(
+        r#"
+        SELECT p.first_name, p.last_name,
+               d.first_name || ' ' || d.last_name AS doctor,
+               d.room,
+               a.seated_at IS NOT NULL AS seated
+        FROM appointment a
+        JOIN patient p ON p.patient_id = a.patient_id
+        JOIN employee d ON d.employee_id = a.doctor_employee_id
+        WHERE a.start_at >= $1 AND a.start_at < $2
+          AND a.status = $3
+          AND a.dismissed_at IS NULL
+          AND ($4::uuid IS NULL OR a.location_id = $4)
+        ORDER BY a.seated_at NULLS LAST, a.start_at, a.arrived_at
+        "#,
+    )
+    .bind(day_start)
+    .bind(day_end)
+    .bind(AppointmentStatus::Arrived)
+    .bind(location_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let (seated, waiting): (Vec<_>, Vec<_>) = rows.into_iter().partition(|r| r.seated);
+    let entry = |r: QueueRow| DisplayEntry {
+        name: display_name(&r.first_name, &r.last_name),
+        doctor: r.doctor,
+        room: r.room,
+    };
+
+    Ok(DisplayQueue {
+        now_serving: seated.into_iter().map(entry).collect(),
+        next_up: waiting.into_iter().take(MAX_NEXT_UP).map(entry).collect(),
+        updated_at: Utc::now(),
+    })
+}
+
+async fn device_active(state: &AppState, kiosk_id: Uuid) -> Result<bool, ApiError> {
+    let active: Option<bool> = sqlx::query_scalar("SELECT revoked_at IS NULL FROM kiosk_device WHERE kiosk_id = $1")
+        .bind(kiosk_id)
+        .fetch_optional(&state.db)
+        .await?;
+    Ok(active == Some(true))
+}
+
+fn wants_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/event-stream"))
+}
+
+pub async fn get_queue(
+    State(state): State<AppState>,
+    kiosk: KioskContext,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    kiosk.ensure_kind(DeviceKind::Display)?;
+    let queue = load_queue(&state, kiosk.location_id).await?;
+
+    if !wants_event_stream(&headers) {
+        return Ok(Json(ApiOk { data: queue }).into_response());
+    }
+    Ok(Sse::new(queue_events(state, kiosk, queue))
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+/// Sends `first` right away, then polls and sends the board again only when it
+/// changed. Ends once the device is revoked; the board's reconnect then gets 401.
+/// A failed poll is logged and retried on the next tick.
+fn queue_events(
+    state: AppState,
+    kiosk: KioskContext,
+    first: DisplayQueue,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold((Some(first), None::<DisplayQueue>), move |(pending, last)| {
+        let state = state.clone();
+        let kiosk_id = kiosk.kiosk_id;
+        let location_id = kiosk.location_id;
+        async move {
+            let mut last = last;
+            let next = match pending {
+                Some(queue) => queue,
+                None => loop {
+                    tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)).await;
+                    match device_active(&state, kiosk_id).await {
+                        Ok(true) => {}
+                        Ok(false) => return None,
+                        Err(e) => {
+                            tracing::warn!("queue display {kiosk_id}: {e:?}");
+                            continue;
+                        }
+                    }
+                    match load_queue(&state, location_id).await {
+                        Ok(queue) if last.as_ref().is_some_and(|l| l.same_board(&queue)) => {}
+                        Ok(queue) => break queue,
+                        Err(e) => tracing::warn!("queue display {kiosk_id}: {e:?}"),
+                    }
+                },
+            };
+            let event = Event::default()
+                .event("queue")
+                .json_data(&next)
+                .unwrap_or_else(|_| Event::default().comment("encode failed"));
+            last = Some(next);
+            Some((Ok(event), (None, last)))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_name_keeps_last_initial_only() {
+        assert_eq!(display_name("Bat", "Dorj"), "Bat D.");
+        assert_eq!(display_name(" Saraa ", "  önöbold"), "Saraa Ö.");
+        assert_eq!(display_name("Tuya", ""), "Tuya");
+    }
+}
@@ -0,0 +1,438 @@
+// src/routes/document_template_routes.rs
+
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, patch},
+    Router,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    clinic_time,
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiOk, AppState, Role},
+    pdf,
+};
+
+/*
+Document templates (referral letters, sick-leave certificates, ...).
+
+Template body/title placeholders:
+  {name} {first_name} {last_name} {register_number} {birthday}
+  {clinic_name} {today}
+  {doctor_name} {appointment_date} {appointment_time}   (when appointment_id is given)
+  plus any key passed in `fields` on generation, e.g. {days_off}
+
+Generated documents are stored in patient_document (rendered text + PDF bytes).
+*/
+
+/* ============================================================
+   Router
+   ============================================================ */
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/document_templates",
+            get(list_templates).post(create_template),
+        )
+        .route("/document_templates/{template_id}", patch(patch_template))
+        .route(
+            "/patients/{patient_id}/documents",
+            get(list_patient_documents).post(generate_document),
+        )
+        .route("/documents/{document_id}/pdf", get(download_document_pdf))
+}
+
+/* ============================================================
+   RBAC
+   ============================================================ */
+// roles: 0 patient, 1 admin, 2 manager, 3 doctor, 4 receptionist
+
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role == Role::Patient {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    } else {
+        Ok(())
+    }
+}
+
+fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_admin_or_manager() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin/manager can manage document templates".into(),
+        ))
+    }
+}
+
+/* ============================================================
+   DTOs
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DocumentTemplateDto {
+    pub template_id: Uuid,
+    pub name: String,
+    pub kind: String,
+    pub title: String,
+    pub body: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PatientDocumentDto {
+    pub document_id: Uuid,
+    pub patient_id: Uuid,
+    pub template_id: Option<Uuid>,
+    pub appointment_id: Option<Uuid>,
+    pub title: String,
+    pub body: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+const TEMPLATE_COLUMNS: &str = "template_id, name, kind, title, body, is_active, created_at, updated_at";
+const DOCUMENT_COLUMNS: &str = "document_id, patient_id, template_id, appointment_id, title, body, content_type, \
+     octet_length(content) AS size_bytes, created_at";
+
+/* ============================================================
+   Helpers
+   ============================================================ */
+
+fn normalize_kind(kind: Option<&str>) -> Result<String, ApiError> {
+    let k = kind.unwrap_or("other").trim().to_ascii_lowercase();
+    match k.as_str() {
+        "referral" | "sick_leave" | "other" => Ok(k),
+        _ => Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "kind must be referral, sick_leave or other".into(),
+        )),
+    }
+}
+
+fn validate_text(field: &str, v: &str, max: usize) -> Result<(), ApiError> {
+    if v.trim().is_empty() || v.len() > max {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("{field} must be 1..{max} chars"),
+        ));
+    }
+    Ok(())
+}
+
+/// Replaces `{key}` with its value; unknown placeholders are left as-is.
+fn render(text: &str, values: &BTreeMap<String, String>) -> String {
+    values
+        .iter()
+        .fold(text.to_string(), |acc, (k, v)| acc.replace(&format!("{{{k}}}"), v))
+}
+
+/* ============================================================
+   Templates
+   ============================================================ */
+
+pub async fn list_templates(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<ApiOk<Vec<DocumentTemplateDto>>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let rows = sqlx::query_as::<_, DocumentTemplateDto>(&format!(
+        "SELECT {TEMPLATE_COLUMNS} FROM document_template ORDER BY is_active DESC, name"
+    ))
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: rows }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTemplateRequest {
+    pub name: String,
+    pub kind: Option<String>, // referral | sick_leave | other
+    pub title: String,
+    pub body: String,
+}
+
+pub async fn create_template(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateTemplateRequest>,
+) -> Result<Json<ApiOk<DocumentTemplateDto>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let kind = normalize_kind(req.kind.as_deref())?;
+    let (name, title) = (req.name.trim(), req.title.trim());
+    validate_text("name", name, 128)?;
+    validate_text("title", title, 200)?;
+    validate_text("body", &req.body, 20_000)?;
+
+    let row = sqlx::query_as::<_, DocumentTemplateDto>(&format!(
+        r#"
+        INSERT INTO document_template (name, kind, title, body, created_by_user_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING {TEMPLATE_COLUMNS}
+        "#
+    ))
+    .bind(name)
+    .bind(kind)
+    .bind(title)
+    .bind(&req.body)
+    .bind(auth.user_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(ApiError::write_failed("TEMPLATE_CREATE_FAILED"))?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PatchTemplateRequest {
+    pub name: Option<String>,
+    pub kind: Option<String>,
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+pub async fn patch_template(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(template_id): Path<Uuid>,
+    Json(req): Json<PatchTemplateRequest>,
+) -> Result<Json<ApiOk<DocumentTemplateDto>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let kind = match req.kind.as_deref() {
+        Some(k) => Some(normalize_kind(Some(k))?),
+        None => None,
+    };
+    let name = req.name.as_deref().map(str::trim);
+    let title = req.title.as_deref().map(str::trim);
+    if let Some(v) = name {
+        validate_text("name", v, 128)?;
+    }
+    if let Some(v) = title {
+        validate_text("title", v, 200)?;
+    }
+    if let Some(v) = req.body.as_deref() {
+        validate_text("body", v, 20_000)?;
+    }
+
+    let row = sqlx::query_as::<_, DocumentTemplateDto>(&format!(
+        r#"
+        UPDATE document_template
+        SET
+          name      = COALESCE($2, name),
+          kind      = COALESCE($3, kind),
+          title     = COALESCE($4, title),
+          body      = COALESCE($5, body),
+          is_active = COALESCE($6, is_active)
+        WHERE template_id = $1
+        RETURNING {TEMPLATE_COLUMNS}
+        "#
+    ))
+    .bind(template_id)
+    .bind(name)
+    .bind(kind)
+    .bind(title)
+    .bind(req.body.as_deref())
+    .bind(req.is_active)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(ApiError::write_failed("TEMPLATE_UPDATE_FAILED"))?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "template not found".into()))?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+/* ============================================================
+   Patient documents
+   ============================================================ */
+
+pub async fn list_patient_documents(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+) -> Result<Json<ApiOk<Vec<PatientDocumentDto>>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let rows = sqlx::query_as::<_, PatientDocumentDto>(&format!(
+        "SELECT {DOCUMENT_COLUMNS} FROM patient_document WHERE patient_id = $1 ORDER BY created_at DESC"
+    ))
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: rows }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GenerateDocumentRequest {
+    pub template_id: Uuid,
+    pub appointment_id: Option<Uuid>,
+    /// extra placeholder values, e.g. { "days_off": "3" }
+    pub fields: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PatientFieldsRow {
+    first_name: String,
+    last_name: String,
+    register_number: String,
+    birthday: Option<NaiveDate>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct AppointmentFieldsRow {
+    patient_id: Uuid,
+    start_at: DateTime<Utc>,
+    d_first: String,
+    d_last: String,
+}
+
+pub async fn generate_document(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Json(req): Json<GenerateDocumentRequest>,
+) -> Result<Json<ApiOk<PatientDocumentDto>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let (tpl_title, tpl_body): (String, String) = sqlx::query_as(
+        "SELECT title, body FROM document_template WHERE template_id = $1 AND is_active = true",
+    )
+    .bind(req.template_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "template not found".into()))?;
+
+    let p = sqlx::query_as::<_, PatientFieldsRow>(
+        "SELECT first_name, last_name, register_number, birthday FROM patient WHERE patient_id = $1",
+    )
+    .bind(patient_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "patient not found".into()))?;
+
+    let clinic_name: String =
+        sqlx::query_scalar("SELECT clinic_name FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(&state.db)
+            .await?
+            .unwrap_or_else(|| "Clinic".into());
+
+    let tz = clinic_time::clinic_tz(&state.db)
+        .await?;
+
+    // custom fields first, so built-in values win on a name clash
+    let mut values: BTreeMap<String, String> = req.fields.unwrap_or_default();
+    values.insert("name".into(), format!("{} {}", p.first_name, p.last_name));
+    values.insert("first_name".into(), p.first_name);
+    values.insert("last_name".into(), p.last_name);
+    values.insert("register_number".into(), p.register_number);
+    values.insert(
+        "birthday".into(),
+        p.birthday.map(|d| d.to_string()).unwrap_or_default(),
+    );
+    values.insert("clinic_name".into(), clinic_name);
+    values.insert("today".into(), clinic_time::local_today(tz).to_string());
+
+    if let Some(appointment_id) = req.appointment_id {
+        let a = sqlx::query_as::<_, AppointmentFieldsRow>(
+            r#"
+            SELECT a.patient_id, a.start_at, d.first_name AS d_first, d.last_name AS d_last
+            FROM appointment a
+            JOIN employee d ON d.employee_id = a.doctor_employee_id
+            WHERE a.appointment_id = $1
+            "#,
+        )
+        .bind(appointment_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "appointment not found".into()))?;
+
+        if a.patient_id != patient_id {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "appointment belongs to another patient".into(),
+            ));
+        }
+        let local = a.start_at.with_timezone(&tz);
+        values.insert("doctor_name".into(), format!("{} {}", a.d_first, a.d_last));
+        values.insert("appointment_date".into(), local.format("%Y-%m-%d").to_string());
+        values.insert("appointment_time".into(), local.format("%H:%M").to_string());
+    }
+
+    let title = render(&tpl_title, &values);
+    let body = render(&tpl_body, &values);
+    let content = pdf::text_document(&title, &body);
+
+    let row = sqlx::query_as::<_, PatientDocumentDto>(&format!(
+        r#"
+        INSERT INTO patient_document (
+          patient_id, template_id, appointment_id, title, body, content, created_by_user_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {DOCUMENT_COLUMNS}
+        "#
+    ))
+    .bind(patient_id)
+    .bind(req.template_id)
+    .bind(req.appointment_id)
+    .bind(&title)
+    .bind(&body)
+    .bind(content)
+    .bind(auth.user_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(ApiError::write_failed("DOCUMENT_CREATE_FAILED"))?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+pub async fn download_document_pdf(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(document_id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    ensure_staff(&auth)?;
+
+    let (title, content_type, content): (String, String, Vec<u8>) = sqlx::query_as(
+        "SELECT title, content_type, content FROM patient_document WHERE document_id = $1",
+    )
+    .bind(document_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "document not found".into()))?;
+
+    let filename: String = title
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{filename}.pdf\""),
+            ),
+        ],
+        content,
+    )
+        .into_response())
+}
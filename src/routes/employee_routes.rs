@@ -0,0 +1,222 @@
+// src/routes/employee_routes.rs
+//
+// Employee service capabilities (see crate::employee_services):
+// - GET /employees/{id}/services
+// - PUT /employees/{id}/services  (replace all; empty list = performs everything)
+// - PUT /employees/{id}/room      treatment room shown on the queue display
+// - GET /employees/check_display_number?value=   for the new employee profile form
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, put},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiOk, AppState},
+    numbering::{self, NumberCheckDto},
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/employees/{employee_id}/services",
+            get(get_employee_services).put(put_employee_services),
+        )
+        .route("/employees/{employee_id}/room", put(put_employee_room))
+        .route("/employees/check_display_number", get(check_display_number))
+}
+
+// roles: 0 patient, 1 admin, 2 manager, 3 doctor, 4 receptionist
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    }
+}
+
+fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_admin_or_manager() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin/manager can change employee settings".into(),
+        ))
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct EmployeeServiceRow {
+    pub service_id: Uuid,
+    pub service_type: String,
+    pub display_name: String,
+    pub default_duration_min: Option<i32>,
+    /// per-employee override, None = catalog default
+    pub duration_min: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmployeeServicesData {
+    pub employee_id: Uuid,
+    /// false = no capability rows, the employee performs every service
+    pub restricted: bool,
+    pub services: Vec<EmployeeServiceRow>,
+}
+
+async fn load_employee_services(state: &AppState, employee_id: Uuid) -> Result<EmployeeServicesData, ApiError> {
+    let exists: Option<Uuid> = sqlx::query_scalar("SELECT employee_id FROM employee WHERE employee_id = $1")
+        .bind(employee_id)
+        .fetch_optional(&state.db)
+        .await?;
+    if exists.is_none() {
+        return Err(ApiError::NotFound("NOT_FOUND", "employee not found".into()));
+    }
+
+    let services: Vec<EmployeeServiceRow> = sqlx::query_as::<_, EmployeeServiceRow>(
+        r#"
+        SELECT
+          sc.service_id,
+          sc.service_type,
+          sc.display_name,
+          sc.default_duration_min,
+          es.duration_min
+        FROM employee_service es
+        JOIN service_catalog sc ON sc.service_id = es.service_id
+        WHERE es.employee_id = $1
+        ORDER BY sc.display_number ASC
+        "#,
+    )
+    .bind(employee_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(EmployeeServicesData {
+        employee_id,
+        restricted: !services.is_empty(),
+        services,
+    })
+}
+
+pub async fn get_employee_services(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(employee_id): Path<Uuid>,
+) -> Result<Json<ApiOk<EmployeeServicesData>>, ApiError> {
+    ensure_staff(&auth)?;
+    let data = load_employee_services(&state, employee_id).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PutEmployeeServicesRequest {
+    pub services: Vec<EmployeeServiceInput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmployeeServiceInput {
+    pub service_id: Uuid,
+    pub duration_min: Option<i32>,
+}
+
+pub async fn put_employee_services(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(employee_id): Path<Uuid>,
+    Json(req): Json<PutEmployeeServicesRequest>,
+) -> Result<Json<ApiOk<EmployeeServicesData>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    if req.services.iter().any(|s| s.duration_min.is_some_and(|d| d <= 0)) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "duration_min must be > 0".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query("DELETE FROM employee_service WHERE employee_id = $1")
+        .bind(employee_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for s in &req.services {
+        sqlx::query(
+            r#"
+            INSERT INTO employee_service (employee_id, service_id, duration_min)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(employee_id)
+        .bind(s.service_id)
+        .bind(s.duration_min)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::write_failed("EMPLOYEE_SERVICES_FAILED"))?;
+    }
+
+    tx.commit().await?;
+
+    let data = load_employee_services(&state, employee_id).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PutEmployeeRoomRequest {
+    /// null or blank = no room
+    pub room: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct EmployeeRoomData {
+    pub employee_id: Uuid,
+    pub room: Option<String>,
+}
+
+pub async fn put_employee_room(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(employee_id): Path<Uuid>,
+    Json(req): Json<PutEmployeeRoomRequest>,
+) -> Result<Json<ApiOk<EmployeeRoomData>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let room = req.room.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+    if room.as_deref().is_some_and(|r| r.chars().count() > 50) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "room must be at most 50 chars".into()));
+    }
+
+    let data = sqlx::query_as::<_, EmployeeRoomData>(
+        "UPDATE employee SET room = $2 WHERE employee_id = $1 RETURNING employee_id, room",
+    )
+    .bind(employee_id)
+    .bind(room)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(ApiError::write_failed("EMPLOYEE_UPDATE_FAILED"))?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "employee not found".into()))?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckDisplayNumberQuery {
+    pub value: String,
+    /// the employee being edited: their own number counts as available
+    pub employee_id: Option<Uuid>,
+}
+
+pub async fn check_display_number(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<CheckDisplayNumberQuery>,
+) -> Result<Json<ApiOk<NumberCheckDto>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let mut conn = state.db.acquire().await?;
+    let data = numbering::check_employee_number(&mut conn, &q.value, q.employee_id).await?;
+    Ok(Json(ApiOk { data }))
+}
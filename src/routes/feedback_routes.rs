@@ -0,0 +1,132 @@
+// src/routes/feedback_routes.rs
+//
+// Post-visit rating form (links are texted by jobs::feedback_requests), no login:
+// - GET  /feedback/{token}   what the page shows: clinic, first name, doctor, visit date
+// - POST /feedback/{token}   { rating: 1..5, comment? }; the link then stops working
+// Aggregates are in /reports/feedback.
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    auth,
+    error::ApiError,
+    extract::Json,
+    models::{ApiOk, AppState},
+    pii::PiiString,
+    services::feedback::{self, FeedbackSubmission},
+};
+
+pub fn public_router() -> Router<AppState> {
+    Router::new().route("/feedback/{token}", get(open_feedback_form).post(submit_feedback))
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct FeedbackLink {
+    feedback_id: Uuid,
+    first_name: String,
+    doctor_name: String,
+    visit_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    submitted_at: Option<DateTime<Utc>>,
+}
+
+/// Unknown token = 404, answered = FEEDBACK_LINK_USED, expired = FEEDBACK_LINK_EXPIRED.
+async fn usable_link(conn: &mut PgConnection, token: &str, for_update: bool) -> Result<FeedbackLink, ApiError> {
+    let link = sqlx::query_as::<_, FeedbackLink>(&format!(
+        r#"
+        SELECT f.feedback_id, p.first_name, d.first_name || ' ' || d.last_name AS doctor_name,
+               a.start_at AS visit_at, f.expires_at, f.submitted_at
+        FROM appointment_feedback f
+        JOIN appointment a ON a.appointment_id = f.appointment_id
+        JOIN patient p ON p.patient_id = a.patient_id
+        JOIN employee d ON d.employee_id = a.doctor_employee_id
+        WHERE f.token_hash = $1
+        {lock}
+        "#,
+        lock = if for_update { "FOR UPDATE OF f" } else { "" },
+    ))
+    .bind(auth::hash_access_token(token))
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "feedback link not found".into()))?;
+
+    if link.submitted_at.is_some() {
+        return Err(ApiError::Conflict("FEEDBACK_LINK_USED", "feedback was already given".into()));
+    }
+    if link.expires_at <= Utc::now() {
+        return Err(ApiError::Conflict("FEEDBACK_LINK_EXPIRED", "this link has expired".into()));
+    }
+    Ok(link)
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackFormInfo {
+    pub clinic_name: String,
+    pub first_name: String,
+    pub doctor_name: String,
+    pub visit_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub async fn open_feedback_form(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<ApiOk<FeedbackFormInfo>>, ApiError> {
+    let mut conn = state.db.acquire().await?;
+    let link = usable_link(&mut conn, &token, false).await?;
+    let clinic_name: String = sqlx::query_scalar("SELECT clinic_name FROM clinic_settings LIMIT 1")
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(Json(ApiOk {
+        data: FeedbackFormInfo {
+            clinic_name,
+            first_name: link.first_name,
+            doctor_name: link.doctor_name,
+            visit_at: link.visit_at,
+            expires_at: link.expires_at,
+        },
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackSubmitted {
+    pub submitted_at: DateTime<Utc>,
+}
+
+pub async fn submit_feedback(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Json(req): Json<FeedbackSubmission>,
+) -> Result<Json<ApiOk<FeedbackSubmitted>>, ApiError> {
+    let s = feedback::normalize(req)?;
+
+    let mut tx = state.db.begin().await?;
+    let link = usable_link(&mut tx, &token, true).await?;
+
+    let submitted_at: DateTime<Utc> = sqlx::query_scalar(
+        r#"
+        UPDATE appointment_feedback
+        SET rating = $2, comment = $3, submitted_at = now()
+        WHERE feedback_id = $1
+        RETURNING submitted_at
+        "#,
+    )
+    .bind(link.feedback_id)
+    .bind(s.rating)
+    .bind(s.comment.map(PiiString::from))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("FEEDBACK_SUBMIT_FAILED"))?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: FeedbackSubmitted { submitted_at } }))
+}
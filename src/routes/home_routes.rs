@@ -1,13 +1,9 @@
-use axum::{Json, Router, extract::State, routing::get};
+use axum::{Router, extract::State, routing::get};
 
 use crate::error::ApiError;
+use crate::extract::Json;
 use crate::middleware::auth_context::AuthContext;
-use crate::models::AppState;
-
-#[derive(serde::Serialize)]
-pub struct HomeResponse {
-    pub data: HomeData,
-}
+use crate::models::{ApiOk, AppState};
 
 #[derive(serde::Serialize)]
 pub struct HomeData {
@@ -22,21 +18,10 @@ pub fn router() -> Router<AppState> {
 pub async fn home(
     State(_state): State<AppState>,
     auth: AuthContext,
-) -> Result<Json<HomeResponse>, ApiError> {
-    // DB stores a single role (smallint):
-    // 0 patient, 1 admin, 2 manager, 3 doctor, 4 receptionist
-    let view = match auth.role {
-        1 => "admin",
-        2 => "manager",
-        3 => "doctor",
-        4 => "receptionist",
-        0 => "patient",
-        _ => "unknown",
-    };
-
-    Ok(Json(HomeResponse {
+) -> Result<Json<ApiOk<HomeData>>, ApiError> {
+    Ok(Json(ApiOk {
         data: HomeData {
-            view: view.to_string(),
+            view: auth.role.to_string(),
             message: "placeholder home payload (role-based)".to_string(),
         },
     }))
@@ -0,0 +1,385 @@
+// src/routes/household_routes.rs
+//
+// Households: related patients (parents, children, ...) grouped for the front desk.
+// A patient is in at most one household (household_member.patient_id is unique).
+// Family members show up in GET /patients/{id}/summary; back-to-back family booking
+// is POST /appointments/household (appointment_routes).
+
+use axum::{
+    extract::{Path, State},
+    routing::{get, post, put},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiOk, AppState, OkData},
+};
+
+pub const RELATIONSHIPS: [&str; 6] = ["head", "spouse", "parent", "child", "sibling", "other"];
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/households", post(create_household))
+        .route(
+            "/households/{household_id}",
+            get(get_household).patch(patch_household).delete(delete_household),
+        )
+        .route(
+            "/households/{household_id}/members/{patient_id}",
+            put(put_household_member).delete(remove_household_member),
+        )
+}
+
+fn ensure_front_desk(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "staff only".into()))
+    }
+}
+
+fn validate_relationship(rel: Option<&str>) -> Result<&'static str, ApiError> {
+    let rel = rel.map(str::trim).unwrap_or("other");
+    RELATIONSHIPS
+        .into_iter()
+        .find(|r| r.eq_ignore_ascii_case(rel))
+        .ok_or_else(|| {
+            ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                format!("relationship must be one of {}", RELATIONSHIPS.join(", ")),
+            )
+        })
+}
+
+fn validate_household_name(name: &str) -> Result<(), ApiError> {
+    if name.is_empty() || name.len() > 128 {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "name must be 1..128 chars".into(),
+        ));
+    }
+    Ok(())
+}
+
+/* ============================================================
+   DTOs
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct HouseholdMemberRow {
+    pub patient_id: Uuid,
+    pub register_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub birthday: Option<chrono::NaiveDate>,
+    pub relationship: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HouseholdDto {
+    pub household_id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub members: Vec<HouseholdMemberRow>,
+}
+
+/* ============================================================
+   Shared helpers (also used by the patient summary / family booking)
+   ============================================================ */
+
+async fn fetch_household(conn: &mut PgConnection, household_id: Uuid) -> Result<Option<HouseholdDto>, ApiError> {
+    let head: Option<(String, DateTime<Utc>)> =
+        sqlx::query_as("SELECT name, created_at FROM household WHERE household_id = $1")
+            .bind(household_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    let Some((name, created_at)) = head else {
+        return Ok(None);
+    };
+
+    let members = sqlx::query_as::<_, HouseholdMemberRow>(
+        r#"
+        SELECT p.patient_id, p.register_number, p.first_name, p.last_name, p.birthday, m.relationship
+        FROM household_member m
+        JOIN patient p ON p.patient_id = m.patient_id
+        WHERE m.household_id = $1
+        ORDER BY array_position(ARRAY['head','spouse','parent','child','sibling','other'], m.relationship),
+                 p.birthday NULLS LAST, p.last_name, p.first_name
+        "#,
+    )
+    .bind(household_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(HouseholdDto { household_id, name, created_at, members }))
+}
+
+/// The patient's household (all members, the patient included), if any.
+pub async fn household_of(conn: &mut PgConnection, patient_id: Uuid) -> Result<Option<HouseholdDto>, ApiError> {
+    let household_id: Option<Uuid> =
+        sqlx::query_scalar("SELECT household_id FROM household_member WHERE patient_id = $1")
+            .bind(patient_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    match household_id {
+        Some(id) => fetch_household(conn, id).await,
+        None => Ok(None),
+    }
+}
+
+async fn insert_member(
+    conn: &mut PgConnection,
+    household_id: Uuid,
+    patient_id: Uuid,
+    relationship: &str,
+) -> Result<(), ApiError> {
+    // a patient in another household is a conflict, not a silent move
+    let res = sqlx::query(
+        r#"
+        INSERT INTO household_member (household_id, patient_id, relationship)
+        SELECT $1, $2, $3
+        WHERE EXISTS (SELECT 1 FROM patient WHERE patient_id = $2)
+        ON CONFLICT (household_id, patient_id) DO UPDATE SET relationship = EXCLUDED.relationship
+        "#,
+    )
+    .bind(household_id)
+    .bind(patient_id)
+    .bind(relationship)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| match ApiError::from(e) {
+        ApiError::Conflict(..) => ApiError::Conflict(
+            "HOUSEHOLD_MEMBER_EXISTS",
+            format!("patient {patient_id} already belongs to another household"),
+        ),
+        other => other,
+    })?;
+
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "patient not found".into()));
+    }
+    Ok(())
+}
+
+/* ============================================================
+   Handlers
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct HouseholdMemberInput {
+    pub patient_id: Uuid,
+    /// head | spouse | parent | child | sibling | other (default)
+    pub relationship: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHouseholdRequest {
+    pub name: String,
+    #[serde(default)]
+    pub members: Vec<HouseholdMemberInput>,
+}
+
+pub async fn create_household(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateHouseholdRequest>,
+) -> Result<Json<ApiOk<HouseholdDto>>, ApiError> {
+    ensure_front_desk(&auth)?;
+
+    let name = req.name.trim();
+    validate_household_name(name)?;
+    let members = req
+        .members
+        .iter()
+        .map(|m| Ok((m.patient_id, validate_relationship(m.relationship.as_deref())?)))
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let mut tx = state.db.begin().await?;
+
+    let household_id: Uuid =
+        sqlx::query_scalar("INSERT INTO household (name) VALUES ($1) RETURNING household_id")
+            .bind(name)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(ApiError::write_failed("HOUSEHOLD_CREATE_FAILED"))?;
+
+    for (patient_id, relationship) in &members {
+        insert_member(&mut tx, household_id, *patient_id, relationship).await?;
+    }
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "household.create",
+        "household",
+        Some(household_id),
+        serde_json::json!({ "name": name, "patient_ids": members.iter().map(|(id, _)| id).collect::<Vec<_>>() }),
+    )
+    .await?;
+
+    let data = fetch_household(&mut tx, household_id)
+        .await?
+        .ok_or_else(|| ApiError::Internal("household vanished".into()))?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+pub async fn get_household(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(household_id): Path<Uuid>,
+) -> Result<Json<ApiOk<HouseholdDto>>, ApiError> {
+    ensure_front_desk(&auth)?;
+
+    let mut conn = state.db.acquire().await?;
+    let data = fetch_household(&mut conn, household_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "household not found".into()))?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PatchHouseholdRequest {
+    pub name: Option<String>,
+}
+
+pub async fn patch_household(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(household_id): Path<Uuid>,
+    Json(req): Json<PatchHouseholdRequest>,
+) -> Result<Json<ApiOk<HouseholdDto>>, ApiError> {
+    ensure_front_desk(&auth)?;
+
+    let name = req.name.as_deref().map(str::trim);
+    if let Some(name) = name {
+        validate_household_name(name)?;
+    }
+
+    let mut conn = state.db.acquire().await?;
+    let res = sqlx::query("UPDATE household SET name = COALESCE($2, name) WHERE household_id = $1")
+        .bind(household_id)
+        .bind(name)
+        .execute(&mut *conn)
+        .await
+        .map_err(ApiError::write_failed("HOUSEHOLD_UPDATE_FAILED"))?;
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "household not found".into()));
+    }
+
+    let data = fetch_household(&mut conn, household_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "household not found".into()))?;
+    Ok(Json(ApiOk { data }))
+}
+
+/// Dissolves the household; the patients themselves stay.
+pub async fn delete_household(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(household_id): Path<Uuid>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    ensure_front_desk(&auth)?;
+
+    let mut tx = state.db.begin().await?;
+    let res = sqlx::query("DELETE FROM household WHERE household_id = $1")
+        .bind(household_id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "household not found".into()));
+    }
+    audit::record(&mut *tx, &auth, "household.delete", "household", Some(household_id), serde_json::json!({}))
+        .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PutHouseholdMemberRequest {
+    pub relationship: Option<String>,
+}
+
+/// Adds the patient, or changes their relationship if already a member.
+pub async fn put_household_member(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path((household_id, patient_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<PutHouseholdMemberRequest>,
+) -> Result<Json<ApiOk<HouseholdDto>>, ApiError> {
+    ensure_front_desk(&auth)?;
+    let relationship = validate_relationship(req.relationship.as_deref())?;
+
+    let mut tx = state.db.begin().await?;
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM household WHERE household_id = $1)")
+        .bind(household_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !exists {
+        return Err(ApiError::NotFound("NOT_FOUND", "household not found".into()));
+    }
+
+    insert_member(&mut tx, household_id, patient_id, relationship).await?;
+    audit::record(
+        &mut *tx,
+        &auth,
+        "household.member_set",
+        "household",
+        Some(household_id),
+        serde_json::json!({ "patient_id": patient_id, "relationship": relationship }),
+    )
+    .await?;
+
+    let data = fetch_household(&mut tx, household_id)
+        .await?
+        .ok_or_else(|| ApiError::Internal("household vanished".into()))?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+pub async fn remove_household_member(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path((household_id, patient_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ApiOk<HouseholdDto>>, ApiError> {
+    ensure_front_desk(&auth)?;
+
+    let mut tx = state.db.begin().await?;
+    let res = sqlx::query("DELETE FROM household_member WHERE household_id = $1 AND patient_id = $2")
+        .bind(household_id)
+        .bind(patient_id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "household member not found".into()));
+    }
+    audit::record(
+        &mut *tx,
+        &auth,
+        "household.member_remove",
+        "household",
+        Some(household_id),
+        serde_json::json!({ "patient_id": patient_id }),
+    )
+    .await?;
+
+    let data = fetch_household(&mut tx, household_id)
+        .await?
+        .ok_or_else(|| ApiError::Internal("household vanished".into()))?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
@@ -0,0 +1,594 @@
+// src/routes/imaging_routes.rs
+//
+// Imaging orders (radiographs, CBCT):
+// - POST /patients/{id}/imaging_orders             doctor (or admin/manager on a doctor's behalf)
+// - GET  /patients/{id}/imaging                    imaging history, newest first, with attachments
+// - GET  /imaging_orders?status=requested          worklist
+// - POST /imaging_orders/{id}/perform | cancel
+// - POST /imaging_orders/{id}/attachments          raw file body (Content-Type header, ?file_name=)
+// - GET|DELETE /imaging_attachments/{id}
+// Files live in imaging_attachment (bytes in the database, like patient documents).
+
+use std::collections::HashMap;
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State, rejection::BytesRejection},
+    http::{HeaderMap, header},
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiList, ApiOk, AppState, ImagingModality, ImagingStatus, OkData, Role},
+};
+
+/// What the imaging devices export: stills, DICOM, zipped CBCT volumes.
+const ALLOWED_CONTENT_TYPES: [&str; 5] = ["image/jpeg", "image/png", "image/tiff", "application/dicom", "application/zip"];
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/patients/{patient_id}/imaging_orders", post(create_imaging_order))
+        .route("/patients/{patient_id}/imaging", get(get_patient_imaging))
+        .route("/imaging_orders", get(list_imaging_orders))
+        .route("/imaging_orders/{imaging_order_id}/perform", post(perform_imaging_order))
+        .route("/imaging_orders/{imaging_order_id}/cancel", post(cancel_imaging_order))
+        .route("/imaging_orders/{imaging_order_id}/attachments", post(upload_imaging_attachment))
+        .route(
+            "/imaging_attachments/{attachment_id}",
+            get(download_imaging_attachment).delete(delete_imaging_attachment),
+        )
+}
+
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    }
+}
+
+fn order_not_found() -> ApiError {
+    ApiError::NotFound("NOT_FOUND", "imaging order not found".into())
+}
+
+fn clean_text(field: &str, value: Option<String>, max: usize) -> Result<Option<String>, ApiError> {
+    let value = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    if value.as_deref().is_some_and(|v| v.chars().count() > max) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", format!("{field} must be at most {max} chars")));
+    }
+    Ok(value)
+}
+
+/* ============================================================
+   DTOs
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ImagingOrderDto {
+    pub imaging_order_id: Uuid,
+    pub patient_id: Uuid,
+    pub patient_name: String,
+    pub appointment_id: Option<Uuid>,
+    pub modality: ImagingModality,
+    pub region: Option<String>,
+    pub reason: Option<String>,
+    pub status: ImagingStatus,
+    pub requested_by_employee_id: Uuid,
+    pub requested_by_name: String,
+    pub requested_at: DateTime<Utc>,
+    pub performed_at: Option<DateTime<Utc>>,
+    pub performed_by_user_id: Option<Uuid>,
+    pub dose_notes: Option<String>,
+    pub canceled_at: Option<DateTime<Utc>>,
+    #[sqlx(skip)]
+    pub attachments: Vec<ImagingAttachmentDto>,
+}
+
+const ORDER_SELECT: &str = r#"
+    SELECT
+      o.imaging_order_id, o.patient_id, p.first_name || ' ' || p.last_name AS patient_name,
+      o.appointment_id, o.modality, o.region, o.reason, o.status,
+      o.requested_by_employee_id, e.first_name || ' ' || e.last_name AS requested_by_name,
+      o.requested_at, o.performed_at, o.performed_by_user_id, o.dose_notes, o.canceled_at
+    FROM imaging_order o
+    JOIN patient p ON p.patient_id = o.patient_id
+    JOIN employee e ON e.employee_id = o.requested_by_employee_id
+"#;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ImagingAttachmentDto {
+    pub attachment_id: Uuid,
+    #[serde(skip)]
+    pub imaging_order_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub url: String,
+}
+
+/// Fills `attachments` of the orders (metadata only).
+async fn attach(conn: &mut PgConnection, orders: &mut [ImagingOrderDto]) -> Result<(), ApiError> {
+    let ids: Vec<Uuid> = orders.iter().map(|o| o.imaging_order_id).collect();
+    let rows = sqlx::query_as::<_, ImagingAttachmentDto>(
+        r#"
+        SELECT attachment_id, imaging_order_id, file_name, content_type, size_bytes, created_at
+        FROM imaging_attachment
+        WHERE imaging_order_id = ANY($1)
+        ORDER BY created_at
+        "#,
+    )
+    .bind(&ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut by_order: HashMap<Uuid, Vec<ImagingAttachmentDto>> = HashMap::new();
+    for mut a in rows {
+        a.url = format!("/api/v1/imaging_attachments/{}", a.attachment_id);
+        by_order.entry(a.imaging_order_id).or_default().push(a);
+    }
+    for o in orders {
+        o.attachments = by_order.remove(&o.imaging_order_id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+async fn fetch_order(conn: &mut PgConnection, imaging_order_id: Uuid) -> Result<ImagingOrderDto, ApiError> {
+    let mut order = sqlx::query_as::<_, ImagingOrderDto>(&format!("{ORDER_SELECT} WHERE o.imaging_order_id = $1"))
+        .bind(imaging_order_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(order_not_found)?;
+    attach(conn, std::slice::from_mut(&mut order)).await?;
+    Ok(order)
+}
+
+/// All of a patient's orders, canceled ones too, oldest first (GET /patients/{id}/export).
+pub async fn patient_orders(conn: &mut PgConnection, patient_id: Uuid) -> Result<Vec<ImagingOrderDto>, ApiError> {
+    let mut orders = sqlx::query_as::<_, ImagingOrderDto>(&format!(
+        "{ORDER_SELECT} WHERE o.patient_id = $1 ORDER BY o.requested_at"
+    ))
+    .bind(patient_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    attach(conn, &mut orders).await?;
+    Ok(orders)
+}
+
+/* ============================================================
+   POST /patients/{id}/imaging_orders
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct CreateImagingOrderRequest {
+    pub modality: ImagingModality,
+    pub appointment_id: Option<Uuid>,
+    pub region: Option<String>,
+    pub reason: Option<String>,
+    /// admin/manager only; doctors always order as themselves
+    pub requested_by_employee_id: Option<Uuid>,
+}
+
+pub async fn create_imaging_order(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Json(req): Json<CreateImagingOrderRequest>,
+) -> Result<Json<ApiOk<ImagingOrderDto>>, ApiError> {
+    let requested_by = match auth.role {
+        Role::Doctor => {
+            let mine: Option<Uuid> = sqlx::query_scalar("SELECT employee_id FROM employee WHERE user_id = $1")
+                .bind(auth.user_id)
+                .fetch_optional(&state.db)
+                .await?;
+            let mine = mine.ok_or_else(|| {
+                ApiError::BadRequest("NO_EMPLOYEE_PROFILE", "This user has no employee profile".into())
+            })?;
+            if req.requested_by_employee_id.is_some_and(|id| id != mine) {
+                return Err(ApiError::Forbidden("FORBIDDEN", "Doctors order imaging as themselves".into()));
+            }
+            mine
+        }
+        Role::Admin | Role::Manager => req.requested_by_employee_id.ok_or_else(|| {
+            ApiError::BadRequest("VALIDATION_ERROR", "requested_by_employee_id is required".into())
+        })?,
+        _ => {
+            return Err(ApiError::Forbidden(
+                "FORBIDDEN",
+                "Only doctors and admin/manager can order imaging".into(),
+            ))
+        }
+    };
+    let region = clean_text("region", req.region, 200)?;
+    let reason = clean_text("reason", req.reason, 1000)?;
+
+    let mut tx = state.db.begin().await?;
+
+    if let Some(appointment_id) = req.appointment_id {
+        let owner: Option<Uuid> = sqlx::query_scalar("SELECT patient_id FROM appointment WHERE appointment_id = $1")
+            .bind(appointment_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if owner != Some(patient_id) {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "appointment_id must be an appointment of this patient".into(),
+            ));
+        }
+    }
+
+    let imaging_order_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO imaging_order (patient_id, appointment_id, modality, region, reason, requested_by_employee_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING imaging_order_id
+        "#,
+    )
+    .bind(patient_id)
+    .bind(req.appointment_id)
+    .bind(req.modality)
+    .bind(region)
+    .bind(reason)
+    .bind(requested_by)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("IMAGING_ORDER_CREATE_FAILED"))?;
+
+    let data = fetch_order(&mut tx, imaging_order_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/* ============================================================
+   GET /patients/{id}/imaging | GET /imaging_orders
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct PatientImagingQuery {
+    pub modality: Option<ImagingModality>,
+    /// default: canceled orders are left out
+    pub include_canceled: Option<bool>,
+}
+
+pub async fn get_patient_imaging(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Query(q): Query<PatientImagingQuery>,
+) -> Result<Json<ApiList<ImagingOrderDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    let mut conn = state.db.acquire().await?;
+
+    let mut orders = sqlx::query_as::<_, ImagingOrderDto>(&format!(
+        r#"{ORDER_SELECT}
+        WHERE o.patient_id = $1
+          AND ($2::smallint IS NULL OR o.modality = $2)
+          AND ($3 OR o.status <> $4)
+        ORDER BY COALESCE(o.performed_at, o.requested_at) DESC
+        "#
+    ))
+    .bind(patient_id)
+    .bind(q.modality)
+    .bind(q.include_canceled.unwrap_or(false))
+    .bind(ImagingStatus::Canceled)
+    .fetch_all(&mut *conn)
+    .await?;
+    attach(&mut conn, &mut orders).await?;
+
+    Ok(Json(ApiOk { data: orders }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImagingWorklistQuery {
+    /// default requested
+    pub status: Option<ImagingStatus>,
+    pub modality: Option<ImagingModality>,
+}
+
+/// Orders by status (default: still to be taken), oldest request first.
+pub async fn list_imaging_orders(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ImagingWorklistQuery>,
+) -> Result<Json<ApiList<ImagingOrderDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    let mut conn = state.db.acquire().await?;
+
+    let mut orders = sqlx::query_as::<_, ImagingOrderDto>(&format!(
+        r#"{ORDER_SELECT}
+        WHERE o.status = $1
+          AND ($2::smallint IS NULL OR o.modality = $2)
+        ORDER BY o.requested_at
+        LIMIT 500
+        "#
+    ))
+    .bind(q.status.unwrap_or(ImagingStatus::Requested))
+    .bind(q.modality)
+    .fetch_all(&mut *conn)
+    .await?;
+    attach(&mut conn, &mut orders).await?;
+
+    Ok(Json(ApiOk { data: orders }))
+}
+
+/* ============================================================
+   POST /imaging_orders/{id}/perform | cancel
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct PerformImagingRequest {
+    /// default now
+    pub performed_at: Option<DateTime<Utc>>,
+    pub dose_notes: Option<String>,
+}
+
+async fn lock_status(conn: &mut PgConnection, imaging_order_id: Uuid) -> Result<ImagingStatus, ApiError> {
+    let status: Option<ImagingStatus> =
+        sqlx::query_scalar("SELECT status FROM imaging_order WHERE imaging_order_id = $1 FOR UPDATE")
+            .bind(imaging_order_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    status.ok_or_else(order_not_found)
+}
+
+/// Whoever took the image (any staff) records it.
+pub async fn perform_imaging_order(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(imaging_order_id): Path<Uuid>,
+    Json(req): Json<PerformImagingRequest>,
+) -> Result<Json<ApiOk<ImagingOrderDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    let dose_notes = clean_text("dose_notes", req.dose_notes, 1000)?;
+    let performed_at = req.performed_at.unwrap_or_else(Utc::now);
+    if performed_at > Utc::now() {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "performed_at can't be in the future".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    lock_status(&mut tx, imaging_order_id).await?.ensure_transition(ImagingStatus::Performed)?;
+
+    sqlx::query(
+        r#"
+        UPDATE imaging_order
+        SET status = $2, performed_at = $3, performed_by_user_id = $4, dose_notes = $5
+        WHERE imaging_order_id = $1
+        "#,
+    )
+    .bind(imaging_order_id)
+    .bind(ImagingStatus::Performed)
+    .bind(performed_at)
+    .bind(auth.user_id)
+    .bind(dose_notes.as_deref())
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("IMAGING_ORDER_UPDATE_FAILED"))?;
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "imaging.perform",
+        "imaging_order",
+        Some(imaging_order_id),
+        serde_json::json!({ "performed_at": performed_at, "dose_notes": dose_notes }),
+    )
+    .await?;
+
+    let data = fetch_order(&mut tx, imaging_order_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/// Doctors and admin/manager.
+pub async fn cancel_imaging_order(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(imaging_order_id): Path<Uuid>,
+) -> Result<Json<ApiOk<ImagingOrderDto>>, ApiError> {
+    if !(auth.role.is_admin_or_manager() || auth.role == Role::Doctor) {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only doctors and admin/manager can cancel imaging orders".into(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    lock_status(&mut tx, imaging_order_id).await?.ensure_transition(ImagingStatus::Canceled)?;
+
+    sqlx::query("UPDATE imaging_order SET status = $2, canceled_at = now() WHERE imaging_order_id = $1")
+        .bind(imaging_order_id)
+        .bind(ImagingStatus::Canceled)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::write_failed("IMAGING_ORDER_UPDATE_FAILED"))?;
+
+    audit::record(&mut *tx, &auth, "imaging.cancel", "imaging_order", Some(imaging_order_id), serde_json::json!({}))
+        .await?;
+
+    let data = fetch_order(&mut tx, imaging_order_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/* ============================================================
+   Attachments
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    pub file_name: Option<String>,
+}
+
+/// Keeps a file name safe for Content-Disposition.
+fn clean_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .filter(|c| !c.is_control() && !matches!(c, '"' | '\\' | '/'))
+        .take(120)
+        .collect();
+    cleaned.trim().to_string()
+}
+
+pub async fn upload_imaging_attachment(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(imaging_order_id): Path<Uuid>,
+    Query(q): Query<UploadQuery>,
+    headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Json<ApiOk<ImagingOrderDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    let body = body?;
+    if body.is_empty() {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "file body is required".into()));
+    }
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or("").trim().to_ascii_lowercase())
+        .filter(|v| ALLOWED_CONTENT_TYPES.contains(&v.as_str()))
+        .ok_or_else(|| {
+            ApiError::UnsupportedMediaType(
+                "UNSUPPORTED_MEDIA_TYPE",
+                format!("Content-Type must be one of {}", ALLOWED_CONTENT_TYPES.join(", ")),
+            )
+        })?;
+
+    let mut tx = state.db.begin().await?;
+    let (status, modality): (ImagingStatus, ImagingModality) =
+        sqlx::query_as("SELECT status, modality FROM imaging_order WHERE imaging_order_id = $1 FOR UPDATE")
+            .bind(imaging_order_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(order_not_found)?;
+    if status == ImagingStatus::Canceled {
+        return Err(ApiError::Conflict(
+            "INVALID_STATUS_TRANSITION",
+            "can't attach files to a canceled imaging order".into(),
+        ));
+    }
+
+    let file_name = q
+        .file_name
+        .as_deref()
+        .map(clean_file_name)
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("{modality}-{}", Utc::now().format("%Y%m%d-%H%M%S")));
+
+    let attachment_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO imaging_attachment (imaging_order_id, file_name, content_type, size_bytes, content, uploaded_by_user_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING attachment_id
+        "#,
+    )
+    .bind(imaging_order_id)
+    .bind(&file_name)
+    .bind(&content_type)
+    .bind(body.len() as i32)
+    .bind(body.as_ref())
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("IMAGING_UPLOAD_FAILED"))?;
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "imaging.attach",
+        "imaging_order",
+        Some(imaging_order_id),
+        serde_json::json!({ "attachment_id": attachment_id, "file_name": file_name, "size_bytes": body.len() }),
+    )
+    .await?;
+
+    let data = fetch_order(&mut tx, imaging_order_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+pub async fn download_imaging_attachment(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(attachment_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_staff(&auth)?;
+
+    let (file_name, content_type, bytes): (String, String, Vec<u8>) =
+        sqlx::query_as("SELECT file_name, content_type, content FROM imaging_attachment WHERE attachment_id = $1")
+            .bind(attachment_id)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "attachment not found".into()))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, format!("inline; filename=\"{file_name}\"")),
+            // attachments are never edited in place
+            (header::CACHE_CONTROL, "private, max-age=31536000, immutable".to_string()),
+        ],
+        bytes,
+    ))
+}
+
+/// Admin/manager: removes a wrongly attached file.
+pub async fn delete_imaging_attachment(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(attachment_id): Path<Uuid>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    if !auth.role.is_admin_or_manager() {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin/manager can delete imaging files".into(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let order: Option<Uuid> =
+        sqlx::query_scalar("DELETE FROM imaging_attachment WHERE attachment_id = $1 RETURNING imaging_order_id")
+            .bind(attachment_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let order = order.ok_or_else(|| ApiError::NotFound("NOT_FOUND", "attachment not found".into()))?;
+    audit::record(
+        &mut *tx,
+        &auth,
+        "imaging.detach",
+        "imaging_order",
+        Some(order),
+        serde_json::json!({ "attachment_id": attachment_id }),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_are_safe_for_content_disposition() {
+        assert_eq!(clean_file_name(" pano \"1\".dcm"), "pano 1.dcm");
+        assert_eq!(clean_file_name("../../etc/passwd"), "....etcpasswd");
+        assert_eq!(clean_file_name("a\r\nb"), "ab");
+    }
+}
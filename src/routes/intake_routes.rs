@@ -0,0 +1,665 @@
+// src/routes/intake_routes.rs
+//
+// Pre-visit intake forms (see services::intake):
+// - POST /patients/{id}/intake                 staff: new link, texted to the patient
+// - GET  /patients/{id}/intake                 staff: the patient's links and submissions
+// - GET  /patients/{id}/medical_history        staff
+// - GET  /intakes?status=submitted             staff: review queue
+// - POST /intakes/{id}/approve | reject | revoke
+// Public, no login, the token is the credential (single use, expiring):
+// - GET  /intake/{token}                       who the form is for
+// - POST /intake/{token}                       submit the form
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    audit, auth, clinic_time,
+    consent::{self, ContactPurpose},
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiList, ApiOk, AppState, IntakeStatus, SmsDirection},
+    pii::PiiString,
+    services::{
+        intake::{self, IntakeForm},
+        patients,
+    },
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/patients/{patient_id}/intake", post(create_intake).get(list_patient_intakes))
+        .route("/patients/{patient_id}/medical_history", get(get_medical_history))
+        .route("/intakes", get(list_intakes))
+        .route("/intakes/{intake_id}/approve", post(approve_intake))
+        .route("/intakes/{intake_id}/reject", post(reject_intake))
+        .route("/intakes/{intake_id}/revoke", post(revoke_intake))
+}
+
+/// Unauthenticated; mounted with a small body limit.
+pub fn public_router() -> Router<AppState> {
+    Router::new().route("/intake/{token}", get(open_intake_form).post(submit_intake_form))
+}
+
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    }
+}
+
+fn intake_not_found() -> ApiError {
+    ApiError::NotFound("NOT_FOUND", "intake not found".into())
+}
+
+/* ============================================================
+   DTOs
+   ============================================================ */
+
+#[derive(Debug, sqlx::FromRow)]
+struct IntakeRow {
+    intake_id: Uuid,
+    patient_id: Uuid,
+    patient_name: String,
+    phone_number_id: Option<Uuid>,
+    status: IntakeStatus,
+    expires_at: DateTime<Utc>,
+    submission: Option<PiiString>,
+    submitted_at: Option<DateTime<Utc>>,
+    reviewed_by_user_id: Option<Uuid>,
+    reviewed_at: Option<DateTime<Utc>>,
+    review_note: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntakeDto {
+    pub intake_id: Uuid,
+    pub patient_id: Uuid,
+    pub patient_name: String,
+    pub phone_number_id: Option<Uuid>,
+    pub status: IntakeStatus,
+    pub expires_at: DateTime<Utc>,
+    /// what the patient filled in, None before submission
+    pub submission: Option<IntakeForm>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub reviewed_by_user_id: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<IntakeRow> for IntakeDto {
+    type Error = ApiError;
+
+    fn try_from(r: IntakeRow) -> Result<Self, ApiError> {
+        let submission = r
+            .submission
+            .map(|s| serde_json::from_str(&s.0))
+            .transpose()
+            .map_err(|e| ApiError::Internal(format!("intake {}: bad submission: {e}", r.intake_id)))?;
+        Ok(Self {
+            intake_id: r.intake_id,
+            patient_id: r.patient_id,
+            patient_name: r.patient_name,
+            phone_number_id: r.phone_number_id,
+            status: r.status,
+            expires_at: r.expires_at,
+            submission,
+            submitted_at: r.submitted_at,
+            reviewed_by_user_id: r.reviewed_by_user_id,
+            reviewed_at: r.reviewed_at,
+            review_note: r.review_note,
+            created_at: r.created_at,
+        })
+    }
+}
+
+const INTAKE_SELECT: &str = r#"
+    SELECT
+      i.intake_id, i.patient_id, p.first_name || ' ' || p.last_name AS patient_name,
+      i.phone_number_id, i.status, i.expires_at, i.submission, i.submitted_at,
+      i.reviewed_by_user_id, i.reviewed_at, i.review_note, i.created_at
+    FROM patient_intake i
+    JOIN patient p ON p.patient_id = i.patient_id
+"#;
+
+async fn fetch_intake(conn: &mut PgConnection, intake_id: Uuid) -> Result<IntakeDto, ApiError> {
+    sqlx::query_as::<_, IntakeRow>(&format!("{INTAKE_SELECT} WHERE i.intake_id = $1"))
+        .bind(intake_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(intake_not_found)?
+        .try_into()
+}
+
+/* ============================================================
+   POST /patients/{id}/intake
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct CreateIntakeRequest {
+    /// default: the patient's primary number
+    pub phone_number_id: Option<Uuid>,
+    /// default 72, max 336
+    pub expires_in_hours: Option<i64>,
+    /// default true; false = only return the link (e.g. to show as a QR code at the desk)
+    pub send_sms: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedIntake {
+    #[serde(flatten)]
+    pub intake: IntakeDto,
+    /// shown once; only its hash is stored. None without INTAKE_FORM_URL
+    pub link: Option<String>,
+    pub token: String,
+    /// the outbound sms row, when texted
+    pub sms_id: Option<Uuid>,
+}
+
+/// Earlier unused links of the patient are revoked, so only the newest one works.
+pub async fn create_intake(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Json(req): Json<CreateIntakeRequest>,
+) -> Result<Json<ApiOk<CreatedIntake>>, ApiError> {
+    ensure_staff(&auth)?;
+    let hours = req.expires_in_hours.unwrap_or(intake::DEFAULT_LINK_TTL_HOURS);
+    if !(1..=intake::MAX_LINK_TTL_HOURS).contains(&hours) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("expires_in_hours must be 1..{}", intake::MAX_LINK_TTL_HOURS),
+        ));
+    }
+    let send_sms = req.send_sms.unwrap_or(true);
+    if send_sms && state.intake_form_url.is_none() {
+        return Err(ApiError::Conflict(
+            "INTAKE_NOT_CONFIGURED",
+            "INTAKE_FORM_URL is not set, links can't be texted".into(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    let first_name: String = sqlx::query_scalar("SELECT first_name FROM patient WHERE patient_id = $1")
+        .bind(patient_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "patient not found".into()))?;
+
+    let phone: Option<(Uuid, bool)> = if send_sms || req.phone_number_id.is_some() {
+        let row: Option<(Uuid, bool)> = sqlx::query_as(&format!(
+            r#"
+            SELECT ph.phone_number_id, {allowed}
+            FROM phone_number ph
+            JOIN patient p ON p.patient_id = ph.patient_id
+            WHERE ph.patient_id = $1
+              AND ph.released_at IS NULL
+              AND (ph.phone_number_id = $2 OR ($2::uuid IS NULL AND ph.is_primary))
+            "#,
+            allowed = consent::allowed_sql(ContactPurpose::Transactional),
+        ))
+        .bind(patient_id)
+        .bind(req.phone_number_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let row = row.ok_or_else(|| {
+            ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                match req.phone_number_id {
+                    Some(_) => "phone_number_id is not a number of this patient".into(),
+                    None => "the patient has no primary phone number".into(),
+                },
+            )
+        })?;
+        if send_sms && !row.1 {
+            return Err(ApiError::Conflict(
+                "CONTACT_OPTED_OUT",
+                "the patient opted out of service messages to this number".into(),
+            ));
+        }
+        Some(row)
+    } else {
+        None
+    };
+
+    let revoked = sqlx::query("UPDATE patient_intake SET status = $2 WHERE patient_id = $1 AND status = $3")
+        .bind(patient_id)
+        .bind(IntakeStatus::Revoked)
+        .bind(IntakeStatus::Sent)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::write_failed("INTAKE_CREATE_FAILED"))?
+        .rows_affected();
+
+    let token = auth::generate_access_token();
+    let intake_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO patient_intake (patient_id, token_hash, phone_number_id, expires_at, created_by_user_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING intake_id
+        "#,
+    )
+    .bind(patient_id)
+    .bind(auth::hash_access_token(&token))
+    .bind(phone.map(|(id, _)| id))
+    .bind(Utc::now() + Duration::hours(hours))
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("INTAKE_CREATE_FAILED"))?;
+
+    let link = state.intake_form_url.as_deref().map(|base| intake::link(base, &token));
+    let sms_id = match (&link, phone) {
+        (Some(link), Some((phone_number_id, _))) if send_sms => {
+            let id: Uuid = sqlx::query_scalar(
+                r#"
+                INSERT INTO sms (phone_number_id, direction, sent_at, subject, sms_text, note)
+                VALUES ($1, $2, now(), 'Intake form', $3, 'intake')
+                RETURNING sms_id
+                "#,
+            )
+            .bind(phone_number_id)
+            .bind(SmsDirection::Send as i16)
+            .bind(PiiString::from(intake::sms_text(&first_name, link)))
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(ApiError::write_failed("INTAKE_CREATE_FAILED"))?;
+            Some(id)
+        }
+        _ => None,
+    };
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "intake.create",
+        "patient",
+        Some(patient_id),
+        serde_json::json!({ "intake_id": intake_id, "sms_id": sms_id, "revoked_links": revoked }),
+    )
+    .await?;
+
+    let intake = fetch_intake(&mut tx, intake_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: CreatedIntake { intake, link, token, sms_id } }))
+}
+
+/* ============================================================
+   Staff views
+   ============================================================ */
+
+/// Newest first.
+pub async fn list_patient_intakes(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+) -> Result<Json<ApiList<IntakeDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    let data = patient_intakes(&mut *state.db.acquire().await?, patient_id).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+/// A patient's intake links with their submissions, newest first (also GET /patients/{id}/export).
+pub async fn patient_intakes(conn: &mut PgConnection, patient_id: Uuid) -> Result<Vec<IntakeDto>, ApiError> {
+    sqlx::query_as::<_, IntakeRow>(&format!(
+        "{INTAKE_SELECT} WHERE i.patient_id = $1 ORDER BY i.created_at DESC"
+    ))
+    .bind(patient_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(IntakeDto::try_from)
+    .collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListIntakesQuery {
+    /// default submitted
+    pub status: Option<IntakeStatus>,
+}
+
+/// Oldest submission first (the review queue), else newest link first.
+pub async fn list_intakes(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ListIntakesQuery>,
+) -> Result<Json<ApiList<IntakeDto>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let rows = sqlx::query_as::<_, IntakeRow>(&format!(
+        r#"{INTAKE_SELECT}
+        WHERE i.status = $1
+        ORDER BY i.submitted_at NULLS LAST, i.created_at DESC
+        LIMIT 500
+        "#
+    ))
+    .bind(q.status.unwrap_or(IntakeStatus::Submitted))
+    .fetch_all(&state.db)
+    .await?;
+
+    let data = rows.into_iter().map(IntakeDto::try_from).collect::<Result<_, _>>()?;
+    Ok(Json(ApiOk { data }))
+}
+
+#[derive(Debug, Default, Serialize, sqlx::FromRow)]
+pub struct MedicalHistoryDto {
+    pub allergies: Option<PiiString>,
+    pub medications: Option<PiiString>,
+    pub conditions: Option<PiiString>,
+    pub notes: Option<PiiString>,
+    pub updated_by_user_id: Option<Uuid>,
+    /// None = nothing recorded yet
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+pub async fn get_medical_history(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+) -> Result<Json<ApiOk<MedicalHistoryDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    patients::get(&*state.repos.patients, patient_id).await?;
+
+    let row = sqlx::query_as::<_, MedicalHistoryDto>(
+        r#"
+        SELECT allergies, medications, conditions, notes, updated_by_user_id, updated_at
+        FROM patient_medical_history
+        WHERE patient_id = $1
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: row.unwrap_or_default() }))
+}
+
+/* ============================================================
+   Review: approve / reject / revoke
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct IntakeDecisionRequest {
+    pub note: Option<String>,
+}
+
+fn clean_note(req: IntakeDecisionRequest) -> Result<Option<String>, ApiError> {
+    let note = req.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    if note.as_deref().is_some_and(|n| n.chars().count() > 1000) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "note must be at most 1000 chars".into()));
+    }
+    Ok(note)
+}
+
+/// Locks the intake; (status, patient_id, submission).
+async fn lock_intake(
+    conn: &mut PgConnection,
+    intake_id: Uuid,
+) -> Result<(IntakeStatus, Uuid, Option<PiiString>), ApiError> {
+    sqlx::query_as("SELECT status, patient_id, submission FROM patient_intake WHERE intake_id = $1 FOR UPDATE")
+        .bind(intake_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(intake_not_found)
+}
+
+async fn set_reviewed(
+    conn: &mut PgConnection,
+    auth: &AuthContext,
+    intake_id: Uuid,
+    status: IntakeStatus,
+    note: Option<&str>,
+) -> Result<(), ApiError> {
+    sqlx::query(
+        r#"
+        UPDATE patient_intake
+        SET status = $2, reviewed_by_user_id = $3, reviewed_at = now(), review_note = $4
+        WHERE intake_id = $1
+        "#,
+    )
+    .bind(intake_id)
+    .bind(status)
+    .bind(auth.user_id)
+    .bind(note)
+    .execute(&mut *conn)
+    .await
+    .map_err(ApiError::write_failed("INTAKE_UPDATE_FAILED"))?;
+    Ok(())
+}
+
+/// Copies the submission onto the patient (demographics) and the medical
+/// history; fields the patient left blank keep their stored value.
+pub async fn approve_intake(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(intake_id): Path<Uuid>,
+    Json(req): Json<IntakeDecisionRequest>,
+) -> Result<Json<ApiOk<IntakeDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    let note = clean_note(req)?;
+
+    let mut tx = state.db.begin().await?;
+    let (status, patient_id, submission) = lock_intake(&mut tx, intake_id).await?;
+    status.ensure_transition(IntakeStatus::Approved)?;
+    let form: IntakeForm = submission
+        .map(|s| serde_json::from_str(&s.0))
+        .transpose()
+        .map_err(|e| ApiError::Internal(format!("intake {intake_id}: bad submission: {e}")))?
+        .unwrap_or_default();
+
+    patients::update(&*state.repos.patients, patient_id, intake::patient_patch(&form)).await?;
+
+    let m = &form.medical_history;
+    if !m.is_empty() {
+        sqlx::query(
+            r#"
+            INSERT INTO patient_medical_history (patient_id, allergies, medications, conditions, notes, updated_by_user_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (patient_id) DO UPDATE SET
+              allergies   = COALESCE(EXCLUDED.allergies, patient_medical_history.allergies),
+              medications = COALESCE(EXCLUDED.medications, patient_medical_history.medications),
+              conditions  = COALESCE(EXCLUDED.conditions, patient_medical_history.conditions),
+              notes       = COALESCE(EXCLUDED.notes, patient_medical_history.notes),
+              updated_by_user_id = EXCLUDED.updated_by_user_id,
+              updated_at  = now()
+            "#,
+        )
+        .bind(patient_id)
+        .bind(m.allergies.as_deref().map(PiiString::from))
+        .bind(m.medications.as_deref().map(PiiString::from))
+        .bind(m.conditions.as_deref().map(PiiString::from))
+        .bind(m.notes.as_deref().map(PiiString::from))
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::write_failed("INTAKE_UPDATE_FAILED"))?;
+    }
+
+    set_reviewed(&mut tx, &auth, intake_id, IntakeStatus::Approved, note.as_deref()).await?;
+    audit::record(
+        &mut *tx,
+        &auth,
+        "intake.approve",
+        "patient",
+        Some(patient_id),
+        serde_json::json!({ "intake_id": intake_id, "fields": intake::given_fields(&form) }),
+    )
+    .await?;
+
+    let data = fetch_intake(&mut tx, intake_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/// The patient record is left as is.
+pub async fn reject_intake(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(intake_id): Path<Uuid>,
+    Json(req): Json<IntakeDecisionRequest>,
+) -> Result<Json<ApiOk<IntakeDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    let note = clean_note(req)?;
+
+    let mut tx = state.db.begin().await?;
+    let (status, patient_id, _) = lock_intake(&mut tx, intake_id).await?;
+    status.ensure_transition(IntakeStatus::Rejected)?;
+
+    set_reviewed(&mut tx, &auth, intake_id, IntakeStatus::Rejected, note.as_deref()).await?;
+    audit::record(
+        &mut *tx,
+        &auth,
+        "intake.reject",
+        "patient",
+        Some(patient_id),
+        serde_json::json!({ "intake_id": intake_id, "note": note }),
+    )
+    .await?;
+
+    let data = fetch_intake(&mut tx, intake_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/// Kills an unused link (sent to the wrong number, etc.).
+pub async fn revoke_intake(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(intake_id): Path<Uuid>,
+) -> Result<Json<ApiOk<IntakeDto>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let mut tx = state.db.begin().await?;
+    let (status, patient_id, _) = lock_intake(&mut tx, intake_id).await?;
+    status.ensure_transition(IntakeStatus::Revoked)?;
+
+    sqlx::query("UPDATE patient_intake SET status = $2 WHERE intake_id = $1")
+        .bind(intake_id)
+        .bind(IntakeStatus::Revoked)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::write_failed("INTAKE_UPDATE_FAILED"))?;
+    audit::record(
+        &mut *tx,
+        &auth,
+        "intake.revoke",
+        "patient",
+        Some(patient_id),
+        serde_json::json!({ "intake_id": intake_id }),
+    )
+    .await?;
+
+    let data = fetch_intake(&mut tx, intake_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/* ============================================================
+   Public: GET / POST /intake/{token}
+   ============================================================ */
+
+/// (intake_id, patient_id, patient first name, expires_at) of a usable link:
+/// unknown token = 404, used/revoked = INTAKE_LINK_USED, expired = INTAKE_LINK_EXPIRED.
+async fn usable_link(
+    conn: &mut PgConnection,
+    token: &str,
+    for_update: bool,
+) -> Result<(Uuid, Uuid, String, DateTime<Utc>), ApiError> {
+    let row: Option<(Uuid, Uuid, String, IntakeStatus, DateTime<Utc>)> = sqlx::query_as(&format!(
+        r#"
+        SELECT i.intake_id, i.patient_id, p.first_name, i.status, i.expires_at
+        FROM patient_intake i
+        JOIN patient p ON p.patient_id = i.patient_id
+        WHERE i.token_hash = $1
+        {lock}
+        "#,
+        lock = if for_update { "FOR UPDATE OF i" } else { "" },
+    ))
+    .bind(auth::hash_access_token(token))
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let (intake_id, patient_id, first_name, status, expires_at) =
+        row.ok_or_else(|| ApiError::NotFound("NOT_FOUND", "intake link not found".into()))?;
+    if status != IntakeStatus::Sent {
+        return Err(ApiError::Conflict("INTAKE_LINK_USED", "this link has already been used".into()));
+    }
+    if expires_at <= Utc::now() {
+        return Err(ApiError::Conflict("INTAKE_LINK_EXPIRED", "this link has expired".into()));
+    }
+    Ok((intake_id, patient_id, first_name, expires_at))
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntakeFormInfo {
+    pub clinic_name: String,
+    pub first_name: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// What the form page needs to greet the patient; nothing else about them.
+pub async fn open_intake_form(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<ApiOk<IntakeFormInfo>>, ApiError> {
+    let mut conn = state.db.acquire().await?;
+    let (_, _, first_name, expires_at) = usable_link(&mut conn, &token, false).await?;
+    let clinic_name: String = sqlx::query_scalar("SELECT clinic_name FROM clinic_settings LIMIT 1")
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(Json(ApiOk { data: IntakeFormInfo { clinic_name, first_name, expires_at } }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntakeSubmitted {
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// Stores the form for review; the link can't be used again.
+pub async fn submit_intake_form(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Json(form): Json<IntakeForm>,
+) -> Result<Json<ApiOk<IntakeSubmitted>>, ApiError> {
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let form = intake::normalize(form, clinic_time::local_today(tz))?;
+    let submission = serde_json::to_string(&form).map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let mut tx = state.db.begin().await?;
+    let (intake_id, _, _, _) = usable_link(&mut tx, &token, true).await?;
+
+    let submitted_at: DateTime<Utc> = sqlx::query_scalar(
+        r#"
+        UPDATE patient_intake
+        SET status = $2, submission = $3, submitted_at = now()
+        WHERE intake_id = $1
+        RETURNING submitted_at
+        "#,
+    )
+    .bind(intake_id)
+    .bind(IntakeStatus::Submitted)
+    .bind(PiiString::from(submission))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("INTAKE_SUBMIT_FAILED"))?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: IntakeSubmitted { submitted_at } }))
+}
@@ -0,0 +1,392 @@
+// src/routes/kiosk_routes.rs
+//
+// Reception kiosk (self check-in tablet):
+// - GET  /kiosks                   admin/manager: registered devices
+// - POST /kiosks                   admin/manager: register one; the device token is shown once
+// - POST /kiosks/{id}/revoke       admin/manager
+// - POST /kiosk/checkin            kiosk token only (KioskContext), nothing else accepts it
+// Queue displays are registered here too (kind = "display"), see display_routes.
+// A check-in marks the patient's remaining appointments of the clinic-local day
+// as arrived, the same as the front desk's /arrived milestone.
+
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    audit, auth, clinic_time,
+    error::ApiError,
+    extract::Json,
+    middleware::{auth_context::AuthContext, kiosk_context::KioskContext},
+    models::{ApiList, ApiOk, AppState, AppointmentStatus, DeviceKind},
+    services::patients::{self, MAX_PHONE_DIGITS, PHONE_MATCH_DIGITS},
+};
+
+const MAX_CHECKIN_MATCHES: i64 = 10;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/kiosks", get(list_kiosks).post(create_kiosk))
+        .route("/kiosks/{kiosk_id}/revoke", post(revoke_kiosk))
+        .route("/kiosk/checkin", post(kiosk_checkin))
+}
+
+fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_admin_or_manager() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin/manager can manage kiosks".into(),
+        ))
+    }
+}
+
+/* ============================================================
+   Device management
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct KioskDto {
+    pub kiosk_id: Uuid,
+    pub name: String,
+    pub kind: DeviceKind,
+    pub location_id: Option<Uuid>,
+    pub created_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+const KIOSK_COLUMNS: &str = "kiosk_id, name, kind, location_id, created_by_user_id, created_at, last_seen_at, revoked_at";
+
+/// Active devices first, then by name.
+pub async fn list_kiosks(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<ApiList<KioskDto>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let rows = sqlx::query_as::<_, KioskDto>(&format!(
+        "SELECT {KIOSK_COLUMNS} FROM kiosk_device ORDER BY revoked_at IS NOT NULL, lower(name)"
+    ))
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: rows }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateKioskRequest {
+    pub name: String,
+    /// default kiosk
+    pub kind: Option<DeviceKind>,
+    pub location_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedKiosk {
+    #[serde(flatten)]
+    pub kiosk: KioskDto,
+    /// shown once; only its hash is stored. The tablet sends it as `Authorization: Bearer`
+    pub token: String,
+}
+
+pub async fn create_kiosk(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateKioskRequest>,
+) -> Result<Json<ApiOk<CreatedKiosk>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    auth.ensure_not_impersonating()?;
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "name must be 1..100 chars".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    if let Some(location_id) = req.location_id {
+        let active: Option<bool> = sqlx::query_scalar("SELECT is_active FROM clinic_location WHERE location_id = $1")
+            .bind(location_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if active != Some(true) {
+            return Err(ApiError::BadRequest("VALIDATION_ERROR", "location_id is not an active location".into()));
+        }
+    }
+
+    let token = auth::generate_access_token();
+    let kiosk = sqlx::query_as::<_, KioskDto>(&format!(
+        r#"
+        INSERT INTO kiosk_device (name, kind, location_id, token_hash, created_by_user_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING {KIOSK_COLUMNS}
+        "#
+    ))
+    .bind(name)
+    .bind(req.kind.unwrap_or(DeviceKind::Kiosk))
+    .bind(req.location_id)
+    .bind(auth::hash_access_token(&token))
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("KIOSK_CREATE_FAILED"))?;
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "kiosk.create",
+        "kiosk_device",
+        Some(kiosk.kiosk_id),
+        serde_json::json!({ "name": name, "kind": kiosk.kind, "location_id": req.location_id }),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: CreatedKiosk { kiosk, token } }))
+}
+
+/// The device's next request fails; re-registering gives it a new token.
+pub async fn revoke_kiosk(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(kiosk_id): Path<Uuid>,
+) -> Result<Json<ApiOk<KioskDto>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let mut tx = state.db.begin().await?;
+    let kiosk = sqlx::query_as::<_, KioskDto>(&format!(
+        r#"
+        UPDATE kiosk_device
+        SET revoked_at = COALESCE(revoked_at, now())
+        WHERE kiosk_id = $1
+        RETURNING {KIOSK_COLUMNS}
+        "#
+    ))
+    .bind(kiosk_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("KIOSK_UPDATE_FAILED"))?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "kiosk not found".into()))?;
+
+    audit::record(&mut *tx, &auth, "kiosk.revoke", "kiosk_device", Some(kiosk_id), serde_json::json!({})).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: kiosk }))
+}
+
+/* ============================================================
+   POST /kiosk/checkin
+   ============================================================ */
+
+/// Exactly one of the two, as typed by the patient.
+#[derive(Debug, Deserialize)]
+pub struct KioskCheckinRequest {
+    pub register_number: Option<String>,
+    /// any formatting; matched on the last 8 digits like /phone_numbers/lookup, so
+    /// "+976 8811 2233" finds a number stored as "88112233" and the other way round
+    pub phone: Option<String>,
+}
+
+/// What the tablet may show: no last names, register numbers or ids.
+#[derive(Debug, Serialize)]
+pub struct KioskAppointment {
+    pub first_name: String,
+    pub start_at: DateTime<Utc>,
+    pub doctor_name: String,
+    /// false = was already marked arrived
+    pub checked_in_now: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KioskCheckin {
+    pub appointments: Vec<KioskAppointment>,
+}
+
+enum CheckinKey {
+    RegisterNumber(String),
+    /// phone_match_key of the typed number
+    PhoneKey(String),
+}
+
+fn checkin_key(req: KioskCheckinRequest) -> Result<CheckinKey, ApiError> {
+    let register_number = req.register_number.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+    let phone = req.phone.filter(|p| !p.trim().is_empty());
+    match (register_number, phone) {
+        (Some(r), None) => Ok(CheckinKey::RegisterNumber(r)),
+        (None, Some(p)) => {
+            let digits = p.chars().filter(char::is_ascii_digit).count();
+            match patients::phone_match_key(&p) {
+                Some(key) if digits <= MAX_PHONE_DIGITS => Ok(CheckinKey::PhoneKey(key)),
+                _ => Err(ApiError::BadRequest(
+                    "VALIDATION_ERROR",
+                    format!("phone must have {PHONE_MATCH_DIGITS}..{MAX_PHONE_DIGITS} digits"),
+                )),
+            }
+        }
+        _ => Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "give either register_number or phone".into(),
+        )),
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct CheckinRow {
+    appointment_id: Uuid,
+    status: AppointmentStatus,
+    first_name: String,
+    start_at: DateTime<Utc>,
+    doctor_name: String,
+}
+
+/// Every matching patient's appointments later today (a shared family phone
+/// checks the whole family in); appointments already arrived are listed as such.
+pub async fn kiosk_checkin(
+    State(state): State<AppState>,
+    kiosk: KioskContext,
+    Json(req): Json<KioskCheckinRequest>,
+) -> Result<Json<ApiOk<KioskCheckin>>, ApiError> {
+    kiosk.ensure_kind(DeviceKind::Kiosk)?;
+    let key = checkin_key(req)?;
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let (day_start, day_end) = clinic_time::local_days_range(clinic_time::local_today(tz), 1, tz);
+
+    let (match_sql, value) = match key {
+        CheckinKey::RegisterNumber(r) => ("lower(p.register_number) = lower($1)", r),
+        // same key as services::patients::lookup_phone; the 8 is spelled out so the
+        // planner can use phone_number_match_key_idx (059)
+        CheckinKey::PhoneKey(k) => (
+            r#"EXISTS (
+                SELECT 1 FROM phone_number ph
+                WHERE ph.patient_id = p.patient_id
+                  AND ph.released_at IS NULL
+                  AND right(regexp_replace(ph.phone_number, '\D', '', 'g'), 8) = $1
+            )"#,
+            k,
+        ),
+    };
+
+    let mut tx = state.db.begin().await?;
+    let rows = sqlx::query_as::<_, CheckinRow>(&format!(
+        r#"
+        SELECT a.appointment_id, a.status, p.first_name, a.start_at,
+               d.first_name || ' ' || d.last_name AS doctor_name
+        FROM appointment a
+        JOIN patient p ON p.patient_id = a.patient_id
+        JOIN employee d ON d.employee_id = a.doctor_employee_id
+        WHERE {match_sql}
+          AND a.start_at >= $2 AND a.start_at < $3
+          AND a.end_at > now()
+          AND a.status IN ($4, $5, $6)
+          AND ($7::uuid IS NULL OR a.location_id = $7)
+        ORDER BY a.start_at
+        LIMIT {MAX_CHECKIN_MATCHES}
+        FOR UPDATE OF a
+        "#
+    ))
+    .bind(&value)
+    .bind(day_start)
+    .bind(day_end)
+    .bind(AppointmentStatus::Scheduled)
+    .bind(AppointmentStatus::Confirmed)
+    .bind(AppointmentStatus::Arrived)
+    .bind(kiosk.location_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if rows.is_empty() {
+        return Err(ApiError::NotFound(
+            "KIOSK_NO_APPOINTMENT",
+            "no appointment found for today".into(),
+        ));
+    }
+
+    let mut appointments = Vec::with_capacity(rows.len());
+    for r in rows {
+        let checked_in_now = r.status != AppointmentStatus::Arrived;
+        if checked_in_now {
+            r.status.ensure_transition(AppointmentStatus::Arrived)?;
+            // updated_by_user_id NULL: the change log shows no user, the audit entry names the kiosk
+            sqlx::query(
+                r#"
+                UPDATE appointment
+                SET status = $2, arrived_at = COALESCE(arrived_at, now()), updated_at = now(), updated_by_user_id = NULL
+                WHERE appointment_id = $1
+                "#,
+            )
+            .bind(r.appointment_id)
+            .bind(AppointmentStatus::Arrived)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiError::write_failed("APPOINTMENT_UPDATE_FAILED"))?;
+
+            audit::record_kiosk(
+                &mut *tx,
+                kiosk.kiosk_id,
+                "appointment.kiosk_checkin",
+                "appointment",
+                Some(r.appointment_id),
+                serde_json::json!({ "kiosk_name": kiosk.name }),
+            )
+            .await?;
+        }
+        appointments.push(KioskAppointment {
+            first_name: r.first_name,
+            start_at: r.start_at,
+            doctor_name: r.doctor_name,
+            checked_in_now,
+        });
+    }
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: KioskCheckin { appointments } }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(register_number: Option<&str>, phone: Option<&str>) -> KioskCheckinRequest {
+        KioskCheckinRequest {
+            register_number: register_number.map(str::to_string),
+            phone: phone.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn checkin_takes_exactly_one_key() {
+        assert!(matches!(
+            checkin_key(req(Some(" АБ12345678 "), None)),
+            Ok(CheckinKey::RegisterNumber(r)) if r == "АБ12345678"
+        ));
+        assert!(matches!(
+            checkin_key(req(Some(" "), Some("+976 9911-2233"))),
+            Ok(CheckinKey::PhoneKey(k)) if k == "99112233"
+        ));
+        assert!(checkin_key(req(None, None)).is_err());
+        assert!(checkin_key(req(Some("A1"), Some("99112233"))).is_err());
+        assert!(checkin_key(req(None, Some("9911"))).is_err());
+        assert!(checkin_key(req(None, Some("+976 9911 2233 4455 667"))).is_err());
+    }
+
+    #[test]
+    fn phone_matches_with_or_without_country_code() {
+        let key = |phone: &str| match checkin_key(req(None, Some(phone))) {
+            Ok(CheckinKey::PhoneKey(k)) => k,
+            _ => panic!("{phone}"),
+        };
+        // typed either way, the key is what a stored "88112233" or "+97688112233" matches on
+        for typed in ["88112233", "+976 8811 2233", "00976-8811-2233"] {
+            assert_eq!(key(typed), "88112233");
+        }
+        for stored in ["88112233", "+97688112233"] {
+            assert_eq!(patients::phone_match_key(stored).as_deref(), Some("88112233"));
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// src/routes/medical_alert_routes.rs
+//
+// Clinical alerts (see services::medical_alerts), any staff:
+// - GET  /patients/{id}/alerts[?include_resolved=true]   active first, newest first
+// - POST /patients/{id}/alerts                           { kind, label, note? }
+// - POST /medical_alerts/{id}/resolve                    no longer applies (kept for history)
+// Active alerts gate booking: POST /appointments needs acknowledge_warnings=true.
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiList, ApiOk, AppState, MedicalAlertKind},
+    pii::PiiString,
+    services::{
+        medical_alerts::{MedicalAlertDto, ALERT_COLUMNS},
+        patients,
+    },
+};
+
+const MAX_LABEL_LEN: usize = 200;
+const MAX_NOTE_LEN: usize = 1000;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/patients/{patient_id}/alerts", get(list_alerts).post(create_alert))
+        .route("/medical_alerts/{alert_id}/resolve", post(resolve_alert))
+}
+
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListAlertsQuery {
+    pub include_resolved: Option<bool>,
+}
+
+pub async fn list_alerts(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Query(q): Query<ListAlertsQuery>,
+) -> Result<Json<ApiList<MedicalAlertDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    patients::get(&*state.repos.patients, patient_id).await?;
+
+    let rows = sqlx::query_as::<_, MedicalAlertDto>(&format!(
+        r#"
+        SELECT {ALERT_COLUMNS}
+        FROM patient_medical_alert
+        WHERE patient_id = $1
+          AND ($2 OR resolved_at IS NULL)
+        ORDER BY resolved_at IS NOT NULL, created_at DESC
+        "#
+    ))
+    .bind(patient_id)
+    .bind(q.include_resolved.unwrap_or(false))
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: rows }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAlertRequest {
+    pub kind: MedicalAlertKind,
+    /// e.g. "Lidocaine", "Warfarin"
+    pub label: String,
+    pub note: Option<String>,
+}
+
+pub async fn create_alert(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Json(req): Json<CreateAlertRequest>,
+) -> Result<Json<ApiOk<MedicalAlertDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    let label = req.label.trim();
+    if label.is_empty() || label.chars().count() > MAX_LABEL_LEN {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("label must be 1..{MAX_LABEL_LEN} chars"),
+        ));
+    }
+    let note = req.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    if note.as_deref().is_some_and(|n| n.chars().count() > MAX_NOTE_LEN) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("note must be at most {MAX_NOTE_LEN} chars"),
+        ));
+    }
+    patients::get(&*state.repos.patients, patient_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let alert = sqlx::query_as::<_, MedicalAlertDto>(&format!(
+        r#"
+        INSERT INTO patient_medical_alert (patient_id, kind, label, note, created_by_user_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING {ALERT_COLUMNS}
+        "#
+    ))
+    .bind(patient_id)
+    .bind(req.kind)
+    .bind(PiiString::from(label))
+    .bind(note.map(PiiString::from))
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("MEDICAL_ALERT_CREATE_FAILED"))?;
+
+    // the label is PII, the kind is enough for the trail
+    audit::record(
+        &mut *tx,
+        &auth,
+        "medical_alert.create",
+        "patient",
+        Some(patient_id),
+        serde_json::json!({ "alert_id": alert.alert_id, "kind": alert.kind }),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: alert }))
+}
+
+/// Resolving twice keeps the first resolution.
+pub async fn resolve_alert(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(alert_id): Path<Uuid>,
+) -> Result<Json<ApiOk<MedicalAlertDto>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let mut tx = state.db.begin().await?;
+    let alert = sqlx::query_as::<_, MedicalAlertDto>(&format!(
+        r#"
+        UPDATE patient_medical_alert
+        SET resolved_at = COALESCE(resolved_at, now()),
+            resolved_by_user_id = COALESCE(resolved_by_user_id, $2)
+        WHERE alert_id = $1
+        RETURNING {ALERT_COLUMNS}
+        "#
+    ))
+    .bind(alert_id)
+    .bind(auth.user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("MEDICAL_ALERT_UPDATE_FAILED"))?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "alert not found".into()))?;
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "medical_alert.resolve",
+        "patient",
+        Some(alert.patient_id),
+        serde_json::json!({ "alert_id": alert_id }),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: alert }))
+}
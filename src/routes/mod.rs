@@ -1,5 +1,5 @@
-use crate::models::AppState;
-use axum::Router;
+use crate::{error::ApiError, models::AppState, permissions, telemetry};
+use axum::{Router, extract::DefaultBodyLimit, middleware};
 
 pub mod auth_routes;
 pub mod home_routes;
@@ -10,19 +10,100 @@ pub mod user_routes;
 pub mod clinic_routes;
 pub mod appointment_routes;
 pub mod task_routes;
-// pub mod report_routes; maybe later
+pub mod notification_routes;
+pub mod report_routes;
+pub mod document_template_routes;
+pub mod admin_routes;
+pub mod photo_routes;
+pub mod employee_routes;
+pub mod household_routes;
+pub mod time_off_routes;
+pub mod shift_routes;
+pub mod procedure_template_routes;
+pub mod imaging_routes;
+pub mod intake_routes;
+pub mod kiosk_routes;
+pub mod display_routes;
+pub mod feedback_routes;
+pub mod medical_alert_routes;
+pub mod sync_routes;
+pub mod ws_routes;
 
+// Request body limits (JSON extractors only; GET routes are unaffected).
+// - auth: login/refresh payloads are tiny, keep brute-force bodies cheap
+// - public intake / feedback forms: unauthenticated too, a filled form is a few KB
+// - document templates: template bodies can be long
+// - photos: raw phone pictures before server-side resizing
+// - imaging: DICOM files and zipped CBCT volumes
+const DEFAULT_BODY_LIMIT: usize = 1024 * 1024;
+const AUTH_BODY_LIMIT: usize = 16 * 1024;
+const PUBLIC_FORM_BODY_LIMIT: usize = 64 * 1024;
+const DOCUMENT_BODY_LIMIT: usize = 8 * 1024 * 1024;
+const PHOTO_BODY_LIMIT: usize = 10 * 1024 * 1024;
+const IMAGING_BODY_LIMIT: usize = 64 * 1024 * 1024;
 
+/// Mounts every API version. Handlers are plain functions, so a new version
+/// reuses them and only registers what actually changed.
 pub fn router(state: AppState) -> Router {
     Router::new()
-        .nest("/api/v1/auth", auth_routes::router())
-        .nest("/api/v1/users", user_routes::router())
-        .nest("/api/v1/services", service_routes::router())
-        .nest("/api/v1", clinic_routes::router()) 
-        .nest("/api/v1", patient_comm_routes::router())
-        .nest("/api/v1", patient_routes::router())
-        .nest("/api/v1", appointment_routes::router()) //fixed here
-        .nest("/api/v1", task_routes::router())
+        .nest("/api/v1", api_v1(state.clone()))
+        .nest("/api/v2", api_v2(state.clone()))
         .merge(home_routes::router())
+        .fallback(not_found)
+        .layer(DefaultBodyLimit::max(DEFAULT_BODY_LIMIT))
         .with_state(state)
 }
+
+fn api_v1(state: AppState) -> Router<AppState> {
+    Router::new()
+        .nest(
+            "/auth",
+            auth_routes::router().layer(DefaultBodyLimit::max(AUTH_BODY_LIMIT)),
+        )
+        .nest("/users", user_routes::router())
+        .nest("/admin", admin_routes::router())
+        .nest("/services", service_routes::router())
+        .merge(clinic_routes::router())
+        .merge(patient_comm_routes::router())
+        .merge(patient_routes::router())
+        .merge(appointment_routes::router())
+        .merge(task_routes::router())
+        .merge(notification_routes::router())
+        .merge(report_routes::router())
+        .merge(document_template_routes::router().layer(DefaultBodyLimit::max(DOCUMENT_BODY_LIMIT)))
+        .merge(photo_routes::router().layer(DefaultBodyLimit::max(PHOTO_BODY_LIMIT)))
+        .merge(employee_routes::router())
+        .merge(household_routes::router())
+        .merge(time_off_routes::router())
+        .merge(shift_routes::router())
+        .merge(procedure_template_routes::router())
+        .merge(imaging_routes::router().layer(DefaultBodyLimit::max(IMAGING_BODY_LIMIT)))
+        .merge(intake_routes::router())
+        .merge(medical_alert_routes::router())
+        .merge(sync_routes::router())
+        .merge(ws_routes::router())
+        .merge(intake_routes::public_router().layer(DefaultBodyLimit::max(PUBLIC_FORM_BODY_LIMIT)))
+        .merge(kiosk_routes::router())
+        .merge(display_routes::router())
+        .merge(feedback_routes::public_router().layer(DefaultBodyLimit::max(PUBLIC_FORM_BODY_LIMIT)))
+        // role gate from permissions::ROUTES, on matched routes only (404s stay 404s)
+        .route_layer(middleware::from_fn_with_state(state, permissions::enforce))
+        .route_layer(middleware::from_fn(telemetry::record_route))
+        // v2 falls through to a standalone v1 router, so v1 needs its own fallback
+        .fallback(not_found)
+}
+
+/// v2 = v2-specific routes, everything else falls through to v1.
+/// To change an endpoint in v2, register it here (same path); v1 keeps the old one.
+fn api_v2(state: AppState) -> Router<AppState> {
+    Router::new()
+        .merge(patient_comm_routes::router_v2())
+        .route_layer(middleware::from_fn_with_state(state.clone(), permissions::enforce))
+        .route_layer(middleware::from_fn(telemetry::record_route))
+        .fallback_service(api_v1(state.clone()).with_state(state))
+}
+
+/// Unknown routes get the usual error envelope instead of an empty 404.
+async fn not_found() -> ApiError {
+    ApiError::NotFound("NOT_FOUND", "no such route".into())
+}
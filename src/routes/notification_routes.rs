@@ -0,0 +1,160 @@
+// src/routes/notification_routes.rs
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiOk, AppState, OkData},
+};
+
+/* ============================================================
+   Router
+   ============================================================ */
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/notifications", get(list_my_notifications))
+        .route("/notifications/read_all", post(mark_all_read))
+        .route("/notifications/{notification_id}/read", post(mark_read))
+}
+
+/* ============================================================
+   DTOs
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct NotificationDto {
+    pub notification_id: Uuid,
+    pub kind: String,
+    pub title: String,
+    pub body: Option<String>,
+    pub task_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationList {
+    pub items: Vec<NotificationDto>,
+    pub unread_count: i64,
+}
+
+/* ============================================================
+   Helpers
+   ============================================================ */
+
+async fn my_employee_id(state: &AppState, auth: &AuthContext) -> Result<Uuid, ApiError> {
+    let emp: Option<Uuid> = sqlx::query_scalar("SELECT employee_id FROM employee WHERE user_id = $1")
+        .bind(auth.user_id)
+        .fetch_optional(&state.db)
+        .await?;
+
+    emp.ok_or_else(|| {
+        ApiError::BadRequest("NO_EMPLOYEE_PROFILE", "This user has no employee profile".into())
+    })
+}
+
+/* ============================================================
+   GET /notifications
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct ListNotificationsQuery {
+    pub unread_only: Option<bool>,
+    pub limit: Option<i64>, // default 50
+}
+
+pub async fn list_my_notifications(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ListNotificationsQuery>,
+) -> Result<Json<ApiOk<NotificationList>>, ApiError> {
+    let my_emp = my_employee_id(&state, &auth).await?;
+    let limit = q.limit.unwrap_or(50).clamp(1, 200);
+
+    let items = sqlx::query_as::<_, NotificationDto>(
+        r#"
+        SELECT notification_id, kind, title, body, task_id, created_at, read_at
+        FROM notification
+        WHERE recipient_employee_id = $1
+          AND ($2 = false OR read_at IS NULL)
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(my_emp)
+    .bind(q.unread_only.unwrap_or(false))
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await?;
+
+    let unread_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM notification WHERE recipient_employee_id = $1 AND read_at IS NULL",
+    )
+    .bind(my_emp)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk {
+        data: NotificationList { items, unread_count },
+    }))
+}
+
+/* ============================================================
+   POST /notifications/{id}/read, /notifications/read_all
+   ============================================================ */
+
+pub async fn mark_read(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(notification_id): Path<Uuid>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    let my_emp = my_employee_id(&state, &auth).await?;
+
+    let res = sqlx::query(
+        r#"
+        UPDATE notification
+        SET read_at = COALESCE(read_at, now())
+        WHERE notification_id = $1 AND recipient_employee_id = $2
+        "#,
+    )
+    .bind(notification_id)
+    .bind(my_emp)
+    .execute(&state.db)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "notification not found".into()));
+    }
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
+
+pub async fn mark_all_read(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    let my_emp = my_employee_id(&state, &auth).await?;
+
+    sqlx::query(
+        r#"
+        UPDATE notification
+        SET read_at = now()
+        WHERE recipient_employee_id = $1 AND read_at IS NULL
+        "#,
+    )
+    .bind(my_emp)
+    .execute(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
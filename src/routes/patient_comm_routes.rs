@@ -2,8 +2,9 @@
 
 use axum::{
     extract::{Path, Query, State},
-    routing::{get, post},
-    Json, Router,
+    middleware,
+    routing::{any, get, post},
+    Router,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -11,15 +12,34 @@ use sqlx::QueryBuilder;
 use uuid::Uuid;
 
 use crate::{
+    audit,
+    consent::{self, ContactPurpose},
+    contact_prefs::{self, ContactPreferencesDto, ContactPreferencesRequest},
+    cursor::{self, Cursor},
     error::ApiError,
-    middleware::auth_context::AuthContext,
-    models::{AppState, OkData, OkResponse, PhoneNumberRow, SmsDirection, SmsRow},
+    extract::Json,
+    middleware::{
+        auth_context::AuthContext,
+        deprecation::{self, Deprecation},
+    },
+    models::{ApiList, ApiOk, AppState, OkData, PhoneNumberRow, Role, SmsDirection, SmsRow},
+    phone_sharing::{self, TransferDto},
+    pii::PiiString,
+    services::patients::{self, PhoneLookup},
+    sms_replies,
+    sms_segments::{self, SmsEstimate},
 };
 
 // --------------------------
 // Router
 // --------------------------
 
+/// phone_numbers_alias: deprecated 2026-10-16, removed 2027-04-01 (gone in v2)
+const PHONE_NUMBERS_ALIAS_DEPRECATION: Deprecation = Deprecation {
+    deprecated_at: 1792108800,
+    sunset_at: 1806537600,
+};
+
 pub fn router() -> Router<AppState> {
     Router::new()
         // -----------------------
@@ -29,13 +49,17 @@ pub fn router() -> Router<AppState> {
             "/patients/{patient_id}/phone_numbers",
             get(list_phone_numbers).post(add_phone_number),
         )
-        // Alias: already the same endpoint; kept for clarity / future separation.
+        // Alias: same response as /phone_numbers. Deprecated, use /phone_numbers.
         .route(
             "/patients/{patient_id}/phone_numbers_alias",
-            get(list_phone_numbers),
+            get(list_phone_numbers).layer(middleware::from_fn_with_state(
+                PHONE_NUMBERS_ALIAS_DEPRECATION,
+                deprecation::headers,
+            )),
         )
         // Phone number utility
         .route("/phone_numbers/normalize", post(normalize_phone_number))
+        .route("/phone_numbers/lookup", get(lookup_phone_number))
         // Phone number single-resource endpoints
         .route(
             "/phone_numbers/{phone_number_id}",
@@ -47,6 +71,25 @@ pub fn router() -> Router<AppState> {
             "/phone_numbers/{phone_number_id}/make_primary",
             post(make_primary),
         )
+        .route(
+            "/phone_numbers/{phone_number_id}/transfer",
+            post(transfer_phone_number),
+        )
+        // -----------------------
+        // Do-not-contact flags
+        // -----------------------
+        .route(
+            "/phone_numbers/{phone_number_id}/consent",
+            get(get_phone_consent).put(update_phone_consent),
+        )
+        .route(
+            "/patients/{patient_id}/consent",
+            get(get_patient_consent).put(update_patient_consent),
+        )
+        .route(
+            "/patients/{patient_id}/contact_preferences",
+            get(get_contact_preferences).put(update_contact_preferences),
+        )
         // -----------------------
         // SMS (per phone number)
         // -----------------------
@@ -59,8 +102,34 @@ pub fn router() -> Router<AppState> {
         // -----------------------
         .route("/sms", get(search_sms))
         .route("/sms/{sms_id}", get(get_sms).delete(delete_sms))
+        .route("/sms/{sms_id}/restore", post(restore_sms))
         .route("/sms/bulk_send", post(bulk_send_sms))
+        .route("/sms/batches/{batch_id}", get(get_sms_batch))
         .route("/sms/render", post(render_sms_template))
+        .route("/sms/estimate", post(estimate_sms))
+        // -----------------------
+        // SMS conversation (per patient, all phone numbers)
+        // -----------------------
+        .route("/patients/{patient_id}/conversation", get(get_conversation))
+        .route("/patients/{patient_id}/conversation/read", post(mark_conversation_read))
+}
+
+/// /api/v2 differences; all other paths fall through to `router()`.
+pub fn router_v2() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/patients/{patient_id}/phone_numbers_alias",
+            any(phone_numbers_alias_removed),
+        )
+        // v2: `{ data, next_cursor }` with keyset pagination instead of a bare array + offset
+        .route("/sms", get(search_sms_page))
+}
+
+async fn phone_numbers_alias_removed() -> ApiError {
+    ApiError::NotFound(
+        "NOT_FOUND",
+        "phone_numbers_alias was removed in v2; use /patients/{patient_id}/phone_numbers".into(),
+    )
 }
 
 // --------------------------
@@ -68,13 +137,16 @@ pub fn router() -> Router<AppState> {
 // --------------------------
 // roles: 0 patient, 1 admin, 2 manager, 3 doctor, 4 receptionist
 
-fn ensure_staff(_auth: &AuthContext) -> Result<(), ApiError> {
-    // tighten later. For now: any authenticated user can call.
-    Ok(())
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "staff only".into()))
+    }
 }
 
 fn ensure_admin(auth: &AuthContext) -> Result<(), ApiError> {
-    if auth.role == 1 {
+    if auth.role == Role::Admin {
         Ok(())
     } else {
         Err(ApiError::Forbidden("FORBIDDEN", "admin only".into()))
@@ -82,13 +154,22 @@ fn ensure_admin(auth: &AuthContext) -> Result<(), ApiError> {
 }
 
 fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
-    if auth.role == 1 || auth.role == 2 {
+    if auth.role.is_admin_or_manager() {
         Ok(())
     } else {
         Err(ApiError::Forbidden("FORBIDDEN", "admin/manager only".into()))
     }
 }
 
+fn ensure_front_desk(auth: &AuthContext) -> Result<(), ApiError> {
+    // roles 1..4 (staff); patients can't read other people's threads
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "staff only".into()))
+    }
+}
+
 // --------------------------
 // Phone numbers: list + add
 // --------------------------
@@ -97,7 +178,7 @@ pub async fn list_phone_numbers(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(patient_id): Path<Uuid>,
-) -> Result<Json<Vec<PhoneNumberRow>>, ApiError> {
+) -> Result<Json<ApiList<PhoneNumberRow>>, ApiError> {
     ensure_staff(&auth)?;
 
     let rows: Vec<PhoneNumberRow> = sqlx::query_as::<_, PhoneNumberRow>(
@@ -111,16 +192,15 @@ pub async fn list_phone_numbers(
           created_at,
           updated_at
         FROM phone_number
-        WHERE patient_id = $1
+        WHERE patient_id = $1 AND released_at IS NULL
         ORDER BY is_primary DESC, created_at ASC
         "#,
     )
     .bind(patient_id)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    Ok(Json(rows))
+    Ok(Json(ApiOk { data: rows }))
 }
 
 #[derive(Debug, Deserialize)]
@@ -128,6 +208,9 @@ pub struct AddPhoneNumberRequest {
     pub phone_number: String,
     pub label: String,
     pub is_primary: Option<bool>,
+    /// another patient has the number and that's fine (phone_number_sharing = confirm)
+    #[serde(default)]
+    pub shared: bool,
 }
 
 pub async fn add_phone_number(
@@ -135,7 +218,7 @@ pub async fn add_phone_number(
     auth: AuthContext,
     Path(patient_id): Path<Uuid>,
     Json(req): Json<AddPhoneNumberRequest>,
-) -> Result<Json<PhoneNumberRow>, ApiError> {
+) -> Result<Json<ApiOk<PhoneNumberRow>>, ApiError> {
     ensure_staff(&auth)?;
 
     let phone_number = normalize_e164_strict(req.phone_number.trim())?;
@@ -150,11 +233,13 @@ pub async fn add_phone_number(
 
     let is_primary = req.is_primary.unwrap_or(false);
 
+    let sharing = phone_sharing::load_policy(&state.db).await?;
+    phone_sharing::ensure_can_use(&*state.repos.patients, sharing, &phone_number, patient_id, req.shared).await?;
+
     let mut tx = state
         .db
         .begin()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
     if is_primary {
         sqlx::query(
@@ -166,8 +251,7 @@ pub async fn add_phone_number(
         )
         .bind(patient_id)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
     }
 
     let row: PhoneNumberRow = sqlx::query_as::<_, PhoneNumberRow>(
@@ -189,14 +273,12 @@ pub async fn add_phone_number(
     .bind(label)
     .bind(is_primary)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     tx.commit()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
-    Ok(Json(row))
+    Ok(Json(ApiOk { data: row }))
 }
 
 // --------------------------
@@ -208,11 +290,6 @@ pub struct NormalizeRequest {
     pub raw: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct NormalizeResponse {
-    pub data: NormalizeData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct NormalizeData {
     pub normalized: String,
@@ -222,11 +299,11 @@ pub async fn normalize_phone_number(
     State(_state): State<AppState>,
     auth: AuthContext,
     Json(req): Json<NormalizeRequest>,
-) -> Result<Json<NormalizeResponse>, ApiError> {
+) -> Result<Json<ApiOk<NormalizeData>>, ApiError> {
     ensure_staff(&auth)?;
 
     let normalized = normalize_e164_strict(req.raw.trim())?;
-    Ok(Json(NormalizeResponse {
+    Ok(Json(ApiOk {
         data: NormalizeData { normalized },
     }))
 }
@@ -234,11 +311,7 @@ pub async fn normalize_phone_number(
 fn normalize_e164_strict(raw: &str) -> Result<String, ApiError> {
     let mut s = raw.trim().to_string();
 
-    s = s.replace(' ', "")
-        .replace('-', "")
-        .replace('(', "")
-        .replace(')', "")
-        .replace('.', "");
+    s = s.replace([' ', '-', '(', ')', '.'], "");
 
     // Support "00" prefix
     if s.starts_with("00") {
@@ -270,6 +343,31 @@ fn normalize_e164_strict(raw: &str) -> Result<String, ApiError> {
     Ok(s)
 }
 
+// --------------------------
+// Phone numbers: lookup (inbound-call screen-pop, SMS sender matching)
+// --------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct LookupQuery {
+    pub number: String,
+}
+
+pub async fn lookup_phone_number(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<LookupQuery>,
+) -> Result<Json<ApiOk<PhoneLookup>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    // an unencoded "+976..." arrives as " 976..." (query strings decode '+' as a space)
+    let number = match q.number.strip_prefix(' ') {
+        Some(rest) => format!("+{}", rest.trim_start()),
+        None => q.number,
+    };
+    let data = patients::lookup_phone(&*state.repos.patients, &number).await?;
+    Ok(Json(ApiOk { data }))
+}
+
 // --------------------------
 // Phone numbers: GET one
 // --------------------------
@@ -278,7 +376,7 @@ pub async fn get_phone_number(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(phone_number_id): Path<Uuid>,
-) -> Result<Json<PhoneNumberRow>, ApiError> {
+) -> Result<Json<ApiOk<PhoneNumberRow>>, ApiError> {
     ensure_staff(&auth)?;
 
     let row: PhoneNumberRow = sqlx::query_as::<_, PhoneNumberRow>(
@@ -297,11 +395,10 @@ pub async fn get_phone_number(
     )
     .bind(phone_number_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "phone number not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "phone number not found".into()))?;
 
-    Ok(Json(row))
+    Ok(Json(ApiOk { data: row }))
 }
 
 // --------------------------
@@ -312,27 +409,25 @@ pub async fn make_primary(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(phone_number_id): Path<Uuid>,
-) -> Result<Json<PhoneNumberRow>, ApiError> {
+) -> Result<Json<ApiOk<PhoneNumberRow>>, ApiError> {
     ensure_staff(&auth)?;
 
     let mut tx = state
         .db
         .begin()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
     let patient_id: Uuid = sqlx::query_scalar(
         r#"
         SELECT patient_id
         FROM phone_number
-        WHERE phone_number_id = $1
+        WHERE phone_number_id = $1 AND released_at IS NULL
         "#,
     )
     .bind(phone_number_id)
     .fetch_optional(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "phone number not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "phone number not found".into()))?;
 
     // unset all for patient
     sqlx::query(
@@ -344,8 +439,7 @@ pub async fn make_primary(
     )
     .bind(patient_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     // set this one
     let updated: PhoneNumberRow = sqlx::query_as::<_, PhoneNumberRow>(
@@ -365,14 +459,49 @@ pub async fn make_primary(
     )
     .bind(phone_number_id)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     tx.commit()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
-    Ok(Json(updated))
+    Ok(Json(ApiOk { data: updated }))
+}
+
+// --------------------------
+// Phone numbers: transfer to another patient (phone_sharing.rs)
+// --------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct TransferPhoneNumberRequest {
+    pub to_patient_id: Uuid,
+    /// take the SMS history (and opt-outs) along; otherwise it stays with the old patient
+    #[serde(default)]
+    pub move_sms: bool,
+    #[serde(default)]
+    pub make_primary: bool,
+}
+
+pub async fn transfer_phone_number(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(phone_number_id): Path<Uuid>,
+    Json(req): Json<TransferPhoneNumberRequest>,
+) -> Result<Json<ApiOk<TransferDto>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let mut tx = state.db.begin().await?;
+    let data = phone_sharing::transfer(
+        &mut tx,
+        &auth,
+        phone_number_id,
+        req.to_patient_id,
+        req.move_sms,
+        req.make_primary,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
 }
 
 // --------------------------
@@ -384,6 +513,9 @@ pub struct UpdatePhoneNumberRequest {
     pub phone_number: Option<String>,
     pub label: Option<String>,
     pub is_primary: Option<bool>,
+    /// see AddPhoneNumberRequest
+    #[serde(default)]
+    pub shared: bool,
 }
 
 pub async fn update_phone_number(
@@ -391,7 +523,7 @@ pub async fn update_phone_number(
     auth: AuthContext,
     Path(phone_number_id): Path<Uuid>,
     Json(req): Json<UpdatePhoneNumberRequest>,
-) -> Result<Json<PhoneNumberRow>, ApiError> {
+) -> Result<Json<ApiOk<PhoneNumberRow>>, ApiError> {
     ensure_staff(&auth)?;
 
     let existing: PhoneNumberRow = sqlx::query_as::<_, PhoneNumberRow>(
@@ -405,23 +537,27 @@ pub async fn update_phone_number(
           created_at,
           updated_at
         FROM phone_number
-        WHERE phone_number_id = $1
+        WHERE phone_number_id = $1 AND released_at IS NULL
         "#,
     )
     .bind(phone_number_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "phone number not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "phone number not found".into()))?;
 
     let new_phone = match req.phone_number.as_deref().map(str::trim) {
         Some(s) if !s.is_empty() => normalize_e164_strict(s)?,
         _ => existing.phone_number.clone(),
     };
+    if new_phone != existing.phone_number {
+        let sharing = phone_sharing::load_policy(&state.db).await?;
+        phone_sharing::ensure_can_use(&*state.repos.patients, sharing, &new_phone, existing.patient_id, req.shared)
+            .await?;
+    }
 
     let new_label: String = match req.label.as_deref().map(str::trim) {
         None => existing.label.clone(),
-        Some(v) if v.is_empty() => {
+        Some("") => {
             return Err(ApiError::BadRequest(
                 "VALIDATION_ERROR",
                 "label cannot be empty".into(),
@@ -435,8 +571,7 @@ pub async fn update_phone_number(
     let mut tx = state
         .db
         .begin()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
     // update base fields
     sqlx::query(
@@ -452,8 +587,7 @@ pub async fn update_phone_number(
     .bind(&new_label)
     .bind(phone_number_id)
     .execute(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     // enforce one primary
     if want_primary {
@@ -466,8 +600,7 @@ pub async fn update_phone_number(
         )
         .bind(existing.patient_id)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
         sqlx::query(
             r#"
@@ -478,8 +611,7 @@ pub async fn update_phone_number(
         )
         .bind(phone_number_id)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
     } else if req.is_primary == Some(false) {
         sqlx::query(
             r#"
@@ -490,8 +622,7 @@ pub async fn update_phone_number(
         )
         .bind(phone_number_id)
         .execute(&mut *tx)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
     }
 
     let out: PhoneNumberRow = sqlx::query_as::<_, PhoneNumberRow>(
@@ -510,14 +641,12 @@ pub async fn update_phone_number(
     )
     .bind(phone_number_id)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     tx.commit()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
-    Ok(Json(out))
+    Ok(Json(ApiOk { data: out }))
 }
 
 // --------------------------
@@ -529,7 +658,7 @@ pub async fn delete_phone_number(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(phone_number_id): Path<Uuid>,
-) -> Result<Json<OkResponse>, ApiError> {
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
     ensure_admin_or_manager(&auth)?;
 
     // Use EXISTS -> bool to avoid scalar type decoding mismatches (prevents 500s)
@@ -544,8 +673,7 @@ pub async fn delete_phone_number(
     )
     .bind(phone_number_id)
     .fetch_one(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     if has_sms {
         return Err(ApiError::BadRequest(
@@ -562,14 +690,13 @@ pub async fn delete_phone_number(
     )
     .bind(phone_number_id)
     .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     if res.rows_affected() == 0 {
-        return Err(ApiError::BadRequest("NOT_FOUND", "phone number not found".into()));
+        return Err(ApiError::NotFound("NOT_FOUND", "phone number not found".into()));
     }
 
-    Ok(Json(OkResponse {
+    Ok(Json(ApiOk {
         data: OkData { ok: true },
     }))
 }
@@ -592,7 +719,7 @@ pub async fn add_sms(
     auth: AuthContext,
     Path(phone_number_id): Path<Uuid>,
     Json(req): Json<AddSmsRequest>,
-) -> Result<Json<SmsRow>, ApiError> {
+) -> Result<Json<ApiOk<SmsRow>>, ApiError> {
     ensure_staff(&auth)?;
 
     if req.direction != 0 && req.direction != 1 {
@@ -612,6 +739,21 @@ pub async fn add_sms(
 
     let sent_at = req.sent_at.unwrap_or_else(Utc::now);
 
+    let mut tx = state.db.begin().await?;
+
+    let released: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM phone_number WHERE phone_number_id = $1 AND released_at IS NOT NULL)",
+    )
+    .bind(phone_number_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if released {
+        return Err(ApiError::Conflict(
+            "PHONE_NUMBER_RELEASED",
+            "this number was transferred to another patient".into(),
+        ));
+    }
+
     let row: SmsRow = sqlx::query_as::<_, SmsRow>(
         r#"
         INSERT INTO sms (phone_number_id, direction, sent_at, subject, sms_text, note)
@@ -631,21 +773,77 @@ pub async fn add_sms(
     .bind(req.direction)
     .bind(sent_at)
     .bind(req.subject.as_deref())
-    .bind(sms_text)
+    .bind(PiiString::from(sms_text))
     .bind(req.note.as_deref())
-    .fetch_one(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .fetch_one(&mut *tx)
+    .await?;
 
-    Ok(Json(row))
+    // inbound "STOP": no more messages of any kind to this number
+    if req.direction == 0 && consent::is_stop_request(sms_text) {
+        let opted_out = sqlx::query(
+            r#"
+            UPDATE phone_number
+            SET marketing_opt_out_at = COALESCE(marketing_opt_out_at, now()),
+                transactional_opt_out_at = COALESCE(transactional_opt_out_at, now()),
+                updated_at = now()
+            WHERE phone_number_id = $1
+              AND (marketing_opt_out_at IS NULL OR transactional_opt_out_at IS NULL)
+            "#,
+        )
+        .bind(phone_number_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if opted_out > 0 {
+            audit::record(
+                &mut *tx,
+                &auth,
+                "contact.opt_out",
+                "phone_number",
+                Some(phone_number_id),
+                serde_json::json!({ "source": "inbound_stop", "sms_id": row.sms_id }),
+            )
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    // replies to reminders ("1" = confirm, ...); the SMS is stored either way
+    if req.direction == 0
+        && !consent::is_stop_request(sms_text)
+        && let Err(e) = sms_replies::handle_inbound(&state, &auth, phone_number_id, row.sms_id, sms_text).await
+    {
+        tracing::warn!("sms {}: reply not processed: {e:?}", row.sms_id);
+    }
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IncludeDeletedQuery {
+    /// admin only: soft-deleted messages too
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+/// Soft-deleted SMS (migration 061) are for admins' eyes only.
+fn include_deleted(auth: &AuthContext, requested: bool) -> Result<bool, ApiError> {
+    if requested {
+        ensure_admin(auth)?;
+    }
+    Ok(requested)
 }
 
 pub async fn list_sms_for_phone(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(phone_number_id): Path<Uuid>,
-) -> Result<Json<Vec<SmsRow>>, ApiError> {
+    Query(q): Query<IncludeDeletedQuery>,
+) -> Result<Json<ApiList<SmsRow>>, ApiError> {
     ensure_staff(&auth)?;
+    let include_deleted = include_deleted(&auth, q.include_deleted)?;
 
     let rows: Vec<SmsRow> = sqlx::query_as::<_, SmsRow>(
         r#"
@@ -657,18 +855,21 @@ pub async fn list_sms_for_phone(
           subject,
           sms_text,
           note,
-          created_at
+          created_at,
+          deleted_at,
+          deleted_by_user_id
         FROM sms
         WHERE phone_number_id = $1
+          AND ($2 OR deleted_at IS NULL)
         ORDER BY sent_at DESC
         "#,
     )
     .bind(phone_number_id)
+    .bind(include_deleted)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    Ok(Json(rows))
+    Ok(Json(ApiOk { data: rows }))
 }
 
 // ============================================================================
@@ -679,8 +880,10 @@ pub async fn get_sms(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(sms_id): Path<Uuid>,
-) -> Result<Json<SmsRow>, ApiError> {
+    Query(q): Query<IncludeDeletedQuery>,
+) -> Result<Json<ApiOk<SmsRow>>, ApiError> {
     ensure_staff(&auth)?;
+    let include_deleted = include_deleted(&auth, q.include_deleted)?;
 
     let row: SmsRow = sqlx::query_as::<_, SmsRow>(
         r#"
@@ -692,18 +895,21 @@ pub async fn get_sms(
           subject,
           sms_text,
           note,
-          created_at
+          created_at,
+          deleted_at,
+          deleted_by_user_id
         FROM sms
         WHERE sms_id = $1
+          AND ($2 OR deleted_at IS NULL)
         "#,
     )
     .bind(sms_id)
+    .bind(include_deleted)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "sms not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "sms not found".into()))?;
 
-    Ok(Json(row))
+    Ok(Json(ApiOk { data: row }))
 }
 
 #[derive(Debug, Deserialize)]
@@ -715,28 +921,27 @@ pub struct SmsSearchQuery {
     pub to: Option<DateTime<Utc>>,
     pub q: Option<String>,
     pub limit: Option<i64>,
+    /// v1 only
     pub offset: Option<i64>,
+    /// v2 only: `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// admin only: soft-deleted messages too
+    #[serde(default)]
+    pub include_deleted: bool,
 }
 
-pub async fn search_sms(
-    State(state): State<AppState>,
-    auth: AuthContext,
-    Query(q): Query<SmsSearchQuery>,
-) -> Result<Json<Vec<SmsRow>>, ApiError> {
-    ensure_staff(&auth)?;
-
-    if let Some(d) = q.direction {
-        if d != 0 && d != 1 {
-            return Err(ApiError::BadRequest(
-                "VALIDATION_ERROR",
-                "direction must be 0 or 1".into(),
-            ));
-        }
+/// SELECT + WHERE of the SMS search (shared by v1 and v2); callers add ORDER BY/LIMIT.
+fn sms_search_query(auth: &AuthContext, q: &SmsSearchQuery) -> Result<QueryBuilder<'static, sqlx::Postgres>, ApiError> {
+    let include_deleted = include_deleted(auth, q.include_deleted)?;
+    if let Some(d) = q.direction
+        && d != 0 && d != 1
+    {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "direction must be 0 or 1".into(),
+        ));
     }
 
-    let limit = q.limit.unwrap_or(50).clamp(1, 200);
-    let offset = q.offset.unwrap_or(0).max(0);
-
     // Use QueryBuilder for safe dynamic SQL
     let mut qb: QueryBuilder<sqlx::Postgres> = QueryBuilder::new(
         r#"
@@ -748,7 +953,9 @@ pub async fn search_sms(
           s.subject,
           s.sms_text,
           s.note,
-          s.created_at
+          s.created_at,
+          s.deleted_at,
+          s.deleted_by_user_id
         FROM sms s
         "#,
     );
@@ -760,6 +967,10 @@ pub async fn search_sms(
 
     qb.push(" WHERE 1=1 ");
 
+    if !include_deleted {
+        qb.push(" AND s.deleted_at IS NULL ");
+    }
+
     if let Some(pid) = q.patient_id {
         qb.push(" AND pn.patient_id = ");
         qb.push_bind(pid);
@@ -782,15 +993,29 @@ pub async fn search_sms(
     }
     if let Some(keyword) = q.q.as_ref().map(|s| s.trim()).filter(|s| !s.is_empty()) {
         let like = format!("%{}%", keyword);
-    
+
+        // encrypted sms_text (see pii.rs) only matches via subject
         qb.push(" AND (s.sms_text ILIKE ");
         qb.push_bind(like.clone());   // bind owned
         qb.push(" OR s.subject ILIKE ");
         qb.push_bind(like);           // move owned
         qb.push(") ");
     }
-    
 
+    Ok(qb)
+}
+
+pub async fn search_sms(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<SmsSearchQuery>,
+) -> Result<Json<ApiList<SmsRow>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let limit = q.limit.unwrap_or(50).clamp(1, 200);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    let mut qb = sms_search_query(&auth, &q)?;
     qb.push(" ORDER BY s.sent_at DESC ");
     qb.push(" LIMIT ");
     qb.push_bind(limit);
@@ -799,41 +1024,124 @@ pub async fn search_sms(
 
     let rows: Vec<SmsRow> = qb
         .build_query_as::<SmsRow>()
-        .fetch_all(&state.db)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .fetch_all(state.read_db())
+        .await?;
 
-    Ok(Json(rows))
+    Ok(Json(ApiOk { data: rows }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct SmsPageResponse {
+    pub data: Vec<SmsRow>,
+    /// pass back as `cursor` for the next page; None = last page
+    pub next_cursor: Option<String>,
+}
+
+/// GET /api/v2/sms: same filters as v1, newest first, cursor instead of offset.
+pub async fn search_sms_page(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<SmsSearchQuery>,
+) -> Result<Json<SmsPageResponse>, ApiError> {
+    ensure_staff(&auth)?;
+
+    if q.offset.is_some() {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "offset is not supported in v2; use cursor".into(),
+        ));
+    }
+    let limit = q.limit.unwrap_or(50).clamp(1, 200);
+    let after = q.cursor.as_deref().map(Cursor::decode).transpose()?;
+
+    let mut qb = sms_search_query(&auth, &q)?;
+    if let Some(c) = after {
+        qb.push(" AND (s.sent_at, s.sms_id) < (");
+        qb.push_bind(c.at);
+        qb.push(", ");
+        qb.push_bind(c.id);
+        qb.push(") ");
+    }
+    qb.push(" ORDER BY s.sent_at DESC, s.sms_id DESC LIMIT ");
+    qb.push_bind(limit + 1);
+
+    let rows: Vec<SmsRow> = qb
+        .build_query_as::<SmsRow>()
+        .fetch_all(state.read_db())
+        .await?;
+
+    let (data, next_cursor) = cursor::page(rows, limit, |r| Cursor { at: r.sent_at, id: r.sms_id });
+    Ok(Json(SmsPageResponse { data, next_cursor }))
 }
 
 pub async fn delete_sms(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(sms_id): Path<Uuid>,
-) -> Result<Json<OkResponse>, ApiError> {
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
     // Spec: admin-only delete
     ensure_admin(&auth)?;
 
+    // soft delete: communication history is kept (migration 061)
+    let mut tx = state.db.begin().await?;
     let res = sqlx::query(
         r#"
-        DELETE FROM sms
-        WHERE sms_id = $1
+        UPDATE sms
+        SET deleted_at = now(), deleted_by_user_id = $2
+        WHERE sms_id = $1 AND deleted_at IS NULL
         "#,
     )
     .bind(sms_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await?;
 
     if res.rows_affected() == 0 {
-        return Err(ApiError::BadRequest("NOT_FOUND", "sms not found".into()));
+        return Err(ApiError::NotFound("NOT_FOUND", "sms not found".into()));
     }
+    audit::record(&mut *tx, &auth, "sms.delete", "sms", Some(sms_id), serde_json::json!({})).await?;
+    tx.commit().await?;
 
-    Ok(Json(OkResponse {
+    Ok(Json(ApiOk {
         data: OkData { ok: true },
     }))
 }
 
+/// POST /sms/{sms_id}/restore (admin): undoes a soft delete.
+pub async fn restore_sms(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(sms_id): Path<Uuid>,
+) -> Result<Json<ApiOk<SmsRow>>, ApiError> {
+    ensure_admin(&auth)?;
+
+    let mut tx = state.db.begin().await?;
+    let row: SmsRow = sqlx::query_as::<_, SmsRow>(
+        r#"
+        UPDATE sms
+        SET deleted_at = NULL, deleted_by_user_id = NULL
+        WHERE sms_id = $1 AND deleted_at IS NOT NULL
+        RETURNING
+          sms_id,
+          phone_number_id,
+          direction,
+          sent_at,
+          subject,
+          sms_text,
+          note,
+          created_at
+        "#,
+    )
+    .bind(sms_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "no deleted sms with this id".into()))?;
+    audit::record(&mut *tx, &auth, "sms.restore", "sms", Some(sms_id), serde_json::json!({})).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
 // ============================================================================
 // SMS bulk_send: store rows only (direction=Send)
 // ============================================================================
@@ -843,11 +1151,9 @@ pub struct BulkSendRequest {
     pub phone_number_ids: Vec<Uuid>,
     pub text: String,
     pub dry_run: Option<bool>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct BulkSendResponse {
-    pub data: BulkSendData,
+    /// default marketing; recipients opted out for it are skipped
+    #[serde(default)]
+    pub purpose: ContactPurpose,
 }
 
 #[derive(Debug, Serialize)]
@@ -855,16 +1161,21 @@ pub struct BulkSendData {
     pub dry_run: bool,
     pub requested: usize,
     pub valid: usize,
-    pub created: usize,
+    /// the queued batch (GET /sms/batches/{id}); None on dry runs and without valid recipients
+    pub batch_id: Option<Uuid>,
     pub invalid_phone_number_ids: Vec<Uuid>,
-    pub sms_rows: Vec<SmsRow>,
+    /// exist, but the number or its patient opted out for this purpose
+    pub opted_out_phone_number_ids: Vec<Uuid>,
+    /// dry run only: encoding/segments for the valid recipients
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<SmsEstimate>,
 }
 
 pub async fn bulk_send_sms(
     State(state): State<AppState>,
     auth: AuthContext,
     Json(req): Json<BulkSendRequest>,
-) -> Result<Json<BulkSendResponse>, ApiError> {
+) -> Result<Json<ApiOk<BulkSendData>>, ApiError> {
     ensure_staff(&auth)?;
 
     let dry_run = req.dry_run.unwrap_or(false);
@@ -890,93 +1201,351 @@ pub async fn bulk_send_sms(
         ));
     }
 
-    // Validate IDs exist
-    let existing_ids: Vec<Uuid> = sqlx::query_scalar(
+    // Validate IDs exist, split off the opted-out ones
+    let existing: Vec<(Uuid, bool)> = sqlx::query_as(&format!(
         r#"
-        SELECT phone_number_id
-        FROM phone_number
-        WHERE phone_number_id = ANY($1)
+        SELECT ph.phone_number_id, {allowed}
+        FROM phone_number ph
+        JOIN patient p ON p.patient_id = ph.patient_id
+        WHERE ph.phone_number_id = ANY($1)
+          AND ph.released_at IS NULL
         "#,
-    )
+        allowed = consent::allowed_sql(req.purpose),
+    ))
     .bind(&req.phone_number_ids)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     let mut invalid = Vec::new();
     for id in &req.phone_number_ids {
-        if !existing_ids.contains(id) {
+        if !existing.iter().any(|(e, _)| e == id) {
             invalid.push(*id);
         }
     }
 
-    let valid_ids = existing_ids;
+    let (valid_ids, opted_out): (Vec<_>, Vec<_>) = existing.into_iter().partition(|(_, allowed)| *allowed);
+    let valid_ids: Vec<Uuid> = valid_ids.into_iter().map(|(id, _)| id).collect();
+    let opted_out: Vec<Uuid> = opted_out.into_iter().map(|(id, _)| id).collect();
     let valid_count = valid_ids.len();
 
     if dry_run {
-        return Ok(Json(BulkSendResponse {
+        return Ok(Json(ApiOk {
             data: BulkSendData {
                 dry_run: true,
                 requested: req.phone_number_ids.len(),
                 valid: valid_count,
-                created: 0,
+                batch_id: None,
                 invalid_phone_number_ids: invalid,
-                sms_rows: vec![],
+                opted_out_phone_number_ids: opted_out,
+                estimate: Some(sms_segments::estimate(text, valid_count, state.sms_segment_price_cents)),
             },
         }));
     }
 
-    let mut tx = state
-        .db
-        .begin()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    let mut created_rows: Vec<SmsRow> = Vec::with_capacity(valid_count);
-
-    // Insert one row per recipient
-    for pnid in valid_ids {
-        let row: SmsRow = sqlx::query_as::<_, SmsRow>(
+    // sent by jobs::sms_batches; recipients are checked again there
+    let batch_id = if valid_ids.is_empty() {
+        None
+    } else {
+        let mut tx = state.db.begin().await?;
+        let batch_id: Uuid = sqlx::query_scalar(
             r#"
-            INSERT INTO sms (phone_number_id, direction, sent_at, subject, sms_text, note)
-            VALUES ($1, $2, now(), NULL, $3, NULL)
-            RETURNING
-              sms_id,
-              phone_number_id,
-              direction,
-              sent_at,
-              subject,
-              sms_text,
-              note,
-              created_at
+            INSERT INTO sms_batch (sms_text, purpose, requested, created_by_user_id)
+            VALUES ($1, $2, $3, $4)
+            RETURNING batch_id
             "#,
         )
-        .bind(pnid)
-        .bind(SmsDirection::Send as i16)
-        .bind(text)
+        .bind(PiiString::from(text))
+        .bind(req.purpose.as_str())
+        .bind(req.phone_number_ids.len() as i32)
+        .bind(auth.user_id)
         .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
-        created_rows.push(row);
-    }
+        sqlx::query(
+            r#"
+            INSERT INTO sms_batch_recipient (batch_id, phone_number_id)
+            SELECT $1, t.phone_number_id
+            FROM UNNEST($2::uuid[]) AS t(phone_number_id)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(batch_id)
+        .bind(&valid_ids)
+        .execute(&mut *tx)
+        .await?;
 
-    tx.commit()
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        tx.commit().await?;
+        Some(batch_id)
+    };
 
-    Ok(Json(BulkSendResponse {
+    Ok(Json(ApiOk {
         data: BulkSendData {
             dry_run: false,
             requested: req.phone_number_ids.len(),
             valid: valid_count,
-            created: created_rows.len(),
+            batch_id,
             invalid_phone_number_ids: invalid,
-            sms_rows: created_rows,
+            opted_out_phone_number_ids: opted_out,
+            estimate: None,
         },
     }))
 }
 
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SmsBatchFailure {
+    pub phone_number_id: Uuid,
+    pub error: Option<String>, // "opted_out" | "number_released"
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SmsBatchData {
+    pub batch_id: Uuid,
+    pub status: String, // "queued" | "sending" | "done"
+    pub purpose: String,
+    pub requested: i32,
+    /// recipients queued (valid ids, duplicates removed)
+    pub total: i64,
+    /// not processed yet, including recipients waiting out their quiet hours
+    pub pending: i64,
+    pub sent: i64,
+    pub failed: i64,
+    #[sqlx(skip)]
+    pub failures: Vec<SmsBatchFailure>,
+    pub created_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// GET /sms/batches/{batch_id}: progress of a bulk_send.
+pub async fn get_sms_batch(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(batch_id): Path<Uuid>,
+) -> Result<Json<ApiOk<SmsBatchData>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let mut data: SmsBatchData = sqlx::query_as(
+        r#"
+        SELECT
+          b.batch_id, b.status, b.purpose, b.requested, b.created_by_user_id,
+          b.created_at, b.started_at, b.finished_at,
+          COUNT(r.phone_number_id) AS total,
+          COUNT(r.phone_number_id) FILTER (WHERE r.status = 'pending') AS pending,
+          COUNT(r.phone_number_id) FILTER (WHERE r.status = 'sent') AS sent,
+          COUNT(r.phone_number_id) FILTER (WHERE r.status = 'failed') AS failed
+        FROM sms_batch b
+        LEFT JOIN sms_batch_recipient r ON r.batch_id = b.batch_id
+        WHERE b.batch_id = $1
+        GROUP BY b.batch_id
+        "#,
+    )
+    .bind(batch_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "sms batch not found".into()))?;
+
+    data.failures = sqlx::query_as(
+        r#"
+        SELECT phone_number_id, error
+        FROM sms_batch_recipient
+        WHERE batch_id = $1 AND status = 'failed'
+        ORDER BY processed_at, phone_number_id
+        "#,
+    )
+    .bind(batch_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+// ============================================================================
+// Do-not-contact flags (phone number / patient)
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ConsentRow {
+    /// None = allowed
+    pub marketing_opt_out_at: Option<DateTime<Utc>>,
+    pub transactional_opt_out_at: Option<DateTime<Utc>>,
+}
+
+/// `true` = may be contacted, `false` = opt out; omitted = unchanged
+#[derive(Debug, Deserialize)]
+pub struct UpdateConsentRequest {
+    pub marketing: Option<bool>,
+    pub transactional: Option<bool>,
+}
+
+/// (table, id column, audit target type)
+const PHONE_CONSENT: (&str, &str, &str) = ("phone_number", "phone_number_id", "phone_number");
+const PATIENT_CONSENT: (&str, &str, &str) = ("patient", "patient_id", "patient");
+
+async fn fetch_consent(
+    state: &AppState,
+    (table, id_col, _): (&str, &str, &str),
+    id: Uuid,
+) -> Result<ConsentRow, ApiError> {
+    sqlx::query_as::<_, ConsentRow>(&format!(
+        "SELECT marketing_opt_out_at, transactional_opt_out_at FROM {table} WHERE {id_col} = $1"
+    ))
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", format!("{table} not found")))
+}
+
+async fn update_consent(
+    state: &AppState,
+    auth: &AuthContext,
+    (table, id_col, target_type): (&str, &str, &str),
+    id: Uuid,
+    req: &UpdateConsentRequest,
+) -> Result<ConsentRow, ApiError> {
+    ensure_front_desk(auth)?;
+
+    let mut tx = state.db.begin().await?;
+
+    // an existing opt-out keeps its original timestamp
+    let row = sqlx::query_as::<_, ConsentRow>(&format!(
+        r#"
+        UPDATE {table}
+        SET
+          marketing_opt_out_at = CASE
+            WHEN $2::boolean IS NULL THEN marketing_opt_out_at
+            WHEN $2 THEN NULL
+            ELSE COALESCE(marketing_opt_out_at, now())
+          END,
+          transactional_opt_out_at = CASE
+            WHEN $3::boolean IS NULL THEN transactional_opt_out_at
+            WHEN $3 THEN NULL
+            ELSE COALESCE(transactional_opt_out_at, now())
+          END
+        WHERE {id_col} = $1
+        RETURNING marketing_opt_out_at, transactional_opt_out_at
+        "#
+    ))
+    .bind(id)
+    .bind(req.marketing)
+    .bind(req.transactional)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", format!("{table} not found")))?;
+
+    audit::record(
+        &mut *tx,
+        auth,
+        "contact.consent",
+        target_type,
+        Some(id),
+        serde_json::json!({ "marketing": req.marketing, "transactional": req.transactional }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(row)
+}
+
+pub async fn get_phone_consent(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(phone_number_id): Path<Uuid>,
+) -> Result<Json<ApiOk<ConsentRow>>, ApiError> {
+    ensure_front_desk(&auth)?;
+    let data = fetch_consent(&state, PHONE_CONSENT, phone_number_id).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+pub async fn update_phone_consent(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(phone_number_id): Path<Uuid>,
+    Json(req): Json<UpdateConsentRequest>,
+) -> Result<Json<ApiOk<ConsentRow>>, ApiError> {
+    let data = update_consent(&state, &auth, PHONE_CONSENT, phone_number_id, &req).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+/// Patient-level flags only; a number can also be opted out on its own.
+pub async fn get_patient_consent(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+) -> Result<Json<ApiOk<ConsentRow>>, ApiError> {
+    ensure_front_desk(&auth)?;
+    let data = fetch_consent(&state, PATIENT_CONSENT, patient_id).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+pub async fn update_patient_consent(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Json(req): Json<UpdateConsentRequest>,
+) -> Result<Json<ApiOk<ConsentRow>>, ApiError> {
+    let data = update_consent(&state, &auth, PATIENT_CONSENT, patient_id, &req).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+// ============================================================================
+// Contact preferences: channel, language, quiet hours (see contact_prefs)
+// ============================================================================
+
+pub async fn get_contact_preferences(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+) -> Result<Json<ApiOk<ContactPreferencesDto>>, ApiError> {
+    ensure_front_desk(&auth)?;
+    let data = contact_prefs::load(&state.db, patient_id).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+pub async fn update_contact_preferences(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Json(req): Json<ContactPreferencesRequest>,
+) -> Result<Json<ApiOk<ContactPreferencesDto>>, ApiError> {
+    ensure_front_desk(&auth)?;
+    let prefs = req.validate()?;
+    let data = contact_prefs::save(&state.db, &auth, patient_id, &prefs).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+// ============================================================================
+// SMS estimate: encoding + segments before sending
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct EstimateSmsRequest {
+    pub text: String,
+    /// default 1
+    pub recipients: Option<usize>,
+}
+
+pub async fn estimate_sms(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<EstimateSmsRequest>,
+) -> Result<Json<ApiOk<SmsEstimate>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    // same trimming as bulk_send, so the numbers match what gets stored
+    let text = req.text.trim();
+    if text.is_empty() {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "text is required".into(),
+        ));
+    }
+
+    Ok(Json(ApiOk {
+        data: sms_segments::estimate(text, req.recipients.unwrap_or(1), state.sms_segment_price_cents),
+    }))
+}
+
 // ============================================================================
 // SMS render: simple placeholder replacement (no schema change)
 // ============================================================================
@@ -987,11 +1556,6 @@ pub struct RenderTemplateRequest {
     pub patient_id: Uuid,
 }
 
-#[derive(Debug, Serialize)]
-pub struct RenderTemplateResponse {
-    pub data: RenderTemplateData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct RenderTemplateData {
     pub rendered: String,
@@ -1008,7 +1572,7 @@ pub async fn render_sms_template(
     State(state): State<AppState>,
     auth: AuthContext,
     Json(req): Json<RenderTemplateRequest>,
-) -> Result<Json<RenderTemplateResponse>, ApiError> {
+) -> Result<Json<ApiOk<RenderTemplateData>>, ApiError> {
     ensure_staff(&auth)?;
 
     let tpl = req.template.trim().to_string();
@@ -1028,9 +1592,8 @@ pub async fn render_sms_template(
     )
     .bind(req.patient_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "patient not found".into()))?;
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "patient not found".into()))?;
 
     let full_name = format!("{} {}", p.first_name, p.last_name);
 
@@ -1042,7 +1605,174 @@ pub async fn render_sms_template(
         .replace("{last_name}", &p.last_name)
         .replace("{register_number}", &p.register_number);
 
-    Ok(Json(RenderTemplateResponse {
+    Ok(Json(ApiOk {
         data: RenderTemplateData { rendered },
     }))
 }
+
+// ============================================================================
+// SMS conversation: every phone number of a patient as one thread
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ConversationMessage {
+    pub sms_id: Uuid,
+    pub phone_number_id: Uuid,
+    pub phone_number: String,
+    pub phone_label: String,
+    pub direction: SmsDirection,
+    pub sent_at: DateTime<Utc>,
+    pub subject: Option<String>,
+    pub sms_text: PiiString,
+    pub note: Option<String>,
+    pub read_at: Option<DateTime<Utc>>,
+    /// incoming and nobody marked it read yet
+    pub unread: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConversationQuery {
+    pub limit: Option<i64>,
+    /// `next_cursor` of the previous page (older messages)
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConversationData {
+    pub patient_id: Uuid,
+    /// oldest first within the page; the first page is the most recent one
+    pub messages: Vec<ConversationMessage>,
+    /// unread incoming messages in the whole thread
+    pub unread_count: i64,
+    /// older messages; None = start of the thread
+    pub next_cursor: Option<String>,
+}
+
+async fn ensure_patient_exists(state: &AppState, patient_id: Uuid) -> Result<(), ApiError> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM patient WHERE patient_id = $1)")
+        .bind(patient_id)
+        .fetch_one(&state.db)
+        .await?;
+    if !exists {
+        return Err(ApiError::NotFound("NOT_FOUND", "patient not found".into()));
+    }
+    Ok(())
+}
+
+/// GET /patients/{id}/conversation?limit=&cursor=
+/// Reading doesn't mark anything; the client calls /conversation/read once shown.
+pub async fn get_conversation(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Query(q): Query<ConversationQuery>,
+) -> Result<Json<ApiOk<ConversationData>>, ApiError> {
+    ensure_front_desk(&auth)?;
+    ensure_patient_exists(&state, patient_id).await?;
+
+    let limit = q.limit.unwrap_or(50).clamp(1, 200);
+    let after = q.cursor.as_deref().map(Cursor::decode).transpose()?;
+
+    let rows: Vec<ConversationMessage> = sqlx::query_as::<_, ConversationMessage>(
+        r#"
+        SELECT
+          s.sms_id,
+          s.phone_number_id,
+          pn.phone_number,
+          pn.label AS phone_label,
+          s.direction,
+          s.sent_at,
+          s.subject,
+          s.sms_text,
+          s.note,
+          s.read_at,
+          (s.direction = 0 AND s.read_at IS NULL) AS unread
+        FROM sms s
+        JOIN phone_number pn ON pn.phone_number_id = s.phone_number_id
+        WHERE pn.patient_id = $1
+          AND s.deleted_at IS NULL
+          AND ($2::timestamptz IS NULL OR (s.sent_at, s.sms_id) < ($2, $3))
+        ORDER BY s.sent_at DESC, s.sms_id DESC
+        LIMIT $4
+        "#,
+    )
+    .bind(patient_id)
+    .bind(after.map(|c| c.at))
+    .bind(after.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let (mut messages, next_cursor) = cursor::page(rows, limit, |m| Cursor { at: m.sent_at, id: m.sms_id });
+    messages.reverse();
+
+    let unread_count: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM sms s
+        JOIN phone_number pn ON pn.phone_number_id = s.phone_number_id
+        WHERE pn.patient_id = $1
+          AND s.deleted_at IS NULL
+          AND s.direction = 0
+          AND s.read_at IS NULL
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk {
+        data: ConversationData {
+            patient_id,
+            messages,
+            unread_count,
+            next_cursor,
+        },
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarkConversationReadRequest {
+    /// mark incoming messages sent up to this time; default now
+    pub up_to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkConversationReadData {
+    pub marked: u64,
+}
+
+/// POST /patients/{id}/conversation/read
+pub async fn mark_conversation_read(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Json(req): Json<MarkConversationReadRequest>,
+) -> Result<Json<ApiOk<MarkConversationReadData>>, ApiError> {
+    ensure_front_desk(&auth)?;
+    ensure_patient_exists(&state, patient_id).await?;
+
+    let marked = sqlx::query(
+        r#"
+        UPDATE sms s
+        SET read_at = now(), read_by_user_id = $2
+        FROM phone_number pn
+        WHERE pn.phone_number_id = s.phone_number_id
+          AND pn.patient_id = $1
+          AND s.deleted_at IS NULL
+          AND s.direction = 0
+          AND s.read_at IS NULL
+          AND s.sent_at <= COALESCE($3, now())
+        "#,
+    )
+    .bind(patient_id)
+    .bind(auth.user_id)
+    .bind(req.up_to)
+    .execute(&state.db)
+    .await?
+    .rows_affected();
+
+    Ok(Json(ApiOk {
+        data: MarkConversationReadData { marked },
+    }))
+}
@@ -2,73 +2,45 @@
 
 use axum::{
     extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
     routing::{get, post},
-    Json, Router,
+    Router,
 };
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
     error::ApiError,
+    extract::Json,
+    jobs::no_show_risk::{self, NoShowRisk},
     middleware::auth_context::AuthContext,
-    models::AppState,
+    models::{ApiList, ApiOk, AppState, Gender, Role},
+    numbering::{self, NumberCheckDto},
+    routes::household_routes::{self, HouseholdDto},
+    routes::imaging_routes::{self, ImagingOrderDto},
+    routes::intake_routes::{self, IntakeDto, MedicalHistoryDto},
+    pii::PiiString,
+    services::medical_alerts::{MedicalAlertDto, ALERT_COLUMNS},
+    services::patients::{self, NewPatient, PatientDeletionRow, PatientPatch, PatientRow},
 };
 
-// use axum::routing::patch;
-// use serde_json::json;
-
-#[derive(Debug, Serialize, sqlx::FromRow)]
-pub struct PatientRow {
-    pub patient_id: Uuid,
-    pub register_number: String,
-    pub user_id: Option<Uuid>,
-    pub first_name: String,
-    pub last_name: String,
-    pub email: Option<String>,
-    pub birthday: Option<chrono::NaiveDate>,
-    pub gender: i16,
-    pub status: i16,
-    pub created_at: chrono::DateTime<chrono::Utc>,
-    pub last_seen_at: Option<chrono::DateTime<chrono::Utc>>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct CreatePatientRequest {
-    pub register_number: Option<String>, // allow override, otherwise DB default generates it
-    pub first_name: String,
-    pub last_name: String,
-    pub email: Option<String>,
-    pub birthday: Option<chrono::NaiveDate>,
-    pub gender: i16, // 0,1,2
-    pub status: Option<i16>, // default 0
-}
-
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/patients", post(create_patient).get(search_patients))
+        .route("/patients/check_register_number", get(check_register_number))
         .route("/patients/{patient_id}", get(get_patient).patch(update_patient))
         .route("/patients/{patient_id}/summary", get(get_patient_summary))
+        .route("/patients/{patient_id}/export", get(export_patient))
         .route("/patients/{patient_id}/archive", post(archive_patient))
         .route("/patients/{patient_id}/restore", post(restore_patient))
+        .route("/patients/{patient_id}/request_deletion", post(request_patient_deletion))
+        .route("/patients/{patient_id}/cancel_deletion", post(cancel_patient_deletion))
         .route("/patients/{patient_id}/link_user/{user_id}", post(link_patient_user))
         .route("/patients/{patient_id}/unlink_user", post(unlink_patient_user))
 }
 
-use serde::de::Deserializer;
-
-fn deserialize_double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
-where
-    D: Deserializer<'de>,
-    T: Deserialize<'de>,
-{
-    // This is called only when the field is present (even if it's `null`).
-    // - null => Option::<T>::deserialize => None => we wrap => Some(None)
-    // - value => Some(value) => we wrap => Some(Some(value))
-    let inner = Option::<T>::deserialize(deserializer)?;
-    Ok(Some(inner))
-}
-
-
 fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
     // adjust to your role model; currently you return Vec<String> roles in /me
     // Here, AuthContext likely has role(s) derived from dcms_user.roles smallint.
@@ -79,93 +51,83 @@ fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
     Ok(())
 }
 
+/// Row with age / primary phone filled in.
+async fn respond(state: &AppState, row: PatientRow) -> Result<Json<ApiOk<PatientRow>>, ApiError> {
+    Ok(Json(ApiOk { data: patients::with_derived(&*state.repos.patients, row).await? }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePatientRequest {
+    pub register_number: Option<String>, // allow override, otherwise generated (numbering.rs)
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+    pub birthday: Option<chrono::NaiveDate>,
+    pub gender: Gender,
+    pub status: Option<i16>, // default 0
+    pub referral_source_id: Option<Uuid>,
+    /// only used for duplicate detection; numbers are added via /patients/{id}/phone_numbers
+    pub phone_number: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePatientQuery {
+    /// create even though likely duplicates exist
+    pub force: Option<bool>,
+}
+
+/// POST /patients[?force=true]: 409 DUPLICATE_PATIENT with `error.details.candidates`
+/// when the patient probably exists already; `force=true` creates anyway.
 pub async fn create_patient(
     State(state): State<AppState>,
     auth: AuthContext,
+    Query(q): Query<CreatePatientQuery>,
     Json(req): Json<CreatePatientRequest>,
-) -> Result<Json<PatientRow>, ApiError> {
+) -> Result<Json<ApiOk<PatientRow>>, ApiError> {
     ensure_staff(&auth)?;
 
-    let first_name = req.first_name.trim();
-    let last_name = req.last_name.trim();
-
-    if first_name.is_empty() || last_name.is_empty() {
-        return Err(ApiError::BadRequest(
-            "VALIDATION_ERROR",
-            "first_name and last_name are required".to_string(),
-        ));
-    }
-    if req.gender < 0 || req.gender > 2 {
-        return Err(ApiError::BadRequest(
-            "VALIDATION_ERROR",
-            "gender must be 0,1,2".to_string(),
-        ));
-    }
-
-    let status = req.status.unwrap_or(0);
-
-    // If register_number provided, insert it; else rely on DB default
-    let row: PatientRow = if let Some(rn) = req.register_number.as_ref().map(|s| s.trim()).filter(|s| !s.is_empty()) {
-        sqlx::query_as::<_, PatientRow>(
-            r#"
-            INSERT INTO patient (register_number, first_name, last_name, email, birthday, gender, status, created_at, last_seen_at)
-            VALUES ($1,$2,$3,$4,$5,$6,$7, now(), now())
-            RETURNING patient_id, register_number, user_id, first_name, last_name, email, birthday, gender, status, created_at, last_seen_at
-            "#,
-        )
-        .bind(rn)
-        .bind(first_name)
-        .bind(last_name)
-        .bind(req.email.as_deref())
-        .bind(req.birthday)
-        .bind(req.gender)
-        .bind(status)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    } else {
-        sqlx::query_as::<_, PatientRow>(
-            r#"
-            INSERT INTO patient (first_name, last_name, email, birthday, gender, status, created_at, last_seen_at)
-            VALUES ($1,$2,$3,$4,$5,$6, now(), now())
-            RETURNING patient_id, register_number, user_id, first_name, last_name, email, birthday, gender, status, created_at, last_seen_at
-            "#,
-        )
-        .bind(first_name)
-        .bind(last_name)
-        .bind(req.email.as_deref())
-        .bind(req.birthday)
-        .bind(req.gender)
-        .bind(status)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
+    let new = NewPatient {
+        register_number: req.register_number,
+        first_name: req.first_name,
+        last_name: req.last_name,
+        email: req.email,
+        birthday: req.birthday,
+        gender: req.gender,
+        status: req.status.unwrap_or(0),
+        referral_source_id: req.referral_source_id,
+        phone_number: req.phone_number,
     };
+    let row = patients::create(&*state.repos.patients, new, q.force.unwrap_or(false)).await?;
+    respond(&state, row).await
+}
 
-    Ok(Json(row))
+#[derive(Debug, Deserialize)]
+pub struct CheckRegisterNumberQuery {
+    pub value: String,
+    /// the patient being edited: their own number counts as available
+    pub patient_id: Option<Uuid>,
+}
+
+/// GET /patients/check_register_number?value=: whether a hand-entered number can be
+/// used, for the create / edit forms (POST/PATCH still answer 409 REGISTER_NUMBER_TAKEN).
+pub async fn check_register_number(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<CheckRegisterNumberQuery>,
+) -> Result<Json<ApiOk<NumberCheckDto>>, ApiError> {
+    ensure_staff(&auth)?;
+    let mut conn = state.db.acquire().await?;
+    let data = numbering::check_register_number(&mut conn, &q.value, q.patient_id).await?;
+    Ok(Json(ApiOk { data }))
 }
 
 pub async fn get_patient(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(patient_id): Path<Uuid>,
-) -> Result<Json<PatientRow>, ApiError> {
+) -> Result<Json<ApiOk<PatientRow>>, ApiError> {
     ensure_staff(&auth)?;
-
-    let row: PatientRow = sqlx::query_as::<_, PatientRow>(
-        r#"
-        SELECT patient_id, register_number, user_id, first_name, last_name, email, birthday, gender, status, created_at, last_seen_at
-        FROM patient
-        WHERE patient_id = $1
-        "#,
-    )
-    .bind(patient_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "patient not found".to_string()))?;
-
-    Ok(Json(row))
+    respond(&state, patients::get(&*state.repos.patients, patient_id).await?).await
 }
 
 #[derive(Debug, Deserialize)]
@@ -177,223 +139,39 @@ pub async fn search_patients(
     State(state): State<AppState>,
     auth: AuthContext,
     Query(q): Query<SearchQuery>,
-) -> Result<Json<Vec<PatientRow>>, ApiError> {
+) -> Result<Json<ApiList<PatientRow>>, ApiError> {
     ensure_staff(&auth)?;
 
-    let query = q.query.unwrap_or_default().trim().to_string();
-    if query.is_empty() {
-        // default: most recent
-        let rows: Vec<PatientRow> = sqlx::query_as::<_, PatientRow>(
-            r#"
-            SELECT patient_id, register_number, user_id, first_name, last_name, email, birthday, gender, status, created_at, last_seen_at
-            FROM patient
-            ORDER BY created_at DESC
-            LIMIT 50
-            "#,
-        )
-        .fetch_all(&state.db)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-        return Ok(Json(rows));
-    }
-
-    let like = format!("%{}%", query);
-
-    let rows: Vec<PatientRow> = sqlx::query_as::<_, PatientRow>(
-        r#"
-        SELECT patient_id, register_number, user_id, first_name, last_name, email, birthday, gender, status, created_at, last_seen_at
-        FROM patient
-        WHERE register_number ILIKE $1
-           OR first_name ILIKE $1
-           OR last_name ILIKE $1
-        ORDER BY created_at DESC
-        LIMIT 50
-        "#,
-    )
-    .bind(like)
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    Ok(Json(rows))
-}
-
-#[derive(Debug, Deserialize)]
-pub struct UpdatePatientRequest {
-    pub register_number: Option<String>, // optional override (rare; usually keep stable)
-    pub user_id: Option<Uuid>,           // allow linking in PATCH (optional)
-    pub first_name: Option<String>,
-    pub last_name: Option<String>,
-    // pub email: Option<Option<String>>,      // set to null: send null? => we handle via Option<Option<String>> below if needed
-    #[serde(default, deserialize_with = "deserialize_double_option")]
-    pub email: Option<Option<String>>,
-    pub birthday: Option<chrono::NaiveDate>,
-    pub gender: Option<i16>,
-    pub status: Option<i16>,
+    let rows = patients::search(&*state.repos.patients, q.query.as_deref().unwrap_or_default()).await?;
+    Ok(Json(ApiOk { data: rows }))
 }
 
 pub async fn update_patient(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(patient_id): Path<Uuid>,
-    Json(req): Json<UpdatePatientRequest>,
-) -> Result<Json<PatientRow>, ApiError> {
+    Json(req): Json<PatientPatch>,
+) -> Result<Json<ApiOk<PatientRow>>, ApiError> {
     ensure_staff(&auth)?;
-
-    // Load existing
-    let existing: PatientRow = sqlx::query_as::<_, PatientRow>(
-        r#"
-        SELECT patient_id, register_number, user_id, first_name, last_name, email,
-               birthday, gender, status, created_at, last_seen_at
-        FROM patient
-        WHERE patient_id = $1
-        "#,
-    )
-    .bind(patient_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "patient not found".to_string()))?;
-
-    // Apply updates with validation
-    let register_number = match req.register_number.as_deref().map(str::trim) {
-        Some(s) if !s.is_empty() => s.to_string(),
-        _ => existing.register_number.clone(),
-    };
-
-    let first_name = match req.first_name.as_deref().map(str::trim) {
-        Some(s) if !s.is_empty() => s.to_string(),
-        _ => existing.first_name.clone(),
-    };
-
-    let last_name = match req.last_name.as_deref().map(str::trim) {
-        Some(s) if !s.is_empty() => s.to_string(),
-        _ => existing.last_name.clone(),
-    };
-
-    // For email: if provided as Some(""), treat as clearing
-    let email: Option<String> = match req.email {
-        None => existing.email.clone(),        // field not provided => keep old
-        Some(None) => None,                    // explicitly null => clear
-        Some(Some(e)) => {
-            let t = e.trim();
-            if t.is_empty() { None } else { Some(t.to_string()) }
-        }
-    };
-    
-
-    let birthday = req.birthday.or(existing.birthday);
-    let gender = req.gender.unwrap_or(existing.gender);
-    let status = req.status.unwrap_or(existing.status);
-    let user_id = req.user_id.or(existing.user_id);
-
-    if gender < 0 || gender > 2 {
-        return Err(ApiError::BadRequest("VALIDATION_ERROR", "gender must be 0,1,2".into()));
-    }
-    // status check based on migration: patient.status 0..3
-    if status < 0 || status > 3 {
-        return Err(ApiError::BadRequest("VALIDATION_ERROR", "status must be 0..3".into()));
-    }
-
-    let updated: PatientRow = sqlx::query_as::<_, PatientRow>(
-        r#"
-        UPDATE patient
-        SET register_number = $1,
-            user_id = $2,
-            first_name = $3,
-            last_name = $4,
-            email = $5,
-            birthday = $6,
-            gender = $7,
-            status = $8,
-            last_seen_at = now()
-        WHERE patient_id = $9
-        RETURNING patient_id, register_number, user_id, first_name, last_name, email,
-                  birthday, gender, status, created_at, last_seen_at
-        "#,
-    )
-    .bind(register_number)
-    .bind(user_id)
-    .bind(first_name)
-    .bind(last_name)
-    .bind(email)
-    .bind(birthday)
-    .bind(gender)
-    .bind(status)
-    .bind(patient_id)
-    .fetch_one(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    Ok(Json(updated))
+    respond(&state, patients::update(&*state.repos.patients, patient_id, req).await?).await
 }
 
 pub async fn link_patient_user(
     State(state): State<AppState>,
     auth: AuthContext,
     Path((patient_id, user_id)): Path<(Uuid, Uuid)>,
-) -> Result<Json<PatientRow>, ApiError> {
+) -> Result<Json<ApiOk<PatientRow>>, ApiError> {
     ensure_staff(&auth)?;
-
-    // Ensure target user exists
-    let exists: Option<Uuid> = sqlx::query_scalar(
-        r#"
-        SELECT user_id
-        FROM "dcms_user"
-        WHERE user_id = $1
-        "#,
-    )
-    .bind(user_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
-
-    if exists.is_none() {
-        return Err(ApiError::BadRequest("NOT_FOUND", "user not found".into()));
-    }
-
-    let updated: PatientRow = sqlx::query_as::<_, PatientRow>(
-        r#"
-        UPDATE patient
-        SET user_id = $1, last_seen_at = now()
-        WHERE patient_id = $2
-        RETURNING patient_id, register_number, user_id, first_name, last_name, email,
-                  birthday, gender, status, created_at, last_seen_at
-        "#,
-    )
-    .bind(user_id)
-    .bind(patient_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "patient not found".into()))?;
-
-    Ok(Json(updated))
+    respond(&state, patients::set_user(&*state.repos.patients, patient_id, Some(user_id)).await?).await
 }
 
 pub async fn unlink_patient_user(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(patient_id): Path<Uuid>,
-) -> Result<Json<PatientRow>, ApiError> {
+) -> Result<Json<ApiOk<PatientRow>>, ApiError> {
     ensure_staff(&auth)?;
-
-    let updated: PatientRow = sqlx::query_as::<_, PatientRow>(
-        r#"
-        UPDATE patient
-        SET user_id = NULL, last_seen_at = now()
-        WHERE patient_id = $1
-        RETURNING patient_id, register_number, user_id, first_name, last_name, email,
-                  birthday, gender, status, created_at, last_seen_at
-        "#,
-    )
-    .bind(patient_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "patient not found".into()))?;
-
-    Ok(Json(updated))
+    respond(&state, patients::set_user(&*state.repos.patients, patient_id, None).await?).await
 }
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
@@ -412,12 +190,7 @@ pub struct SmsRow {
     pub phone_number_id: Uuid,
     pub direction: i16,
     pub sent_at: chrono::DateTime<chrono::Utc>,
-    pub sms_text: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct PatientSummaryResponse {
-    pub data: PatientSummaryData,
+    pub sms_text: PiiString,
 }
 
 #[derive(Debug, Serialize)]
@@ -425,43 +198,36 @@ pub struct PatientSummaryData {
     pub patient: PatientRow,
     pub phone_numbers: Vec<PhoneNumberRow>,
     pub recent_sms: Vec<SmsRow>,
+    /// None = not enough appointment history yet
+    pub no_show_risk: Option<NoShowRisk>,
+    pub high_no_show_risk: bool,
+    /// the patient's household with every member (the patient included); None = not in one
+    pub household: Option<HouseholdDto>,
 }
 
 pub async fn get_patient_summary(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(patient_id): Path<Uuid>,
-) -> Result<Json<PatientSummaryResponse>, ApiError> {
+) -> Result<Json<ApiOk<PatientSummaryData>>, ApiError> {
     ensure_staff(&auth)?;
 
     // patient
-    let patient: PatientRow = sqlx::query_as::<_, PatientRow>(
-        r#"
-        SELECT patient_id, register_number, user_id, first_name, last_name, email,
-               birthday, gender, status, created_at, last_seen_at
-        FROM patient
-        WHERE patient_id = $1
-        "#,
-    )
-    .bind(patient_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "patient not found".into()))?;
+    let patient = patients::get(&*state.repos.patients, patient_id).await?;
+    let patient = patients::with_derived(&*state.repos.patients, patient).await?;
 
     // phone numbers
     let phone_numbers: Vec<PhoneNumberRow> = sqlx::query_as::<_, PhoneNumberRow>(
         r#"
         SELECT phone_number_id, patient_id, phone_number, label, is_primary, created_at
         FROM phone_number
-        WHERE patient_id = $1
+        WHERE patient_id = $1 AND released_at IS NULL
         ORDER BY is_primary DESC, created_at DESC
         "#,
     )
     .bind(patient_id)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     // recent sms across those phone numbers
     let recent_sms: Vec<SmsRow> = sqlx::query_as::<_, SmsRow>(
@@ -469,76 +235,369 @@ pub async fn get_patient_summary(
         SELECT s.sms_id, s.phone_number_id, s.direction, s.sent_at, s.sms_text
         FROM sms s
         JOIN phone_number pn ON pn.phone_number_id = s.phone_number_id
-        WHERE pn.patient_id = $1
+        WHERE pn.patient_id = $1 AND s.deleted_at IS NULL
         ORDER BY s.sent_at DESC
         LIMIT 30
         "#,
     )
     .bind(patient_id)
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
+
+    let no_show_risk: Option<NoShowRisk> = sqlx::query_as::<_, NoShowRisk>(
+        r#"
+        SELECT score, appointment_count, no_show_count, late_cancel_count, computed_at
+        FROM patient_no_show_risk
+        WHERE patient_id = $1
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_optional(&state.db)
+    .await?;
 
-    Ok(Json(PatientSummaryResponse {
+    let household = household_routes::household_of(&mut *state.db.acquire().await?, patient_id).await?;
+
+    Ok(Json(ApiOk {
         data: PatientSummaryData {
             patient,
             phone_numbers,
             recent_sms,
+            high_no_show_risk: no_show_risk::is_high_risk(no_show_risk.as_ref().map(|r| r.score)),
+            no_show_risk,
+            household,
         },
     }))
 }
 
-const PATIENT_STATUS_ACTIVE: i16 = 0;
-const PATIENT_STATUS_ARCHIVED: i16 = 3;
-
 pub async fn archive_patient(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(patient_id): Path<Uuid>,
-) -> Result<Json<PatientRow>, ApiError> {
+) -> Result<Json<ApiOk<PatientRow>>, ApiError> {
     ensure_staff(&auth)?;
-
-    let updated: PatientRow = sqlx::query_as::<_, PatientRow>(
-        r#"
-        UPDATE patient
-        SET status = $1, last_seen_at = now()
-        WHERE patient_id = $2
-        RETURNING patient_id, register_number, user_id, first_name, last_name, email,
-                  birthday, gender, status, created_at, last_seen_at
-        "#,
-    )
-    .bind(PATIENT_STATUS_ARCHIVED)
-    .bind(patient_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "patient not found".into()))?;
-
-    Ok(Json(updated))
+    respond(&state, patients::set_status(&*state.repos.patients, patient_id, patients::PATIENT_STATUS_ARCHIVED).await?).await
 }
 
 pub async fn restore_patient(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(patient_id): Path<Uuid>,
-) -> Result<Json<PatientRow>, ApiError> {
+) -> Result<Json<ApiOk<PatientRow>>, ApiError> {
     ensure_staff(&auth)?;
+    respond(&state, patients::set_status(&*state.repos.patients, patient_id, patients::PATIENT_STATUS_ACTIVE).await?).await
+}
+
+/* ============================================================
+   Deletion requests (anonymized by jobs::patient_retention)
+   ============================================================ */
+
+/// Archives the patient and schedules anonymization after
+/// clinic_settings.patient_retention_days.
+pub async fn request_patient_deletion(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+) -> Result<Json<ApiOk<PatientDeletionRow>>, ApiError> {
+    ensure_staff(&auth)?;
+    let row = patients::request_deletion(&*state.repos.patients, patient_id, auth.user_id).await?;
+    Ok(Json(ApiOk { data: row }))
+}
+
+/// Admin only: drops a pending deletion request (the patient stays archived).
+pub async fn cancel_patient_deletion(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+) -> Result<Json<ApiOk<PatientDeletionRow>>, ApiError> {
+    if auth.role != Role::Admin {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin can cancel a deletion request".into(),
+        ));
+    }
+
+    let row = patients::cancel_deletion(&*state.repos.patients, patient_id).await?;
+    Ok(Json(ApiOk { data: row }))
+}
+
+/* ============================================================
+   Data export (data-access / portability requests)
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExportAppointmentRow {
+    pub appointment_id: Uuid,
+    pub doctor_employee_id: Uuid,
+    pub doctor_name: String,
+    pub location_id: Option<Uuid>,
+    pub start_at: chrono::DateTime<chrono::Utc>,
+    pub end_at: chrono::DateTime<chrono::Utc>,
+    pub status: i16,
+    pub source: String,
+    pub note: Option<String>,
+    pub arrived_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub seated_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub dismissed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExportPlanItemRow {
+    pub appointment_plan_item_id: Uuid,
+    pub appointment_id: Uuid,
+    pub service_id: Uuid,
+    pub service_name: String,
+    pub qty: i32,
+    pub price_cents: i32,
+    pub note: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExportAppointmentNoteRow {
+    pub appointment_note_id: Uuid,
+    pub appointment_id: Uuid,
+    pub author_user_id: Option<Uuid>,
+    pub note_text: String,
+    pub is_pinned: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub procedure_fields: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExportNoteRow {
+    pub patient_note_id: Uuid,
+    pub note_type: String,
+    pub note_text: String,
+    pub is_pinned: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExportWaitlistRow {
+    pub waitlist_entry_id: Uuid,
+    pub reason: Option<String>,
+    pub notes: Option<String>,
+    pub preferred_date: Option<chrono::NaiveDate>,
+    pub status: i16,
+    pub scheduled_appointment_id: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Generated documents without the PDF bytes (GET /documents/{id}/pdf has those).
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExportDocumentRow {
+    pub document_id: Uuid,
+    pub appointment_id: Option<Uuid>,
+    pub title: String,
+    pub body: String,
+    pub content_type: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PatientExport {
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+    pub patient: PatientRow,
+    pub phone_numbers: Vec<PhoneNumberRow>,
+    pub sms: Vec<crate::models::SmsRow>,
+    pub appointments: Vec<ExportAppointmentRow>,
+    pub plan_items: Vec<ExportPlanItemRow>,
+    pub appointment_notes: Vec<ExportAppointmentNoteRow>,
+    pub notes: Vec<ExportNoteRow>,
+    pub waitlist: Vec<ExportWaitlistRow>,
+    pub documents: Vec<ExportDocumentRow>,
+    pub medical_history: Option<MedicalHistoryDto>,
+    pub medical_alerts: Vec<MedicalAlertDto>,
+    /// intake links with what the patient submitted, newest first
+    pub intake: Vec<IntakeDto>,
+    /// metadata and attachment ids; the files are at each attachment's `url`
+    pub imaging_orders: Vec<ImagingOrderDto>,
+}
+
+/// Admin only: everything stored about one patient, as a JSON attachment.
+pub async fn export_patient(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    if auth.role != Role::Admin {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin can export patient data".into(),
+        ));
+    }
 
-    let updated: PatientRow = sqlx::query_as::<_, PatientRow>(
+    let patient = patients::get(&*state.repos.patients, patient_id).await?;
+    let patient = patients::with_derived(&*state.repos.patients, patient).await?;
+
+    let phone_numbers: Vec<PhoneNumberRow> = sqlx::query_as::<_, PhoneNumberRow>(
         r#"
-        UPDATE patient
-        SET status = $1, last_seen_at = now()
-        WHERE patient_id = $2
-        RETURNING patient_id, register_number, user_id, first_name, last_name, email,
-                  birthday, gender, status, created_at, last_seen_at
+        SELECT phone_number_id, patient_id, phone_number, label, is_primary, created_at
+        FROM phone_number
+        WHERE patient_id = $1
+        ORDER BY is_primary DESC, created_at
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    // soft-deleted messages too (deleted_at set)
+    let sms: Vec<crate::models::SmsRow> = sqlx::query_as::<_, crate::models::SmsRow>(
+        r#"
+        SELECT s.sms_id, s.phone_number_id, s.direction, s.sent_at, s.subject, s.sms_text, s.note, s.created_at,
+               s.deleted_at, s.deleted_by_user_id
+        FROM sms s
+        JOIN phone_number pn ON pn.phone_number_id = s.phone_number_id
+        WHERE pn.patient_id = $1
+        ORDER BY s.sent_at
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let appointments: Vec<ExportAppointmentRow> = sqlx::query_as::<_, ExportAppointmentRow>(
+        r#"
+        SELECT
+          a.appointment_id,
+          a.doctor_employee_id,
+          e.first_name || ' ' || e.last_name AS doctor_name,
+          a.location_id,
+          a.start_at,
+          a.end_at,
+          a.status,
+          a.source,
+          a.note,
+          a.arrived_at,
+          a.seated_at,
+          a.dismissed_at,
+          a.created_at
+        FROM appointment a
+        JOIN employee e ON e.employee_id = a.doctor_employee_id
+        WHERE a.patient_id = $1
+        ORDER BY a.start_at
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let plan_items: Vec<ExportPlanItemRow> = sqlx::query_as::<_, ExportPlanItemRow>(
+        r#"
+        SELECT
+          pi.appointment_plan_item_id,
+          pi.appointment_id,
+          pi.service_id,
+          sc.display_name AS service_name,
+          pi.qty,
+          sc.price_cents,
+          pi.note,
+          pi.created_at
+        FROM appointment_plan_item pi
+        JOIN appointment a ON a.appointment_id = pi.appointment_id
+        JOIN service_catalog sc ON sc.service_id = pi.service_id
+        WHERE a.patient_id = $1
+        ORDER BY a.start_at, pi.created_at
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let appointment_notes: Vec<ExportAppointmentNoteRow> = sqlx::query_as::<_, ExportAppointmentNoteRow>(
+        r#"
+        SELECT n.appointment_note_id, n.appointment_id, n.author_user_id, n.note_text, n.is_pinned, n.created_at, n.procedure_fields
+        FROM appointment_note n
+        JOIN appointment a ON a.appointment_id = n.appointment_id
+        WHERE a.patient_id = $1
+        ORDER BY a.start_at, n.created_at
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let notes: Vec<ExportNoteRow> = sqlx::query_as::<_, ExportNoteRow>(
+        r#"
+        SELECT patient_note_id, note_type, note_text, is_pinned, created_at
+        FROM patient_note
+        WHERE patient_id = $1
+        ORDER BY created_at
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let waitlist: Vec<ExportWaitlistRow> = sqlx::query_as::<_, ExportWaitlistRow>(
+        r#"
+        SELECT waitlist_entry_id, reason, notes, preferred_date, status, scheduled_appointment_id, created_at
+        FROM waitlist_entry
+        WHERE patient_id = $1
+        ORDER BY created_at
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let documents: Vec<ExportDocumentRow> = sqlx::query_as::<_, ExportDocumentRow>(
+        r#"
+        SELECT document_id, appointment_id, title, body, content_type, created_at
+        FROM patient_document
+        WHERE patient_id = $1
+        ORDER BY created_at
+        "#,
+    )
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let medical_history: Option<MedicalHistoryDto> = sqlx::query_as::<_, MedicalHistoryDto>(
+        r#"
+        SELECT allergies, medications, conditions, notes, updated_by_user_id, updated_at
+        FROM patient_medical_history
+        WHERE patient_id = $1
         "#,
     )
-    .bind(PATIENT_STATUS_ACTIVE)
     .bind(patient_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "patient not found".into()))?;
+    .await?;
 
-    Ok(Json(updated))
+    let medical_alerts = sqlx::query_as::<_, MedicalAlertDto>(&format!(
+        "SELECT {ALERT_COLUMNS} FROM patient_medical_alert WHERE patient_id = $1 ORDER BY created_at"
+    ))
+    .bind(patient_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut conn = state.db.acquire().await?;
+    let intake = intake_routes::patient_intakes(&mut conn, patient_id).await?;
+    let imaging_orders = imaging_routes::patient_orders(&mut conn, patient_id).await?;
+
+    let filename = format!("patient-{}-export.json", patient.register_number);
+
+    Ok((
+        [(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\""))],
+        Json(ApiOk {
+            data: PatientExport {
+                exported_at: chrono::Utc::now(),
+                patient,
+                phone_numbers,
+                sms,
+                appointments,
+                plan_items,
+                appointment_notes,
+                notes,
+                waitlist,
+                documents,
+                medical_history,
+                medical_alerts,
+                intake,
+                imaging_orders,
+            },
+        }),
+    ))
 }
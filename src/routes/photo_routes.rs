@@ -0,0 +1,323 @@
+// src/routes/photo_routes.rs
+//
+// Profile photos for patients and employees.
+// - POST   /{patients|employees}/{id}/photo   raw image body (JPEG/PNG/WebP)
+// - GET    /{patients|employees}/{id}/photo?size=avatar|full
+// - DELETE /{patients|employees}/{id}/photo
+// Processing lives in crate::photos; URLs show up as `photo_url` on PatientRow and
+// on the patient/doctor of appointment blocks.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State, rejection::BytesRejection},
+    http::header,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiOk, AppState, OkData},
+    photos,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/patients/{patient_id}/photo",
+            get(get_patient_photo)
+                .post(upload_patient_photo)
+                .delete(delete_patient_photo),
+        )
+        .route(
+            "/employees/{employee_id}/photo",
+            get(get_employee_photo)
+                .post(upload_employee_photo)
+                .delete(delete_employee_photo),
+        )
+}
+
+/* ============================================================
+   Owner (patient or employee)
+   ============================================================ */
+
+#[derive(Debug, Clone, Copy)]
+enum Owner {
+    Patient(Uuid),
+    Employee(Uuid),
+}
+
+impl Owner {
+    fn id(self) -> Uuid {
+        match self {
+            Owner::Patient(id) | Owner::Employee(id) => id,
+        }
+    }
+
+    /// (owner table, key column, URL collection)
+    fn names(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            Owner::Patient(_) => ("patient", "patient_id", "patients"),
+            Owner::Employee(_) => ("employee", "employee_id", "employees"),
+        }
+    }
+}
+
+// roles: 0 patient, 1 admin, 2 manager, 3 doctor, 4 receptionist
+fn is_staff(auth: &AuthContext) -> bool {
+    auth.role.is_staff()
+}
+
+/// Patient photos: any staff. Employee photos: admin/manager, or the employee themself.
+async fn ensure_can_edit(state: &AppState, auth: &AuthContext, owner: Owner) -> Result<(), ApiError> {
+    let allowed = match owner {
+        Owner::Patient(_) => is_staff(auth),
+        Owner::Employee(id) => auth.role.is_admin_or_manager() || is_own_employee(state, auth, id).await?,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "You cannot change this photo".into(),
+        ))
+    }
+}
+
+/// Staff see every photo; a patient account only its own.
+async fn ensure_can_view(state: &AppState, auth: &AuthContext, owner: Owner) -> Result<(), ApiError> {
+    if is_staff(auth) {
+        return Ok(());
+    }
+    if let Owner::Patient(id) = owner {
+        let own: Option<bool> =
+            sqlx::query_scalar("SELECT user_id = $2 FROM patient WHERE patient_id = $1")
+                .bind(id)
+                .bind(auth.user_id)
+                .fetch_optional(&state.db)
+                .await?
+                .flatten();
+        if own == Some(true) {
+            return Ok(());
+        }
+    }
+    Err(ApiError::Forbidden("FORBIDDEN", "You cannot view this photo".into()))
+}
+
+async fn is_own_employee(state: &AppState, auth: &AuthContext, employee_id: Uuid) -> Result<bool, ApiError> {
+    let own: Option<Uuid> = sqlx::query_scalar("SELECT user_id FROM employee WHERE employee_id = $1")
+        .bind(employee_id)
+        .fetch_optional(&state.db)
+        .await?
+        .flatten();
+    Ok(own == Some(auth.user_id))
+}
+
+/* ============================================================
+   Upload
+   ============================================================ */
+
+#[derive(Debug, Serialize)]
+pub struct PhotoData {
+    pub photo_url: Option<String>,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub async fn upload_patient_photo(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Json<ApiOk<PhotoData>>, ApiError> {
+    upload_photo(state, auth, Owner::Patient(patient_id), body?).await
+}
+
+pub async fn upload_employee_photo(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(employee_id): Path<Uuid>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Json<ApiOk<PhotoData>>, ApiError> {
+    upload_photo(state, auth, Owner::Employee(employee_id), body?).await
+}
+
+async fn upload_photo(
+    state: AppState,
+    auth: AuthContext,
+    owner: Owner,
+    body: Bytes,
+) -> Result<Json<ApiOk<PhotoData>>, ApiError> {
+    ensure_can_edit(&state, &auth, owner).await?;
+    if body.is_empty() {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "image body is required".into()));
+    }
+
+    // decode/resize is CPU-bound
+    let photo = tokio::task::spawn_blocking(move || photos::process_upload(&body))
+        .await
+        .map_err(|e| ApiError::Internal(format!("photo processing task failed: {e}")))??;
+
+    let (table, key, collection) = owner.names();
+    let mut tx = state.db.begin().await?;
+
+    let updated_at: DateTime<Utc> = sqlx::query_scalar(&format!(
+        "UPDATE {table} SET photo_updated_at = now() WHERE {key} = $1 RETURNING photo_updated_at"
+    ))
+    .bind(owner.id())
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", format!("{table} not found")))?;
+
+    sqlx::query(&format!(
+        r#"
+        INSERT INTO profile_photo ({key}, content_type, full_image, avatar_image, width, height, uploaded_by_user_id, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT ({key}) DO UPDATE SET
+          content_type = EXCLUDED.content_type,
+          full_image = EXCLUDED.full_image,
+          avatar_image = EXCLUDED.avatar_image,
+          width = EXCLUDED.width,
+          height = EXCLUDED.height,
+          uploaded_by_user_id = EXCLUDED.uploaded_by_user_id,
+          updated_at = EXCLUDED.updated_at
+        "#
+    ))
+    .bind(owner.id())
+    .bind(photos::CONTENT_TYPE)
+    .bind(&photo.full)
+    .bind(&photo.avatar)
+    .bind(photo.width as i32)
+    .bind(photo.height as i32)
+    .bind(auth.user_id)
+    .bind(updated_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("PHOTO_UPLOAD_FAILED"))?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiOk {
+        data: PhotoData {
+            photo_url: photos::photo_url(collection, owner.id(), Some(updated_at)),
+            width: photo.width,
+            height: photo.height,
+        },
+    }))
+}
+
+/* ============================================================
+   Download
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct PhotoQuery {
+    /// "full" (default) or "avatar"
+    pub size: Option<String>,
+}
+
+pub async fn get_patient_photo(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+    Query(q): Query<PhotoQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    get_photo(state, auth, Owner::Patient(patient_id), q).await
+}
+
+pub async fn get_employee_photo(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(employee_id): Path<Uuid>,
+    Query(q): Query<PhotoQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    get_photo(state, auth, Owner::Employee(employee_id), q).await
+}
+
+async fn get_photo(
+    state: AppState,
+    auth: AuthContext,
+    owner: Owner,
+    q: PhotoQuery,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_can_view(&state, &auth, owner).await?;
+
+    let column = match q.size.as_deref().unwrap_or("full") {
+        "full" => "full_image",
+        "avatar" => "avatar_image",
+        _ => {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "size must be full or avatar".into(),
+            ))
+        }
+    };
+
+    let (_, key, _) = owner.names();
+    let (content_type, bytes): (String, Vec<u8>) = sqlx::query_as(&format!(
+        "SELECT content_type, {column} FROM profile_photo WHERE {key} = $1"
+    ))
+    .bind(owner.id())
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "photo not found".into()))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            // photo_url is versioned (?v=), so the bytes behind a URL never change
+            (header::CACHE_CONTROL, "private, max-age=31536000, immutable".to_string()),
+        ],
+        bytes,
+    ))
+}
+
+/* ============================================================
+   Delete
+   ============================================================ */
+
+pub async fn delete_patient_photo(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(patient_id): Path<Uuid>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    delete_photo(state, auth, Owner::Patient(patient_id)).await
+}
+
+pub async fn delete_employee_photo(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(employee_id): Path<Uuid>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    delete_photo(state, auth, Owner::Employee(employee_id)).await
+}
+
+async fn delete_photo(state: AppState, auth: AuthContext, owner: Owner) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    ensure_can_edit(&state, &auth, owner).await?;
+
+    let (table, key, _) = owner.names();
+    let mut tx = state.db.begin().await?;
+
+    let res = sqlx::query(&format!("DELETE FROM profile_photo WHERE {key} = $1"))
+        .bind(owner.id())
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "photo not found".into()));
+    }
+
+    sqlx::query(&format!("UPDATE {table} SET photo_updated_at = NULL WHERE {key} = $1"))
+        .bind(owner.id())
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
@@ -0,0 +1,214 @@
+// src/routes/procedure_template_routes.rs
+//
+// Procedure note templates (see services::procedure_templates):
+// - GET   /procedure_templates              (staff; ?service_id=, ?include_inactive=true)
+// - POST  /procedure_templates              (doctor/manager/admin)
+// - PATCH /procedure_templates/{id}         (doctor/manager/admin; deactivate, don't delete)
+// Filling one into an appointment's notes lives in appointment_routes
+// (/appointments/{id}/procedure_notes).
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, patch},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json as SqlJson;
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiList, ApiOk, AppState, Role},
+    services::procedure_templates::{self, FieldDef},
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/procedure_templates", get(list_templates).post(create_template))
+        .route("/procedure_templates/{template_id}", patch(patch_template))
+}
+
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    }
+}
+
+fn ensure_clinical_editor(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_admin_or_manager() || auth.role == Role::Doctor {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only doctors and admin/manager can edit procedure templates".into(),
+        ))
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), ApiError> {
+    if name.is_empty() || name.chars().count() > 128 {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "name must be 1..128 chars".into()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ProcedureTemplateDto {
+    pub template_id: Uuid,
+    pub name: String,
+    pub service_id: Option<Uuid>,
+    pub fields: SqlJson<Vec<FieldDef>>,
+    pub body: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const TEMPLATE_COLUMNS: &str = "template_id, name, service_id, fields, body, is_active, created_at, updated_at";
+
+#[derive(Debug, Deserialize)]
+pub struct ListTemplatesQuery {
+    pub service_id: Option<Uuid>,
+    pub include_inactive: Option<bool>,
+}
+
+/// By name; with ?service_id=, that service's templates plus the general ones.
+pub async fn list_templates(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ListTemplatesQuery>,
+) -> Result<Json<ApiList<ProcedureTemplateDto>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let rows = sqlx::query_as::<_, ProcedureTemplateDto>(&format!(
+        r#"
+        SELECT {TEMPLATE_COLUMNS}
+        FROM procedure_template
+        WHERE ($1 OR is_active)
+          AND ($2::uuid IS NULL OR service_id IS NULL OR service_id = $2)
+        ORDER BY lower(name)
+        "#
+    ))
+    .bind(q.include_inactive.unwrap_or(false))
+    .bind(q.service_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: rows }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTemplateRequest {
+    pub name: String,
+    pub service_id: Option<Uuid>,
+    #[serde(default)]
+    pub fields: Vec<FieldDef>,
+    pub body: String,
+}
+
+pub async fn create_template(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateTemplateRequest>,
+) -> Result<Json<ApiOk<ProcedureTemplateDto>>, ApiError> {
+    ensure_clinical_editor(&auth)?;
+    let name = req.name.trim();
+    validate_name(name)?;
+    procedure_templates::validate_template(&req.fields, &req.body)?;
+
+    let row = sqlx::query_as::<_, ProcedureTemplateDto>(&format!(
+        r#"
+        INSERT INTO procedure_template (name, service_id, fields, body, created_by_user_id, updated_by_user_id)
+        VALUES ($1, $2, $3, $4, $5, $5)
+        ON CONFLICT ((lower(name))) DO NOTHING
+        RETURNING {TEMPLATE_COLUMNS}
+        "#
+    ))
+    .bind(name)
+    .bind(req.service_id)
+    .bind(SqlJson(&req.fields))
+    .bind(&req.body)
+    .bind(auth.user_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(ApiError::write_failed("PROCEDURE_TEMPLATE_CREATE_FAILED"))?
+    .ok_or_else(|| {
+        ApiError::Conflict("PROCEDURE_TEMPLATE_EXISTS", format!("procedure template '{name}' already exists"))
+    })?;
+
+    Ok(Json(ApiOk { data: row }))
+}
+
+/// Omitted fields keep their value; `fields` and `body` are re-validated together.
+#[derive(Debug, Deserialize)]
+pub struct PatchTemplateRequest {
+    pub name: Option<String>,
+    /// null clears
+    #[serde(default, deserialize_with = "crate::services::patients::deserialize_double_option")]
+    pub service_id: Option<Option<Uuid>>,
+    pub fields: Option<Vec<FieldDef>>,
+    pub body: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+pub async fn patch_template(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(template_id): Path<Uuid>,
+    Json(req): Json<PatchTemplateRequest>,
+) -> Result<Json<ApiOk<ProcedureTemplateDto>>, ApiError> {
+    ensure_clinical_editor(&auth)?;
+    let name = req.name.as_deref().map(str::trim);
+    if let Some(name) = name {
+        validate_name(name)?;
+    }
+
+    let mut tx = state.db.begin().await?;
+    let cur: Option<(SqlJson<Vec<FieldDef>>, String)> =
+        sqlx::query_as("SELECT fields, body FROM procedure_template WHERE template_id = $1 FOR UPDATE")
+            .bind(template_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let (SqlJson(cur_fields), cur_body) =
+        cur.ok_or_else(|| ApiError::NotFound("NOT_FOUND", "procedure template not found".into()))?;
+    if req.fields.is_some() || req.body.is_some() {
+        procedure_templates::validate_template(
+            req.fields.as_deref().unwrap_or(&cur_fields),
+            req.body.as_deref().unwrap_or(&cur_body),
+        )?;
+    }
+
+    let row = sqlx::query_as::<_, ProcedureTemplateDto>(&format!(
+        r#"
+        UPDATE procedure_template
+        SET
+          name       = COALESCE($2, name),
+          service_id = CASE WHEN $3 THEN $4 ELSE service_id END,
+          fields     = COALESCE($5, fields),
+          body       = COALESCE($6, body),
+          is_active  = COALESCE($7, is_active),
+          updated_by_user_id = $8
+        WHERE template_id = $1
+        RETURNING {TEMPLATE_COLUMNS}
+        "#
+    ))
+    .bind(template_id)
+    .bind(name)
+    .bind(req.service_id.is_some())
+    .bind(req.service_id.flatten())
+    .bind(req.fields.as_ref().map(SqlJson))
+    .bind(req.body.as_deref())
+    .bind(req.is_active)
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("PROCEDURE_TEMPLATE_UPDATE_FAILED"))?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: row }))
+}
@@ -0,0 +1,1650 @@
+// src/routes/report_routes.rs
+//
+// Appointment stats and revenue read the daily materialized views from
+// migration 033 (see jobs::report_refresh); responses carry `refreshed_at`.
+// GET /dashboard/manager is live (today isn't settled yet), one query.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Router,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+use crate::{
+    clinic_time,
+    consent::{self, ContactPurpose},
+    data_export::csv_field,
+    day_close::{self, DayClose},
+    error::ApiError,
+    extract::Json,
+    jobs::report_refresh,
+    middleware::auth_context::AuthContext,
+    models::{ApiOk, AppState, AppointmentStatus, OkData, Role, TaskStatus},
+    money, pdf,
+    services::{appointments, availability, feedback::RatingCounts},
+};
+
+/*
+Reports are for the manager dashboard: admin (1) + manager (2) only.
+*/
+
+fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_admin_or_manager() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin/manager can view reports".into(),
+        ))
+    }
+}
+
+const MAX_REPORT_DAYS: i64 = 366;
+
+/* ============================================================
+   Router
+   ============================================================ */
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/reports/appointments/stats", get(get_appointment_stats))
+        .route("/reports/utilization", get(get_utilization_report))
+        .route("/reports/revenue", get(get_revenue_report))
+        .route("/reports/commissions", get(get_commission_report))
+        .route("/reports/commissions/{employee_id}/rate", put(set_commission_rate))
+        .route("/reports/referrals", get(get_referral_report))
+        .route("/reports/feedback", get(get_feedback_report))
+        .route("/reports/appointment_sources", get(get_appointment_source_report))
+        .route("/reports/retention", get(get_retention_report))
+        .route("/reports/day_close", get(get_day_close))
+        .route("/reports/day_close/sign_off", post(sign_off_day_close))
+        .route("/dashboard/manager", get(get_manager_dashboard))
+}
+
+/* ============================================================
+   DTOs
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct RangeQuery {
+    pub from: String, // YYYY-MM-DD (inclusive)
+    pub to: String,   // YYYY-MM-DD (inclusive)
+    pub doctor_employee_id: Option<Uuid>,
+}
+
+/// Effective status, derived from the status code + arrival timestamps.
+#[derive(Debug, Default, Serialize)]
+pub struct StatusCounts {
+    pub scheduled: i64,
+    pub arrived: i64,
+    pub seated: i64,
+    pub finished: i64,
+    pub canceled: i64,
+    pub no_show: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppointmentStats {
+    pub total: i64,
+    pub by_status: StatusCounts,
+    /// no-shows / past non-canceled appointments
+    pub no_show_rate: Option<f64>,
+    /// seated -> dismissed
+    pub avg_visit_minutes: Option<f64>,
+    pub booked_minutes: i64,
+    /// booked minutes / clinic open minutes (null when business_hours is not configured)
+    pub fill_rate: Option<f64>,
+    pub new_patients: i64,
+    pub returning_patients: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DoctorAppointmentStats {
+    pub doctor_employee_id: Uuid,
+    pub doctor_display: String,
+    pub stats: AppointmentStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppointmentStatsReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub open_minutes_per_doctor: Option<i64>,
+    /// when the underlying view was last rebuilt
+    pub refreshed_at: Option<DateTime<Utc>>,
+    pub overall: AppointmentStats,
+    pub doctors: Vec<DoctorAppointmentStats>,
+}
+
+/* ============================================================
+   Helpers
+   ============================================================ */
+
+fn parse_range(q: &RangeQuery) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    parse_dates(&q.from, &q.to)
+}
+
+fn parse_dates(from: &str, to: &str) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    let from = NaiveDate::parse_from_str(from.trim(), "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "from must be YYYY-MM-DD".into()))?;
+    let to = NaiveDate::parse_from_str(to.trim(), "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "to must be YYYY-MM-DD".into()))?;
+    if to < from {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "to must not be before from".into()));
+    }
+    if (to - from).num_days() + 1 > MAX_REPORT_DAYS {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("range must be at most {MAX_REPORT_DAYS} days"),
+        ));
+    }
+    Ok((from, to))
+}
+
+/// Open minutes per weekday (Mon=0) from clinic_settings.business_hours.
+fn open_minutes_per_weekday(bh: &JsonValue) -> [i64; 7] {
+    availability::opening_windows(bh).map(|windows| windows.iter().map(|(s, e)| (*e - *s).num_minutes()).sum())
+}
+
+fn ratio(num: i64, den: i64) -> Option<f64> {
+    if den > 0 {
+        Some(num as f64 / den as f64)
+    } else {
+        None
+    }
+}
+
+/* ============================================================
+   GET /reports/appointments/stats
+   ============================================================ */
+
+pub async fn get_appointment_stats(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<RangeQuery>,
+) -> Result<Json<ApiOk<AppointmentStatsReport>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let (from, to) = parse_range(&q)?;
+    let tz = clinic_time::clinic_tz(state.read_db())
+        .await?;
+    let from_ts = clinic_time::local_day_start(from, tz);
+    let to_ts = clinic_time::local_day_start(to.succ_opt().unwrap_or(to), tz);
+
+    let business_hours: Option<JsonValue> =
+        sqlx::query_scalar("SELECT business_hours FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(state.read_db())
+            .await?;
+
+    let per_weekday = business_hours.as_ref().map(open_minutes_per_weekday).unwrap_or_default();
+    let open_minutes: i64 = from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .map(|d| per_weekday[d.weekday().num_days_from_monday() as usize])
+        .sum();
+    let open_minutes_per_doctor = (open_minutes > 0).then_some(open_minutes);
+
+    // Status counts etc. come from report_daily_appointments, where the effective
+    // status is derived from the timestamps (the status code alone is not reliable
+    // for arrived/seated/dismissed; 1 = canceled, 3 without seating = no-show).
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          r.doctor_employee_id,
+          e.first_name,
+          e.last_name,
+
+          SUM(r.total)::int8     AS total,
+          SUM(r.scheduled)::int8 AS scheduled,
+          SUM(r.arrived)::int8   AS arrived,
+          SUM(r.seated)::int8    AS seated,
+          SUM(r.finished)::int8  AS finished,
+          SUM(r.canceled)::int8  AS canceled,
+          SUM(r.no_show)::int8   AS no_show,
+          SUM(r.past_kept)::int8 AS past_kept,
+          SUM(r.timed_visits)::int8     AS timed_visits,
+          SUM(r.visit_minutes)::float8  AS visit_minutes,
+          SUM(r.booked_minutes)::int8   AS booked_minutes
+        FROM report_daily_appointments r
+        JOIN employee e ON e.employee_id = r.doctor_employee_id
+        WHERE r.day BETWEEN $1 AND $2
+          AND ($3::uuid IS NULL OR r.doctor_employee_id = $3)
+        GROUP BY r.doctor_employee_id, e.first_name, e.last_name
+        ORDER BY e.first_name, e.last_name
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(q.doctor_employee_id)
+    .fetch_all(state.read_db())
+    .await?;
+
+    // distinct patients don't add up across days, so these stay on the raw table
+    let patient_rows: Vec<(Uuid, i64, i64)> = sqlx::query_as(
+        r#"
+        WITH a AS (
+          SELECT a.doctor_employee_id, a.patient_id
+          FROM appointment a
+          WHERE a.start_at >= $1
+            AND a.start_at <  $2
+            AND a.status <> 1
+            AND ($3::uuid IS NULL OR a.doctor_employee_id = $3)
+        ),
+        first_visit AS (
+          SELECT x.patient_id, MIN(x.start_at) AS first_at
+          FROM appointment x
+          WHERE x.status <> 1
+            AND x.patient_id IN (SELECT patient_id FROM a)
+          GROUP BY x.patient_id
+        )
+        SELECT
+          a.doctor_employee_id,
+          COUNT(DISTINCT a.patient_id) FILTER (WHERE fv.first_at >= $1) AS new_patients,
+          COUNT(DISTINCT a.patient_id) FILTER (WHERE fv.first_at <  $1) AS returning_patients
+        FROM a
+        LEFT JOIN first_visit fv ON fv.patient_id = a.patient_id
+        GROUP BY a.doctor_employee_id
+        "#,
+    )
+    .bind(from_ts)
+    .bind(to_ts)
+    .bind(q.doctor_employee_id)
+    .fetch_all(state.read_db())
+    .await?;
+    let patients: HashMap<Uuid, (i64, i64)> =
+        patient_rows.into_iter().map(|(id, new, ret)| (id, (new, ret))).collect();
+
+    let mut overall = StatusCounts::default();
+    let (mut total, mut past_kept, mut timed_visits, mut booked_minutes) = (0i64, 0i64, 0i64, 0i64);
+    let (mut visit_minutes, mut new_patients, mut returning_patients) = (0f64, 0i64, 0i64);
+    let mut doctors = Vec::with_capacity(rows.len());
+
+    for r in rows {
+        let g = |c: &str| -> Result<i64, ApiError> { r.try_get::<i64, _>(c).map_err(internal_row) };
+
+        let by_status = StatusCounts {
+            scheduled: g("scheduled")?,
+            arrived: g("arrived")?,
+            seated: g("seated")?,
+            finished: g("finished")?,
+            canceled: g("canceled")?,
+            no_show: g("no_show")?,
+        };
+        let d_total = g("total")?;
+        let d_past_kept = g("past_kept")?;
+        let d_timed = g("timed_visits")?;
+        let d_visit_minutes: f64 = r.try_get("visit_minutes").map_err(internal_row)?;
+        let d_booked = g("booked_minutes")?;
+        let doctor_employee_id: Uuid = r.try_get("doctor_employee_id").map_err(internal_row)?;
+        let (d_new, d_returning) = patients.get(&doctor_employee_id).copied().unwrap_or_default();
+
+        overall.scheduled += by_status.scheduled;
+        overall.arrived += by_status.arrived;
+        overall.seated += by_status.seated;
+        overall.finished += by_status.finished;
+        overall.canceled += by_status.canceled;
+        overall.no_show += by_status.no_show;
+        total += d_total;
+        past_kept += d_past_kept;
+        timed_visits += d_timed;
+        visit_minutes += d_visit_minutes;
+        booked_minutes += d_booked;
+        // a patient can be new/returning for several doctors; overall counts are per doctor-patient pair
+        new_patients += d_new;
+        returning_patients += d_returning;
+
+        let first: String = r.try_get("first_name").map_err(internal_row)?;
+        let last: String = r.try_get("last_name").map_err(internal_row)?;
+
+        doctors.push(DoctorAppointmentStats {
+            doctor_employee_id,
+            doctor_display: format!("{first} {last}"),
+            stats: AppointmentStats {
+                total: d_total,
+                no_show_rate: ratio(by_status.no_show, d_past_kept),
+                by_status,
+                avg_visit_minutes: (d_timed > 0).then(|| d_visit_minutes / d_timed as f64),
+                booked_minutes: d_booked,
+                fill_rate: open_minutes_per_doctor.and_then(|m| ratio(d_booked, m)),
+                new_patients: d_new,
+                returning_patients: d_returning,
+            },
+        });
+    }
+
+    let doctor_count = doctors.len() as i64;
+    let overall = AppointmentStats {
+        total,
+        no_show_rate: ratio(overall.no_show, past_kept),
+        by_status: overall,
+        avg_visit_minutes: (timed_visits > 0).then(|| visit_minutes / timed_visits as f64),
+        booked_minutes,
+        fill_rate: open_minutes_per_doctor.and_then(|m| ratio(booked_minutes, m * doctor_count)),
+        new_patients,
+        returning_patients,
+    };
+
+    let refreshed_at = report_refresh::refreshed_at(state.read_db(), "report_daily_appointments").await?;
+
+    Ok(Json(ApiOk {
+        data: AppointmentStatsReport {
+            from,
+            to,
+            open_minutes_per_doctor,
+            refreshed_at,
+            overall,
+            doctors,
+        },
+    }))
+}
+
+/* ============================================================
+   GET /reports/utilization?date=&doctor_employee_id=
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct UtilizationQuery {
+    pub date: Option<String>, // YYYY-MM-DD, default today (clinic time)
+    pub doctor_employee_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UtilizationGap {
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub minutes: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DoctorUtilization {
+    pub doctor_employee_id: Uuid,
+    pub doctor_display: String,
+    /// appointments that occupy the chair (not canceled / no-show)
+    pub appointments: i64,
+    pub available_minutes: i64,
+    /// booked time inside business hours (overlapping bookings counted once)
+    pub booked_minutes: i64,
+    pub free_minutes: i64,
+    /// booked time outside business hours
+    pub overtime_minutes: i64,
+    /// booked / available (null when the clinic is not open that day)
+    pub utilization: Option<f64>,
+    pub longest_gap_minutes: i64,
+    pub gaps: Vec<UtilizationGap>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UtilizationReport {
+    pub date: NaiveDate,
+    /// clinic_holiday name when the clinic is closed that day
+    pub closed: Option<String>,
+    pub available_minutes_per_doctor: i64,
+    pub doctors: Vec<DoctorUtilization>,
+}
+
+/// Live (not from the report views): booked vs. open minutes per active doctor
+/// for one day, with the free gaps inside business hours.
+pub async fn get_utilization_report(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<UtilizationQuery>,
+) -> Result<Json<ApiOk<UtilizationReport>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let tz = clinic_time::clinic_tz(state.read_db()).await?;
+    let date = match q.date.as_deref() {
+        Some(s) => NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+            .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "date must be YYYY-MM-DD".into()))?,
+        None => clinic_time::local_today(tz),
+    };
+    let (day_start, day_end) = clinic_time::local_days_range(date, 1, tz);
+
+    let business_hours: Option<JsonValue> =
+        sqlx::query_scalar("SELECT business_hours FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(state.read_db())
+            .await?;
+    let closed: Option<String> =
+        sqlx::query_scalar("SELECT name FROM clinic_holiday WHERE holiday_date = $1 AND is_closed = true")
+            .bind(date)
+            .fetch_optional(state.read_db())
+            .await?;
+    let open = match (&business_hours, &closed) {
+        (Some(bh), None) => availability::open_intervals(&availability::opening_windows(bh), tz, date),
+        _ => Vec::new(),
+    };
+    let available_minutes = availability::total_minutes(&open);
+
+    let doctors: Vec<(Uuid, String, String)> = sqlx::query_as(
+        r#"
+        SELECT e.employee_id, e.first_name, e.last_name
+        FROM employee e
+        JOIN "dcms_user" u ON u.user_id = e.user_id
+        WHERE u.roles = 3
+          AND u.is_active = true
+          AND ($1::uuid IS NULL OR e.employee_id = $1)
+        ORDER BY e.first_name, e.last_name
+        "#,
+    )
+    .bind(q.doctor_employee_id)
+    .fetch_all(state.read_db())
+    .await?;
+
+    // canceled / no-show appointments free the chair; clipped to the day
+    let booked: Vec<(Uuid, DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        SELECT doctor_employee_id, GREATEST(start_at, $2), LEAST(end_at, $3)
+        FROM appointment
+        WHERE status NOT IN (1,3)
+          AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+          AND ($1::uuid IS NULL OR doctor_employee_id = $1)
+        "#,
+    )
+    .bind(q.doctor_employee_id)
+    .bind(day_start)
+    .bind(day_end)
+    .fetch_all(state.read_db())
+    .await?;
+
+    let doctors = doctors
+        .into_iter()
+        .map(|(doctor_employee_id, first, last)| {
+            let busy: Vec<_> = booked
+                .iter()
+                .filter(|(id, _, _)| *id == doctor_employee_id)
+                .map(|(_, start, end)| (*start, *end))
+                .collect();
+            let appointments = busy.len() as i64;
+            let busy_minutes = availability::total_minutes(&availability::merge_intervals(busy.clone()));
+            let gaps = availability::gaps(&open, &busy);
+            let free_minutes = availability::total_minutes(&gaps);
+            let booked_minutes = available_minutes - free_minutes;
+
+            DoctorUtilization {
+                doctor_employee_id,
+                doctor_display: format!("{first} {last}"),
+                appointments,
+                available_minutes,
+                booked_minutes,
+                free_minutes,
+                overtime_minutes: busy_minutes - booked_minutes,
+                utilization: ratio(booked_minutes, available_minutes),
+                longest_gap_minutes: gaps.iter().map(|(s, e)| (*e - *s).num_minutes()).max().unwrap_or(0),
+                gaps: gaps
+                    .into_iter()
+                    .map(|(start_at, end_at)| UtilizationGap { start_at, end_at, minutes: (end_at - start_at).num_minutes() })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Ok(Json(ApiOk {
+        data: UtilizationReport {
+            date,
+            closed,
+            available_minutes_per_doctor: available_minutes,
+            doctors,
+        },
+    }))
+}
+
+/* ============================================================
+   GET /reports/revenue
+   ============================================================ */
+
+// There are no invoice tables yet, so "revenue" is production: plan items of
+// finished appointments (dismissed or status 5) priced at the current catalog price.
+// Switch the source query to invoice line items once billing lands.
+
+#[derive(Debug, Serialize)]
+pub struct RevenueByDoctor {
+    pub doctor_employee_id: Uuid,
+    pub doctor_display: String,
+    pub total_cents: i64,
+    pub previous_cents: i64,
+    pub change_pct: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevenueByServiceType {
+    pub service_type: String,
+    pub display_name: String,
+    pub total_cents: i64,
+    pub previous_cents: i64,
+    pub change_pct: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevenueRow {
+    pub month: NaiveDate, // first day of month
+    pub doctor_employee_id: Uuid,
+    pub service_type: String,
+    pub qty: i64,
+    pub total_cents: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevenueReport {
+    pub basis: &'static str,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub previous_from: NaiveDate,
+    pub previous_to: NaiveDate,
+    /// when the underlying view was last rebuilt
+    pub refreshed_at: Option<DateTime<Utc>>,
+    pub total_cents: i64,
+    pub previous_total_cents: i64,
+    pub change_pct: Option<f64>,
+    pub by_doctor: Vec<RevenueByDoctor>,
+    pub by_service_type: Vec<RevenueByServiceType>,
+    pub rows: Vec<RevenueRow>,
+}
+
+fn change_pct(current: i64, previous: i64) -> Option<f64> {
+    ratio(current - previous, previous).map(|r| r * 100.0)
+}
+
+pub async fn get_revenue_report(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<RangeQuery>,
+) -> Result<Json<ApiOk<RevenueReport>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let (from, to) = parse_range(&q)?;
+
+    // previous period = same number of days right before `from`
+    let days = (to - from).num_days() + 1;
+    let previous_to = from - chrono::Duration::days(1);
+    let previous_from = previous_to - chrono::Duration::days(days - 1);
+
+    // report_daily_production: days are already clinic-local
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          (r.day >= $3) AS is_current,
+          date_trunc('month', r.day)::date AS month,
+          r.doctor_employee_id,
+          e.first_name,
+          e.last_name,
+          r.service_type,
+          COALESCE(s.display_name, r.service_type) AS display_name,
+          SUM(r.qty)::int8 AS qty,
+          SUM(r.total_cents)::int8 AS total_cents
+        FROM report_daily_production r
+        JOIN employee e ON e.employee_id = r.doctor_employee_id
+        LEFT JOIN service_catalog s ON s.service_type = r.service_type
+        WHERE r.day BETWEEN $1 AND $2
+          AND ($4::uuid IS NULL OR r.doctor_employee_id = $4)
+        GROUP BY 1, 2, 3, 4, 5, 6, 7
+        ORDER BY 2, 4, 5, 6
+        "#,
+    )
+    .bind(previous_from)
+    .bind(to)
+    .bind(from)
+    .bind(q.doctor_employee_id)
+    .fetch_all(state.read_db())
+    .await?;
+
+    let mut total_cents = 0i64;
+    let mut previous_total_cents = 0i64;
+    let mut by_doctor: Vec<RevenueByDoctor> = Vec::new();
+    let mut by_service_type: Vec<RevenueByServiceType> = Vec::new();
+    let mut out_rows = Vec::new();
+
+    for r in rows {
+        let is_current: bool = r.try_get("is_current").map_err(internal_row)?;
+        let doctor_employee_id: Uuid = r.try_get("doctor_employee_id").map_err(internal_row)?;
+        let service_type: String = r.try_get("service_type").map_err(internal_row)?;
+        let cents: i64 = r.try_get("total_cents").map_err(internal_row)?;
+
+        let doc = match by_doctor.iter_mut().find(|d| d.doctor_employee_id == doctor_employee_id) {
+            Some(d) => d,
+            None => {
+                let first: String = r.try_get("first_name").map_err(internal_row)?;
+                let last: String = r.try_get("last_name").map_err(internal_row)?;
+                by_doctor.push(RevenueByDoctor {
+                    doctor_employee_id,
+                    doctor_display: format!("{first} {last}"),
+                    total_cents: 0,
+                    previous_cents: 0,
+                    change_pct: None,
+                });
+                by_doctor.last_mut().unwrap()
+            }
+        };
+        let svc = match by_service_type.iter_mut().find(|s| s.service_type == service_type) {
+            Some(s) => s,
+            None => {
+                by_service_type.push(RevenueByServiceType {
+                    service_type: service_type.clone(),
+                    display_name: r.try_get("display_name").map_err(internal_row)?,
+                    total_cents: 0,
+                    previous_cents: 0,
+                    change_pct: None,
+                });
+                by_service_type.last_mut().unwrap()
+            }
+        };
+
+        if is_current {
+            total_cents += cents;
+            doc.total_cents += cents;
+            svc.total_cents += cents;
+            out_rows.push(RevenueRow {
+                month: r.try_get("month").map_err(internal_row)?,
+                doctor_employee_id,
+                service_type,
+                qty: r.try_get("qty").map_err(internal_row)?,
+                total_cents: cents,
+            });
+        } else {
+            previous_total_cents += cents;
+            doc.previous_cents += cents;
+            svc.previous_cents += cents;
+        }
+    }
+
+    for d in by_doctor.iter_mut() {
+        d.change_pct = change_pct(d.total_cents, d.previous_cents);
+    }
+    for s in by_service_type.iter_mut() {
+        s.change_pct = change_pct(s.total_cents, s.previous_cents);
+    }
+    by_doctor.sort_by_key(|d| std::cmp::Reverse(d.total_cents));
+    by_service_type.sort_by_key(|s| std::cmp::Reverse(s.total_cents));
+
+    Ok(Json(ApiOk {
+        data: RevenueReport {
+            basis: "production_at_catalog_price",
+            from,
+            to,
+            previous_from,
+            previous_to,
+            refreshed_at: report_refresh::refreshed_at(state.read_db(), "report_daily_production").await?,
+            total_cents,
+            previous_total_cents,
+            change_pct: change_pct(total_cents, previous_total_cents),
+            by_doctor,
+            by_service_type,
+            rows: out_rows,
+        },
+    }))
+}
+
+/* ============================================================
+   GET /reports/commissions?month=YYYY-MM[&format=csv]
+   ============================================================ */
+
+// Same production basis as /reports/revenue (finished appointments' plan items at
+// catalog price), per doctor for one clinic-local month, times employee.commission_bp.
+
+#[derive(Debug, Deserialize)]
+pub struct CommissionQuery {
+    pub month: String,          // YYYY-MM
+    pub format: Option<String>, // "json" (default) | "csv"
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommissionRow {
+    pub doctor_employee_id: Uuid,
+    pub employee_display_number: i64,
+    pub doctor_display: String,
+    pub appointments: i64,
+    pub production_cents: i64,
+    pub commission_bp: i32,
+    pub commission_cents: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommissionReport {
+    pub basis: &'static str,
+    pub month: String,
+    pub currency_code: String,
+    pub total_production_cents: i64,
+    pub total_commission_cents: i64,
+    pub doctors: Vec<CommissionRow>,
+}
+
+fn commission_csv(report: &CommissionReport) -> String {
+    let mut out = String::from(
+        "month,employee_display_number,doctor,appointments,production_cents,commission_bp,commission_cents,currency\n",
+    );
+    for d in &report.doctors {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            report.month,
+            d.employee_display_number,
+            csv_field(&d.doctor_display),
+            d.appointments,
+            d.production_cents,
+            d.commission_bp,
+            d.commission_cents,
+            report.currency_code,
+        ));
+    }
+    out
+}
+
+pub async fn get_commission_report(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<CommissionQuery>,
+) -> Result<Response, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let month_start = NaiveDate::parse_from_str(&format!("{}-01", q.month.trim()), "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "month must be YYYY-MM".into()))?;
+    let next_month = month_start + chrono::Months::new(1);
+    let csv = match q.format.as_deref().map(str::trim) {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(_) => {
+            return Err(ApiError::BadRequest("VALIDATION_ERROR", "format must be json or csv".into()));
+        }
+    };
+
+    let tz = clinic_time::clinic_tz(state.read_db())
+        .await?;
+
+    let currency_code: String =
+        sqlx::query_scalar("SELECT currency_code FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(state.read_db())
+            .await?
+            .unwrap_or_else(|| money::DEFAULT_CURRENCY.into());
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          e.employee_id,
+          e.employee_display_number,
+          e.first_name,
+          e.last_name,
+          e.commission_bp,
+          COUNT(DISTINCT a.appointment_id)::int8 AS appointments,
+          COALESCE(SUM(pi.qty::int8 * s.price_cents), 0)::int8 AS production_cents
+        FROM appointment a
+        JOIN employee e ON e.employee_id = a.doctor_employee_id
+        LEFT JOIN appointment_plan_item pi ON pi.appointment_id = a.appointment_id
+        LEFT JOIN service_catalog s ON s.service_id = pi.service_id
+        WHERE a.start_at >= $1
+          AND a.start_at <  $2
+          AND a.status <> 1
+          AND (a.dismissed_at IS NOT NULL OR a.status = 5)
+        GROUP BY e.employee_id, e.employee_display_number, e.first_name, e.last_name, e.commission_bp
+        ORDER BY e.employee_display_number
+        "#,
+    )
+    .bind(clinic_time::local_day_start(month_start, tz))
+    .bind(clinic_time::local_day_start(next_month, tz))
+    .fetch_all(state.read_db())
+    .await?;
+
+    let mut doctors = Vec::with_capacity(rows.len());
+    for r in rows {
+        let first: String = r.try_get("first_name").map_err(internal_row)?;
+        let last: String = r.try_get("last_name").map_err(internal_row)?;
+        let production_cents: i64 = r.try_get("production_cents").map_err(internal_row)?;
+        let commission_bp: i32 = r.try_get("commission_bp").map_err(internal_row)?;
+        doctors.push(CommissionRow {
+            doctor_employee_id: r.try_get("employee_id").map_err(internal_row)?,
+            employee_display_number: r.try_get("employee_display_number").map_err(internal_row)?,
+            doctor_display: format!("{first} {last}"),
+            appointments: r.try_get("appointments").map_err(internal_row)?,
+            production_cents,
+            commission_bp,
+            commission_cents: money::bp_of(production_cents, commission_bp as i64),
+        });
+    }
+
+    let report = CommissionReport {
+        basis: "production_at_catalog_price",
+        month: month_start.format("%Y-%m").to_string(),
+        currency_code,
+        total_production_cents: doctors.iter().map(|d| d.production_cents).sum(),
+        total_commission_cents: doctors.iter().map(|d| d.commission_cents).sum(),
+        doctors,
+    };
+
+    if csv {
+        let filename = format!("commissions-{}.csv", report.month);
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+            ],
+            commission_csv(&report),
+        )
+            .into_response());
+    }
+
+    Ok(Json(ApiOk { data: report }).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetCommissionRateRequest {
+    pub commission_bp: i32, // 0..10000 (1500 = 15%)
+}
+
+/// PUT /reports/commissions/{employee_id}/rate (admin only)
+pub async fn set_commission_rate(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(employee_id): Path<Uuid>,
+    Json(req): Json<SetCommissionRateRequest>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    if auth.role != Role::Admin {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin can change commission rates".into(),
+        ));
+    }
+    if !(0..=10_000).contains(&req.commission_bp) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "commission_bp must be 0..10000 (basis points)".into(),
+        ));
+    }
+
+    let res = sqlx::query("UPDATE employee SET commission_bp = $2 WHERE employee_id = $1")
+        .bind(employee_id)
+        .bind(req.commission_bp)
+        .execute(&state.db)
+        .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "employee not found".into()));
+    }
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
+
+/* ============================================================
+   GET /reports/referrals?from=&to=
+   ============================================================ */
+
+// Patients registered in the range (clinic-local days), grouped by referral source,
+// with everything they have produced so far (same production basis as
+// /reports/revenue, but over the patient's whole history, not just the range).
+
+#[derive(Debug, Deserialize)]
+pub struct ReferralReportQuery {
+    pub from: String, // YYYY-MM-DD (inclusive), by patient.created_at
+    pub to: String,   // YYYY-MM-DD (inclusive)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReferralSourceStats {
+    /// None = no source recorded
+    pub referral_source_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub new_patients: i64,
+    /// new patients with at least one finished appointment
+    pub patients_with_visits: i64,
+    pub production_cents: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReferralReport {
+    pub basis: &'static str,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub currency_code: String,
+    pub total_new_patients: i64,
+    pub total_production_cents: i64,
+    pub sources: Vec<ReferralSourceStats>,
+}
+
+pub async fn get_referral_report(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ReferralReportQuery>,
+) -> Result<Json<ApiOk<ReferralReport>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let (from, to) = parse_dates(&q.from, &q.to)?;
+
+    let tz = clinic_time::clinic_tz(state.read_db()).await?;
+
+    let currency_code: String =
+        sqlx::query_scalar("SELECT currency_code FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(state.read_db())
+            .await?
+            .unwrap_or_else(|| money::DEFAULT_CURRENCY.into());
+
+    let sources = sqlx::query_as::<_, ReferralSourceStats>(
+        r#"
+        WITH cohort AS (
+          SELECT patient_id, referral_source_id
+          FROM patient
+          WHERE created_at >= $1
+            AND created_at <  $2
+        ),
+        production AS (
+          SELECT a.patient_id, SUM(pi.qty::int8 * s.price_cents)::int8 AS cents
+          FROM appointment a
+          JOIN cohort c ON c.patient_id = a.patient_id
+          JOIN appointment_plan_item pi ON pi.appointment_id = a.appointment_id
+          JOIN service_catalog s ON s.service_id = pi.service_id
+          WHERE a.status <> 1
+            AND (a.dismissed_at IS NOT NULL OR a.status = 5)
+          GROUP BY a.patient_id
+        ),
+        visited AS (
+          SELECT DISTINCT a.patient_id
+          FROM appointment a
+          JOIN cohort c ON c.patient_id = a.patient_id
+          WHERE a.status <> 1
+            AND (a.dismissed_at IS NOT NULL OR a.status = 5)
+        )
+        SELECT
+          c.referral_source_id,
+          rs.name,
+          COUNT(*)::int8 AS new_patients,
+          COUNT(v.patient_id)::int8 AS patients_with_visits,
+          COALESCE(SUM(p.cents), 0)::int8 AS production_cents
+        FROM cohort c
+        LEFT JOIN referral_source rs ON rs.referral_source_id = c.referral_source_id
+        LEFT JOIN production p ON p.patient_id = c.patient_id
+        LEFT JOIN visited v ON v.patient_id = c.patient_id
+        GROUP BY c.referral_source_id, rs.name
+        ORDER BY production_cents DESC, new_patients DESC, rs.name NULLS LAST
+        "#,
+    )
+    .bind(clinic_time::local_day_start(from, tz))
+    .bind(clinic_time::local_day_start(to + chrono::Days::new(1), tz))
+    .fetch_all(state.read_db())
+    .await?;
+
+    Ok(Json(ApiOk {
+        data: ReferralReport {
+            basis: "production_at_catalog_price",
+            from,
+            to,
+            currency_code,
+            total_new_patients: sources.iter().map(|s| s.new_patients).sum(),
+            total_production_cents: sources.iter().map(|s| s.production_cents).sum(),
+            sources,
+        },
+    }))
+}
+
+/* ============================================================
+   GET /reports/feedback?from=&to=&doctor_employee_id=
+   ============================================================ */
+
+// Post-visit ratings (services::feedback) by the visit's clinic-local month and
+// doctor. Every texted link counts as a request; unanswered ones only lower the
+// response rate. Comments are not part of the report.
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackScores {
+    pub requests: i64,
+    pub responses: i64,
+    /// responses / requests
+    pub response_rate: Option<f64>,
+    pub avg_rating: Option<f64>,
+    /// -100..100 (5 = promoter, 1–3 = detractor)
+    pub nps: Option<f64>,
+    /// responses per rating, index 0 = 1
+    pub ratings: RatingCounts,
+}
+
+impl FeedbackScores {
+    fn new(requests: i64, ratings: RatingCounts) -> Self {
+        Self {
+            requests,
+            responses: ratings.responses(),
+            response_rate: ratio(ratings.responses(), requests),
+            avg_rating: ratings.average(),
+            nps: ratings.nps(),
+            ratings,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackRow {
+    pub month: NaiveDate, // first day of month
+    pub doctor_employee_id: Uuid,
+    pub doctor_display: String,
+    #[serde(flatten)]
+    pub scores: FeedbackScores,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub overall: FeedbackScores,
+    pub rows: Vec<FeedbackRow>,
+}
+
+pub async fn get_feedback_report(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<RangeQuery>,
+) -> Result<Json<ApiOk<FeedbackReport>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let (from, to) = parse_range(&q)?;
+    let tz = clinic_time::clinic_tz(state.read_db()).await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          date_trunc('month', a.start_at AT TIME ZONE $3)::date AS month,
+          a.doctor_employee_id,
+          e.first_name,
+          e.last_name,
+          COUNT(*)::int8 AS requests,
+          COUNT(*) FILTER (WHERE f.rating = 1)::int8 AS r1,
+          COUNT(*) FILTER (WHERE f.rating = 2)::int8 AS r2,
+          COUNT(*) FILTER (WHERE f.rating = 3)::int8 AS r3,
+          COUNT(*) FILTER (WHERE f.rating = 4)::int8 AS r4,
+          COUNT(*) FILTER (WHERE f.rating = 5)::int8 AS r5
+        FROM appointment_feedback f
+        JOIN appointment a ON a.appointment_id = f.appointment_id
+        JOIN employee e ON e.employee_id = a.doctor_employee_id
+        WHERE a.start_at >= $1
+          AND a.start_at <  $2
+          AND ($4::uuid IS NULL OR a.doctor_employee_id = $4)
+        GROUP BY 1, 2, 3, 4
+        ORDER BY 1, 3, 4
+        "#,
+    )
+    .bind(clinic_time::local_day_start(from, tz))
+    .bind(clinic_time::local_day_start(to + chrono::Days::new(1), tz))
+    .bind(tz.name())
+    .bind(q.doctor_employee_id)
+    .fetch_all(state.read_db())
+    .await?;
+
+    let mut total_requests = 0i64;
+    let mut total_ratings = RatingCounts::default();
+    let mut out_rows = Vec::with_capacity(rows.len());
+    for r in rows {
+        let requests: i64 = r.try_get("requests").map_err(internal_row)?;
+        let mut counts = [0i64; 5];
+        for (i, c) in counts.iter_mut().enumerate() {
+            *c = r.try_get(format!("r{}", i + 1).as_str()).map_err(internal_row)?;
+        }
+        let ratings = RatingCounts(counts);
+        total_requests += requests;
+        total_ratings.add(&ratings);
+
+        let first: String = r.try_get("first_name").map_err(internal_row)?;
+        let last: String = r.try_get("last_name").map_err(internal_row)?;
+        out_rows.push(FeedbackRow {
+            month: r.try_get("month").map_err(internal_row)?,
+            doctor_employee_id: r.try_get("doctor_employee_id").map_err(internal_row)?,
+            doctor_display: format!("{first} {last}"),
+            scores: FeedbackScores::new(requests, ratings),
+        });
+    }
+
+    Ok(Json(ApiOk {
+        data: FeedbackReport {
+            from,
+            to,
+            overall: FeedbackScores::new(total_requests, total_ratings),
+            rows: out_rows,
+        },
+    }))
+}
+
+/* ============================================================
+   GET /reports/appointment_sources?from=&to=
+   ============================================================ */
+
+// Bookings made in the range (appointment.created_at, clinic-local days) per
+// channel, with how they turned out so far, plus the waitlist entries created in
+// the range and how many of them were booked. Live, not from the report views
+// (those are per appointment day, not per booking day).
+
+#[derive(Debug, Deserialize)]
+pub struct AppointmentSourceQuery {
+    pub from: String, // YYYY-MM-DD (inclusive), by appointment.created_at
+    pub to: String,   // YYYY-MM-DD (inclusive)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SourceStats {
+    pub source: String, // "SCHEDULED" | "WALKIN" | "WAITLIST" | any newer channel
+    pub booked: i64,
+    pub canceled: i64,
+    pub no_show: i64,
+    pub finished: i64,
+    /// booked / all bookings in the range
+    #[sqlx(skip)]
+    pub share: Option<f64>,
+    /// no-shows / past non-canceled bookings
+    #[sqlx(skip)]
+    pub no_show_rate: Option<f64>,
+    #[serde(skip)]
+    past_kept: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WaitlistConversion {
+    pub entries: i64,
+    /// entries that got an appointment
+    pub booked: i64,
+    pub removed: i64,
+    /// still waiting or contacted
+    pub open: i64,
+    #[sqlx(skip)]
+    pub conversion_rate: Option<f64>,
+    /// entry created -> appointment booked, for the booked ones
+    pub avg_days_to_book: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppointmentSourceReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total_booked: i64,
+    pub sources: Vec<SourceStats>,
+    pub waitlist: WaitlistConversion,
+}
+
+pub async fn get_appointment_source_report(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<AppointmentSourceQuery>,
+) -> Result<Json<ApiOk<AppointmentSourceReport>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let (from, to) = parse_dates(&q.from, &q.to)?;
+
+    let tz = clinic_time::clinic_tz(state.read_db()).await?;
+    let start = clinic_time::local_day_start(from, tz);
+    let end = clinic_time::local_day_start(to + chrono::Days::new(1), tz);
+
+    // known channels always show up, with zeros
+    let mut sources = sqlx::query_as::<_, SourceStats>(
+        r#"
+        WITH booked AS (
+          SELECT a.source, a.status, a.start_at, a.arrived_at, a.seated_at,
+                 (a.dismissed_at IS NOT NULL OR a.status = 5) AS finished
+          FROM appointment a
+          WHERE a.created_at >= $1 AND a.created_at < $2
+        ),
+        channels AS (
+          SELECT unnest($3::text[]) AS source
+          UNION
+          SELECT DISTINCT source FROM booked
+        )
+        SELECT
+          c.source,
+          COUNT(b.source)::int8 AS booked,
+          COUNT(*) FILTER (WHERE b.status = 1)::int8 AS canceled,
+          COUNT(*) FILTER (
+            WHERE b.status = 3 AND NOT b.finished AND b.arrived_at IS NULL AND b.seated_at IS NULL
+          )::int8 AS no_show,
+          COUNT(*) FILTER (WHERE b.status <> 1 AND b.finished)::int8 AS finished,
+          COUNT(*) FILTER (WHERE b.status <> 1 AND b.start_at < now())::int8 AS past_kept
+        FROM channels c
+        LEFT JOIN booked b ON b.source = c.source
+        GROUP BY c.source
+        ORDER BY booked DESC, c.source
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(appointments::SOURCES)
+    .fetch_all(state.read_db())
+    .await?;
+
+    let total_booked: i64 = sources.iter().map(|s| s.booked).sum();
+    for s in &mut sources {
+        s.share = ratio(s.booked, total_booked);
+        s.no_show_rate = ratio(s.no_show, s.past_kept);
+    }
+
+    let mut waitlist = sqlx::query_as::<_, WaitlistConversion>(
+        r#"
+        SELECT
+          COUNT(*)::int8 AS entries,
+          COUNT(*) FILTER (WHERE w.scheduled_appointment_id IS NOT NULL OR w.status = 2)::int8 AS booked,
+          COUNT(*) FILTER (WHERE w.scheduled_appointment_id IS NULL AND w.status = 3)::int8 AS removed,
+          COUNT(*) FILTER (WHERE w.scheduled_appointment_id IS NULL AND w.status IN (0, 1))::int8 AS open,
+          (AVG(EXTRACT(EPOCH FROM (a.created_at - w.created_at)) / 86400.0))::float8 AS avg_days_to_book
+        FROM waitlist_entry w
+        LEFT JOIN appointment a ON a.appointment_id = w.scheduled_appointment_id
+        WHERE w.created_at >= $1 AND w.created_at < $2
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_one(state.read_db())
+    .await?;
+    waitlist.conversion_rate = ratio(waitlist.booked, waitlist.entries);
+
+    Ok(Json(ApiOk {
+        data: AppointmentSourceReport { from, to, total_booked, sources, waitlist },
+    }))
+}
+
+/* ============================================================
+   GET /reports/retention?months=&min_visits=[&format=csv]
+   ============================================================ */
+
+// Churn among regular patients, live. A visit is a finished, non-canceled
+// appointment; a patient is regular with at least `min_visits` of them, and lapsed
+// when regular, the last visit is more than `months` ago and nothing is booked
+// ahead. Cohorts are the clinic-local month of the first visit. Anonymized patients
+// and those waiting for deletion are left out.
+//
+// `outreach` lists the lapsed patients, most recently seen first, with their
+// primary number and whether it may get marketing SMS: the ids go straight into
+// POST /sms/bulk_send (which takes up to MAX_OUTREACH), and `format=csv` exports the
+// list for calling. There is no recall module to hand it to (README "Not built yet").
+
+const DEFAULT_LAPSED_MONTHS: u32 = 12;
+const MAX_LAPSED_MONTHS: u32 = 60;
+const DEFAULT_MIN_VISITS: i64 = 2;
+const MAX_MIN_VISITS: i64 = 20;
+/// same cap as POST /sms/bulk_send
+const MAX_OUTREACH: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct RetentionQuery {
+    /// not seen for this many months (default 12, 1..60)
+    pub months: Option<u32>,
+    /// finished visits that make a patient regular (default 2, 2..20)
+    pub min_visits: Option<i64>,
+    pub format: Option<String>, // "json" (default) | "csv" (the outreach list)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RetentionCohort {
+    /// first day of the first-visit month
+    pub cohort: NaiveDate,
+    /// patients with at least one visit
+    pub patients: i64,
+    pub regular: i64,
+    pub lapsed: i64,
+    /// lapsed / regular
+    #[sqlx(skip)]
+    pub churn_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OutreachPatient {
+    pub patient_id: Uuid,
+    pub register_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub cohort: NaiveDate,
+    pub visits: i64,
+    pub last_visit_at: DateTime<Utc>,
+    /// primary live number; None when the patient has none
+    pub phone_number_id: Option<Uuid>,
+    pub phone_number: Option<String>,
+    /// neither the number nor the patient opted out of marketing
+    pub marketing_allowed: bool,
+    pub preferred_channel: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RetentionReport {
+    pub months: u32,
+    pub min_visits: i64,
+    /// last visits before this count as lapsed
+    pub lapsed_before: DateTime<Utc>,
+    pub regular: i64,
+    pub lapsed: i64,
+    pub churn_rate: Option<f64>,
+    pub cohorts: Vec<RetentionCohort>,
+    /// all lapsed patients; `outreach` holds the first MAX_OUTREACH
+    pub outreach_total: i64,
+    pub outreach: Vec<OutreachPatient>,
+}
+
+/// Per-patient visits: $1 min_visits, $2 lapsed_before, $3 clinic tz.
+const RETENTION_PATIENTS_CTE: &str = r#"
+    WITH visits AS (
+      SELECT a.patient_id,
+             MIN(a.start_at) AS first_visit_at,
+             MAX(a.start_at) AS last_visit_at,
+             COUNT(*)::int8 AS visits
+      FROM appointment a
+      WHERE a.status <> 1
+        AND (a.dismissed_at IS NOT NULL OR a.status = 5)
+        AND a.start_at < now()
+      GROUP BY a.patient_id
+    ),
+    retention AS (
+      SELECT v.*,
+             date_trunc('month', v.first_visit_at AT TIME ZONE $3)::date AS cohort,
+             v.visits >= $1 AS regular,
+             v.visits >= $1
+               AND v.last_visit_at < $2
+               AND NOT EXISTS (
+                 SELECT 1 FROM appointment b
+                 WHERE b.patient_id = v.patient_id AND b.status <> 1 AND b.start_at >= now()
+               ) AS lapsed
+      FROM visits v
+      JOIN patient p ON p.patient_id = v.patient_id
+      WHERE p.anonymized_at IS NULL AND p.deletion_requested_at IS NULL
+    )
+"#;
+
+fn outreach_csv(report: &RetentionReport) -> String {
+    let mut out = String::from(
+        "register_number,first_name,last_name,cohort,visits,last_visit_at,phone_number,marketing_allowed,preferred_channel\n",
+    );
+    for p in &report.outreach {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            csv_field(&p.register_number),
+            csv_field(&p.first_name),
+            csv_field(&p.last_name),
+            p.cohort.format("%Y-%m"),
+            p.visits,
+            p.last_visit_at.to_rfc3339(),
+            csv_field(p.phone_number.as_deref().unwrap_or("")),
+            p.marketing_allowed,
+            p.preferred_channel.as_deref().unwrap_or(""),
+        ));
+    }
+    out
+}
+
+pub async fn get_retention_report(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<RetentionQuery>,
+) -> Result<Response, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let months = q.months.unwrap_or(DEFAULT_LAPSED_MONTHS);
+    if !(1..=MAX_LAPSED_MONTHS).contains(&months) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("months must be 1..{MAX_LAPSED_MONTHS}"),
+        ));
+    }
+    let min_visits = q.min_visits.unwrap_or(DEFAULT_MIN_VISITS);
+    if !(2..=MAX_MIN_VISITS).contains(&min_visits) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("min_visits must be 2..{MAX_MIN_VISITS}"),
+        ));
+    }
+    let csv = match q.format.as_deref().map(str::trim) {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(_) => {
+            return Err(ApiError::BadRequest("VALIDATION_ERROR", "format must be json or csv".into()));
+        }
+    };
+
+    let now = Utc::now();
+    let lapsed_before = now
+        .checked_sub_months(chrono::Months::new(months))
+        .ok_or_else(|| ApiError::Internal("lapsed_before out of range".into()))?;
+    let tz = clinic_time::clinic_tz(state.read_db()).await?;
+
+    let mut cohorts = sqlx::query_as::<_, RetentionCohort>(&format!(
+        r#"
+        {RETENTION_PATIENTS_CTE}
+        SELECT
+          r.cohort,
+          COUNT(*)::int8 AS patients,
+          COUNT(*) FILTER (WHERE r.regular)::int8 AS regular,
+          COUNT(*) FILTER (WHERE r.lapsed)::int8 AS lapsed
+        FROM retention r
+        GROUP BY r.cohort
+        ORDER BY r.cohort
+        "#
+    ))
+    .bind(min_visits)
+    .bind(lapsed_before)
+    .bind(tz.name())
+    .fetch_all(state.read_db())
+    .await?;
+    for c in &mut cohorts {
+        c.churn_rate = ratio(c.lapsed, c.regular);
+    }
+    let regular: i64 = cohorts.iter().map(|c| c.regular).sum();
+    let lapsed: i64 = cohorts.iter().map(|c| c.lapsed).sum();
+
+    let outreach = sqlx::query_as::<_, OutreachPatient>(&format!(
+        r#"
+        {RETENTION_PATIENTS_CTE}
+        SELECT
+          p.patient_id, p.register_number, p.first_name, p.last_name,
+          r.cohort, r.visits, r.last_visit_at,
+          ph.phone_number_id, ph.phone_number,
+          (ph.phone_number_id IS NOT NULL AND {allowed}) AS marketing_allowed,
+          p.preferred_channel
+        FROM retention r
+        JOIN patient p ON p.patient_id = r.patient_id
+        LEFT JOIN LATERAL (
+          SELECT pn.phone_number_id, pn.phone_number, pn.marketing_opt_out_at
+          FROM phone_number pn
+          WHERE pn.patient_id = r.patient_id AND pn.is_primary AND pn.released_at IS NULL
+          LIMIT 1
+        ) ph ON TRUE
+        WHERE r.lapsed
+        ORDER BY r.last_visit_at DESC, p.register_number
+        LIMIT $4
+        "#,
+        allowed = consent::allowed_sql(ContactPurpose::Marketing),
+    ))
+    .bind(min_visits)
+    .bind(lapsed_before)
+    .bind(tz.name())
+    .bind(MAX_OUTREACH)
+    .fetch_all(state.read_db())
+    .await?;
+
+    let report = RetentionReport {
+        months,
+        min_visits,
+        lapsed_before,
+        regular,
+        lapsed,
+        churn_rate: ratio(lapsed, regular),
+        cohorts,
+        outreach_total: lapsed,
+        outreach,
+    };
+
+    if csv {
+        let filename = format!("retention-outreach-{}.csv", clinic_time::local_today(tz));
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+            ],
+            outreach_csv(&report),
+        )
+            .into_response());
+    }
+
+    Ok(Json(ApiOk { data: report }).into_response())
+}
+
+/* ============================================================
+   GET /reports/day_close?date=[&format=json|html|pdf]
+   POST /reports/day_close/sign_off
+   ============================================================ */
+
+// The manager's end-of-day page (crate::day_close). Live until signed off, the
+// signed snapshot afterwards; html/pdf are the printable (and mailable) copy.
+
+#[derive(Debug, Deserialize)]
+pub struct DayCloseQuery {
+    pub date: Option<String>,   // YYYY-MM-DD, default clinic-local today
+    pub format: Option<String>, // "json" (default) | "html" | "pdf"
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DayCloseSignOffRequest {
+    pub date: String, // YYYY-MM-DD
+    pub note: Option<String>,
+}
+
+/// A past or current clinic-local day.
+fn parse_close_date(date: Option<&str>, tz: chrono_tz::Tz) -> Result<NaiveDate, ApiError> {
+    let today = clinic_time::local_today(tz);
+    let date = match date.map(str::trim) {
+        None | Some("") => today,
+        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", "date must be YYYY-MM-DD".into()))?,
+    };
+    if date > today {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "date must not be in the future".into()));
+    }
+    Ok(date)
+}
+
+pub async fn get_day_close(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<DayCloseQuery>,
+) -> Result<Response, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let format = match q.format.as_deref().map(str::trim) {
+        None | Some("") | Some("json") => "json",
+        Some(f @ ("html" | "pdf")) => f,
+        Some(_) => {
+            return Err(ApiError::BadRequest("VALIDATION_ERROR", "format must be json, html or pdf".into()));
+        }
+    };
+    let tz = clinic_time::clinic_tz(state.read_db()).await?;
+    let date = parse_close_date(q.date.as_deref(), tz)?;
+    let day = day_close::load(state.read_db(), date, tz).await?;
+
+    let (content_type, body) = match format {
+        "html" => ("text/html; charset=utf-8", day.to_html(tz).into_bytes()),
+        "pdf" => ("application/pdf", pdf::text_document(&day.title(), &day.to_text(tz))),
+        _ => return Ok(Json(ApiOk { data: day }).into_response()),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("inline; filename=\"day-close-{date}.{format}\"")),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+pub async fn sign_off_day_close(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<DayCloseSignOffRequest>,
+) -> Result<Json<ApiOk<DayClose>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let date = parse_close_date(Some(&req.date), tz)?;
+    let note = day_close::normalize_note(req.note)?;
+    let day = day_close::sign_off(&state.db, &auth, date, tz, note).await?;
+    Ok(Json(ApiOk { data: day }))
+}
+
+/* ============================================================
+   GET /dashboard/manager
+   ============================================================ */
+
+// Today's numbers for the manager home screen, clinic-local day. Revenue has the
+// same production basis as /reports/revenue; there are no payment, balance or
+// stock tables, so collected revenue, outstanding balances and low stock are not
+// reported (README "Not built yet").
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TodayAppointments {
+    /// not canceled
+    pub booked: i64,
+    /// arrived so far (also those seated or finished since)
+    pub arrived: i64,
+    pub finished: i64,
+    pub no_show: i64,
+    pub canceled: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManagerDashboard {
+    pub date: NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub appointments: TodayAppointments,
+    pub revenue_basis: &'static str,
+    pub revenue_today_cents: i64,
+    /// open / in progress, nobody assigned (the inbox)
+    pub unassigned_tasks: i64,
+}
+
+pub async fn get_manager_dashboard(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<ApiOk<ManagerDashboard>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let date = clinic_time::local_today(tz);
+    let (day_start, day_end) = clinic_time::local_days_range(date, 1, tz);
+
+    let row = sqlx::query(
+        r#"
+        WITH today AS (
+          SELECT a.appointment_id, a.status, a.arrived_at,
+                 (a.dismissed_at IS NOT NULL OR a.status = $5) AS finished
+          FROM appointment a
+          WHERE a.start_at >= $1 AND a.start_at < $2
+        )
+        SELECT
+          COUNT(*) FILTER (WHERE t.status <> $3) AS booked,
+          COUNT(*) FILTER (WHERE t.status <> $3 AND t.arrived_at IS NOT NULL) AS arrived,
+          COUNT(*) FILTER (WHERE t.status <> $3 AND t.finished) AS finished,
+          COUNT(*) FILTER (WHERE t.status = $4 AND t.arrived_at IS NULL AND NOT t.finished) AS no_show,
+          COUNT(*) FILTER (WHERE t.status = $3) AS canceled,
+          (
+            SELECT COALESCE(SUM(pi.qty::int8 * s.price_cents), 0)::int8
+            FROM today f
+            JOIN appointment_plan_item pi ON pi.appointment_id = f.appointment_id
+            JOIN service_catalog s ON s.service_id = pi.service_id
+            WHERE f.status <> $3 AND f.finished
+          ) AS revenue_today_cents,
+          (
+            SELECT COUNT(*) FROM task
+            WHERE assigned_to_employee_id IS NULL AND status IN ($6, $7)
+          ) AS unassigned_tasks
+        FROM today t
+        "#,
+    )
+    .bind(day_start)
+    .bind(day_end)
+    .bind(AppointmentStatus::Canceled)
+    .bind(AppointmentStatus::NoShow)
+    .bind(AppointmentStatus::Finished)
+    .bind(TaskStatus::Open)
+    .bind(TaskStatus::InProgress)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk {
+        data: ManagerDashboard {
+            date,
+            generated_at: Utc::now(),
+            appointments: TodayAppointments::from_row(&row).map_err(internal_row)?,
+            revenue_basis: "production_at_catalog_price",
+            revenue_today_cents: row.try_get("revenue_today_cents").map_err(internal_row)?,
+            unassigned_tasks: row.try_get("unassigned_tasks").map_err(internal_row)?,
+        },
+    }))
+}
+
+/* ============================================================
+   misc
+   ============================================================ */
+
+fn internal_row(e: sqlx::Error) -> ApiError {
+    ApiError::Internal(format!("row decode error: {e}"))
+}
@@ -1,21 +1,55 @@
 // src/routes/service_routes.rs
 
-use axum::{Json, Router, extract::State, routing::get};
+use axum::{
+    Router,
+    extract::{Path, Query, State},
+    middleware,
+    routing::get,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
 
 use crate::{
     error::ApiError,
-    middleware::auth_context::AuthContext,
-    models::{AppState, ServiceCatalogRow},
+    extract::Json,
+    middleware::{auth_context::AuthContext, etag},
+    models::{ApiList, ApiOk, AppState, ServiceCatalogRow},
+    money, photos,
 };
 
     pub fn router() -> Router<AppState> {
-        Router::new().route("/", get(list_services))
+        Router::new()
+            .route("/", get(list_services))
+            // booking UI: doctors who perform the service
+            .route("/{service_id}/providers", get(list_service_providers))
+            .route_layer(middleware::from_fn(etag::etag))
     }
 
+/// Catalog row + tax computed from clinic_settings (price_cents is net, in clinic currency).
+#[derive(Debug, Serialize)]
+pub struct ServiceCatalogItem {
+    #[serde(flatten)]
+    pub row: ServiceCatalogRow,
+    pub currency_code: String,
+    pub tax_rate_bp: i64,
+    pub tax_cents: i64,
+    pub gross_cents: i64,
+}
+
 pub async fn list_services( 
     State(state): State<AppState>,
     _auth: AuthContext,
-) -> Result<Json<Vec<ServiceCatalogRow>>, ApiError> {
+) -> Result<Json<ApiList<ServiceCatalogItem>>, ApiError> {
+    let settings = sqlx::query_as::<_, (String, JsonValue)>(
+        "SELECT currency_code, tax_rates FROM clinic_settings WHERE singleton_id = TRUE",
+    )
+    .fetch_optional(&state.db)
+    .await?;
+    let (currency_code, tax_rates) =
+        settings.unwrap_or_else(|| (money::DEFAULT_CURRENCY.into(), money::default_tax_rates()));
+
     let rows: Vec<ServiceCatalogRow> = sqlx::query_as::<_, ServiceCatalogRow>(
         r#"
         SELECT
@@ -26,6 +60,7 @@ pub async fn list_services(
           default_duration_min,
           disclaimer,
           price_cents,
+          tax_category,
           is_active,
           created_at,
           updated_at
@@ -35,8 +70,115 @@ pub async fn list_services(
         "#,
     )
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
+
+    let items = rows
+        .into_iter()
+        .map(|row| {
+            let tax_rate_bp = money::tax_rate_bp(&tax_rates, &row.tax_category);
+            let tax_cents = money::tax_cents(row.price_cents as i64, tax_rate_bp);
+            ServiceCatalogItem {
+                gross_cents: row.price_cents as i64 + tax_cents,
+                currency_code: currency_code.clone(),
+                tax_rate_bp,
+                tax_cents,
+                row,
+            }
+        })
+        .collect();
+
+    Ok(Json(ApiOk { data: items }))
+}
+
+/* ============================================================
+   GET /services/{id}/providers
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct ProvidersQuery {
+    pub location_id: Option<Uuid>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ProviderDbRow {
+    employee_id: Uuid,
+    employee_display_number: i64,
+    display_name: String,
+    photo_updated_at: Option<DateTime<Utc>>,
+    location_id: Option<Uuid>,
+    duration_min: Option<i32>,
+    duration_overridden: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceProvider {
+    pub employee_id: Uuid,
+    pub employee_display_number: i64,
+    pub display_name: String,
+    pub photo_url: Option<String>,
+    pub location_id: Option<Uuid>,
+    /// doctor override, else the catalog default (None = unspecified)
+    pub duration_min: Option<i32>,
+    pub duration_overridden: bool,
+}
+
+/// Active doctors who perform the service: explicitly listed in employee_service,
+/// or with no capability rows at all (= performs everything).
+pub async fn list_service_providers(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+    Path(service_id): Path<Uuid>,
+    Query(q): Query<ProvidersQuery>,
+) -> Result<Json<ApiList<ServiceProvider>>, ApiError> {
+    let exists: Option<Uuid> = sqlx::query_scalar("SELECT service_id FROM service_catalog WHERE service_id = $1")
+        .bind(service_id)
+        .fetch_optional(&state.db)
+        .await?;
+    if exists.is_none() {
+        return Err(ApiError::NotFound("NOT_FOUND", "service not found".into()));
+    }
+
+    let rows: Vec<ProviderDbRow> = sqlx::query_as::<_, ProviderDbRow>(
+        r#"
+        SELECT
+          e.employee_id,
+          e.employee_display_number,
+          e.first_name || ' ' || e.last_name AS display_name,
+          e.photo_updated_at,
+          e.location_id,
+          COALESCE(es.duration_min, sc.default_duration_min) AS duration_min,
+          (es.duration_min IS NOT NULL) AS duration_overridden
+        FROM employee e
+        JOIN "dcms_user" u ON u.user_id = e.user_id
+        JOIN service_catalog sc ON sc.service_id = $1
+        LEFT JOIN employee_service es ON es.employee_id = e.employee_id AND es.service_id = sc.service_id
+        WHERE u.roles = 3
+          AND u.is_active = true
+          AND (
+            es.service_id IS NOT NULL
+            OR NOT EXISTS (SELECT 1 FROM employee_service x WHERE x.employee_id = e.employee_id)
+          )
+          AND ($2::uuid IS NULL OR e.location_id = $2)
+        ORDER BY e.employee_display_number ASC
+        "#,
+    )
+    .bind(service_id)
+    .bind(q.location_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let data = rows
+        .into_iter()
+        .map(|r| ServiceProvider {
+            photo_url: photos::photo_url("employees", r.employee_id, r.photo_updated_at),
+            employee_id: r.employee_id,
+            employee_display_number: r.employee_display_number,
+            display_name: r.display_name,
+            location_id: r.location_id,
+            duration_min: r.duration_min,
+            duration_overridden: r.duration_overridden,
+        })
+        .collect();
 
-    Ok(Json(rows))
+    Ok(Json(ApiOk { data }))
 }
@@ -0,0 +1,625 @@
+// src/routes/shift_routes.rs
+//
+// Staff shift roster (reception, assistants; doctors are scheduled by business
+// hours and time off). Admin/manager edit it, all staff can read it. A shift may
+// not overlap another shift of the same employee or their approved time off.
+// Appointments check it when an assistant is assigned (ASSISTANT_NOT_ON_SHIFT);
+// GET /assistants/{id}/day shows an assistant's shifts against their assignments.
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, patch},
+    Router,
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    audit, clinic_time,
+    error::ApiError,
+    extract::Json,
+    locations,
+    middleware::auth_context::AuthContext,
+    models::{ApiList, ApiOk, AppState, AppointmentStatus, OkData, Role, TimeOffStatus},
+    services::{availability, patients::deserialize_double_option},
+};
+
+const MAX_SHIFT_HOURS: i64 = 24;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/shifts", get(list_shifts).post(create_shift))
+        .route("/shifts/week", get(get_shift_week))
+        .route("/shifts/{shift_id}", patch(patch_shift).delete(delete_shift))
+        .route("/assistants/{employee_id}/day", get(get_assistant_day))
+}
+
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    }
+}
+
+fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_admin_or_manager() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin/manager can edit the shift roster".into(),
+        ))
+    }
+}
+
+fn shift_not_found() -> ApiError {
+    ApiError::NotFound("NOT_FOUND", "shift not found".into())
+}
+
+/* ============================================================
+   DTOs
+   ============================================================ */
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ShiftRow {
+    pub shift_id: Uuid,
+    pub employee_id: Uuid,
+    pub employee_name: String,
+    pub location_id: Option<Uuid>,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub note: Option<String>,
+}
+
+const SHIFT_SELECT: &str = r#"
+    SELECT
+      s.shift_id, s.employee_id, e.first_name || ' ' || e.last_name AS employee_name,
+      s.location_id, s.start_at, s.end_at, s.note
+    FROM staff_shift s
+    JOIN employee e ON e.employee_id = s.employee_id
+"#;
+
+async fn fetch_shift(conn: &mut PgConnection, shift_id: Uuid) -> Result<Option<ShiftRow>, ApiError> {
+    Ok(sqlx::query_as::<_, ShiftRow>(&format!("{SHIFT_SELECT} WHERE s.shift_id = $1"))
+        .bind(shift_id)
+        .fetch_optional(conn)
+        .await?)
+}
+
+/// Shifts overlapping [start, end), optionally one employee's, in the given
+/// locations (shifts without a location always match).
+async fn shifts_in_range(
+    db: &sqlx::PgPool,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    employee_id: Option<Uuid>,
+    locations: Option<&[Uuid]>,
+) -> Result<Vec<ShiftRow>, ApiError> {
+    Ok(sqlx::query_as::<_, ShiftRow>(&format!(
+        r#"{SHIFT_SELECT}
+        WHERE s.start_at < $2 AND s.end_at > $1
+          AND ($3::uuid IS NULL OR s.employee_id = $3)
+          AND ($4::uuid[] IS NULL OR s.location_id IS NULL OR s.location_id = ANY($4))
+        ORDER BY s.start_at, employee_name
+        "#
+    ))
+    .bind(start)
+    .bind(end)
+    .bind(employee_id)
+    .bind(locations)
+    .fetch_all(db)
+    .await?)
+}
+
+/// Range, rostered employee, overlapping shifts and approved time off. Runs on
+/// the write transaction; `exclude` is the shift being edited.
+async fn validate_shift(
+    conn: &mut PgConnection,
+    employee_id: Uuid,
+    start_at: DateTime<Utc>,
+    end_at: DateTime<Utc>,
+    exclude: Option<Uuid>,
+) -> Result<(), ApiError> {
+    if end_at <= start_at {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "end_at must be > start_at".into()));
+    }
+    if end_at - start_at > Duration::hours(MAX_SHIFT_HOURS) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("a shift must be at most {MAX_SHIFT_HOURS} hours"),
+        ));
+    }
+
+    let role: Option<Option<Role>> = sqlx::query_scalar(
+        r#"
+        SELECT u.roles
+        FROM employee e
+        LEFT JOIN dcms_user u ON u.user_id = e.user_id
+        WHERE e.employee_id = $1
+        "#,
+    )
+    .bind(employee_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    match role {
+        None => return Err(ApiError::NotFound("NOT_FOUND", "employee not found".into())),
+        Some(Some(Role::Doctor)) => {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "doctors are scheduled by business hours and time off, not shifts".into(),
+            ));
+        }
+        Some(_) => {}
+    }
+
+    let overlapping: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT shift_id
+        FROM staff_shift
+        WHERE employee_id = $1
+          AND ($4::uuid IS NULL OR shift_id <> $4)
+          AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+        LIMIT 1
+        "#,
+    )
+    .bind(employee_id)
+    .bind(start_at)
+    .bind(end_at)
+    .bind(exclude)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(id) = overlapping {
+        return Err(ApiError::Conflict("SHIFT_OVERLAP", format!("overlaps shift {id}")));
+    }
+
+    let time_off: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT time_off_id
+        FROM employee_time_off
+        WHERE employee_id = $1
+          AND status = $4
+          AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+        LIMIT 1
+        "#,
+    )
+    .bind(employee_id)
+    .bind(start_at)
+    .bind(end_at)
+    .bind(TimeOffStatus::Approved)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(id) = time_off {
+        return Err(ApiError::Conflict(
+            "SHIFT_TIME_OFF",
+            format!("the employee has approved time off {id} in this range"),
+        ));
+    }
+
+    Ok(())
+}
+
+fn clean_note(note: Option<String>) -> Result<Option<String>, ApiError> {
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    if note.as_deref().is_some_and(|n| n.chars().count() > 500) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "note must be at most 500 chars".into()));
+    }
+    Ok(note)
+}
+
+/* ============================================================
+   GET /shifts
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct ShiftListQuery {
+    /// shifts overlapping [from, to)
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub employee_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+}
+
+pub async fn list_shifts(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ShiftListQuery>,
+) -> Result<Json<ApiList<ShiftRow>>, ApiError> {
+    ensure_staff(&auth)?;
+    if q.to <= q.from || q.to - q.from > Duration::days(62) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "to must be after from, at most 62 days".into()));
+    }
+    let scope = locations::location_scope(&state.db, &auth, q.location_id).await?;
+
+    let rows = shifts_in_range(&state.db, q.from, q.to, q.employee_id, scope.as_deref()).await?;
+    Ok(Json(ApiOk { data: rows }))
+}
+
+/* ============================================================
+   GET /shifts/week
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct ShiftWeekQuery {
+    /// any date in the week (clinic-local); default: this week
+    pub date: Option<NaiveDate>,
+    pub location_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShiftWeek {
+    /// Monday
+    pub week_start: NaiveDate,
+    pub days: Vec<NaiveDate>,
+    pub employees: Vec<ShiftWeekEmployee>,
+}
+
+/// One roster row: the employee's shifts per day (Mon..Sun, by clinic-local start).
+#[derive(Debug, Serialize)]
+pub struct ShiftWeekEmployee {
+    pub employee_id: Uuid,
+    pub employee_name: String,
+    pub total_minutes: i64,
+    pub days: Vec<Vec<ShiftRow>>,
+}
+
+fn week_roster(rows: Vec<ShiftRow>, week_start: NaiveDate, tz: Tz) -> Vec<ShiftWeekEmployee> {
+    let mut out: Vec<ShiftWeekEmployee> = Vec::new();
+    for row in rows {
+        let day = (row.start_at.with_timezone(&tz).date_naive() - week_start).num_days().clamp(0, 6) as usize;
+        let idx = match out.iter().position(|e| e.employee_id == row.employee_id) {
+            Some(i) => i,
+            None => {
+                out.push(ShiftWeekEmployee {
+                    employee_id: row.employee_id,
+                    employee_name: row.employee_name.clone(),
+                    total_minutes: 0,
+                    days: vec![Vec::new(); 7],
+                });
+                out.len() - 1
+            }
+        };
+        let entry = &mut out[idx];
+        entry.total_minutes += (row.end_at - row.start_at).num_minutes();
+        entry.days[day].push(row);
+    }
+    out.sort_by(|a, b| a.employee_name.cmp(&b.employee_name));
+    out
+}
+
+pub async fn get_shift_week(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ShiftWeekQuery>,
+) -> Result<Json<ApiOk<ShiftWeek>>, ApiError> {
+    ensure_staff(&auth)?;
+    let scope = locations::location_scope(&state.db, &auth, q.location_id).await?;
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+
+    let date = q.date.unwrap_or_else(|| clinic_time::local_today(tz));
+    let week_start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+    let (start, end) = clinic_time::local_days_range(week_start, 7, tz);
+
+    let rows = shifts_in_range(&state.db, start, end, None, scope.as_deref()).await?;
+
+    Ok(Json(ApiOk {
+        data: ShiftWeek {
+            week_start,
+            days: (0..7).map(|i| week_start + Duration::days(i)).collect(),
+            employees: week_roster(rows, week_start, tz),
+        },
+    }))
+}
+
+/* ============================================================
+   POST /shifts
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct CreateShiftRequest {
+    pub employee_id: Uuid,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub location_id: Option<Uuid>,
+    pub note: Option<String>,
+}
+
+pub async fn create_shift(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateShiftRequest>,
+) -> Result<Json<ApiOk<ShiftRow>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let note = clean_note(req.note)?;
+    if let Some(id) = req.location_id {
+        locations::ensure_location_access(&state.db, &auth, id).await?;
+    }
+
+    let mut tx = state.db.begin().await?;
+    validate_shift(&mut tx, req.employee_id, req.start_at, req.end_at, None).await?;
+
+    let shift_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO staff_shift (employee_id, location_id, start_at, end_at, note, created_by_user_id, updated_by_user_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $6)
+        RETURNING shift_id
+        "#,
+    )
+    .bind(req.employee_id)
+    .bind(req.location_id)
+    .bind(req.start_at)
+    .bind(req.end_at)
+    .bind(note.as_deref())
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("SHIFT_CREATE_FAILED"))?;
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "shift.create",
+        "staff_shift",
+        Some(shift_id),
+        serde_json::json!({ "employee_id": req.employee_id, "start_at": req.start_at, "end_at": req.end_at }),
+    )
+    .await?;
+
+    let data = fetch_shift(&mut tx, shift_id).await?.ok_or_else(shift_not_found)?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/* ============================================================
+   PATCH /shifts/{id} | DELETE /shifts/{id}
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct PatchShiftRequest {
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    /// null clears
+    #[serde(default, deserialize_with = "deserialize_double_option")]
+    pub location_id: Option<Option<Uuid>>,
+    /// null clears
+    #[serde(default, deserialize_with = "deserialize_double_option")]
+    pub note: Option<Option<String>>,
+}
+
+pub async fn patch_shift(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(shift_id): Path<Uuid>,
+    Json(req): Json<PatchShiftRequest>,
+) -> Result<Json<ApiOk<ShiftRow>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    if let Some(Some(id)) = req.location_id {
+        locations::ensure_location_access(&state.db, &auth, id).await?;
+    }
+    let note = match req.note {
+        Some(n) => Some(clean_note(n)?),
+        None => None,
+    };
+
+    let mut tx = state.db.begin().await?;
+    let cur: Option<(Uuid, DateTime<Utc>, DateTime<Utc>)> =
+        sqlx::query_as("SELECT employee_id, start_at, end_at FROM staff_shift WHERE shift_id = $1 FOR UPDATE")
+            .bind(shift_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let (employee_id, cur_start, cur_end) = cur.ok_or_else(shift_not_found)?;
+    let (start_at, end_at) = (req.start_at.unwrap_or(cur_start), req.end_at.unwrap_or(cur_end));
+    if (start_at, end_at) != (cur_start, cur_end) {
+        validate_shift(&mut tx, employee_id, start_at, end_at, Some(shift_id)).await?;
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE staff_shift
+        SET start_at = $2,
+            end_at = $3,
+            location_id = CASE WHEN $4 THEN $5 ELSE location_id END,
+            note = CASE WHEN $6 THEN $7 ELSE note END,
+            updated_by_user_id = $8
+        WHERE shift_id = $1
+        "#,
+    )
+    .bind(shift_id)
+    .bind(start_at)
+    .bind(end_at)
+    .bind(req.location_id.is_some())
+    .bind(req.location_id.flatten())
+    .bind(note.is_some())
+    .bind(note.flatten())
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("SHIFT_UPDATE_FAILED"))?;
+
+    audit::record(
+        &mut *tx,
+        &auth,
+        "shift.update",
+        "staff_shift",
+        Some(shift_id),
+        serde_json::json!({ "start_at": start_at, "end_at": end_at }),
+    )
+    .await?;
+
+    let data = fetch_shift(&mut tx, shift_id).await?.ok_or_else(shift_not_found)?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/// Appointments already assigned to the employee keep their assistant.
+pub async fn delete_shift(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(shift_id): Path<Uuid>,
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let mut tx = state.db.begin().await?;
+    let res = sqlx::query("DELETE FROM staff_shift WHERE shift_id = $1")
+        .bind(shift_id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(shift_not_found());
+    }
+    audit::record(&mut *tx, &auth, "shift.delete", "staff_shift", Some(shift_id), serde_json::json!({})).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data: OkData { ok: true } }))
+}
+
+/* ============================================================
+   GET /assistants/{id}/day
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct AssistantDayQuery {
+    /// clinic-local; default today
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AssistantAssignment {
+    pub appointment_id: Uuid,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub status: AppointmentStatus,
+    pub doctor_employee_id: Uuid,
+    pub doctor_name: String,
+    pub patient_id: Uuid,
+    pub patient_name: String,
+    pub location_id: Option<Uuid>,
+    /// covered by the assistant's shifts (false = assigned off shift, e.g. by an override)
+    #[sqlx(skip)]
+    pub on_shift: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssistantDay {
+    pub date: NaiveDate,
+    pub employee_id: Uuid,
+    pub employee_name: String,
+    pub shifts: Vec<ShiftRow>,
+    pub shift_minutes: i64,
+    pub assigned_minutes: i64,
+    /// on shift without an appointment
+    pub idle_minutes: i64,
+    /// assigned / shift minutes; None without shifts
+    pub load: Option<f64>,
+    pub appointments: Vec<AssistantAssignment>,
+}
+
+/// An assistant's day: shifts, slot-occupying appointments they assist in, and
+/// how much of the shift time those fill.
+pub async fn get_assistant_day(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(employee_id): Path<Uuid>,
+    Query(q): Query<AssistantDayQuery>,
+) -> Result<Json<ApiOk<AssistantDay>>, ApiError> {
+    ensure_staff(&auth)?;
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let date = q.date.unwrap_or_else(|| clinic_time::local_today(tz));
+    let (day_start, day_end) = clinic_time::local_days_range(date, 1, tz);
+
+    let employee_name: Option<String> =
+        sqlx::query_scalar("SELECT first_name || ' ' || last_name FROM employee WHERE employee_id = $1")
+            .bind(employee_id)
+            .fetch_optional(&state.db)
+            .await?;
+    let employee_name = employee_name.ok_or_else(|| ApiError::NotFound("NOT_FOUND", "employee not found".into()))?;
+
+    let shifts = shifts_in_range(&state.db, day_start, day_end, Some(employee_id), None).await?;
+    let mut appointments = sqlx::query_as::<_, AssistantAssignment>(
+        r#"
+        SELECT
+          a.appointment_id, a.start_at, a.end_at, a.status,
+          a.doctor_employee_id, d.first_name || ' ' || d.last_name AS doctor_name,
+          a.patient_id, p.first_name || ' ' || p.last_name AS patient_name,
+          a.location_id
+        FROM appointment a
+        JOIN employee d ON d.employee_id = a.doctor_employee_id
+        JOIN patient p ON p.patient_id = a.patient_id
+        WHERE a.assistant_employee_id = $1
+          AND a.status NOT IN ($4, $5)
+          AND a.start_at < $3 AND a.end_at > $2
+        ORDER BY a.start_at
+        "#,
+    )
+    .bind(employee_id)
+    .bind(day_start)
+    .bind(day_end)
+    .bind(AppointmentStatus::Canceled)
+    .bind(AppointmentStatus::NoShow)
+    .fetch_all(&state.db)
+    .await?;
+
+    let on_shift = availability::merge_intervals(shifts.iter().map(|s| (s.start_at, s.end_at)).collect());
+    let assigned = availability::merge_intervals(appointments.iter().map(|a| (a.start_at, a.end_at)).collect());
+    for a in &mut appointments {
+        a.on_shift = on_shift.iter().any(|(start, end)| *start <= a.start_at && a.end_at <= *end);
+    }
+
+    let shift_minutes = availability::total_minutes(&on_shift);
+    let assigned_minutes = availability::total_minutes(&assigned);
+    Ok(Json(ApiOk {
+        data: AssistantDay {
+            date,
+            employee_id,
+            employee_name,
+            shifts,
+            shift_minutes,
+            assigned_minutes,
+            idle_minutes: availability::total_minutes(&availability::gaps(&on_shift, &assigned)),
+            load: (shift_minutes > 0).then(|| assigned_minutes as f64 / shift_minutes as f64),
+            appointments,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn shift(employee: u128, name: &str, start: DateTime<Utc>, hours: i64) -> ShiftRow {
+        ShiftRow {
+            shift_id: Uuid::new_v4(),
+            employee_id: Uuid::from_u128(employee),
+            employee_name: name.into(),
+            location_id: None,
+            start_at: start,
+            end_at: start + Duration::hours(hours),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn roster_groups_by_employee_and_local_day() {
+        let tz: Tz = "Asia/Ulaanbaatar".parse().unwrap();
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        // 2026-10-19 23:00 UTC is Tuesday 07:00 in Ulaanbaatar
+        let rows = vec![
+            shift(2, "Zaya", Utc.with_ymd_and_hms(2026, 10, 19, 1, 0, 0).unwrap(), 8),
+            shift(1, "Bold", Utc.with_ymd_and_hms(2026, 10, 19, 23, 0, 0).unwrap(), 4),
+            shift(2, "Zaya", Utc.with_ymd_and_hms(2026, 10, 21, 1, 0, 0).unwrap(), 6),
+        ];
+
+        let roster = week_roster(rows, monday, tz);
+        assert_eq!(roster.iter().map(|e| e.employee_name.as_str()).collect::<Vec<_>>(), ["Bold", "Zaya"]);
+        assert_eq!(roster[0].days[1].len(), 1);
+        assert_eq!(roster[0].total_minutes, 240);
+        assert_eq!(roster[1].days.iter().map(Vec::len).collect::<Vec<_>>(), [1, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(roster[1].total_minutes, 14 * 60);
+    }
+}
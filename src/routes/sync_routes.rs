@@ -0,0 +1,404 @@
+// src/routes/sync_routes.rs
+//
+// Delta sync for the offline-capable desktop client, any staff:
+// - GET /sync/cursor                          newest change; take it before a full reload
+// - GET /sync/changes?since=<cursor>[&limit=]  changes after it, oldest first
+// - POST /sync/push                           edits made offline, with their base versions
+// Backed by sync_outbox (crate::sync_outbox): every patient / appointment write,
+// jobs and direct SQL included. `payload` is the entity's current state in the
+// shape GET /patients/{id} / GET /appointments/{id} return, null once it is gone, so
+// a client just upserts it (several changes to one entity share the same payload).
+// Doctors only get their own appointments (deletes carry no payload and are kept).
+// SYNC_CURSOR_EXPIRED (409) = the cursor is older than the 30-day log: reload.
+//
+// Push: an entity's version is the seq of its newest change (sync_outbox::version).
+// Items are PATCH bodies, applied in client_ts order in one transaction, each under
+// its own savepoint: one whose entity changed after its base_version is a
+// "conflict" (nothing written, the server state comes back to merge and resend),
+// one the PATCH would refuse is "rejected" with that error, the rest are
+// "accepted". One item per entity and batch (merge queued edits client-side).
+// Appointment items need the PATCH /appointments rights (not doctors).
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use sqlx::{Acquire, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    error::{ApiError, ErrorObject},
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiOk, AppState, Role},
+    services::{
+        appointments::{self, AppointmentPatch},
+        patients::{self, PatientPatch},
+    },
+    sync_outbox::{self, ChangeCursor, ChangeRow},
+};
+
+const DEFAULT_LIMIT: i64 = 200;
+const MAX_LIMIT: i64 = 1000;
+const MAX_PUSH_ITEMS: usize = 100;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sync/cursor", get(get_cursor))
+        .route("/sync/changes", get(list_changes))
+        .route("/sync/push", post(push))
+}
+
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncCursorData {
+    /// None = nothing logged yet; call /sync/changes without `since`
+    pub cursor: Option<String>,
+    /// base_version for entities loaded by the full reload that follows (0 = none logged)
+    pub seq: i64,
+}
+
+pub async fn get_cursor(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<ApiOk<SyncCursorData>>, ApiError> {
+    ensure_staff(&auth)?;
+    let cursor = sync_outbox::head(&state.db).await?;
+    Ok(Json(ApiOk {
+        data: SyncCursorData { seq: cursor.map_or(0, |c| c.seq), cursor: cursor.map(|c| c.encode()) },
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    /// `next_cursor` of the previous call or GET /sync/cursor; omitted = oldest kept change
+    pub since: Option<String>,
+    pub limit: Option<i64>, // default 200, max 1000
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncChangeDto {
+    pub seq: i64,
+    pub entity: String, // "patient" | "appointment"
+    pub entity_id: Uuid,
+    pub op: String, // "insert" | "update" | "delete"
+    pub changed_at: DateTime<Utc>,
+    pub payload: Option<JsonValue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncChangesResponse {
+    pub data: Vec<SyncChangeDto>,
+    /// pass back as `since`; the request's own `since` when nothing changed
+    pub next_cursor: Option<String>,
+    /// more changes are waiting: ask again right away
+    pub has_more: bool,
+}
+
+pub async fn list_changes(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ChangesQuery>,
+) -> Result<Json<SyncChangesResponse>, ApiError> {
+    ensure_staff(&auth)?;
+    let limit = q.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let since = q
+        .since
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .map(ChangeCursor::decode)
+        .transpose()?;
+    if let Some(cursor) = since {
+        sync_outbox::ensure_cursor_kept(&state.db, cursor).await?;
+    }
+
+    let mut rows = sync_outbox::after(&state.db, since, limit + 1).await?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let next_cursor = rows.last().map(ChangeRow::cursor).or(since).map(|c| c.encode());
+
+    let current = CurrentState::load(&state, &rows).await?;
+    let own_doctor = match auth.role {
+        Role::Doctor => {
+            Some(appointments::doctor_employee_id_for_user(&*state.repos.appointments, auth.user_id).await?)
+        }
+        _ => None,
+    };
+
+    let data = rows
+        .into_iter()
+        .filter(|r| match (own_doctor, current.doctor_of(r)) {
+            (Some(me), Some(doctor_id)) => doctor_id == me,
+            _ => true,
+        })
+        .map(|r| SyncChangeDto {
+            payload: current.payload(&r),
+            seq: r.seq,
+            entity: r.entity,
+            entity_id: r.entity_id,
+            op: r.op,
+            changed_at: r.changed_at,
+        })
+        .collect();
+
+    Ok(Json(SyncChangesResponse { data, next_cursor, has_more }))
+}
+
+/// Current state of the entities on a page; missing = deleted since.
+struct CurrentState {
+    patients: HashMap<Uuid, JsonValue>,
+    /// block + its doctor
+    appointments: HashMap<Uuid, (JsonValue, Uuid)>,
+}
+
+impl CurrentState {
+    async fn load(state: &AppState, rows: &[ChangeRow]) -> Result<Self, ApiError> {
+        let ids_of = |entity: &str| {
+            let mut ids: Vec<Uuid> = rows.iter().filter(|r| r.entity == entity).map(|r| r.entity_id).collect();
+            ids.sort();
+            ids.dedup();
+            ids
+        };
+
+        let mut current = CurrentState { patients: HashMap::new(), appointments: HashMap::new() };
+        let patient_ids = ids_of("patient");
+        if !patient_ids.is_empty() {
+            let repo = &*state.repos.patients;
+            for p in patients::page_after(repo, None, Some(&patient_ids), patient_ids.len() as i64).await? {
+                current.patients.insert(p.patient_id, serde_json::to_value(&p).unwrap_or_default());
+            }
+        }
+        let appointment_ids = ids_of("appointment");
+        if !appointment_ids.is_empty() {
+            for b in appointments::blocks_by_ids(&*state.repos.appointments, &appointment_ids).await? {
+                let json = serde_json::to_value(&b).unwrap_or_default();
+                current.appointments.insert(b.appointment_id, (json, b.doctor.id));
+            }
+        }
+        Ok(current)
+    }
+
+    fn payload(&self, r: &ChangeRow) -> Option<JsonValue> {
+        match r.entity.as_str() {
+            "patient" => self.patients.get(&r.entity_id).cloned(),
+            "appointment" => self.appointments.get(&r.entity_id).map(|(json, _)| json.clone()),
+            _ => None,
+        }
+    }
+
+    fn doctor_of(&self, r: &ChangeRow) -> Option<Uuid> {
+        match r.entity.as_str() {
+            "appointment" => self.appointments.get(&r.entity_id).map(|(_, doctor_id)| *doctor_id),
+            _ => None,
+        }
+    }
+}
+
+/* ============================================================
+   POST /sync/push
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct SyncPushRequest {
+    pub items: Vec<SyncPushItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncPushItem {
+    pub entity: String, // "patient" | "appointment"
+    pub entity_id: Uuid,
+    /// seq of the last change the client has of this entity (/sync/changes), or
+    /// /sync/cursor's `seq` when it came with the full reload
+    pub base_version: i64,
+    /// when the edit was made offline; the batch is applied in this order
+    pub client_ts: DateTime<Utc>,
+    /// the PATCH /patients/{id} or PATCH /appointments/{id} body
+    pub patch: JsonValue,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncPushResult {
+    /// position in the request's `items`
+    pub index: usize,
+    pub entity: String,
+    pub entity_id: Uuid,
+    pub status: &'static str, // "accepted" | "conflict" | "rejected"
+    /// version after the write (accepted) or the server's (conflict): the next base_version
+    pub version: Option<i64>,
+    /// current state, as in /sync/changes (accepted / conflict)
+    pub payload: Option<JsonValue>,
+    /// why a rejected item was refused, as in an error response
+    pub error: Option<ErrorObject>,
+}
+
+enum Applied {
+    Accepted(i64),
+    Conflict(i64),
+}
+
+pub async fn push(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<SyncPushRequest>,
+) -> Result<Json<ApiOk<Vec<SyncPushResult>>>, ApiError> {
+    ensure_staff(&auth)?;
+    let items = req.items;
+    if items.is_empty() || items.len() > MAX_PUSH_ITEMS {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("items must have 1..={MAX_PUSH_ITEMS} entries"),
+        ));
+    }
+    let mut keys: Vec<(&str, Uuid)> = items.iter().map(|it| (it.entity.as_str(), it.entity_id)).collect();
+    keys.sort();
+    if keys.windows(2).any(|w| w[0] == w[1]) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "one item per entity; merge queued edits of an entity into one patch".into(),
+        ));
+    }
+
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by_key(|&i| items[i].client_ts);
+
+    let mut tx = state.db.begin().await?;
+    let mut outcomes: Vec<(usize, Result<Applied, ErrorObject>)> = Vec::with_capacity(items.len());
+    for index in order {
+        let mut savepoint = tx.begin().await?;
+        let outcome = match apply_item(&mut savepoint, &state, &auth, &items[index]).await {
+            Ok(Applied::Accepted(version)) => {
+                savepoint.commit().await?;
+                Ok(Applied::Accepted(version))
+            }
+            Ok(conflict) => {
+                savepoint.rollback().await?;
+                Ok(conflict)
+            }
+            Err(e) => {
+                // Internal has no error object: fail the whole batch
+                let Some(error) = e.to_error_object() else { return Err(e) };
+                savepoint.rollback().await?;
+                Err(error)
+            }
+        };
+        outcomes.push((index, outcome));
+    }
+    tx.commit().await?;
+
+    outcomes.sort_by_key(|(index, _)| *index);
+    let mut data = Vec::with_capacity(outcomes.len());
+    for (index, outcome) in outcomes {
+        let item = &items[index];
+        let (status, version, error) = match outcome {
+            Ok(Applied::Accepted(version)) => ("accepted", Some(version), None),
+            Ok(Applied::Conflict(version)) => ("conflict", Some(version), None),
+            Err(error) => ("rejected", None, Some(error)),
+        };
+        let payload = match version {
+            Some(_) => current_payload(&state, &item.entity, item.entity_id).await?,
+            None => None,
+        };
+        data.push(SyncPushResult {
+            index,
+            entity: item.entity.clone(),
+            entity_id: item.entity_id,
+            status,
+            version,
+            payload,
+            error,
+        });
+    }
+    Ok(Json(ApiOk { data }))
+}
+
+/// Locks the entity's row, compares versions and applies the patch on `conn`.
+async fn apply_item(
+    conn: &mut PgConnection,
+    state: &AppState,
+    auth: &AuthContext,
+    item: &SyncPushItem,
+) -> Result<Applied, ApiError> {
+    let id = item.entity_id;
+    let lock_sql = match item.entity.as_str() {
+        "patient" => "SELECT 1 FROM patient WHERE patient_id = $1 FOR UPDATE",
+        "appointment" => {
+            if !matches!(auth.role, Role::Admin | Role::Manager | Role::Receptionist) {
+                return Err(ApiError::Forbidden(
+                    "FORBIDDEN",
+                    "Only admin/manager/receptionist can manage appointments".into(),
+                ));
+            }
+            "SELECT 1 FROM appointment WHERE appointment_id = $1 FOR UPDATE"
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "entity must be patient or appointment".into(),
+            ));
+        }
+    };
+    let found: Option<i32> = sqlx::query_scalar(lock_sql).bind(id).fetch_optional(&mut *conn).await?;
+    if found.is_none() {
+        return Err(ApiError::NotFound("NOT_FOUND", format!("{} not found", item.entity)));
+    }
+
+    let current = sync_outbox::version(conn, &item.entity, id).await?;
+    if current > item.base_version {
+        return Ok(Applied::Conflict(current));
+    }
+
+    let invalid_patch = |e: serde_json::Error| ApiError::BadRequest("VALIDATION_ERROR", format!("invalid patch: {e}"));
+    if item.entity == "patient" {
+        let patch: PatientPatch = serde_json::from_value(item.patch.clone()).map_err(invalid_patch)?;
+        patients::update_in(conn, &*state.repos.patients, id, patch).await?;
+    } else {
+        let patch: AppointmentPatch = serde_json::from_value(item.patch.clone()).map_err(invalid_patch)?;
+        appointments::update_in(conn, &state.db, &*state.repos.appointments, auth, id, patch).await?;
+    }
+
+    let version = sync_outbox::version(conn, &item.entity, id).await?;
+    audit::record(
+        &mut *conn,
+        auth,
+        "sync.push",
+        &item.entity,
+        Some(id),
+        json!({ "client_ts": item.client_ts, "base_version": item.base_version, "version": version }),
+    )
+    .await?;
+    Ok(Applied::Accepted(version))
+}
+
+/// The entity as GET /patients/{id} / GET /appointments/{id} return it (primary,
+/// so it includes this push); None once it is gone.
+async fn current_payload(state: &AppState, entity: &str, id: Uuid) -> Result<Option<JsonValue>, ApiError> {
+    let payload = match entity {
+        "patient" => {
+            let repo = &*state.repos.patients;
+            match repo.get(id).await? {
+                Some(row) => Some(serde_json::to_value(patients::with_derived(repo, row).await?).unwrap_or_default()),
+                None => None,
+            }
+        }
+        _ => appointments::blocks_by_ids(&*state.repos.appointments, &[id])
+            .await?
+            .into_iter()
+            .next()
+            .map(|b| serde_json::to_value(&b).unwrap_or_default()),
+    };
+    Ok(payload)
+}
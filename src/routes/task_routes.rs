@@ -2,18 +2,24 @@
 
 use axum::{
     extract::{Path, Query, State},
-    routing::{get, patch, post},
-    Json, Router,
+    routing::{delete, get, patch, post},
+    Router,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
+use sqlx::{postgres::PgRow, QueryBuilder, Row};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::{
+    audit,
     error::ApiError,
+    extract::Json,
+    jobs::task_recurrence::Recurrence,
     middleware::auth_context::AuthContext,
-    models::AppState,
+    models::{ApiOk, AppState, Role, SmsDirection, TaskPriority, TaskStatus},
+    notifications::notify_task_participants,
+    pii::PiiString,
 };
 
 /*
@@ -25,10 +31,10 @@ Roles (dcms_user.roles):
 4 receptionist
 */
 
-fn is_admin(auth: &AuthContext) -> bool { auth.role == 1 }
-fn is_manager(auth: &AuthContext) -> bool { auth.role == 2 }
-fn is_doctor(auth: &AuthContext) -> bool { auth.role == 3 }
-fn is_receptionist(auth: &AuthContext) -> bool { auth.role == 4 }
+fn is_admin(auth: &AuthContext) -> bool { auth.role == Role::Admin }
+fn is_manager(auth: &AuthContext) -> bool { auth.role == Role::Manager }
+fn is_doctor(auth: &AuthContext) -> bool { auth.role == Role::Doctor }
+fn is_receptionist(auth: &AuthContext) -> bool { auth.role == Role::Receptionist }
 
 fn can_manage_tasks(auth: &AuthContext) -> bool {
     is_admin(auth) || is_manager(auth) || is_receptionist(auth)
@@ -49,6 +55,26 @@ fn ensure_manage(auth: &AuthContext) -> Result<(), ApiError> {
     }
 }
 
+fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
+    if is_admin(auth) || is_manager(auth) {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin/manager can manage task templates".into(),
+        ))
+    }
+}
+
+/// Validates and normalizes a recurrence spec; empty string = no recurrence.
+fn normalize_recurrence(spec: Option<&str>) -> Result<Option<String>, ApiError> {
+    let Some(spec) = spec.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    Recurrence::parse(spec).map_err(|e| ApiError::BadRequest("VALIDATION_ERROR", e))?;
+    Ok(Some(spec.to_ascii_uppercase()))
+}
+
 fn ensure_create(auth: &AuthContext) -> Result<(), ApiError> {
     if can_create_tasks(auth) {
         Ok(())
@@ -70,8 +96,7 @@ async fn resolve_employee_id_by_user_id(state: &AppState, user_id: Uuid) -> Resu
     )
     .bind(user_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
     let Some(row) = row else {
         return Err(ApiError::BadRequest(
@@ -97,23 +122,26 @@ pub fn router() -> Router<AppState> {
         .route("/tasks/inbox", get(list_tasks_inbox))
         .route("/tasks/my", get(list_tasks_my))
         .route("/tasks/created", get(list_tasks_created))
+        .route("/tasks/board", get(get_task_board))
+        .route("/tasks/bulk_assign", post(bulk_assign_tasks))
         .route("/tasks/{task_id}", get(get_task))
         .route("/tasks/{task_id}", patch(patch_task))
         .route("/tasks/{task_id}/assign", post(assign_task))
         .route("/tasks/{task_id}/start", post(start_task))
         .route("/tasks/{task_id}/complete", post(complete_task))
         .route("/tasks/{task_id}/cancel", post(cancel_task))
+        .route("/tasks/{task_id}/comments", post(add_task_comment))
+        .route("/tasks/{task_id}/watchers", post(add_task_watcher))
+        .route("/tasks/{task_id}/watchers/{employee_id}", delete(remove_task_watcher))
+        .route("/task_templates", get(list_task_templates).post(create_task_template))
+        .route("/task_templates/{task_template_id}", patch(patch_task_template))
+        .route("/task_templates/{task_template_id}/instantiate", post(instantiate_task_template))
 }
 
 /* ============================================================
    DTOs
    ============================================================ */
 
-#[derive(Debug, Serialize)]
-pub struct ApiOk<T> {
-    pub data: T,
-}
-
 #[derive(Debug, Serialize)]
 pub struct PersonBrief {
     pub id: Uuid,
@@ -125,14 +153,15 @@ pub struct PersonBrief {
 pub struct TaskDto {
     pub task_id: Uuid,
     pub task_type: String,
-    pub status: i16,
-    pub priority: i16,
+    pub status: TaskStatus,
+    pub priority: TaskPriority,
     pub due_at: Option<DateTime<Utc>>,
     pub title: String,
     pub details: Option<String>,
 
     pub patient: Option<PersonBrief>,
     pub appointment_id: Option<Uuid>,
+    pub sms: Option<SmsBrief>,
 
     pub created_by: PersonBrief,
     pub assigned_to: Option<PersonBrief>,
@@ -142,66 +171,161 @@ pub struct TaskDto {
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub canceled_at: Option<DateTime<Utc>>,
+
+    pub task_template_id: Option<Uuid>,
+    pub recurrence: Option<String>,
+    pub next_occurrence_task_id: Option<Uuid>,
+
+    pub watchers: Vec<PersonBrief>,
+    pub comments: Vec<TaskCommentDto>,
+    pub activity: Vec<TaskActivityDto>,
+}
+
+/// The SMS a task is about (task.sms_id), for deep-linking into the conversation.
+#[derive(Debug, Serialize)]
+pub struct SmsBrief {
+    pub sms_id: Uuid,
+    pub phone_number_id: Uuid,
+    pub phone_number: String,
+    pub direction: SmsDirection,
+    pub sent_at: DateTime<Utc>,
+    /// start of the text; None once the SMS is deleted
+    pub excerpt: Option<String>,
+    pub deleted: bool,
+}
+
+const SMS_EXCERPT_CHARS: usize = 80;
+
+fn excerpt(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(max_chars) {
+        Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskCommentDto {
+    pub task_comment_id: Uuid,
+    pub parent_comment_id: Option<Uuid>,
+    pub author: PersonBrief,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub replies: Vec<TaskCommentDto>,
+}
+
+/// One entry of the computed timeline: "created" | "assigned" | "unassigned" | "started" | "completed" | "canceled".
+#[derive(Debug, Serialize)]
+pub struct TaskActivityDto {
+    pub kind: &'static str,
+    pub at: DateTime<Utc>,
+    pub actor: Option<PersonBrief>,
+    pub assignee: Option<PersonBrief>,
 }
 
 /* ============================================================
    Helpers: authorization + fetch
    ============================================================ */
 
+/// Task columns + all joined people; append a WHERE/ORDER BY as needed.
+const TASK_SELECT: &str = r#"
+    SELECT
+      t.task_id,
+      t.task_type,
+      t.status,
+      t.priority,
+      t.due_at,
+      t.title,
+      t.details,
+      t.patient_id,
+      t.appointment_id,
+      t.sms_id,
+      t.created_at,
+      t.updated_at,
+      t.started_at,
+      t.completed_at,
+      t.canceled_at,
+      t.assigned_at,
+      t.task_template_id,
+      t.recurrence,
+      t.next_occurrence_task_id,
+
+      cb.employee_id AS cb_id,
+      cb.employee_display_number AS cb_no,
+      cb.first_name AS cb_first,
+      cb.last_name  AS cb_last,
+
+      at.employee_id AS at_id,
+      at.employee_display_number AS at_no,
+      at.first_name AS at_first,
+      at.last_name  AS at_last,
+
+      p.patient_id AS p_id,
+      p.first_name AS p_first,
+      p.last_name  AS p_last,
+      p.register_number AS p_reg,
+
+      s.phone_number_id AS s_phone_id,
+      s.direction AS s_direction,
+      s.sent_at AS s_sent_at,
+      s.sms_text AS s_text,
+      s.deleted_at AS s_deleted_at,
+      sp.phone_number AS s_phone,
+
+      ab.employee_id AS ab_id,
+      ab.employee_display_number AS ab_no,
+      ab.first_name AS ab_first,
+      ab.last_name  AS ab_last,
+
+      sb.employee_id AS sb_id,
+      sb.employee_display_number AS sb_no,
+      sb.first_name AS sb_first,
+      sb.last_name  AS sb_last,
+
+      xb.employee_id AS xb_id,
+      xb.employee_display_number AS xb_no,
+      xb.first_name AS xb_first,
+      xb.last_name  AS xb_last,
+
+      kb.employee_id AS kb_id,
+      kb.employee_display_number AS kb_no,
+      kb.first_name AS kb_first,
+      kb.last_name  AS kb_last
+
+    FROM task t
+    JOIN employee cb ON cb.employee_id = t.created_by_employee_id
+    LEFT JOIN employee at ON at.employee_id = t.assigned_to_employee_id
+    LEFT JOIN patient p ON p.patient_id = t.patient_id
+    LEFT JOIN sms s ON s.sms_id = t.sms_id
+    LEFT JOIN phone_number sp ON sp.phone_number_id = s.phone_number_id
+    LEFT JOIN employee ab ON ab.employee_id = t.assigned_by_employee_id
+    LEFT JOIN employee sb ON sb.employee_id = t.started_by_employee_id
+    LEFT JOIN employee xb ON xb.employee_id = t.completed_by_employee_id
+    LEFT JOIN employee kb ON kb.employee_id = t.canceled_by_employee_id
+"#;
+
 async fn fetch_task_with_joins(state: &AppState, task_id: Uuid) -> Result<TaskDto, ApiError> {
-    let row = sqlx::query(
-        r#"
-        SELECT
-          t.task_id,
-          t.task_type,
-          t.status,
-          t.priority,
-          t.due_at,
-          t.title,
-          t.details,
-          t.patient_id,
-          t.appointment_id,
-          t.created_at,
-          t.updated_at,
-          t.started_at,
-          t.completed_at,
-          t.canceled_at,
-
-          cb.employee_id AS cb_id,
-          cb.employee_display_number AS cb_no,
-          cb.first_name AS cb_first,
-          cb.last_name  AS cb_last,
-
-          at.employee_id AS at_id,
-          at.employee_display_number AS at_no,
-          at.first_name AS at_first,
-          at.last_name  AS at_last,
-
-          p.patient_id AS p_id,
-          p.first_name AS p_first,
-          p.last_name  AS p_last,
-          p.register_number AS p_reg
-
-        FROM task t
-        JOIN employee cb ON cb.employee_id = t.created_by_employee_id
-        LEFT JOIN employee at ON at.employee_id = t.assigned_to_employee_id
-        LEFT JOIN patient p ON p.patient_id = t.patient_id
-        WHERE t.task_id = $1
-        "#,
-    )
-    .bind(task_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    let sql = format!("{TASK_SELECT} WHERE t.task_id = $1");
+    let row = sqlx::query(&sql)
+        .bind(task_id)
+        .fetch_optional(&state.db)
+        .await?;
 
     let Some(r) = row else {
-        return Err(ApiError::BadRequest("NOT_FOUND", "task not found".into()));
+        return Err(ApiError::NotFound("NOT_FOUND", "task not found".into()));
     };
 
+    let mut items = vec![task_from_row(&r)?];
+    attach_watchers_and_comments(state, &mut items).await?;
+    Ok(items.remove(0))
+}
+
+/// Folds one TASK_SELECT row into a DTO (watchers/comments are attached separately).
+fn task_from_row(r: &PgRow) -> Result<TaskDto, ApiError> {
     let task_id: Uuid = r.try_get("task_id").map_err(internal_row)?;
     let task_type: String = r.try_get("task_type").map_err(internal_row)?;
-    let status: i16 = r.try_get("status").map_err(internal_row)?;
-    let priority: i16 = r.try_get("priority").map_err(internal_row)?;
+    let status: TaskStatus = r.try_get("status").map_err(internal_row)?;
+    let priority: TaskPriority = r.try_get("priority").map_err(internal_row)?;
     let due_at: Option<DateTime<Utc>> = r.try_get("due_at").ok();
     let title: String = r.try_get("title").map_err(internal_row)?;
     let details: Option<String> = r.try_get("details").ok();
@@ -212,6 +336,11 @@ async fn fetch_task_with_joins(state: &AppState, task_id: Uuid) -> Result<TaskDt
     let started_at: Option<DateTime<Utc>> = r.try_get("started_at").ok();
     let completed_at: Option<DateTime<Utc>> = r.try_get("completed_at").ok();
     let canceled_at: Option<DateTime<Utc>> = r.try_get("canceled_at").ok();
+    let assigned_at: Option<DateTime<Utc>> = r.try_get("assigned_at").ok();
+    let task_template_id: Option<Uuid> = r.try_get("task_template_id").map_err(internal_row)?;
+    let recurrence: Option<String> = r.try_get("recurrence").map_err(internal_row)?;
+    let next_occurrence_task_id: Option<Uuid> =
+        r.try_get("next_occurrence_task_id").map_err(internal_row)?;
 
     let cb_id: Uuid = r.try_get("cb_id").map_err(internal_row)?;
     let cb_no: i64 = r.try_get("cb_no").map_err(internal_row)?;
@@ -224,19 +353,7 @@ async fn fetch_task_with_joins(state: &AppState, task_id: Uuid) -> Result<TaskDt
         number: Some(cb_no),
     };
 
-    let at_id: Option<Uuid> = r.try_get("at_id").ok();
-    let assigned_to = if let Some(aid) = at_id {
-        let at_no: i64 = r.try_get("at_no").map_err(internal_row)?;
-        let at_first: String = r.try_get("at_first").map_err(internal_row)?;
-        let at_last: String = r.try_get("at_last").map_err(internal_row)?;
-        Some(PersonBrief {
-            id: aid,
-            display: format!("{at_first} {at_last}"),
-            number: Some(at_no),
-        })
-    } else {
-        None
-    };
+    let assigned_to = employee_brief(r, "at")?;
 
     let p_id: Option<Uuid> = r.try_get("p_id").ok();
     let patient = if let Some(pid) = p_id {
@@ -255,6 +372,60 @@ async fn fetch_task_with_joins(state: &AppState, task_id: Uuid) -> Result<TaskDt
         None
     };
 
+    let sms_id: Option<Uuid> = r.try_get("sms_id").map_err(internal_row)?;
+    let sms = match sms_id {
+        Some(sms_id) => {
+            let deleted_at: Option<DateTime<Utc>> = r.try_get("s_deleted_at").map_err(internal_row)?;
+            let text: PiiString = r.try_get("s_text").map_err(internal_row)?;
+            Some(SmsBrief {
+                sms_id,
+                phone_number_id: r.try_get("s_phone_id").map_err(internal_row)?,
+                phone_number: r.try_get("s_phone").map_err(internal_row)?,
+                direction: r.try_get("s_direction").map_err(internal_row)?,
+                sent_at: r.try_get("s_sent_at").map_err(internal_row)?,
+                excerpt: deleted_at.is_none().then(|| excerpt(&text.0, SMS_EXCERPT_CHARS)),
+                deleted: deleted_at.is_some(),
+            })
+        }
+        None => None,
+    };
+
+    // Activity timeline is computed from the actor/timestamp columns on task.
+    let mut activity = vec![TaskActivityDto {
+        kind: "created",
+        at: created_at,
+        actor: Some(PersonBrief {
+            id: created_by.id,
+            display: created_by.display.clone(),
+            number: created_by.number,
+        }),
+        assignee: None,
+    }];
+    if let Some(at) = assigned_at {
+        let assignee = employee_brief(r, "at")?;
+        activity.push(TaskActivityDto {
+            kind: if assignee.is_some() { "assigned" } else { "unassigned" },
+            at,
+            actor: employee_brief(r, "ab")?,
+            assignee,
+        });
+    }
+    for (kind, at, prefix) in [
+        ("started", started_at, "sb"),
+        ("completed", completed_at, "xb"),
+        ("canceled", canceled_at, "kb"),
+    ] {
+        if let Some(at) = at {
+            activity.push(TaskActivityDto {
+                kind,
+                at,
+                actor: employee_brief(r, prefix)?,
+                assignee: None,
+            });
+        }
+    }
+    activity.sort_by_key(|a| a.at);
+
     Ok(TaskDto {
         task_id,
         task_type,
@@ -265,6 +436,7 @@ async fn fetch_task_with_joins(state: &AppState, task_id: Uuid) -> Result<TaskDt
         details,
         patient,
         appointment_id,
+        sms,
         created_by,
         assigned_to,
         created_at,
@@ -272,9 +444,142 @@ async fn fetch_task_with_joins(state: &AppState, task_id: Uuid) -> Result<TaskDt
         started_at,
         completed_at,
         canceled_at,
+        task_template_id,
+        recurrence,
+        next_occurrence_task_id,
+        watchers: vec![],
+        comments: vec![],
+        activity,
     })
 }
 
+/// Loads watchers and comments for all given tasks in two queries (no per-task round trips).
+async fn attach_watchers_and_comments(state: &AppState, items: &mut [TaskDto]) -> Result<(), ApiError> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<Uuid> = items.iter().map(|t| t.task_id).collect();
+    let mut watchers = fetch_task_watchers(state, &ids).await?;
+    let mut comments = fetch_task_comments(state, &ids).await?;
+    for t in items.iter_mut() {
+        t.watchers = watchers.remove(&t.task_id).unwrap_or_default();
+        t.comments = comments.remove(&t.task_id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// Reads the `{prefix}_id/_no/_first/_last` employee columns of a joined row.
+fn employee_brief(r: &PgRow, prefix: &str) -> Result<Option<PersonBrief>, ApiError> {
+    let id: Option<Uuid> = r.try_get(format!("{prefix}_id").as_str()).map_err(internal_row)?;
+    let Some(id) = id else {
+        return Ok(None);
+    };
+    let no: i64 = r.try_get(format!("{prefix}_no").as_str()).map_err(internal_row)?;
+    let first: String = r.try_get(format!("{prefix}_first").as_str()).map_err(internal_row)?;
+    let last: String = r.try_get(format!("{prefix}_last").as_str()).map_err(internal_row)?;
+    Ok(Some(PersonBrief {
+        id,
+        display: format!("{first} {last}"),
+        number: Some(no),
+    }))
+}
+
+async fn fetch_task_watchers(
+    state: &AppState,
+    task_ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<PersonBrief>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          w.task_id,
+          e.employee_id AS e_id,
+          e.employee_display_number AS e_no,
+          e.first_name AS e_first,
+          e.last_name  AS e_last
+        FROM task_watcher w
+        JOIN employee e ON e.employee_id = w.employee_id
+        WHERE w.task_id = ANY($1)
+        ORDER BY w.created_at ASC
+        "#,
+    )
+    .bind(task_ids)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut out: HashMap<Uuid, Vec<PersonBrief>> = HashMap::new();
+    for r in rows {
+        let task_id: Uuid = r.try_get("task_id").map_err(internal_row)?;
+        if let Some(p) = employee_brief(&r, "e")? {
+            out.entry(task_id).or_default().push(p);
+        }
+    }
+    Ok(out)
+}
+
+async fn fetch_task_comments(
+    state: &AppState,
+    task_ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<TaskCommentDto>>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          c.task_id,
+          c.task_comment_id,
+          c.parent_comment_id,
+          c.body,
+          c.created_at,
+
+          e.employee_id AS e_id,
+          e.employee_display_number AS e_no,
+          e.first_name AS e_first,
+          e.last_name  AS e_last
+        FROM task_comment c
+        JOIN employee e ON e.employee_id = c.author_employee_id
+        WHERE c.task_id = ANY($1)
+        ORDER BY c.created_at ASC, c.task_comment_id ASC
+        "#,
+    )
+    .bind(task_ids)
+    .fetch_all(&state.db)
+    .await?;
+
+    // Group by (task, parent), then build each task's tree from the top-level comments down.
+    type ByParent = HashMap<(Uuid, Option<Uuid>), Vec<TaskCommentDto>>;
+    let mut by_parent: ByParent = HashMap::new();
+    for r in rows {
+        let Some(author) = employee_brief(&r, "e")? else {
+            continue;
+        };
+        let task_id: Uuid = r.try_get("task_id").map_err(internal_row)?;
+        let parent_comment_id: Option<Uuid> = r.try_get("parent_comment_id").map_err(internal_row)?;
+        by_parent.entry((task_id, parent_comment_id)).or_default().push(TaskCommentDto {
+            task_comment_id: r.try_get("task_comment_id").map_err(internal_row)?,
+            parent_comment_id,
+            author,
+            body: r.try_get("body").map_err(internal_row)?,
+            created_at: r.try_get("created_at").map_err(internal_row)?,
+            replies: vec![],
+        });
+    }
+
+    fn attach(task_id: Uuid, parent: Option<Uuid>, by_parent: &mut ByParent) -> Vec<TaskCommentDto> {
+        let mut items = by_parent.remove(&(task_id, parent)).unwrap_or_default();
+        for c in items.iter_mut() {
+            c.replies = attach(task_id, Some(c.task_comment_id), by_parent);
+        }
+        items
+    }
+
+    let mut out = HashMap::new();
+    for &task_id in task_ids {
+        let tree = attach(task_id, None, &mut by_parent);
+        if !tree.is_empty() {
+            out.insert(task_id, tree);
+        }
+    }
+    Ok(out)
+}
+
 async fn ensure_can_view_task(
     state: &AppState,
     auth: &AuthContext,
@@ -286,12 +591,13 @@ async fn ensure_can_view_task(
         return Ok(dto);
     }
 
-    // doctor: can view if created_by == me OR assigned_to == me
+    // doctor: can view if created_by == me OR assigned_to == me OR watching
     if is_doctor(auth) {
         let my_emp = resolve_employee_id_by_user_id(state, auth.user_id).await?;
         let created_ok = dto.created_by.id == my_emp;
         let assigned_ok = dto.assigned_to.as_ref().map(|x| x.id) == Some(my_emp);
-        if created_ok || assigned_ok {
+        let watching_ok = dto.watchers.iter().any(|w| w.id == my_emp);
+        if created_ok || assigned_ok || watching_ok {
             return Ok(dto);
         }
         return Err(ApiError::Forbidden("FORBIDDEN", "cannot view this task".into()));
@@ -300,6 +606,32 @@ async fn ensure_can_view_task(
     Err(ApiError::Forbidden("FORBIDDEN", "cannot view this task".into()))
 }
 
+/// The patient of an SMS a task gets linked to; it must be the task's patient
+/// (`patient_id`) when the task has one.
+async fn sms_link_patient(state: &AppState, sms_id: Uuid, patient_id: Option<Uuid>) -> Result<Uuid, ApiError> {
+    let sms_patient: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT ph.patient_id
+        FROM sms s
+        JOIN phone_number ph ON ph.phone_number_id = s.phone_number_id
+        WHERE s.sms_id = $1 AND s.deleted_at IS NULL
+        "#,
+    )
+    .bind(sms_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let Some(sms_patient) = sms_patient else {
+        return Err(ApiError::NotFound("NOT_FOUND", "sms not found".into()));
+    };
+    if patient_id.is_some_and(|p| p != sms_patient) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "sms_id belongs to another patient".into(),
+        ));
+    }
+    Ok(sms_patient)
+}
+
 /* ============================================================
    POST /tasks
    ============================================================ */
@@ -310,12 +642,16 @@ pub struct CreateTaskRequest {
     pub title: String,
     pub details: Option<String>,
 
-    pub priority: Option<i16>, // 0 normal, 1 high, 2 urgent
+    pub priority: Option<TaskPriority>,
     pub due_at: Option<DateTime<Utc>>,
 
     pub assigned_to_employee_id: Option<Uuid>,
     pub patient_id: Option<Uuid>,
     pub appointment_id: Option<Uuid>,
+    /// the SMS the task is about; fills in patient_id
+    pub sms_id: Option<Uuid>,
+
+    pub recurrence: Option<String>, // e.g. "FREQ=WEEKLY;BYDAY=MO"
 }
 
 pub async fn create_task(
@@ -332,11 +668,21 @@ pub async fn create_task(
         return Err(ApiError::BadRequest("VALIDATION_ERROR", "task_type is required".into()));
     }
 
-    let priority = req.priority.unwrap_or(0);
-    if !(0..=2).contains(&priority) {
-        return Err(ApiError::BadRequest("VALIDATION_ERROR", "priority must be 0..2".into()));
+    let priority = req.priority.unwrap_or(TaskPriority::Normal);
+
+    let recurrence = normalize_recurrence(req.recurrence.as_deref())?;
+    if recurrence.is_some() && req.due_at.is_none() {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "due_at is required for recurring tasks".into(),
+        ));
     }
 
+    let patient_id = match req.sms_id {
+        Some(sms_id) => Some(sms_link_patient(&state, sms_id, req.patient_id).await?),
+        None => req.patient_id,
+    };
+
     let created_by_employee_id = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
 
     let row = sqlx::query(
@@ -346,30 +692,41 @@ pub async fn create_task(
           assigned_to_employee_id,
           patient_id,
           appointment_id,
+          sms_id,
           task_type,
           status,
           priority,
           due_at,
           title,
           details,
-          updated_by_employee_id
+          updated_by_employee_id,
+          assigned_at,
+          assigned_by_employee_id,
+          recurrence
+        )
+        VALUES (
+          $1,$2,$3,$4,$11,$5,0,$6,$7,$8,$9,$1,
+          CASE WHEN $2::uuid IS NULL THEN NULL ELSE now() END,
+          CASE WHEN $2::uuid IS NULL THEN NULL ELSE $1 END,
+          $10
         )
-        VALUES ($1,$2,$3,$4,$5,0,$6,$7,$8,$9,$1)
         RETURNING task_id
         "#,
     )
     .bind(created_by_employee_id)
     .bind(req.assigned_to_employee_id)
-    .bind(req.patient_id)
+    .bind(patient_id)
     .bind(req.appointment_id)
     .bind(req.task_type.trim())
     .bind(priority)
     .bind(req.due_at)
     .bind(req.title.trim())
     .bind(req.details)
+    .bind(recurrence)
+    .bind(req.sms_id)
     .fetch_one(&state.db)
     .await
-    .map_err(|e| ApiError::BadRequest("TASK_CREATE_FAILED", format!("{e}")))?;
+    .map_err(ApiError::write_failed("TASK_CREATE_FAILED"))?;
 
     let task_id: Uuid = row.try_get("task_id").map_err(internal_row)?;
     let dto = ensure_can_view_task(&state, &auth, task_id).await?;
@@ -395,72 +752,87 @@ pub async fn get_task(
 
 #[derive(Debug, Deserialize)]
 pub struct ListQuery {
-    pub status: Option<i16>,  // optional filter
-    pub limit: Option<i64>,   // default 50
-    pub offset: Option<i64>,  // default 0
+    pub status: Option<TaskStatus>, // optional filters
+    pub priority: Option<TaskPriority>,
+    pub due_from: Option<DateTime<Utc>>,
+    pub due_to: Option<DateTime<Utc>>,
+    pub patient_id: Option<Uuid>,
+    pub assigned_to: Option<Uuid>,
+    pub limit: Option<i64>,    // default 50
+    pub offset: Option<i64>,   // default 0
+}
+
+/// Fixed part of each list endpoint; user filters from ListQuery are ANDed on top.
+enum ListScope {
+    Inbox,
+    AssignedTo(Uuid),
+    CreatedBy(Uuid),
 }
 
 async fn list_tasks_common(
     state: &AppState,
-    where_sql: &str,
-    binds: Vec<Uuid>,
+    scope: ListScope,
     q: &ListQuery,
 ) -> Result<Vec<TaskDto>, ApiError> {
     let limit = q.limit.unwrap_or(50).clamp(1, 200);
     let offset = q.offset.unwrap_or(0).max(0);
 
-    // NOTE: This is a simple approach without dynamic SQL builder crate.
-    // We only support optional status filter in Phase 1.
-    let mut sql = format!(
-        r#"
-        SELECT
-          t.task_id
-        FROM task t
-        {where_sql}
-        "#
-    );
+    // One joined query for the whole page (watchers/comments are batched below).
+    let mut qb: QueryBuilder<sqlx::Postgres> = QueryBuilder::new(TASK_SELECT);
 
-    if q.status.is_some() {
-        sql.push_str(" AND t.status = $XSTATUS ");
+    match scope {
+        ListScope::Inbox => {
+            qb.push(" WHERE t.assigned_to_employee_id IS NULL AND t.status IN (0,1) ");
+        }
+        ListScope::AssignedTo(emp) => {
+            qb.push(" WHERE t.assigned_to_employee_id = ");
+            qb.push_bind(emp);
+            qb.push(" AND t.status IN (0,1) ");
+        }
+        ListScope::CreatedBy(emp) => {
+            qb.push(" WHERE t.created_by_employee_id = ");
+            qb.push_bind(emp);
+        }
     }
 
-    sql.push_str(" ORDER BY COALESCE(t.due_at, t.created_at) ASC, t.created_at ASC ");
-    sql.push_str(" LIMIT $XLIMIT OFFSET $XOFFSET ");
-
-    // Replace placeholders with positional args
-    // binds are $1..$n, then optional status, then limit, offset.
-    let mut idx = 1;
-    for _ in &binds {
-        idx += 1;
+    if let Some(st) = q.status {
+        qb.push(" AND t.status = ");
+        qb.push_bind(st);
     }
-    let status_idx = idx;
-    let limit_idx = if q.status.is_some() { status_idx + 1 } else { status_idx };
-    let offset_idx = limit_idx + 1;
-
-    let sql = sql
-        .replace("$XSTATUS", &status_idx.to_string())
-        .replace("$XLIMIT", &limit_idx.to_string())
-        .replace("$XOFFSET", &offset_idx.to_string());
-
-    let mut query = sqlx::query(&sql);
-    for b in binds {
-        query = query.bind(b);
+    if let Some(p) = q.priority {
+        qb.push(" AND t.priority = ");
+        qb.push_bind(p);
     }
-    if let Some(st) = q.status {
-        query = query.bind(st);
+    if let Some(from) = q.due_from {
+        qb.push(" AND t.due_at >= ");
+        qb.push_bind(from);
+    }
+    if let Some(to) = q.due_to {
+        qb.push(" AND t.due_at <= ");
+        qb.push_bind(to);
+    }
+    if let Some(pid) = q.patient_id {
+        qb.push(" AND t.patient_id = ");
+        qb.push_bind(pid);
     }
-    query = query.bind(limit).bind(offset);
+    if let Some(emp) = q.assigned_to {
+        qb.push(" AND t.assigned_to_employee_id = ");
+        qb.push_bind(emp);
+    }
+
+    qb.push(" ORDER BY COALESCE(t.due_at, t.created_at) ASC, t.created_at ASC ");
+    qb.push(" LIMIT ");
+    qb.push_bind(limit);
+    qb.push(" OFFSET ");
+    qb.push_bind(offset);
 
-    let rows = query
+    let rows = qb
+        .build()
         .fetch_all(&state.db)
-        .await
-        .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+        .await?;
 
-    let mut out = Vec::with_capacity(rows.len());
-    for r in rows {
-        let task_id: Uuid = r.try_get("task_id").map_err(internal_row)?;
-        out.push(fetch_task_with_joins(state, task_id).await?);
-    }
+    let mut out = rows.iter().map(task_from_row).collect::<Result<Vec<_>, _>>()?;
+    attach_watchers_and_comments(state, &mut out).await?;
     Ok(out)
 }
 
@@ -472,13 +844,7 @@ pub async fn list_tasks_inbox(
 ) -> Result<Json<ApiOk<Vec<TaskDto>>>, ApiError> {
     ensure_manage(&auth)?;
 
-    let items = list_tasks_common(
-        &state,
-        "WHERE t.assigned_to_employee_id IS NULL AND t.status IN (0,1)",
-        vec![],
-        &q,
-    )
-    .await?;
+    let items = list_tasks_common(&state, ListScope::Inbox, &q).await?;
 
     Ok(Json(ApiOk { data: items }))
 }
@@ -491,13 +857,7 @@ pub async fn list_tasks_my(
 ) -> Result<Json<ApiOk<Vec<TaskDto>>>, ApiError> {
     let my_emp = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
 
-    let items = list_tasks_common(
-        &state,
-        "WHERE t.assigned_to_employee_id = $1 AND t.status IN (0,1)",
-        vec![my_emp],
-        &q,
-    )
-    .await?;
+    let items = list_tasks_common(&state, ListScope::AssignedTo(my_emp), &q).await?;
 
     Ok(Json(ApiOk { data: items }))
 }
@@ -510,17 +870,209 @@ pub async fn list_tasks_created(
 ) -> Result<Json<ApiOk<Vec<TaskDto>>>, ApiError> {
     let my_emp = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
 
-    let items = list_tasks_common(
-        &state,
-        "WHERE t.created_by_employee_id = $1",
-        vec![my_emp],
-        &q,
-    )
-    .await?;
+    let items = list_tasks_common(&state, ListScope::CreatedBy(my_emp), &q).await?;
 
     Ok(Json(ApiOk { data: items }))
 }
 
+/* ============================================================
+   GET /tasks/board (kanban)
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct BoardQuery {
+    pub group_by: Option<String>, // "status" (default) | "assignee"
+    pub per_column: Option<i64>,  // default 20
+    pub priority: Option<TaskPriority>,
+    pub due_from: Option<DateTime<Utc>>,
+    pub due_to: Option<DateTime<Utc>>,
+    pub patient_id: Option<Uuid>,
+    pub assigned_to: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BoardColumn {
+    pub key: String, // status or assignee employee_id ("unassigned" for none)
+    pub label: String,
+    pub count: i64, // total in column, may exceed items.len()
+    pub items: Vec<TaskDto>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BoardDto {
+    pub group_by: &'static str,
+    pub columns: Vec<BoardColumn>,
+}
+
+/// WHERE clause shared by the count and item queries of the board.
+fn push_board_filters(
+    qb: &mut QueryBuilder<'_, sqlx::Postgres>,
+    q: &BoardQuery,
+    by_assignee: bool,
+    only_mine: Option<Uuid>,
+) {
+    qb.push(" WHERE 1=1 ");
+    if by_assignee {
+        // assignee board shows active work only
+        qb.push(" AND t.status IN (0,1) ");
+    }
+    if let Some(me) = only_mine {
+        qb.push(" AND (t.created_by_employee_id = ");
+        qb.push_bind(me);
+        qb.push(" OR t.assigned_to_employee_id = ");
+        qb.push_bind(me);
+        qb.push(" OR EXISTS (SELECT 1 FROM task_watcher w WHERE w.task_id = t.task_id AND w.employee_id = ");
+        qb.push_bind(me);
+        qb.push(")) ");
+    }
+    if let Some(p) = q.priority {
+        qb.push(" AND t.priority = ");
+        qb.push_bind(p);
+    }
+    if let Some(from) = q.due_from {
+        qb.push(" AND t.due_at >= ");
+        qb.push_bind(from);
+    }
+    if let Some(to) = q.due_to {
+        qb.push(" AND t.due_at <= ");
+        qb.push_bind(to);
+    }
+    if let Some(pid) = q.patient_id {
+        qb.push(" AND t.patient_id = ");
+        qb.push_bind(pid);
+    }
+    if let Some(emp) = q.assigned_to {
+        qb.push(" AND t.assigned_to_employee_id = ");
+        qb.push_bind(emp);
+    }
+}
+
+pub async fn get_task_board(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<BoardQuery>,
+) -> Result<Json<ApiOk<BoardDto>>, ApiError> {
+    ensure_create(&auth)?;
+
+    let by_assignee = match q.group_by.as_deref() {
+        None | Some("status") => false,
+        Some("assignee") => true,
+        Some(_) => {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "group_by must be status or assignee".into(),
+            ))
+        }
+    };
+    let per_column = q.per_column.unwrap_or(20).clamp(1, 100);
+
+    // doctors only see tasks they created, are assigned to, or watch
+    let only_mine = if can_manage_tasks(&auth) {
+        None
+    } else {
+        Some(resolve_employee_id_by_user_id(&state, auth.user_id).await?)
+    };
+
+    let column_expr = if by_assignee { "t.assigned_to_employee_id::text" } else { "t.status::text" };
+
+    // 1) totals per column
+    let mut qb: QueryBuilder<sqlx::Postgres> = QueryBuilder::new(format!(
+        "SELECT {column_expr} AS col, COUNT(*) AS n FROM task t"
+    ));
+    push_board_filters(&mut qb, &q, by_assignee, only_mine);
+    qb.push(" GROUP BY 1 ");
+    let count_rows = qb
+        .build()
+        .fetch_all(&state.db)
+        .await?;
+
+    let mut counts: HashMap<Option<String>, i64> = HashMap::new();
+    for r in count_rows {
+        let col: Option<String> = r.try_get("col").map_err(internal_row)?;
+        let n: i64 = r.try_get("n").map_err(internal_row)?;
+        counts.insert(col, n);
+    }
+
+    // 2) top N per column (active: soonest due first; closed: most recently closed first)
+    let mut qb: QueryBuilder<sqlx::Postgres> = QueryBuilder::new(format!(
+        r#"
+        WITH ranked AS (
+          SELECT
+            t.task_id,
+            row_number() OVER (
+              PARTITION BY {column_expr}
+              ORDER BY
+                CASE WHEN t.status IN (0,1) THEN COALESCE(t.due_at, t.created_at) END ASC,
+                COALESCE(t.completed_at, t.canceled_at, t.updated_at) DESC
+            ) AS rn
+          FROM task t
+        "#
+    ));
+    push_board_filters(&mut qb, &q, by_assignee, only_mine);
+    qb.push(" ) ");
+    qb.push(TASK_SELECT);
+    qb.push(" JOIN ranked r ON r.task_id = t.task_id WHERE r.rn <= ");
+    qb.push_bind(per_column);
+    qb.push(" ORDER BY r.rn ASC ");
+
+    let rows = qb
+        .build()
+        .fetch_all(&state.db)
+        .await?;
+
+    let mut items = rows.iter().map(task_from_row).collect::<Result<Vec<_>, _>>()?;
+    attach_watchers_and_comments(&state, &mut items).await?;
+
+    let columns = if by_assignee {
+        // one column per assignee that has work, unassigned first, then by name
+        let mut cols: Vec<BoardColumn> = Vec::new();
+        for t in items {
+            let (key, label) = match &t.assigned_to {
+                Some(p) => (p.id.to_string(), p.display.clone()),
+                None => ("unassigned".to_string(), "Unassigned".to_string()),
+            };
+            match cols.iter_mut().find(|c| c.key == key) {
+                Some(c) => c.items.push(t),
+                None => {
+                    let count_key = t.assigned_to.as_ref().map(|p| p.id.to_string());
+                    cols.push(BoardColumn {
+                        key,
+                        label,
+                        count: counts.get(&count_key).copied().unwrap_or(0),
+                        items: vec![t],
+                    });
+                }
+            }
+        }
+        cols.sort_by(|a, b| (a.key != "unassigned", &a.label).cmp(&(b.key != "unassigned", &b.label)));
+        cols
+    } else {
+        // fixed four columns, even when empty
+        let mut cols: Vec<BoardColumn> = TaskStatus::ALL
+            .iter()
+            .map(|&st| BoardColumn {
+                key: st.to_string(),
+                label: st.to_string(),
+                count: counts.get(&Some((st as i16).to_string())).copied().unwrap_or(0),
+                items: vec![],
+            })
+            .collect();
+        for t in items {
+            if let Some(c) = cols.get_mut(t.status as usize) {
+                c.items.push(t);
+            }
+        }
+        cols
+    };
+
+    Ok(Json(ApiOk {
+        data: BoardDto {
+            group_by: if by_assignee { "assignee" } else { "status" },
+            columns,
+        },
+    }))
+}
+
 /* ============================================================
    PATCH /tasks/{id}
    ============================================================ */
@@ -530,14 +1082,17 @@ pub struct PatchTaskRequest {
     pub task_type: Option<String>,
     pub title: Option<String>,
     pub details: Option<Option<String>>,
-    pub priority: Option<i16>,
+    pub priority: Option<TaskPriority>,
     pub due_at: Option<Option<DateTime<Utc>>>,
 
     pub assigned_to_employee_id: Option<Option<Uuid>>,
     pub patient_id: Option<Option<Uuid>>,
     pub appointment_id: Option<Option<Uuid>>,
+    pub sms_id: Option<Option<Uuid>>,
 
-    pub status: Option<i16>, // allow manage-role only
+    pub status: Option<TaskStatus>, // allow manage-role only
+
+    pub recurrence: Option<String>, // "" = stop recurring
 }
 
 pub async fn patch_task(
@@ -572,15 +1127,18 @@ pub async fn patch_task(
         }
     }
 
-    if let Some(p) = req.priority {
-        if !(0..=2).contains(&p) {
-            return Err(ApiError::BadRequest("VALIDATION_ERROR", "priority must be 0..2".into()));
-        }
-    }
     if let Some(st) = req.status {
-        if !(0..=3).contains(&st) {
-            return Err(ApiError::BadRequest("VALIDATION_ERROR", "status must be 0..3".into()));
-        }
+        current.status.ensure_transition(st)?;
+    }
+    // Some("") clears, None keeps
+    let recurrence = match req.recurrence.as_deref() {
+        None => None,
+        Some(spec) => Some(normalize_recurrence(Some(spec))?.unwrap_or_default()),
+    };
+    let mut patient_id = req.patient_id.unwrap_or(None);
+    if let Some(sms_id) = req.sms_id.unwrap_or(None) {
+        let task_patient = patient_id.or(current.patient.as_ref().map(|p| p.id));
+        patient_id = Some(sms_link_patient(&state, sms_id, task_patient).await?);
     }
 
     let row = sqlx::query(
@@ -594,10 +1152,20 @@ pub async fn patch_task(
           due_at    = COALESCE($6, due_at),
 
           assigned_to_employee_id = COALESCE($7, assigned_to_employee_id),
+          assigned_at = CASE
+            WHEN $7::uuid IS DISTINCT FROM assigned_to_employee_id AND $7::uuid IS NOT NULL THEN now()
+            ELSE assigned_at
+          END,
+          assigned_by_employee_id = CASE
+            WHEN $7::uuid IS DISTINCT FROM assigned_to_employee_id AND $7::uuid IS NOT NULL THEN $11
+            ELSE assigned_by_employee_id
+          END,
           patient_id              = COALESCE($8, patient_id),
           appointment_id          = COALESCE($9, appointment_id),
+          sms_id                  = COALESCE($13, sms_id),
 
           status = COALESCE($10, status),
+          recurrence = CASE WHEN $12::text IS NULL THEN recurrence ELSE NULLIF($12, '') END,
           updated_by_employee_id = $11
         WHERE task_id = $1
         RETURNING task_id
@@ -610,18 +1178,26 @@ pub async fn patch_task(
     .bind(req.priority)
     .bind(req.due_at.unwrap_or(None))
     .bind(req.assigned_to_employee_id.unwrap_or(None))
-    .bind(req.patient_id.unwrap_or(None))
+    .bind(patient_id)
     .bind(req.appointment_id.unwrap_or(None))
     .bind(req.status)
     .bind(my_emp)
+    .bind(recurrence)
+    .bind(req.sms_id.unwrap_or(None))
     .fetch_optional(&state.db)
     .await
-    .map_err(|e| ApiError::BadRequest("TASK_UPDATE_FAILED", format!("{e}")))?;
+    .map_err(ApiError::write_failed("TASK_UPDATE_FAILED"))?;
 
     let Some(_row) = row else {
-        return Err(ApiError::BadRequest("NOT_FOUND", "task not found".into()));
+        return Err(ApiError::NotFound("NOT_FOUND", "task not found".into()));
     };
 
+    if let Some(st) = req.status
+        && st != current.status
+    {
+        notify_status_change(&state, task_id, my_emp, "TASK_STATUS_CHANGED", "Task status changed").await;
+    }
+
     let dto = ensure_can_view_task(&state, &auth, task_id).await?;
     Ok(Json(ApiOk { data: dto }))
 }
@@ -648,6 +1224,8 @@ pub async fn assign_task(
         r#"
         UPDATE task
         SET assigned_to_employee_id = $2,
+            assigned_at = now(),
+            assigned_by_employee_id = $3,
             updated_by_employee_id = $3
         WHERE task_id = $1
         "#,
@@ -657,12 +1235,111 @@ pub async fn assign_task(
     .bind(my_emp)
     .execute(&state.db)
     .await
-    .map_err(|e| ApiError::BadRequest("TASK_ASSIGN_FAILED", format!("{e}")))?;
+    .map_err(ApiError::write_failed("TASK_ASSIGN_FAILED"))?;
 
     let dto = ensure_can_view_task(&state, &auth, task_id).await?;
     Ok(Json(ApiOk { data: dto }))
 }
 
+/* ============================================================
+   POST /tasks/bulk_assign
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct BulkAssignRequest {
+    pub from_employee_id: Uuid,
+    pub to_employee_id: Option<Uuid>, // null = back to the inbox
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkAssignResult {
+    pub from_employee_id: Uuid,
+    pub to_employee_id: Option<Uuid>,
+    pub moved: usize,
+    /// of `moved`: already started (they stay in progress)
+    pub in_progress: usize,
+    pub task_ids: Vec<Uuid>,
+}
+
+// Moves every open / in-progress task of one employee (e.g. going on leave) in one
+// transaction; done and canceled tasks keep their assignee.
+pub async fn bulk_assign_tasks(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<BulkAssignRequest>,
+) -> Result<Json<ApiOk<BulkAssignResult>>, ApiError> {
+    if !(is_admin(&auth) || is_manager(&auth)) {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin/manager can reassign tasks in bulk".into(),
+        ));
+    }
+    if req.to_employee_id == Some(req.from_employee_id) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "to_employee_id must differ from from_employee_id".into(),
+        ));
+    }
+
+    let my_emp = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
+    let mut tx = state.db.begin().await?;
+
+    if let Some(to) = req.to_employee_id {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM employee WHERE employee_id = $1)")
+            .bind(to)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !exists {
+            return Err(ApiError::NotFound("NOT_FOUND", "employee not found".into()));
+        }
+    }
+
+    let moved: Vec<(Uuid, TaskStatus)> = sqlx::query_as(
+        r#"
+        UPDATE task
+        SET assigned_to_employee_id = $2,
+            assigned_at = now(),
+            assigned_by_employee_id = $3,
+            updated_by_employee_id = $3
+        WHERE assigned_to_employee_id = $1
+          AND status IN (0,1)
+        RETURNING task_id, status
+        "#,
+    )
+    .bind(req.from_employee_id)
+    .bind(req.to_employee_id)
+    .bind(my_emp)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("TASK_ASSIGN_FAILED"))?;
+
+    let task_ids: Vec<Uuid> = moved.iter().map(|(id, _)| *id).collect();
+    let in_progress = moved.iter().filter(|(_, st)| *st == TaskStatus::InProgress).count();
+
+    if !task_ids.is_empty() {
+        audit::record(
+            &mut *tx,
+            &auth,
+            "task.bulk_assign",
+            "employee",
+            Some(req.from_employee_id),
+            serde_json::json!({ "to_employee_id": req.to_employee_id, "task_ids": task_ids }),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(Json(ApiOk {
+        data: BulkAssignResult {
+            from_employee_id: req.from_employee_id,
+            to_employee_id: req.to_employee_id,
+            moved: task_ids.len(),
+            in_progress,
+            task_ids,
+        },
+    }))
+}
+
 /* ============================================================
    Status transitions
    ============================================================ */
@@ -684,21 +1361,34 @@ pub async fn start_task(
         }
     }
 
-    sqlx::query(
+    // previous status: starting an in-progress task again doesn't notify
+    let previous: Option<TaskStatus> = sqlx::query_scalar(
         r#"
-        UPDATE task
-        SET status = 1,
-            started_at = COALESCE(started_at, now()),
+        WITH prev AS (
+          SELECT task_id, status FROM task WHERE task_id = $1 FOR UPDATE
+        )
+        UPDATE task t
+        SET status = $4,
+            started_at = COALESCE(t.started_at, now()),
+            started_by_employee_id = COALESCE(t.started_by_employee_id, $2),
             updated_by_employee_id = $2
-        WHERE task_id = $1
-          AND status IN (0,1)
+        FROM prev
+        WHERE t.task_id = prev.task_id
+          AND prev.status IN ($3, $4)
+        RETURNING prev.status
         "#,
     )
     .bind(task_id)
     .bind(my_emp)
-    .execute(&state.db)
+    .bind(TaskStatus::Open)
+    .bind(TaskStatus::InProgress)
+    .fetch_optional(&state.db)
     .await
-    .map_err(|e| ApiError::BadRequest("TASK_START_FAILED", format!("{e}")))?;
+    .map_err(ApiError::write_failed("TASK_START_FAILED"))?;
+
+    if previous == Some(TaskStatus::Open) {
+        notify_status_change(&state, task_id, my_emp, "TASK_STARTED", "Task started").await;
+    }
 
     let dto = ensure_can_view_task(&state, &auth, task_id).await?;
     Ok(Json(ApiOk { data: dto }))
@@ -720,11 +1410,12 @@ pub async fn complete_task(
         }
     }
 
-    sqlx::query(
+    let res = sqlx::query(
         r#"
         UPDATE task
         SET status = 2,
             completed_at = COALESCE(completed_at, now()),
+            completed_by_employee_id = COALESCE(completed_by_employee_id, $2),
             updated_by_employee_id = $2
         WHERE task_id = $1
           AND status IN (0,1)
@@ -734,7 +1425,11 @@ pub async fn complete_task(
     .bind(my_emp)
     .execute(&state.db)
     .await
-    .map_err(|e| ApiError::BadRequest("TASK_COMPLETE_FAILED", format!("{e}")))?;
+    .map_err(ApiError::write_failed("TASK_COMPLETE_FAILED"))?;
+
+    if res.rows_affected() > 0 {
+        notify_status_change(&state, task_id, my_emp, "TASK_COMPLETED", "Task completed").await;
+    }
 
     let dto = ensure_can_view_task(&state, &auth, task_id).await?;
     Ok(Json(ApiOk { data: dto }))
@@ -749,20 +1444,19 @@ pub async fn cancel_task(
     let dto = ensure_can_view_task(&state, &auth, task_id).await?;
     let my_emp = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
 
-    if !can_manage_tasks(&auth) {
-        if !(is_doctor(&auth) && dto.created_by.id == my_emp) {
-            return Err(ApiError::Forbidden(
-                "FORBIDDEN",
-                "Doctor can only cancel tasks they created".into(),
-            ));
-        }
+    if !(can_manage_tasks(&auth) || is_doctor(&auth) && dto.created_by.id == my_emp) {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Doctor can only cancel tasks they created".into(),
+        ));
     }
 
-    sqlx::query(
+    let res = sqlx::query(
         r#"
         UPDATE task
         SET status = 3,
             canceled_at = COALESCE(canceled_at, now()),
+            canceled_by_employee_id = COALESCE(canceled_by_employee_id, $2),
             updated_by_employee_id = $2
         WHERE task_id = $1
           AND status IN (0,1)
@@ -772,7 +1466,408 @@ pub async fn cancel_task(
     .bind(my_emp)
     .execute(&state.db)
     .await
-    .map_err(|e| ApiError::BadRequest("TASK_CANCEL_FAILED", format!("{e}")))?;
+    .map_err(ApiError::write_failed("TASK_CANCEL_FAILED"))?;
+
+    if res.rows_affected() > 0 {
+        notify_status_change(&state, task_id, my_emp, "TASK_CANCELED", "Task canceled").await;
+    }
+
+    let dto = ensure_can_view_task(&state, &auth, task_id).await?;
+    Ok(Json(ApiOk { data: dto }))
+}
+
+/* ============================================================
+   POST /tasks/{id}/comments
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct AddTaskCommentRequest {
+    pub body: String,
+    pub parent_comment_id: Option<Uuid>, // reply to an existing comment on the same task
+}
+
+pub async fn add_task_comment(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(task_id): Path<Uuid>,
+    Json(req): Json<AddTaskCommentRequest>,
+) -> Result<Json<ApiOk<TaskDto>>, ApiError> {
+    // anyone who can view the task can comment on it
+    ensure_can_view_task(&state, &auth, task_id).await?;
+    let my_emp = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
+
+    let body = req.body.trim();
+    if body.is_empty() {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "body is required".into()));
+    }
+
+    if let Some(parent_id) = req.parent_comment_id {
+        let parent = sqlx::query(
+            r#"
+            SELECT 1
+            FROM task_comment
+            WHERE task_comment_id = $1 AND task_id = $2
+            "#,
+        )
+        .bind(parent_id)
+        .bind(task_id)
+        .fetch_optional(&state.db)
+        .await?;
+
+        if parent.is_none() {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "parent_comment_id does not belong to this task".into(),
+            ));
+        }
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO task_comment (task_id, parent_comment_id, author_employee_id, body)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(task_id)
+    .bind(req.parent_comment_id)
+    .bind(my_emp)
+    .bind(body)
+    .execute(&state.db)
+    .await
+    .map_err(ApiError::write_failed("TASK_COMMENT_FAILED"))?;
+
+    let dto = ensure_can_view_task(&state, &auth, task_id).await?;
+    Ok(Json(ApiOk { data: dto }))
+}
+
+/* ============================================================
+   Watchers
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct AddWatcherRequest {
+    pub employee_id: Option<Uuid>, // default: me
+}
+
+// POST /tasks/{id}/watchers : anyone who can view may watch; manage roles may add others
+pub async fn add_task_watcher(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(task_id): Path<Uuid>,
+    Json(req): Json<AddWatcherRequest>,
+) -> Result<Json<ApiOk<TaskDto>>, ApiError> {
+    ensure_can_view_task(&state, &auth, task_id).await?;
+    let my_emp = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
+
+    let employee_id = req.employee_id.unwrap_or(my_emp);
+    if employee_id != my_emp {
+        ensure_manage(&auth)?;
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO task_watcher (task_id, employee_id, added_by_employee_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (task_id, employee_id) DO NOTHING
+        "#,
+    )
+    .bind(task_id)
+    .bind(employee_id)
+    .bind(my_emp)
+    .execute(&state.db)
+    .await
+    .map_err(ApiError::write_failed("TASK_WATCH_FAILED"))?;
+
+    let dto = ensure_can_view_task(&state, &auth, task_id).await?;
+    Ok(Json(ApiOk { data: dto }))
+}
+
+// DELETE /tasks/{id}/watchers/{employee_id} : self, or manage roles for anyone
+pub async fn remove_task_watcher(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path((task_id, employee_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ApiOk<TaskDto>>, ApiError> {
+    let my_emp = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
+    if employee_id != my_emp {
+        ensure_manage(&auth)?;
+    }
+
+    sqlx::query("DELETE FROM task_watcher WHERE task_id = $1 AND employee_id = $2")
+        .bind(task_id)
+        .bind(employee_id)
+        .execute(&state.db)
+        .await?;
+
+    let dto = ensure_can_view_task(&state, &auth, task_id).await?;
+    Ok(Json(ApiOk { data: dto }))
+}
+
+/* ============================================================
+   Task templates (admin/manager)
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TaskTemplateDto {
+    pub task_template_id: Uuid,
+    pub name: String,
+    pub task_type: String,
+    pub title: String,
+    pub details: Option<String>,
+    pub priority: TaskPriority,
+    pub default_assignee_employee_id: Option<Uuid>,
+    pub recurrence: Option<String>,
+    pub is_active: bool,
+    pub created_by_employee_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const TASK_TEMPLATE_COLUMNS: &str = r#"
+    task_template_id,
+    name,
+    task_type,
+    title,
+    details,
+    priority,
+    default_assignee_employee_id,
+    recurrence,
+    is_active,
+    created_by_employee_id,
+    created_at,
+    updated_at
+"#;
+
+async fn fetch_task_template(state: &AppState, task_template_id: Uuid) -> Result<TaskTemplateDto, ApiError> {
+    let sql = format!("SELECT {TASK_TEMPLATE_COLUMNS} FROM task_template WHERE task_template_id = $1");
+    sqlx::query_as::<_, TaskTemplateDto>(&sql)
+        .bind(task_template_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "task template not found".into()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListTemplatesQuery {
+    pub include_inactive: Option<bool>,
+}
+
+// GET /task_templates : any staff can browse (to instantiate); inactive only for admin/manager
+pub async fn list_task_templates(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<ListTemplatesQuery>,
+) -> Result<Json<ApiOk<Vec<TaskTemplateDto>>>, ApiError> {
+    ensure_create(&auth)?;
+    let include_inactive = q.include_inactive.unwrap_or(false) && (is_admin(&auth) || is_manager(&auth));
+
+    let sql = format!(
+        "SELECT {TASK_TEMPLATE_COLUMNS} FROM task_template WHERE ($1 OR is_active) ORDER BY name ASC"
+    );
+    let items = sqlx::query_as::<_, TaskTemplateDto>(&sql)
+        .bind(include_inactive)
+        .fetch_all(&state.db)
+        .await?;
+
+    Ok(Json(ApiOk { data: items }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTaskTemplateRequest {
+    pub name: String,
+    pub task_type: String,
+    pub title: String,
+    pub details: Option<String>,
+    pub priority: Option<TaskPriority>,
+    pub default_assignee_employee_id: Option<Uuid>,
+    pub recurrence: Option<String>,
+}
+
+pub async fn create_task_template(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateTaskTemplateRequest>,
+) -> Result<Json<ApiOk<TaskTemplateDto>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    if req.name.trim().is_empty() || req.title.trim().is_empty() || req.task_type.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "name, title and task_type are required".into(),
+        ));
+    }
+    let priority = req.priority.unwrap_or(TaskPriority::Normal);
+    let recurrence = normalize_recurrence(req.recurrence.as_deref())?;
+    let my_emp = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
+
+    let task_template_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO task_template (
+          name, task_type, title, details, priority,
+          default_assignee_employee_id, recurrence, created_by_employee_id
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+        RETURNING task_template_id
+        "#,
+    )
+    .bind(req.name.trim())
+    .bind(req.task_type.trim())
+    .bind(req.title.trim())
+    .bind(req.details)
+    .bind(priority)
+    .bind(req.default_assignee_employee_id)
+    .bind(recurrence)
+    .bind(my_emp)
+    .fetch_one(&state.db)
+    .await
+    .map_err(ApiError::write_failed("TASK_TEMPLATE_CREATE_FAILED"))?;
+
+    let dto = fetch_task_template(&state, task_template_id).await?;
+    Ok(Json(ApiOk { data: dto }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PatchTaskTemplateRequest {
+    pub name: Option<String>,
+    pub task_type: Option<String>,
+    pub title: Option<String>,
+    pub details: Option<String>,
+    pub priority: Option<TaskPriority>,
+    pub default_assignee_employee_id: Option<Uuid>,
+    pub recurrence: Option<String>, // "" = one-off
+    pub is_active: Option<bool>,
+}
+
+pub async fn patch_task_template(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(task_template_id): Path<Uuid>,
+    Json(req): Json<PatchTaskTemplateRequest>,
+) -> Result<Json<ApiOk<TaskTemplateDto>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let recurrence = match req.recurrence.as_deref() {
+        None => None,
+        Some(spec) => Some(normalize_recurrence(Some(spec))?.unwrap_or_default()),
+    };
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE task_template
+        SET
+          name      = COALESCE($2, name),
+          task_type = COALESCE($3, task_type),
+          title     = COALESCE($4, title),
+          details   = COALESCE($5, details),
+          priority  = COALESCE($6, priority),
+          default_assignee_employee_id = COALESCE($7, default_assignee_employee_id),
+          recurrence = CASE WHEN $8::text IS NULL THEN recurrence ELSE NULLIF($8, '') END,
+          is_active = COALESCE($9, is_active)
+        WHERE task_template_id = $1
+        "#,
+    )
+    .bind(task_template_id)
+    .bind(req.name.as_deref().map(str::trim))
+    .bind(req.task_type.as_deref().map(str::trim))
+    .bind(req.title.as_deref().map(str::trim))
+    .bind(req.details)
+    .bind(req.priority)
+    .bind(req.default_assignee_employee_id)
+    .bind(recurrence)
+    .bind(req.is_active)
+    .execute(&state.db)
+    .await
+    .map_err(ApiError::write_failed("TASK_TEMPLATE_UPDATE_FAILED"))?;
+
+    if updated.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "task template not found".into()));
+    }
+
+    let dto = fetch_task_template(&state, task_template_id).await?;
+    Ok(Json(ApiOk { data: dto }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InstantiateTemplateRequest {
+    pub due_at: Option<DateTime<Utc>>,
+    pub assigned_to_employee_id: Option<Uuid>, // defaults to the template's assignee
+    pub patient_id: Option<Uuid>,
+    pub appointment_id: Option<Uuid>,
+    pub sms_id: Option<Uuid>,
+}
+
+// POST /task_templates/{id}/instantiate : create a task (carrying the recurrence) from a template
+pub async fn instantiate_task_template(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(task_template_id): Path<Uuid>,
+    Json(req): Json<InstantiateTemplateRequest>,
+) -> Result<Json<ApiOk<TaskDto>>, ApiError> {
+    ensure_create(&auth)?;
+
+    let tpl = fetch_task_template(&state, task_template_id).await?;
+    if !tpl.is_active {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "task template is inactive".into()));
+    }
+    if tpl.recurrence.is_some() && req.due_at.is_none() {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "due_at is required for recurring templates".into(),
+        ));
+    }
+
+    let patient_id = match req.sms_id {
+        Some(sms_id) => Some(sms_link_patient(&state, sms_id, req.patient_id).await?),
+        None => req.patient_id,
+    };
+
+    let my_emp = resolve_employee_id_by_user_id(&state, auth.user_id).await?;
+    let assignee = req.assigned_to_employee_id.or(tpl.default_assignee_employee_id);
+
+    let task_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO task (
+          created_by_employee_id,
+          assigned_to_employee_id,
+          patient_id,
+          appointment_id,
+          sms_id,
+          task_type,
+          status,
+          priority,
+          due_at,
+          title,
+          details,
+          updated_by_employee_id,
+          assigned_at,
+          assigned_by_employee_id,
+          task_template_id,
+          recurrence
+        )
+        VALUES (
+          $1,$2,$3,$4,$12,$5,0,$6,$7,$8,$9,$1,
+          CASE WHEN $2::uuid IS NULL THEN NULL ELSE now() END,
+          CASE WHEN $2::uuid IS NULL THEN NULL ELSE $1 END,
+          $10,$11
+        )
+        RETURNING task_id
+        "#,
+    )
+    .bind(my_emp)
+    .bind(assignee)
+    .bind(patient_id)
+    .bind(req.appointment_id)
+    .bind(&tpl.task_type)
+    .bind(tpl.priority)
+    .bind(req.due_at)
+    .bind(&tpl.title)
+    .bind(&tpl.details)
+    .bind(tpl.task_template_id)
+    .bind(&tpl.recurrence)
+    .bind(req.sms_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(ApiError::write_failed("TASK_CREATE_FAILED"))?;
 
     let dto = ensure_can_view_task(&state, &auth, task_id).await?;
     Ok(Json(ApiOk { data: dto }))
@@ -782,6 +1877,26 @@ pub async fn cancel_task(
    misc
    ============================================================ */
 
+// Notifications are best-effort: a failure must not fail the status change itself.
+async fn notify_status_change(state: &AppState, task_id: Uuid, actor: Uuid, kind: &str, body: &str) {
+    if let Err(e) = notify_task_participants(&state.db, task_id, actor, kind, body).await {
+        tracing::warn!("task {task_id}: failed to create notifications: {e}");
+    }
+}
+
 fn internal_row(e: sqlx::Error) -> ApiError {
     ApiError::Internal(format!("row decode error: {e}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sms_excerpts_are_cut_on_characters() {
+        assert_eq!(excerpt("  Please call me back  ", 80), "Please call me back");
+        assert_eq!(excerpt("Сайн байна уу, маргааш", 14), "Сайн байна уу,…");
+        assert_eq!(excerpt("abc def", 4), "abc…");
+        assert_eq!(excerpt("abcd", 4), "abcd");
+    }
+}
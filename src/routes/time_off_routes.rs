@@ -0,0 +1,522 @@
+// src/routes/time_off_routes.rs
+//
+// Employee time off. Staff request it for themselves (admin/manager also on
+// someone's behalf); admin/manager approve or deny. Approved ranges block the
+// doctor's slots (availability search, booking) and approval opens a
+// RESCHEDULE_APPOINTMENT task (unassigned = front-desk queue) for every upcoming
+// appointment the employee has in the range.
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    audit, clinic_time,
+    error::ApiError,
+    extract::Json,
+    middleware::auth_context::AuthContext,
+    models::{ApiList, ApiOk, AppState, Role, TaskPriority, TaskStatus, TimeOffStatus},
+};
+
+const MAX_TIME_OFF_DAYS: i64 = 366;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/time_off", get(list_time_off).post(create_time_off))
+        .route("/time_off/{time_off_id}/conflicts", get(get_time_off_conflicts))
+        .route("/time_off/{time_off_id}/approve", post(approve_time_off))
+        .route("/time_off/{time_off_id}/deny", post(deny_time_off))
+        .route("/time_off/{time_off_id}/cancel", post(cancel_time_off))
+}
+
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    }
+}
+
+fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_admin_or_manager() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin/manager can decide time off".into(),
+        ))
+    }
+}
+
+async fn my_employee_id(db: &sqlx::PgPool, auth: &AuthContext) -> Result<Option<Uuid>, ApiError> {
+    Ok(sqlx::query_scalar("SELECT employee_id FROM employee WHERE user_id = $1")
+        .bind(auth.user_id)
+        .fetch_optional(db)
+        .await?)
+}
+
+fn time_off_not_found() -> ApiError {
+    ApiError::NotFound("NOT_FOUND", "time off not found".into())
+}
+
+/// Employee the request is for; NOT_FOUND when it doesn't exist.
+async fn time_off_owner(db: &sqlx::PgPool, time_off_id: Uuid) -> Result<Uuid, ApiError> {
+    let owner: Option<Uuid> = sqlx::query_scalar("SELECT employee_id FROM employee_time_off WHERE time_off_id = $1")
+        .bind(time_off_id)
+        .fetch_optional(db)
+        .await?;
+    owner.ok_or_else(time_off_not_found)
+}
+
+/* ============================================================
+   DTOs
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TimeOffRow {
+    pub time_off_id: Uuid,
+    pub employee_id: Uuid,
+    pub employee_name: String,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub reason: Option<String>,
+    pub status: TimeOffStatus,
+    pub requested_by_user_id: Option<Uuid>,
+    pub decided_by_user_id: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub decision_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+const TIME_OFF_SELECT: &str = r#"
+    SELECT
+      t.time_off_id, t.employee_id, e.first_name || ' ' || e.last_name AS employee_name,
+      t.start_at, t.end_at, t.reason, t.status,
+      t.requested_by_user_id, t.decided_by_user_id, t.decided_at, t.decision_note, t.created_at
+    FROM employee_time_off t
+    JOIN employee e ON e.employee_id = t.employee_id
+"#;
+
+/// An upcoming appointment of the employee inside the time off.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TimeOffConflict {
+    pub appointment_id: Uuid,
+    pub patient_id: Uuid,
+    pub patient_name: String,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    /// open RESCHEDULE_APPOINTMENT task for it, if any
+    pub task_id: Option<Uuid>,
+}
+
+/// approve response: the request plus the appointments flagged for rescheduling
+#[derive(Debug, Serialize)]
+pub struct TimeOffApprovalResponse {
+    pub data: TimeOffRow,
+    pub conflicts: Vec<TimeOffConflict>,
+}
+
+async fn fetch_time_off(conn: &mut PgConnection, time_off_id: Uuid) -> Result<Option<TimeOffRow>, ApiError> {
+    Ok(sqlx::query_as::<_, TimeOffRow>(&format!("{TIME_OFF_SELECT} WHERE t.time_off_id = $1"))
+        .bind(time_off_id)
+        .fetch_optional(conn)
+        .await?)
+}
+
+/// Doctor or assistant on a slot-occupying appointment that hasn't ended yet.
+const CONFLICTS_CTE: &str = r#"
+    WITH conflict AS (
+      SELECT
+        a.appointment_id, a.patient_id, p.first_name || ' ' || p.last_name AS patient_name,
+        a.start_at, a.end_at
+      FROM appointment a
+      JOIN patient p ON p.patient_id = a.patient_id
+      WHERE (a.doctor_employee_id = $1 OR a.assistant_employee_id = $1)
+        AND a.status NOT IN (1,3)
+        AND a.end_at > now()
+        AND tstzrange(a.start_at, a.end_at, '[)') && tstzrange($2, $3, '[)')
+    ),
+    open_task AS (
+      SELECT DISTINCT ON (t.appointment_id) t.appointment_id, t.task_id
+      FROM task t
+      WHERE t.appointment_id IN (SELECT appointment_id FROM conflict)
+        AND t.task_type = 'RESCHEDULE_APPOINTMENT'
+        AND t.status IN (0,1)
+      ORDER BY t.appointment_id, t.created_at
+    )
+"#;
+
+/* ============================================================
+   GET /time_off
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct TimeOffListQuery {
+    pub employee_id: Option<Uuid>,
+    pub status: Option<TimeOffStatus>,
+    /// ranges overlapping [from, to)
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Admin/manager see everyone's requests; other staff only their own.
+pub async fn list_time_off(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(q): Query<TimeOffListQuery>,
+) -> Result<Json<ApiList<TimeOffRow>>, ApiError> {
+    ensure_staff(&auth)?;
+
+    let employee_id = if auth.role.is_admin_or_manager() {
+        q.employee_id
+    } else {
+        let mine = my_employee_id(&state.db, &auth).await?.ok_or_else(|| {
+            ApiError::BadRequest("NO_EMPLOYEE_PROFILE", "This user has no employee profile".into())
+        })?;
+        if q.employee_id.is_some_and(|id| id != mine) {
+            return Err(ApiError::Forbidden("FORBIDDEN", "You can only view your own time off".into()));
+        }
+        Some(mine)
+    };
+
+    let rows = sqlx::query_as::<_, TimeOffRow>(&format!(
+        r#"{TIME_OFF_SELECT}
+        WHERE ($1::uuid IS NULL OR t.employee_id = $1)
+          AND ($2::smallint IS NULL OR t.status = $2)
+          AND ($3::timestamptz IS NULL OR t.end_at > $3)
+          AND ($4::timestamptz IS NULL OR t.start_at < $4)
+        ORDER BY t.start_at DESC
+        LIMIT 500
+        "#
+    ))
+    .bind(employee_id)
+    .bind(q.status)
+    .bind(q.from)
+    .bind(q.to)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiOk { data: rows }))
+}
+
+/* ============================================================
+   POST /time_off
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTimeOffRequest {
+    /// default: the caller's own employee profile; others = admin/manager only
+    pub employee_id: Option<Uuid>,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+fn validate_range(start_at: DateTime<Utc>, end_at: DateTime<Utc>, now: DateTime<Utc>) -> Result<(), ApiError> {
+    if end_at <= start_at {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "end_at must be > start_at".into()));
+    }
+    if end_at <= now {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "time off must end in the future".into()));
+    }
+    if end_at - start_at > Duration::days(MAX_TIME_OFF_DAYS) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("time off must be at most {MAX_TIME_OFF_DAYS} days"),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn create_time_off(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(req): Json<CreateTimeOffRequest>,
+) -> Result<Json<ApiOk<TimeOffRow>>, ApiError> {
+    ensure_staff(&auth)?;
+    validate_range(req.start_at, req.end_at, Utc::now())?;
+    let reason = req.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    if reason.is_some_and(|r| r.chars().count() > 500) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "reason must be at most 500 chars".into()));
+    }
+
+    let mine = my_employee_id(&state.db, &auth).await?;
+    let employee_id = match req.employee_id {
+        Some(id) if Some(id) != mine && !auth.role.is_admin_or_manager() => {
+            return Err(ApiError::Forbidden(
+                "FORBIDDEN",
+                "Only admin/manager can request time off for someone else".into(),
+            ));
+        }
+        Some(id) => id,
+        None => mine.ok_or_else(|| {
+            ApiError::BadRequest("NO_EMPLOYEE_PROFILE", "This user has no employee profile".into())
+        })?,
+    };
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM employee WHERE employee_id = $1)")
+        .bind(employee_id)
+        .fetch_one(&state.db)
+        .await?;
+    if !exists {
+        return Err(ApiError::NotFound("NOT_FOUND", "employee not found".into()));
+    }
+
+    let overlapping: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT time_off_id
+        FROM employee_time_off
+        WHERE employee_id = $1
+          AND status IN ($4, $5)
+          AND tstzrange(start_at, end_at, '[)') && tstzrange($2, $3, '[)')
+        LIMIT 1
+        "#,
+    )
+    .bind(employee_id)
+    .bind(req.start_at)
+    .bind(req.end_at)
+    .bind(TimeOffStatus::Pending)
+    .bind(TimeOffStatus::Approved)
+    .fetch_optional(&state.db)
+    .await?;
+    if let Some(id) = overlapping {
+        return Err(ApiError::Conflict(
+            "TIME_OFF_OVERLAP",
+            format!("overlaps pending or approved time off {id}"),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let time_off_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO employee_time_off (employee_id, start_at, end_at, reason, requested_by_user_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING time_off_id
+        "#,
+    )
+    .bind(employee_id)
+    .bind(req.start_at)
+    .bind(req.end_at)
+    .bind(reason)
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("TIME_OFF_CREATE_FAILED"))?;
+
+    let data = fetch_time_off(&mut tx, time_off_id).await?.ok_or_else(time_off_not_found)?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/* ============================================================
+   GET /time_off/{id}/conflicts
+   ============================================================ */
+
+/// Upcoming appointments inside the range, for deciding a request
+/// (nothing is changed).
+pub async fn get_time_off_conflicts(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(time_off_id): Path<Uuid>,
+) -> Result<Json<ApiList<TimeOffConflict>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+    let mut conn = state.db.acquire().await?;
+    let row = fetch_time_off(&mut conn, time_off_id).await?.ok_or_else(time_off_not_found)?;
+
+    let conflicts = sqlx::query_as::<_, TimeOffConflict>(&format!(
+        r#"{CONFLICTS_CTE}
+        SELECT c.appointment_id, c.patient_id, c.patient_name, c.start_at, c.end_at, ot.task_id
+        FROM conflict c
+        LEFT JOIN open_task ot ON ot.appointment_id = c.appointment_id
+        ORDER BY c.start_at
+        "#
+    ))
+    .bind(row.employee_id)
+    .bind(row.start_at)
+    .bind(row.end_at)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Json(ApiOk { data: conflicts }))
+}
+
+/* ============================================================
+   POST /time_off/{id}/approve | deny | cancel
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct TimeOffDecisionRequest {
+    pub note: Option<String>,
+}
+
+/// Locks the request and moves it to `next`; the caller commits.
+async fn transition(
+    tx: &mut PgConnection,
+    auth: &AuthContext,
+    time_off_id: Uuid,
+    next: TimeOffStatus,
+    note: Option<&str>,
+) -> Result<TimeOffRow, ApiError> {
+    let current: Option<TimeOffStatus> =
+        sqlx::query_scalar("SELECT status FROM employee_time_off WHERE time_off_id = $1 FOR UPDATE")
+            .bind(time_off_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    current.ok_or_else(time_off_not_found)?.ensure_transition(next)?;
+
+    // approve/deny record the decider; cancel keeps the decision as it was
+    let decided = next != TimeOffStatus::Canceled;
+    sqlx::query(
+        r#"
+        UPDATE employee_time_off
+        SET status = $2,
+            decided_by_user_id = CASE WHEN $3 THEN $4 ELSE decided_by_user_id END,
+            decided_at         = CASE WHEN $3 THEN now() ELSE decided_at END,
+            decision_note      = CASE WHEN $3 THEN $5 ELSE decision_note END
+        WHERE time_off_id = $1
+        "#,
+    )
+    .bind(time_off_id)
+    .bind(next)
+    .bind(decided)
+    .bind(auth.user_id)
+    .bind(note)
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("TIME_OFF_UPDATE_FAILED"))?;
+
+    audit::record(
+        &mut *tx,
+        auth,
+        &format!("time_off.{next}"),
+        "employee_time_off",
+        Some(time_off_id),
+        serde_json::json!({ "from": current, "note": note }),
+    )
+    .await?;
+
+    fetch_time_off(tx, time_off_id).await?.ok_or_else(time_off_not_found)
+}
+
+/// Managers don't decide their own requests; admins may.
+async fn ensure_can_decide(state: &AppState, auth: &AuthContext, time_off_id: Uuid) -> Result<(), ApiError> {
+    ensure_admin_or_manager(auth)?;
+    if auth.role == Role::Manager {
+        let owner = time_off_owner(&state.db, time_off_id).await?;
+        if my_employee_id(&state.db, auth).await? == Some(owner) {
+            return Err(ApiError::Forbidden(
+                "FORBIDDEN",
+                "You cannot decide your own time off".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn clean_note(req: TimeOffDecisionRequest) -> Option<String> {
+    req.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty())
+}
+
+pub async fn approve_time_off(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(time_off_id): Path<Uuid>,
+    Json(req): Json<TimeOffDecisionRequest>,
+) -> Result<Json<TimeOffApprovalResponse>, ApiError> {
+    ensure_can_decide(&state, &auth, time_off_id).await?;
+    let note = clean_note(req);
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let creator = my_employee_id(&state.db, &auth).await?;
+
+    let mut tx = state.db.begin().await?;
+    let data = transition(&mut tx, &auth, time_off_id, TimeOffStatus::Approved, note.as_deref()).await?;
+
+    let local = |t: DateTime<Utc>| t.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string();
+    let details = format!(
+        "{} is off from {} to {}; the appointment needs a new time or doctor.",
+        data.employee_name,
+        local(data.start_at),
+        local(data.end_at),
+    );
+
+    // one task per conflicting appointment without an open reschedule task
+    let conflicts = sqlx::query_as::<_, TimeOffConflict>(&format!(
+        r#"{CONFLICTS_CTE},
+        created AS (
+          INSERT INTO task (
+            created_by_employee_id, patient_id, appointment_id, task_type,
+            status, priority, due_at, title, details, updated_by_employee_id
+          )
+          SELECT $4, c.patient_id, c.appointment_id, 'RESCHEDULE_APPOINTMENT', $5, $6, c.start_at,
+                 'Reschedule appointment: ' || c.patient_name, $7, $4
+          FROM conflict c
+          WHERE NOT EXISTS (SELECT 1 FROM open_task ot WHERE ot.appointment_id = c.appointment_id)
+          RETURNING appointment_id, task_id
+        )
+        SELECT c.appointment_id, c.patient_id, c.patient_name, c.start_at, c.end_at,
+               COALESCE(cr.task_id, ot.task_id) AS task_id
+        FROM conflict c
+        LEFT JOIN created cr ON cr.appointment_id = c.appointment_id
+        LEFT JOIN open_task ot ON ot.appointment_id = c.appointment_id
+        ORDER BY c.start_at
+        "#
+    ))
+    .bind(data.employee_id)
+    .bind(data.start_at)
+    .bind(data.end_at)
+    .bind(creator.unwrap_or(data.employee_id))
+    .bind(TaskStatus::Open)
+    .bind(TaskPriority::High)
+    .bind(&details)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("TASK_CREATE_FAILED"))?;
+
+    tx.commit().await?;
+
+    Ok(Json(TimeOffApprovalResponse { data, conflicts }))
+}
+
+pub async fn deny_time_off(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(time_off_id): Path<Uuid>,
+    Json(req): Json<TimeOffDecisionRequest>,
+) -> Result<Json<ApiOk<TimeOffRow>>, ApiError> {
+    ensure_can_decide(&state, &auth, time_off_id).await?;
+    let note = clean_note(req);
+
+    let mut tx = state.db.begin().await?;
+    let data = transition(&mut tx, &auth, time_off_id, TimeOffStatus::Denied, note.as_deref()).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+/// The employee withdraws a request, or admin/manager cancel one (also after
+/// approval; reschedule tasks already created stay open).
+pub async fn cancel_time_off(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(time_off_id): Path<Uuid>,
+) -> Result<Json<ApiOk<TimeOffRow>>, ApiError> {
+    ensure_staff(&auth)?;
+    if !auth.role.is_admin_or_manager() {
+        let owner = time_off_owner(&state.db, time_off_id).await?;
+        if my_employee_id(&state.db, &auth).await? != Some(owner) {
+            return Err(ApiError::Forbidden("FORBIDDEN", "You can only cancel your own time off".into()));
+        }
+    }
+
+    let mut tx = state.db.begin().await?;
+    let data = transition(&mut tx, &auth, time_off_id, TimeOffStatus::Canceled, None).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
@@ -1,23 +1,32 @@
 // src/routes/user_routes.rs
+//
+// Staff/patient accounts (admin/manager). Staff accounts that act as employees
+// (doctors above all: appointments resolve the doctor through employee.user_id)
+// get their employee row via POST /users/{id}/employee_profile or an inline
+// `employee` on create.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     routing::{get, post},
-    Json, Router,
+    Router,
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
+    audit,
     auth::hash_password,
-    error::ApiError,
+    error::{ApiError, DbError},
+    extract::Json,
+    login_events::{self, LoginHistoryQuery, LoginEventRow},
     middleware::auth_context::AuthContext,
-    models::AppState,
+    models::{ApiList, ApiOk, AppState, Gender, Role},
+    numbering,
 };
 
 fn ensure_admin_or_manager(auth: &AuthContext) -> Result<(), ApiError> {
     // roles: 1 admin, 2 manager
-    if auth.role == 1 || auth.role == 2 {
+    if auth.role.is_admin_or_manager() {
         Ok(())
     } else {
         Err(ApiError::Forbidden(
@@ -32,55 +41,74 @@ pub struct UserPublicRow {
     pub user_id: Uuid,
     pub username: String,
     pub display_name: String,
-    pub roles: i16,
+    pub roles: Role,
     pub is_active: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct UsersListResponse {
-    pub data: UsersListData,
-}
-
 #[derive(Debug, Serialize)]
 pub struct UsersListData {
     pub users: Vec<UserPublicRow>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct UserGetResponse {
-    pub data: UserPublicRow,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct CreateUserRequest {
     pub username: String,
     pub display_name: String,
     pub password: String,
-    pub roles: i16,              // 0..4
+    pub roles: Role,
     pub is_active: Option<bool>, // default true
+    /// staff roles only: create/link the employee profile in the same transaction
+    pub employee: Option<EmployeeProfileRequest>,
 }
 
 #[derive(Debug, Serialize)]
-pub struct CreateUserResponse {
-    pub data: UserPublicRow,
+pub struct CreatedUser {
+    #[serde(flatten)]
+    pub user: UserPublicRow,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub employee: Option<EmployeeProfileRow>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct UpdateUserRequest {
-    pub display_name: Option<String>,
-    pub roles: Option<i16>,
-    pub is_active: Option<bool>,
+pub struct EmployeeProfileRequest {
+    /// link this existing, unlinked employee instead of creating a new one
+    pub employee_id: Option<Uuid>,
+    /// default: first word of the user's display_name
+    pub first_name: Option<String>,
+    /// default: rest of the user's display_name
+    pub last_name: Option<String>,
+    /// default unspecified
+    pub gender: Option<Gender>,
+    pub prim_phone_number: Option<String>,
+    pub email: Option<String>,
+    pub birthday: Option<chrono::NaiveDate>,
+    /// default today
+    pub hired_at: Option<chrono::NaiveDate>,
+    /// default the first clinic location
+    pub location_id: Option<Uuid>,
+    /// default the next free number (numbering.rs); new profiles only
+    pub employee_display_number: Option<i64>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct UpdateUserResponse {
-    pub data: UserPublicRow,
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct EmployeeProfileRow {
+    pub employee_id: Uuid,
+    pub employee_display_number: i64,
+    pub user_id: Option<Uuid>,
+    pub first_name: String,
+    pub last_name: String,
+    pub gender: Gender,
+    pub status: i16,
+    pub location_id: Option<Uuid>,
+    pub hired_at: Option<chrono::NaiveDate>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct OkResponse {
-    pub data: OkData,
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRequest {
+    pub display_name: Option<String>,
+    pub roles: Option<Role>,
+    pub is_active: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -98,12 +126,16 @@ pub fn router() -> Router<AppState> {
         .route("/{user_id}/disable", post(disable_user))
         // /api/v1/users/{user_id}/enable
         .route("/{user_id}/enable", post(enable_user))
+        // /api/v1/users/{user_id}/employee_profile
+        .route("/{user_id}/employee_profile", post(create_employee_profile))
+        // /api/v1/users/{user_id}/login_history?success=&limit=&offset=
+        .route("/{user_id}/login_history", get(login_history))
 }
 
 pub async fn list_users(
     State(state): State<AppState>,
     auth: AuthContext,
-) -> Result<Json<UsersListResponse>, ApiError> {
+) -> Result<Json<ApiOk<UsersListData>>, ApiError> {
     ensure_admin_or_manager(&auth)?;
 
     let users: Vec<UserPublicRow> = sqlx::query_as::<_, UserPublicRow>(
@@ -115,10 +147,9 @@ pub async fn list_users(
         "#,
     )
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .await?;
 
-    Ok(Json(UsersListResponse {
+    Ok(Json(ApiOk {
         data: UsersListData { users },
     }))
 }
@@ -127,7 +158,7 @@ pub async fn get_user(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<UserGetResponse>, ApiError> {
+) -> Result<Json<ApiOk<UserPublicRow>>, ApiError> {
     ensure_admin_or_manager(&auth)?;
 
     let user: UserPublicRow = sqlx::query_as::<_, UserPublicRow>(
@@ -139,21 +170,10 @@ pub async fn get_user(
     )
     .bind(user_id)
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "user not found".into()))?;
-
-    Ok(Json(UserGetResponse { data: user }))
-}
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "user not found".into()))?;
 
-fn validate_role(roles: i16) -> Result<(), ApiError> {
-    if !(0..=4).contains(&roles) {
-        return Err(ApiError::BadRequest(
-            "VALIDATION_ERROR",
-            "roles must be one of 0..4".into(),
-        ));
-    }
-    Ok(())
+    Ok(Json(ApiOk { data: user }))
 }
 
 fn validate_username(username: &str) -> Result<(), ApiError> {
@@ -195,24 +215,252 @@ fn validate_password(pw: &str) -> Result<(), ApiError> {
     Ok(())
 }
 
+/// Guard rules for role / active changes (`existing` = None when creating):
+/// - only admins grant or revoke admin, which includes disabling or re-enabling
+///   an admin account
+/// - nobody changes their own role
+/// - the last active admin can't be demoted or disabled
+fn check_role_change(
+    auth: &AuthContext,
+    existing: Option<&UserPublicRow>,
+    roles: Role,
+    is_active: bool,
+    other_active_admins: i64,
+) -> Result<(), ApiError> {
+    let old_roles = existing.map(|u| u.roles);
+    let role_changed = old_roles != Some(roles);
+
+    if role_changed && (roles == Role::Admin || old_roles == Some(Role::Admin)) && auth.role != Role::Admin {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin can grant or revoke the admin role".into(),
+        ));
+    }
+    let admin_active_changed =
+        existing.is_some_and(|u| u.roles == Role::Admin && roles == Role::Admin && u.is_active != is_active);
+    if admin_active_changed && auth.role != Role::Admin {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin can disable or enable an admin account".into(),
+        ));
+    }
+
+    let Some(existing) = existing else {
+        return Ok(());
+    };
+
+    if role_changed && existing.user_id == auth.user_id {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "You cannot change your own role".into(),
+        ));
+    }
+
+    let stays_admin = roles == Role::Admin && is_active;
+    if existing.roles == Role::Admin && existing.is_active && !stays_admin && other_active_admins == 0 {
+        return Err(ApiError::Conflict(
+            "LAST_ADMIN",
+            "The last active admin cannot be demoted or disabled".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Locks the target row (and every active admin row) so two concurrent demotions
+/// can't both see "another admin is left".
+async fn lock_for_role_change(
+    tx: &mut sqlx::PgConnection,
+    user_id: Uuid,
+) -> Result<(UserPublicRow, i64), ApiError> {
+    let admins: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT user_id FROM "dcms_user"
+        WHERE roles = $1 AND is_active
+        ORDER BY user_id
+        FOR UPDATE
+        "#,
+    )
+    .bind(Role::Admin)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let existing: UserPublicRow = sqlx::query_as::<_, UserPublicRow>(
+        r#"
+        SELECT user_id, username, display_name, roles, is_active, created_at
+        FROM "dcms_user"
+        WHERE user_id = $1
+        FOR UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "user not found".into()))?;
+
+    let other_active_admins = admins.iter().filter(|id| **id != user_id).count() as i64;
+    Ok((existing, other_active_admins))
+}
+
+const EMPLOYEE_PROFILE_COLUMNS: &str = "employee_id, employee_display_number, user_id, first_name, last_name, \
+     gender, status, location_id, hired_at";
+
+/// `display_name` split into (first, last) at the first space.
+fn split_display_name(display_name: &str) -> (String, String) {
+    match display_name.trim().split_once(' ') {
+        Some((first, last)) => (first.to_string(), last.trim().to_string()),
+        None => (display_name.trim().to_string(), String::new()),
+    }
+}
+
+/// Creates (or links, with `employee_id`) the employee row of a staff user.
+async fn attach_employee_profile(
+    conn: &mut sqlx::PgConnection,
+    auth: &AuthContext,
+    user: &UserPublicRow,
+    req: &EmployeeProfileRequest,
+) -> Result<EmployeeProfileRow, ApiError> {
+    if user.roles == Role::Patient {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "patients don't have employee profiles".into(),
+        ));
+    }
+
+    let linked: Option<Uuid> = sqlx::query_scalar("SELECT employee_id FROM employee WHERE user_id = $1")
+        .bind(user.user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if let Some(employee_id) = linked {
+        return Err(ApiError::Conflict(
+            "EMPLOYEE_PROFILE_EXISTS",
+            format!("user is already linked to employee {employee_id}"),
+        ));
+    }
+
+    if req.employee_id.is_some() && req.employee_display_number.is_some() {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "employee_display_number can't be set when linking an existing employee".into(),
+        ));
+    }
+
+    let profile = if let Some(employee_id) = req.employee_id {
+        let row = sqlx::query_as::<_, EmployeeProfileRow>(&format!(
+            r#"
+            UPDATE employee
+            SET user_id = $1, last_updated_at = now()
+            WHERE employee_id = $2 AND user_id IS NULL
+            RETURNING {EMPLOYEE_PROFILE_COLUMNS}
+            "#
+        ))
+        .bind(user.user_id)
+        .bind(employee_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        match row {
+            Some(row) => row,
+            None => {
+                let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM employee WHERE employee_id = $1)")
+                    .bind(employee_id)
+                    .fetch_one(&mut *conn)
+                    .await?;
+                return Err(if exists {
+                    ApiError::Conflict("EMPLOYEE_ALREADY_LINKED", "employee is linked to another user".into())
+                } else {
+                    ApiError::NotFound("NOT_FOUND", "employee not found".into())
+                });
+            }
+        }
+    } else {
+        let (default_first, default_last) = split_display_name(&user.display_name);
+        let first_name = req.first_name.as_deref().map(str::trim).unwrap_or(&default_first).to_string();
+        let last_name = req.last_name.as_deref().map(str::trim).unwrap_or(&default_last).to_string();
+        if first_name.is_empty() {
+            return Err(ApiError::BadRequest("VALIDATION_ERROR", "first_name is required".into()));
+        }
+        let gender = req.gender.unwrap_or(Gender::Unspecified);
+        let display_number = match req.employee_display_number {
+            Some(n) if n <= 0 => {
+                return Err(ApiError::BadRequest("VALIDATION_ERROR", "employee_display_number must be > 0".into()));
+            }
+            Some(n) => n,
+            None => numbering::next_employee_display_number(&mut *conn).await?,
+        };
+
+        sqlx::query_as::<_, EmployeeProfileRow>(&format!(
+            r#"
+            INSERT INTO employee
+              (user_id, first_name, last_name, gender, status,
+               prim_phone_number, email, birthday, hired_at, location_id, employee_display_number)
+            VALUES
+              ($1, $2, $3, $4, 1,
+               $5, $6, $7, COALESCE($8, CURRENT_DATE),
+               COALESCE($9, (SELECT location_id FROM clinic_location ORDER BY created_at LIMIT 1)), $10)
+            RETURNING {EMPLOYEE_PROFILE_COLUMNS}
+            "#
+        ))
+        .bind(user.user_id)
+        .bind(&first_name)
+        .bind(&last_name)
+        .bind(gender)
+        .bind(req.prim_phone_number.as_deref().map(str::trim).filter(|s| !s.is_empty()))
+        .bind(req.email.as_deref().map(str::trim).filter(|s| !s.is_empty()))
+        .bind(req.birthday)
+        .bind(req.hired_at)
+        .bind(req.location_id)
+        .bind(display_number)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| match DbError::from(e) {
+            DbError::UniqueViolation { constraint: Some(c) } if c == "employee_employee_display_number_key" => {
+                ApiError::Conflict("EMPLOYEE_NUMBER_TAKEN", "employee_display_number is already in use".into())
+            }
+            DbError::UniqueViolation { .. } => {
+                ApiError::Conflict("EMPLOYEE_PROFILE_EXISTS", "user already has an employee profile".into())
+            }
+            other => other.into(),
+        })?
+    };
+
+    audit::record(
+        &mut *conn,
+        auth,
+        "employee.link_user",
+        "employee",
+        Some(profile.employee_id),
+        serde_json::json!({ "user_id": user.user_id, "created": req.employee_id.is_none() }),
+    )
+    .await?;
+
+    Ok(profile)
+}
+
 pub async fn create_user(
     State(state): State<AppState>,
     auth: AuthContext,
     Json(req): Json<CreateUserRequest>,
-) -> Result<Json<CreateUserResponse>, ApiError> {
+) -> Result<Json<ApiOk<CreatedUser>>, ApiError> {
     ensure_admin_or_manager(&auth)?;
 
     validate_username(&req.username)?;
     validate_display_name(&req.display_name)?;
     validate_password(&req.password)?;
-    validate_role(req.roles)?;
+    check_role_change(&auth, None, req.roles, req.is_active.unwrap_or(true), 0)?;
+    if req.employee.is_some() && req.roles == Role::Patient {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "employee is only allowed for staff roles".into(),
+        ));
+    }
 
     let username = req.username.trim().to_string();
     let display_name = req.display_name.trim().to_string();
     let is_active = req.is_active.unwrap_or(true);
 
     let pw_hash = hash_password(req.password.trim())
-        .map_err(|e| ApiError::Internal(e))?;
+        .map_err(ApiError::Internal)?;
+
+    let mut tx = state.db.begin().await?;
 
     // Insert
     let user: UserPublicRow = sqlx::query_as::<_, UserPublicRow>(
@@ -227,37 +475,69 @@ pub async fn create_user(
     .bind(&pw_hash)
     .bind(req.roles)
     .bind(is_active)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| {
-        // If you want better UX later, detect unique violation on username.
-        ApiError::Internal(format!("db error: {e}"))
+    .map_err(|e| match DbError::from(e) {
+        DbError::UniqueViolation { .. } => {
+            ApiError::Conflict("USERNAME_TAKEN", format!("username '{username}' is already taken"))
+        }
+        other => other.into(),
     })?;
 
-    Ok(Json(CreateUserResponse { data: user }))
+    let employee = match &req.employee {
+        Some(e) => Some(attach_employee_profile(&mut tx, &auth, &user, e).await?),
+        None => None,
+    };
+
+    tx.commit().await?;
+
+    Ok(Json(ApiOk {
+        data: CreatedUser { user, employee },
+    }))
 }
 
-pub async fn update_user(
+/// Creates or links the employee profile of an existing staff user.
+pub async fn create_employee_profile(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(user_id): Path<Uuid>,
-    Json(req): Json<UpdateUserRequest>,
-) -> Result<Json<UpdateUserResponse>, ApiError> {
+    Json(req): Json<EmployeeProfileRequest>,
+) -> Result<Json<ApiOk<EmployeeProfileRow>>, ApiError> {
     ensure_admin_or_manager(&auth)?;
 
-    // Load existing
-    let existing: UserPublicRow = sqlx::query_as::<_, UserPublicRow>(
+    let mut tx = state.db.begin().await?;
+
+    let user: UserPublicRow = sqlx::query_as::<_, UserPublicRow>(
         r#"
         SELECT user_id, username, display_name, roles, is_active, created_at
         FROM "dcms_user"
         WHERE user_id = $1
+        FOR UPDATE
         "#,
     )
     .bind(user_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?
-    .ok_or_else(|| ApiError::BadRequest("NOT_FOUND", "user not found".into()))?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "user not found".into()))?;
+
+    let data = attach_employee_profile(&mut tx, &auth, &user, &req).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiOk { data }))
+}
+
+pub async fn update_user(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(user_id): Path<Uuid>,
+    Json(req): Json<UpdateUserRequest>,
+) -> Result<Json<ApiOk<UserPublicRow>>, ApiError> {
+    ensure_admin_or_manager(&auth)?;
+
+    let mut tx = state.db.begin().await?;
+
+    // Load existing
+    let (existing, other_active_admins) = lock_for_role_change(&mut tx, user_id).await?;
 
     // Compute updates
     let display_name = match req.display_name.as_deref().map(str::trim) {
@@ -268,15 +548,10 @@ pub async fn update_user(
         _ => existing.display_name.clone(),
     };
 
-    let roles = match req.roles {
-        Some(r) => {
-            validate_role(r)?;
-            r
-        }
-        None => existing.roles,
-    };
+    let roles = req.roles.unwrap_or(existing.roles);
 
     let is_active = req.is_active.unwrap_or(existing.is_active);
+    check_role_change(&auth, Some(&existing), roles, is_active, other_active_admins)?;
 
     // Apply
     let updated: UserPublicRow = sqlx::query_as::<_, UserPublicRow>(
@@ -293,21 +568,42 @@ pub async fn update_user(
     .bind(roles)
     .bind(is_active)
     .bind(user_id)
-    .fetch_one(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if existing.roles != roles {
+        audit::record(
+            &mut *tx,
+            &auth,
+            "user.role_change",
+            "dcms_user",
+            Some(user_id),
+            serde_json::json!({ "from": existing.roles, "to": roles }),
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    // cached sessions carry the role and were checked against is_active
+    state.session_cache.invalidate_user(user_id);
 
-    Ok(Json(UpdateUserResponse { data: updated }))
+    Ok(Json(ApiOk { data: updated }))
 }
 
 pub async fn disable_user(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<OkResponse>, ApiError> {
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
     ensure_admin_or_manager(&auth)?;
+    auth.ensure_not_impersonating()?;
 
-    let res = sqlx::query(
+    let mut tx = state.db.begin().await?;
+    let (existing, other_active_admins) = lock_for_role_change(&mut tx, user_id).await?;
+    check_role_change(&auth, Some(&existing), existing.roles, false, other_active_admins)?;
+
+    sqlx::query(
         r#"
         UPDATE "dcms_user"
         SET is_active = false
@@ -315,15 +611,14 @@ pub async fn disable_user(
         "#,
     )
     .bind(user_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .execute(&mut *tx)
+    .await?;
 
-    if res.rows_affected() == 0 {
-        return Err(ApiError::BadRequest("NOT_FOUND", "user not found".into()));
-    }
+    tx.commit().await?;
 
-    Ok(Json(OkResponse {
+    state.session_cache.invalidate_user(user_id);
+
+    Ok(Json(ApiOk {
         data: OkData { ok: true },
     }))
 }
@@ -332,10 +627,14 @@ pub async fn enable_user(
     State(state): State<AppState>,
     auth: AuthContext,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<OkResponse>, ApiError> {
+) -> Result<Json<ApiOk<OkData>>, ApiError> {
     ensure_admin_or_manager(&auth)?;
 
-    let res = sqlx::query(
+    let mut tx = state.db.begin().await?;
+    let (existing, other_active_admins) = lock_for_role_change(&mut tx, user_id).await?;
+    check_role_change(&auth, Some(&existing), existing.roles, true, other_active_admins)?;
+
+    sqlx::query(
         r#"
         UPDATE "dcms_user"
         SET is_active = true
@@ -343,20 +642,44 @@ pub async fn enable_user(
         "#,
     )
     .bind(user_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| ApiError::Internal(format!("db error: {e}")))?;
+    .execute(&mut *tx)
+    .await?;
 
-    if res.rows_affected() == 0 {
-        return Err(ApiError::BadRequest("NOT_FOUND", "user not found".into()));
-    }
+    tx.commit().await?;
 
-    Ok(Json(OkResponse {
+    Ok(Json(ApiOk {
         data: OkData { ok: true },
     }))
 }
 
 
+/// Login attempts of any account (admin only), newest first.
+pub async fn login_history(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(user_id): Path<Uuid>,
+    Query(q): Query<LoginHistoryQuery>,
+) -> Result<Json<ApiList<LoginEventRow>>, ApiError> {
+    if auth.role != Role::Admin {
+        return Err(ApiError::Forbidden(
+            "FORBIDDEN",
+            "Only admin can view login history".into(),
+        ));
+    }
+
+    let exists: bool = sqlx::query_scalar(r#"SELECT EXISTS (SELECT 1 FROM "dcms_user" WHERE user_id = $1)"#)
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if !exists {
+        return Err(ApiError::NotFound("NOT_FOUND", "user not found".into()));
+    }
+
+    let data = login_events::list_for_user(&state.db, user_id, &q).await?;
+    Ok(Json(ApiOk { data }))
+}
+
+
 // In src/routes/user_routes.rs (at the bottom)
 #[cfg(test)]
 mod tests {
@@ -365,15 +688,16 @@ mod tests {
     
     #[test]
     fn test_validate_role_bounds() {
-        // Valid roles should pass
-        assert!(validate_role(0).is_ok());
-        assert!(validate_role(2).is_ok());
-        assert!(validate_role(4).is_ok());
-        
+        let role = |v: serde_json::Value| serde_json::from_value::<Role>(v);
+        // Valid roles should pass, by code or by name
+        assert_eq!(role(serde_json::json!(0)).unwrap(), Role::Patient);
+        assert_eq!(role(serde_json::json!(2)).unwrap(), Role::Manager);
+        assert_eq!(role(serde_json::json!("receptionist")).unwrap(), Role::Receptionist);
+
         // Invalid roles should fail
-        assert!(validate_role(-1).is_err());
-        assert!(validate_role(5).is_err());
-        assert!(validate_role(100).is_err());
+        assert!(role(serde_json::json!(-1)).is_err());
+        assert!(role(serde_json::json!(5)).is_err());
+        assert!(role(serde_json::json!(100)).is_err());
     }
     
     #[test]
@@ -384,6 +708,72 @@ mod tests {
         assert!(validate_username("  ").is_err()); // Only whitespace
     }
     
+    fn user(user_id: Uuid, roles: Role) -> UserPublicRow {
+        UserPublicRow {
+            user_id,
+            username: "someone".into(),
+            display_name: "Someone".into(),
+            roles,
+            is_active: true,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn actor(user_id: Uuid, role: Role) -> AuthContext {
+        AuthContext {
+            user_id,
+            role,
+            session_token_id: Uuid::new_v4(),
+            impersonator_user_id: None,
+        }
+    }
+
+    #[test]
+    fn test_split_display_name() {
+        assert_eq!(split_display_name("Anna Maria Lee"), ("Anna".into(), "Maria Lee".into()));
+        assert_eq!(split_display_name(" Bold "), ("Bold".into(), String::new()));
+    }
+
+    #[test]
+    fn test_role_change_guards() {
+        let (me, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let manager = actor(me, Role::Manager);
+        let admin = actor(me, Role::Admin);
+
+        // manager can't promote anyone (incl. themselves) to admin, or create one
+        assert!(check_role_change(&manager, Some(&user(other, Role::Receptionist)), Role::Admin, true, 1).is_err());
+        assert!(check_role_change(&manager, Some(&user(me, Role::Manager)), Role::Admin, true, 1).is_err());
+        assert!(check_role_change(&manager, None, Role::Admin, true, 0).is_err());
+        // ... or demote an admin
+        assert!(check_role_change(&manager, Some(&user(other, Role::Admin)), Role::Manager, true, 1).is_err());
+        // ... or disable / re-enable one, even with other admins left
+        assert!(matches!(
+            check_role_change(&manager, Some(&user(other, Role::Admin)), Role::Admin, false, 2),
+            Err(ApiError::Forbidden(..))
+        ));
+        let disabled_admin = UserPublicRow { is_active: false, ..user(other, Role::Admin) };
+        assert!(check_role_change(&manager, Some(&disabled_admin), Role::Admin, true, 2).is_err());
+        assert!(check_role_change(&admin, Some(&user(other, Role::Admin)), Role::Admin, false, 1).is_ok());
+        assert!(check_role_change(&admin, Some(&disabled_admin), Role::Admin, true, 1).is_ok());
+        // but can still manage non-admin roles
+        assert!(check_role_change(&manager, Some(&user(other, Role::Receptionist)), Role::Doctor, true, 1).is_ok());
+        assert!(check_role_change(&manager, None, Role::Receptionist, true, 0).is_ok());
+        assert!(check_role_change(&manager, Some(&user(other, Role::Doctor)), Role::Doctor, false, 1).is_ok());
+
+        // nobody changes their own role
+        assert!(check_role_change(&admin, Some(&user(me, Role::Admin)), Role::Manager, true, 3).is_err());
+        // unchanged role on self is fine (e.g. display_name edit)
+        assert!(check_role_change(&admin, Some(&user(me, Role::Admin)), Role::Admin, true, 0).is_ok());
+
+        // last active admin: no demote, no disable
+        assert!(matches!(
+            check_role_change(&admin, Some(&user(other, Role::Admin)), Role::Manager, true, 0),
+            Err(ApiError::Conflict("LAST_ADMIN", _))
+        ));
+        assert!(check_role_change(&admin, Some(&user(other, Role::Admin)), Role::Admin, false, 0).is_err());
+        assert!(check_role_change(&admin, Some(&user(other, Role::Admin)), Role::Manager, true, 1).is_ok());
+    }
+
     #[test]
     fn test_validate_password() {
         assert!(validate_password("password123").is_ok());
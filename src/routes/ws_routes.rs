@@ -0,0 +1,192 @@
+// src/routes/ws_routes.rs
+//
+// Realtime channel for the desktop client, staff only:
+// - GET /ws                  WebSocket (the WebView can't send Authorization on it,
+//                            and a token in the URL would end up in access logs,
+//                            so the first message signs in)
+// - GET /presence            the same presence snapshot, for polling clients
+//
+// Client -> server (JSON text frames):
+//   {"type":"hello","access_token":"...","view":"day:2026-10-16"}   within 10s
+//   {"type":"view","view":"queue"}            schedule view now open; null = none
+// Server -> client:
+//   {"type":"presence","data":[PresenceDto]}  after hello, then on every change
+//   {"type":"error","error":{code,message}}   bad message; after hello fails or
+//                                             the session ends, the socket closes
+// The session is re-checked every minute, so a logout or revoke closes the socket.
+// Presence itself lives in crate::presence.
+
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        State,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code},
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    error::{ApiError, ErrorObject},
+    extract::Json,
+    middleware::auth_context::{self, AuthContext},
+    models::{ApiOk, AppState},
+    presence::PresenceDto,
+};
+
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+const SESSION_RECHECK: Duration = Duration::from_secs(60);
+const MAX_MESSAGE_BYTES: usize = 16 * 1024;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/ws", get(connect))
+        .route("/presence", get(list_presence))
+}
+
+fn ensure_staff(auth: &AuthContext) -> Result<(), ApiError> {
+    if auth.role.is_staff() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("FORBIDDEN", "Staff only".into()))
+    }
+}
+
+pub async fn list_presence(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<ApiOk<Vec<PresenceDto>>>, ApiError> {
+    ensure_staff(&auth)?;
+    Ok(Json(ApiOk { data: state.presence.snapshot() }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Hello { access_token: String, view: Option<String> },
+    View { view: Option<String> },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Presence { data: Vec<PresenceDto> },
+    Error { error: ErrorObject },
+}
+
+pub async fn connect(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.max_message_size(MAX_MESSAGE_BYTES).on_upgrade(move |socket| run(state, socket))
+}
+
+async fn run(state: AppState, mut socket: WebSocket) {
+    let (token, auth, view) = match hello(&state, &mut socket).await {
+        Ok(signed_in) => signed_in,
+        Err(e) => return close_with(socket, e).await,
+    };
+    let display_name = match display_name(&state, &auth).await {
+        Ok(name) => name,
+        Err(e) => return close_with(socket, e).await,
+    };
+
+    let mut changes = state.presence.subscribe();
+    let connection_id = state.presence.join(auth.user_id, display_name, auth.role, view);
+    let mut recheck = tokio::time::interval(SESSION_RECHECK);
+    recheck.tick().await;
+
+    let ended = loop {
+        tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(text.as_str()) {
+                    Ok(ClientMessage::View { view }) => state.presence.set_view(connection_id, view),
+                    Ok(ClientMessage::Hello { .. }) => {}
+                    Err(e) => {
+                        let e = ApiError::BadRequest("VALIDATION_ERROR", format!("invalid message: {e}"));
+                        if send_error(&mut socket, &e).await.is_err() {
+                            break None;
+                        }
+                    }
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break None,
+                // pings are answered by the socket itself
+                Some(Ok(_)) => {}
+            },
+            changed = changes.recv() => match changed {
+                Ok(()) | Err(RecvError::Lagged(_)) => {
+                    // changes that piled up meanwhile are all in this snapshot
+                    changes = changes.resubscribe();
+                    if send_snapshot(&state, &mut socket).await.is_err() {
+                        break None;
+                    }
+                }
+                Err(RecvError::Closed) => break None,
+            },
+            _ = recheck.tick() => {
+                if let Err(e) = auth_context::authenticate(&state, &token).await {
+                    break Some(e);
+                }
+            }
+        }
+    };
+
+    state.presence.leave(connection_id);
+    if let Some(e) = ended {
+        close_with(socket, e).await;
+    }
+}
+
+/// Waits for the hello and signs it in (the first snapshot follows the join).
+async fn hello(state: &AppState, socket: &mut WebSocket) -> Result<(String, AuthContext, Option<String>), ApiError> {
+    let expected = || ApiError::BadRequest("VALIDATION_ERROR", "first message must be a hello".into());
+    let text = match tokio::time::timeout(HELLO_TIMEOUT, socket.recv()).await {
+        Ok(Some(Ok(Message::Text(text)))) => text,
+        Ok(_) => return Err(expected()),
+        Err(_) => return Err(ApiError::Unauthorized("UNAUTHORIZED", "no hello within 10s".into())),
+    };
+    let Ok(ClientMessage::Hello { access_token, view }) = serde_json::from_str(text.as_str()) else {
+        return Err(expected());
+    };
+    let auth = auth_context::authenticate(state, &access_token).await?;
+    ensure_staff(&auth)?;
+    Ok((access_token, auth, view))
+}
+
+async fn display_name(state: &AppState, auth: &AuthContext) -> Result<String, ApiError> {
+    Ok(sqlx::query_scalar(r#"SELECT display_name FROM "dcms_user" WHERE user_id = $1"#)
+        .bind(auth.user_id)
+        .fetch_one(&state.db)
+        .await?)
+}
+
+async fn send(socket: &mut WebSocket, msg: &ServerMessage) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(msg).unwrap_or_default();
+    socket.send(Message::Text(json.into())).await
+}
+
+async fn send_snapshot(state: &AppState, socket: &mut WebSocket) -> Result<(), axum::Error> {
+    send(socket, &ServerMessage::Presence { data: state.presence.snapshot() }).await
+}
+
+async fn send_error(socket: &mut WebSocket, e: &ApiError) -> Result<(), axum::Error> {
+    match e.to_error_object() {
+        Some(error) => send(socket, &ServerMessage::Error { error }).await,
+        None => Ok(()),
+    }
+}
+
+/// Reports `e` (internal errors only go to the log) and closes the socket.
+async fn close_with(mut socket: WebSocket, e: ApiError) {
+    let code = match &e {
+        ApiError::Internal(msg) => {
+            tracing::error!("ws: {msg}");
+            close_code::ERROR
+        }
+        _ => close_code::POLICY,
+    };
+    let _ = send_error(&mut socket, &e).await;
+    let frame = CloseFrame { code, reason: "".into() };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
@@ -0,0 +1,1395 @@
+// src/services/appointments.rs
+//
+// Appointments: schedule blocks, the waiting-room queue, booking (closures, overlap
+// policy, plan items, the first-visit checklist), status milestones and the note
+// timeline.
+// Used by routes::appointment_routes; the role gates (who may manage / which doctor
+// a user may see) stay there. Reads go through repos::AppointmentRepo; the
+// transactional writes (booking, PATCH, plan items, notes) run on a PgConnection.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    employee_services,
+    error::ApiError,
+    locations,
+    middleware::auth_context::AuthContext,
+    models::AppointmentStatus,
+    onboarding,
+    overlap_policy::{self, OverlapOutcome},
+    repos::AppointmentRepo,
+    services::procedure_templates::FilledProcedure,
+};
+
+/* ============================================================
+   DTOs
+   ============================================================ */
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonBrief {
+    pub id: Uuid,
+    pub display: String,
+    pub number: Option<i64>,
+    pub photo_url: Option<String>,
+    /// patients only: 0..100, see jobs::no_show_risk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_show_risk: Option<i16>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppointmentPlanItemDto {
+    pub service_id: Uuid,
+    pub display_name: String,
+    pub qty: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppointmentBlockDto {
+    pub appointment_id: Uuid,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub status: AppointmentStatus,
+    pub priority: i16,
+    pub color_override: Option<i32>,
+    pub note: Option<String>,
+
+    // Phase-1 add-ons (from migrations 014; if you didn't add confirmed/reminder columns, remove them)
+    pub source: String,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub reminder_sent_at: Option<DateTime<Utc>>,
+
+    pub location_id: Option<Uuid>,
+
+    pub patient: PersonBrief,
+    pub doctor: PersonBrief,
+
+    pub planned_items: Vec<AppointmentPlanItemDto>,
+    pub planned_summary: String,
+
+    /// high no-show risk patient, not confirmed yet: reception should call
+    pub needs_double_confirm: bool,
+}
+
+/// Schedule views with `?detail=summary`: enough to draw the block; the client loads
+/// the full block (GET /appointments/{id}) when it is opened.
+#[derive(Debug, Serialize)]
+pub struct AppointmentBlockSummaryDto {
+    pub appointment_id: Uuid,
+    pub patient_id: Uuid,
+    pub doctor_employee_id: Uuid,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub status: AppointmentStatus,
+    pub patient_display: String,
+    pub planned_summary: String,
+}
+
+impl From<AppointmentBlockDto> for AppointmentBlockSummaryDto {
+    fn from(b: AppointmentBlockDto) -> Self {
+        Self {
+            appointment_id: b.appointment_id,
+            patient_id: b.patient.id,
+            doctor_employee_id: b.doctor.id,
+            start_at: b.start_at,
+            end_at: b.end_at,
+            status: b.status,
+            patient_display: b.patient.display,
+            planned_summary: b.planned_summary,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueEntryDto {
+    pub appointment_id: Uuid,
+    /// "scheduled" | "arrived" | "seated" | "dismissed" (derived from timestamps)
+    pub queue_state: &'static str,
+    pub status: AppointmentStatus,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub arrived_at: Option<DateTime<Utc>>,
+    pub seated_at: Option<DateTime<Utc>>,
+    pub dismissed_at: Option<DateTime<Utc>>,
+    /// Minutes since arrival (still waiting) or minutes waited before being seated/dismissed.
+    pub wait_minutes: Option<i64>,
+    pub patient: PersonBrief,
+    pub doctor: PersonBrief,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AppointmentNoteDto {
+    pub appointment_note_id: Uuid,
+    pub appointment_id: Uuid,
+    pub author_user_id: Option<Uuid>,
+    pub author_name: Option<String>,
+    pub note_text: String,
+    pub is_pinned: bool,
+    pub created_at: DateTime<Utc>,
+    /// set on procedure notes (filled from a procedure template)
+    pub procedure_template_id: Option<Uuid>,
+    pub procedure_fields: Option<JsonValue>,
+}
+
+/// One changed field (GET /appointments/{id}/history); values are the column's JSON,
+/// with status codes as names.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AppointmentChangeDto {
+    pub changed_at: DateTime<Utc>,
+    /// column name, or "created" (new_value = start_at, end_at, doctor_employee_id, status)
+    pub field: String,
+    pub old_value: Option<JsonValue>,
+    pub new_value: Option<JsonValue>,
+    /// None = system job, or the user was deleted
+    pub changed_by_user_id: Option<Uuid>,
+    pub changed_by_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePlanItem {
+    pub service_id: Uuid,
+    pub qty: Option<i32>,
+    pub note: Option<String>,
+}
+
+/* ============================================================
+   Validation rules
+   ============================================================ */
+
+pub const MAX_BATCH_IDS: usize = 100;
+
+/// `ids=a,b,c` of the batch GET: 1..MAX_BATCH_IDS distinct UUIDs, in request order.
+pub fn parse_id_list(raw: &str) -> Result<Vec<Uuid>, ApiError> {
+    let mut ids: Vec<Uuid> = Vec::new();
+    for part in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let id = Uuid::parse_str(part)
+            .map_err(|_| ApiError::BadRequest("VALIDATION_ERROR", format!("ids: {part} is not a UUID")))?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.is_empty() || ids.len() > MAX_BATCH_IDS {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("ids must list 1..{MAX_BATCH_IDS} appointment ids"),
+        ));
+    }
+    Ok(ids)
+}
+
+/// Booking channels (appointment.source).
+pub const SOURCES: [&str; 3] = ["SCHEDULED", "WALKIN", "WAITLIST"];
+
+pub fn normalize_source(s: Option<String>) -> Result<String, ApiError> {
+    let v = s.unwrap_or_else(|| "SCHEDULED".to_string());
+    let up = v.trim().to_uppercase();
+    if SOURCES.contains(&up.as_str()) {
+        Ok(up)
+    } else {
+        Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "source must be SCHEDULED, WALKIN, or WAITLIST".into(),
+        ))
+    }
+}
+
+/// 0 normal, 1 asap
+pub fn validate_priority(priority: i16) -> Result<(), ApiError> {
+    if priority != 0 && priority != 1 {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "priority must be 0 or 1".into()));
+    }
+    Ok(())
+}
+
+pub fn queue_state(
+    arrived_at: Option<DateTime<Utc>>,
+    seated_at: Option<DateTime<Utc>>,
+    dismissed_at: Option<DateTime<Utc>>,
+) -> &'static str {
+    if dismissed_at.is_some() {
+        "dismissed"
+    } else if seated_at.is_some() {
+        "seated"
+    } else if arrived_at.is_some() {
+        "arrived"
+    } else {
+        "scheduled"
+    }
+}
+
+/// "Cleaning + Filling×2", or "(no planned items)"
+pub fn planned_summary(items: &[AppointmentPlanItemDto]) -> String {
+    if items.is_empty() {
+        return "(no planned items)".into();
+    }
+    items
+        .iter()
+        .map(|it| {
+            if it.qty <= 1 {
+                it.display_name.clone()
+            } else {
+                format!("{}×{}", it.display_name, it.qty)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/* ============================================================
+   Reads
+   ============================================================ */
+
+pub async fn doctor_employee_id_for_user(repo: &dyn AppointmentRepo, user_id: Uuid) -> Result<Uuid, ApiError> {
+    repo.employee_id_for_user(user_id)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest("NO_EMPLOYEE_PROFILE", "Doctor account has no employee profile".into()))
+}
+
+fn appointment_not_found() -> ApiError {
+    ApiError::NotFound("NOT_FOUND", "appointment not found".into())
+}
+
+/// Doctor of the appointment; NOT_FOUND when it doesn't exist.
+pub async fn doctor_of(repo: &dyn AppointmentRepo, appointment_id: Uuid) -> Result<Uuid, ApiError> {
+    repo.doctor_of(appointment_id).await?.ok_or_else(appointment_not_found)
+}
+
+/// A doctor's blocks starting in [start_ts, end_ts). `locations`: None = all.
+pub async fn blocks_in_range(
+    repo: &dyn AppointmentRepo,
+    doctor_employee_id: Uuid,
+    start_ts: DateTime<Utc>,
+    end_ts: DateTime<Utc>,
+    locations: Option<&[Uuid]>,
+) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+    repo.blocks_in_range(doctor_employee_id, start_ts, end_ts, locations).await
+}
+
+/// Order of the overdue list (`?sort=`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverdueSort {
+    /// oldest first
+    #[default]
+    StartAt,
+    /// newest first
+    StartAtDesc,
+    /// ASAP first, then oldest
+    Priority,
+}
+
+/// Past appointments of one doctor still scheduled (optionally also no-shows)
+/// that started in [since, now).
+#[derive(Debug)]
+pub struct OverdueFilter {
+    pub doctor_employee_id: Uuid,
+    pub since: DateTime<Utc>,
+    pub now: DateTime<Utc>,
+    /// None = all
+    pub locations: Option<Vec<Uuid>>,
+    pub include_no_show: bool,
+    pub sort: OverdueSort,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Whole-range counts (ignoring limit/offset) for the UI badge.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OverdueCounts {
+    pub scheduled: i64,
+    pub no_show: i64,
+}
+
+impl OverdueCounts {
+    pub fn total(&self, include_no_show: bool) -> i64 {
+        self.scheduled + if include_no_show { self.no_show } else { 0 }
+    }
+}
+
+/// One page of the overdue list plus the counts over the whole range.
+pub async fn overdue(
+    repo: &dyn AppointmentRepo,
+    filter: &OverdueFilter,
+) -> Result<(Vec<AppointmentBlockDto>, OverdueCounts), ApiError> {
+    let page = repo.overdue(filter).await?;
+    let counts = repo.overdue_counts(filter).await?;
+    Ok((page, counts))
+}
+
+/// The block; NOT_FOUND when the appointment doesn't exist.
+pub async fn get_block(repo: &dyn AppointmentRepo, appointment_id: Uuid) -> Result<AppointmentBlockDto, ApiError> {
+    repo.get_block(appointment_id).await?.ok_or_else(appointment_not_found)
+}
+
+/// Unknown ids are left out.
+pub async fn blocks_by_ids(
+    repo: &dyn AppointmentRepo,
+    appointment_ids: &[Uuid],
+) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+    repo.blocks_by_ids(appointment_ids).await
+}
+
+/// One keyset page (by appointment_id) for the HQ sync stream; `ids` None = every appointment.
+#[cfg_attr(not(feature = "hq-grpc"), allow(dead_code))]
+pub async fn blocks_after(
+    repo: &dyn AppointmentRepo,
+    after: Option<Uuid>,
+    ids: Option<&[Uuid]>,
+    limit: i64,
+) -> Result<Vec<AppointmentBlockDto>, ApiError> {
+    repo.blocks_after(after, ids, limit).await
+}
+
+/// Minutes since arrival, until seated (or dismissed without being seated); None before arrival.
+pub fn wait_minutes(
+    arrived_at: Option<DateTime<Utc>>,
+    seated_at: Option<DateTime<Utc>>,
+    dismissed_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<i64> {
+    let wait_end = seated_at.or(dismissed_at).unwrap_or(now);
+    arrived_at.map(|a| (wait_end - a).num_minutes().max(0))
+}
+
+/// Waiting room for [start_ts, end_ts): waiting first, then seated, then dismissed.
+/// Canceled appointments never show up.
+pub async fn queue(
+    repo: &dyn AppointmentRepo,
+    start_ts: DateTime<Utc>,
+    end_ts: DateTime<Utc>,
+    doctor_employee_id: Option<Uuid>,
+    locations: Option<&[Uuid]>,
+) -> Result<Vec<QueueEntryDto>, ApiError> {
+    let mut out = repo.queue(start_ts, end_ts, doctor_employee_id, locations).await?;
+    let now = Utc::now();
+    for e in &mut out {
+        e.wait_minutes = wait_minutes(e.arrived_at, e.seated_at, e.dismissed_at, now);
+    }
+    Ok(out)
+}
+
+/* ============================================================
+   Booking
+   ============================================================ */
+
+/// Blocks booking on clinic closure dates (clinic_holiday.is_closed), checked on
+/// the clinic-local dates the appointment touches. Admin/manager may override.
+pub async fn ensure_clinic_open(
+    repo: &dyn AppointmentRepo,
+    auth: &AuthContext,
+    start_at: DateTime<Utc>,
+    end_at: DateTime<Utc>,
+    override_closure: bool,
+) -> Result<(), ApiError> {
+    if override_closure && auth.role.is_admin_or_manager() {
+        return Ok(());
+    }
+
+    let tz = repo.clinic_tz().await?;
+    let first_day = start_at.with_timezone(&tz).date_naive();
+    let last_day = (end_at - chrono::Duration::seconds(1)).with_timezone(&tz).date_naive();
+
+    match repo.first_closure(first_day, last_day).await? {
+        Some((date, name)) => Err(ApiError::Conflict(
+            "CLINIC_CLOSED",
+            format!("clinic is closed on {date} ({name})"),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Blocks booking while the doctor has approved time off overlapping the range.
+/// Admin/manager may override (same flag as closures).
+pub async fn ensure_doctor_not_off(
+    repo: &dyn AppointmentRepo,
+    auth: &AuthContext,
+    doctor_employee_id: Uuid,
+    start_at: DateTime<Utc>,
+    end_at: DateTime<Utc>,
+    override_closure: bool,
+) -> Result<(), ApiError> {
+    if override_closure && auth.role.is_admin_or_manager() {
+        return Ok(());
+    }
+
+    match repo.first_time_off(doctor_employee_id, start_at, end_at).await? {
+        Some((off_start, off_end)) => {
+            let tz = repo.clinic_tz().await?;
+            let fmt = |t: DateTime<Utc>| t.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string();
+            Err(ApiError::Conflict(
+                "DOCTOR_TIME_OFF",
+                format!("doctor is off from {} to {}", fmt(off_start), fmt(off_end)),
+            ))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Checks an appointment's assistant: an active employee whose login (if any)
+/// may assist, not assisting another appointment at the same time, and on
+/// shift for the whole range (see staff_shift). Admin/manager may override the
+/// shift rule only (same flag as closures). `exclude` = the appointment itself.
+pub async fn ensure_assistant_available(
+    repo: &dyn AppointmentRepo,
+    auth: &AuthContext,
+    assistant_employee_id: Option<Uuid>,
+    start_at: DateTime<Utc>,
+    end_at: DateTime<Utc>,
+    exclude: Option<Uuid>,
+    override_closure: bool,
+) -> Result<(), ApiError> {
+    let Some(assistant_employee_id) = assistant_employee_id else {
+        return Ok(());
+    };
+
+    match repo.assistant_profile(assistant_employee_id).await? {
+        None => return Err(ApiError::NotFound("NOT_FOUND", "assistant not found".into())),
+        Some((_, false)) => {
+            return Err(ApiError::BadRequest("VALIDATION_ERROR", "the assistant is not an active employee".into()));
+        }
+        Some((Some(role), true)) if !role.can_assist() => {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                format!("a {role} can't be assigned as assistant"),
+            ));
+        }
+        Some(_) => {}
+    }
+
+    if let Some((other, other_start, other_end)) =
+        repo.assistant_conflict(assistant_employee_id, start_at, end_at, exclude).await?
+    {
+        let tz = repo.clinic_tz().await?;
+        let fmt = |t: DateTime<Utc>| t.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string();
+        return Err(ApiError::Conflict(
+            "ASSISTANT_DOUBLE_BOOKED",
+            format!("the assistant is on appointment {other} from {} to {}", fmt(other_start), fmt(other_end)),
+        ));
+    }
+
+    if override_closure && auth.role.is_admin_or_manager() {
+        return Ok(());
+    }
+    if repo.on_shift(assistant_employee_id, start_at, end_at).await? {
+        Ok(())
+    } else {
+        Err(ApiError::Conflict(
+            "ASSISTANT_NOT_ON_SHIFT",
+            "the assistant has no shift covering this time".into(),
+        ))
+    }
+}
+
+/// `end_at` as given, else start + the planned services' duration (rounded to the slot size).
+pub async fn resolve_end_at(
+    db: &PgPool,
+    doctor_employee_id: Uuid,
+    start_at: DateTime<Utc>,
+    end_at: Option<DateTime<Utc>>,
+    planned_items: Option<&[CreatePlanItem]>,
+) -> Result<DateTime<Utc>, ApiError> {
+    let end_at = match (end_at, planned_items) {
+        (Some(end_at), _) => end_at,
+        (None, Some(items)) if !items.is_empty() => {
+            let items: Vec<(Uuid, i32)> = items.iter().map(|it| (it.service_id, it.qty.unwrap_or(1))).collect();
+            let minutes = employee_services::planned_duration_min(db, doctor_employee_id, &items).await?;
+            start_at + chrono::Duration::minutes(minutes)
+        }
+        (None, _) => {
+            return Err(ApiError::BadRequest(
+                "VALIDATION_ERROR",
+                "end_at is required unless planned_items are given".into(),
+            ))
+        }
+    };
+    if end_at <= start_at {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "end_at must be > start_at".into()));
+    }
+    Ok(end_at)
+}
+
+/// Back-to-back slots from `start_at`: each member's slot is `duration_min` long,
+/// else as long as its planned services.
+pub async fn consecutive_slots(
+    db: &PgPool,
+    doctor_employee_id: Uuid,
+    start_at: DateTime<Utc>,
+    members: &[(Option<i64>, Option<&[CreatePlanItem]>)],
+) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>, ApiError> {
+    let mut slots = Vec::with_capacity(members.len());
+    let mut start_at = start_at;
+    for (duration_min, planned_items) in members {
+        let end_at = match *duration_min {
+            Some(min) if min > 0 => start_at + chrono::Duration::minutes(min),
+            Some(_) => {
+                return Err(ApiError::BadRequest("VALIDATION_ERROR", "duration_min must be > 0".into()));
+            }
+            None => resolve_end_at(db, doctor_employee_id, start_at, None, *planned_items).await?,
+        };
+        slots.push((start_at, end_at));
+        start_at = end_at;
+    }
+    Ok(slots)
+}
+
+/// Requested location, else the doctor's home location; access-checked.
+pub async fn resolve_location(
+    db: &PgPool,
+    auth: &AuthContext,
+    doctor_employee_id: Uuid,
+    requested: Option<Uuid>,
+) -> Result<Option<Uuid>, ApiError> {
+    let location_id = match requested {
+        Some(id) => Some(id),
+        None => sqlx::query_scalar::<_, Option<Uuid>>("SELECT location_id FROM employee WHERE employee_id = $1")
+            .bind(doctor_employee_id)
+            .fetch_optional(db)
+            .await?
+            .flatten(),
+    };
+    if let Some(id) = location_id {
+        locations::ensure_location_access(db, auth, id).await?;
+    }
+    Ok(location_id)
+}
+
+/// Every patient must be a member of the household.
+pub async fn ensure_household_members(db: &PgPool, household_id: Uuid, patient_ids: &[Uuid]) -> Result<(), ApiError> {
+    let household_exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM household WHERE household_id = $1)")
+            .bind(household_id)
+            .fetch_one(db)
+            .await?;
+    if !household_exists {
+        return Err(ApiError::NotFound("NOT_FOUND", "household not found".into()));
+    }
+    let in_household: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT patient_id FROM household_member WHERE household_id = $1 AND patient_id = ANY($2)",
+    )
+    .bind(household_id)
+    .bind(patient_ids)
+    .fetch_all(db)
+    .await?;
+    if let Some(outsider) = patient_ids.iter().find(|id| !in_household.contains(id)) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("patient {outsider} is not a member of this household"),
+        ));
+    }
+    Ok(())
+}
+
+/// A validated booking: end, location and source already resolved.
+pub struct NewAppointment {
+    pub patient_id: Uuid,
+    pub doctor_employee_id: Uuid,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub assistant_employee_id: Option<Uuid>,
+    pub receptionist_employee_id: Option<Uuid>,
+    pub note: Option<String>,
+    pub priority: i16,
+    pub is_new_patient: bool,
+    pub planned_items: Option<Vec<CreatePlanItem>>,
+    pub source: String,
+    pub location_id: Option<Uuid>,
+    pub override_overlap: bool,
+}
+
+/// Inserts one appointment (+ plan items and the pinned note) on `conn`, which is
+/// inside the caller's transaction; the overlap check also sees rows the same
+/// transaction booked before (family booking).
+pub async fn insert(
+    conn: &mut PgConnection,
+    auth: &AuthContext,
+    new: NewAppointment,
+) -> Result<(Uuid, OverlapOutcome), ApiError> {
+    let overlap = overlap_policy::check(
+        conn,
+        auth,
+        new.doctor_employee_id,
+        new.start_at,
+        new.end_at,
+        None,
+        new.override_overlap,
+    )
+    .await?;
+
+    let appointment_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO appointment (
+          patient_id,
+          doctor_employee_id,
+          receptionist_employee_id,
+          assistant_employee_id,
+          start_at,
+          end_at,
+          status,
+          is_new_patient,
+          priority,
+          note,
+          source,
+          created_by_user_id,
+          updated_by_user_id,
+          location_id,
+          overlap_allowed
+        )
+        VALUES ($1,$2,$3,$4,$5,$6, 0, $7, $8, $9, $10, $11, $11, $12, $13)
+        RETURNING appointment_id
+        "#,
+    )
+    .bind(new.patient_id)
+    .bind(new.doctor_employee_id)
+    .bind(new.receptionist_employee_id)
+    .bind(new.assistant_employee_id)
+    .bind(new.start_at)
+    .bind(new.end_at)
+    .bind(new.is_new_patient)
+    .bind(new.priority)
+    .bind(new.note.as_deref())
+    .bind(&new.source)
+    .bind(auth.user_id)
+    .bind(new.location_id)
+    .bind(overlap.overlap_allowed)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(ApiError::write_failed("APPOINTMENT_CREATE_FAILED"))?;
+
+    overlap_policy::record_override(conn, auth, appointment_id, &overlap).await?;
+
+    if new.is_new_patient {
+        onboarding::attach(conn, appointment_id).await?;
+    }
+
+    if let Some(items) = new.planned_items {
+        insert_plan_items(conn, appointment_id, new.doctor_employee_id, items).await?;
+    }
+
+    if let Some(text) = new.note.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        add_note(conn, appointment_id, auth.user_id, text, true).await?;
+    }
+
+    Ok((appointment_id, overlap))
+}
+
+async fn insert_plan_items(
+    conn: &mut PgConnection,
+    appointment_id: Uuid,
+    doctor_employee_id: Uuid,
+    items: Vec<CreatePlanItem>,
+) -> Result<(), ApiError> {
+    let service_ids: Vec<Uuid> = items.iter().map(|it| it.service_id).collect();
+    employee_services::ensure_can_perform(&mut *conn, doctor_employee_id, &service_ids).await?;
+
+    for it in items {
+        let qty = it.qty.unwrap_or(1);
+        if qty <= 0 {
+            return Err(ApiError::BadRequest("VALIDATION_ERROR", "qty must be > 0".into()));
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO appointment_plan_item (appointment_id, service_id, qty, note)
+            VALUES ($1,$2,$3,$4)
+            "#,
+        )
+        .bind(appointment_id)
+        .bind(it.service_id)
+        .bind(qty)
+        .bind(it.note)
+        .execute(&mut *conn)
+        .await
+        .map_err(ApiError::write_failed("PLAN_ITEM_CREATE_FAILED"))?;
+    }
+    Ok(())
+}
+
+/// Replaces all plan items (the doctor must perform every service).
+pub async fn replace_plan_items(
+    db: &PgPool,
+    auth: &AuthContext,
+    appointment_id: Uuid,
+    items: Vec<CreatePlanItem>,
+) -> Result<(), ApiError> {
+    let mut tx = db.begin().await?;
+
+    let doctor_employee_id: Uuid =
+        sqlx::query_scalar("SELECT doctor_employee_id FROM appointment WHERE appointment_id = $1")
+            .bind(appointment_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "appointment not found".into()))?;
+
+    sqlx::query("DELETE FROM appointment_plan_item WHERE appointment_id = $1")
+        .bind(appointment_id)
+        .execute(&mut *tx)
+        .await?;
+
+    insert_plan_items(&mut tx, appointment_id, doctor_employee_id, items).await?;
+
+    sqlx::query(
+        r#"
+        UPDATE appointment
+        SET updated_at = now(), updated_by_user_id = $2
+        WHERE appointment_id = $1
+        "#,
+    )
+    .bind(appointment_id)
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/* ============================================================
+   Updates
+   ============================================================ */
+
+#[derive(Debug, Deserialize)]
+pub struct AppointmentPatch {
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub status: Option<AppointmentStatus>,
+    pub priority: Option<i16>,
+    pub assistant_employee_id: Option<Option<Uuid>>,
+    pub receptionist_employee_id: Option<Option<Uuid>>,
+    pub note: Option<Option<String>>,
+    pub color_override: Option<Option<i32>>,
+
+    // Phase-1 add-ons
+    pub source: Option<String>,
+    pub confirmed_at: Option<Option<DateTime<Utc>>>,
+    pub reminder_sent_at: Option<Option<DateTime<Utc>>>,
+
+    /// admin/manager only: reschedule onto a closure date or the doctor's time off, or
+    /// assign an assistant who is off shift, anyway
+    pub override_closure: Option<bool>,
+
+    /// admin/manager only: reschedule past the doctor's overlap policy (audited)
+    pub override_overlap: Option<bool>,
+
+    pub location_id: Option<Uuid>,
+}
+
+/// Applies a PATCH: closure and overlap rules for the resulting time range, and a
+/// note set here lands on the timeline as the new pinned note.
+pub async fn update(
+    db: &PgPool,
+    repo: &dyn AppointmentRepo,
+    auth: &AuthContext,
+    appointment_id: Uuid,
+    patch: AppointmentPatch,
+) -> Result<OverlapOutcome, ApiError> {
+    let source = check_update(db, repo, auth, appointment_id, &patch).await?;
+    let mut tx = db.begin().await?;
+    let overlap = apply_update(&mut tx, auth, appointment_id, patch, source).await?;
+    tx.commit().await?;
+    Ok(overlap)
+}
+
+/// `update` on `conn`, inside the caller's transaction (POST /sync/push); the
+/// closure / time-off / assistant checks still read through `db` and `repo`.
+pub async fn update_in(
+    conn: &mut PgConnection,
+    db: &PgPool,
+    repo: &dyn AppointmentRepo,
+    auth: &AuthContext,
+    appointment_id: Uuid,
+    patch: AppointmentPatch,
+) -> Result<OverlapOutcome, ApiError> {
+    let source = check_update(db, repo, auth, appointment_id, &patch).await?;
+    apply_update(conn, auth, appointment_id, patch, source).await
+}
+
+/// The checks that don't need the row lock; returns the normalized source.
+async fn check_update(
+    db: &PgPool,
+    repo: &dyn AppointmentRepo,
+    auth: &AuthContext,
+    appointment_id: Uuid,
+    patch: &AppointmentPatch,
+) -> Result<Option<String>, ApiError> {
+    if let Some(p) = patch.priority {
+        validate_priority(p)?;
+    }
+
+    let source = match patch.source.clone() {
+        Some(s) => Some(normalize_source(Some(s))?),
+        None => None,
+    };
+
+    if let Some(id) = patch.location_id {
+        locations::ensure_location_access(db, auth, id).await?;
+    }
+
+    // rescheduling: the new time range must not fall on a closure date or the doctor's
+    // time off; the assistant (new or kept) must be on shift for it
+    let rescheduled = patch.start_at.is_some() || patch.end_at.is_some();
+    let assistant_set = matches!(patch.assistant_employee_id, Some(Some(_)));
+    if rescheduled || assistant_set {
+        let cur = sqlx::query_as::<_, (Uuid, Option<Uuid>, DateTime<Utc>, DateTime<Utc>)>(
+            "SELECT doctor_employee_id, assistant_employee_id, start_at, end_at FROM appointment WHERE appointment_id = $1",
+        )
+        .bind(appointment_id)
+        .fetch_optional(db)
+        .await?;
+        let Some((doctor_employee_id, cur_assistant, cur_start, cur_end)) = cur else {
+            return Err(ApiError::NotFound("NOT_FOUND", "appointment not found".into()));
+        };
+        let (start_at, end_at) = (patch.start_at.unwrap_or(cur_start), patch.end_at.unwrap_or(cur_end));
+        let assistant = patch.assistant_employee_id.unwrap_or(cur_assistant);
+        if end_at > start_at {
+            let override_closure = patch.override_closure.unwrap_or(false);
+            if rescheduled {
+                ensure_clinic_open(repo, auth, start_at, end_at, override_closure).await?;
+                ensure_doctor_not_off(repo, auth, doctor_employee_id, start_at, end_at, override_closure).await?;
+            }
+            ensure_assistant_available(
+                repo,
+                auth,
+                assistant,
+                start_at,
+                end_at,
+                Some(appointment_id),
+                override_closure,
+            )
+            .await?;
+        }
+    }
+
+    Ok(source)
+}
+
+async fn apply_update(
+    conn: &mut PgConnection,
+    auth: &AuthContext,
+    appointment_id: Uuid,
+    patch: AppointmentPatch,
+    source: Option<String>,
+) -> Result<OverlapOutcome, ApiError> {
+    let note_text = patch.note.clone().flatten();
+
+    // time or status change: re-check the doctor's overlap policy for the result
+    let mut overlap = OverlapOutcome::default();
+    let mut overlap_allowed: Option<bool> = None;
+    if patch.start_at.is_some() || patch.end_at.is_some() || patch.status.is_some() {
+        let cur: Option<(Uuid, DateTime<Utc>, DateTime<Utc>, AppointmentStatus)> = sqlx::query_as(
+            "SELECT doctor_employee_id, start_at, end_at, status FROM appointment WHERE appointment_id = $1 FOR UPDATE",
+        )
+        .bind(appointment_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some((doctor_employee_id, cur_start, cur_end, cur_status)) = cur else {
+            return Err(ApiError::NotFound("NOT_FOUND", "appointment not found".into()));
+        };
+        if let Some(next) = patch.status {
+            cur_status.ensure_transition(next)?;
+        }
+        let (start_at, end_at) = (patch.start_at.unwrap_or(cur_start), patch.end_at.unwrap_or(cur_end));
+        let status = patch.status.unwrap_or(cur_status);
+        if end_at > start_at && status.occupies_slot() {
+            overlap = overlap_policy::check(
+                &mut *conn,
+                auth,
+                doctor_employee_id,
+                start_at,
+                end_at,
+                Some(appointment_id),
+                patch.override_overlap.unwrap_or(false),
+            )
+            .await?;
+            overlap_allowed = Some(overlap.overlap_allowed);
+        }
+    }
+
+    let row: Option<(DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        UPDATE appointment
+        SET
+          start_at = COALESCE($2, start_at),
+          end_at   = COALESCE($3, end_at),
+          status   = COALESCE($4, status),
+          priority = COALESCE($5, priority),
+          assistant_employee_id    = COALESCE($6, assistant_employee_id),
+          receptionist_employee_id = COALESCE($7, receptionist_employee_id),
+          note           = COALESCE($8, note),
+          color_override = COALESCE($9, color_override),
+          source         = COALESCE($10, source),
+          confirmed_at       = COALESCE($11, confirmed_at),
+          reminder_sent_at   = COALESCE($12, reminder_sent_at),
+          location_id        = COALESCE($14, location_id),
+          overlap_allowed    = COALESCE($15, overlap_allowed),
+          canceled_at = CASE
+            WHEN $4 = $16 THEN COALESCE(canceled_at, now())
+            WHEN $4 IS NOT NULL THEN NULL
+            ELSE canceled_at
+          END,
+          updated_at = now(),
+          updated_by_user_id = $13
+        WHERE appointment_id = $1
+        RETURNING start_at, end_at
+        "#,
+    )
+    .bind(appointment_id)
+    .bind(patch.start_at)
+    .bind(patch.end_at)
+    .bind(patch.status)
+    .bind(patch.priority)
+    .bind(patch.assistant_employee_id.unwrap_or(None))
+    .bind(patch.receptionist_employee_id.unwrap_or(None))
+    .bind(patch.note.unwrap_or(None))
+    .bind(patch.color_override.unwrap_or(None))
+    .bind(source)
+    .bind(patch.confirmed_at.unwrap_or(None))
+    .bind(patch.reminder_sent_at.unwrap_or(None))
+    .bind(auth.user_id)
+    .bind(patch.location_id)
+    .bind(overlap_allowed)
+    .bind(AppointmentStatus::Canceled)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(ApiError::write_failed("APPOINTMENT_UPDATE_FAILED"))?;
+
+    let Some((start_at, end_at)) = row else {
+        return Err(ApiError::NotFound("NOT_FOUND", "appointment not found".into()));
+    };
+    if end_at <= start_at {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "end_at must be > start_at".into()));
+    }
+
+    overlap_policy::record_override(&mut *conn, auth, appointment_id, &overlap).await?;
+
+    if let Some(text) = note_text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        add_note(conn, appointment_id, auth.user_id, text, true).await?;
+    }
+
+    Ok(overlap)
+}
+
+/// Front-desk milestones; each stamps its timestamp once (repeat calls keep the first).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Milestone {
+    Arrived,
+    Seated,
+    Dismissed,
+    Confirmed,
+    ReminderSent,
+}
+
+impl Milestone {
+    /// Status the milestone moves the appointment to (seating keeps it arrived).
+    pub fn status(self) -> Option<AppointmentStatus> {
+        match self {
+            Milestone::Arrived | Milestone::Seated => Some(AppointmentStatus::Arrived),
+            Milestone::Dismissed => Some(AppointmentStatus::Finished),
+            Milestone::Confirmed | Milestone::ReminderSent => None,
+        }
+    }
+}
+
+pub async fn mark(
+    repo: &dyn AppointmentRepo,
+    auth: &AuthContext,
+    appointment_id: Uuid,
+    milestone: Milestone,
+) -> Result<(), ApiError> {
+    if let Some(next) = milestone.status() {
+        let current = repo.status_of(appointment_id).await?.ok_or_else(appointment_not_found)?;
+        current.ensure_transition(next)?;
+    }
+    if milestone == Milestone::Seated {
+        onboarding::ensure_can_seat(repo.seating_blockers(appointment_id).await?)?;
+    }
+    repo.mark(appointment_id, auth.user_id, milestone).await
+}
+
+/* ============================================================
+   Note timeline (append-only)
+   appointment.note = latest pinned note, kept for older clients
+   ============================================================ */
+
+pub async fn add_note(
+    conn: &mut PgConnection,
+    appointment_id: Uuid,
+    author_user_id: Uuid,
+    note_text: &str,
+    is_pinned: bool,
+) -> Result<(), ApiError> {
+    insert_note(conn, appointment_id, author_user_id, note_text, is_pinned, None).await
+}
+
+/// A note rendered from a procedure template, keeping the field values.
+pub async fn add_procedure_note(
+    conn: &mut PgConnection,
+    appointment_id: Uuid,
+    author_user_id: Uuid,
+    template_id: Uuid,
+    filled: &FilledProcedure,
+    is_pinned: bool,
+) -> Result<(), ApiError> {
+    let procedure = (template_id, JsonValue::Object(filled.fields.clone()));
+    insert_note(conn, appointment_id, author_user_id, &filled.note_text, is_pinned, Some(procedure)).await
+}
+
+async fn insert_note(
+    conn: &mut PgConnection,
+    appointment_id: Uuid,
+    author_user_id: Uuid,
+    note_text: &str,
+    is_pinned: bool,
+    procedure: Option<(Uuid, JsonValue)>,
+) -> Result<(), ApiError> {
+    let (template_id, fields) = procedure.unzip();
+    sqlx::query(
+        r#"
+        INSERT INTO appointment_note (appointment_id, author_user_id, note_text, is_pinned, procedure_template_id, procedure_fields)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(appointment_id)
+    .bind(author_user_id)
+    .bind(note_text)
+    .bind(is_pinned)
+    .bind(template_id)
+    .bind(fields)
+    .execute(&mut *conn)
+    .await
+    .map_err(ApiError::write_failed("APPOINTMENT_NOTE_FAILED"))?;
+
+    if is_pinned {
+        sync_legacy_note(conn, appointment_id).await?;
+    }
+    Ok(())
+}
+
+/// Only the pin flag of a note changes; NOT_FOUND for a note of another appointment.
+pub async fn set_note_pinned(
+    db: &PgPool,
+    appointment_id: Uuid,
+    appointment_note_id: Uuid,
+    is_pinned: bool,
+) -> Result<(), ApiError> {
+    let mut tx = db.begin().await?;
+
+    let res = sqlx::query(
+        r#"
+        UPDATE appointment_note
+        SET is_pinned = $3
+        WHERE appointment_note_id = $2 AND appointment_id = $1
+        "#,
+    )
+    .bind(appointment_id)
+    .bind(appointment_note_id)
+    .bind(is_pinned)
+    .execute(&mut *tx)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(ApiError::NotFound("NOT_FOUND", "note not found".into()));
+    }
+
+    sync_legacy_note(&mut tx, appointment_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// appointment.note := latest pinned note (NULL when nothing is pinned).
+async fn sync_legacy_note(conn: &mut PgConnection, appointment_id: Uuid) -> Result<(), ApiError> {
+    sqlx::query(
+        r#"
+        UPDATE appointment
+        SET note = (
+          SELECT n.note_text
+          FROM appointment_note n
+          WHERE n.appointment_id = $1 AND n.is_pinned
+          ORDER BY n.created_at DESC, n.appointment_note_id DESC
+          LIMIT 1
+        )
+        WHERE appointment_id = $1
+        "#,
+    )
+    .bind(appointment_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Oldest first.
+pub async fn notes(repo: &dyn AppointmentRepo, appointment_id: Uuid) -> Result<Vec<AppointmentNoteDto>, ApiError> {
+    repo.notes(appointment_id).await
+}
+
+/* ============================================================
+   Change history (appointment_change, trigger-fed)
+   ============================================================ */
+
+/// Oldest first.
+pub async fn history(repo: &dyn AppointmentRepo, appointment_id: Uuid) -> Result<Vec<AppointmentChangeDto>, ApiError> {
+    let mut changes = repo.history(appointment_id).await?;
+    for c in &mut changes {
+        name_statuses(c);
+    }
+    Ok(changes)
+}
+
+/// The trigger logs raw SMALLINT codes; the API speaks status names.
+fn name_statuses(change: &mut AppointmentChangeDto) {
+    fn name(v: &mut JsonValue) {
+        if let Some(s) = v.as_i64().and_then(|c| i16::try_from(c).ok()).and_then(AppointmentStatus::from_code) {
+            *v = JsonValue::from(s.as_str());
+        }
+    }
+    let values = [change.old_value.as_mut(), change.new_value.as_mut()].into_iter().flatten();
+    match change.field.as_str() {
+        "status" => values.for_each(name),
+        "created" => values.filter_map(|v| v.get_mut("status")).for_each(name),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{models::Role, repos::fake::FakeAppointmentRepo};
+
+    fn item(name: &str, qty: i32) -> AppointmentPlanItemDto {
+        AppointmentPlanItemDto { service_id: Uuid::nil(), display_name: name.into(), qty }
+    }
+
+    #[test]
+    fn summary_lists_items_with_quantities() {
+        assert_eq!(planned_summary(&[]), "(no planned items)");
+        assert_eq!(planned_summary(&[item("Cleaning", 1), item("Filling", 2)]), "Cleaning + Filling×2");
+    }
+
+    #[test]
+    fn id_list_is_deduplicated_and_bounded() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(parse_id_list(&format!("{a}, {b},{a},")).unwrap(), [a, b]);
+        assert!(parse_id_list("").is_err());
+        assert!(parse_id_list(&format!("{a},nope")).is_err());
+        let too_many = (0..=MAX_BATCH_IDS).map(|_| Uuid::new_v4().to_string()).collect::<Vec<_>>().join(",");
+        assert!(parse_id_list(&too_many).is_err());
+    }
+
+    #[test]
+    fn queue_state_follows_latest_milestone() {
+        let t = Some(Utc::now());
+        assert_eq!(queue_state(None, None, None), "scheduled");
+        assert_eq!(queue_state(t, None, None), "arrived");
+        assert_eq!(queue_state(t, t, None), "seated");
+        assert_eq!(queue_state(t, None, t), "dismissed");
+    }
+
+    #[test]
+    fn source_and_priority_rules() {
+        assert_eq!(normalize_source(None).unwrap(), "SCHEDULED");
+        assert_eq!(normalize_source(Some(" walkin ".into())).unwrap(), "WALKIN");
+        assert!(normalize_source(Some("phone".into())).is_err());
+        assert!(validate_priority(1).is_ok());
+        assert!(validate_priority(2).is_err());
+    }
+
+    #[test]
+    fn wait_runs_from_arrival_until_seated() {
+        let t = |m: i64| DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::minutes(m);
+        assert_eq!(wait_minutes(None, None, None, t(30)), None);
+        assert_eq!(wait_minutes(Some(t(0)), None, None, t(25)), Some(25));
+        assert_eq!(wait_minutes(Some(t(0)), Some(t(10)), Some(t(50)), t(90)), Some(10));
+        assert_eq!(wait_minutes(Some(t(0)), None, Some(t(5)), t(90)), Some(5));
+    }
+
+    fn auth(role: Role) -> AuthContext {
+        AuthContext { user_id: Uuid::new_v4(), role, session_token_id: Uuid::nil(), impersonator_user_id: None }
+    }
+
+    #[tokio::test]
+    async fn closure_is_checked_on_clinic_local_dates() {
+        let closed = NaiveDate::from_ymd_opt(2026, 12, 3).unwrap();
+        let repo = FakeAppointmentRepo {
+            tz: chrono_tz::Asia::Ulaanbaatar,
+            closures: vec![(closed, "Holiday".into())],
+            ..Default::default()
+        };
+        // 17:00Z on Dec 2 is 01:00 on Dec 3 in Ulaanbaatar (UTC+8)
+        let start = "2026-12-02T17:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let end = start + chrono::Duration::minutes(30);
+
+        let err = ensure_clinic_open(&repo, &auth(Role::Receptionist), start, end, false).await.unwrap_err();
+        assert!(matches!(err, ApiError::Conflict("CLINIC_CLOSED", _)), "{err:?}");
+        // only admin/manager may override
+        assert!(ensure_clinic_open(&repo, &auth(Role::Receptionist), start, end, true).await.is_err());
+        assert!(ensure_clinic_open(&repo, &auth(Role::Manager), start, end, true).await.is_ok());
+        // ending exactly at local midnight doesn't touch the next day
+        let before = "2026-12-02T15:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(ensure_clinic_open(&repo, &auth(Role::Receptionist), before, before + chrono::Duration::minutes(30), false).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn assistant_must_be_active_free_and_on_shift() {
+        let (assistant, doctor, retired) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let (booked, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let t = |h: u32| "2026-10-19T00:00:00Z".parse::<DateTime<Utc>>().unwrap() + chrono::Duration::hours(h.into());
+        let repo = FakeAppointmentRepo {
+            assistants: HashMap::from([
+                (assistant, (Some(Role::Receptionist), true)),
+                (doctor, (Some(Role::Doctor), true)),
+                (retired, (None, false)),
+            ]),
+            assisting: vec![(assistant, booked, t(2), t(3))],
+            shifts: vec![(assistant, t(1), t(5)), (assistant, t(5), t(9))],
+            ..Default::default()
+        };
+        let front_desk = auth(Role::Receptionist);
+        let check = |who, start, end, exclude, role| {
+            let auth = auth(role);
+            let repo = &repo;
+            async move { ensure_assistant_available(repo, &auth, Some(who), start, end, exclude, role == Role::Manager).await }
+        };
+
+        // back-to-back shifts join up
+        assert!(check(assistant, t(4), t(6), None, Role::Receptionist).await.is_ok());
+        let err = check(assistant, t(8), t(10), None, Role::Receptionist).await.unwrap_err();
+        assert!(matches!(err, ApiError::Conflict("ASSISTANT_NOT_ON_SHIFT", _)), "{err:?}");
+        assert!(check(assistant, t(8), t(10), None, Role::Manager).await.is_ok());
+
+        let err = check(assistant, t(2), t(4), None, Role::Manager).await.unwrap_err();
+        assert!(matches!(err, ApiError::Conflict("ASSISTANT_DOUBLE_BOOKED", _)), "{err:?}");
+        // rescheduling the booked appointment itself doesn't clash with itself
+        assert!(check(assistant, t(2), t(4), Some(booked), Role::Receptionist).await.is_ok());
+        assert!(check(assistant, t(2), t(4), Some(other), Role::Receptionist).await.is_err());
+
+        assert!(matches!(check(doctor, t(4), t(6), None, Role::Receptionist).await, Err(ApiError::BadRequest(..))));
+        assert!(matches!(check(retired, t(4), t(6), None, Role::Receptionist).await, Err(ApiError::BadRequest(..))));
+        assert!(matches!(check(Uuid::new_v4(), t(4), t(6), None, Role::Receptionist).await, Err(ApiError::NotFound(..))));
+        assert!(ensure_assistant_available(&repo, &front_desk, None, t(8), t(10), None, false).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn lookups_map_missing_rows_to_errors() {
+        let doctor_user = Uuid::new_v4();
+        let doctor = Uuid::new_v4();
+        let appointment = Uuid::new_v4();
+        let repo = FakeAppointmentRepo {
+            employees: [(doctor_user, doctor)].into(),
+            doctors: [(appointment, doctor)].into(),
+            statuses: Mutex::new([(appointment, AppointmentStatus::Scheduled)].into()),
+            ..Default::default()
+        };
+
+        assert_eq!(doctor_employee_id_for_user(&repo, doctor_user).await.unwrap(), doctor);
+        let err = doctor_employee_id_for_user(&repo, Uuid::new_v4()).await.unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest("NO_EMPLOYEE_PROFILE", _)), "{err:?}");
+        assert_eq!(doctor_of(&repo, appointment).await.unwrap(), doctor);
+        assert!(matches!(doctor_of(&repo, Uuid::new_v4()).await, Err(ApiError::NotFound(..))));
+        assert!(matches!(get_block(&repo, appointment).await, Err(ApiError::NotFound(..))));
+
+        mark(&repo, &auth(Role::Receptionist), appointment, Milestone::Arrived).await.unwrap();
+        assert_eq!(*repo.marked.lock().unwrap(), vec![(appointment, Milestone::Arrived)]);
+        let err = mark(&repo, &auth(Role::Receptionist), Uuid::new_v4(), Milestone::Seated).await.unwrap_err();
+        assert!(matches!(err, ApiError::NotFound(..)), "{err:?}");
+    }
+
+    #[test]
+    fn history_shows_status_names() {
+        let change = |field: &str, old: Option<JsonValue>, new: Option<JsonValue>| AppointmentChangeDto {
+            changed_at: Utc::now(),
+            field: field.into(),
+            old_value: old,
+            new_value: new,
+            changed_by_user_id: None,
+            changed_by_name: None,
+        };
+
+        let mut c = change("status", Some(0.into()), Some(4.into()));
+        name_statuses(&mut c);
+        assert_eq!((c.old_value.unwrap(), c.new_value.unwrap()), ("scheduled".into(), "arrived".into()));
+
+        let mut c = change("created", None, Some(serde_json::json!({ "status": 0, "end_at": "2026-01-01T09:30:00+00:00" })));
+        name_statuses(&mut c);
+        assert_eq!(c.new_value.unwrap()["status"], "scheduled");
+
+        let mut c = change("priority", Some(0.into()), Some(1.into()));
+        name_statuses(&mut c);
+        assert_eq!(c.new_value.unwrap(), 1);
+    }
+
+    #[test]
+    fn overdue_total_counts_no_shows_only_when_included() {
+        let counts = OverdueCounts { scheduled: 7, no_show: 3 };
+        assert_eq!(counts.total(false), 7);
+        assert_eq!(counts.total(true), 10);
+
+        let sort = |s: &str| serde_json::from_value::<OverdueSort>(s.into());
+        assert_eq!(sort("start_at_desc").unwrap(), OverdueSort::StartAtDesc);
+        assert_eq!(sort("priority").unwrap(), OverdueSort::Priority);
+        assert!(sort("-start_at").is_err());
+    }
+
+    #[tokio::test]
+    async fn milestones_follow_status_transitions() {
+        let (booked, canceled) = (Uuid::new_v4(), Uuid::new_v4());
+        let repo = FakeAppointmentRepo {
+            statuses: Mutex::new([(booked, AppointmentStatus::Scheduled), (canceled, AppointmentStatus::Canceled)].into()),
+            ..Default::default()
+        };
+        let desk = auth(Role::Receptionist);
+        let status = |id| repo.statuses.lock().unwrap()[&id];
+
+        // no dismissal before arrival, no arrival on a canceled appointment
+        let err = mark(&repo, &desk, booked, Milestone::Dismissed).await.unwrap_err();
+        assert!(matches!(err, ApiError::Conflict("INVALID_STATUS_TRANSITION", _)), "{err:?}");
+        assert!(mark(&repo, &desk, canceled, Milestone::Arrived).await.is_err());
+        // confirming only stamps, whatever the status
+        mark(&repo, &desk, canceled, Milestone::Confirmed).await.unwrap();
+        assert_eq!(status(canceled), AppointmentStatus::Canceled);
+
+        for m in [Milestone::Arrived, Milestone::Seated, Milestone::Dismissed] {
+            mark(&repo, &desk, booked, m).await.unwrap();
+        }
+        assert_eq!(status(booked), AppointmentStatus::Finished);
+        // repeat calls are harmless
+        mark(&repo, &desk, booked, Milestone::Dismissed).await.unwrap();
+        assert!(mark(&repo, &desk, booked, Milestone::Arrived).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn open_required_onboarding_items_block_seating() {
+        let (first_visit, regular) = (Uuid::new_v4(), Uuid::new_v4());
+        let consent = onboarding::SeatingBlocker {
+            onboarding_item_id: Uuid::new_v4(),
+            code: "consent".into(),
+            label: "Treatment consent".into(),
+        };
+        let repo = FakeAppointmentRepo {
+            statuses: Mutex::new(
+                [(first_visit, AppointmentStatus::Arrived), (regular, AppointmentStatus::Arrived)].into(),
+            ),
+            seating_blockers: [(first_visit, vec![consent])].into(),
+            ..Default::default()
+        };
+        let desk = auth(Role::Receptionist);
+
+        let err = mark(&repo, &desk, first_visit, Milestone::Seated).await.unwrap_err();
+        let ApiError::ConflictWithDetails("ONBOARDING_INCOMPLETE", msg, details) = err else {
+            panic!("{err:?}");
+        };
+        assert!(msg.contains("Treatment consent"), "{msg}");
+        assert_eq!(details["items"][0]["code"], "consent");
+        // only seating is held back
+        mark(&repo, &desk, first_visit, Milestone::Confirmed).await.unwrap();
+        mark(&repo, &desk, regular, Milestone::Seated).await.unwrap();
+        assert_eq!(
+            *repo.marked.lock().unwrap(),
+            vec![(first_visit, Milestone::Confirmed), (regular, Milestone::Seated)]
+        );
+    }
+}
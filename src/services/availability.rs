@@ -0,0 +1,530 @@
+// src/services/availability.rs
+//
+// "Next available" search: open slots for a service across one or all doctors
+// who perform it. A slot starts on the clinic's slot grid inside a
+// business_hours window, fits the doctor's duration for the service (rounded up
+// to whole slots), and does not overlap a booking that occupies the chair.
+// Closed holidays are skipped; times are clinic-local.
+// Also the day grid behind GET /schedule/grid, so clients don't each redo the
+// slotting math.
+
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+use crate::{
+    clinic_time, employee_services,
+    error::ApiError,
+    repos::AppointmentRepo,
+    services::appointments::{self, PersonBrief},
+};
+
+pub const DEFAULT_LIMIT: usize = 5;
+pub const MAX_LIMIT: usize = 50;
+pub const DEFAULT_DAYS: u64 = 7;
+pub const MAX_DAYS: u64 = 31;
+
+/// A doctor who performs the service, with their duration for it
+/// (override, else the catalog default; None = unspecified).
+#[derive(Debug, Clone)]
+pub struct SlotProvider {
+    pub doctor: PersonBrief,
+    pub location_id: Option<Uuid>,
+    pub duration_min: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AvailableSlotDto {
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub duration_min: i64,
+    pub location_id: Option<Uuid>,
+    pub doctor: PersonBrief,
+}
+
+#[derive(Debug)]
+pub struct SlotSearch {
+    pub service_id: Uuid,
+    pub doctor_employee_id: Option<Uuid>,
+    /// None = every location
+    pub locations: Option<Vec<Uuid>>,
+    /// first clinic-local day searched
+    pub from: NaiveDate,
+    pub days: u64,
+    pub limit: usize,
+}
+
+/// Opening windows per weekday (Mon=0) from clinic_settings.business_hours:
+/// { "mon": [{"start":"09:00","end":"18:00"}], "tue": [...], ... }
+/// Missing days are closed; malformed or empty ranges are ignored.
+pub fn opening_windows(bh: &JsonValue) -> [Vec<(NaiveTime, NaiveTime)>; 7] {
+    const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+    let mut out: [Vec<(NaiveTime, NaiveTime)>; 7] = Default::default();
+    for (i, day) in DAYS.iter().enumerate() {
+        let Some(ranges) = bh.get(*day).and_then(|v| v.as_array()) else {
+            continue;
+        };
+        for r in ranges {
+            let start = r.get("start").and_then(|v| v.as_str());
+            let end = r.get("end").and_then(|v| v.as_str());
+            if let (Some(s), Some(e)) = (start, end)
+                && let (Ok(s), Ok(e)) = (NaiveTime::parse_from_str(s, "%H:%M"), NaiveTime::parse_from_str(e, "%H:%M"))
+                && e > s
+            {
+                out[i].push((s, e));
+            }
+        }
+        out[i].sort();
+    }
+    out
+}
+
+/// The clinic's slot grid: where a booking may start.
+pub struct SlotGrid {
+    pub windows: [Vec<(NaiveTime, NaiveTime)>; 7],
+    pub tz: Tz,
+    pub slot_minutes: i64,
+}
+
+impl SlotGrid {
+    /// Up to `limit` free [start, end) slots of `duration_min`, earliest first.
+    /// Slots start at a window's opening plus whole slots, end by its close,
+    /// start no earlier than `not_before` and miss every `busy` interval.
+    /// Local times that do not exist (DST gap) are skipped.
+    pub fn free_slots(
+        &self,
+        days: &[NaiveDate],
+        duration_min: i64,
+        busy: &[(DateTime<Utc>, DateTime<Utc>)],
+        not_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let step = Duration::minutes(self.slot_minutes.max(1));
+        let duration = Duration::minutes(duration_min.max(1));
+        let mut out = Vec::new();
+
+        for day in days {
+            for (open, close) in &self.windows[day.weekday().num_days_from_monday() as usize] {
+                let close = day.and_time(*close);
+                let mut cursor = day.and_time(*open);
+                while cursor + duration <= close {
+                    if let Some(start) = self.tz.from_local_datetime(&cursor).earliest() {
+                        let start = start.with_timezone(&Utc);
+                        let end = start + duration;
+                        if start >= not_before && !busy.iter().any(|(b_start, b_end)| *b_start < end && start < *b_end) {
+                            out.push((start, end));
+                            if out.len() >= limit {
+                                return out;
+                            }
+                        }
+                    }
+                    cursor += step;
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A day's opening windows as UTC intervals, earliest first. Windows whose
+/// bounds fall into a DST gap are dropped.
+pub fn open_intervals(
+    windows: &[Vec<(NaiveTime, NaiveTime)>; 7],
+    tz: Tz,
+    day: NaiveDate,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    windows[day.weekday().num_days_from_monday() as usize]
+        .iter()
+        .filter_map(|(open, close)| {
+            let open = tz.from_local_datetime(&day.and_time(*open)).earliest()?;
+            let close = tz.from_local_datetime(&day.and_time(*close)).earliest()?;
+            Some((open.with_timezone(&Utc), close.with_timezone(&Utc)))
+        })
+        .collect()
+}
+
+/// Overlapping or touching intervals joined, earliest first.
+pub fn merge_intervals(mut intervals: Vec<(DateTime<Utc>, DateTime<Utc>)>) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    intervals.retain(|(start, end)| start < end);
+    intervals.sort();
+    let mut out: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::with_capacity(intervals.len());
+    for (start, end) in intervals {
+        match out.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => out.push((start, end)),
+        }
+    }
+    out
+}
+
+/// The parts of the `open` intervals not covered by any `busy` interval.
+pub fn gaps(
+    open: &[(DateTime<Utc>, DateTime<Utc>)],
+    busy: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let busy = merge_intervals(busy.to_vec());
+    let mut out = Vec::new();
+    for (open_start, open_end) in open {
+        let mut cursor = *open_start;
+        for (b_start, b_end) in busy.iter().filter(|(s, e)| s < open_end && e > open_start) {
+            if *b_start > cursor {
+                out.push((cursor, *b_start));
+            }
+            cursor = cursor.max(*b_end);
+        }
+        if cursor < *open_end {
+            out.push((cursor, *open_end));
+        }
+    }
+    out
+}
+
+pub fn total_minutes(intervals: &[(DateTime<Utc>, DateTime<Utc>)]) -> i64 {
+    intervals.iter().map(|(start, end)| (*end - *start).num_minutes()).sum()
+}
+
+/// The next open slots across the doctors who perform the service, earliest
+/// first (ties by doctor number). Doctors without a duration for the service
+/// are skipped; a doctor who does not perform it yields no slots.
+pub async fn search(
+    repo: &dyn AppointmentRepo,
+    q: &SlotSearch,
+    now: DateTime<Utc>,
+) -> Result<Vec<AvailableSlotDto>, ApiError> {
+    let providers = repo
+        .slot_providers(q.service_id, q.doctor_employee_id, q.locations.as_deref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "service not found".into()))?;
+    if providers.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let tz = repo.clinic_tz().await?;
+    let (slot_minutes, business_hours) = repo.scheduling_settings().await?;
+    let grid = SlotGrid {
+        windows: opening_windows(&business_hours),
+        tz,
+        slot_minutes,
+    };
+
+    let last_day = q.from + Days::new(q.days.saturating_sub(1));
+    let closed = repo.closed_days(q.from, last_day).await?;
+    let days: Vec<NaiveDate> = q
+        .from
+        .iter_days()
+        .take(q.days as usize)
+        .filter(|d| !closed.contains(d))
+        .collect();
+
+    let (start_ts, end_ts) = clinic_time::local_days_range(q.from, q.days, tz);
+    let doctor_ids: Vec<Uuid> = providers.iter().map(|p| p.doctor.id).collect();
+    let busy = repo.busy_intervals(&doctor_ids, start_ts, end_ts).await?;
+
+    let mut out = Vec::new();
+    for p in providers {
+        let Some(minutes) = p.duration_min else {
+            continue;
+        };
+        let duration_min = employee_services::round_up_to_slot(minutes.into(), slot_minutes);
+        let own_busy: Vec<_> = busy
+            .iter()
+            .filter(|(doctor_id, _, _)| *doctor_id == p.doctor.id)
+            .map(|(_, start, end)| (*start, *end))
+            .collect();
+        for (start_at, end_at) in grid.free_slots(&days, duration_min, &own_busy, now, q.limit) {
+            out.push(AvailableSlotDto {
+                start_at,
+                end_at,
+                duration_min,
+                location_id: p.location_id,
+                doctor: p.doctor.clone(),
+            });
+        }
+    }
+
+    out.sort_by_key(|s| (s.start_at, s.doctor.number));
+    out.truncate(q.limit);
+    Ok(out)
+}
+
+/// One doctor's day on the slot grid (GET /schedule/grid).
+#[derive(Debug, Serialize)]
+pub struct ScheduleGridDto {
+    pub date: NaiveDate,
+    pub doctor_employee_id: Uuid,
+    pub slot_minutes: i64,
+    /// clinic closure that day: no row is open
+    pub closed: bool,
+    pub rows: Vec<GridRowDto>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct GridRowDto {
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    /// wholly inside business hours
+    pub open: bool,
+    /// an appointment that holds the chair overlaps the slot
+    pub busy: bool,
+    /// those appointments, by start
+    pub appointment_ids: Vec<Uuid>,
+}
+
+/// Rows of `slot_minutes` from the first opening to the last close, on the grid
+/// that starts at the first opening (breaks come back as closed rows). Widened by
+/// whole slots to take in appointments outside the hours; no hours and no
+/// appointments = no rows.
+pub fn grid_rows(
+    open: &[(DateTime<Utc>, DateTime<Utc>)],
+    slot_minutes: i64,
+    appointments: &[(Uuid, DateTime<Utc>, DateTime<Utc>)],
+) -> Vec<GridRowDto> {
+    let step_min = slot_minutes.max(1);
+    let step = Duration::minutes(step_min);
+    let first_open = open.iter().map(|(start, _)| *start).min();
+    let first_booked = appointments.iter().map(|(_, start, _)| *start).min();
+    let Some(anchor) = first_open.or(first_booked) else {
+        return vec![];
+    };
+    let first = first_open.into_iter().chain(first_booked).min().unwrap_or(anchor);
+    let last = open.iter().map(|(_, end)| *end).chain(appointments.iter().map(|(_, _, end)| *end)).max();
+    let Some(last) = last else {
+        return vec![];
+    };
+
+    let slots_before = ((anchor - first).num_minutes() + step_min - 1) / step_min;
+    let mut cursor = anchor - Duration::minutes(slots_before * step_min);
+    let mut rows = Vec::new();
+    while cursor < last {
+        let end = cursor + step;
+        let mut ids: Vec<(DateTime<Utc>, Uuid)> = appointments
+            .iter()
+            .filter(|(_, a_start, a_end)| *a_start < end && cursor < *a_end)
+            .map(|(id, a_start, _)| (*a_start, *id))
+            .collect();
+        ids.sort();
+        rows.push(GridRowDto {
+            start_at: cursor,
+            end_at: end,
+            open: open.iter().any(|(o_start, o_end)| *o_start <= cursor && end <= *o_end),
+            busy: !ids.is_empty(),
+            appointment_ids: ids.into_iter().map(|(_, id)| id).collect(),
+        });
+        cursor = end;
+    }
+    rows
+}
+
+/// The grid for one doctor and clinic-local day; canceled and no-show appointments
+/// don't hold a slot and are left out.
+pub async fn day_grid(
+    repo: &dyn AppointmentRepo,
+    doctor_employee_id: Uuid,
+    date: NaiveDate,
+    locations: Option<&[Uuid]>,
+) -> Result<ScheduleGridDto, ApiError> {
+    let tz = repo.clinic_tz().await?;
+    let (slot_minutes, business_hours) = repo.scheduling_settings().await?;
+    let closed = repo.closed_days(date, date).await?.contains(&date);
+    let open = if closed { vec![] } else { open_intervals(&opening_windows(&business_hours), tz, date) };
+
+    let (day_start, day_end) = clinic_time::local_days_range(date, 1, tz);
+    let booked: Vec<(Uuid, DateTime<Utc>, DateTime<Utc>)> =
+        appointments::blocks_in_range(repo, doctor_employee_id, day_start, day_end, locations)
+            .await?
+            .into_iter()
+            .filter(|b| b.status.occupies_slot())
+            .map(|b| (b.appointment_id, b.start_at.max(day_start), b.end_at.min(day_end)))
+            .collect();
+
+    Ok(ScheduleGridDto {
+        date,
+        doctor_employee_id,
+        slot_minutes,
+        closed,
+        rows: grid_rows(&open, slot_minutes, &booked),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::repos::fake::FakeAppointmentRepo;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn doctor(id: Uuid, number: i64) -> PersonBrief {
+        PersonBrief {
+            id,
+            display: format!("Doctor {number}"),
+            number: Some(number),
+            photo_url: None,
+            no_show_risk: None,
+        }
+    }
+
+    fn weekday_hours() -> JsonValue {
+        json!({
+            "mon": [{"start": "09:00", "end": "12:00"}],
+            "tue": [{"start": "09:00", "end": "12:00"}],
+            "wed": [{"start": "09:00", "end": "12:00"}],
+            "thu": [{"start": "09:00", "end": "12:00"}],
+            "fri": [{"start": "09:00", "end": "12:00"}]
+        })
+    }
+
+    #[test]
+    fn opening_windows_skip_malformed_ranges() {
+        let windows = opening_windows(&json!({
+            "mon": [{"start": "14:00", "end": "18:00"}, {"start": "09:00", "end": "12:00"}],
+            "tue": [{"start": "12:00", "end": "09:00"}, {"start": "9am", "end": "noon"}],
+            "wed": "closed"
+        }));
+        let t = |s| NaiveTime::parse_from_str(s, "%H:%M").unwrap();
+        assert_eq!(windows[0], vec![(t("09:00"), t("12:00")), (t("14:00"), t("18:00"))]);
+        assert!(windows[1..].iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn slots_fit_the_window_and_skip_bookings() {
+        let grid = SlotGrid { windows: opening_windows(&weekday_hours()), tz: Tz::UTC, slot_minutes: 30 };
+        // Monday: 09:30-10:30 is booked
+        let busy = [(at("2026-03-02T09:30:00Z"), at("2026-03-02T10:30:00Z"))];
+        let slots = grid.free_slots(&[date("2026-03-02")], 60, &busy, at("2026-03-01T00:00:00Z"), 10);
+        let starts: Vec<_> = slots.iter().map(|(s, _)| s.format("%H:%M").to_string()).collect();
+        assert_eq!(starts, ["10:30", "11:00"]);
+        assert_eq!(slots[0].1, at("2026-03-02T11:30:00Z"));
+    }
+
+    #[test]
+    fn slots_are_clinic_local_and_not_in_the_past() {
+        let tz = clinic_time::parse_tz("Asia/Ulaanbaatar").unwrap();
+        let grid = SlotGrid { windows: opening_windows(&weekday_hours()), tz, slot_minutes: 60 };
+        // 09:00 local = 01:00 UTC; "now" is 10:15 local
+        let slots = grid.free_slots(&[date("2026-03-02")], 30, &[], at("2026-03-02T02:15:00Z"), 10);
+        assert_eq!(slots, vec![(at("2026-03-02T03:00:00Z"), at("2026-03-02T03:30:00Z"))]);
+    }
+
+    #[test]
+    fn gaps_are_open_time_not_covered_by_bookings() {
+        let tz = clinic_time::parse_tz("Asia/Ulaanbaatar").unwrap();
+        let windows = opening_windows(&json!({
+            "mon": [{"start": "09:00", "end": "12:00"}, {"start": "13:00", "end": "15:00"}]
+        }));
+        let open = open_intervals(&windows, tz, date("2026-03-02"));
+        assert_eq!(open[0], (at("2026-03-02T01:00:00Z"), at("2026-03-02T04:00:00Z")));
+        assert!(open_intervals(&windows, tz, date("2026-03-03")).is_empty());
+
+        // two overlapping bookings 09:30-10:30 local, one 11:30-13:30 across lunch
+        let busy = [
+            (at("2026-03-02T01:30:00Z"), at("2026-03-02T02:15:00Z")),
+            (at("2026-03-02T02:00:00Z"), at("2026-03-02T02:30:00Z")),
+            (at("2026-03-02T03:30:00Z"), at("2026-03-02T05:30:00Z")),
+        ];
+        let free = gaps(&open, &busy);
+        assert_eq!(
+            free,
+            vec![
+                (at("2026-03-02T01:00:00Z"), at("2026-03-02T01:30:00Z")),
+                (at("2026-03-02T02:30:00Z"), at("2026-03-02T03:30:00Z")),
+                (at("2026-03-02T05:30:00Z"), at("2026-03-02T07:00:00Z")),
+            ]
+        );
+        assert_eq!(total_minutes(&open), 300);
+        assert_eq!(total_minutes(&free), 180);
+        assert_eq!(total_minutes(&merge_intervals(busy.to_vec())), 180);
+    }
+
+    #[test]
+    fn grid_rows_mark_breaks_and_widen_for_bookings_outside_hours() {
+        let open = [
+            (at("2026-03-02T09:00:00Z"), at("2026-03-02T10:00:00Z")),
+            (at("2026-03-02T10:30:00Z"), at("2026-03-02T11:00:00Z")),
+        ];
+        let (early, long) = (Uuid::new_v4(), Uuid::new_v4());
+        let booked = [
+            // starts off-grid before opening
+            (early, at("2026-03-02T08:10:00Z"), at("2026-03-02T08:40:00Z")),
+            (long, at("2026-03-02T09:20:00Z"), at("2026-03-02T10:10:00Z")),
+        ];
+        let rows = grid_rows(&open, 30, &booked);
+        let starts: Vec<_> = rows.iter().map(|r| r.start_at.format("%H:%M").to_string()).collect();
+        assert_eq!(starts, ["08:00", "08:30", "09:00", "09:30", "10:00", "10:30"]);
+        assert_eq!(rows.iter().map(|r| r.open).collect::<Vec<_>>(), [false, false, true, true, false, true]);
+        assert_eq!(rows[1].appointment_ids, [early]);
+        assert_eq!(rows[2].appointment_ids, [long]);
+        assert!(rows[4].busy && !rows[5].busy);
+
+        assert!(grid_rows(&[], 30, &[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_merges_doctors_and_skips_closed_days() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let service_id = Uuid::new_v4();
+        let repo = FakeAppointmentRepo {
+            business_hours: weekday_hours(),
+            closures: vec![(date("2026-03-02"), "Holiday".into())],
+            providers: HashMap::from([(
+                service_id,
+                vec![
+                    SlotProvider { doctor: doctor(a, 1), location_id: None, duration_min: Some(50) },
+                    SlotProvider { doctor: doctor(b, 2), location_id: None, duration_min: Some(30) },
+                ],
+            )]),
+            // doctor 2 is booked all Tuesday morning but the last half hour
+            busy: vec![(b, at("2026-03-03T09:00:00Z"), at("2026-03-03T11:30:00Z"))],
+            ..Default::default()
+        };
+        let q = SlotSearch {
+            service_id,
+            doctor_employee_id: None,
+            locations: None,
+            from: date("2026-03-02"),
+            days: 7,
+            limit: 4,
+        };
+
+        let slots = search(&repo, &q, at("2026-03-01T00:00:00Z")).await.unwrap();
+        let got: Vec<_> = slots.iter().map(|s| (s.start_at, s.doctor.number, s.duration_min)).collect();
+        assert_eq!(
+            got,
+            vec![
+                (at("2026-03-03T09:00:00Z"), Some(1), 60),
+                (at("2026-03-03T09:30:00Z"), Some(1), 60),
+                (at("2026-03-03T10:00:00Z"), Some(1), 60),
+                (at("2026-03-03T10:30:00Z"), Some(1), 60),
+            ]
+        );
+
+        let q = SlotSearch { doctor_employee_id: Some(b), limit: 1, ..q };
+        let slots = search(&repo, &q, at("2026-03-01T00:00:00Z")).await.unwrap();
+        assert_eq!(slots[0].start_at, at("2026-03-03T11:30:00Z"));
+    }
+
+    #[tokio::test]
+    async fn search_unknown_service_is_not_found() {
+        let q = SlotSearch {
+            service_id: Uuid::new_v4(),
+            doctor_employee_id: None,
+            locations: None,
+            from: date("2026-03-02"),
+            days: 7,
+            limit: 5,
+        };
+        let err = search(&FakeAppointmentRepo::default(), &q, Utc::now()).await.unwrap_err();
+        assert!(matches!(err, ApiError::NotFound(..)));
+    }
+}
@@ -0,0 +1,116 @@
+// src/services/feedback.rs
+//
+// Post-visit feedback: the rating link texted after dismissal (jobs::feedback_requests),
+// what the patient may submit through it, and the score math of /reports/feedback.
+// On the 1–5 scale NPS counts 5 as promoter, 4 as passive and 1–3 as detractor.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::ApiError, i18n::Lang};
+
+/// Links expire this long after they were texted.
+pub const LINK_TTL_DAYS: i64 = 7;
+/// Appointments dismissed longer ago than this don't get a request (e.g. when the
+/// setting is switched on, or the job was down for a while).
+pub const SEND_WITHIN_HOURS: i64 = 24;
+
+const MAX_COMMENT_LEN: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeedbackSubmission {
+    /// 1..=5
+    pub rating: i16,
+    pub comment: Option<String>,
+}
+
+/// Checks the rating and trims the comment (blank = none).
+pub fn normalize(s: FeedbackSubmission) -> Result<FeedbackSubmission, ApiError> {
+    if !(1..=5).contains(&s.rating) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "rating must be 1..5".into()));
+    }
+    let comment = s.comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    if comment.as_deref().is_some_and(|c| c.chars().count() > MAX_COMMENT_LEN) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("comment must be at most {MAX_COMMENT_LEN} chars"),
+        ));
+    }
+    Ok(FeedbackSubmission { rating: s.rating, comment })
+}
+
+/// In the patient's preferred_language (English when unset).
+pub fn sms_text(lang: Lang, first_name: &str, clinic_name: &str, link: &str) -> String {
+    match lang {
+        Lang::En => format!("{first_name}, thank you for visiting {clinic_name}. How was your visit? {link}"),
+        Lang::Mn => format!("{first_name}, {clinic_name}-д үйлчлүүлсэнд баярлалаа. Үзлэг тань ямар байсан бэ? {link}"),
+    }
+}
+
+/// Per-score counts, index 0 = rating 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RatingCounts(pub [i64; 5]);
+
+impl RatingCounts {
+    pub fn responses(&self) -> i64 {
+        self.0.iter().sum()
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        let n = self.responses();
+        let total: i64 = self.0.iter().zip(1..).map(|(c, score)| c * score).sum();
+        (n > 0).then(|| total as f64 / n as f64)
+    }
+
+    /// % promoters − % detractors, -100..100; None without responses.
+    pub fn nps(&self) -> Option<f64> {
+        let n = self.responses();
+        let promoters = self.0[4];
+        let detractors = self.0[0] + self.0[1] + self.0[2];
+        (n > 0).then(|| (promoters - detractors) as f64 * 100.0 / n as f64)
+    }
+
+    pub fn add(&mut self, other: &RatingCounts) {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a += b;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_checks_rating_and_trims_comment() {
+        let ok = normalize(FeedbackSubmission { rating: 5, comment: Some("  great  ".into()) }).unwrap();
+        assert_eq!(ok.comment.as_deref(), Some("great"));
+        let blank = normalize(FeedbackSubmission { rating: 1, comment: Some("   ".into()) }).unwrap();
+        assert_eq!(blank.comment, None);
+
+        assert!(normalize(FeedbackSubmission { rating: 0, comment: None }).is_err());
+        assert!(normalize(FeedbackSubmission { rating: 6, comment: None }).is_err());
+        assert!(normalize(FeedbackSubmission { rating: 3, comment: Some("x".repeat(1001)) }).is_err());
+    }
+
+    #[test]
+    fn sms_text_in_the_patient_language() {
+        let link = "https://x.test/f/abc";
+        assert!(sms_text(Lang::En, "Bat", "Smile", link).starts_with("Bat, thank you for visiting Smile."));
+        assert!(sms_text(Lang::Mn, "Бат", "Smile", link).contains("Smile-д үйлчлүүлсэнд баярлалаа"));
+        assert!(sms_text(Lang::Mn, "Бат", "Smile", link).ends_with(link));
+    }
+
+    #[test]
+    fn scores_from_counts() {
+        // 1x1, 1x3, 2x4, 6x5
+        let c = RatingCounts([1, 0, 1, 2, 6]);
+        assert_eq!(c.responses(), 10);
+        assert_eq!(c.average(), Some(4.2));
+        assert_eq!(c.nps(), Some(40.0));
+
+        let empty = RatingCounts::default();
+        assert_eq!(empty.average(), None);
+        assert_eq!(empty.nps(), None);
+    }
+}
@@ -0,0 +1,182 @@
+// src/services/intake.rs
+//
+// Pre-visit intake forms: what a patient may fill in through a texted link
+// (demographics + medical history) and how an approved submission maps onto the
+// patient record. The form is kept as submitted until staff review it; nothing
+// touches `patient` before approval. Every field is optional, blank = not given,
+// and a field that wasn't given keeps the stored value on approval.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::ApiError, models::Gender, services::patients::PatientPatch};
+
+pub const DEFAULT_LINK_TTL_HOURS: i64 = 72;
+pub const MAX_LINK_TTL_HOURS: i64 = 14 * 24;
+
+const MAX_NAME_LEN: usize = 100;
+const MAX_EMAIL_LEN: usize = 254;
+const MAX_TEXT_LEN: usize = 2000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MedicalHistoryForm {
+    pub allergies: Option<String>,
+    pub medications: Option<String>,
+    pub conditions: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl MedicalHistoryForm {
+    pub fn is_empty(&self) -> bool {
+        self.allergies.is_none() && self.medications.is_none() && self.conditions.is_none() && self.notes.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IntakeForm {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub birthday: Option<NaiveDate>,
+    pub gender: Option<Gender>,
+    #[serde(default)]
+    pub medical_history: MedicalHistoryForm,
+}
+
+fn clean(field: &str, value: Option<String>, max: usize) -> Result<Option<String>, ApiError> {
+    let value = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    if value.as_deref().is_some_and(|v| v.chars().count() > max) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", format!("{field} must be at most {max} chars")));
+    }
+    Ok(value)
+}
+
+/// Trims every text field (blank = not given) and checks lengths, email shape
+/// and that the birthday lies between 1900 and `today` (clinic-local).
+pub fn normalize(form: IntakeForm, today: NaiveDate) -> Result<IntakeForm, ApiError> {
+    let email = clean("email", form.email, MAX_EMAIL_LEN)?;
+    if email.as_deref().is_some_and(|e| {
+        let (local, domain) = e.split_once('@').unwrap_or_default();
+        local.is_empty() || !domain.contains('.') || e.contains(char::is_whitespace)
+    }) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "email is not a valid address".into()));
+    }
+    let earliest = NaiveDate::from_ymd_opt(1900, 1, 1).expect("valid date");
+    if form.birthday.is_some_and(|b| b < earliest || b > today) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "birthday is out of range".into()));
+    }
+
+    let m = form.medical_history;
+    Ok(IntakeForm {
+        first_name: clean("first_name", form.first_name, MAX_NAME_LEN)?,
+        last_name: clean("last_name", form.last_name, MAX_NAME_LEN)?,
+        email,
+        birthday: form.birthday,
+        gender: form.gender,
+        medical_history: MedicalHistoryForm {
+            allergies: clean("allergies", m.allergies, MAX_TEXT_LEN)?,
+            medications: clean("medications", m.medications, MAX_TEXT_LEN)?,
+            conditions: clean("conditions", m.conditions, MAX_TEXT_LEN)?,
+            notes: clean("notes", m.notes, MAX_TEXT_LEN)?,
+        },
+    })
+}
+
+/// The patient update an approved (normalized) form makes; omitted fields stay.
+pub fn patient_patch(form: &IntakeForm) -> PatientPatch {
+    PatientPatch {
+        first_name: form.first_name.clone(),
+        last_name: form.last_name.clone(),
+        email: form.email.clone().map(Some),
+        birthday: form.birthday,
+        gender: form.gender,
+        ..PatientPatch::default()
+    }
+}
+
+/// Names of the fields the form fills in, for the audit trail (values are PII).
+pub fn given_fields(form: &IntakeForm) -> Vec<&'static str> {
+    let m = &form.medical_history;
+    [
+        ("first_name", form.first_name.is_some()),
+        ("last_name", form.last_name.is_some()),
+        ("email", form.email.is_some()),
+        ("birthday", form.birthday.is_some()),
+        ("gender", form.gender.is_some()),
+        ("allergies", m.allergies.is_some()),
+        ("medications", m.medications.is_some()),
+        ("conditions", m.conditions.is_some()),
+        ("notes", m.notes.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(name, given)| given.then_some(name))
+    .collect()
+}
+
+/// `{base}/{token}`, whether or not the configured form URL ends with a slash
+/// (INTAKE_FORM_URL, also used for FEEDBACK_FORM_URL).
+pub fn link(base: &str, token: &str) -> String {
+    format!("{}/{token}", base.trim_end_matches('/'))
+}
+
+pub fn sms_text(first_name: &str, link: &str) -> String {
+    format!("{first_name}, please fill in your details before your visit: {link}")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 16).unwrap()
+    }
+
+    #[test]
+    fn normalize_trims_and_drops_blanks() {
+        let form: IntakeForm = serde_json::from_value(json!({
+            "first_name": "  Bat ",
+            "last_name": "",
+            "email": " bat@example.mn ",
+            "gender": "male",
+            "medical_history": { "allergies": " penicillin ", "notes": "   " }
+        }))
+        .unwrap();
+        let form = normalize(form, today()).unwrap();
+
+        assert_eq!(form.first_name.as_deref(), Some("Bat"));
+        assert_eq!(form.last_name, None);
+        assert_eq!(form.email.as_deref(), Some("bat@example.mn"));
+        assert_eq!(form.medical_history.allergies.as_deref(), Some("penicillin"));
+        assert_eq!(form.medical_history.notes, None);
+        assert_eq!(given_fields(&form), ["first_name", "email", "gender", "allergies"]);
+
+        let patch = patient_patch(&form);
+        assert_eq!(patch.last_name, None);
+        assert_eq!(patch.email, Some(Some("bat@example.mn".into())));
+    }
+
+    #[test]
+    fn normalize_rejects_bad_values() {
+        let bad = [
+            json!({ "email": "not-an-email" }),
+            json!({ "email": "a@b" }),
+            json!({ "birthday": "2026-10-17" }),
+            json!({ "birthday": "1899-12-31" }),
+            json!({ "medical_history": { "conditions": "x".repeat(2001) } }),
+        ];
+        for v in bad {
+            let form: IntakeForm = serde_json::from_value(v.clone()).unwrap();
+            assert!(normalize(form, today()).is_err(), "{v}");
+        }
+        assert!(serde_json::from_value::<IntakeForm>(json!({ "status": 0 })).is_err());
+    }
+
+    #[test]
+    fn link_joins_base_and_token() {
+        assert_eq!(link("https://c.mn/intake/", "abc"), "https://c.mn/intake/abc");
+        assert_eq!(link("https://c.mn/intake", "abc"), "https://c.mn/intake/abc");
+    }
+}
@@ -0,0 +1,95 @@
+// src/services/medical_alerts.rs
+//
+// Clinical alerts (patient_medical_alert, migration 053) and the booking gate:
+// booking a patient with active alerts fails with 409 CLINICAL_ALERTS (alerts in
+// `error.details.alerts`) until the request says acknowledge_warnings=true; the
+// acknowledged booking then carries the alerts in its `warnings`.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{error::ApiError, models::MedicalAlertKind, pii::PiiString};
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MedicalAlertDto {
+    pub alert_id: Uuid,
+    pub patient_id: Uuid,
+    pub kind: MedicalAlertKind,
+    pub label: PiiString,
+    pub note: Option<PiiString>,
+    pub created_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_by_user_id: Option<Uuid>,
+    /// None = active
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+pub const ALERT_COLUMNS: &str = "alert_id, patient_id, kind, label, note, created_by_user_id, created_at, \
+                                 resolved_by_user_id, resolved_at";
+
+/// An active alert as shown at booking time.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BookingAlert {
+    pub patient_id: Uuid,
+    pub patient_name: String,
+    pub kind: MedicalAlertKind,
+    pub label: PiiString,
+}
+
+/// Active alerts of the patients being booked, per patient oldest first.
+pub async fn active_for(conn: &mut PgConnection, patient_ids: &[Uuid]) -> Result<Vec<BookingAlert>, ApiError> {
+    Ok(sqlx::query_as::<_, BookingAlert>(
+        r#"
+        SELECT al.patient_id, p.first_name || ' ' || p.last_name AS patient_name, al.kind, al.label
+        FROM patient_medical_alert al
+        JOIN patient p ON p.patient_id = al.patient_id
+        WHERE al.patient_id = ANY($1)
+          AND al.resolved_at IS NULL
+        ORDER BY array_position($1, al.patient_id), al.created_at
+        "#,
+    )
+    .bind(patient_ids)
+    .fetch_all(&mut *conn)
+    .await?)
+}
+
+/// The booking's alert warnings, or 409 CLINICAL_ALERTS when there are alerts
+/// nobody acknowledged yet.
+pub fn booking_warnings(alerts: &[BookingAlert], acknowledged: bool) -> Result<Vec<String>, ApiError> {
+    if !alerts.is_empty() && !acknowledged {
+        return Err(ApiError::ConflictWithDetails(
+            "CLINICAL_ALERTS",
+            format!(
+                "{} active clinical alert(s); resend with acknowledge_warnings=true to book anyway",
+                alerts.len()
+            ),
+            serde_json::json!({ "alerts": alerts }),
+        ));
+    }
+    Ok(alerts
+        .iter()
+        .map(|a| format!("{} alert for {}: {}", a.kind, a.patient_name, a.label.0))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unacknowledged_alerts_block_booking() {
+        assert_eq!(booking_warnings(&[], false).unwrap(), Vec::<String>::new());
+
+        let alerts = [BookingAlert {
+            patient_id: Uuid::nil(),
+            patient_name: "Bat Dorj".into(),
+            kind: MedicalAlertKind::Allergy,
+            label: PiiString::from("lidocaine"),
+        }];
+        let err = booking_warnings(&alerts, false).unwrap_err();
+        assert!(matches!(err, ApiError::ConflictWithDetails("CLINICAL_ALERTS", ..)), "{err:?}");
+        assert_eq!(booking_warnings(&alerts, true).unwrap(), ["allergy alert for Bat Dorj: lidocaine"]);
+    }
+}
@@ -0,0 +1,15 @@
+// src/services/mod.rs
+//
+// Service layer: the queries and business rules behind the route handlers.
+// Functions take a pool/connection (and the AuthContext where a rule depends on
+// the caller, e.g. who may override a closure) and return domain rows or ApiError;
+// routes keep RBAC gates, request parsing/validation and the `{ data }` envelope.
+// ("service" as in layer; the dental service catalog is routes::service_routes.)
+
+pub mod appointments;
+pub mod availability;
+pub mod feedback;
+pub mod intake;
+pub mod medical_alerts;
+pub mod patients;
+pub mod procedure_templates;
@@ -0,0 +1,705 @@
+// src/services/patients.rs
+//
+// Patients: the patient row (with derived age / primary phone), duplicate
+// detection on create, profile updates, archive/restore and deletion requests.
+// Used by routes::patient_routes; summary/export assembly stays there.
+// SQL lives in repos::patients.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::{PgConnection, Row, postgres::PgRow};
+use uuid::Uuid;
+
+use crate::{
+    clinic_time,
+    error::ApiError,
+    models::Gender,
+    numbering,
+    photos,
+    pii::PiiString,
+    repos::{self, PatientRepo},
+};
+
+/* ============================================================
+   Patient row
+   ============================================================ */
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PatientRow {
+    pub patient_id: Uuid,
+    pub register_number: String,
+    pub user_id: Option<Uuid>,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<PiiString>,
+    pub birthday: Option<chrono::NaiveDate>,
+    pub gender: Gender,
+    pub status: i16,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// versioned URL of the profile photo (from photo_updated_at), None without one
+    pub photo_url: Option<String>,
+    /// how the patient found the clinic (clinic referral_source catalog)
+    pub referral_source_id: Option<Uuid>,
+
+    // derived, so lists don't need a follow-up call per row (see `fill_derived`)
+    /// "first last"
+    pub display_name: String,
+    /// full years on the clinic-local date; None without a birthday
+    pub age: Option<i32>,
+    pub primary_phone: Option<String>,
+}
+
+// manual: photo_url is derived from patient_id + the photo_updated_at column
+impl<'r> sqlx::FromRow<'r, PgRow> for PatientRow {
+    fn from_row(r: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let patient_id: Uuid = r.try_get("patient_id")?;
+        let first_name: String = r.try_get("first_name")?;
+        let last_name: String = r.try_get("last_name")?;
+        Ok(Self {
+            patient_id,
+            register_number: r.try_get("register_number")?,
+            user_id: r.try_get("user_id")?,
+            display_name: format!("{first_name} {last_name}"),
+            first_name,
+            last_name,
+            email: r.try_get("email")?,
+            birthday: r.try_get("birthday")?,
+            gender: r.try_get("gender")?,
+            status: r.try_get("status")?,
+            created_at: r.try_get("created_at")?,
+            last_seen_at: r.try_get("last_seen_at")?,
+            photo_url: photos::photo_url("patients", patient_id, r.try_get("photo_updated_at")?),
+            referral_source_id: r.try_get("referral_source_id")?,
+            age: None,
+            primary_phone: None,
+        })
+    }
+}
+
+pub const PATIENT_STATUS_ACTIVE: i16 = 0;
+pub const PATIENT_STATUS_ARCHIVED: i16 = 3;
+
+/// Newest first, both for a search and for the default (empty query) list.
+const SEARCH_LIMIT: i64 = 50;
+
+/// Stored patient fields, as written by create / PATCH.
+#[derive(Debug, Clone)]
+pub struct PatientFields {
+    /// None on create = generated by the DB default; on update = unchanged
+    pub register_number: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<PiiString>,
+    pub birthday: Option<chrono::NaiveDate>,
+    pub gender: Gender,
+    pub status: i16,
+    pub referral_source_id: Option<Uuid>,
+}
+
+/// Full years between `birthday` and `today`; None for a birthday in the future.
+/// Feb 29 birthdays count on Mar 1 in non-leap years.
+pub fn age_on(birthday: chrono::NaiveDate, today: chrono::NaiveDate) -> Option<i32> {
+    use chrono::Datelike;
+    if birthday > today {
+        return None;
+    }
+    let had_birthday = (today.month(), today.day()) >= (birthday.month(), birthday.day());
+    Some(today.year() - birthday.year() - i32::from(!had_birthday))
+}
+
+/// Fills `age` (clinic timezone) and `primary_phone` with one extra query for all rows.
+pub async fn fill_derived(repo: &dyn PatientRepo, rows: &mut [PatientRow]) -> Result<(), ApiError> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let today = clinic_time::local_today(repo.clinic_tz().await?);
+
+    let ids: Vec<Uuid> = rows.iter().map(|p| p.patient_id).collect();
+    let phones = repo.primary_phones(&ids).await?;
+
+    for p in rows {
+        p.age = p.birthday.and_then(|b| age_on(b, today));
+        p.primary_phone = phones.get(&p.patient_id).cloned();
+    }
+    Ok(())
+}
+
+pub async fn with_derived(repo: &dyn PatientRepo, mut row: PatientRow) -> Result<PatientRow, ApiError> {
+    fill_derived(repo, std::slice::from_mut(&mut row)).await?;
+    Ok(row)
+}
+
+fn patient_not_found() -> ApiError {
+    ApiError::NotFound("NOT_FOUND", "patient not found".into())
+}
+
+/// Without derived fields; NOT_FOUND when missing.
+pub async fn get(repo: &dyn PatientRepo, patient_id: Uuid) -> Result<PatientRow, ApiError> {
+    repo.get(patient_id).await?.ok_or_else(patient_not_found)
+}
+
+/// Register number / first / last name substring; empty query = most recent 50.
+pub async fn search(repo: &dyn PatientRepo, query: &str) -> Result<Vec<PatientRow>, ApiError> {
+    let mut rows = repo.search(query.trim(), SEARCH_LIMIT).await?;
+    fill_derived(repo, &mut rows).await?;
+    Ok(rows)
+}
+
+/// One keyset page (by patient_id), derived fields filled; `ids` None = every patient.
+/// The HQ sync stream pages with it, /sync/changes loads a page's patients by id.
+pub async fn page_after(
+    repo: &dyn PatientRepo,
+    after: Option<Uuid>,
+    ids: Option<&[Uuid]>,
+    limit: i64,
+) -> Result<Vec<PatientRow>, ApiError> {
+    let mut rows = repo.page_after(after, ids, limit).await?;
+    fill_derived(repo, &mut rows).await?;
+    Ok(rows)
+}
+
+/* ============================================================
+   Create (with duplicate detection)
+   ============================================================ */
+
+/// Existing patient that looks like the one being created.
+#[derive(Debug, Serialize)]
+pub struct DuplicateCandidate {
+    pub patient_id: Uuid,
+    pub register_number: String,
+    pub display_name: String,
+    pub birthday: Option<chrono::NaiveDate>,
+    /// "name_birthday" and/or "phone"
+    pub matched_on: Vec<&'static str>,
+}
+
+/// What a new patient is matched on: normalized names + birthday, phone suffix.
+#[derive(Debug)]
+pub struct DuplicateKey {
+    pub first_name: String,
+    pub last_name: String,
+    pub birthday: Option<chrono::NaiveDate>,
+    pub phone_key: Option<String>,
+}
+
+const MAX_DUPLICATE_CANDIDATES: i64 = 10;
+/// phone numbers match on their last 8 digits, so "+976 9911 8840" finds "99118840"
+pub const PHONE_MATCH_DIGITS: usize = 8;
+
+/// lowercase, trimmed, inner whitespace collapsed (same as `norm_name` in the repo's SQL)
+fn normalize_name(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// The last PHONE_MATCH_DIGITS digits, what stored numbers are matched on
+/// (`right(regexp_replace(phone_number, '\D', '', 'g'), 8)`, index from migration 059).
+pub fn phone_match_key(raw: &str) -> Option<String> {
+    let digits: String = raw.chars().filter(char::is_ascii_digit).collect();
+    (digits.len() >= PHONE_MATCH_DIGITS).then(|| digits[digits.len() - PHONE_MATCH_DIGITS..].to_string())
+}
+
+/// Same name (either order) + same birthday, or a phone number ending in the same digits.
+/// Anonymized patients are never candidates.
+pub async fn find_duplicate_candidates(
+    repo: &dyn PatientRepo,
+    first_name: &str,
+    last_name: &str,
+    birthday: Option<chrono::NaiveDate>,
+    phone_number: Option<&str>,
+) -> Result<Vec<DuplicateCandidate>, ApiError> {
+    let key = DuplicateKey {
+        first_name: normalize_name(first_name),
+        last_name: normalize_name(last_name),
+        birthday,
+        phone_key: phone_number.and_then(phone_match_key),
+    };
+    if key.birthday.is_none() && key.phone_key.is_none() {
+        return Ok(vec![]);
+    }
+    repo.duplicate_candidates(&key, MAX_DUPLICATE_CANDIDATES).await
+}
+
+/// Picking a source: it must exist and still be active (old patients may keep inactive ones).
+pub async fn ensure_referral_source(repo: &dyn PatientRepo, referral_source_id: Uuid) -> Result<(), ApiError> {
+    match repo.referral_source_active(referral_source_id).await? {
+        None => Err(ApiError::NotFound("NOT_FOUND", "referral source not found".into())),
+        Some(false) => Err(ApiError::BadRequest("VALIDATION_ERROR", "referral source is inactive".into())),
+        Some(true) => Ok(()),
+    }
+}
+
+/* ============================================================
+   Phone lookup (inbound-call screen-pop, SMS sender matching)
+   ============================================================ */
+
+/// A stored number that matches the looked-up one, with its patient.
+#[derive(Debug, Serialize)]
+pub struct PhoneMatch {
+    pub phone_number_id: Uuid,
+    pub phone_number: String,
+    pub label: String,
+    pub is_primary: bool,
+    /// "exact" (same digits) | "last_digits" (same last 8 only, e.g. stored without +976)
+    pub matched_on: &'static str,
+    pub patient_id: Uuid,
+    pub register_number: String,
+    pub display_name: String,
+    pub birthday: Option<chrono::NaiveDate>,
+    pub status: i16,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PhoneLookup {
+    /// the query as matched: digits, with a leading '+' if it had one (or "00")
+    pub normalized: String,
+    /// exact matches first, then primary numbers
+    pub matches: Vec<PhoneMatch>,
+}
+
+const MAX_PHONE_MATCHES: i64 = 20;
+/// E.164 limit
+pub const MAX_PHONE_DIGITS: usize = 15;
+
+/// Caller IDs and SMS senders come in every shape: "+976 9911-8840", "0097699118840",
+/// "(9911) 88.40". Separators are dropped and "00" becomes "+"; anything else is rejected.
+pub fn normalize_lookup_number(raw: &str) -> Result<String, ApiError> {
+    let compact: String = raw.chars().filter(|c| !c.is_whitespace() && !"-().".contains(*c)).collect();
+    let (plus, digits) = match compact.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => match compact.strip_prefix("00") {
+            Some(rest) => (true, rest),
+            None => (false, compact.as_str()),
+        },
+    };
+    if !digits.chars().all(|c| c.is_ascii_digit()) || !(PHONE_MATCH_DIGITS..=MAX_PHONE_DIGITS).contains(&digits.len()) {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            format!("number must have {PHONE_MATCH_DIGITS}..{MAX_PHONE_DIGITS} digits"),
+        ));
+    }
+    Ok(if plus { format!("+{digits}") } else { digits.to_string() })
+}
+
+/// Patients whose stored numbers match `raw`, on all digits or on the last 8 (numbers
+/// are often stored without the country code, or callers show up without one).
+/// Anonymized patients never match.
+pub async fn lookup_phone(repo: &dyn PatientRepo, raw: &str) -> Result<PhoneLookup, ApiError> {
+    let normalized = normalize_lookup_number(raw)?;
+    let digits = normalized.trim_start_matches('+');
+    let key = &digits[digits.len() - PHONE_MATCH_DIGITS..];
+    let matches = repo.phone_matches(digits, key, MAX_PHONE_MATCHES).await?;
+    Ok(PhoneLookup { normalized, matches })
+}
+
+pub struct NewPatient {
+    /// None/blank = generated (numbering.rs)
+    pub register_number: Option<String>,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+    pub birthday: Option<chrono::NaiveDate>,
+    pub gender: Gender,
+    pub status: i16,
+    pub referral_source_id: Option<Uuid>,
+    /// only used for duplicate detection
+    pub phone_number: Option<String>,
+}
+
+/// 409 DUPLICATE_PATIENT with `details.candidates` when the patient probably
+/// exists already, unless `force`.
+pub async fn create(repo: &dyn PatientRepo, new: NewPatient, force: bool) -> Result<PatientRow, ApiError> {
+    let first_name = new.first_name.trim();
+    let last_name = new.last_name.trim();
+
+    if first_name.is_empty() || last_name.is_empty() {
+        return Err(ApiError::BadRequest(
+            "VALIDATION_ERROR",
+            "first_name and last_name are required".to_string(),
+        ));
+    }
+
+    if let Some(id) = new.referral_source_id {
+        ensure_referral_source(repo, id).await?;
+    }
+
+    if !force {
+        let candidates =
+            find_duplicate_candidates(repo, first_name, last_name, new.birthday, new.phone_number.as_deref()).await?;
+        if !candidates.is_empty() {
+            return Err(ApiError::ConflictWithDetails(
+                "DUPLICATE_PATIENT",
+                format!(
+                    "{} existing patient(s) look like the same person; resend with ?force=true to create anyway",
+                    candidates.len()
+                ),
+                serde_json::json!({ "candidates": candidates }),
+            ));
+        }
+    }
+
+    let fields = PatientFields {
+        register_number: non_blank(new.register_number.as_deref())
+            .map(|rn| numbering::normalize_register_number(&rn))
+            .transpose()?,
+        user_id: None,
+        first_name: first_name.to_string(),
+        last_name: last_name.to_string(),
+        email: new.email.as_deref().map(PiiString::from),
+        birthday: new.birthday,
+        gender: new.gender,
+        status: new.status,
+        referral_source_id: new.referral_source_id,
+    };
+    repo.insert(&fields).await
+}
+
+fn non_blank(s: Option<&str>) -> Option<String> {
+    s.map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+/* ============================================================
+   Update
+   ============================================================ */
+
+pub(crate) fn deserialize_double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    // This is called only when the field is present (even if it's `null`).
+    // - null => Option::<T>::deserialize => None => we wrap => Some(None)
+    // - value => Some(value) => we wrap => Some(Some(value))
+    let inner = Option::<T>::deserialize(deserializer)?;
+    Ok(Some(inner))
+}
+
+/// PATCH body: omitted fields keep their value.
+#[derive(Debug, Default, Deserialize)]
+pub struct PatientPatch {
+    pub register_number: Option<String>, // optional override (rare; usually keep stable)
+    pub user_id: Option<Uuid>,           // allow linking in PATCH (optional)
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    /// null or "" clears
+    #[serde(default, deserialize_with = "deserialize_double_option")]
+    pub email: Option<Option<String>>,
+    pub birthday: Option<chrono::NaiveDate>,
+    pub gender: Option<Gender>,
+    pub status: Option<i16>,
+    /// null clears
+    #[serde(default, deserialize_with = "deserialize_double_option")]
+    pub referral_source_id: Option<Option<Uuid>>,
+}
+
+pub async fn update(repo: &dyn PatientRepo, patient_id: Uuid, patch: PatientPatch) -> Result<PatientRow, ApiError> {
+    let fields = patched_fields(repo, patient_id, patch).await?;
+    repo.update(patient_id, &fields).await?.ok_or_else(patient_not_found)
+}
+
+/// `update` on `conn`, inside the caller's transaction (POST /sync/push).
+pub async fn update_in(
+    conn: &mut PgConnection,
+    repo: &dyn PatientRepo,
+    patient_id: Uuid,
+    patch: PatientPatch,
+) -> Result<PatientRow, ApiError> {
+    let fields = patched_fields(repo, patient_id, patch).await?;
+    repos::patients::update_on(conn, patient_id, &fields).await?.ok_or_else(patient_not_found)
+}
+
+/// The stored row with `patch` applied, validated.
+async fn patched_fields(
+    repo: &dyn PatientRepo,
+    patient_id: Uuid,
+    patch: PatientPatch,
+) -> Result<PatientFields, ApiError> {
+    let existing = get(repo, patient_id).await?;
+
+    // blank names / register number keep the stored value
+    let register_number = non_blank(patch.register_number.as_deref())
+        .map(|rn| numbering::normalize_register_number(&rn))
+        .transpose()?;
+    let first_name = non_blank(patch.first_name.as_deref()).unwrap_or(existing.first_name);
+    let last_name = non_blank(patch.last_name.as_deref()).unwrap_or(existing.last_name);
+
+    let email: Option<PiiString> = match patch.email {
+        None => existing.email,                // field not provided => keep old
+        Some(None) => None,                    // explicitly null => clear
+        Some(Some(e)) => {
+            let t = e.trim();
+            if t.is_empty() { None } else { Some(PiiString::from(t)) }
+        }
+    };
+
+    let referral_source_id = match patch.referral_source_id {
+        None => existing.referral_source_id,
+        Some(None) => None,
+        // re-sending the current (possibly inactive) source is fine
+        Some(Some(id)) if Some(id) == existing.referral_source_id => Some(id),
+        Some(Some(id)) => {
+            ensure_referral_source(repo, id).await?;
+            Some(id)
+        }
+    };
+
+    let fields = PatientFields {
+        register_number,
+        user_id: patch.user_id.or(existing.user_id),
+        first_name,
+        last_name,
+        email,
+        birthday: patch.birthday.or(existing.birthday),
+        gender: patch.gender.unwrap_or(existing.gender),
+        status: patch.status.unwrap_or(existing.status),
+        referral_source_id,
+    };
+
+    // status check based on migration: patient.status 0..3
+    if !(0..=3).contains(&fields.status) {
+        return Err(ApiError::BadRequest("VALIDATION_ERROR", "status must be 0..3".into()));
+    }
+
+    Ok(fields)
+}
+
+/// None unlinks.
+pub async fn set_user(repo: &dyn PatientRepo, patient_id: Uuid, user_id: Option<Uuid>) -> Result<PatientRow, ApiError> {
+    if let Some(user_id) = user_id
+        && !repo.user_exists(user_id).await?
+    {
+        return Err(ApiError::NotFound("NOT_FOUND", "user not found".into()));
+    }
+    repo.set_user(patient_id, user_id).await?.ok_or_else(patient_not_found)
+}
+
+/// Archive (PATIENT_STATUS_ARCHIVED) / restore (PATIENT_STATUS_ACTIVE).
+pub async fn set_status(repo: &dyn PatientRepo, patient_id: Uuid, status: i16) -> Result<PatientRow, ApiError> {
+    repo.set_status(patient_id, status).await?.ok_or_else(patient_not_found)
+}
+
+/* ============================================================
+   Deletion requests (anonymized by jobs::patient_retention)
+   ============================================================ */
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PatientDeletionRow {
+    pub patient_id: Uuid,
+    pub status: i16,
+    pub deletion_requested_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub deletion_due_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub anonymized_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Archives the patient and schedules anonymization after
+/// clinic_settings.patient_retention_days; repeating a request keeps the first one.
+pub async fn request_deletion(
+    repo: &dyn PatientRepo,
+    patient_id: Uuid,
+    requested_by_user_id: Uuid,
+) -> Result<PatientDeletionRow, ApiError> {
+    repo.request_deletion(patient_id, requested_by_user_id).await?.ok_or_else(|| {
+        ApiError::NotFound("NOT_FOUND", "patient not found or already anonymized".into())
+    })
+}
+
+/// Drops a pending deletion request (the patient stays archived).
+pub async fn cancel_deletion(repo: &dyn PatientRepo, patient_id: Uuid) -> Result<PatientDeletionRow, ApiError> {
+    repo.cancel_deletion(patient_id).await?.ok_or_else(|| {
+        ApiError::NotFound("NOT_FOUND", "no pending deletion request for this patient".into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::repos::fake::FakePatientRepo;
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    #[test]
+    fn age_counts_full_years() {
+        assert_eq!(age_on(d(1990, 6, 15), d(2026, 6, 14)), Some(35));
+        assert_eq!(age_on(d(1990, 6, 15), d(2026, 6, 15)), Some(36));
+        // leap-day birthday: a year older on Mar 1 in non-leap years
+        assert_eq!(age_on(d(2000, 2, 29), d(2026, 2, 28)), Some(25));
+        assert_eq!(age_on(d(2000, 2, 29), d(2026, 3, 1)), Some(26));
+        assert_eq!(age_on(d(2026, 10, 17), d(2026, 10, 16)), None);
+    }
+
+    #[test]
+    fn duplicate_match_keys() {
+        assert_eq!(normalize_name("  Bat  Erdene "), "bat erdene");
+        assert_eq!(normalize_name("БАТ"), "бат");
+        assert_eq!(phone_match_key("+976 9911-8840").as_deref(), Some("99118840"));
+        assert_eq!(phone_match_key("99118840").as_deref(), Some("99118840"));
+        assert_eq!(phone_match_key("12345"), None);
+    }
+
+    #[test]
+    fn patch_distinguishes_null_from_omitted() {
+        let p: PatientPatch = serde_json::from_str(r#"{"email": null}"#).unwrap();
+        assert_eq!(p.email, Some(None));
+        assert_eq!(p.referral_source_id, None);
+        let p: PatientPatch = serde_json::from_str(r#"{"email": "a@b.mn"}"#).unwrap();
+        assert_eq!(p.email, Some(Some("a@b.mn".into())));
+    }
+
+    fn new_patient(first: &str, last: &str, birthday: Option<NaiveDate>) -> NewPatient {
+        NewPatient {
+            register_number: None,
+            first_name: first.into(),
+            last_name: last.into(),
+            email: None,
+            birthday,
+            gender: Gender::Female,
+            status: 0,
+            referral_source_id: None,
+            phone_number: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn create_flags_likely_duplicates_unless_forced() {
+        let repo = FakePatientRepo::default();
+        create(&repo, new_patient("Bat", "Erdene", Some(d(1990, 1, 2))), false).await.unwrap();
+
+        // same person with swapped, padded names
+        let err = create(&repo, new_patient(" erdene ", "BAT", Some(d(1990, 1, 2))), false).await.unwrap_err();
+        assert!(matches!(err, ApiError::ConflictWithDetails("DUPLICATE_PATIENT", ..)), "{err:?}");
+
+        // no birthday and no phone: nothing to match on
+        create(&repo, new_patient("Bat", "Erdene", None), false).await.unwrap();
+        let forced = create(&repo, new_patient("Bat", "Erdene", Some(d(1990, 1, 2))), true).await.unwrap();
+        assert_eq!(forced.first_name, "Bat");
+        assert_eq!(repo.patients.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn create_validates_names_gender_and_referral_source() {
+        let inactive = Uuid::new_v4();
+        let repo = FakePatientRepo { referral_sources: [(inactive, false)].into(), ..Default::default() };
+
+        assert!(create(&repo, new_patient("  ", "X", None), false).await.is_err());
+        assert!(serde_json::from_value::<PatientPatch>(serde_json::json!({ "gender": 3 })).is_err());
+        let err = create(&repo, NewPatient { referral_source_id: Some(inactive), ..new_patient("A", "B", None) }, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest("VALIDATION_ERROR", _)), "{err:?}");
+        let err = create(&repo, NewPatient { referral_source_id: Some(Uuid::new_v4()), ..new_patient("A", "B", None) }, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::NotFound(..)), "{err:?}");
+        assert!(repo.patients.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn update_merges_patch_over_stored_row() {
+        let source = Uuid::new_v4();
+        let repo = FakePatientRepo { referral_sources: [(source, true)].into(), ..Default::default() };
+        let created = create(
+            &repo,
+            NewPatient {
+                email: Some("a@b.mn".into()),
+                referral_source_id: Some(source),
+                ..new_patient("Bat", "Erdene", Some(d(1990, 1, 2)))
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        let id = created.patient_id;
+
+        // deactivated since: re-sending the patient's current source is still fine
+        let repo = FakePatientRepo { referral_sources: [(source, false)].into(), ..repo };
+
+        let patch: PatientPatch =
+            serde_json::from_str(&format!(r#"{{"first_name": "  ", "email": "", "referral_source_id": "{source}"}}"#))
+                .unwrap();
+        let row = update(&repo, id, patch).await.unwrap();
+        assert_eq!(row.first_name, "Bat");
+        assert_eq!(row.register_number, created.register_number);
+        assert_eq!(row.email, None);
+        assert_eq!(row.referral_source_id, Some(source));
+
+        let err = update(&repo, id, PatientPatch { status: Some(4), ..Default::default() }).await.unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest("VALIDATION_ERROR", _)), "{err:?}");
+        let err = update(&repo, Uuid::new_v4(), PatientPatch::default()).await.unwrap_err();
+        assert!(matches!(err, ApiError::NotFound(..)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn derived_fields_use_clinic_date_and_primary_phone() {
+        let repo = FakePatientRepo::default();
+        let row = create(&repo, new_patient("Bat", "Erdene", Some(d(1990, 1, 2))), false).await.unwrap();
+        let repo = FakePatientRepo { primary_phones: [(row.patient_id, "99118840".into())].into(), ..repo };
+
+        let rows = search(&repo, "erd").await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].primary_phone.as_deref(), Some("99118840"));
+        assert_eq!(rows[0].age, age_on(d(1990, 1, 2), clinic_time::local_today(repo.tz)));
+        assert!(search(&repo, "nobody").await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn lookup_numbers_are_normalized() {
+        assert_eq!(normalize_lookup_number(" +976 9911-8840 ").unwrap(), "+97699118840");
+        assert_eq!(normalize_lookup_number("0097699118840").unwrap(), "+97699118840");
+        assert_eq!(normalize_lookup_number("(9911) 88.40").unwrap(), "99118840");
+        assert!(normalize_lookup_number("9911884").is_err());
+        assert!(normalize_lookup_number("9911884x").is_err());
+        assert!(normalize_lookup_number("+1234567890123456").is_err());
+    }
+
+    #[tokio::test]
+    async fn phone_lookup_matches_exact_before_last_digits() {
+        let repo = FakePatientRepo::default();
+        let local = create(&repo, new_patient("Bat", "Erdene", None), false).await.unwrap();
+        let intl = create(&repo, new_patient("Saraa", "Dorj", None), false).await.unwrap();
+        let other = create(&repo, new_patient("Anu", "Bold", None), false).await.unwrap();
+        let phones = [
+            (local.patient_id, "9911 8840".to_string()),
+            (intl.patient_id, "+976-9911-8840".to_string()),
+            (other.patient_id, "88119911".to_string()),
+        ];
+        let repo = FakePatientRepo { primary_phones: phones.into(), ..repo };
+
+        let found = lookup_phone(&repo, "+97699118840").await.unwrap();
+        assert_eq!(found.normalized, "+97699118840");
+        let hits: Vec<(Uuid, &str)> = found.matches.iter().map(|m| (m.patient_id, m.matched_on)).collect();
+        assert_eq!(hits, [(intl.patient_id, "exact"), (local.patient_id, "last_digits")]);
+
+        // stored without the country code: the local number is the exact one now
+        let found = lookup_phone(&repo, "99118840").await.unwrap();
+        assert_eq!(found.matches[0].patient_id, local.patient_id);
+        assert_eq!(found.matches[0].matched_on, "exact");
+        assert!(lookup_phone(&repo, "12-34").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn pages_walk_every_patient_once() {
+        let repo = FakePatientRepo::default();
+        for name in ["A", "B", "C", "D", "E"] {
+            create(&repo, new_patient(name, "X", None), false).await.unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut after = None;
+        loop {
+            let page = page_after(&repo, after, None, 2).await.unwrap();
+            let Some(last) = page.last() else { break };
+            after = Some(last.patient_id);
+            seen.extend(page.iter().map(|p| p.patient_id));
+        }
+        assert_eq!(seen.len(), 5);
+        assert!(seen.windows(2).all(|w| w[0] < w[1]));
+
+        let only = [seen[3], Uuid::new_v4()];
+        let page = page_after(&repo, None, Some(&only), 10).await.unwrap();
+        assert_eq!(page.iter().map(|p| p.patient_id).collect::<Vec<_>>(), vec![seen[3]]);
+    }
+}
@@ -0,0 +1,349 @@
+// src/services/procedure_templates.rs
+//
+// Procedure note templates: a named body with {placeholders} plus typed field
+// definitions (tooth, surfaces, choice, number, text). Filling one validates and
+// normalizes the values, then renders the body; a body line whose placeholders
+// all came out empty is dropped, so optional fields can sit on their own line.
+// The rendered text lands on the appointment's note timeline with the field
+// values kept alongside (appointment_note.procedure_fields).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{clinic_time, error::ApiError};
+
+pub const MAX_FIELDS: usize = 30;
+pub const MAX_TEXT_LEN: usize = 2000;
+
+/// Filled from the appointment, not the request; can't be field keys.
+pub const CONTEXT_KEYS: [&str; 3] = ["patient_name", "doctor_name", "date"];
+
+/// Tooth surfaces: mesial, occlusal, distal, buccal, lingual, incisal, facial, palatal.
+const SURFACES: &str = "MODBLIFP";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    Text,
+    /// FDI two-digit notation, permanent (11..48) or primary (51..85)
+    Tooth,
+    /// letters from MODBLIFP, e.g. "MOD"
+    Surfaces,
+    /// one of `options`
+    Choice,
+    Number,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldDef {
+    pub key: String,
+    pub label: String,
+    pub kind: FieldKind,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    /// pre-filled value (validated like an entered one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<JsonValue>,
+}
+
+fn invalid(msg: String) -> ApiError {
+    ApiError::BadRequest("VALIDATION_ERROR", msg)
+}
+
+fn is_tooth(n: u64) -> bool {
+    let (quadrant, tooth) = (n / 10, n % 10);
+    match quadrant {
+        1..=4 => (1..=8).contains(&tooth),
+        5..=8 => (1..=5).contains(&tooth),
+        _ => false,
+    }
+}
+
+/// `{name}` placeholders in the body, in order.
+fn placeholders(body: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut rest = body;
+    while let Some(open) = rest.find('{') {
+        let after = &rest[open + 1..];
+        match after.find('}') {
+            Some(close) => {
+                out.push(&after[..close]);
+                rest = &after[close + 1..];
+            }
+            None => break,
+        }
+    }
+    out
+}
+
+impl FieldDef {
+    /// Normalized value; Null = empty.
+    fn normalize(&self, value: &JsonValue) -> Result<JsonValue, ApiError> {
+        let err = |what: &str| invalid(format!("{}: {what}", self.key));
+        if value.is_null() || value.as_str().is_some_and(|s| s.trim().is_empty()) {
+            return Ok(JsonValue::Null);
+        }
+        match self.kind {
+            FieldKind::Text => {
+                let s = value.as_str().ok_or_else(|| err("must be text"))?.trim();
+                if s.chars().count() > MAX_TEXT_LEN {
+                    return Err(err(&format!("must be at most {MAX_TEXT_LEN} chars")));
+                }
+                Ok(s.into())
+            }
+            FieldKind::Tooth => {
+                let n = match value {
+                    JsonValue::Number(n) => n.as_u64(),
+                    JsonValue::String(s) => s.trim().parse().ok(),
+                    _ => None,
+                };
+                match n {
+                    Some(n) if is_tooth(n) => Ok(n.to_string().into()),
+                    _ => Err(err("must be an FDI tooth number (11..48, 51..85)")),
+                }
+            }
+            FieldKind::Surfaces => {
+                let letters: String = match value {
+                    JsonValue::String(s) => s.clone(),
+                    JsonValue::Array(items) => {
+                        items.iter().map(|v| v.as_str().unwrap_or("?")).collect::<Vec<_>>().concat()
+                    }
+                    _ => return Err(err("must be surface letters")),
+                };
+                let mut out = String::new();
+                for c in letters.chars().filter(|c| !c.is_whitespace() && *c != ',') {
+                    let c = c.to_ascii_uppercase();
+                    if !SURFACES.contains(c) {
+                        return Err(err(&format!("surfaces must be letters from {SURFACES}")));
+                    }
+                    if !out.contains(c) {
+                        out.push(c);
+                    }
+                }
+                Ok(out.into())
+            }
+            FieldKind::Choice => {
+                let s = value.as_str().ok_or_else(|| err("must be text"))?.trim();
+                self.options
+                    .iter()
+                    .find(|o| o.eq_ignore_ascii_case(s))
+                    .map(|o| JsonValue::from(o.as_str()))
+                    .ok_or_else(|| err(&format!("must be one of {}", self.options.join(", "))))
+            }
+            FieldKind::Number => match value {
+                JsonValue::Number(_) => Ok(value.clone()),
+                _ => Err(err("must be a number")),
+            },
+        }
+    }
+}
+
+/// Checks a template's field definitions against its body.
+pub fn validate_template(fields: &[FieldDef], body: &str) -> Result<(), ApiError> {
+    if fields.len() > MAX_FIELDS {
+        return Err(invalid(format!("at most {MAX_FIELDS} fields")));
+    }
+    for (i, f) in fields.iter().enumerate() {
+        let key_ok = f.key.len() <= 32
+            && f.key.starts_with(|c: char| c.is_ascii_lowercase())
+            && f.key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !key_ok {
+            return Err(invalid(format!("field key '{}' must be snake_case, at most 32 chars", f.key)));
+        }
+        if CONTEXT_KEYS.contains(&f.key.as_str()) || fields[..i].iter().any(|g| g.key == f.key) {
+            return Err(invalid(format!("field key '{}' is reserved or repeated", f.key)));
+        }
+        if f.label.trim().is_empty() {
+            return Err(invalid(format!("{}: label is required", f.key)));
+        }
+        if (f.kind == FieldKind::Choice) == f.options.is_empty() {
+            return Err(invalid(format!("{}: options are required for choice fields only", f.key)));
+        }
+        if let Some(d) = &f.default {
+            f.normalize(d)?;
+        }
+    }
+    if body.trim().is_empty() {
+        return Err(invalid("body is required".into()));
+    }
+    if let Some(unknown) = placeholders(body)
+        .into_iter()
+        .find(|p| !CONTEXT_KEYS.contains(p) && !fields.iter().any(|f| f.key == *p))
+    {
+        return Err(invalid(format!("body uses unknown placeholder {{{unknown}}}")));
+    }
+    Ok(())
+}
+
+/// A filled template: normalized values by key and the rendered note.
+#[derive(Debug, Serialize)]
+pub struct FilledProcedure {
+    pub note_text: String,
+    pub fields: Map<String, JsonValue>,
+    /// required fields still empty (a draft may have some; saving may not)
+    pub missing: Vec<String>,
+}
+
+/// Applies defaults, validates `values` (unknown keys are rejected) and renders the body.
+pub fn fill(
+    fields: &[FieldDef],
+    body: &str,
+    values: &Map<String, JsonValue>,
+    context: &HashMap<&str, String>,
+) -> Result<FilledProcedure, ApiError> {
+    if let Some(unknown) = values.keys().find(|k| !fields.iter().any(|f| &f.key == *k)) {
+        return Err(invalid(format!("unknown field {unknown}")));
+    }
+
+    let mut normalized = Map::new();
+    let mut missing = Vec::new();
+    for f in fields {
+        let value = match values.get(&f.key) {
+            Some(v) => f.normalize(v)?,
+            None => f.default.as_ref().map(|d| f.normalize(d)).transpose()?.unwrap_or(JsonValue::Null),
+        };
+        if value.is_null() && f.required {
+            missing.push(f.key.clone());
+        }
+        normalized.insert(f.key.clone(), value);
+    }
+
+    let text_of = |key: &str| -> String {
+        match normalized.get(key) {
+            Some(JsonValue::String(s)) => s.clone(),
+            Some(JsonValue::Null) => String::new(),
+            Some(v) => v.to_string(),
+            None => context.get(key).cloned().unwrap_or_default(),
+        }
+    };
+    let lines: Vec<String> = body
+        .lines()
+        .filter_map(|line| {
+            let keys = placeholders(line);
+            let mut out = line.to_string();
+            for key in &keys {
+                out = out.replace(&format!("{{{key}}}"), &text_of(key));
+            }
+            let all_empty = !keys.is_empty() && keys.iter().all(|k| text_of(k).is_empty());
+            (!all_empty).then_some(out)
+        })
+        .collect();
+
+    Ok(FilledProcedure {
+        note_text: lines.join("\n").trim().to_string(),
+        fields: normalized,
+        missing,
+    })
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct TemplateSource {
+    pub fields: sqlx::types::Json<Vec<FieldDef>>,
+    pub body: String,
+}
+
+/// Fields and body of an active template; NOT_FOUND otherwise.
+pub async fn load(db: &PgPool, template_id: Uuid) -> Result<TemplateSource, ApiError> {
+    sqlx::query_as::<_, TemplateSource>(
+        "SELECT fields, body FROM procedure_template WHERE template_id = $1 AND is_active",
+    )
+    .bind(template_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("NOT_FOUND", "procedure template not found".into()))
+}
+
+/// {patient_name}, {doctor_name} and the clinic-local {date} of the appointment.
+pub async fn appointment_context(db: &PgPool, appointment_id: Uuid) -> Result<HashMap<&'static str, String>, ApiError> {
+    let row: Option<(String, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        r#"
+        SELECT p.first_name || ' ' || p.last_name, d.first_name || ' ' || d.last_name, a.start_at
+        FROM appointment a
+        JOIN patient p ON p.patient_id = a.patient_id
+        JOIN employee d ON d.employee_id = a.doctor_employee_id
+        WHERE a.appointment_id = $1
+        "#,
+    )
+    .bind(appointment_id)
+    .fetch_optional(db)
+    .await?;
+    let (patient_name, doctor_name, start_at) =
+        row.ok_or_else(|| ApiError::NotFound("NOT_FOUND", "appointment not found".into()))?;
+    let tz = clinic_time::clinic_tz(db).await?;
+
+    Ok(HashMap::from([
+        ("patient_name", patient_name),
+        ("doctor_name", doctor_name),
+        ("date", start_at.with_timezone(&tz).date_naive().to_string()),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn filling() -> Vec<FieldDef> {
+        serde_json::from_value(json!([
+            { "key": "tooth", "label": "Tooth", "kind": "tooth", "required": true },
+            { "key": "surfaces", "label": "Surfaces", "kind": "surfaces", "required": true },
+            { "key": "anesthetic", "label": "Anesthetic", "kind": "choice",
+              "options": ["None", "Lidocaine 2%"], "default": "none" },
+            { "key": "shade", "label": "Shade", "kind": "text" },
+        ]))
+        .unwrap()
+    }
+
+    const BODY: &str = "Composite filling {tooth} {surfaces} for {patient_name}\nAnesthetic: {anesthetic}\nShade: {shade}";
+
+    #[test]
+    fn fill_normalizes_values_and_drops_empty_lines() {
+        let values = json!({ "tooth": 36, "surfaces": "mod" }).as_object().unwrap().clone();
+        let context = HashMap::from([("patient_name", "Bat Dorj".to_string())]);
+        let filled = fill(&filling(), BODY, &values, &context).unwrap();
+
+        assert_eq!(filled.note_text, "Composite filling 36 MOD for Bat Dorj\nAnesthetic: None");
+        assert_eq!(filled.fields["anesthetic"], json!("None"));
+        assert_eq!(filled.fields["shade"], JsonValue::Null);
+        assert!(filled.missing.is_empty());
+
+        let draft = fill(&filling(), BODY, &Map::new(), &context).unwrap();
+        assert_eq!(draft.missing, ["tooth", "surfaces"]);
+    }
+
+    #[test]
+    fn fill_rejects_bad_values() {
+        let bad = |v: JsonValue| fill(&filling(), BODY, v.as_object().unwrap(), &HashMap::new()).is_err();
+        assert!(bad(json!({ "tooth": 19 })));
+        assert!(bad(json!({ "tooth": 86 })));
+        assert!(bad(json!({ "surfaces": "MX" })));
+        assert!(bad(json!({ "anesthetic": "Articaine" })));
+        assert!(bad(json!({ "color": "A2" })));
+        assert!(!bad(json!({ "tooth": "55", "surfaces": ["o", "B", "O"] })));
+    }
+
+    #[test]
+    fn templates_check_keys_options_and_placeholders() {
+        assert!(validate_template(&filling(), BODY).is_ok());
+        assert!(validate_template(&filling(), "Filling {toth}").is_err());
+
+        let mut fields = filling();
+        fields[3].key = "date".into();
+        assert!(validate_template(&fields, "x").is_err());
+
+        let mut fields = filling();
+        fields[2].options.clear();
+        assert!(validate_template(&fields, "x").is_err());
+
+        let mut fields = filling();
+        fields[0].default = Some(json!(99));
+        assert!(validate_template(&fields, "x").is_err());
+    }
+}
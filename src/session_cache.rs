@@ -0,0 +1,142 @@
+// src/session_cache.rs
+//
+// In-memory cache in front of the AuthContext session lookup.
+// - token hash -> (user, role, session) for SESSION_CACHE_TTL_SECS; entries past
+//   the session's expires_at are ignored, so a stale entry can't outlive a session
+// - every route that revokes a session, changes a role or disables a user must
+//   call one of the invalidate_* methods
+// - last_seen_at is written at most once per LAST_SEEN_INTERVAL_SECS per session
+//
+// The cache is per process: with several instances behind a load balancer a
+// revoked token can stay usable elsewhere for up to the TTL.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use moka::sync::Cache;
+use uuid::Uuid;
+
+use crate::{i18n::Lang, models::Role};
+
+const SESSION_CACHE_TTL_SECS: u64 = 30;
+const SESSION_CACHE_MAX_ENTRIES: u64 = 10_000;
+const LAST_SEEN_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct CachedSession {
+    pub session_token_id: Uuid,
+    pub user_id: Uuid,
+    pub role: Role,
+    pub impersonator_user_id: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub preferred_language: Option<Lang>,
+}
+
+#[derive(Clone)]
+pub struct SessionCache {
+    by_hash: Cache<String, CachedSession>,
+    last_seen: Cache<Uuid, ()>,
+}
+
+impl SessionCache {
+    pub fn new() -> Self {
+        Self {
+            by_hash: Cache::builder()
+                .max_capacity(SESSION_CACHE_MAX_ENTRIES)
+                .time_to_live(Duration::from_secs(SESSION_CACHE_TTL_SECS))
+                .support_invalidation_closures()
+                .build(),
+            last_seen: Cache::builder()
+                .max_capacity(SESSION_CACHE_MAX_ENTRIES)
+                .time_to_live(Duration::from_secs(LAST_SEEN_INTERVAL_SECS))
+                .build(),
+        }
+    }
+
+    pub fn get(&self, token_hash: &str) -> Option<CachedSession> {
+        self.by_hash
+            .get(token_hash)
+            .filter(|s| s.expires_at > Utc::now())
+    }
+
+    pub fn insert(&self, token_hash: String, session: CachedSession) {
+        self.by_hash.insert(token_hash, session);
+    }
+
+    /// True if last_seen_at should be written now (at most once per interval).
+    pub fn should_touch(&self, session_token_id: Uuid) -> bool {
+        if self.last_seen.contains_key(&session_token_id) {
+            return false;
+        }
+        self.last_seen.insert(session_token_id, ());
+        true
+    }
+
+    pub fn invalidate_session(&self, session_token_id: Uuid) {
+        self.invalidate_where(move |s| s.session_token_id == session_token_id);
+    }
+
+    /// All sessions of a user (role change, disable, revoke-all, password change).
+    pub fn invalidate_user(&self, user_id: Uuid) {
+        self.invalidate_where(move |s| s.user_id == user_id);
+    }
+
+    fn invalidate_where(&self, pred: impl Fn(&CachedSession) -> bool + Send + Sync + 'static) {
+        // only fails if support_invalidation_closures() was not enabled
+        if let Err(e) = self.by_hash.invalidate_entries_if(move |_, s| pred(s)) {
+            tracing::warn!("session cache invalidation failed, clearing it: {e}");
+            self.by_hash.invalidate_all();
+        }
+    }
+}
+
+impl Default for SessionCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(user_id: Uuid, expires_in: chrono::Duration) -> CachedSession {
+        CachedSession {
+            session_token_id: Uuid::new_v4(),
+            user_id,
+            role: Role::Receptionist,
+            impersonator_user_id: None,
+            expires_at: Utc::now() + expires_in,
+            preferred_language: None,
+        }
+    }
+
+    #[test]
+    fn invalidation_and_expiry() {
+        let cache = SessionCache::new();
+        let user = Uuid::new_v4();
+
+        let a = session(user, chrono::Duration::hours(1));
+        let a_id = a.session_token_id;
+        cache.insert("a".into(), a);
+        cache.insert("b".into(), session(user, chrono::Duration::hours(1)));
+        cache.insert("c".into(), session(Uuid::new_v4(), chrono::Duration::hours(1)));
+        cache.insert("expired".into(), session(user, chrono::Duration::seconds(-1)));
+
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("expired").is_none());
+
+        cache.invalidate_session(a_id);
+        cache.by_hash.run_pending_tasks();
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+
+        cache.invalidate_user(user);
+        cache.by_hash.run_pending_tasks();
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+
+        assert!(cache.should_touch(a_id));
+        assert!(!cache.should_touch(a_id));
+    }
+}
@@ -0,0 +1,307 @@
+// src/sms_replies.rs
+//
+// Replies to appointment reminders (clinic_settings.sms_reply_policy). An inbound
+// SMS (add_sms with direction=0) whose first word is a configured keyword acts on
+// the patient's next reminded appointment that hasn't started yet:
+// - confirm keyword:    the same confirm milestone as POST /appointments/{id}/confirm
+// - reschedule keyword: a RESCHEDULE_APPOINTMENT task for the front desk, linked to
+//   the reply (task.sms_id); one open task per appointment, like time-off conflicts
+// Replies that match nothing, or arrive without such an appointment, are just stored.
+// STOP keywords are handled by crate::consent before this runs.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    audit, clinic_time,
+    error::ApiError,
+    middleware::auth_context::AuthContext,
+    models::{AppState, AppointmentStatus, TaskPriority, TaskStatus},
+    services::appointments::{self, Milestone},
+};
+
+const MAX_KEYWORDS: usize = 10;
+const MAX_KEYWORD_LEN: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmsReplyPolicy {
+    pub enabled: bool,
+    pub confirm_keywords: Vec<String>,
+    pub reschedule_keywords: Vec<String>,
+}
+
+impl Default for SmsReplyPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            confirm_keywords: vec!["1".into()],
+            reschedule_keywords: vec!["2".into()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmsReply {
+    Confirm,
+    Reschedule,
+}
+
+/// First word without surrounding punctuation, uppercased ("1.", "yes!" -> "1", "YES").
+fn first_word(text: &str) -> Option<String> {
+    text.split_whitespace()
+        .next()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_uppercase())
+        .filter(|w| !w.is_empty())
+}
+
+impl SmsReplyPolicy {
+    /// Parses and validates a policy as stored in clinic_settings.sms_reply_policy.
+    pub fn from_json(v: &JsonValue) -> Result<Self, String> {
+        let p: Self = serde_json::from_value(v.clone()).map_err(|e| format!("sms_reply_policy: {e}"))?;
+
+        for (name, list) in [("confirm_keywords", &p.confirm_keywords), ("reschedule_keywords", &p.reschedule_keywords)] {
+            if list.is_empty() || list.len() > MAX_KEYWORDS {
+                return Err(format!("sms_reply_policy.{name} must have 1..{MAX_KEYWORDS} entries"));
+            }
+            // a keyword has to survive first_word() unchanged, or it could never match
+            if list.iter().any(|k| k.chars().count() > MAX_KEYWORD_LEN || first_word(k).as_deref() != Some(&k.to_uppercase())) {
+                return Err(format!(
+                    "sms_reply_policy.{name} entries must be single words of 1..{MAX_KEYWORD_LEN} chars"
+                ));
+            }
+        }
+        if p.confirm_keywords.iter().any(|c| p.reschedule_keywords.iter().any(|r| r.to_uppercase() == c.to_uppercase())) {
+            return Err("sms_reply_policy: a keyword can't both confirm and reschedule".into());
+        }
+        Ok(p)
+    }
+
+    /// What an inbound text asks for, by its first word (case-insensitive).
+    pub fn classify(&self, text: &str) -> Option<SmsReply> {
+        if !self.enabled {
+            return None;
+        }
+        let word = first_word(text)?;
+        let has = |list: &[String]| list.iter().any(|k| k.to_uppercase() == word);
+        if has(&self.confirm_keywords) {
+            Some(SmsReply::Confirm)
+        } else if has(&self.reschedule_keywords) {
+            Some(SmsReply::Reschedule)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn default_policy_json() -> JsonValue {
+    serde_json::to_value(SmsReplyPolicy::default()).unwrap_or_default()
+}
+
+async fn load_policy(db: &PgPool) -> Result<SmsReplyPolicy, ApiError> {
+    let raw: Option<JsonValue> =
+        sqlx::query_scalar("SELECT sms_reply_policy FROM clinic_settings WHERE singleton_id = TRUE")
+            .fetch_optional(db)
+            .await?;
+    let Some(raw) = raw else {
+        return Ok(SmsReplyPolicy::default());
+    };
+    Ok(SmsReplyPolicy::from_json(&raw).unwrap_or_else(|e| {
+        // validated on write; a broken row just disables reply handling
+        tracing::warn!("{e}; SMS replies are not processed");
+        SmsReplyPolicy::default()
+    }))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SmsReplyOutcome {
+    pub action: SmsReply,
+    pub appointment_id: Uuid,
+    /// the reschedule task (new or the one already open)
+    pub task_id: Option<Uuid>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ReplyTarget {
+    appointment_id: Uuid,
+    patient_id: Uuid,
+    doctor_employee_id: Uuid,
+    patient_name: String,
+    start_at: DateTime<Utc>,
+}
+
+/// Acts on an inbound SMS that was just stored; None = not a reply to act on.
+pub async fn handle_inbound(
+    state: &AppState,
+    auth: &AuthContext,
+    phone_number_id: Uuid,
+    sms_id: Uuid,
+    text: &str,
+) -> Result<Option<SmsReplyOutcome>, ApiError> {
+    let Some(action) = load_policy(&state.db).await?.classify(text) else {
+        return Ok(None);
+    };
+
+    let target = sqlx::query_as::<_, ReplyTarget>(
+        r#"
+        SELECT a.appointment_id, a.patient_id, a.doctor_employee_id,
+               p.first_name || ' ' || p.last_name AS patient_name, a.start_at
+        FROM phone_number ph
+        JOIN appointment a ON a.patient_id = ph.patient_id
+        JOIN patient p ON p.patient_id = a.patient_id
+        WHERE ph.phone_number_id = $1
+          AND a.reminder_sent_at IS NOT NULL
+          AND a.start_at > now()
+          AND a.status IN ($2, $3)
+        ORDER BY a.start_at
+        LIMIT 1
+        "#,
+    )
+    .bind(phone_number_id)
+    .bind(AppointmentStatus::Scheduled)
+    .bind(AppointmentStatus::Confirmed)
+    .fetch_optional(&state.db)
+    .await?;
+    let Some(target) = target else {
+        return Ok(None);
+    };
+
+    let task_id = match action {
+        SmsReply::Confirm => {
+            appointments::mark(&*state.repos.appointments, auth, target.appointment_id, Milestone::Confirmed).await?;
+            audit::record(
+                &state.db,
+                auth,
+                "appointment.sms_confirm",
+                "appointment",
+                Some(target.appointment_id),
+                serde_json::json!({ "sms_id": sms_id }),
+            )
+            .await?;
+            None
+        }
+        SmsReply::Reschedule => Some(open_reschedule_task(state, auth, &target, sms_id).await?),
+    };
+
+    Ok(Some(SmsReplyOutcome { action, appointment_id: target.appointment_id, task_id }))
+}
+
+async fn open_reschedule_task(
+    state: &AppState,
+    auth: &AuthContext,
+    target: &ReplyTarget,
+    sms_id: Uuid,
+) -> Result<Uuid, ApiError> {
+    let tz = clinic_time::clinic_tz(&state.db).await?;
+    let details = format!(
+        "The patient replied to the reminder asking to reschedule the appointment on {}.",
+        target.start_at.with_timezone(&tz).format("%Y-%m-%d %H:%M"),
+    );
+
+    let mut tx = state.db.begin().await?;
+    // the task creator is whoever recorded the SMS, else the appointment's doctor
+    let creator: Option<Uuid> = sqlx::query_scalar("SELECT employee_id FROM employee WHERE user_id = $1")
+        .bind(auth.user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    let open: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT task_id FROM task
+        WHERE appointment_id = $1
+          AND task_type = 'RESCHEDULE_APPOINTMENT'
+          AND status IN ($2, $3)
+        ORDER BY created_at
+        LIMIT 1
+        FOR UPDATE
+        "#,
+    )
+    .bind(target.appointment_id)
+    .bind(TaskStatus::Open)
+    .bind(TaskStatus::InProgress)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(task_id) = open {
+        return Ok(task_id);
+    }
+
+    let task_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO task (
+          created_by_employee_id, patient_id, appointment_id, sms_id, task_type,
+          status, priority, due_at, title, details, updated_by_employee_id
+        )
+        VALUES ($1, $2, $3, $8, 'RESCHEDULE_APPOINTMENT', $4, $5, now(), 'Reschedule appointment: ' || $6, $7, $1)
+        RETURNING task_id
+        "#,
+    )
+    .bind(creator.unwrap_or(target.doctor_employee_id))
+    .bind(target.patient_id)
+    .bind(target.appointment_id)
+    .bind(TaskStatus::Open)
+    .bind(TaskPriority::High)
+    .bind(&target.patient_name)
+    .bind(&details)
+    .bind(sms_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::write_failed("TASK_CREATE_FAILED"))?;
+
+    audit::record(
+        &mut *tx,
+        auth,
+        "appointment.sms_reschedule",
+        "appointment",
+        Some(target.appointment_id),
+        serde_json::json!({ "sms_id": sms_id, "task_id": task_id }),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(task_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn policy_validation() {
+        assert_eq!(SmsReplyPolicy::from_json(&default_policy_json()).unwrap(), SmsReplyPolicy::default());
+        let bad = [
+            json!({ "enabled": true, "confirm_keywords": [], "reschedule_keywords": ["2"] }),
+            json!({ "enabled": true, "confirm_keywords": ["yes please"], "reschedule_keywords": ["2"] }),
+            json!({ "enabled": true, "confirm_keywords": ["1."], "reschedule_keywords": ["2"] }),
+            json!({ "enabled": true, "confirm_keywords": ["ok"], "reschedule_keywords": ["OK"] }),
+            json!({ "enabled": true, "confirm_keywords": ["1"], "reschedule_keywords": ["2"], "x": 1 }),
+        ];
+        for v in bad {
+            assert!(SmsReplyPolicy::from_json(&v).is_err(), "{v}");
+        }
+    }
+
+    #[test]
+    fn classify_by_first_word() {
+        let p = SmsReplyPolicy::from_json(&json!({
+            "enabled": true,
+            "confirm_keywords": ["1", "yes", "тийм"],
+            "reschedule_keywords": ["2"]
+        }))
+        .unwrap();
+        assert_eq!(p.classify(" 1 "), Some(SmsReply::Confirm));
+        assert_eq!(p.classify("Yes!"), Some(SmsReply::Confirm));
+        assert_eq!(p.classify("Тийм, ирнэ"), Some(SmsReply::Confirm));
+        assert_eq!(p.classify("2. can't make it"), Some(SmsReply::Reschedule));
+        assert_eq!(p.classify("12"), None);
+        assert_eq!(p.classify("see you at 1"), None);
+        assert_eq!(p.classify(""), None);
+
+        assert_eq!(SmsReplyPolicy::default().classify("1"), None, "disabled by default");
+    }
+}
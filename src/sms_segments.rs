@@ -0,0 +1,102 @@
+// src/sms_segments.rs
+//
+// SMS length/segment estimate (POST /sms/estimate, bulk_send dry_run).
+// GSM-7 when every character is in the GSM 03.38 default alphabet (extension
+// characters like `€` or `{` take two septets), otherwise UCS-2, counted in UTF-16
+// units. Concatenated messages lose room to the UDH: 160/153 septets for GSM-7,
+// 70/67 units for UCS-2 — a single Cyrillic letter turns a message into UCS-2.
+
+use serde::Serialize;
+
+/// GSM 03.38 default alphabet (without the escape character)
+const GSM7_BASIC: &str = "@£$¥èéùìòÇ\nØø\rÅåΔ_ΦΓΛΩΠΨΣΘΞÆæßÉ !\"#¤%&'()*+,-./0123456789:;<=>?\
+¡ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÑÜ§¿abcdefghijklmnopqrstuvwxyzäöñüà";
+/// extension table; each costs escape + char
+const GSM7_EXTENDED: &str = "^{}\\[~]|€\x0c";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmsEncoding {
+    Gsm7,
+    Ucs2,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SmsEstimate {
+    pub encoding: SmsEncoding,
+    /// septets (GSM-7) or UTF-16 units (UCS-2)
+    pub length: usize,
+    pub segments: usize,
+    /// room per segment at this length (160/153 or 70/67)
+    pub per_segment: usize,
+    pub recipients: usize,
+    pub total_segments: usize,
+    /// SMS_SEGMENT_PRICE_CENTS; None when not configured
+    pub price_per_segment_cents: Option<i64>,
+    pub total_cost_cents: Option<i64>,
+}
+
+fn gsm7_length(text: &str) -> Option<usize> {
+    text.chars().try_fold(0usize, |len, c| {
+        if GSM7_BASIC.contains(c) {
+            Some(len + 1)
+        } else if GSM7_EXTENDED.contains(c) {
+            Some(len + 2)
+        } else {
+            None
+        }
+    })
+}
+
+pub fn estimate(text: &str, recipients: usize, price_per_segment_cents: Option<i64>) -> SmsEstimate {
+    let (encoding, length, single, multi) = match gsm7_length(text) {
+        Some(len) => (SmsEncoding::Gsm7, len, 160, 153),
+        None => (SmsEncoding::Ucs2, text.encode_utf16().count(), 70, 67),
+    };
+    let (segments, per_segment) = if length <= single {
+        (usize::from(length > 0), single)
+    } else {
+        (length.div_ceil(multi), multi)
+    };
+    let total_segments = segments * recipients;
+    SmsEstimate {
+        encoding,
+        length,
+        segments,
+        per_segment,
+        recipients,
+        total_segments,
+        price_per_segment_cents,
+        total_cost_cents: price_per_segment_cents.map(|p| p * total_segments as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gsm7_limits_and_extension_chars() {
+        let e = estimate(&"a".repeat(160), 1, None);
+        assert_eq!((e.encoding, e.length, e.segments), (SmsEncoding::Gsm7, 160, 1));
+        let e = estimate(&"a".repeat(161), 1, None);
+        assert_eq!((e.segments, e.per_segment), (2, 153));
+        // `€` is escape + char
+        let e = estimate(&format!("{}€", "a".repeat(159)), 1, None);
+        assert_eq!((e.length, e.segments), (161, 2));
+        assert_eq!(estimate("", 1, None).segments, 0);
+    }
+
+    #[test]
+    fn cyrillic_switches_to_ucs2() {
+        let text = "Сайн байна уу, таны шүдний эмчийн цаг маргааш 10:00 цагт. ".repeat(3);
+        let e = estimate(&text, 500, Some(25));
+        assert_eq!(e.encoding, SmsEncoding::Ucs2);
+        assert_eq!(e.length, text.chars().count());
+        assert_eq!((e.segments, e.per_segment), (3, 67));
+        assert_eq!(e.total_segments, 1500);
+        assert_eq!(e.total_cost_cents, Some(37_500));
+        // emoji are two UTF-16 units
+        assert_eq!(estimate("😀", 1, None).length, 2);
+    }
+}
@@ -0,0 +1,137 @@
+// src/sync_outbox.rs
+//
+// Reads of sync_outbox (migrations/054_hq_change.sql, renamed in 055): one row per
+// patient / appointment write, filled by triggers. Read by GET /sync/changes (desktop
+// client) and the HQ sync ChangeFeed (hq_grpc); POST /sync/push takes an entity's
+// newest seq as its version.
+// Order is (tx_id, seq), and only transactions older than the snapshot's xmin are
+// read: every one of those has finished, so no row can later appear behind a
+// cursor already handed out. `seq` alone is allocated before commit and can't be
+// used as the cursor.
+// Cursor token = base64url("<tx_id>:<seq>"), opaque to clients like crate::cursor.
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangeCursor {
+    pub tx_id: i64,
+    pub seq: i64,
+}
+
+impl ChangeCursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.tx_id, self.seq))
+    }
+
+    pub fn decode(token: &str) -> Result<ChangeCursor, ApiError> {
+        let invalid = || ApiError::BadRequest("VALIDATION_ERROR", "invalid cursor".into());
+        let raw = URL_SAFE_NO_PAD.decode(token.trim()).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (tx_id, seq) = raw.split_once(':').ok_or_else(invalid)?;
+        Ok(ChangeCursor {
+            tx_id: tx_id.parse().map_err(|_| invalid())?,
+            seq: seq.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ChangeRow {
+    pub seq: i64,
+    pub tx_id: i64,
+    pub entity: String,
+    pub entity_id: Uuid,
+    pub op: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl ChangeRow {
+    pub fn cursor(&self) -> ChangeCursor {
+        ChangeCursor { tx_id: self.tx_id, seq: self.seq }
+    }
+}
+
+/// CONFLICT once the cleanup job has purged the cursor's row: changes after it may
+/// be gone too, so the client has to reload everything and start from a new cursor.
+pub async fn ensure_cursor_kept(db: &PgPool, cursor: ChangeCursor) -> Result<(), ApiError> {
+    let kept: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM sync_outbox WHERE tx_id = $1 AND seq = $2)")
+        .bind(cursor.tx_id)
+        .bind(cursor.seq)
+        .fetch_one(db)
+        .await?;
+    if !kept {
+        return Err(ApiError::Conflict(
+            "SYNC_CURSOR_EXPIRED",
+            "cursor is older than the change log; reload everything and start from a new cursor".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Cursor of the newest finished change (None = log empty): take it before a full
+/// reload, then follow changes from it.
+pub async fn head(db: &PgPool) -> Result<Option<ChangeCursor>, ApiError> {
+    Ok(sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT tx_id, seq
+        FROM sync_outbox
+        WHERE tx_id < pg_snapshot_xmin(pg_current_snapshot())::text::bigint
+        ORDER BY tx_id DESC, seq DESC
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(db)
+    .await?
+    .map(|(tx_id, seq)| ChangeCursor { tx_id, seq }))
+}
+
+/// Finished changes after `after` (None = from the oldest kept), oldest first.
+pub async fn after(db: &PgPool, after: Option<ChangeCursor>, limit: i64) -> Result<Vec<ChangeRow>, ApiError> {
+    Ok(sqlx::query_as::<_, ChangeRow>(
+        r#"
+        SELECT seq, tx_id, entity, entity_id, op, changed_at
+        FROM sync_outbox
+        WHERE ($1::bigint IS NULL OR (tx_id, seq) > ($1, $2))
+          AND tx_id < pg_snapshot_xmin(pg_current_snapshot())::text::bigint
+        ORDER BY tx_id, seq
+        LIMIT $3
+        "#,
+    )
+    .bind(after.map(|c| c.tx_id))
+    .bind(after.map(|c| c.seq))
+    .bind(limit)
+    .fetch_all(db)
+    .await?)
+}
+
+/// Version of one entity for POST /sync/push: the seq of its newest kept change, 0
+/// without one. Read it with the entity's row locked: writers of that row log in
+/// lock order, so no change with a lower seq can still commit behind it.
+pub async fn version(conn: &mut PgConnection, entity: &str, entity_id: Uuid) -> Result<i64, ApiError> {
+    Ok(
+        sqlx::query_scalar("SELECT COALESCE(max(seq), 0) FROM sync_outbox WHERE entity = $1 AND entity_id = $2")
+            .bind(entity)
+            .bind(entity_id)
+            .fetch_one(conn)
+            .await?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips_and_rejects_garbage() {
+        let c = ChangeCursor { tx_id: 7_301_442, seq: 19 };
+        assert_eq!(ChangeCursor::decode(&c.encode()).unwrap(), c);
+        assert!(ChangeCursor::decode("7301442:19").is_err());
+        assert!(ChangeCursor::decode(&URL_SAFE_NO_PAD.encode("7301442")).is_err());
+        assert!(ChangeCursor::decode(&URL_SAFE_NO_PAD.encode("a:19")).is_err());
+    }
+}
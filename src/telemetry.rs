@@ -0,0 +1,297 @@
+// src/telemetry.rs
+//
+// Logging plus optional trace export. Logs go to stdout as before (RUST_LOG, default
+// info). With OTEL_EXPORTER_OTLP_ENDPOINT set, spans are also exported over OTLP/gRPC
+// (Jaeger, Tempo, an OpenTelemetry Collector):
+// - one server span per request, named after the matched route
+//   ("GET /api/v1/appointments/week"), with tower-http's request/response events
+// - one client span per SQL statement under it, named after the statement: a leading
+//   `/* name */` comment when the query has one (see repos::appointments), else
+//   sqlx's summary ("SELECT employee_id FROM employee …"); the SQL is in db.statement
+// Statement spans are rebuilt from sqlx's per-query log event (it carries the elapsed
+// time), so every query gets one without instrumenting the call sites.
+//
+// Slow statements (DB_SLOW_QUERY_MS, see db::PoolSettings) are always logged, export
+// or not: one `dcms::slow_query` warning with the route, duration, statement name and
+// the SQL on one line with string literals masked. Bound parameters are never logged;
+// sqlx's own slow-statement warning (raw SQL, no route) is filtered out.
+
+use std::time::{Duration, SystemTime};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{
+    global::BoxedTracer,
+    trace::{Span as _, SpanKind, Tracer, TracerProvider as _},
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use tracing::{field::Visit, Level, Metadata, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    filter::{filter_fn, FilterExt, Targets},
+    layer::Context,
+    prelude::*,
+    registry::LookupSpan,
+    EnvFilter, Layer,
+};
+
+use crate::{config::Config, middleware::request_context};
+
+/// target of the request span (debug, so stdout logs don't carry it)
+const REQUEST_TARGET: &str = "dcms::request";
+const SQLX_TARGET: &str = "sqlx::query";
+const SLOW_QUERY_TARGET: &str = "dcms::slow_query";
+
+/// Keeps the exporter alive; `shutdown` flushes the spans still queued.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("trace export shutdown failed: {e}");
+        }
+    }
+}
+
+fn log_filter() -> anyhow::Result<EnvFilter> {
+    Ok(EnvFilter::from_default_env().add_directive("info".parse()?))
+}
+
+/// sqlx logs slow statements at WARN (db.rs sets that up); SlowQueries replaces them.
+fn is_raw_slow_statement(meta: &Metadata<'_>) -> bool {
+    meta.target() == SQLX_TARGET && *meta.level() == Level::WARN
+}
+
+/// Installs the global subscriber. Call once, inside the Tokio runtime (the gRPC
+/// exporter needs it).
+pub fn init(cfg: &Config) -> anyhow::Result<Telemetry> {
+    let logs = tracing_subscriber::fmt::layer()
+        .with_filter(log_filter()?.and(filter_fn(|meta| !is_raw_slow_statement(meta))));
+    let Some(endpoint) = cfg.otlp_endpoint.as_deref() else {
+        tracing_subscriber::registry().with(logs).with(slow_queries()).init();
+        return Ok(Telemetry { provider: None });
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(cfg.otel_service_name.clone()).build())
+        .build();
+    opentelemetry::global::set_tracer_provider(provider.clone());
+
+    // no context activation: entering a span would start it, and the request span is
+    // only renamed after routing (see record_route)
+    let spans = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer("dcms"))
+        .with_context_activation(false)
+        .with_filter(
+            log_filter()?
+                .add_directive(format!("{REQUEST_TARGET}=debug").parse()?)
+                .add_directive("tower_http=debug".parse()?)
+                .and(filter_fn(|meta| !is_raw_slow_statement(meta))),
+        );
+    let statements = SqlSpans { tracer: opentelemetry::global::tracer("sqlx") }
+        .with_filter(Targets::new().with_target(SQLX_TARGET, Level::TRACE));
+
+    tracing_subscriber::registry().with(logs).with(spans).with(statements).with(slow_queries()).init();
+    tracing::info!("exporting traces to {endpoint}");
+    Ok(Telemetry { provider: Some(provider) })
+}
+
+/// `TraceLayer::make_span_with`: the request span; the route is filled in by
+/// `record_route` once the router has matched.
+pub fn request_span(req: &Request) -> Span {
+    tracing::debug_span!(
+        target: REQUEST_TARGET,
+        "request",
+        method = %req.method(),
+        uri = %req.uri(),
+        http.route = tracing::field::Empty,
+        otel.name = %req.method(),
+        otel.kind = "server",
+    )
+}
+
+/// Route layer: names the request span after the route pattern, not the raw URI,
+/// so every GET /patients/{patient_id} lands in one bucket.
+pub async fn record_route(matched: Option<MatchedPath>, req: Request, next: Next) -> Response {
+    if let Some(route) = matched {
+        let span = Span::current();
+        span.record("http.route", route.as_str());
+        span.record("otel.name", format!("{} {}", req.method(), route.as_str()));
+    }
+    next.run(req).await
+}
+
+/// Span name for a statement: its `/* name */` comment, else the summary.
+fn statement_name(summary: &str, sql: Option<&str>) -> String {
+    let text = sql.unwrap_or(summary).trim_start();
+    text.strip_prefix("/*")
+        .and_then(|rest| rest.split_once("*/"))
+        .map(|(name, _)| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| summary.to_string())
+}
+
+/// The statement on one line, string literals masked ('?'), for logs.
+fn loggable_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut in_literal = false;
+    while let Some(c) = chars.next() {
+        match (in_literal, c) {
+            // '' is an escaped quote inside a literal
+            (true, '\'') if chars.peek() == Some(&'\'') => {
+                chars.next();
+            }
+            (true, '\'') => {
+                in_literal = false;
+                out.push_str("?'");
+            }
+            (true, _) => {}
+            (false, '\'') => {
+                in_literal = true;
+                out.push('\'');
+            }
+            (false, _) => out.push(c),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Default)]
+struct QueryEvent {
+    summary: String,
+    /// only set when longer than the summary
+    sql: Option<String>,
+    elapsed_secs: f64,
+    rows_returned: Option<i64>,
+    rows_affected: Option<i64>,
+}
+
+impl Visit for QueryEvent {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        match field.name() {
+            "summary" => self.summary = value.to_string(),
+            "db.statement" if !value.is_empty() => self.sql = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.elapsed_secs = value;
+        }
+    }
+
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        let value = i64::try_from(value).unwrap_or(i64::MAX);
+        match field.name() {
+            "rows_returned" => self.rows_returned = Some(value),
+            "rows_affected" => self.rows_affected = Some(value),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, _field: &tracing::field::Field, _value: &dyn std::fmt::Debug) {}
+}
+
+/// Turns sqlx's "query finished" events into client spans under the current span.
+struct SqlSpans {
+    tracer: BoxedTracer,
+}
+
+impl<S> Layer<S> for SqlSpans
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut q = QueryEvent::default();
+        event.record(&mut q);
+        if q.summary.is_empty() {
+            return;
+        }
+
+        let end = SystemTime::now();
+        let start = end.checked_sub(Duration::from_secs_f64(q.elapsed_secs)).unwrap_or(end);
+        let mut attributes = vec![
+            KeyValue::new("db.system", "postgresql"),
+            KeyValue::new("db.statement", q.sql.clone().unwrap_or_else(|| q.summary.clone())),
+        ];
+        attributes.extend(q.rows_returned.map(|n| KeyValue::new("db.rows_returned", n)));
+        attributes.extend(q.rows_affected.map(|n| KeyValue::new("db.rows_affected", n)));
+
+        let parent = Span::current().context();
+        let mut span = self
+            .tracer
+            .span_builder(statement_name(&q.summary, q.sql.as_deref()))
+            .with_kind(SpanKind::Client)
+            .with_start_time(start)
+            .with_attributes(attributes)
+            .start_with_context(&self.tracer, &parent);
+        span.end_with_timestamp(end);
+    }
+}
+
+/// Logs sqlx's slow-statement events again, with the route they came from.
+struct SlowQueries;
+
+/// Add it as the last layer: it logs from inside on_event.
+fn slow_queries<S>() -> impl Layer<S>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    SlowQueries.with_filter(Targets::new().with_target(SQLX_TARGET, Level::WARN))
+}
+
+impl<S: tracing::Subscriber> Layer<S> for SlowQueries {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut q = QueryEvent::default();
+        event.record(&mut q);
+        if q.summary.is_empty() {
+            return;
+        }
+        tracing::warn!(
+            target: SLOW_QUERY_TARGET,
+            route = %request_context::current_route(),
+            elapsed_ms = (q.elapsed_secs * 1000.0).round() as u64,
+            statement = %statement_name(&q.summary, q.sql.as_deref()),
+            sql = %loggable_sql(q.sql.as_deref().unwrap_or(&q.summary)),
+            "slow query"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements_are_named_by_leading_comment() {
+        let sql = "/* appointments.blocks_in_range */\n SELECT a.appointment_id FROM appointment a";
+        assert_eq!(statement_name("/* appointments.blocks_in_range */ SELECT …", Some(sql)), "appointments.blocks_in_range");
+        assert_eq!(statement_name("SELECT status FROM appointment …", None), "SELECT status FROM appointment …");
+        assert_eq!(statement_name("/**/ SELECT 1", None), "/**/ SELECT 1");
+    }
+
+    #[test]
+    fn slow_query_sql_masks_literals() {
+        let sql = "\n  SELECT patient_id FROM patient\n  WHERE last_name = 'O''Brien' AND register_no = $1\n    AND note <> ''\n";
+        assert_eq!(
+            loggable_sql(sql),
+            "SELECT patient_id FROM patient WHERE last_name = '?' AND register_no = $1 AND note <> '?'"
+        );
+    }
+}